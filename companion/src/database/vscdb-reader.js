@@ -0,0 +1,317 @@
+/**
+ * VS Code / Cursor state.vscdb Reader
+ * Opens Cursor's workspace and global SQLite databases read-only and extracts
+ * AI chat transcripts, composer sessions and recently edited files.
+ *
+ * All reads for one extraction run inside a single read transaction, so they
+ * see one consistent WAL snapshot even while Cursor keeps writing. SQLITE_BUSY
+ * and SQLITE_LOCKED are retried with exponential backoff; if the database
+ * cannot be opened in place (e.g. the -shm file is not accessible), the
+ * database and its WAL are copied to a temp directory and read from there.
+ */
+
+const sqlite3 = require('sqlite3');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+
+const DEFAULT_OPTIONS = {
+  retries: 5,
+  backoffMs: 100,
+  maxBackoffMs: 3000,
+  busyTimeoutMs: 2000,
+  copyOnFailure: true,
+};
+
+const RETRYABLE_CODES = new Set(['SQLITE_BUSY', 'SQLITE_LOCKED', 'SQLITE_PROTOCOL']);
+
+const KEYS = {
+  chatData: 'workbench.panel.aichat.view.aichat.chatdata',
+  composerIndex: 'composer.composerData',
+  prompts: 'aiService.prompts',
+  generations: 'aiService.generations',
+  history: 'history.entries',
+};
+
+function sleep(ms) {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+function parseJSON(value) {
+  if (value === null || value === undefined) return null;
+  try {
+    return JSON.parse(Buffer.isBuffer(value) ? value.toString('utf8') : value);
+  } catch {
+    return null;
+  }
+}
+
+function fileUriToPath(uri) {
+  if (!uri) return null;
+  if (typeof uri === 'object') uri = uri.external || uri.fsPath || uri.path || null;
+  if (typeof uri !== 'string') return null;
+  if (!uri.startsWith('file://')) return uri;
+  try {
+    return decodeURIComponent(new URL(uri).pathname);
+  } catch {
+    return uri.replace(/^file:\/\//, '');
+  }
+}
+
+class VSCDBReader {
+  constructor(dbPath, options = {}) {
+    this.dbPath = dbPath;
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.db = null;
+    this.snapshotDir = null;
+    this.hasDiskKV = false;
+  }
+
+  async withRetry(operation, label) {
+    let delay = this.options.backoffMs;
+    for (let attempt = 0; ; attempt++) {
+      try {
+        return await operation();
+      } catch (error) {
+        if (!RETRYABLE_CODES.has(error.code) || attempt >= this.options.retries) throw error;
+        console.warn(`[VSCDB] ${label} hit ${error.code}, retrying in ${delay}ms`);
+        await sleep(delay);
+        delay = Math.min(delay * 2, this.options.maxBackoffMs);
+      }
+    }
+  }
+
+  openAt(filePath) {
+    return new Promise((resolve, reject) => {
+      const db = new sqlite3.Database(filePath, sqlite3.OPEN_READONLY, (err) => {
+        if (err) reject(err);
+        else resolve(db);
+      });
+    });
+  }
+
+  /**
+   * Copy the database plus WAL/SHM side files so a snapshot can be read in isolation
+   */
+  copyToSnapshot() {
+    this.snapshotDir = fs.mkdtempSync(path.join(os.tmpdir(), 'vscdb-'));
+    const target = path.join(this.snapshotDir, path.basename(this.dbPath));
+    for (const suffix of ['', '-wal', '-shm']) {
+      if (fs.existsSync(this.dbPath + suffix)) fs.copyFileSync(this.dbPath + suffix, target + suffix);
+    }
+    return target;
+  }
+
+  async open() {
+    if (this.db) return this;
+    if (!fs.existsSync(this.dbPath)) {
+      const error = new Error(`Database not found: ${this.dbPath}`);
+      error.code = 'ENOENT';
+      throw error;
+    }
+
+    try {
+      this.db = await this.withRetry(() => this.openAt(this.dbPath), 'open');
+      this.db.configure('busyTimeout', this.options.busyTimeoutMs);
+      // Touch the schema so WAL/SHM access problems surface here, not mid-extraction
+      await this.withRetry(() => this.all("SELECT name FROM sqlite_master WHERE type = 'table'"), 'schema');
+    } catch (error) {
+      if (this.db) await this.closeHandle();
+      if (!this.options.copyOnFailure) throw error;
+      console.warn(`[VSCDB] Opening ${this.dbPath} in place failed (${error.code || error.message}); using a copy`);
+      this.db = await this.openAt(this.copyToSnapshot());
+    }
+
+    const tables = await this.all("SELECT name FROM sqlite_master WHERE type = 'table'");
+    this.hasDiskKV = tables.some((t) => t.name === 'cursorDiskKV');
+    return this;
+  }
+
+  all(sql, params = []) {
+    return new Promise((resolve, reject) => {
+      this.db.all(sql, params, (err, rows) => (err ? reject(err) : resolve(rows)));
+    });
+  }
+
+  exec(sql) {
+    return new Promise((resolve, reject) => {
+      this.db.exec(sql, (err) => (err ? reject(err) : resolve()));
+    });
+  }
+
+  /**
+   * Run fn inside one read transaction so every query sees the same snapshot
+   */
+  async withSnapshot(fn) {
+    await this.open();
+    return this.withRetry(async () => {
+      await this.exec('BEGIN');
+      try {
+        return await fn();
+      } finally {
+        await this.exec('COMMIT').catch(() => {});
+      }
+    }, 'snapshot');
+  }
+
+  async getItem(key) {
+    const rows = await this.all('SELECT value FROM ItemTable WHERE key = ?', [key]);
+    return rows.length > 0 ? parseJSON(rows[0].value) : null;
+  }
+
+  async getDiskKV(pattern) {
+    if (!this.hasDiskKV) return [];
+    const rows = await this.all('SELECT key, value FROM cursorDiskKV WHERE key LIKE ?', [pattern]);
+    return rows.map((row) => ({ key: row.key, value: parseJSON(row.value) })).filter((row) => row.value);
+  }
+
+  // ---------------------------------------------------------------------------
+  // Extractors (call inside withSnapshot)
+  // ---------------------------------------------------------------------------
+
+  /**
+   * Legacy chat panel tabs with their message bubbles
+   */
+  async getChatTranscripts() {
+    const data = await this.getItem(KEYS.chatData);
+    const tabs = (data && data.tabs) || [];
+    return tabs.map((tab) => ({
+      id: tab.tabId,
+      title: tab.chatTitle || null,
+      lastSendTime: tab.lastSendTime || null,
+      messages: (tab.bubbles || []).map((bubble) => ({
+        id: bubble.id,
+        role: bubble.type === 'user' ? 'user' : 'assistant',
+        text: bubble.text || bubble.rawText || '',
+        model: bubble.modelType || null,
+        files: (bubble.selections || []).map((s) => fileUriToPath(s.uri)).filter(Boolean),
+      })),
+    }));
+  }
+
+  /**
+   * Composer sessions from the workspace index, enriched with full
+   * conversations from the global cursorDiskKV table when available
+   */
+  async getComposerSessions() {
+    const index = await this.getItem(KEYS.composerIndex);
+    const sessions = new Map();
+
+    for (const composer of (index && index.allComposers) || []) {
+      sessions.set(composer.composerId, {
+        id: composer.composerId,
+        name: composer.name || null,
+        mode: composer.unifiedMode || composer.forceMode || null,
+        createdAt: composer.createdAt || null,
+        lastUpdatedAt: composer.lastUpdatedAt || null,
+        messages: [],
+      });
+    }
+
+    for (const { key, value } of await this.getDiskKV('composerData:%')) {
+      const id = value.composerId || key.slice('composerData:'.length);
+      const session = sessions.get(id) || {
+        id,
+        name: value.name || null,
+        mode: value.unifiedMode || null,
+        createdAt: value.createdAt || null,
+        lastUpdatedAt: value.lastUpdatedAt || null,
+        messages: [],
+      };
+
+      const headers = value.conversation || value.fullConversationHeadersOnly || [];
+      const bubbles = new Map();
+      if (!value.conversation && headers.length > 0) {
+        for (const bubble of await this.getDiskKV(`bubbleId:${id}:%`)) {
+          bubbles.set(bubble.value.bubbleId, bubble.value);
+        }
+      }
+
+      session.messages = headers.map((header) => {
+        const bubble = bubbles.get(header.bubbleId) || header;
+        return {
+          id: header.bubbleId,
+          role: header.type === 1 ? 'user' : 'assistant',
+          text: bubble.text || bubble.rawText || '',
+          timestamp: bubble.timingInfo?.clientStartTime || bubble.createdAt || null,
+          files: (bubble.context?.fileSelections || []).map((s) => fileUriToPath(s.uri)).filter(Boolean),
+        };
+      });
+      sessions.set(id, session);
+    }
+
+    return [...sessions.values()];
+  }
+
+  /**
+   * Prompt inputs and generation records kept by aiService
+   */
+  async getPromptsAndGenerations() {
+    const prompts = (await this.getItem(KEYS.prompts)) || [];
+    const generations = (await this.getItem(KEYS.generations)) || [];
+    return {
+      prompts: Array.isArray(prompts) ? prompts : [],
+      generations: Array.isArray(generations) ? generations : [],
+    };
+  }
+
+  /**
+   * Recently opened/edited files from editor history
+   */
+  async getRecentlyEditedFiles() {
+    const entries = (await this.getItem(KEYS.history)) || [];
+    return (Array.isArray(entries) ? entries : [])
+      .map((entry) => fileUriToPath(entry.editor?.resource || entry.resource))
+      .filter(Boolean);
+  }
+
+  /**
+   * Extract everything in one consistent snapshot
+   */
+  async extractAll() {
+    return this.withSnapshot(async () => ({
+      dbPath: this.dbPath,
+      fromCopy: Boolean(this.snapshotDir),
+      chats: await this.getChatTranscripts(),
+      composers: await this.getComposerSessions(),
+      ...(await this.getPromptsAndGenerations()),
+      recentFiles: await this.getRecentlyEditedFiles(),
+    }));
+  }
+
+  closeHandle() {
+    return new Promise((resolve) => {
+      if (!this.db) return resolve();
+      this.db.close(() => {
+        this.db = null;
+        resolve();
+      });
+    });
+  }
+
+  async close() {
+    await this.closeHandle();
+    if (this.snapshotDir) {
+      fs.rmSync(this.snapshotDir, { recursive: true, force: true });
+      this.snapshotDir = null;
+    }
+  }
+}
+
+/**
+ * Open, extract and close in one call
+ */
+async function readVSCDB(dbPath, options = {}) {
+  const reader = new VSCDBReader(dbPath, options);
+  try {
+    return await reader.extractAll();
+  } finally {
+    await reader.close();
+  }
+}
+
+module.exports = {
+  VSCDBReader,
+  readVSCDB,
+  KEYS,
+};
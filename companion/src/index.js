@@ -38,6 +38,7 @@ const createFileContentsRoutes = require('./routes/file-contents.js');
 const createMCPRoutes = require('./routes/mcp.js');
 const createExportImportRoutes = require('./routes/export-import.js');
 const createHuggingFaceRoutes = require('./routes/huggingface.js');
+const createOperationRoutes = require('./routes/operations.js');
 const createRung1Routes = require('./routes/rung1.js');
 const createRung2Routes = require('./routes/rung2.js');
 const createRung3Routes = require('./routes/rung3.js');
@@ -47,6 +48,7 @@ const TokensService = require('./services/tokens/tokens-service.js');
 const EditsService = require('./services/edits/edits-service.js');
 const FunctionsService = require('./services/functions/functions-service.js');
 const RobustDataCaptureService = require('./services/robust-data-capture');
+const { AdaptiveRateController } = require('./services/scheduling/adaptive-rate-controller');
 
 // Initialize Express app
 const app = express();
@@ -116,6 +118,7 @@ function enqueue(kind, payload) {
 
 // Initialize Services
 let robustDataCapture = new RobustDataCaptureService(persistentDB, rawData);
+const captureRates = new AdaptiveRateController();
 const tokensService = new TokensService(cursorDbParser, persistentDB);
 const editsService = new EditsService(cursorDbParser, persistentDB);
 const functionsService = new FunctionsService(cursorDbParser, persistentDB);
@@ -126,6 +129,7 @@ const fileWatcherService = createFileWatcherService({
   queueSystem,
   enqueue,
   io,
+  rateController: captureRates,
   broadcastUpdate: (type, data) => io.emit('activityUpdate', { type, data })
 });
 
@@ -133,7 +137,7 @@ const fileWatcherService = createFileWatcherService({
 const deps = {
   app, db, persistentDB, cursorDbParser, queryCache, queue, sequence,
   rawData, queueSystem, robustDataCapture, terminalMonitor, ideStateCapture,
-  tokensService, editsService, functionsService, abstractionEngine
+  tokensService, editsService, functionsService, abstractionEngine, captureRates
 };
 
 createCoreRoutes(deps);
@@ -149,6 +153,7 @@ createFileContentsRoutes(deps);
 createMCPRoutes({ ...deps, getCurrentWorkspace: () => os.homedir(), broadcastUpdate: (type, data) => io.emit('activityUpdate', { type, data }) });
 createExportImportRoutes(deps);
 createHuggingFaceRoutes(deps);
+createOperationRoutes(deps);
 createRung1Routes(deps);
 createRung2Routes(deps);
 createRung3Routes(deps);
@@ -157,6 +162,7 @@ createRung3Routes(deps);
 async function startCapture() {
   console.log('[COMPANION] Starting capture...');
   await robustDataCapture.init();
+  await captureRates.start();
   ideStateCapture.start(2000);
   fileWatcherService.start();
 
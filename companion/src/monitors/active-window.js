@@ -0,0 +1,466 @@
+/**
+ * Active Window
+ * Which application and window the user is in: app name, window title,
+ * bundle id (macOS) and process path, plus a coarse kind (editor, browser,
+ * terminal) so sessions can tell time in Cursor from time in a browser.
+ *
+ *   macOS    NSWorkspace's frontmost application and the front window of
+ *            its process from CGWindowListCopyWindowInfo, through the
+ *            JavaScript for Automation ObjC bridge. Window titles need
+ *            Screen Recording (see permission-onboarding); without it the
+ *            title is null but the app is still known.
+ *   Windows  GetForegroundWindow / GetWindowText / GetWindowThreadProcessId
+ *            through PowerShell P/Invoke.
+ *   Linux    X11: _NET_ACTIVE_WINDOW and the window's _NET_WM_NAME,
+ *            WM_CLASS and _NET_WM_PID via xprop. Wayland has no portable
+ *            way to ask; sway and Hyprland answer over their IPC, other
+ *            compositors are unsupported.
+ *
+ * watchActiveWindow() keeps one helper process running on macOS and Windows
+ * (starting PowerShell or osascript per check would cost more than the
+ * check) and polls on Linux, where each query is a cheap xprop/IPC call.
+ */
+
+const fs = require('fs');
+const path = require('path');
+const readline = require('readline');
+const { execFile, spawn } = require('child_process');
+const { promisify } = require('util');
+const { CompanionError, ERROR_CODES, fromSystemError } = require('../utils/errors');
+
+const execFileAsync = promisify(execFile);
+
+const DEFAULT_OPTIONS = {
+  intervalMs: 1000, // How often the active window is sampled
+  timeoutMs: 10000, // For one-shot queries
+};
+
+// Matched against "bundleId app processName"; Cursor's bundle id is com.todesktop.230313mzl4w4u92
+const CURSOR_APP = /\bcursor\b|230313mzl4w4u92/i;
+const KIND_NAMES = {
+  editor: [
+    'cursor',
+    'vscode',
+    'visual studio',
+    '\\bcode(?:-insiders|-oss)?\\b',
+    'vscodium',
+    'windsurf',
+    '\\bzed\\b',
+    'jetbrains',
+    'intellij',
+    '\\bidea\\b',
+    'pycharm',
+    'webstorm',
+    'goland',
+    'clion',
+    'rider',
+    'phpstorm',
+    'rubymine',
+    'android studio',
+    'sublime',
+    'xcode',
+    'neovide',
+    'emacs',
+    'gvim',
+    'macvim',
+  ],
+  browser: [
+    'chrome',
+    'chromium',
+    'firefox',
+    'safari',
+    'msedge',
+    'microsoft edge',
+    '\\bbrave\\b',
+    'company\\.thebrowser',
+    '\\barc\\b',
+    'opera',
+    'vivaldi',
+    'orion',
+    'zen browser',
+  ],
+  terminal: [
+    'terminal',
+    'iterm',
+    '\\bwarp\\b',
+    'alacritty',
+    'kitty',
+    'wezterm',
+    'konsole',
+    'xterm',
+    '\\bhyper\\b',
+    'ghostty',
+    'tabby',
+    'tilix',
+    '\\bfoot\\b',
+  ],
+};
+const KINDS = Object.entries(KIND_NAMES).map(([kind, names]) => [new RegExp(names.join('|'), 'i'), kind]);
+
+/**
+ * Coarse kind of a window's application: 'editor' | 'browser' | 'terminal' | 'other'
+ */
+function classifyWindow(window) {
+  const haystack = [window.bundleId, window.app, window.processPath && path.basename(window.processPath)]
+    .filter(Boolean)
+    .join(' ');
+  const match = KINDS.find(([pattern]) => pattern.test(haystack));
+  return match ? match[1] : 'other';
+}
+
+/**
+ * Fill in the common fields of a platform answer
+ */
+function finishWindow(raw) {
+  if (!raw || (!raw.app && !raw.pid)) return null;
+  const window = {
+    app: raw.app || null,
+    title: raw.title || null,
+    bundleId: raw.bundleId || null,
+    processPath: raw.processPath || null,
+    pid: Number.isInteger(raw.pid) && raw.pid > 0 ? raw.pid : null,
+    platform: process.platform,
+  };
+  const identity = [window.bundleId, window.app, window.processPath].filter(Boolean).join(' ');
+  const isCursor = CURSOR_APP.test(identity);
+  return { ...window, kind: classifyWindow(window), isCursor };
+}
+
+// ---------------------------------------------------------------------------
+// macOS
+// ---------------------------------------------------------------------------
+
+// active() answers for the frontmost app; the clipboard watcher's helper calls it too
+const MAC_ACTIVE = `
+ObjC.import('AppKit');
+ObjC.import('CoreGraphics');
+function active() {
+  const app = $.NSWorkspace.sharedWorkspace.frontmostApplication;
+  if (!app || app.isNil()) return null;
+  const pid = app.processIdentifier;
+  const options = $.kCGWindowListOptionOnScreenOnly | $.kCGWindowListExcludeDesktopElements;
+  const list = $.CGWindowListCopyWindowInfo(options, $.kCGNullWindowID);
+  const windows = ObjC.deepUnwrap(ObjC.castRefToObject(list)) || [];
+  // Front to back: the first normal-layer window of the app is its front window
+  const front = windows.find((w) => w.kCGWindowOwnerPID === pid && w.kCGWindowLayer === 0);
+  const executable = app.executableURL;
+  return {
+    app: ObjC.unwrap(app.localizedName) || null,
+    bundleId: ObjC.unwrap(app.bundleIdentifier) || null,
+    processPath: executable && !executable.isNil() ? ObjC.unwrap(executable.path) : null,
+    pid,
+    title: front && front.kCGWindowName ? front.kCGWindowName : null,
+  };
+}
+`;
+
+const MAC_SCRIPT = (loopMs) => `${MAC_ACTIVE}
+${
+  loopMs
+    ? `const out = $.NSFileHandle.fileHandleWithStandardOutput;
+let last = null;
+for (;;) {
+  const line = JSON.stringify(active());
+  if (line !== last) {
+    out.writeData($(line + '\\n').dataUsingEncoding($.NSUTF8StringEncoding));
+    last = line;
+  }
+  // Running the run loop (rather than delay()) lets NSWorkspace see activation changes
+  $.NSRunLoop.currentRunLoop.runUntilDate($.NSDate.dateWithTimeIntervalSinceNow(${loopMs / 1000}));
+}`
+    : 'JSON.stringify(active());'
+}
+`;
+
+// ---------------------------------------------------------------------------
+// Windows
+// ---------------------------------------------------------------------------
+
+// Get-ForegroundApp answers for the foreground window ($null for none); the clipboard
+// watcher's helper calls it too
+const WINDOWS_ACTIVE = `
+Add-Type -Namespace CursorTelemetry -Name Foreground -MemberDefinition @'
+[DllImport("user32.dll")] public static extern IntPtr GetForegroundWindow();
+[DllImport("user32.dll", CharSet = CharSet.Unicode)]
+public static extern int GetWindowText(IntPtr window, System.Text.StringBuilder text, int count);
+[DllImport("user32.dll")]
+public static extern uint GetWindowThreadProcessId(IntPtr window, out uint processId);
+'@
+function Get-ForegroundApp {
+  $window = [CursorTelemetry.Foreground]::GetForegroundWindow()
+  if ($window -eq [IntPtr]::Zero) { return $null }
+  $text = New-Object System.Text.StringBuilder 1024
+  [void][CursorTelemetry.Foreground]::GetWindowText($window, $text, 1024)
+  $ownerId = [uint32]0
+  [void][CursorTelemetry.Foreground]::GetWindowThreadProcessId($window, [ref]$ownerId)
+  $process = Get-Process -Id $ownerId -ErrorAction SilentlyContinue
+  $app = $null
+  $processPath = $null
+  if ($process) {
+    $app = $process.ProcessName
+    # Elevated processes hide their path and module from unelevated callers
+    try {
+      $processPath = $process.Path
+      $description = $process.MainModule.FileVersionInfo.FileDescription
+      if ($description) { $app = $description }
+    } catch {}
+  }
+  @{ app = $app; title = $text.ToString(); processPath = $processPath; pid = [int]$ownerId }
+}
+`;
+
+const WINDOWS_SCRIPT = (loopMs) => `${WINDOWS_ACTIVE}
+function Get-ActiveWindow {
+  $result = Get-ForegroundApp
+  if ($null -eq $result) { return 'null' }
+  $result | ConvertTo-Json -Compress
+}
+${
+  loopMs
+    ? `$last = $null
+while ($true) {
+  $line = Get-ActiveWindow
+  if ($line -ne $last) { [Console]::Out.WriteLine($line); [Console]::Out.Flush(); $last = $line }
+  Start-Sleep -Milliseconds ${loopMs}
+}`
+    : 'Get-ActiveWindow'
+}
+`;
+
+const POWERSHELL_ARGS = ['-NoProfile', '-NonInteractive', '-Command'];
+
+// ---------------------------------------------------------------------------
+// Linux
+// ---------------------------------------------------------------------------
+
+function xpropValue(output, property) {
+  const line = output.split('\n').find((entry) => entry.startsWith(`${property}(`));
+  if (!line || !line.includes('=')) return null;
+  return line.slice(line.indexOf('=') + 1).trim();
+}
+
+// 'WM_CLASS(STRING) = "code", "Code"' -> ['code', 'Code']
+function quotedStrings(value) {
+  const matches = String(value || '').matchAll(/"((?:[^"\\]|\\.)*)"/g);
+  return [...matches].map((match) => match[1].replace(/\\(.)/g, '$1'));
+}
+
+function processPathOf(pid) {
+  if (!pid) return null;
+  try {
+    return fs.readlinkSync(`/proc/${pid}/exe`);
+  } catch {
+    return null;
+  }
+}
+
+async function activeWindowX11(options) {
+  const run = (args) => execFileAsync('xprop', args, { timeout: options.timeoutMs }).then((r) => r.stdout);
+  const root = await run(['-root', '_NET_ACTIVE_WINDOW']);
+  const id = /window id # (0x[0-9a-f]+)/i.exec(root)?.[1];
+  if (!id || /^0x0+$/i.test(id)) return null;
+  const output = await run(['-id', id, '_NET_WM_NAME', 'WM_NAME', 'WM_CLASS', '_NET_WM_PID']);
+  const pid = Number(xpropValue(output, '_NET_WM_PID')) || null;
+  const wmClass = quotedStrings(xpropValue(output, 'WM_CLASS'));
+  const [title] = quotedStrings(xpropValue(output, '_NET_WM_NAME') ?? xpropValue(output, 'WM_NAME'));
+  return {
+    app: wmClass[1] || wmClass[0] || null,
+    title: title ?? null,
+    processPath: processPathOf(pid),
+    pid,
+  };
+}
+
+async function activeWindowSway(options) {
+  const { stdout } = await execFileAsync('swaymsg', ['-t', 'get_tree', '-r'], {
+    timeout: options.timeoutMs,
+    maxBuffer: 16 * 1024 * 1024,
+  });
+  const stack = [JSON.parse(stdout)];
+  while (stack.length > 0) {
+    const node = stack.pop();
+    if (node.focused && (node.type === 'con' || node.type === 'floating_con')) {
+      return {
+        app: node.app_id || node.window_properties?.class || null,
+        title: node.name || null,
+        processPath: processPathOf(node.pid),
+        pid: node.pid || null,
+      };
+    }
+    stack.push(...(node.nodes || []), ...(node.floating_nodes || []));
+  }
+  return null;
+}
+
+async function activeWindowHyprland(options) {
+  const { stdout } = await execFileAsync('hyprctl', ['activewindow', '-j'], { timeout: options.timeoutMs });
+  const window = JSON.parse(stdout || '{}');
+  if (!window.pid) return null;
+  return {
+    app: window.class || null,
+    title: window.title || null,
+    processPath: processPathOf(window.pid),
+    pid: window.pid,
+  };
+}
+
+const BACKEND_COMMANDS = { x11: 'xprop', sway: 'swaymsg', hyprland: 'hyprctl' };
+
+function linuxBackend() {
+  const env = process.env;
+  if (env.SWAYSOCK) return { name: 'sway', query: activeWindowSway };
+  if (env.HYPRLAND_INSTANCE_SIGNATURE) return { name: 'hyprland', query: activeWindowHyprland };
+  const wayland = env.XDG_SESSION_TYPE === 'wayland' || (!env.XDG_SESSION_TYPE && env.WAYLAND_DISPLAY);
+  // Under XWayland, _NET_ACTIVE_WINDOW only knows about X clients
+  if (!wayland && env.DISPLAY) return { name: 'x11', query: activeWindowX11 };
+  return null;
+}
+
+// ---------------------------------------------------------------------------
+// API
+// ---------------------------------------------------------------------------
+
+function parseLine(line) {
+  try {
+    return finishWindow(JSON.parse(line));
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * The window the user is in right now
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Promise<object|null>} { app, title, bundleId, processPath, pid, platform, kind:
+ *   'editor'|'browser'|'terminal'|'other', isCursor }, or null when no window has focus.
+ *   Rejects with UNAVAILABLE where there's no way to ask (a Wayland compositor other than sway
+ *   or Hyprland, a missing xprop)
+ */
+async function getActiveWindow(options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  try {
+    if (process.platform === 'darwin') {
+      const args = ['-l', 'JavaScript', '-e', MAC_SCRIPT(0)];
+      const { stdout } = await execFileAsync('osascript', args, { timeout: opts.timeoutMs });
+      return parseLine(stdout.trim());
+    }
+    if (process.platform === 'win32') {
+      const args = [...POWERSHELL_ARGS, WINDOWS_SCRIPT(0)];
+      const run = { timeout: opts.timeoutMs, windowsHide: true };
+      const { stdout } = await execFileAsync('powershell.exe', args, run);
+      return parseLine(stdout.trim());
+    }
+  } catch (error) {
+    throw fromSystemError(error, { command: process.platform === 'darwin' ? 'osascript' : 'powershell.exe' });
+  }
+
+  const backend = process.platform === 'linux' ? linuxBackend() : null;
+  if (!backend) {
+    throw new CompanionError(ERROR_CODES.UNAVAILABLE, 'No way to read the active window in this session', {
+      details: { platform: process.platform, session: process.env.XDG_SESSION_TYPE || null },
+    });
+  }
+  try {
+    return finishWindow(await backend.query(opts));
+  } catch (error) {
+    throw fromSystemError(error, { command: BACKEND_COMMANDS[backend.name] });
+  }
+}
+
+function sameWindow(a, b) {
+  if (!a || !b) return a === b;
+  return a.pid === b.pid && a.app === b.app && a.title === b.title && a.bundleId === b.bundleId;
+}
+
+/**
+ * Report every change of the active window
+ * @param {Function} callback - Receives (window, previous, { timestamp }); window is as from
+ *   getActiveWindow, or null while nothing has focus
+ * @param {object} options - See DEFAULT_OPTIONS, plus signal and onError (helper failures;
+ *   default logs them)
+ * @returns {object} { stop(), current() }
+ */
+function watchActiveWindow(callback, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const { signal = null } = options;
+  const onError =
+    options.onError || ((error) => console.warn('[WINDOW] Active window watch failed:', error.message));
+  let current;
+  let stopped = false;
+  let helper = null;
+  let timer = null;
+  let polling = false;
+
+  const report = (window) => {
+    if (stopped || (current !== undefined && sameWindow(window, current))) return;
+    const previous = current ?? null;
+    current = window;
+    try {
+      callback(window, previous, { timestamp: Date.now() });
+    } catch (error) {
+      console.warn('[WINDOW] Active window callback threw:', error.message);
+    }
+  };
+
+  const poll = async () => {
+    if (polling || stopped) return;
+    polling = true;
+    try {
+      report(await getActiveWindow(opts));
+    } catch (error) {
+      // Nothing to poll in this session; asking again won't change that
+      if (error.code === ERROR_CODES.UNAVAILABLE) clearInterval(timer);
+      onError(error);
+    } finally {
+      polling = false;
+    }
+  };
+
+  const startPolling = () => {
+    timer = setInterval(poll, opts.intervalMs);
+    timer.unref();
+    poll();
+  };
+
+  const startHelper = () => {
+    const [command, args] =
+      process.platform === 'darwin'
+        ? ['osascript', ['-l', 'JavaScript', '-e', MAC_SCRIPT(opts.intervalMs)]]
+        : ['powershell.exe', [...POWERSHELL_ARGS, WINDOWS_SCRIPT(opts.intervalMs)]];
+    helper = spawn(command, args, { stdio: ['ignore', 'pipe', 'pipe'], windowsHide: true });
+    readline.createInterface({ input: helper.stdout, crlfDelay: Infinity }).on('line', (line) => {
+      if (line.trim()) report(parseLine(line.trim()));
+    });
+    helper.on('error', (error) => onError(fromSystemError(error, { command })));
+    helper.on('exit', (code) => {
+      helper = null;
+      if (stopped) return;
+      // The helper died (killed, or the bridge failed); keep going one query at a time
+      onError(new CompanionError(ERROR_CODES.IO, `${command} exited with code ${code}; polling instead`));
+      startPolling();
+    });
+  };
+
+  const stop = () => {
+    stopped = true;
+    clearInterval(timer);
+    if (helper) helper.kill();
+  };
+
+  if (process.platform === 'darwin' || process.platform === 'win32') startHelper();
+  else startPolling();
+  if (signal) signal.addEventListener('abort', stop, { once: true });
+
+  return { stop, current: () => current ?? null };
+}
+
+module.exports = {
+  getActiveWindow,
+  watchActiveWindow,
+  classifyWindow,
+  finishWindow,
+  MAC_ACTIVE,
+  WINDOWS_ACTIVE,
+  CURSOR_APP,
+  DEFAULT_OPTIONS,
+};
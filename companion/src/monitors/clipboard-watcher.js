@@ -0,0 +1,374 @@
+/**
+ * Clipboard Watcher
+ * Notices clipboard copies and records a hash of the copied text with the
+ * app it was copied from, so edits that insert the same text can be
+ * attributed to a paste, and to a paste from an AI chat in particular (see
+ * edit-attribution's clipboard option).
+ *
+ * The text itself stays out of this process unless includeContent is set: a
+ * helper watches the clipboard's change counter, reads the text only when it
+ * changes and prints its hash.
+ *
+ *   macOS    NSPasteboard changeCount, hashed with CommonCrypto, through the
+ *            JavaScript for Automation ObjC bridge
+ *   Windows  GetClipboardSequenceNumber, hashed with .NET, through PowerShell
+ *   Linux    wl-paste --watch on Wayland compositors with data control (not
+ *            GNOME); xclip's selection TIMESTAMP on X11. A short-lived Node
+ *            process hashes what they print
+ *
+ * The hash is pasteHash() from edit-attribution: SHA-256 of the text with
+ * whitespace collapsed. Non-text copies are ignored, and so are copies that
+ * password managers mark as concealed (macOS, Windows). The source app is
+ * the frontmost window: on macOS and Windows the helper reads it in the same
+ * check that sees the change counter move, so a copy followed by a quick
+ * switch back to Cursor is still credited to the app it came from. On Linux
+ * it's the active window when the copy is noticed.
+ *
+ * There is no paste event to watch without a keyboard hook, so pastes are
+ * recognised from the other side: matchPaste() hashes text an edit inserted
+ * and looks it up among the recent copies.
+ */
+
+const readline = require('readline');
+const EventEmitter = require('events');
+const { execFile, spawn } = require('child_process');
+const { promisify } = require('util');
+const { getActiveWindow, finishWindow, MAC_ACTIVE, WINDOWS_ACTIVE } = require('./active-window');
+const { pasteHash } = require('../services/alignment/edit-attribution');
+const { CompanionError, ERROR_CODES, fromSystemError } = require('../utils/errors');
+
+const execFileAsync = promisify(execFile);
+
+const DEFAULT_OPTIONS = {
+  intervalMs: 500, // How often the change counter is checked
+  includeContent: false, // Keep the copied text on copy events (off: hashes only)
+  maxCopies: 200, // Recent copies kept for matchPaste()
+  maxAgeMs: 30 * 60 * 1000, // Copies older than this are forgotten
+  chatHashes: null, // Set of pasteHash() values, or a predicate, for AI chat text shown in Cursor
+  timeoutMs: 10000,
+};
+
+// Apps, and browser window titles, that are AI chats
+const CHAT_APPS =
+  /com\.openai\.chat|chatgpt|com\.anthropic\.claude|\bclaude\b|\bgemini\b|copilot|perplexity|deepseek/i;
+
+// Environment variable carrying the watch spec to the helper scripts
+const SPEC_ENV = 'CURSOR_TELEMETRY_CLIPBOARD';
+
+// Helpers print one line per copy: { hash (base64 SHA-256), length, lines, text?, source? },
+// source being the frontmost window as active-window's helpers see it (null for none)
+
+// org.nspasteboard.* markers are the convention password managers use for secrets
+const MAC_SCRIPT = `${MAC_ACTIVE}
+ObjC.bindFunction('CC_SHA256', ['void *', ['void *', 'unsigned int', 'void *']]);
+const SKIP_TYPES = [
+  'org.nspasteboard.ConcealedType',
+  'org.nspasteboard.TransientType',
+  'org.nspasteboard.AutoGeneratedType',
+];
+
+function run() {
+  const spec = JSON.parse(ObjC.unwrap($.NSProcessInfo.processInfo.environment.objectForKey('${SPEC_ENV}')));
+  const out = $.NSFileHandle.fileHandleWithStandardOutput;
+  const board = $.NSPasteboard.generalPasteboard;
+  let count = board.changeCount;
+  for (;;) {
+    delay(spec.intervalMs / 1000);
+    if (board.changeCount === count) continue;
+    count = board.changeCount;
+    const source = active();
+    const types = ObjC.deepUnwrap(board.types) || [];
+    if (types.some((type) => SKIP_TYPES.includes(type))) continue;
+    const text = ObjC.unwrap(board.stringForType($.NSPasteboardTypeString));
+    const normalized = text ? text.replace(/\\s+/g, ' ').trim() : '';
+    if (!normalized) continue;
+    const data = $(normalized).dataUsingEncoding($.NSUTF8StringEncoding);
+    const digest = $.NSMutableData.dataWithLength(32);
+    $.CC_SHA256(data.bytes, data.length, digest.mutableBytes);
+    const copy = {
+      hash: ObjC.unwrap(digest.base64EncodedStringWithOptions(0)),
+      length: normalized.length,
+      lines: text.split(/\\r\\n|\\r|\\n/).length,
+      text: spec.includeContent ? text : undefined,
+      source,
+    };
+    out.writeData($(JSON.stringify(copy) + '\\n').dataUsingEncoding($.NSUTF8StringEncoding));
+  }
+}
+`;
+
+// Clipboard history and monitoring tools skip data carrying this format
+const WINDOWS_SCRIPT = `${WINDOWS_ACTIVE}
+$exclude = 'ExcludeClipboardContentFromMonitorProcessing'
+Add-Type -AssemblyName System.Windows.Forms
+Add-Type -Namespace CursorTelemetry -Name Clipboard -MemberDefinition @'
+[DllImport("user32.dll")] public static extern uint GetClipboardSequenceNumber();
+'@
+$spec = $env:${SPEC_ENV} | ConvertFrom-Json
+$sha = [System.Security.Cryptography.SHA256]::Create()
+$last = [CursorTelemetry.Clipboard]::GetClipboardSequenceNumber()
+while ($true) {
+  Start-Sleep -Milliseconds $spec.intervalMs
+  $sequence = [CursorTelemetry.Clipboard]::GetClipboardSequenceNumber()
+  if ($sequence -eq $last) { continue }
+  $last = $sequence
+  $source = Get-ForegroundApp
+  try {
+    if ([System.Windows.Forms.Clipboard]::ContainsData($exclude)) { continue }
+    if (-not [System.Windows.Forms.Clipboard]::ContainsText()) { continue }
+    $text = [System.Windows.Forms.Clipboard]::GetText()
+  } catch {
+    continue # Another process has the clipboard open
+  }
+  $normalized = [regex]::Replace($text, '\\s+', ' ').Trim(' ')
+  if (-not $normalized) { continue }
+  $copy = [ordered]@{
+    hash = [Convert]::ToBase64String($sha.ComputeHash([Text.Encoding]::UTF8.GetBytes($normalized)))
+    length = $normalized.Length
+    lines = ($text -split '\\r\\n|\\r|\\n').Count
+    source = $source
+  }
+  if ($spec.includeContent) { $copy.text = $text }
+  [Console]::Out.WriteLine(($copy | ConvertTo-Json -Compress))
+  [Console]::Out.Flush()
+}
+`;
+
+// The clipboard classes need a single-threaded apartment
+const POWERSHELL_ARGS = ['-NoProfile', '-NonInteractive', '-STA', '-Command'];
+
+// Run by the Node binary (or Electron as Node) on Linux with the clipboard text on stdin
+const HASH_SCRIPT = `
+const chunks = [];
+process.stdin.on('data', (chunk) => chunks.push(chunk));
+process.stdin.on('end', () => {
+  const text = Buffer.concat(chunks).toString('utf8');
+  const normalized = text.replace(/\\s+/g, ' ').trim();
+  if (!normalized) return;
+  const hash = require('crypto').createHash('sha256').update(normalized, 'utf8').digest('base64');
+  const lines = text.split(/\\r\\n|\\r|\\n/).length;
+  const content = process.env.${SPEC_ENV}_CONTENT === '1' ? text : undefined;
+  process.stdout.write(JSON.stringify({ hash, length: normalized.length, lines, text: content }) + '\\n');
+});
+`;
+
+function linuxBackend() {
+  const env = process.env;
+  const wayland = env.XDG_SESSION_TYPE === 'wayland' || (!env.XDG_SESSION_TYPE && env.WAYLAND_DISPLAY);
+  if (wayland && env.WAYLAND_DISPLAY) return 'wayland';
+  return env.DISPLAY ? 'x11' : null;
+}
+
+/**
+ * Whether a window belongs to an AI chat: a chat app, or a browser showing one
+ */
+function isChatWindow(window) {
+  if (!window) return false;
+  if (CHAT_APPS.test([window.bundleId, window.app].filter(Boolean).join(' '))) return true;
+  return window.kind === 'browser' && CHAT_APPS.test(window.title || '');
+}
+
+class ClipboardWatcher extends EventEmitter {
+  constructor(options = {}) {
+    super();
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.isMonitoring = false;
+    this.copies = []; // Oldest first
+    this.helper = null;
+    this.timer = null;
+    this.lastSelection = undefined; // X11 selection TIMESTAMP; null while nothing owns it
+    this.polling = false;
+  }
+
+  /**
+   * Start watching; emits 'copy' with { timestamp, hash, length, lines, source, fromChat, text? }
+   * where source is the frontmost window (see active-window) or null
+   */
+  start() {
+    if (this.isMonitoring) return;
+    this.isMonitoring = true;
+    if (process.platform === 'darwin') {
+      this.startHelper('osascript', ['-l', 'JavaScript', '-e', MAC_SCRIPT]);
+    } else if (process.platform === 'win32') {
+      this.startHelper('powershell.exe', [...POWERSHELL_ARGS, WINDOWS_SCRIPT]);
+    } else if (process.platform === 'linux' && linuxBackend() === 'wayland') {
+      this.startHelper('wl-paste', ['--type', 'text', '--watch', process.execPath, '-e', HASH_SCRIPT]);
+    } else if (process.platform === 'linux' && linuxBackend() === 'x11') {
+      this.timer = setInterval(() => this.pollX11(), this.options.intervalMs);
+      this.timer.unref();
+      this.pollX11();
+    } else {
+      this.isMonitoring = false;
+      this.report(new CompanionError(ERROR_CODES.UNAVAILABLE, 'No clipboard to watch in this session'));
+    }
+  }
+
+  stop() {
+    this.isMonitoring = false;
+    clearInterval(this.timer);
+    this.timer = null;
+    if (this.helper) this.helper.kill();
+    this.helper = null;
+  }
+
+  helperEnv() {
+    const spec = { intervalMs: this.options.intervalMs, includeContent: this.options.includeContent };
+    return {
+      ...process.env,
+      [SPEC_ENV]: JSON.stringify(spec),
+      [`${SPEC_ENV}_CONTENT`]: this.options.includeContent ? '1' : '0',
+      ELECTRON_RUN_AS_NODE: '1', // process.execPath is Electron inside the app
+    };
+  }
+
+  startHelper(command, args) {
+    const helper = spawn(command, args, {
+      stdio: ['ignore', 'pipe', 'pipe'],
+      env: this.helperEnv(),
+      windowsHide: true,
+    });
+    this.helper = helper;
+    readline.createInterface({ input: helper.stdout, crlfDelay: Infinity }).on('line', (line) => {
+      if (line.trim()) this.handleLine(line);
+    });
+    helper.on('error', (error) => this.report(fromSystemError(error, { command })));
+    helper.on('exit', (code) => {
+      if (this.helper !== helper) return;
+      this.helper = null;
+      if (!this.isMonitoring) return;
+      this.isMonitoring = false;
+      this.report(new CompanionError(ERROR_CODES.IO, `${command} exited with code ${code}`));
+    });
+  }
+
+  /**
+   * X11 has no change notification without a helper of its own, but the CLIPBOARD selection's
+   * TIMESTAMP changes with every copy and carries no content
+   */
+  async pollX11() {
+    if (this.polling || !this.isMonitoring) return;
+    this.polling = true;
+    const { timeoutMs } = this.options;
+    try {
+      const selection = await execFileAsync('xclip', ['-o', '-selection', 'clipboard', '-t', 'TIMESTAMP'], {
+        timeout: timeoutMs,
+      }).then(
+        ({ stdout }) => stdout.trim(),
+        (error) => {
+          if (error.code === 'ENOENT') throw fromSystemError(error, { command: 'xclip' });
+          return null; // Nothing owns the clipboard
+        }
+      );
+      const changed = this.lastSelection !== undefined && selection !== this.lastSelection;
+      this.lastSelection = selection;
+      if (!changed || selection === null) return;
+      const script = 'xclip -o -selection clipboard 2>/dev/null | "$0" -e "$1"';
+      const { stdout } = await execFileAsync('sh', ['-c', script, process.execPath, HASH_SCRIPT], {
+        timeout: timeoutMs,
+        env: this.helperEnv(),
+      });
+      if (stdout.trim()) await this.handleLine(stdout.trim());
+    } catch (error) {
+      const failure = fromSystemError(error, { command: 'xclip' });
+      if (failure.code === ERROR_CODES.UNAVAILABLE) this.stop();
+      this.report(failure);
+    } finally {
+      this.polling = false;
+    }
+  }
+
+  async handleLine(line) {
+    let parsed;
+    try {
+      parsed = JSON.parse(line);
+    } catch {
+      return;
+    }
+    if (!parsed.hash) return;
+    const timestamp = Date.now();
+    // The macOS and Windows helpers say which app was in front when they saw the change
+    const source =
+      'source' in parsed
+        ? finishWindow(parsed.source)
+        : await getActiveWindow({ timeoutMs: this.options.timeoutMs }).catch(() => null);
+    const hash = Buffer.from(parsed.hash, 'base64').toString('hex');
+    const copy = {
+      timestamp,
+      hash,
+      length: parsed.length,
+      lines: parsed.lines,
+      source,
+      fromChat: isChatWindow(source) || Boolean(source?.isCursor && this.isChatText(hash)),
+    };
+    if (this.options.includeContent && typeof parsed.text === 'string') copy.text = parsed.text;
+
+    this.copies.push(copy);
+    this.prune(timestamp);
+    try {
+      this.emit('copy', { ...copy });
+    } catch (error) {
+      console.warn('[CLIPBOARD] Copy listener threw:', error.message);
+    }
+  }
+
+  isChatText(hash) {
+    const { chatHashes } = this.options;
+    if (typeof chatHashes === 'function') return Boolean(chatHashes(hash));
+    return Boolean(chatHashes && chatHashes.has(hash));
+  }
+
+  prune(now = Date.now()) {
+    const { maxAgeMs, maxCopies } = this.options;
+    this.copies = this.copies.filter((copy) => now - copy.timestamp <= maxAgeMs).slice(-maxCopies);
+  }
+
+  report(error) {
+    if (this.listenerCount('error') > 0) this.emit('error', error);
+    else console.warn('[CLIPBOARD] Clipboard watch failed:', error.message);
+  }
+
+  /**
+   * Recent copies, oldest first, in the shape edit-attribution's clipboard option takes
+   * @returns {Array} [{ timestamp, hash, length, lines, source, fromChat }]
+   */
+  recentCopies() {
+    this.prune();
+    return this.copies.map(({ text, ...copy }) => copy);
+  }
+
+  /**
+   * Whether text an edit inserted was pasted from a recent copy; emits 'paste' when it was
+   * @param {string} text - The inserted text
+   * @param {object} context - { timestamp (of the edit; default now), filePath }
+   * @returns {object|null} { timestamp, filePath, hash, copiedAt, source, fromChat }, a paste
+   *   event edit-attribution takes as is, or null
+   */
+  matchPaste(text, context = {}) {
+    const hash = pasteHash(text);
+    if (!hash) return null;
+    const timestamp = context.timestamp ?? Date.now();
+    this.prune();
+    const copy = this.copies.findLast((entry) => entry.hash === hash && entry.timestamp <= timestamp);
+    if (!copy) return null;
+    const paste = {
+      timestamp,
+      filePath: context.filePath || null,
+      hash,
+      copiedAt: copy.timestamp,
+      source: copy.source,
+      fromChat: copy.fromChat,
+    };
+    try {
+      this.emit('paste', { ...paste });
+    } catch (error) {
+      console.warn('[CLIPBOARD] Paste listener threw:', error.message);
+    }
+    return paste;
+  }
+}
+
+module.exports = {
+  ClipboardWatcher,
+  isChatWindow,
+  DEFAULT_OPTIONS,
+};
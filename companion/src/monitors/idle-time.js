@@ -0,0 +1,281 @@
+/**
+ * Idle Time
+ * How long since the user last touched the keyboard or mouse, from the
+ * system's own input bookkeeping, so session segmentation can stop counting
+ * wall-clock time during breaks.
+ *
+ *   macOS    CGEventSourceSecondsSinceLastEventType over any input event,
+ *            through the JavaScript for Automation ObjC bridge (no
+ *            permission needed: only the time is read, not the events)
+ *   Windows  GetLastInputInfo through PowerShell P/Invoke
+ *   Linux    GNOME's Mutter IdleMonitor (X11 and Wayland), xprintidle on
+ *            X11, the freedesktop ScreenSaver interface (KDE), then
+ *            systemd-logind's IdleSinceHint, whichever answers first
+ *
+ * watchIdle() reports the transitions: 'idle' once input has been quiet for
+ * thresholdMs (with the time it went quiet), 'active' when input resumes
+ * (with how long the break lasted). Like active-window, it keeps one helper
+ * process running on macOS and Windows and polls on Linux.
+ */
+
+const readline = require('readline');
+const { execFile, spawn } = require('child_process');
+const { promisify } = require('util');
+const { CompanionError, ERROR_CODES, fromSystemError } = require('../utils/errors');
+
+const execFileAsync = promisify(execFile);
+
+const DEFAULT_OPTIONS = {
+  thresholdMs: 5 * 60 * 1000, // Quiet input for this long is a break
+  intervalMs: 5000, // How often idle time is sampled
+  timeoutMs: 10000, // For one-shot queries
+};
+
+// kCGEventSourceStateCombinedSessionState (0) and kCGAnyInputEventType (~0)
+const MAC_SCRIPT = (loopMs) => `
+ObjC.import('CoreGraphics');
+function idleMs() {
+  return Math.round($.CGEventSourceSecondsSinceLastEventType(0, 4294967295) * 1000);
+}
+${
+  loopMs
+    ? `const out = $.NSFileHandle.fileHandleWithStandardOutput;
+for (;;) {
+  out.writeData($(String(idleMs()) + '\\n').dataUsingEncoding($.NSUTF8StringEncoding));
+  delay(${loopMs / 1000});
+}`
+    : 'String(idleMs());'
+}
+`;
+
+// TickCount wraps after 49.7 days; the unsigned difference is still right
+const WINDOWS_SCRIPT = (loopMs) => `
+Add-Type -Namespace CursorTelemetry -Name Input -MemberDefinition @'
+[StructLayout(LayoutKind.Sequential)]
+public struct LASTINPUTINFO { public uint cbSize; public uint dwTime; }
+[DllImport("user32.dll")]
+public static extern bool GetLastInputInfo(ref LASTINPUTINFO info);
+public static uint IdleMs() {
+  LASTINPUTINFO info = new LASTINPUTINFO();
+  info.cbSize = (uint)Marshal.SizeOf(info);
+  if (!GetLastInputInfo(ref info)) return 0;
+  return unchecked((uint)Environment.TickCount - info.dwTime);
+}
+'@
+${
+  loopMs
+    ? `while ($true) {
+  [Console]::Out.WriteLine([CursorTelemetry.Input]::IdleMs())
+  [Console]::Out.Flush()
+  Start-Sleep -Milliseconds ${loopMs}
+}`
+    : '[CursorTelemetry.Input]::IdleMs()'
+}
+`;
+
+const POWERSHELL_ARGS = ['-NoProfile', '-NonInteractive', '-Command'];
+
+// Linux sources, tried in order; each resolves to milliseconds or throws
+const LINUX_SOURCES = [
+  {
+    name: 'mutter',
+    command: 'gdbus',
+    args: [
+      'call',
+      '--session',
+      '--dest',
+      'org.gnome.Mutter.IdleMonitor',
+      '--object-path',
+      '/org/gnome/Mutter/IdleMonitor/Core',
+      '--method',
+      'org.gnome.Mutter.IdleMonitor.GetIdletime',
+    ],
+    // "(uint64 1234,)"
+    parse: (stdout) => Number(/uint64\s+(\d+)/.exec(stdout)?.[1]),
+  },
+  {
+    name: 'xprintidle',
+    command: 'xprintidle',
+    args: [],
+    available: () => Boolean(process.env.DISPLAY),
+    parse: (stdout) => Number(stdout.trim()),
+  },
+  {
+    name: 'screensaver',
+    command: 'gdbus',
+    args: [
+      'call',
+      '--session',
+      '--dest',
+      'org.freedesktop.ScreenSaver',
+      '--object-path',
+      '/org/freedesktop/ScreenSaver',
+      '--method',
+      'org.freedesktop.ScreenSaver.GetSessionIdleTime',
+    ],
+    // "(uint32 12,)" - seconds
+    parse: (stdout) => Number(/uint32\s+(\d+)/.exec(stdout)?.[1]) * 1000,
+  },
+  {
+    name: 'logind',
+    command: 'loginctl',
+    args: ['show-session', process.env.XDG_SESSION_ID || 'auto', '-p', 'IdleHint', '-p', 'IdleSinceHint'],
+    // IdleSinceHint is a realtime timestamp in microseconds, meaningful only while IdleHint=yes
+    parse: (stdout) => {
+      if (!/IdleHint=yes/.test(stdout)) return 0;
+      const since = Number(/IdleSinceHint=(\d+)/.exec(stdout)?.[1]) / 1000;
+      return since > 0 ? Math.max(0, Date.now() - since) : NaN;
+    },
+  },
+];
+
+let linuxSource = null; // The first source that answered, tried first next time
+
+async function linuxIdleMs(options) {
+  const sources = linuxSource
+    ? [linuxSource, ...LINUX_SOURCES.filter((source) => source !== linuxSource)]
+    : LINUX_SOURCES;
+  for (const source of sources) {
+    if (source.available && !source.available()) continue;
+    try {
+      const { stdout } = await execFileAsync(source.command, source.args, { timeout: options.timeoutMs });
+      const idleMs = source.parse(stdout);
+      if (Number.isFinite(idleMs)) {
+        linuxSource = source;
+        return Math.round(idleMs);
+      }
+    } catch {
+      // Not this desktop; try the next source
+    }
+  }
+  linuxSource = null;
+  throw new CompanionError(ERROR_CODES.UNAVAILABLE, 'No idle time source answered in this session', {
+    details: { tried: LINUX_SOURCES.map((source) => source.name) },
+  });
+}
+
+/**
+ * Milliseconds since the last keyboard or mouse input
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Promise<number>} Rejects with UNAVAILABLE where no source answers (e.g. a headless
+ *   session, or a Wayland compositor without an idle interface)
+ */
+async function getIdleTimeMs(options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  if (process.platform === 'linux') return linuxIdleMs(opts);
+  const [command, args] =
+    process.platform === 'darwin'
+      ? ['osascript', ['-l', 'JavaScript', '-e', MAC_SCRIPT(0)]]
+      : process.platform === 'win32'
+        ? ['powershell.exe', [...POWERSHELL_ARGS, WINDOWS_SCRIPT(0)]]
+        : [null, null];
+  if (!command) {
+    throw new CompanionError(ERROR_CODES.UNAVAILABLE, `Idle time isn't available on ${process.platform}`);
+  }
+  try {
+    const { stdout } = await execFileAsync(command, args, { timeout: opts.timeoutMs, windowsHide: true });
+    const idleMs = Number(stdout.trim());
+    if (!Number.isFinite(idleMs)) throw new CompanionError(ERROR_CODES.IO, `Unexpected ${command} output`);
+    return idleMs;
+  } catch (error) {
+    throw fromSystemError(error, { command });
+  }
+}
+
+/**
+ * Report transitions between active and idle
+ * @param {Function} callback - Receives { state: 'idle', idleMs, since } when input has been quiet
+ *   for thresholdMs (since = when it went quiet), and { state: 'active', idleMs, since, idleForMs }
+ *   when input resumes after that (idleForMs = length of the break); timestamps in ms
+ * @param {object} options - See DEFAULT_OPTIONS, plus signal and onError (default logs)
+ * @returns {object} { stop(), current(): { state, idleMs, since } | null }
+ */
+function watchIdle(callback, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const { signal = null } = options;
+  const onError = options.onError || ((error) => console.warn('[IDLE] Idle watch failed:', error.message));
+  let current = null;
+  let stopped = false;
+  let helper = null;
+  let timer = null;
+  let polling = false;
+
+  const emit = (event) => {
+    try {
+      callback(event);
+    } catch (error) {
+      console.warn('[IDLE] Idle callback threw:', error.message);
+    }
+  };
+
+  // since is when the current state began: when input went quiet, or when it resumed
+  const sample = (idleMs) => {
+    if (stopped || !Number.isFinite(idleMs)) return;
+    const lastInput = Date.now() - idleMs;
+    const previous = current;
+    if (idleMs >= opts.thresholdMs) {
+      current = { state: 'idle', idleMs, since: previous?.state === 'idle' ? previous.since : lastInput };
+      if (previous?.state !== 'idle') emit({ ...current });
+    } else if (previous?.state === 'idle') {
+      current = { state: 'active', idleMs, since: lastInput };
+      emit({ ...current, idleForMs: Math.max(0, lastInput - previous.since) });
+    } else {
+      current = { state: 'active', idleMs, since: previous ? previous.since : lastInput };
+    }
+  };
+
+  const poll = async () => {
+    if (polling || stopped) return;
+    polling = true;
+    try {
+      sample(await getIdleTimeMs(opts));
+    } catch (error) {
+      if (error.code === ERROR_CODES.UNAVAILABLE) clearInterval(timer);
+      onError(error);
+    } finally {
+      polling = false;
+    }
+  };
+
+  const startPolling = () => {
+    timer = setInterval(poll, opts.intervalMs);
+    timer.unref();
+    poll();
+  };
+
+  const startHelper = () => {
+    const [command, args] =
+      process.platform === 'darwin'
+        ? ['osascript', ['-l', 'JavaScript', '-e', MAC_SCRIPT(opts.intervalMs)]]
+        : ['powershell.exe', [...POWERSHELL_ARGS, WINDOWS_SCRIPT(opts.intervalMs)]];
+    helper = spawn(command, args, { stdio: ['ignore', 'pipe', 'pipe'], windowsHide: true });
+    readline.createInterface({ input: helper.stdout, crlfDelay: Infinity }).on('line', (line) => {
+      if (line.trim()) sample(Number(line.trim()));
+    });
+    helper.on('error', (error) => onError(fromSystemError(error, { command })));
+    helper.on('exit', (code) => {
+      helper = null;
+      if (stopped) return;
+      onError(new CompanionError(ERROR_CODES.IO, `${command} exited with code ${code}; polling instead`));
+      startPolling();
+    });
+  };
+
+  const stop = () => {
+    stopped = true;
+    clearInterval(timer);
+    if (helper) helper.kill();
+  };
+
+  if (process.platform === 'darwin' || process.platform === 'win32') startHelper();
+  else startPolling();
+  if (signal) signal.addEventListener('abort', stop, { once: true });
+
+  return { stop, current: () => (current ? { ...current } : null) };
+}
+
+module.exports = {
+  getIdleTimeMs,
+  watchIdle,
+  DEFAULT_OPTIONS,
+};
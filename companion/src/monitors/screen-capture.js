@@ -0,0 +1,722 @@
+/**
+ * Screen Capture
+ * Screenshots of the screen or of the Cursor window, downscaled and with the
+ * windows of configured apps blurred out, returned as an image buffer. The
+ * image never touches the disk: helpers hand it back over stdout.
+ *
+ *   macOS    CGWindowListCreateImage, drawn and encoded with AppKit through
+ *            the JavaScript for Automation ObjC bridge; needs Screen
+ *            Recording (see permission-onboarding)
+ *   Windows  GDI CopyFromScreen with System.Drawing, through PowerShell
+ *   Linux    X11: ImageMagick's import; wlroots compositors (sway,
+ *            Hyprland): grim. Masking, cropping and scaling are done by
+ *            ImageMagick. Other Wayland compositors only offer capture
+ *            through the ScreenCast portal, which asks the user every time,
+ *            so they're unsupported
+ *
+ * captureAppWindow() captures one window and never anything else on screen,
+ * for when screenshots must not show other apps at all.
+ *
+ * Masks cover a window's whole bounds, including parts hidden behind other
+ * windows: masking too much is fine, too little isn't. 'blur' pixelates the
+ * area (coarse enough that text can't be read back), 'fill' paints it grey.
+ * The screen is the main display on macOS and Windows and every output on
+ * Linux.
+ */
+
+const { execFile, spawn } = require('child_process');
+const { promisify } = require('util');
+const { CURSOR_APP } = require('./active-window');
+const { CompanionError, ERROR_CODES, fromSystemError } = require('../utils/errors');
+
+const execFileAsync = promisify(execFile);
+
+const DEFAULT_OPTIONS = {
+  target: 'screen', // 'screen' | 'cursor' (the Cursor window's area of the screen)
+  maxWidth: 1600, // Output is scaled down to fit maxWidth x maxHeight, never up
+  maxHeight: 1600,
+  scale: 1, // Additional scale factor (0-1]
+  format: 'png', // 'png' | 'jpeg' | 'webp' (webp only where ImageMagick has it, i.e. Linux)
+  quality: 80, // jpeg / webp
+  maskApps: [], // App names or bundle ids (case-insensitive substrings) whose windows are masked
+  maskMode: 'blur', // 'blur' | 'fill'
+  blockSize: 24, // Output pixels per block when blurring
+  timeoutMs: 20000,
+};
+
+// Environment variable carrying the capture spec to the helper scripts
+const SPEC_ENV = 'CURSOR_TELEMETRY_CAPTURE';
+const MAX_OUTPUT_BYTES = 256 * 1024 * 1024;
+const POWERSHELL_ARGS = ['-NoProfile', '-NonInteractive', '-Command'];
+
+// ---------------------------------------------------------------------------
+// macOS
+// ---------------------------------------------------------------------------
+
+const MAC_SCRIPT = `
+ObjC.import('AppKit');
+ObjC.import('CoreGraphics');
+
+function newRep(width, height) {
+  const init =
+    'initWithBitmapDataPlanesPixelsWidePixelsHighBitsPerSampleSamplesPerPixel' +
+    'HasAlphaIsPlanarColorSpaceNameBytesPerRowBitsPerPixel';
+  const colorSpace = $.NSDeviceRGBColorSpace;
+  return $.NSBitmapImageRep.alloc[init](null, width, height, 8, 4, true, false, colorSpace, 0, 0);
+}
+
+function run() {
+  const spec = JSON.parse(ObjC.unwrap($.NSProcessInfo.processInfo.environment.objectForKey('${SPEC_ENV}')));
+  if (typeof $.CGPreflightScreenCaptureAccess === 'function' && !$.CGPreflightScreenCaptureAccess()) {
+    return JSON.stringify({ error: 'permission' });
+  }
+  const cursorApp = new RegExp(spec.cursorApp, 'i');
+  const onScreen = $.kCGWindowListOptionOnScreenOnly | $.kCGWindowListExcludeDesktopElements;
+  const info = ObjC.deepUnwrap(ObjC.castRefToObject($.CGWindowListCopyWindowInfo(onScreen, 0))) || [];
+  const windows = info
+    .filter((w) => w.kCGWindowLayer === 0 && w.kCGWindowBounds)
+    .map((w) => {
+      const app = $.NSRunningApplication.runningApplicationWithProcessIdentifier(w.kCGWindowOwnerPID);
+      const b = w.kCGWindowBounds;
+      return {
+        id: w.kCGWindowNumber,
+        pid: w.kCGWindowOwnerPID,
+        app: w.kCGWindowOwnerName || null,
+        bundleId: app && !app.isNil() ? ObjC.unwrap(app.bundleIdentifier) || null : null,
+        bounds: { x: b.X, y: b.Y, width: b.Width, height: b.Height },
+      };
+    });
+
+  let area;
+  let image;
+  let own = null;
+  if (spec.window) {
+    // Just this window's own pixels (kCGWindowListOptionIncludingWindow), whatever covers it
+    const pattern = spec.window.pattern && new RegExp(spec.window.pattern, 'i');
+    const matches = (w) =>
+      spec.window.pid
+        ? w.pid === spec.window.pid
+        : [w.app, w.bundleId].some((name) => name && pattern.test(name));
+    own = windows.find(matches); // Front to back, so the frontmost
+    if (!own) return JSON.stringify({ error: 'not-found' });
+    area = own.bounds;
+    const rect = $.CGRectMake(area.x, area.y, area.width, area.height);
+    const only = $.kCGWindowListOptionIncludingWindow;
+    image = $.CGWindowListCreateImage(rect, only, own.id, $.kCGWindowImageBoundsIgnoreFraming);
+  } else {
+    if (spec.target === 'cursor') {
+      const cursor = windows.find((w) => cursorApp.test([w.app, w.bundleId].join(' ')));
+      if (!cursor) return JSON.stringify({ error: 'not-found' });
+      area = cursor.bounds;
+    } else {
+      const b = $.CGDisplayBounds($.CGMainDisplayID());
+      area = { x: b.origin.x, y: b.origin.y, width: b.size.width, height: b.size.height };
+    }
+    const rect = $.CGRectMake(area.x, area.y, area.width, area.height);
+    image = $.CGWindowListCreateImage(rect, $.kCGWindowListOptionOnScreenOnly, 0, 0);
+  }
+  const pixelWidth = $.CGImageGetWidth(image);
+  const pixelHeight = $.CGImageGetHeight(image);
+  if (!pixelWidth || !pixelHeight) return JSON.stringify({ error: 'empty' });
+
+  const scale = Math.min(1, spec.scale, spec.maxWidth / pixelWidth, spec.maxHeight / pixelHeight);
+  const width = Math.max(1, Math.round(pixelWidth * scale));
+  const height = Math.max(1, Math.round(pixelHeight * scale));
+  const rep = newRep(width, height);
+  const context = $.NSGraphicsContext.graphicsContextWithBitmapImageRep(rep);
+  $.NSGraphicsContext.setCurrentContext(context);
+  context.setImageInterpolation($.NSImageInterpolationHigh);
+  const scaled = $.NSImage.alloc.initWithCGImageSize(image, $.NSMakeSize(width, height));
+  scaled.drawInRect($.NSMakeRect(0, 0, width, height));
+
+  // Window bounds are points from the top left; the bitmap is pixels from the bottom left
+  const k = width / area.width;
+  const masked = [];
+  const maskApps = spec.maskApps.map((name) => name.toLowerCase());
+  for (const w of windows) {
+    const names = [w.app, w.bundleId].filter(Boolean).join(' ').toLowerCase();
+    if (!maskApps.some((name) => names.includes(name))) continue;
+    const left = Math.max(0, (w.bounds.x - area.x) * k);
+    const top = Math.max(0, (w.bounds.y - area.y) * k);
+    const right = Math.min(width, (w.bounds.x + w.bounds.width - area.x) * k);
+    const bottom = Math.min(height, (w.bounds.y + w.bounds.height - area.y) * k);
+    if (right <= left || bottom <= top) continue;
+    const target = $.NSMakeRect(left, height - bottom, right - left, bottom - top);
+    if (spec.maskMode === 'fill') {
+      $.NSColor.colorWithCalibratedWhiteAlpha(0.5, 1).setFill;
+      $.NSRectFill(target);
+    } else {
+      // Pixelate: shrink the area into a few blocks, then draw them back without smoothing
+      const snapshot = $.NSImage.alloc.initWithCGImageSize(rep.CGImage, $.NSMakeSize(width, height));
+      const tiny = newRep(
+        Math.max(1, Math.round((right - left) / spec.blockSize)),
+        Math.max(1, Math.round((bottom - top) / spec.blockSize))
+      );
+      $.NSGraphicsContext.setCurrentContext($.NSGraphicsContext.graphicsContextWithBitmapImageRep(tiny));
+      snapshot.drawInRectFromRectOperationFraction(
+        $.NSMakeRect(0, 0, tiny.pixelsWide, tiny.pixelsHigh), target, $.NSCompositingOperationCopy, 1
+      );
+      $.NSGraphicsContext.setCurrentContext(context);
+      context.setImageInterpolation($.NSImageInterpolationNone);
+      tiny.drawInRect(target);
+      context.setImageInterpolation($.NSImageInterpolationHigh);
+    }
+    masked.push({ app: w.app, bundleId: w.bundleId, bounds: w.bounds });
+  }
+  context.flushGraphics;
+
+  // NSBitmapImageFileTypeJPEG (3), NSBitmapImageFileTypePNG (4)
+  const properties = spec.format === 'jpeg' ? $({ NSImageCompressionFactor: spec.quality / 100 }) : $();
+  const data = rep.representationUsingTypeProperties(spec.format === 'jpeg' ? 3 : 4, properties);
+  return JSON.stringify({
+    width,
+    height,
+    scale: width / area.width,
+    area,
+    masked,
+    window: own && { app: own.app, bundleId: own.bundleId, pid: own.pid, bounds: own.bounds },
+    data: ObjC.unwrap(data.base64EncodedStringWithOptions(0)),
+  });
+}
+`;
+
+// ---------------------------------------------------------------------------
+// Windows
+// ---------------------------------------------------------------------------
+
+const WINDOWS_SCRIPT = `
+Add-Type -AssemblyName System.Drawing, System.Windows.Forms
+Add-Type -Namespace CursorTelemetry -Name Capture -MemberDefinition @'
+[DllImport("user32.dll")] public static extern bool SetProcessDPIAware();
+[DllImport("user32.dll")] public static extern bool IsIconic(IntPtr window);
+[StructLayout(LayoutKind.Sequential)] public struct RECT { public int Left, Top, Right, Bottom; }
+[DllImport("user32.dll")] public static extern bool GetWindowRect(IntPtr window, out RECT rect);
+[DllImport("user32.dll")] public static extern bool PrintWindow(IntPtr window, IntPtr dc, uint flags);
+'@
+# Physical pixels, so window rectangles and the captured bitmap agree
+[void][CursorTelemetry.Capture]::SetProcessDPIAware()
+$spec = $env:${SPEC_ENV} | ConvertFrom-Json
+
+$windows = @(Get-Process | Where-Object { $_.MainWindowHandle -ne [IntPtr]::Zero } | ForEach-Object {
+  $handle = $_.MainWindowHandle
+  if ([CursorTelemetry.Capture]::IsIconic($handle)) { return }
+  $rect = New-Object CursorTelemetry.Capture+RECT
+  if (-not [CursorTelemetry.Capture]::GetWindowRect($handle, [ref]$rect)) { return }
+  $description = $null
+  try { $description = $_.MainModule.FileVersionInfo.FileDescription } catch {}
+  [pscustomobject]@{
+    handle = $handle
+    pid = $_.Id
+    app = $(if ($description) { $description } else { $_.ProcessName })
+    processName = $_.ProcessName
+    bounds = [pscustomobject]@{
+      x = $rect.Left; y = $rect.Top; width = $rect.Right - $rect.Left; height = $rect.Bottom - $rect.Top
+    }
+  }
+})
+
+$own = $null
+if ($spec.window) {
+  $own = $windows | Where-Object {
+    if ($spec.window.pid) { $_.pid -eq $spec.window.pid }
+    else { $_.app -match $spec.window.pattern -or $_.processName -match $spec.window.pattern }
+  } | Select-Object -First 1
+  if (-not $own) { return '{"error":"not-found"}' }
+  $area = $own.bounds
+} elseif ($spec.target -eq 'cursor') {
+  $cursor = $windows |
+    Where-Object { ($_.app + ' ' + $_.processName) -match $spec.cursorApp } |
+    Select-Object -First 1
+  if (-not $cursor) { return '{"error":"not-found"}' }
+  $area = $cursor.bounds
+} else {
+  $screen = [System.Windows.Forms.Screen]::PrimaryScreen.Bounds
+  $area = [pscustomobject]@{ x = $screen.X; y = $screen.Y; width = $screen.Width; height = $screen.Height }
+}
+
+$source = New-Object System.Drawing.Bitmap $area.width, $area.height
+$graphics = [System.Drawing.Graphics]::FromImage($source)
+if ($own) {
+  # The window renders itself into our bitmap (PW_RENDERFULLCONTENT), so nothing covering it is included
+  $dc = $graphics.GetHdc()
+  $printed = [CursorTelemetry.Capture]::PrintWindow($own.handle, $dc, 2)
+  $graphics.ReleaseHdc($dc)
+  if (-not $printed) { return '{"error":"print-window"}' }
+} else {
+  $graphics.CopyFromScreen($area.x, $area.y, 0, 0, $source.Size)
+}
+$graphics.Dispose()
+
+$fit = [Math]::Min($spec.maxWidth / $area.width, $spec.maxHeight / $area.height)
+$scale = [Math]::Min(1, [Math]::Min($spec.scale, $fit))
+$width = [Math]::Max(1, [int][Math]::Round($area.width * $scale))
+$height = [Math]::Max(1, [int][Math]::Round($area.height * $scale))
+$output = New-Object System.Drawing.Bitmap $width, $height
+$graphics = [System.Drawing.Graphics]::FromImage($output)
+$graphics.InterpolationMode = [System.Drawing.Drawing2D.InterpolationMode]::HighQualityBicubic
+$graphics.DrawImage($source, 0, 0, $width, $height)
+$source.Dispose()
+
+$masked = @()
+foreach ($window in $windows) {
+  $names = ($window.app + ' ' + $window.processName).ToLower()
+  if (-not ($spec.maskApps | Where-Object { $names.Contains($_.ToLower()) })) { continue }
+  $left = [Math]::Max(0, ($window.bounds.x - $area.x) * $scale)
+  $top = [Math]::Max(0, ($window.bounds.y - $area.y) * $scale)
+  $right = [Math]::Min($width, ($window.bounds.x + $window.bounds.width - $area.x) * $scale)
+  $bottom = [Math]::Min($height, ($window.bounds.y + $window.bounds.height - $area.y) * $scale)
+  if ($right -le $left -or $bottom -le $top) { continue }
+  $target = New-Object System.Drawing.RectangleF $left, $top, ($right - $left), ($bottom - $top)
+  if ($spec.maskMode -eq 'fill') {
+    $graphics.FillRectangle([System.Drawing.Brushes]::Gray, $target)
+  } else {
+    # Pixelate: shrink the area into a few blocks, then draw them back without smoothing
+    $tinyWidth = [Math]::Max(1, [int][Math]::Round($target.Width / $spec.blockSize))
+    $tinyHeight = [Math]::Max(1, [int][Math]::Round($target.Height / $spec.blockSize))
+    $tiny = New-Object System.Drawing.Bitmap $tinyWidth, $tinyHeight
+    $tinyGraphics = [System.Drawing.Graphics]::FromImage($tiny)
+    $tinyGraphics.InterpolationMode = [System.Drawing.Drawing2D.InterpolationMode]::HighQualityBilinear
+    $tinyRect = New-Object System.Drawing.RectangleF 0, 0, $tinyWidth, $tinyHeight
+    $tinyGraphics.DrawImage($output, $tinyRect, $target, [System.Drawing.GraphicsUnit]::Pixel)
+    $tinyGraphics.Dispose()
+    $graphics.InterpolationMode = [System.Drawing.Drawing2D.InterpolationMode]::NearestNeighbor
+    $graphics.PixelOffsetMode = [System.Drawing.Drawing2D.PixelOffsetMode]::Half
+    $graphics.DrawImage($tiny, $target)
+    $graphics.InterpolationMode = [System.Drawing.Drawing2D.InterpolationMode]::HighQualityBicubic
+    $graphics.PixelOffsetMode = [System.Drawing.Drawing2D.PixelOffsetMode]::Default
+    $tiny.Dispose()
+  }
+  $masked += [pscustomobject]@{ app = $window.app; bounds = $window.bounds }
+}
+$graphics.Dispose()
+
+$stream = New-Object System.IO.MemoryStream
+if ($spec.format -eq 'jpeg') {
+  $codec = [System.Drawing.Imaging.ImageCodecInfo]::GetImageEncoders() |
+    Where-Object { $_.MimeType -eq 'image/jpeg' }
+  $quality = [System.Drawing.Imaging.Encoder]::Quality
+  $parameters = New-Object System.Drawing.Imaging.EncoderParameters 1
+  $parameters.Param[0] = New-Object System.Drawing.Imaging.EncoderParameter $quality, ([long]$spec.quality)
+  $output.Save($stream, $codec, $parameters)
+} else {
+  $output.Save($stream, [System.Drawing.Imaging.ImageFormat]::Png)
+}
+$output.Dispose()
+[pscustomobject]@{
+  width = $width; height = $height; scale = $scale; area = $area; masked = @($masked)
+  window = $(if ($own) { [pscustomobject]@{ app = $own.app; pid = $own.pid; bounds = $own.bounds } })
+  data = [Convert]::ToBase64String($stream.ToArray())
+} | ConvertTo-Json -Compress -Depth 4
+`;
+
+// ---------------------------------------------------------------------------
+// Linux
+// ---------------------------------------------------------------------------
+
+/**
+ * Run a command with optional stdin, collecting stdout as a Buffer
+ */
+function runBinary(command, args, input, timeoutMs) {
+  return new Promise((resolve, reject) => {
+    const child = spawn(command, args, { stdio: ['pipe', 'pipe', 'pipe'] });
+    const chunks = [];
+    let size = 0;
+    let stderr = '';
+    const timer = setTimeout(() => {
+      child.kill();
+      reject(new CompanionError(ERROR_CODES.TIMEOUT, `${command} timed out after ${timeoutMs}ms`));
+    }, timeoutMs);
+    child.stdout.on('data', (chunk) => {
+      size += chunk.length;
+      if (size > MAX_OUTPUT_BYTES) child.kill();
+      else chunks.push(chunk);
+    });
+    child.stderr.on('data', (chunk) => {
+      stderr += chunk;
+    });
+    child.on('error', (error) => {
+      clearTimeout(timer);
+      reject(fromSystemError(error, { command }));
+    });
+    child.on('close', (code) => {
+      clearTimeout(timer);
+      const message = `${command} exited with code ${code}`;
+      if (code === 0) resolve(Buffer.concat(chunks));
+      else reject(new CompanionError(ERROR_CODES.IO, message, { details: { stderr: stderr.trim() } }));
+    });
+    child.stdin.on('error', () => {}); // The command may exit before reading everything
+    child.stdin.end(input || undefined);
+  });
+}
+
+/**
+ * Width and height from a PNG's IHDR chunk
+ */
+function pngSize(buffer) {
+  if (buffer.length < 24 || buffer.readUInt32BE(12) !== 0x49484452) return null;
+  return { width: buffer.readUInt32BE(16), height: buffer.readUInt32BE(20) };
+}
+
+function linuxBackend() {
+  const env = process.env;
+  if (env.SWAYSOCK) return 'sway';
+  if (env.HYPRLAND_INSTANCE_SIGNATURE) return 'hyprland';
+  const wayland = env.XDG_SESSION_TYPE === 'wayland' || (!env.XDG_SESSION_TYPE && env.WAYLAND_DISPLAY);
+  return !wayland && env.DISPLAY ? 'x11' : null;
+}
+
+/**
+ * Visible windows with their owner, bounds (root / layout coordinates) and layer: windows on a
+ * higher layer may cover those on a lower one. X11 layers are the stacking order; sway and
+ * Hyprland only tell floating (1) from tiled (0) windows
+ */
+async function linuxWindows(backend, timeoutMs) {
+  const run = async (command, args) => (await execFileAsync(command, args, { timeout: timeoutMs })).stdout;
+  if (backend === 'x11') {
+    // "0x03a00007  0 4242   10 40   800 600  code.Code  host Title"
+    const output = await run('wmctrl', ['-lGpx']);
+    // "_NET_CLIENT_LIST_STACKING(WINDOW): window id # 0x1a00003, 0x3a00007", bottom to top
+    const stacking = await run('xprop', ['-root', '_NET_CLIENT_LIST_STACKING']).then(
+      (stdout) => (stdout.match(/0x[0-9a-f]+/gi) || []).map((id) => parseInt(id, 16)),
+      () => [] // Unknown order: every window is on the same layer
+    );
+    // "_NET_CURRENT_DESKTOP(CARDINAL) = 1"; without it every desktop counts as current
+    const desktop = await run('xprop', ['-root', '_NET_CURRENT_DESKTOP']).then(
+      (stdout) => (/=\s*(\d+)/.exec(stdout) || [])[1] ?? null,
+      () => null
+    );
+    const windows = output
+      .split('\n')
+      .map((line) => line.trim().split(/\s+/))
+      .filter((fields) => fields.length >= 8)
+      // Desktop -1 is a sticky window, shown on every desktop
+      .filter(([, onDesktop]) => desktop === null || onDesktop === desktop || onDesktop === '-1')
+      .map(([id, , pid, x, y, width, height, wmClass]) => ({
+        id,
+        pid: Number(pid) || null,
+        app: wmClass.split('.').pop(),
+        bundleId: wmClass,
+        bounds: { x: Number(x), y: Number(y), width: Number(width), height: Number(height) },
+        layer: Math.max(0, stacking.indexOf(parseInt(id, 16))),
+      }));
+    // Minimized (iconic) windows keep their geometry but aren't drawn
+    const hidden = await Promise.all(
+      windows.map((window) =>
+        run('xprop', ['-id', window.id, '_NET_WM_STATE']).then(
+          (stdout) => /_NET_WM_STATE_HIDDEN/.test(stdout),
+          () => false
+        )
+      )
+    );
+    return windows.filter((window, i) => !hidden[i]);
+  }
+  if (backend === 'sway') {
+    const windows = [];
+    const stack = [JSON.parse(await run('swaymsg', ['-t', 'get_tree', '-r']))];
+    while (stack.length > 0) {
+      const node = stack.pop();
+      if ((node.type === 'con' || node.type === 'floating_con') && node.pid && node.visible) {
+        const app = node.app_id || node.window_properties?.class || null;
+        const layer = node.type === 'floating_con' ? 1 : 0;
+        windows.push({ id: node.id, pid: node.pid, app, bundleId: app, bounds: node.rect, layer });
+      }
+      stack.push(...(node.nodes || []), ...(node.floating_nodes || []));
+    }
+    return windows;
+  }
+  // Clients on every workspace are listed; only those on a monitor's active workspace are visible
+  const monitors = JSON.parse(await run('hyprctl', ['monitors', '-j']));
+  const visible = new Set(
+    monitors.flatMap((monitor) => [monitor.activeWorkspace?.id, monitor.specialWorkspace?.id])
+  );
+  const clients = JSON.parse(await run('hyprctl', ['clients', '-j']));
+  return clients
+    .filter((client) => client.mapped && !client.hidden && visible.has(client.workspace?.id))
+    .map((client) => ({
+      id: client.address,
+      pid: client.pid,
+      app: client.class,
+      bundleId: client.class,
+      bounds: { x: client.at[0], y: client.at[1], width: client.size[0], height: client.size[1] },
+      layer: client.floating ? 1 : 0,
+    }));
+}
+
+/**
+ * X11 top-level windows drawn above `own` that aren't its own: override-redirect popups
+ * (menus, tooltips, notifications) are missing from _NET_CLIENT_LIST_STACKING, so the root's
+ * children are walked instead, bottom to top
+ * @returns {Promise<Array<object>>} [{ app: null, bundleId: null, bounds }]
+ */
+async function x11Overlays(own, timeoutMs) {
+  const run = async (args) => (await execFileAsync('xwininfo', args, { timeout: timeoutMs })).stdout;
+
+  // The client window wmctrl reports is reparented into a window manager frame
+  let frame = own.id;
+  for (let depth = 0; depth < 8; depth++) {
+    const parent = /Parent window id: (0x[0-9a-f]+)(.*)/i.exec(await run(['-tree', '-id', frame]));
+    if (!parent || /the root window/.test(parent[2])) break;
+    frame = parent[1];
+  }
+
+  // "     0x3a00007 "Title": ("code" "Code")  800x600+10+40  +10+40" (last pair is absolute)
+  const child = /^\s+(0x[0-9a-f]+)\b.*\s(\d+)x(\d+)\+-?\d+\+-?\d+\s+\+(-?\d+)\+(-?\d+)\s*$/i;
+  const children = (await run(['-root', '-children']))
+    .split('\n')
+    .map((line) => child.exec(line))
+    .filter(Boolean)
+    .map(([, id, width, height, x, y]) => ({
+      id,
+      bounds: { x: Number(x), y: Number(y), width: Number(width), height: Number(height) },
+    }));
+  const index = children.findIndex((child) => parseInt(child.id, 16) === parseInt(frame, 16));
+  // Frame not found: stacking unknown, so everything overlapping may be on top
+  const above = children.slice(index + 1).filter((window) => intersects(window.bounds, own.bounds));
+
+  const overlays = await Promise.all(
+    above.map(async (window) => {
+      const viewable = await run(['-stats', '-id', window.id]).then(
+        (stdout) => /Map State:\s*IsViewable/.test(stdout),
+        () => false // Destroyed since it was listed
+      );
+      if (!viewable) return null;
+      // Popups of the app itself (completion lists, its own menus) are part of its window
+      const pid = await execFileAsync('xprop', ['-id', window.id, '_NET_WM_PID'], { timeout: timeoutMs })
+        .then(({ stdout }) => Number((/=\s*(\d+)/.exec(stdout) || [])[1]) || null)
+        .catch(() => null);
+      return own.pid && pid === own.pid ? null : { app: null, bundleId: null, bounds: window.bounds };
+    })
+  );
+  return overlays.filter(Boolean);
+}
+
+function intersects(a, b) {
+  return a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height;
+}
+
+function clip(bounds, screen) {
+  const x = Math.max(screen.x, bounds.x);
+  const y = Math.max(screen.y, bounds.y);
+  const width = Math.min(screen.x + screen.width, bounds.x + bounds.width) - x;
+  const height = Math.min(screen.y + screen.height, bounds.y + bounds.height) - y;
+  return width > 0 && height > 0 ? { x, y, width, height } : null;
+}
+
+async function imageMagick(args, input, timeoutMs) {
+  try {
+    return await runBinary('magick', args, input, timeoutMs);
+  } catch (error) {
+    if (error.code !== ERROR_CODES.UNAVAILABLE) throw error;
+    return runBinary('convert', args, input, timeoutMs); // ImageMagick 6
+  }
+}
+
+/**
+ * Screenshot through the compositor tools and ImageMagick. With opts.window ({ pid } or
+ * { pattern }) only that window's area is kept, and every window that may cover it is filled
+ */
+async function captureLinux(opts) {
+  const backend = linuxBackend();
+  if (!backend) {
+    throw new CompanionError(ERROR_CODES.UNAVAILABLE, 'Screen capture needs X11, sway or Hyprland', {
+      details: { session: process.env.XDG_SESSION_TYPE || null },
+    });
+  }
+  const needWindows = opts.window || opts.target === 'cursor' || opts.maskApps.length > 0;
+  const windows = needWindows ? await linuxWindows(backend, opts.timeoutMs) : [];
+  // grim -s 1 captures in layout coordinates, so window bounds line up with pixels
+  const screenshot =
+    backend === 'x11'
+      ? await runBinary('import', ['-silent', '-window', 'root', 'png:-'], null, opts.timeoutMs)
+      : await runBinary('grim', ['-s', '1', '-t', 'png', '-'], null, opts.timeoutMs);
+  const size = pngSize(screenshot);
+  if (!size) throw new CompanionError(ERROR_CODES.IO, 'Screen grab did not return a PNG');
+
+  const screen = { x: 0, y: 0, width: size.width, height: size.height };
+  let area = screen;
+  let own = null;
+  let masks;
+  if (opts.window) {
+    const { pid, pattern } = opts.window;
+    const matches = (window) =>
+      pid ? window.pid === pid : [window.app, window.bundleId].some((name) => name && pattern.test(name));
+    own = windows.filter(matches).sort((a, b) => b.layer - a.layer)[0];
+    area = own && clip(own.bounds, screen);
+    if (!area) throw new CompanionError(ERROR_CODES.NOT_FOUND, 'The window is not on screen');
+    masks = windows
+      .filter((window) => window !== own && window.layer >= own.layer)
+      .filter((window) => intersects(window.bounds, own.bounds))
+      .map((window) => ({ window, mode: 'fill' }));
+    if (backend === 'x11') {
+      for (const window of await x11Overlays(own, opts.timeoutMs)) masks.push({ window, mode: 'fill' });
+    }
+  } else {
+    if (opts.target === 'cursor') {
+      const cursor = windows.find((window) => CURSOR_APP.test(`${window.app} ${window.bundleId}`));
+      area = cursor && clip(cursor.bounds, screen);
+      if (!area) throw new CompanionError(ERROR_CODES.NOT_FOUND, 'No Cursor window is on screen');
+    }
+    const maskApps = opts.maskApps.map((name) => name.toLowerCase());
+    masks = windows
+      .filter((window) => {
+        const names = `${window.app || ''} ${window.bundleId || ''}`.toLowerCase();
+        return maskApps.some((name) => names.includes(name));
+      })
+      .map((window) => ({ window, mode: opts.maskMode }));
+  }
+
+  const scale = Math.min(1, opts.scale, opts.maxWidth / area.width, opts.maxHeight / area.height);
+  const width = Math.max(1, Math.round(area.width * scale));
+  const height = Math.max(1, Math.round(area.height * scale));
+  // Blocks are blockSize pixels in the output, so larger before scaling down
+  const block = Math.max(1, Math.round(opts.blockSize / scale));
+
+  const args = ['png:-'];
+  const masked = [];
+  for (const { window, mode } of masks) {
+    const left = Math.max(0, window.bounds.x);
+    const top = Math.max(0, window.bounds.y);
+    const right = Math.min(size.width, window.bounds.x + window.bounds.width);
+    const bottom = Math.min(size.height, window.bounds.y + window.bounds.height);
+    if (right <= left || bottom <= top) continue;
+    if (mode === 'fill') {
+      args.push('-fill', 'gray', '-draw', `rectangle ${left},${top} ${right - 1},${bottom - 1}`);
+    } else {
+      // Pixelate: crop the window's area, shrink it to blocks, blow it back up without smoothing
+      const geometry = `${right - left}x${bottom - top}`;
+      const blocks = `${Math.ceil((right - left) / block)}x${Math.ceil((bottom - top) / block)}!`;
+      args.push('(', '+clone', '-crop', `${geometry}+${left}+${top}`, '+repage');
+      args.push('-scale', blocks, '-scale', `${geometry}!`, ')');
+      args.push('-geometry', `+${left}+${top}`, '-composite');
+    }
+    masked.push({ app: window.app, bundleId: window.bundleId, bounds: window.bounds });
+  }
+  if (area !== screen) {
+    args.push('-crop', `${area.width}x${area.height}+${area.x}+${area.y}`, '+repage');
+  }
+  if (scale < 1) args.push('-resize', `${width}x${height}!`);
+  if (opts.format !== 'png') args.push('-quality', String(opts.quality));
+  args.push(`${opts.format}:-`);
+
+  const buffer = await imageMagick(args, screenshot, opts.timeoutMs);
+  const window = own && { app: own.app, bundleId: own.bundleId, pid: own.pid, bounds: own.bounds };
+  return { buffer, width, height, scale, area, masked, window };
+}
+
+// ---------------------------------------------------------------------------
+// API
+// ---------------------------------------------------------------------------
+
+function validate(opts) {
+  if (!opts.window && !['screen', 'cursor'].includes(opts.target)) {
+    throw new CompanionError(ERROR_CODES.INVALID_INPUT, `Unknown capture target: ${opts.target}`);
+  }
+  if (!['png', 'jpeg', 'webp'].includes(opts.format)) {
+    throw new CompanionError(ERROR_CODES.INVALID_INPUT, `Unknown image format: ${opts.format}`);
+  }
+  if (opts.format === 'webp' && process.platform !== 'linux') {
+    throw new CompanionError(ERROR_CODES.UNAVAILABLE, 'WebP encoding needs ImageMagick (Linux only)');
+  }
+  if (!(opts.scale > 0 && opts.scale <= 1) || !(opts.maxWidth > 0) || !(opts.maxHeight > 0)) {
+    throw new CompanionError(ERROR_CODES.INVALID_INPUT, 'scale must be in (0, 1], max sizes > 0');
+  }
+}
+
+/**
+ * Run a bridge helper with the spec in its environment and decode its JSON answer
+ */
+async function runHelper(command, args, opts) {
+  const spec = {
+    target: opts.target,
+    cursorApp: CURSOR_APP.source,
+    window: opts.window && { pid: opts.window.pid || null, pattern: opts.window.pattern?.source || null },
+    maxWidth: opts.maxWidth,
+    maxHeight: opts.maxHeight,
+    scale: opts.scale,
+    format: opts.format,
+    quality: opts.quality,
+    maskApps: opts.maskApps,
+    maskMode: opts.maskMode,
+    blockSize: opts.blockSize,
+  };
+  let stdout;
+  try {
+    ({ stdout } = await execFileAsync(command, args, {
+      timeout: opts.timeoutMs,
+      maxBuffer: MAX_OUTPUT_BYTES,
+      windowsHide: true,
+      env: { ...process.env, [SPEC_ENV]: JSON.stringify(spec) },
+    }));
+  } catch (error) {
+    throw fromSystemError(error, { command });
+  }
+  const { error, data, ...result } = JSON.parse(stdout.trim());
+  if (error === 'permission') {
+    throw new CompanionError(ERROR_CODES.PERMISSION_DENIED, 'Screen Recording permission is not granted');
+  }
+  if (error === 'not-found') {
+    const what = opts.window ? 'The window' : 'No Cursor window';
+    throw new CompanionError(ERROR_CODES.NOT_FOUND, `${what} is not on screen`);
+  }
+  if (error) throw new CompanionError(ERROR_CODES.IO, `Screen capture failed: ${error}`);
+  return { ...result, buffer: Buffer.from(data, 'base64') };
+}
+
+async function capture(opts) {
+  validate(opts);
+  let result;
+  if (process.platform === 'darwin') {
+    result = await runHelper('osascript', ['-l', 'JavaScript', '-e', MAC_SCRIPT], opts);
+  } else if (process.platform === 'win32') {
+    result = await runHelper('powershell.exe', [...POWERSHELL_ARGS, WINDOWS_SCRIPT], opts);
+  } else if (process.platform === 'linux') {
+    result = await captureLinux(opts);
+  } else {
+    throw new CompanionError(ERROR_CODES.UNAVAILABLE, `No screen capture on ${process.platform}`);
+  }
+  return { ...result, format: opts.format, target: opts.target, platform: process.platform };
+}
+
+/**
+ * Take a screenshot
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Promise<object>} { buffer, format, width, height, scale (output pixels per screen
+ *   point), area: { x, y, width, height } captured, masked: [{ app, bundleId, bounds }], target,
+ *   platform }. Rejects with PERMISSION_DENIED (macOS Screen Recording), NOT_FOUND (target
+ *   'cursor' without a Cursor window on screen) or UNAVAILABLE (no capture method)
+ */
+async function captureScreenshot(options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options, maskApps: options.maskApps || DEFAULT_OPTIONS.maskApps };
+  return capture({ ...opts, window: null });
+}
+
+/**
+ * Capture one app's window and nothing else. macOS asks the window server for that window's
+ * own pixels and Windows has the window paint itself (PrintWindow), so whatever covers it is
+ * left out; Linux crops the screen to the window and fills every window that may cover it
+ * @param {number|string} [app] - A pid, or a bundle id / app name (WM_CLASS on Linux, process
+ *   name or description on Windows) matched whole and case-insensitively; Cursor when omitted
+ * @param {object} options - See DEFAULT_OPTIONS; target and maskApps don't apply
+ * @returns {Promise<object>} As captureScreenshot, with target 'window' and window: { app,
+ *   bundleId, pid, bounds }; masked lists the windows filled over it. Rejects with NOT_FOUND
+ *   when the app has no window on screen (minimized, another space or workspace)
+ */
+async function captureAppWindow(app, options = {}) {
+  let window;
+  if (app === undefined || app === null) {
+    window = { pattern: CURSOR_APP };
+  } else if (Number.isInteger(app) && app > 0) {
+    window = { pid: app };
+  } else if (typeof app === 'string' && app.trim()) {
+    window = { pattern: new RegExp(`^${app.trim().replace(/[.*+?^${}()|[\]\\]/g, '\\$&')}$`, 'i') };
+  } else {
+    throw new CompanionError(ERROR_CODES.INVALID_INPUT, 'Expected a pid, bundle id or app name');
+  }
+  return capture({ ...DEFAULT_OPTIONS, ...options, target: 'window', maskApps: [], window });
+}
+
+module.exports = {
+  captureScreenshot,
+  captureAppWindow,
+  DEFAULT_OPTIONS,
+};
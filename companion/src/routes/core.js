@@ -2,6 +2,9 @@
  * Core API routes - health, queue, debug
  */
 
+const { getCallMetrics } = require('../utils/call-metrics');
+const { getLogs } = require('../utils/logger');
+
 function createCoreRoutes(deps) {
   const {
     app,
@@ -14,6 +17,7 @@ function createCoreRoutes(deps) {
     queryCache,
     dataAccessControl,
     robustDataCapture,
+    captureRates,
   } = deps;
 
   // Health check
@@ -342,6 +346,36 @@ function createCoreRoutes(deps) {
       });
     }
   });
+
+  // Current adaptive snapshot/polling interval per file and capture source
+  app.get('/api/capture/rates', (req, res) => {
+    res.set('Cache-Control', 'no-cache');
+    if (!captureRates) {
+      return res.status(503).json({ success: false, error: 'Adaptive capture rates not initialized' });
+    }
+    res.json({ success: true, rates: captureRates.getEffectiveRates() });
+  });
+
+  // Per-function call counts, latencies and peak memory for instrumented modules
+  app.get('/api/diagnostic/metrics', (req, res) => {
+    res.set('Cache-Control', 'no-cache');
+    res.json({
+      success: true,
+      metrics: getCallMetrics({ module: req.query.module || null, reset: req.query.reset === 'true' }),
+    });
+  });
+
+  // Recent entries from the in-memory log buffer
+  app.get('/api/diagnostic/logs', (req, res) => {
+    res.set('Cache-Control', 'no-cache');
+    const logs = getLogs({
+      since: req.query.since !== undefined ? parseInt(req.query.since, 10) : -1,
+      level: req.query.level || null,
+      module: req.query.module || null,
+      limit: parseInt(req.query.limit, 10) || 500,
+    });
+    res.json({ success: true, logs });
+  });
 }
 
 module.exports = createCoreRoutes;
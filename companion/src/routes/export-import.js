@@ -2,6 +2,9 @@
  * Export/Import API routes
  */
 
+const { writeJsonArray } = require('../utils/chunked-result');
+const { ERROR_CODES } = require('../utils/errors');
+
 function createExportImportRoutes(deps) {
   const {
     app,
@@ -149,6 +152,12 @@ function createExportImportRoutes(deps) {
         res.write(chunk);
       };
 
+      // Arrays that can run to many MB go out a batch of items at a time
+      const writeArray = async (items) => {
+        if (Array.isArray(items)) await writeJsonArray(res, items);
+        else writeChunk(JSON.stringify(items || []));
+      };
+
       // Helper to abstract entry if needed
       const processEntry = (entry) => {
         if (abstractionLevel > 0 && abstractionEngine) {
@@ -502,27 +511,15 @@ function createExportImportRoutes(deps) {
           const workspace = req.query.workspace || req.query.workspace_path || null;
           const graph = await moduleGraphService.getModuleGraph(workspace, { forceRefresh: false });
 
-          writeChunk(
-            '      "nodes": ' +
-              JSON.stringify(graph.nodes || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
-          writeChunk(
-            '      "edges": ' +
-              JSON.stringify(graph.edges || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
-          writeChunk(
-            '      "events": ' +
-              JSON.stringify(graph.events || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
+          writeChunk('      "nodes": ');
+          await writeArray(graph.nodes);
+          writeChunk(',\n');
+          writeChunk('      "edges": ');
+          await writeArray(graph.edges);
+          writeChunk(',\n');
+          writeChunk('      "events": ');
+          await writeArray(graph.events);
+          writeChunk(',\n');
           writeChunk(
             '      "hierarchy": ' +
               JSON.stringify(graph.hierarchy || {})
@@ -559,20 +556,12 @@ function createExportImportRoutes(deps) {
           const callgraph = await functionsService.getCallGraph(workspace);
           const stats = await functionsService.getFunctionStats(workspace);
 
-          writeChunk(
-            '      "functionChanges": ' +
-              JSON.stringify(changes || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
-          writeChunk(
-            '      "functions": ' +
-              JSON.stringify(functions || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
+          writeChunk('      "functionChanges": ');
+          await writeArray(changes);
+          writeChunk(',\n');
+          writeChunk('      "functions": ');
+          await writeArray(functions);
+          writeChunk(',\n');
           writeChunk(
             '      "callgraph": ' +
               JSON.stringify(callgraph || {})
@@ -607,13 +596,9 @@ function createExportImportRoutes(deps) {
           const scripts = await editsService.getEditScripts(workspace, {});
           const operations = await editsService.getOperationTypes(workspace);
 
-          writeChunk(
-            '      "editScripts": ' +
-              JSON.stringify(scripts || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
+          writeChunk('      "editScripts": ');
+          await writeArray(scripts);
+          writeChunk(',\n');
           writeChunk(
             '      "operations": ' +
               JSON.stringify(operations || {})
@@ -674,13 +659,9 @@ function createExportImportRoutes(deps) {
           tokensService.updatePIIOptions(originalPIIOptions);
           tokensService.setFuzzSemanticExpressiveness(originalFuzzOption);
 
-          writeChunk(
-            '      "tokens": ' +
-              JSON.stringify(tokens || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
+          writeChunk('      "tokens": ');
+          await writeArray(tokens);
+          writeChunk(',\n');
           writeChunk(
             '      "stats": ' +
               JSON.stringify(stats || {})
@@ -774,41 +755,21 @@ function createExportImportRoutes(deps) {
             rawDataExport.source = 'memory';
           }
 
-          writeChunk(
-            '      "systemResources": ' +
-              JSON.stringify(rawDataExport.systemResources || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
-          writeChunk(
-            '      "gitData": ' +
-              JSON.stringify(rawDataExport.gitData || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
-          writeChunk(
-            '      "appleScript": ' +
-              JSON.stringify(rawDataExport.appleScript || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
-          writeChunk(
-            '      "cursorDatabase": ' +
-              JSON.stringify(rawDataExport.cursorDatabase || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
-          writeChunk(
-            '      "logs": ' +
-              JSON.stringify(rawDataExport.logs || [])
-                .split('\n')
-                .join('\n      ') +
-              ',\n'
-          );
+          writeChunk('      "systemResources": ');
+          await writeArray(rawDataExport.systemResources);
+          writeChunk(',\n');
+          writeChunk('      "gitData": ');
+          await writeArray(rawDataExport.gitData);
+          writeChunk(',\n');
+          writeChunk('      "appleScript": ');
+          await writeArray(rawDataExport.appleScript);
+          writeChunk(',\n');
+          writeChunk('      "cursorDatabase": ');
+          await writeArray(rawDataExport.cursorDatabase);
+          writeChunk(',\n');
+          writeChunk('      "logs": ');
+          await writeArray(rawDataExport.logs);
+          writeChunk(',\n');
           writeChunk(
             '      "source": "' + (rawDataExport.source || 'unknown') + '",\n'
           );
@@ -868,7 +829,9 @@ function createExportImportRoutes(deps) {
     } catch (error) {
       console.error('Error in streaming export:', error);
       // Try to close JSON properly on error
-      if (!res.headersSent) {
+      if (res.destroyed || error.code === ERROR_CODES.CANCELLED) {
+        res.destroy(); // The client went away
+      } else if (!res.headersSent) {
         res.status(500).json({ success: false, error: error.message });
       } else {
         res.write(`\n  "error": "${error.message.replace(/"/g, '\\"')}"\n}`);
@@ -5,6 +5,7 @@
 const path = require('path');
 const HuggingFaceExporter = require('../services/huggingface/exporter.js');
 const HuggingFaceUploader = require('../services/huggingface/uploader.js');
+const { sendError } = require('../utils/errors');
 
 function createHuggingFaceRoutes(deps) {
   const { app, persistentDB } = deps;
@@ -19,10 +20,19 @@ function createHuggingFaceRoutes(deps) {
       };
       const outputDir = path.join(__dirname, '../../data', `export-${Date.now()}`);
       const exporter = new HuggingFaceExporter(persistentDB, options);
-      const result = await exporter.exportToHuggingFaceFormat(outputDir);
-      res.json(result);
+      const operation = exporter.startExport(outputDir);
+
+      // background=true returns at once; poll or cancel via /api/operations/:id
+      if (req.query.background === 'true') {
+        return res.status(202).json({ success: true, operation: operation.toJSON() });
+      }
+      res.on('close', () => {
+        if (!res.writableEnded) operation.cancel('Client disconnected');
+      });
+      const result = await operation;
+      res.json({ ...result, operationId: operation.id });
     } catch (error) {
-      res.status(500).json({ success: false, error: error.message });
+      if (!res.headersSent) sendError(res, error);
     }
   });
 
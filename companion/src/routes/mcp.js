@@ -7,6 +7,7 @@
 const crypto = require('crypto');
 const path = require('path');
 const MCPSearchService = require('../services/mcp-search-service');
+const { pipeResultToResponse } = require('../utils/chunked-result');
 
 function createMCPRoutes(deps) {
   const {
@@ -534,12 +535,16 @@ function createMCPRoutes(deps) {
 
       console.log(`[MCP Search] Workflow search: "${query}" - ${results.matches.length} matches (${results.rung})`);
 
-      res.json({
-        success: true,
-        ...results
-      });
+      // A large limit can return many matches: stream them instead of one JSON.stringify
+      const { matches, ...rest } = results;
+      await pipeResultToResponse(res, matches, { key: 'matches', fields: { success: true, ...rest } });
     } catch (error) {
       console.error('[MCP Search] Error:', error);
+      // Part of the body is already out: a 500 can't be sent, so cut the response short
+      if (res.headersSent) {
+        res.destroy();
+        return;
+      }
       res.status(500).json({
         success: false,
         error: error.message
@@ -0,0 +1,36 @@
+/**
+ * Long-running operation API routes
+ * List, inspect and cancel work started through utils/operation-handle
+ */
+
+const { listOperations, getOperation, cancelOperation } = require('../utils/operation-handle');
+const { CompanionError, ERROR_CODES, sendError } = require('../utils/errors');
+
+function notFound(res, id) {
+  sendError(res, new CompanionError(ERROR_CODES.NOT_FOUND, `Operation not found: ${id}`));
+}
+
+function createOperationRoutes(deps) {
+  const { app } = deps;
+
+  app.get('/api/operations', (req, res) => {
+    const status = req.query.status || null;
+    const operations = listOperations().filter((op) => !status || op.status === status);
+    res.json({ success: true, operations });
+  });
+
+  app.get('/api/operations/:id', (req, res) => {
+    const operation = getOperation(req.params.id);
+    if (!operation) return notFound(res, req.params.id);
+    res.json({ success: true, operation: operation.toJSON() });
+  });
+
+  app.post('/api/operations/:id/cancel', (req, res) => {
+    const operation = getOperation(req.params.id);
+    if (!operation) return notFound(res, req.params.id);
+    const cancelled = cancelOperation(req.params.id, req.body?.reason || 'Cancelled via API');
+    res.json({ success: true, cancelled, operation: operation.toJSON() });
+  });
+}
+
+module.exports = createOperationRoutes;
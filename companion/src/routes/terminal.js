@@ -2,6 +2,20 @@
  * Terminal monitoring API routes
  */
 
+const { EventColumns } = require('../services/analytics/event-columns');
+
+// Terminal commands as columns: source as the type, the program run, and whether it failed
+const COMMAND_COLUMNS = {
+  numericColumns: { failed: Uint8Array },
+  dictionaryColumns: ['command'],
+  rowMapper: (cmd) => ({
+    timestamp: cmd.timestamp,
+    type: cmd.source,
+    command: String(cmd.command || '').split(' ')[0],
+    failed: cmd.exit_code && cmd.exit_code !== 0 ? 1 : 0,
+  }),
+};
+
 function createTerminalRoutes(deps) {
   const { app, persistentDB, terminalMonitor } = deps;
 
@@ -35,38 +49,29 @@ function createTerminalRoutes(deps) {
   app.get('/api/terminal/stats', async (req, res) => {
     try {
       const memoryStats = terminalMonitor.getStats();
-      const allCommands = await persistentDB.getAllTerminalCommands();
-
-      const now = Date.now();
-      const last24h = now - 24 * 60 * 60 * 1000;
-      const recentCommands = allCommands.filter((cmd) => cmd.timestamp > last24h);
-      const errorCommands = allCommands.filter((cmd) => cmd.exit_code && cmd.exit_code !== 0);
+      const commands = EventColumns.fromEvents(
+        await persistentDB.getAllTerminalCommands(),
+        COMMAND_COLUMNS
+      );
+      const total = commands.count();
+      const errorCount = commands.sum('failed');
 
       const bySource = {};
-      allCommands.forEach((cmd) => {
-        bySource[cmd.source] = (bySource[cmd.source] || 0) + 1;
-      });
-
-      const commandCounts = {};
-      allCommands.forEach((cmd) => {
-        const shortCmd = cmd.command.split(' ')[0];
-        commandCounts[shortCmd] = (commandCounts[shortCmd] || 0) + 1;
-      });
-      const topCommands = Object.entries(commandCounts)
-        .sort((a, b) => b[1] - a[1])
+      for (const [source, group] of Object.entries(commands.groupBy('type', null, []))) {
+        bySource[source] = group.count;
+      }
+      const topCommands = Object.entries(commands.groupBy('command', null, []))
+        .sort((a, b) => b[1].count - a[1].count)
         .slice(0, 10)
-        .map(([cmd, count]) => ({ command: cmd, count }));
+        .map(([command, group]) => ({ command, count: group.count }));
 
       res.json({
         success: true,
         data: {
-          total: allCommands.length,
-          last24h: recentCommands.length,
-          errorCount: errorCommands.length,
-          errorRate:
-            allCommands.length > 0
-              ? ((errorCommands.length / allCommands.length) * 100).toFixed(2)
-              : 0,
+          total,
+          last24h: commands.count(commands.filter({ since: Date.now() - 24 * 60 * 60 * 1000 })),
+          errorCount,
+          errorRate: total > 0 ? ((errorCount / total) * 100).toFixed(2) : 0,
           bySource: bySource,
           topCommands: topCommands,
           memory: memoryStats,
@@ -2,6 +2,8 @@
  * Workspace API routes
  */
 
+const { EventColumns } = require('../services/analytics/event-columns');
+
 function createWorkspaceRoutes(deps) {
   const {
     app,
@@ -142,9 +144,9 @@ function createWorkspaceRoutes(deps) {
 
   app.get('/api/workspace/:workspacePath/sessions', (req, res) => {
     const { workspacePath } = req.params;
-    const workspaceEntries = entries.filter((entry) => entry.workspace_path === workspacePath);
-    const sessionIds = [...new Set(workspaceEntries.map((entry) => entry.session_id))];
-    res.json(sessionIds);
+    const store = EventColumns.fromEvents(entries);
+    const sessions = store.groupBy('session', store.filter({ workspaces: [workspacePath] }), []);
+    res.json(Object.keys(sessions).filter(Boolean));
   });
 
   // === Workspace Discovery & Registration API ===
@@ -0,0 +1,257 @@
+/**
+ * Edit Attribution
+ * Classifies each hunk of an edit as an AI completion, a paste, or manual
+ * typing from cheap signals available for every captured edit:
+ *  - paste: the inserted text matches a clipboard paste near the edit time, or
+ *    hashes the same as a recent clipboard copy (see clipboard-watcher), which
+ *    also says whether it was copied from an AI chat
+ *  - typing: how many keystrokes landed in the file just before the edit,
+ *    relative to the number of inserted characters
+ *  - insertion shape: large multi-line insertions that arrive in a single
+ *    burst are rarely typed
+ *  - completion similarity: overlap with recently shown AI completions
+ *
+ * Each signal feeds a score per class; the best score wins and the margin
+ * over the runner-up becomes the confidence.
+ */
+
+const crypto = require('crypto');
+const diff = require('diff');
+const { toMillis } = require('../../utils/timestamps');
+
+const LABELS = {
+  AI: 'ai',
+  PASTE: 'paste',
+  MANUAL: 'manual',
+};
+
+const DEFAULT_OPTIONS = {
+  windowMs: 10000, // Typing/paste events within 10s before the edit are considered
+  burstCharsThreshold: 40, // Inserting this many characters at once is not typing
+  pasteSimilarity: 0.8,
+  completionSimilarity: 0.6,
+  typingCoverage: 0.6, // Typed characters / inserted characters to call a hunk manual
+  completions: [], // Recently shown AI completions: [{ text, timestamp, filePath }]
+  clipboard: [], // Recent clipboard copies: [{ hash, timestamp, fromChat }]
+  clipboardMaxAgeMs: 30 * 60 * 1000, // Copies older than this before the edit are ignored
+  timestamp: null, // Edit time when the diff/hunks carry none
+};
+
+function normalize(text) {
+  return String(text || '')
+    .replace(/\r\n?/g, '\n')
+    .replace(/\s+/g, ' ')
+    .trim();
+}
+
+/**
+ * Hash of text as the clipboard watcher records it: SHA-256 (hex) of the text with whitespace
+ * collapsed, so reindenting a paste doesn't change it
+ */
+function pasteHash(text) {
+  const normalized = normalize(text);
+  return normalized ? crypto.createHash('sha256').update(normalized, 'utf8').digest('hex') : null;
+}
+
+function tokenize(text) {
+  return normalize(text).split(/[^A-Za-z0-9_$]+/).filter(Boolean);
+}
+
+/**
+ * Share of the inserted text's tokens found in the candidate (containment, not Jaccard,
+ * so a hunk that applied part of a larger completion still scores high)
+ */
+function containment(inserted, candidate) {
+  const a = normalize(inserted);
+  const b = normalize(candidate);
+  if (!a || !b) return 0;
+  if (b.includes(a)) return 1;
+  if (a.includes(b)) return b.length / a.length;
+
+  const tokens = tokenize(a);
+  if (tokens.length === 0) return 0;
+  const pool = new Map();
+  for (const t of tokenize(b)) pool.set(t, (pool.get(t) || 0) + 1);
+  let shared = 0;
+  for (const t of tokens) {
+    const n = pool.get(t);
+    if (n) {
+      shared++;
+      pool.set(t, n - 1);
+    }
+  }
+  return shared / tokens.length;
+}
+
+function sameFile(event, filePath) {
+  const eventPath = event.filePath || event.file_path || event.file;
+  return !filePath || !eventPath || eventPath === filePath;
+}
+
+/**
+ * Normalize the diff argument into hunks: { added, removed, startLine, timestamp, filePath }
+ */
+function toHunks(input, options) {
+  if (Array.isArray(input)) {
+    return input.map((hunk) => ({ ...hunk, added: hunk.added || '', removed: hunk.removed || '' }));
+  }
+
+  const edit = input || {};
+  const before = edit.before ?? edit.before_code ?? edit.before_content ?? '';
+  const after = edit.after ?? edit.after_code ?? edit.after_content ?? '';
+  const timestamp = edit.timestamp ?? options.timestamp;
+  const filePath = edit.filePath || edit.file_path;
+
+  const hunks = [];
+  let line = 1;
+  let open = null;
+  for (const part of diff.diffLines(before, after)) {
+    const lines = part.count ?? part.value.split('\n').length - (part.value.endsWith('\n') ? 1 : 0);
+    if (!part.added && !part.removed) {
+      if (open) hunks.push(open);
+      open = null;
+      line += lines;
+      continue;
+    }
+    if (!open) open = { added: '', removed: '', startLine: line, timestamp, filePath };
+    if (part.added) {
+      open.added += part.value;
+      line += lines;
+    } else {
+      open.removed += part.value;
+    }
+  }
+  if (open) hunks.push(open);
+  return hunks;
+}
+
+function scoreHunk(hunk, typingEvents, pasteEvents, opts) {
+  const t = toMillis(hunk.timestamp ?? opts.timestamp);
+  const inserted = hunk.added;
+  const insertedChars = inserted.replace(/\s+/g, '').length;
+  const insertedLines = inserted ? inserted.replace(/\n$/, '').split('\n').length : 0;
+  const inWindow = (event) => {
+    const et = toMillis(event.timestamp);
+    return t === null || et === null || (et <= t + 1000 && et >= t - opts.windowMs);
+  };
+
+  // Typed characters near the edit
+  let typedChars = 0;
+  let lastTyping = null;
+  for (const event of typingEvents) {
+    if (!sameFile(event, hunk.filePath) || !inWindow(event)) continue;
+    typedChars += event.chars ?? event.length ?? (event.text ? event.text.length : 1);
+    const et = toMillis(event.timestamp);
+    if (et !== null && (lastTyping === null || et > lastTyping)) lastTyping = et;
+  }
+  const typingCoverage = insertedChars > 0 ? Math.min(1, typedChars / insertedChars) : typedChars > 0 ? 1 : 0;
+
+  // Paste events and copies may carry only a hash; those match the whole insertion or nothing
+  const insertedHash = pasteHash(inserted);
+  let pasteMatch = 0;
+  let pasteFromChat = false;
+  for (const event of pasteEvents) {
+    if (!sameFile(event, hunk.filePath) || !inWindow(event)) continue;
+    const match =
+      event.text || event.content
+        ? containment(inserted, event.text || event.content)
+        : Number(Boolean(event.hash) && event.hash === insertedHash);
+    pasteMatch = Math.max(pasteMatch, match);
+    if (match >= opts.pasteSimilarity && event.fromChat) pasteFromChat = true;
+  }
+  for (const copy of opts.clipboard || []) {
+    const ct = toMillis(copy.timestamp);
+    if (t !== null && ct !== null && (ct > t + 1000 || ct < t - opts.clipboardMaxAgeMs)) continue;
+    if (!insertedHash || copy.hash !== insertedHash) continue;
+    pasteMatch = 1;
+    if (copy.fromChat) pasteFromChat = true;
+  }
+
+  let completionMatch = 0;
+  for (const completion of opts.completions || []) {
+    if (!sameFile(completion, hunk.filePath)) continue;
+    const ct = toMillis(completion.timestamp);
+    if (t !== null && ct !== null && (ct > t + 1000 || ct < t - opts.windowMs * 6)) continue;
+    completionMatch = Math.max(completionMatch, containment(inserted, completion.text || completion.code));
+  }
+
+  const burst = insertedChars >= opts.burstCharsThreshold;
+  const multiLine = insertedLines > 1;
+
+  const scores = {
+    [LABELS.PASTE]: pasteMatch >= opts.pasteSimilarity ? pasteMatch : pasteMatch * 0.5,
+    [LABELS.AI]:
+      (completionMatch >= opts.completionSimilarity ? completionMatch : completionMatch * 0.5) +
+      (burst ? 0.3 : 0) +
+      (burst && multiLine ? 0.15 : 0) -
+      typingCoverage * 0.4,
+    [LABELS.MANUAL]:
+      (typingCoverage >= opts.typingCoverage ? 0.6 + typingCoverage * 0.3 : typingCoverage * 0.6) +
+      (!burst ? 0.3 : 0) +
+      (insertedChars === 0 ? 0.2 : 0),
+  };
+
+  // A paste match explains the burst, so it should not also count as AI
+  if (scores[LABELS.PASTE] >= opts.pasteSimilarity) scores[LABELS.AI] -= 0.3;
+
+  const ranked = Object.entries(scores).sort((a, b) => b[1] - a[1]);
+  const [label, best] = ranked[0];
+  const runnerUp = ranked[1][1];
+  const confidence = Math.max(0, Math.min(1, 0.5 + (best - runnerUp) / 2));
+
+  return {
+    label,
+    confidence: Math.round(confidence * 1000) / 1000,
+    scores,
+    features: {
+      insertedChars,
+      insertedLines,
+      removedLines: hunk.removed ? hunk.removed.replace(/\n$/, '').split('\n').length : 0,
+      typedChars,
+      typingCoverage,
+      pasteMatch,
+      pasteFromChat,
+      completionMatch,
+      burst,
+      msSinceTyping: t !== null && lastTyping !== null ? t - lastTyping : null,
+    },
+  };
+}
+
+/**
+ * Attribute each hunk of an edit to AI, paste or manual typing
+ * @param {object|Array} edit - { before, after, timestamp, filePath } (or entry row), or hunks
+ *   [{ added, removed, startLine, timestamp, filePath }]
+ * @param {Array} typingEvents - [{ timestamp, filePath, chars | text }]
+ * @param {Array} pasteEvents - [{ timestamp, filePath, text | hash, fromChat }]
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {object} { hunks, summary: { ai, paste, manual, label } }
+ */
+function attributeEdit(edit, typingEvents = [], pasteEvents = [], options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const hunks = toHunks(edit, opts).map((hunk, index) => ({
+    index,
+    startLine: hunk.startLine ?? null,
+    ...scoreHunk(hunk, typingEvents || [], pasteEvents || [], opts),
+  }));
+
+  // Edit-level label: the class that contributed the most inserted characters
+  const summary = { [LABELS.AI]: 0, [LABELS.PASTE]: 0, [LABELS.MANUAL]: 0, label: null };
+  const chars = { [LABELS.AI]: 0, [LABELS.PASTE]: 0, [LABELS.MANUAL]: 0 };
+  for (const hunk of hunks) {
+    summary[hunk.label]++;
+    chars[hunk.label] += hunk.features.insertedChars;
+  }
+  if (hunks.length > 0) {
+    summary.label = Object.entries(chars).sort((a, b) => b[1] - a[1] || summary[b[0]] - summary[a[0]])[0][0];
+  }
+
+  return { hunks, summary };
+}
+
+module.exports = {
+  attributeEdit,
+  pasteHash,
+  LABELS,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,219 @@
+/**
+ * Line Provenance
+ * An incremental blame over a file's captured history: every line of the
+ * current content carries the label (ai / paste / manual, or any label the
+ * history uses) and the step of the change that last wrote it, so questions
+ * like "what fraction of the final file was AI-written" are a count.
+ *
+ * Steps are applied in the order given, as a line diff against the tracked
+ * content. A line that is rewritten belongs to whoever rewrote it; lines
+ * that only move with surrounding changes keep their provenance. Labels come
+ * per hunk when a step carries an attributeEdit result (see edit-attribution)
+ * and per step otherwise. When a step's before content doesn't match the
+ * tracked content, the difference happened while nobody was watching and
+ * is applied first with unknownLabel. A history that opens with a bare
+ * snapshot starts from it as baseline; pass initialContent: '' to have the
+ * first snapshot's lines attributed to it instead.
+ */
+
+const diff = require('diff');
+const { LABELS } = require('./edit-attribution');
+const { toMillis } = require('../../utils/timestamps');
+
+const DEFAULT_OPTIONS = {
+  ignoreBlankLines: true, // Blank lines don't count towards fractions
+  baselineLabel: 'baseline', // Lines that predate the first step
+  unknownLabel: 'unknown', // Lines from changes between steps that weren't captured
+};
+
+const AI_SOURCES = new Set(['ai', 'composer', 'agent', 'ai-generated', 'copilot', 'tab']);
+
+function splitLines(text) {
+  if (!text) return [];
+  const lines = String(text).replace(/\r\n?/g, '\n').split('\n');
+  if (lines[lines.length - 1] === '') lines.pop();
+  return lines;
+}
+
+function normalize(text) {
+  return String(text ?? '').replace(/\r\n?/g, '\n');
+}
+
+function contentBefore(step) {
+  return step.before ?? step.before_code ?? step.beforeContent ?? null;
+}
+
+function contentAfter(step, current) {
+  const after = step.content ?? step.after ?? step.after_code ?? step.afterContent;
+  if (after !== undefined && after !== null) return after;
+  if (step.diff !== undefined) {
+    const patched = diff.applyPatch(current, step.diff);
+    if (patched === false) throw new Error('Patch does not apply to the tracked content');
+    return patched;
+  }
+  return null;
+}
+
+/**
+ * The step's own label, used for hunks without one
+ */
+function stepLabel(step) {
+  const attribution = step.attribution;
+  if (typeof step.label === 'string') return step.label;
+  if (typeof attribution === 'string') return attribution;
+  if (attribution?.summary?.label) return attribution.summary.label;
+  if (step.aiGenerated !== undefined || step.ai_generated !== undefined) {
+    return (step.aiGenerated ?? step.ai_generated) ? LABELS.AI : LABELS.MANUAL;
+  }
+  if (step.prompt_id || step.promptId) return LABELS.AI;
+  return AI_SOURCES.has(String(step.source || '').toLowerCase()) ? LABELS.AI : LABELS.MANUAL;
+}
+
+/**
+ * Per-line provenance of one file, updated step by step
+ */
+class LineProvenance {
+  /**
+   * @param {string} initialContent - Content before the first step (its lines get baselineLabel)
+   * @param {object} options - See DEFAULT_OPTIONS
+   */
+  constructor(initialContent = null, options = {}) {
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.content = null;
+    this.records = [];
+    this.steps = 0;
+    this.skipped = []; // [{ index, reason }]
+    if (initialContent !== null && initialContent !== undefined) this.reset(initialContent);
+  }
+
+  reset(content) {
+    this.content = normalize(content);
+    this.records = splitLines(this.content).map((text) => ({
+      text,
+      label: this.options.baselineLabel,
+      step: null,
+      timestamp: null,
+    }));
+  }
+
+  /**
+   * Replace the tracked content with `next`; hunk i of the line diff gets labels[i] ?? label
+   */
+  transition(next, origin, labels = []) {
+    const updated = [];
+    let cursor = 0;
+    let hunk = -1;
+    let inHunk = false;
+    for (const part of diff.diffLines(this.content, next)) {
+      const count = splitLines(part.value).length;
+      if (!part.added && !part.removed) {
+        inHunk = false;
+        for (let k = 0; k < count; k++) updated.push(this.records[cursor++]);
+        continue;
+      }
+      if (!inHunk) hunk++;
+      inHunk = true;
+      if (part.removed) {
+        cursor += count;
+        continue;
+      }
+      const label = labels[hunk] ?? origin.label;
+      for (const text of splitLines(part.value)) {
+        updated.push({ text, label, step: origin.step, timestamp: origin.timestamp });
+      }
+    }
+    this.records = updated;
+    this.content = next;
+  }
+
+  /**
+   * Apply one history step
+   * @param {object} step - { content } | { before, after } | { diff } (before_code / after_code also
+   *   accepted), plus a label: label, attribution (a label or an attributeEdit result with per-hunk
+   *   labels), or aiGenerated / source / prompt_id; optional timestamp and id
+   * @param {number} index - Position in the history, recorded on the lines it writes
+   * @returns {boolean} false when the step was skipped (no content, or a patch that doesn't apply)
+   */
+  apply(step, index = this.steps) {
+    const before = contentBefore(step);
+    if (this.content === null) {
+      // History that starts with a full snapshot: its lines predate the history
+      if (before === null && step.content !== undefined) {
+        this.reset(step.content);
+        this.steps++;
+        return true;
+      }
+      this.reset(before ?? '');
+    }
+
+    let next;
+    try {
+      next = contentAfter(step, this.content);
+    } catch (error) {
+      this.skipped.push({ index, reason: error.message });
+      return false;
+    }
+    if (next === null) {
+      this.skipped.push({ index, reason: 'No content, after or diff' });
+      return false;
+    }
+
+    const stepRef = step.id ?? index;
+    const timestamp = toMillis(step.timestamp);
+    if (before !== null && normalize(before) !== this.content) {
+      this.transition(normalize(before), { label: this.options.unknownLabel, step: null, timestamp: null });
+    }
+    const hunkLabels = (step.attribution?.hunks || []).map((hunk) => hunk.label);
+    this.transition(normalize(next), { label: stepLabel(step), step: stepRef, timestamp }, hunkLabels);
+    this.steps++;
+    return true;
+  }
+
+  /**
+   * Current lines with their provenance: [{ line (1-based), text, label, step, timestamp }]
+   */
+  lines() {
+    return this.records.map((record, index) => ({ line: index + 1, ...record }));
+  }
+
+  /**
+   * Line counts and fractions per label over the current content
+   * @returns {object} { lines, byLabel: { label: count }, fractions: { label: share } }
+   */
+  summary() {
+    const counted = this.options.ignoreBlankLines
+      ? this.records.filter((record) => record.text.trim() !== '')
+      : this.records;
+    const byLabel = {};
+    for (const record of counted) byLabel[record.label] = (byLabel[record.label] || 0) + 1;
+    const fractions = {};
+    for (const [label, count] of Object.entries(byLabel)) fractions[label] = count / counted.length;
+    return { lines: counted.length, byLabel, fractions };
+  }
+
+  /**
+   * Share of the current (non-blank) lines with this label
+   */
+  fraction(label) {
+    return this.summary().fractions[label] ?? 0;
+  }
+}
+
+/**
+ * Replay a file's history into per-line provenance
+ * @param {Array<object>} history - Ordered steps (see LineProvenance.apply)
+ * @param {object} options - See DEFAULT_OPTIONS, plus initialContent
+ * @returns {LineProvenance} Tracker at the end of the history; keep applying steps to it as they arrive
+ */
+function trackLineProvenance(history, options = {}) {
+  const { initialContent = null, ...rest } = options;
+  const tracker = new LineProvenance(initialContent, rest);
+  (history || []).forEach((step, index) => tracker.apply(step, index));
+  return tracker;
+}
+
+module.exports = {
+  trackLineProvenance,
+  LineProvenance,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,336 @@
+/**
+ * Prompt Reference Resolution
+ * Finds the file paths, symbol names and selection/line references a prompt
+ * mentions ("fix the bug in parseConfig", "@src/config.ts", "lines 10-20 of
+ * server.js") and resolves them to workspace entities with confidence
+ * scores, so prompts can be linked to the code they talk about.
+ */
+
+const path = require('path');
+const FunctionExtractor = require('../functions/function-extractor');
+
+const DEFAULT_OPTIONS = {
+  minConfidence: 0.3,
+  maxCandidates: 5,
+};
+
+// Common English words that also happen to be valid identifiers
+const STOP_WORDS = new Set(
+  (
+    'a an and are as at be but by can could do does fix for from get has have how i if in into is it its ' +
+    'make me my new not of on or our please set should so that the then this to up use we what when where ' +
+    'which why will with would you your function method class file code bug error test tests line lines'
+  ).split(' ')
+);
+
+const FILE_PATTERN =
+  /(?:^|[\s`'"(\[<@])((?:\.{0,2}[\\/])?(?:[\w.@-]+[\\/])*[\w@-][\w.@-]*\.[A-Za-z][\w]{0,9})(?::(\d+)(?:[-:](\d+))?)?(?=$|[\s`'")\]>,;:!?]|\.(?:\s|$))/g;
+const AT_MENTION = /(?:^|\s)@([\w./\\-]+)/g;
+const BACKTICK = /`([^`\n]{1,200})`/g;
+const CALL_PATTERN = /\b([A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)*)\s*\(\s*\)?/g;
+const IDENTIFIER = /\b([A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)*)\b/g;
+const LINE_RANGE =
+  /\b(?:lines?|L)\s*(\d+)(?:\s*(?:-|–|to|through|and)\s*L?(\d+))?(?:\s+(?:of|in)\s+`?([\w./\\-]+\.\w+)`?)?/gi;
+const SELECTION_PHRASE =
+  /\b(?:(?:the|this|my|current)\s+(?:selected|highlighted)\s+(?:code|text|lines?|block|function)|(?:the|this|my|current)\s+selection|selected\s+(?:code|text|lines?))\b/gi;
+
+function looksLikeSymbol(name) {
+  if (STOP_WORDS.has(name.toLowerCase())) return false;
+  // camelCase, PascalCase, snake_case, SCREAMING_CASE or dotted member access
+  return /[a-z][A-Z]|^[A-Z][a-z]+[A-Z]|_|\.|^[A-Z]{2,}$/.test(name);
+}
+
+function normalizePath(filePath, root) {
+  let normalized = String(filePath).replace(/\\/g, '/');
+  if (root) {
+    const rootNormalized = String(root).replace(/\\/g, '/').replace(/\/$/, '');
+    if (normalized.startsWith(`${rootNormalized}/`)) normalized = normalized.slice(rootNormalized.length + 1);
+  }
+  return normalized.replace(/^\.\//, '');
+}
+
+/**
+ * Build a workspace index from file paths (and optional contents for symbols)
+ * @param {Array<string|object>} files - Paths, or { path, content, language }
+ * @param {object} options - { root, symbols } extra symbols as { name, kind, file, line }
+ */
+function buildWorkspaceIndex(files = [], options = {}) {
+  const extractor = new FunctionExtractor();
+  const index = { root: options.root || null, files: [], symbols: [...(options.symbols || [])] };
+
+  for (const file of files) {
+    const filePath = normalizePath(typeof file === 'string' ? file : file.path, index.root);
+    index.files.push(filePath);
+
+    if (typeof file === 'object' && typeof file.content === 'string') {
+      const language = file.language || extractor.detectLanguage(filePath);
+      for (const fn of extractor.extractFunctions(file.content, language)) {
+        index.symbols.push({ name: fn.name, kind: 'function', file: filePath, line: fn.line });
+      }
+    }
+  }
+
+  return prepareIndex(index);
+}
+
+function prepareIndex(workspaceIndex) {
+  if (workspaceIndex && workspaceIndex.__prepared) return workspaceIndex;
+  const source = Array.isArray(workspaceIndex) ? { files: workspaceIndex } : workspaceIndex || {};
+  const root = source.root || null;
+  const files = (source.files || []).map((f) => normalizePath(typeof f === 'string' ? f : f.path, root));
+
+  const byBasename = new Map();
+  const byStem = new Map();
+  for (const file of files) {
+    const base = path.posix.basename(file).toLowerCase();
+    const stem = base.replace(/\.[^.]+$/, '');
+    if (!byBasename.has(base)) byBasename.set(base, []);
+    byBasename.get(base).push(file);
+    if (!byStem.has(stem)) byStem.set(stem, []);
+    byStem.get(stem).push(file);
+  }
+
+  const symbolsByName = new Map();
+  const symbolsByLower = new Map();
+  for (const symbol of source.symbols || []) {
+    const entry = { ...symbol, file: symbol.file ? normalizePath(symbol.file, root) : null };
+    if (!symbolsByName.has(entry.name)) symbolsByName.set(entry.name, []);
+    symbolsByName.get(entry.name).push(entry);
+    const lower = entry.name.toLowerCase();
+    if (!symbolsByLower.has(lower)) symbolsByLower.set(lower, []);
+    symbolsByLower.get(lower).push(entry);
+  }
+
+  return {
+    __prepared: true,
+    root,
+    files,
+    fileSet: new Set(files),
+    byBasename,
+    byStem,
+    symbolsByName,
+    symbolsByLower,
+  };
+}
+
+function resolveFile(mention, index) {
+  const normalized = normalizePath(mention, index.root).replace(/^(\.\.\/)+/, '');
+  if (index.fileSet.has(normalized)) return [{ entity: { type: 'file', path: normalized }, confidence: 1 }];
+
+  const candidates = [];
+  if (normalized.includes('/')) {
+    const suffixMatches = index.files.filter((f) => f.endsWith(`/${normalized}`));
+    for (const file of suffixMatches) {
+      candidates.push({ entity: { type: 'file', path: file }, confidence: 0.95 / suffixMatches.length });
+    }
+  }
+
+  const base = path.posix.basename(normalized).toLowerCase();
+  const baseMatches = (index.byBasename.get(base) || []).filter(
+    (f) => !candidates.some((c) => c.entity.path === f)
+  );
+  for (const file of baseMatches) {
+    candidates.push({ entity: { type: 'file', path: file }, confidence: 0.8 / baseMatches.length });
+  }
+
+  if (candidates.length === 0) {
+    const stem = base.replace(/\.[^.]+$/, '');
+    const stemMatches = index.byStem.get(stem) || [];
+    for (const file of stemMatches) {
+      candidates.push({ entity: { type: 'file', path: file }, confidence: 0.5 / stemMatches.length });
+    }
+  }
+
+  return candidates;
+}
+
+function resolveSymbol(name, index, referencedFiles, explicit) {
+  const member = name.includes('.') ? name.split('.').pop() : name;
+  const exact = index.symbolsByName.get(name) || index.symbolsByName.get(member) || [];
+  const loose = exact.length > 0 ? [] : index.symbolsByLower.get(member.toLowerCase()) || [];
+  const matches = exact.length > 0 ? exact : loose;
+  // Backticked or called names are deliberate references; bare words less so
+  const base = (exact.length > 0 ? 0.9 : 0.6) * (explicit ? 1 : 0.85);
+
+  return matches.map((symbol) => {
+    let confidence = base / matches.length;
+    if (symbol.file && referencedFiles.has(symbol.file)) confidence = Math.min(1, confidence + 0.3);
+    return {
+      entity: {
+        type: 'symbol',
+        name: symbol.name,
+        kind: symbol.kind || null,
+        file: symbol.file,
+        line: symbol.line ?? null,
+      },
+      confidence,
+    };
+  });
+}
+
+function finalize(reference, options) {
+  const candidates = reference.candidates
+    .filter((c) => c.confidence >= options.minConfidence)
+    .sort((a, b) => b.confidence - a.confidence)
+    .slice(0, options.maxCandidates)
+    .map((c) => ({ ...c, confidence: Math.round(c.confidence * 1000) / 1000 }));
+  return {
+    ...reference,
+    candidates,
+    resolved: candidates.length > 0 ? candidates[0].entity : null,
+    confidence: candidates.length > 0 ? candidates[0].confidence : 0,
+  };
+}
+
+/**
+ * Detect and resolve references in a prompt
+ * @param {string|object} prompt - Prompt text, or { text, activeFile, selection }
+ * @param {object|Array} workspaceIndex - From buildWorkspaceIndex(), or { root, files, symbols }, or a path list
+ * @param {object} options - { minConfidence, maxCandidates }
+ * @returns {object} { references, resolved, unresolved }
+ */
+function resolvePromptReferences(prompt, workspaceIndex, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const index = prepareIndex(workspaceIndex);
+  const text = typeof prompt === 'string' ? prompt : prompt.text || prompt.prompt || '';
+  const activeFile =
+    typeof prompt === 'object' && prompt.activeFile ? normalizePath(prompt.activeFile, index.root) : null;
+
+  const references = [];
+  const claimed = [];
+  const overlaps = (start, end) => claimed.some(([s, e]) => start < e && end > s);
+  const claim = (start, end) => claimed.push([start, end]);
+
+  // File paths, including @-mentions and path:line forms
+  const fileMentions = [];
+  for (const pattern of [AT_MENTION, FILE_PATTERN]) {
+    pattern.lastIndex = 0;
+    let match;
+    while ((match = pattern.exec(text)) !== null) {
+      const mention = match[1];
+      const start = match.index + match[0].indexOf(mention);
+      const end = start + mention.length;
+      const fullEnd = match.index + match[0].length;
+      if (overlaps(start, end) || !/[./\\]/.test(mention)) continue;
+      claim(start, fullEnd);
+      const candidates = resolveFile(mention, index);
+      fileMentions.push({ mention, candidates });
+      references.push({ type: 'file', text: mention, start, end, candidates });
+
+      if (match[2]) {
+        const range = [Number(match[2]), Number(match[3] || match[2])];
+        references.push({
+          type: 'selection',
+          text: text.slice(start, fullEnd),
+          start,
+          end: fullEnd,
+          range,
+          candidates: candidates.map((c) => ({
+            entity: { type: 'selection', path: c.entity.path, startLine: range[0], endLine: range[1] },
+            confidence: c.confidence,
+          })),
+        });
+      }
+    }
+  }
+
+  const referencedFiles = new Set(
+    fileMentions.flatMap((m) => m.candidates.filter((c) => c.confidence >= 0.5).map((c) => c.entity.path))
+  );
+  if (activeFile) referencedFiles.add(activeFile);
+
+  // Line ranges ("lines 10-20 of server.js", "L42")
+  LINE_RANGE.lastIndex = 0;
+  let lineMatch;
+  while ((lineMatch = LINE_RANGE.exec(text)) !== null) {
+    const start = lineMatch.index;
+    const end = start + lineMatch[0].length;
+    if (overlaps(start, start + 1)) continue;
+    claim(start, end);
+    const range = [Number(lineMatch[1]), Number(lineMatch[2] || lineMatch[1])];
+    let targets = [];
+    if (lineMatch[3]) targets = resolveFile(lineMatch[3], index);
+    else if (referencedFiles.size === 1) targets = [{ entity: { path: [...referencedFiles][0] }, confidence: 0.7 }];
+    references.push({
+      type: 'selection',
+      text: lineMatch[0],
+      start,
+      end,
+      range,
+      candidates: targets.map((t) => ({
+        entity: { type: 'selection', path: t.entity.path, startLine: range[0], endLine: range[1] },
+        confidence: t.confidence,
+      })),
+    });
+  }
+
+  // "the selected code" refers to the editor selection captured with the prompt
+  SELECTION_PHRASE.lastIndex = 0;
+  let phrase;
+  while ((phrase = SELECTION_PHRASE.exec(text)) !== null) {
+    const selection = typeof prompt === 'object' ? prompt.selection : null;
+    const selectionFile = selection && selection.file ? normalizePath(selection.file, index.root) : activeFile;
+    references.push({
+      type: 'selection',
+      text: phrase[0],
+      start: phrase.index,
+      end: phrase.index + phrase[0].length,
+      range: selection ? [selection.startLine, selection.endLine] : null,
+      candidates: selectionFile
+        ? [
+            {
+              entity: {
+                type: 'selection',
+                path: selectionFile,
+                startLine: selection ? selection.startLine : null,
+                endLine: selection ? selection.endLine : null,
+              },
+              confidence: selection ? 0.95 : 0.5,
+            },
+          ]
+        : [],
+    });
+  }
+
+  // Symbols: backticked names and calls first, then identifier-shaped words
+  const seenSymbols = new Set();
+  const addSymbol = (name, start, explicit) => {
+    const end = start + name.length;
+    if (overlaps(start, end) || seenSymbols.has(`${name}@${start}`)) return;
+    if (!explicit && !looksLikeSymbol(name)) return;
+    if (explicit && STOP_WORDS.has(name.toLowerCase())) return;
+    const candidates = resolveSymbol(name, index, referencedFiles, explicit);
+    if (candidates.length === 0 && !explicit) return;
+    seenSymbols.add(`${name}@${start}`);
+    claim(start, end);
+    references.push({ type: 'symbol', text: name, start, end, candidates });
+  };
+
+  BACKTICK.lastIndex = 0;
+  let tick;
+  while ((tick = BACKTICK.exec(text)) !== null) {
+    const inner = tick[1].trim().replace(/\(\)$/, '');
+    if (/^[A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)*$/.test(inner)) {
+      addSymbol(inner, tick.index + 1 + tick[1].indexOf(inner), true);
+    }
+  }
+  CALL_PATTERN.lastIndex = 0;
+  let call;
+  while ((call = CALL_PATTERN.exec(text)) !== null) addSymbol(call[1], call.index, true);
+  IDENTIFIER.lastIndex = 0;
+  let word;
+  while ((word = IDENTIFIER.exec(text)) !== null) addSymbol(word[1], word.index, false);
+
+  const finalized = references.sort((a, b) => a.start - b.start).map((r) => finalize(r, opts));
+  return {
+    references: finalized,
+    resolved: finalized.filter((r) => r.resolved),
+    unresolved: finalized.filter((r) => !r.resolved),
+  };
+}
+
+module.exports = {
+  resolvePromptReferences,
+  buildWorkspaceIndex,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,297 @@
+/**
+ * Suggestion Matching
+ * Aligns an AI code suggestion against the content that was actually applied
+ * and reports how much of the suggestion survived, where it diverged, and
+ * whether the user accepted, modified or rejected it.
+ *
+ * Lines are compared whitespace-normalized. The suggestion is first located
+ * inside the (usually much larger) file via anchor lines, then aligned to that
+ * window with an LCS; unmatched lines between anchors are paired up by
+ * character similarity to distinguish "edited" from "dropped".
+ */
+
+const DEFAULT_OPTIONS = {
+  similarLineThreshold: 0.6, // Character similarity for a line to count as modified rather than missing
+  acceptedCoverage: 0.9,
+  rejectedCoverage: 0.3,
+  windowPadding: 20, // Extra lines around the anchor span to include in the alignment window
+  maxLineLength: 400, // Longer lines are compared by token overlap instead of edit distance
+};
+
+function normalizeLine(line) {
+  return line.trim().replace(/\s+/g, ' ');
+}
+
+function splitLines(text) {
+  return String(text || '')
+    .replace(/\r\n?/g, '\n')
+    .split('\n');
+}
+
+function levenshtein(a, b) {
+  if (a === b) return 0;
+  if (a.length === 0) return b.length;
+  if (b.length === 0) return a.length;
+  let previous = new Array(b.length + 1);
+  let current = new Array(b.length + 1);
+  for (let j = 0; j <= b.length; j++) previous[j] = j;
+  for (let i = 1; i <= a.length; i++) {
+    current[0] = i;
+    for (let j = 1; j <= b.length; j++) {
+      const cost = a.charCodeAt(i - 1) === b.charCodeAt(j - 1) ? 0 : 1;
+      current[j] = Math.min(previous[j] + 1, current[j - 1] + 1, previous[j - 1] + cost);
+    }
+    [previous, current] = [current, previous];
+  }
+  return previous[b.length];
+}
+
+function lineSimilarity(a, b, options) {
+  if (a === b) return 1;
+  if (!a || !b) return 0;
+  if (a.length > options.maxLineLength || b.length > options.maxLineLength) {
+    const ta = new Set(a.split(/\W+/).filter(Boolean));
+    const tb = new Set(b.split(/\W+/).filter(Boolean));
+    let shared = 0;
+    for (const t of ta) if (tb.has(t)) shared++;
+    const union = ta.size + tb.size - shared;
+    return union > 0 ? shared / union : 0;
+  }
+  return 1 - levenshtein(a, b) / Math.max(a.length, b.length);
+}
+
+/**
+ * Locate the region of the applied content the suggestion most likely landed in
+ */
+function findWindow(suggestion, applied, options) {
+  const positions = new Map();
+  applied.forEach((line, index) => {
+    if (!line) return;
+    if (!positions.has(line)) positions.set(line, []);
+    positions.get(line).push(index);
+  });
+
+  // Vote for a start offset using every suggestion line that appears in the file
+  const votes = new Map();
+  suggestion.forEach((line, i) => {
+    const hits = positions.get(line);
+    if (!line || !hits || hits.length > 50) return;
+    for (const j of hits) {
+      const offset = j - i;
+      votes.set(offset, (votes.get(offset) || 0) + 1 / hits.length);
+    }
+  });
+
+  if (votes.size === 0) {
+    return applied.length <= suggestion.length * 4 + options.windowPadding
+      ? { start: 0, end: applied.length, anchored: false }
+      : null;
+  }
+
+  let bestOffset = 0;
+  let bestScore = -1;
+  for (const [offset, score] of votes) {
+    if (score > bestScore) {
+      bestScore = score;
+      bestOffset = offset;
+    }
+  }
+
+  return {
+    start: Math.max(0, bestOffset - options.windowPadding),
+    end: Math.min(applied.length, bestOffset + suggestion.length + options.windowPadding),
+    anchored: true,
+  };
+}
+
+/**
+ * LCS over normalized lines; returns matched index pairs in order
+ */
+function lcsPairs(a, b) {
+  const n = a.length;
+  const m = b.length;
+  const table = new Uint32Array((n + 1) * (m + 1));
+  const at = (i, j) => i * (m + 1) + j;
+
+  for (let i = n - 1; i >= 0; i--) {
+    for (let j = m - 1; j >= 0; j--) {
+      table[at(i, j)] =
+        a[i] && a[i] === b[j] ? table[at(i + 1, j + 1)] + 1 : Math.max(table[at(i + 1, j)], table[at(i, j + 1)]);
+    }
+  }
+
+  const pairs = [];
+  let i = 0;
+  let j = 0;
+  while (i < n && j < m) {
+    if (a[i] && a[i] === b[j]) {
+      pairs.push([i, j]);
+      i++;
+      j++;
+    } else if (table[at(i + 1, j)] >= table[at(i, j + 1)]) {
+      i++;
+    } else {
+      j++;
+    }
+  }
+  return pairs;
+}
+
+/**
+ * Match an AI suggestion against the applied content
+ * @param {string} suggestion - Suggested code (completion, code block)
+ * @param {string} afterContent - File content after the edit (or the applied diff's added text)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {object} { verdict, coverage, charCoverage, exactLines, modifiedLines, missingLines,
+ *   insertedLines, window, divergences }
+ */
+function matchSuggestionToEdit(suggestion, afterContent, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const suggestionRaw = splitLines(suggestion);
+  const appliedRaw = splitLines(afterContent);
+  const sugg = suggestionRaw.map(normalizeLine);
+  const applied = appliedRaw.map(normalizeLine);
+
+  const significant = sugg.filter(Boolean).length;
+  const empty = {
+    verdict: 'rejected',
+    coverage: 0,
+    charCoverage: 0,
+    exactLines: 0,
+    modifiedLines: 0,
+    missingLines: significant,
+    insertedLines: 0,
+    window: null,
+    divergences: significant > 0 ? [{ type: 'missing', suggestion: [1, suggestionRaw.length], applied: null }] : [],
+  };
+  if (significant === 0) return { ...empty, verdict: 'empty' };
+
+  const window = findWindow(sugg, applied, opts);
+  if (!window) return empty;
+
+  const target = applied.slice(window.start, window.end);
+  const pairs = lcsPairs(sugg, target);
+
+  // Walk the gaps between exact matches, pairing similar lines as modifications
+  const lineStatus = new Array(sugg.length).fill('missing');
+  const lineScore = new Array(sugg.length).fill(0);
+  const lineTarget = new Array(sugg.length).fill(null);
+  const inserted = [];
+  const bounds = [[-1, -1], ...pairs, [sugg.length, target.length]];
+
+  for (let k = 0; k < bounds.length - 1; k++) {
+    const [si, ti] = bounds[k];
+    const [sNext, tNext] = bounds[k + 1];
+    if (k > 0) {
+      lineStatus[si] = 'exact';
+      lineScore[si] = 1;
+      lineTarget[si] = ti;
+    }
+
+    const used = new Set();
+    let cursor = ti + 1;
+    for (let s = si + 1; s < sNext; s++) {
+      if (!sugg[s]) {
+        lineStatus[s] = 'blank';
+        continue;
+      }
+      let best = -1;
+      let bestSim = 0;
+      for (let c = cursor; c < tNext; c++) {
+        const sim = lineSimilarity(sugg[s], target[c], opts);
+        if (sim > bestSim) {
+          bestSim = sim;
+          best = c;
+        }
+      }
+      if (best !== -1 && bestSim >= opts.similarLineThreshold) {
+        lineStatus[s] = 'modified';
+        lineScore[s] = bestSim;
+        lineTarget[s] = best;
+        used.add(best);
+        cursor = best + 1;
+      }
+    }
+
+    // Applied lines between two matched anchors that nothing in the suggestion maps to
+    if (k > 0 && k < bounds.length - 2) {
+      for (let c = ti + 1; c < tNext; c++) {
+        if (target[c] && !used.has(c)) inserted.push(c);
+      }
+    }
+  }
+
+  // Collapse per-line statuses into contiguous divergence regions (1-based lines)
+  const offset = window.start + 1;
+  const divergences = [];
+  let open = null;
+  for (let s = 0; s <= sugg.length; s++) {
+    const status = s < sugg.length ? lineStatus[s] : null;
+    if (status === 'blank' && open) continue;
+    if (open && status === open.type) {
+      open.suggestion[1] = s + 1;
+      if (lineTarget[s] !== null) open.applied[1] = lineTarget[s] + offset;
+      continue;
+    }
+    if (open) divergences.push(open);
+    open = null;
+    if (status === 'missing' || status === 'modified') {
+      const applied = lineTarget[s] !== null ? [lineTarget[s] + offset, lineTarget[s] + offset] : null;
+      open = { type: status, suggestion: [s + 1, s + 1], applied };
+    }
+  }
+  for (const c of inserted) {
+    const last = divergences[divergences.length - 1];
+    if (last && last.type === 'inserted' && last.applied[1] === c + offset - 1) last.applied[1] = c + offset;
+    else divergences.push({ type: 'inserted', suggestion: null, applied: [c + offset, c + offset] });
+  }
+
+  const matchedTargets = lineTarget.filter((t) => t !== null);
+
+  let exactLines = 0;
+  let modifiedLines = 0;
+  let missingLines = 0;
+  let coveredChars = 0;
+  let totalChars = 0;
+  for (let s = 0; s < sugg.length; s++) {
+    if (lineStatus[s] === 'blank') continue;
+    totalChars += sugg[s].length;
+    coveredChars += sugg[s].length * lineScore[s];
+    if (lineStatus[s] === 'exact') exactLines++;
+    else if (lineStatus[s] === 'modified') modifiedLines++;
+    else missingLines++;
+  }
+
+  const coverage = (exactLines + modifiedLines) / significant;
+  const charCoverage = totalChars > 0 ? coveredChars / totalChars : 0;
+  const insertedLines = inserted.length;
+
+  let verdict = 'modified';
+  if (charCoverage >= opts.acceptedCoverage && modifiedLines === 0 && missingLines === 0) verdict = 'accepted';
+  else if (charCoverage < opts.rejectedCoverage) verdict = 'rejected';
+
+  return {
+    verdict,
+    coverage,
+    charCoverage,
+    exactLines,
+    modifiedLines,
+    missingLines,
+    insertedLines,
+    window: {
+      start: window.start + 1,
+      end: window.end,
+      anchored: window.anchored,
+      matchedSpan:
+        matchedTargets.length > 0
+          ? [Math.min(...matchedTargets) + offset, Math.max(...matchedTargets) + offset]
+          : null,
+    },
+    divergences,
+  };
+}
+
+module.exports = {
+  matchSuggestionToEdit,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,241 @@
+/**
+ * Trace Alignment
+ * Matches AI prompt/response events to the file edits that follow them,
+ * scoring each candidate pair on time proximity, overlap between the
+ * response's code blocks and the lines the edit added, and file-path hints.
+ * The result is bidirectional: every edit gets at most one prompt, every
+ * prompt gets the list of edits attributed to it.
+ */
+
+const { extractCodeBlocks } = require('../../utils/code-blocks');
+const { toMillis } = require('../../utils/timestamps');
+const { parseDetails } = require('../../utils/event-details');
+
+const DEFAULT_WEIGHTS = {
+  temporal: 0.3,
+  content: 0.5,
+  file: 0.2,
+};
+
+const DEFAULT_OPTIONS = {
+  windowMs: 10 * 60 * 1000, // Edits more than 10 minutes after a prompt are not candidates
+  halfLifeMs: 2 * 60 * 1000, // Temporal score halves every 2 minutes
+  clockSkewMs: 5000, // Tolerate edits stamped slightly before the prompt
+  minConfidence: 0.35,
+  minLineLength: 4, // Ignore trivial lines like "}" when comparing content
+  requireSameWorkspace: true,
+  weights: DEFAULT_WEIGHTS,
+};
+
+function normalizeLine(line) {
+  return line.trim().replace(/\s+/g, ' ');
+}
+
+function basename(filePath) {
+  if (!filePath) return null;
+  const parts = String(filePath).split(/[\\/]/);
+  return parts[parts.length - 1] || null;
+}
+
+function lineSet(text, minLength) {
+  const set = new Set();
+  for (const line of String(text || '').split('\n')) {
+    const normalized = normalizeLine(line);
+    if (normalized.length >= minLength) set.add(normalized);
+  }
+  return set;
+}
+
+function tokenSet(text) {
+  return new Set(String(text || '').match(/[A-Za-z_$][\w$]*|\d+/g) || []);
+}
+
+/**
+ * Lines present in `after` but not in `before` (multiset difference)
+ */
+function addedLines(before, after) {
+  const counts = new Map();
+  for (const line of String(before || '').split('\n')) {
+    const key = normalizeLine(line);
+    counts.set(key, (counts.get(key) || 0) + 1);
+  }
+  const added = [];
+  for (const line of String(after || '').split('\n')) {
+    const key = normalizeLine(line);
+    const remaining = counts.get(key) || 0;
+    if (remaining > 0) counts.set(key, remaining - 1);
+    else added.push(line);
+  }
+  return added.join('\n');
+}
+
+function normalizePrompt(prompt, index, options) {
+  const details = parseDetails(prompt);
+  const response = prompt.response || prompt.responseText || details.response || '';
+  const blocks = prompt.codeBlocks || extractCodeBlocks(response);
+  const text = prompt.text || prompt.prompt || details.prompt || '';
+  const codeText = blocks.map((b) => b.code).join('\n');
+
+  return {
+    id: prompt.id !== undefined ? prompt.id : index,
+    time: toMillis(prompt.timestamp),
+    workspace: prompt.workspace_path || prompt.workspacePath || null,
+    lines: lineSet(codeText, options.minLineLength),
+    tokens: tokenSet(codeText),
+    paths: blocks.map((b) => basename(b.path)).filter(Boolean),
+    mentions: `${text}\n${response}`,
+  };
+}
+
+function normalizeEdit(edit, index, options) {
+  const details = parseDetails(edit);
+  const before = edit.before_code ?? edit.beforeContent ?? details.before_content ?? '';
+  const after = edit.after_code ?? edit.afterContent ?? details.after_content ?? '';
+  const added = edit.added ?? details.added ?? addedLines(before, after);
+  const filePath = edit.file_path || edit.filePath || details.file_path || null;
+
+  return {
+    id: edit.id !== undefined ? edit.id : index,
+    time: toMillis(edit.timestamp),
+    workspace: edit.workspace_path || edit.workspacePath || null,
+    file: basename(filePath),
+    lines: lineSet(added, options.minLineLength),
+    tokens: tokenSet(added),
+  };
+}
+
+function temporalScore(lagMs, options) {
+  return Math.pow(0.5, Math.max(0, lagMs) / options.halfLifeMs);
+}
+
+/**
+ * Share of the edit's added lines that appear in the response code, falling
+ * back to identifier overlap when lines were reformatted on apply
+ */
+function contentScore(prompt, edit) {
+  if (edit.lines.size === 0 || prompt.lines.size === 0) return 0;
+
+  let contained = 0;
+  for (const line of edit.lines) {
+    if (prompt.lines.has(line)) contained++;
+  }
+  const lineScore = contained / edit.lines.size;
+
+  let shared = 0;
+  for (const token of edit.tokens) {
+    if (prompt.tokens.has(token)) shared++;
+  }
+  const union = edit.tokens.size + prompt.tokens.size - shared;
+  const tokenScore = union > 0 ? shared / union : 0;
+
+  return Math.max(lineScore, tokenScore);
+}
+
+function fileScore(prompt, edit) {
+  if (!edit.file) return 0;
+  if (prompt.paths.includes(edit.file)) return 1;
+  return prompt.mentions.includes(edit.file) ? 0.6 : 0;
+}
+
+/**
+ * Align prompt events to edit events
+ * @param {Array} promptEvents - Prompts with { id, timestamp, text, response | codeBlocks, workspace_path }
+ * @param {Array} editEvents - Edits with { id, timestamp, file_path, before_code, after_code } or event details
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {object} { alignments, promptToEdits, editToPrompt, unmatchedPrompts, unmatchedEdits, stats }
+ */
+function alignTraces(promptEvents = [], editEvents = [], options = {}) {
+  const config = {
+    ...DEFAULT_OPTIONS,
+    ...options,
+    weights: { ...DEFAULT_WEIGHTS, ...(options.weights || {}) },
+  };
+
+  const prompts = promptEvents
+    .map((p, i) => normalizePrompt(p, i, config))
+    .filter((p) => p.time !== null)
+    .sort((a, b) => a.time - b.time);
+  const edits = editEvents
+    .map((e, i) => normalizeEdit(e, i, config))
+    .filter((e) => e.time !== null)
+    .sort((a, b) => a.time - b.time);
+
+  const candidates = [];
+  let windowStart = 0;
+
+  for (const edit of edits) {
+    // Prompts are sorted, so advance past those too old to matter for this edit
+    while (windowStart < prompts.length && prompts[windowStart].time < edit.time - config.windowMs) {
+      windowStart++;
+    }
+
+    for (let i = windowStart; i < prompts.length; i++) {
+      const prompt = prompts[i];
+      const lagMs = edit.time - prompt.time;
+      if (lagMs < -config.clockSkewMs) break;
+      if (config.requireSameWorkspace && prompt.workspace && edit.workspace && prompt.workspace !== edit.workspace) {
+        continue;
+      }
+
+      const components = {
+        temporal: temporalScore(lagMs, config),
+        content: contentScore(prompt, edit),
+        file: fileScore(prompt, edit),
+      };
+      const confidence =
+        components.temporal * config.weights.temporal +
+        components.content * config.weights.content +
+        components.file * config.weights.file;
+
+      if (confidence >= config.minConfidence) {
+        candidates.push({ promptId: prompt.id, editId: edit.id, confidence, lagMs, components });
+      }
+    }
+  }
+
+  // Greedy assignment: strongest pairs first, each edit attributed to one prompt
+  candidates.sort((a, b) => b.confidence - a.confidence || a.lagMs - b.lagMs);
+  const alignments = [];
+  const editToPrompt = {};
+  const promptToEdits = {};
+
+  for (const candidate of candidates) {
+    if (editToPrompt[candidate.editId] !== undefined) continue;
+    editToPrompt[candidate.editId] = candidate.promptId;
+    (promptToEdits[candidate.promptId] = promptToEdits[candidate.promptId] || []).push(candidate.editId);
+    alignments.push({
+      ...candidate,
+      confidence: Math.round(candidate.confidence * 1000) / 1000,
+    });
+  }
+
+  const promptOrder = new Map(prompts.map((p, i) => [p.id, i]));
+  alignments.sort((a, b) => promptOrder.get(a.promptId) - promptOrder.get(b.promptId) || a.lagMs - b.lagMs);
+
+  const unmatchedPrompts = prompts.filter((p) => !promptToEdits[p.id]).map((p) => p.id);
+  const unmatchedEdits = edits.filter((e) => editToPrompt[e.id] === undefined).map((e) => e.id);
+
+  return {
+    alignments,
+    promptToEdits,
+    editToPrompt,
+    unmatchedPrompts,
+    unmatchedEdits,
+    stats: {
+      prompts: prompts.length,
+      edits: edits.length,
+      candidates: candidates.length,
+      aligned: alignments.length,
+      editCoverage: edits.length > 0 ? alignments.length / edits.length : 0,
+      meanConfidence:
+        alignments.length > 0 ? alignments.reduce((sum, a) => sum + a.confidence, 0) / alignments.length : 0,
+    },
+  };
+}
+
+module.exports = {
+  alignTraces,
+  addedLines,
+  DEFAULT_OPTIONS,
+  DEFAULT_WEIGHTS,
+};
@@ -0,0 +1,238 @@
+/**
+ * Edit Churn Metrics
+ * Replays a file's history (snapshots, before/after entries or unified diffs)
+ * line by line, tracking when each line was born, who wrote it and when it
+ * died. From that: rework (lines rewritten within N minutes of being added),
+ * survival of AI-inserted lines (Kaplan-Meier, lines still alive at the end
+ * are censored) and net vs. gross change.
+ */
+
+const diff = require('diff');
+const { toMillis } = require('../../utils/timestamps');
+
+const DEFAULT_OPTIONS = {
+  reworkWindowMs: 30 * 60 * 1000, // Lines removed within 30 minutes of being added count as rework
+  ignoreBlankLines: true,
+  until: null, // End of observation for censoring (defaults to the last step)
+};
+
+const AI_SOURCES = new Set(['ai', 'composer', 'agent', 'ai-generated', 'copilot', 'tab']);
+
+function isAiStep(step) {
+  if (step.aiGenerated !== undefined) return Boolean(step.aiGenerated);
+  if (step.ai_generated !== undefined) return Boolean(step.ai_generated);
+  return Boolean(step.prompt_id || step.promptId) || AI_SOURCES.has(String(step.source || '').toLowerCase());
+}
+
+function splitLines(text) {
+  if (!text) return [];
+  const lines = String(text).replace(/\r\n?/g, '\n').split('\n');
+  if (lines[lines.length - 1] === '') lines.pop();
+  return lines;
+}
+
+/**
+ * Resolve a history step to the file content after it
+ */
+function contentAfter(step, current) {
+  if (step.content !== undefined) return step.content;
+  if (step.after !== undefined) return step.after;
+  if (step.after_code !== undefined) return step.after_code;
+  if (step.diff !== undefined) {
+    const patched = diff.applyPatch(current, step.diff);
+    if (patched === false) throw new Error('Patch does not apply to the tracked content');
+    return patched;
+  }
+  return null;
+}
+
+function contentBefore(step) {
+  if (step.before !== undefined) return step.before;
+  if (step.before_code !== undefined) return step.before_code;
+  return null;
+}
+
+/**
+ * Kaplan-Meier survival curve over line lifetimes
+ * @returns {object} { curve: [{ t, survival }], medianMs } (median null if never below 0.5)
+ */
+function kaplanMeier(lifetimes) {
+  const sorted = [...lifetimes].sort((a, b) => a.duration - b.duration || b.died - a.died);
+  let atRisk = sorted.length;
+  let survival = 1;
+  let medianMs = null;
+  const curve = [];
+
+  for (let i = 0; i < sorted.length; ) {
+    const t = sorted[i].duration;
+    let deaths = 0;
+    let leaving = 0;
+    while (i < sorted.length && sorted[i].duration === t) {
+      if (sorted[i].died) deaths++;
+      leaving++;
+      i++;
+    }
+    if (deaths > 0) {
+      survival *= 1 - deaths / atRisk;
+      curve.push({ t, survival });
+      if (medianMs === null && survival <= 0.5) medianMs = t;
+    }
+    atRisk -= leaving;
+  }
+  return { curve, medianMs };
+}
+
+function summarizeOrigin(lines, opts) {
+  const died = lines.filter((line) => line.died);
+  const reworked = died.filter((line) => line.duration <= opts.reworkWindowMs);
+  const { curve, medianMs } = kaplanMeier(lines);
+  return {
+    added: lines.length,
+    removed: died.length,
+    surviving: lines.length - died.length,
+    survivalRate: lines.length > 0 ? (lines.length - died.length) / lines.length : null,
+    reworked: reworked.length,
+    reworkRate: lines.length > 0 ? reworked.length / lines.length : null,
+    meanLifetimeMs: died.length > 0 ? died.reduce((sum, line) => sum + line.duration, 0) / died.length : null,
+    medianSurvivalMs: medianMs,
+    survivalCurve: curve,
+  };
+}
+
+/**
+ * Compute churn metrics for one file's history
+ * @param {Array<object>} fileHistory - Ordered steps: { timestamp, content } | { timestamp, before, after }
+ *   | { timestamp, diff }, plus attribution (aiGenerated / ai_generated / source / prompt_id)
+ * @param {object} options - { reworkWindowMs, ignoreBlankLines, until }
+ * @returns {object} { steps, span, gross, net, churnRatio, rework, byOrigin, ai, manual }
+ */
+function computeChurn(fileHistory, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const steps = (fileHistory || [])
+    .map((step, index) => ({ step, index, t: toMillis(step.timestamp) }))
+    .filter((s) => s.t !== null)
+    .sort((a, b) => a.t - b.t || a.index - b.index);
+
+  const counts = (line) => !opts.ignoreBlankLines || line.trim() !== '';
+  const finished = [];
+  let tracked = null; // Line records aligned with the current content
+  let current = '';
+  let initial = null;
+  let firstT = null;
+  let lastT = null;
+  let grossAdded = 0;
+  let grossRemoved = 0;
+  let applied = 0;
+  const skipped = [];
+
+  for (const { step, index, t } of steps) {
+    if (tracked === null) {
+      // Seed with whatever existed before the first observed change; those lines predate the history
+      const seed = contentBefore(step) ?? (step.content !== undefined ? step.content : '');
+      current = seed;
+      initial = seed;
+      tracked = splitLines(seed).map((text) => ({ text, born: null, origin: 'baseline' }));
+      firstT = t;
+      if (step.content !== undefined && contentBefore(step) === null) {
+        lastT = t;
+        continue;
+      }
+    }
+
+    let next;
+    try {
+      next = contentAfter(step, current);
+    } catch (error) {
+      skipped.push({ index, reason: error.message });
+      continue;
+    }
+    if (next === null) {
+      skipped.push({ index, reason: 'No content, after or diff' });
+      continue;
+    }
+
+    const origin = isAiStep(step) ? 'ai' : 'manual';
+    const updated = [];
+    let cursor = 0;
+    for (const part of diff.diffLines(current, next)) {
+      const partLines = splitLines(part.value);
+      if (part.added) {
+        for (const text of partLines) {
+          updated.push({ text, born: t, origin });
+          if (counts(text)) grossAdded++;
+        }
+      } else if (part.removed) {
+        for (let k = 0; k < partLines.length; k++) {
+          const record = tracked[cursor++];
+          if (!record) continue;
+          if (counts(record.text)) grossRemoved++;
+          if (record.born !== null && counts(record.text)) {
+            finished.push({ origin: record.origin, duration: t - record.born, died: true });
+          }
+        }
+      } else {
+        for (let k = 0; k < partLines.length; k++) updated.push(tracked[cursor++]);
+      }
+    }
+
+    tracked = updated;
+    current = next;
+    lastT = t;
+    applied++;
+  }
+
+  if (tracked === null) {
+    return { steps: 0, skipped, span: null, gross: null, net: null, churnRatio: null, rework: null };
+  }
+
+  // Lines still present at the end of observation are censored
+  const end = toMillis(opts.until) ?? lastT;
+  for (const record of tracked) {
+    if (record.born !== null && counts(record.text)) {
+      finished.push({ origin: record.origin, duration: Math.max(0, end - record.born), died: false });
+    }
+  }
+
+  let netAdded = 0;
+  let netRemoved = 0;
+  for (const part of diff.diffLines(initial, current)) {
+    const n = splitLines(part.value).filter(counts).length;
+    if (part.added) netAdded += n;
+    else if (part.removed) netRemoved += n;
+  }
+
+  const gross = grossAdded + grossRemoved;
+  const net = netAdded + netRemoved;
+  const reworked = finished.filter((line) => line.died && line.duration <= opts.reworkWindowMs).length;
+  const byOrigin = {
+    ai: summarizeOrigin(finished.filter((line) => line.origin === 'ai'), opts),
+    manual: summarizeOrigin(finished.filter((line) => line.origin === 'manual'), opts),
+  };
+
+  return {
+    steps: applied,
+    skipped,
+    span: { start: firstT, end, durationMs: end - firstT },
+    gross: { added: grossAdded, removed: grossRemoved, total: gross },
+    net: {
+      added: netAdded,
+      removed: netRemoved,
+      total: net,
+      lineDelta: splitLines(current).filter(counts).length - splitLines(initial).filter(counts).length,
+    },
+    // How much work was thrown away: 1 means every changed line stuck, higher means rewrites
+    churnRatio: net > 0 ? gross / net : gross > 0 ? Infinity : 1,
+    rework: {
+      windowMs: opts.reworkWindowMs,
+      lines: reworked,
+      rate: grossAdded > 0 ? reworked / grossAdded : 0,
+    },
+    byOrigin,
+  };
+}
+
+module.exports = {
+  computeChurn,
+  kaplanMeier,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,257 @@
+/**
+ * Diff Clustering
+ * Groups captured diffs that make the same change — a rename or refactor
+ * applied across many files, the same fix pasted into several handlers —
+ * so repeated edit patterns can be studied as one.
+ *
+ * Each diff is reduced to a feature set: the tokens it removed and added
+ * (after cancelling tokens that were merely moved within changed lines)
+ * plus its normalized changed lines. Similarity is Jaccard over those sets.
+ * MinHash LSH buckets diffs first, so only likely pairs are compared
+ * exactly and clustering stays near-linear in the number of diffs.
+ */
+
+const diff = require('diff');
+const { tokenizeCode } = require('../../utils/diff-engine');
+
+const DEFAULT_OPTIONS = {
+  numHashes: 64,
+  rows: null, // LSH rows per band; chosen from the threshold when null
+  minFeatures: 1, // Diffs with fewer features (e.g. whitespace-only) are left unclustered
+  includeSingletons: false,
+  language: null,
+};
+
+function hash32(text, seed) {
+  let h = (0x811c9dc5 ^ seed) >>> 0;
+  for (let i = 0; i < text.length; i++) {
+    h ^= text.charCodeAt(i);
+    h = Math.imul(h, 0x01000193);
+  }
+  h ^= h >>> 16;
+  h = Math.imul(h, 0x85ebca6b);
+  h ^= h >>> 13;
+  h = Math.imul(h, 0xc2b2ae35);
+  h ^= h >>> 16;
+  return h >>> 0;
+}
+
+/**
+ * Removed and added lines of a diff given as { before, after }, a unified patch, or { removed, added }
+ */
+function changedLines(item) {
+  if (typeof item === 'string' || typeof item.diff === 'string' || typeof item.patch === 'string') {
+    const patch = typeof item === 'string' ? item : item.diff || item.patch;
+    const removed = [];
+    const added = [];
+    for (const line of patch.split('\n')) {
+      if (line.startsWith('+++') || line.startsWith('---')) continue;
+      if (line.startsWith('+')) added.push(line.slice(1));
+      else if (line.startsWith('-')) removed.push(line.slice(1));
+    }
+    return { removed, added };
+  }
+  if (Array.isArray(item.removed) || Array.isArray(item.added)) {
+    return { removed: item.removed || [], added: item.added || [] };
+  }
+
+  const before = item.before ?? item.before_code ?? item.before_content ?? '';
+  const after = item.after ?? item.after_code ?? item.after_content ?? '';
+  const removed = [];
+  const added = [];
+  for (const part of diff.diffLines(before, after)) {
+    if (!part.added && !part.removed) continue;
+    const lines = part.value.replace(/\n$/, '').split('\n');
+    (part.added ? added : removed).push(...lines);
+  }
+  return { removed, added };
+}
+
+/**
+ * Feature set describing what a diff changed
+ */
+function diffFeatures(item, language) {
+  const { removed, added } = changedLines(item);
+  const removedTokens = tokenizeCode(removed.join('\n'), language);
+  const addedTokens = tokenizeCode(added.join('\n'), language);
+
+  // Cancel tokens present on both sides so `foo(a)` -> `bar(a)` reduces to -foo +bar
+  const balance = new Map();
+  for (const token of removedTokens) balance.set(token, (balance.get(token) || 0) - 1);
+  for (const token of addedTokens) balance.set(token, (balance.get(token) || 0) + 1);
+
+  const features = new Set();
+  for (const [token, count] of balance) {
+    if (count < 0) features.add(`-${token}`);
+    else if (count > 0) features.add(`+${token}`);
+  }
+  const normalize = (line) => tokenizeCode(line, language).join(' ');
+  for (const line of removed) if (line.trim()) features.add(`-L ${normalize(line)}`);
+  for (const line of added) if (line.trim()) features.add(`+L ${normalize(line)}`);
+  return features;
+}
+
+function jaccard(a, b) {
+  if (a.size === 0 && b.size === 0) return 1;
+  let shared = 0;
+  const [small, large] = a.size <= b.size ? [a, b] : [b, a];
+  for (const feature of small) if (large.has(feature)) shared++;
+  return shared / (a.size + b.size - shared);
+}
+
+/**
+ * Pick LSH rows per band so the S-curve threshold (1/b)^(1/r) sits below the similarity threshold
+ */
+function chooseRows(numHashes, threshold) {
+  let best = 1;
+  for (let rows = 1; rows <= numHashes; rows++) {
+    if (numHashes % rows !== 0) continue;
+    const bands = numHashes / rows;
+    if (Math.pow(1 / bands, 1 / rows) <= threshold * 0.85) best = rows;
+  }
+  return best;
+}
+
+function minhash(features, numHashes) {
+  const signature = new Uint32Array(numHashes).fill(0xffffffff);
+  for (const feature of features) {
+    const h1 = hash32(feature, 0x9e3779b9);
+    const h2 = hash32(feature, 0x7f4a7c15) | 1;
+    // Double hashing: h_i = h1 + i * h2 gives numHashes independent-enough permutations
+    for (let i = 0; i < numHashes; i++) {
+      const h = (h1 + Math.imul(i, h2)) >>> 0;
+      if (h < signature[i]) signature[i] = h;
+    }
+  }
+  return signature;
+}
+
+/**
+ * Cluster diffs by similarity of what they changed
+ * @param {Array} diffs - { id, before, after } | { id, diff } (unified) | { id, removed, added } | patch strings
+ * @param {number} similarityThreshold - Minimum Jaccard similarity to link two diffs
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {object} { clusters: [{ members, size, representative, cohesion, pattern }], unclustered, stats }
+ */
+function clusterDiffs(diffs, similarityThreshold = 0.7, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const items = (diffs || []).map((item, index) => ({
+    id: typeof item === 'object' && item.id !== undefined ? item.id : index,
+    features: diffFeatures(item, opts.language),
+  }));
+
+  const eligible = items.filter((item) => item.features.size >= opts.minFeatures);
+
+  // Identical feature sets collapse up front so huge exact-repeat groups cost nothing pairwise
+  const exact = new Map();
+  for (const item of eligible) {
+    const key = Array.from(item.features).sort().join('\u0000');
+    if (!exact.has(key)) exact.set(key, { features: item.features, members: [] });
+    exact.get(key).members.push(item.id);
+  }
+  const groups = Array.from(exact.values());
+
+  const rows = opts.rows || chooseRows(opts.numHashes, similarityThreshold);
+  const bands = Math.floor(opts.numHashes / rows);
+  const buckets = new Map();
+  groups.forEach((group, g) => {
+    const signature = minhash(group.features, opts.numHashes);
+    for (let band = 0; band < bands; band++) {
+      const key = `${band}:${Array.from(signature.subarray(band * rows, (band + 1) * rows)).join(',')}`;
+      if (!buckets.has(key)) buckets.set(key, []);
+      buckets.get(key).push(g);
+    }
+  });
+
+  const parent = groups.map((_, g) => g);
+  const find = (g) => {
+    while (parent[g] !== g) g = parent[g] = parent[parent[g]];
+    return g;
+  };
+  const degree = new Float64Array(groups.length);
+  const compared = new Set();
+  let comparisons = 0;
+
+  for (const members of buckets.values()) {
+    for (let x = 0; x < members.length; x++) {
+      for (let y = x + 1; y < members.length; y++) {
+        const a = members[x];
+        const b = members[y];
+        const key = a * groups.length + b;
+        if (compared.has(key)) continue;
+        compared.add(key);
+        comparisons++;
+        const similarity = jaccard(groups[a].features, groups[b].features);
+        if (similarity < similarityThreshold) continue;
+        degree[a] += similarity * groups[b].members.length;
+        degree[b] += similarity * groups[a].members.length;
+        parent[find(a)] = find(b);
+      }
+    }
+  }
+
+  const components = new Map();
+  groups.forEach((group, g) => {
+    const root = find(g);
+    if (!components.has(root)) components.set(root, []);
+    components.get(root).push(g);
+  });
+
+  const clusters = [];
+  const unclustered = items.filter((item) => item.features.size < opts.minFeatures).map((item) => item.id);
+  for (const members of components.values()) {
+    const ids = members.flatMap((g) => groups[g].members);
+    if (ids.length < 2 && !opts.includeSingletons) {
+      unclustered.push(...ids);
+      continue;
+    }
+
+    // Representative: the exact-group most similar to the rest of the cluster
+    const centrality = (g) => degree[g] + groups[g].members.length;
+    const best = members.reduce((p, q) => (centrality(q) > centrality(p) ? q : p));
+
+    // Pattern: token-level features shared by every member
+    const isToken = (f) => !f.startsWith('-L ') && !f.startsWith('+L ');
+    const pattern = Array.from(groups[members[0]].features).filter(
+      (f) => isToken(f) && members.every((g) => groups[g].features.has(f))
+    );
+
+    let cohesion = 1;
+    if (members.length > 1) {
+      let total = 0;
+      for (const g of members) total += jaccard(groups[g].features, groups[best].features);
+      cohesion = total / members.length;
+    }
+
+    clusters.push({
+      members: ids,
+      size: ids.length,
+      representative: groups[best].members[0],
+      cohesion: Math.round(cohesion * 1000) / 1000,
+      pattern: {
+        removed: pattern.filter((f) => f.startsWith('-')).map((f) => f.slice(1)).slice(0, 20),
+        added: pattern.filter((f) => f.startsWith('+')).map((f) => f.slice(1)).slice(0, 20),
+      },
+    });
+  }
+
+  clusters.sort((a, b) => b.size - a.size);
+  return {
+    clusters,
+    unclustered,
+    stats: {
+      diffs: items.length,
+      distinct: groups.length,
+      clustered: clusters.reduce((sum, c) => sum + c.size, 0),
+      bands,
+      rows,
+      comparisons,
+    },
+  };
+}
+
+module.exports = {
+  clusterDiffs,
+  diffFeatures,
+  DEFAULT_OPTIONS,
+};
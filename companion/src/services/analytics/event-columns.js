@@ -0,0 +1,278 @@
+/**
+ * Columnar Event Store
+ * Arrow-style in-memory representation of captured events for dashboard
+ * analytics: each field lives in its own typed array, string fields are
+ * dictionary-encoded, and filters produce index selections that aggregations
+ * consume without materializing per-event objects.
+ *
+ * Records that aren't file events (terminal commands) bring their own
+ * rowMapper and any extra columns it fills: { numericColumns, dictionaryColumns }.
+ */
+
+const { toMillis } = require('../../utils/timestamps');
+const { parseDetails } = require('../../utils/event-details');
+
+const INITIAL_CAPACITY = 1024;
+
+/**
+ * Dictionary encoding for low-cardinality string columns
+ */
+class Dictionary {
+  constructor() {
+    this.values = [];
+    this.codes = new Map();
+  }
+
+  encode(value) {
+    const key = value === undefined || value === null ? '' : String(value);
+    let code = this.codes.get(key);
+    if (code === undefined) {
+      code = this.values.length;
+      this.values.push(key);
+      this.codes.set(key, code);
+    }
+    return code;
+  }
+
+  decode(code) {
+    return this.values[code];
+  }
+
+  lookup(value) {
+    return this.codes.get(String(value));
+  }
+}
+
+const NUMERIC_COLUMNS = {
+  timestamp: Float64Array,
+  linesAdded: Int32Array,
+  linesRemoved: Int32Array,
+  charsChanged: Int32Array,
+  aiGenerated: Uint8Array,
+};
+
+const DICTIONARY_COLUMNS = ['type', 'file', 'language', 'session', 'workspace'];
+
+/**
+ * Default row mapper for events/entries as stored by PersistentDB
+ */
+function defaultRowMapper(event) {
+  const details = parseDetails(event);
+  return {
+    timestamp: toMillis(event.timestamp),
+    type: event.type || event.source || 'unknown',
+    file: event.file_path || details.file_path || details.file || '',
+    language: event.language || details.language || '',
+    session: event.session_id || '',
+    workspace: event.workspace_path || '',
+    linesAdded: event.lines_added ?? details.lines_added ?? details.linesAdded ?? 0,
+    linesRemoved: event.lines_removed ?? details.lines_removed ?? details.linesRemoved ?? 0,
+    charsChanged: details.chars_added ?? details.diff_size ?? details.diffSize ?? 0,
+    aiGenerated: event.ai_generated ? 1 : 0,
+  };
+}
+
+class EventColumns {
+  constructor(options = {}) {
+    this.length = 0;
+    this.capacity = options.capacity || INITIAL_CAPACITY;
+    this.rowMapper = options.rowMapper || defaultRowMapper;
+    this.numericColumns = { ...NUMERIC_COLUMNS, ...options.numericColumns };
+    this.dictionaryColumns = [...new Set([...DICTIONARY_COLUMNS, ...(options.dictionaryColumns || [])])];
+    this.columns = {};
+    this.dictionaries = {};
+
+    for (const [name, ArrayType] of Object.entries(this.numericColumns)) {
+      this.columns[name] = new ArrayType(this.capacity);
+    }
+    for (const name of this.dictionaryColumns) {
+      this.columns[name] = new Uint32Array(this.capacity);
+      this.dictionaries[name] = new Dictionary();
+    }
+  }
+
+  static fromEvents(events, options = {}) {
+    const store = new EventColumns({ ...options, capacity: Math.max(INITIAL_CAPACITY, events.length) });
+    store.appendAll(events);
+    return store;
+  }
+
+  grow(minCapacity) {
+    let capacity = this.capacity;
+    while (capacity < minCapacity) capacity *= 2;
+    for (const [name, column] of Object.entries(this.columns)) {
+      const grown = new column.constructor(capacity);
+      grown.set(column.subarray(0, this.length));
+      this.columns[name] = grown;
+    }
+    this.capacity = capacity;
+  }
+
+  append(event) {
+    if (this.length >= this.capacity) this.grow(this.length + 1);
+    const row = this.rowMapper(event);
+    const i = this.length++;
+    for (const name of Object.keys(this.numericColumns)) {
+      this.columns[name][i] = Number(row[name]) || 0;
+    }
+    for (const name of this.dictionaryColumns) {
+      this.columns[name][i] = this.dictionaries[name].encode(row[name]);
+    }
+  }
+
+  appendAll(events) {
+    if (this.length + events.length > this.capacity) this.grow(this.length + events.length);
+    for (const event of events) this.append(event);
+  }
+
+  /**
+   * All row indices
+   */
+  all() {
+    const selection = new Uint32Array(this.length);
+    for (let i = 0; i < this.length; i++) selection[i] = i;
+    return selection;
+  }
+
+  /**
+   * Filter rows with a vectorized pass over the relevant columns
+   * @param {object} criteria - { since, until, types, files, languages, sessions, workspaces,
+   *   aiGenerated, where: { dictionaryColumn: [values] } }
+   * @param {Uint32Array} selection - Optional prior selection to refine
+   * @returns {Uint32Array} Selected row indices
+   */
+  filter(criteria = {}, selection = null) {
+    const since = toMillis(criteria.since) ?? -Infinity;
+    const until = toMillis(criteria.until) ?? Infinity;
+    const codeSets = {};
+    for (const [criterion, column] of [
+      ['types', 'type'],
+      ['files', 'file'],
+      ['languages', 'language'],
+      ['sessions', 'session'],
+      ['workspaces', 'workspace'],
+      ...Object.keys(criteria.where || {}).map((column) => [column, column]),
+    ]) {
+      const values = criteria.where?.[criterion] || criteria[criterion];
+      if (!values) continue;
+      if (!this.dictionaries[column]) throw new Error(`Cannot filter on non-dictionary column: ${column}`);
+      codeSets[column] = new Set(
+        values.map((v) => this.dictionaries[column].lookup(v)).filter((c) => c !== undefined)
+      );
+    }
+    const ai = criteria.aiGenerated === undefined ? -1 : criteria.aiGenerated ? 1 : 0;
+
+    const { timestamp, aiGenerated } = this.columns;
+    const source = selection || this.all();
+    const out = new Uint32Array(source.length);
+    let n = 0;
+
+    outer: for (let k = 0; k < source.length; k++) {
+      const i = source[k];
+      const t = timestamp[i];
+      if (t < since || t > until) continue;
+      if (ai !== -1 && aiGenerated[i] !== ai) continue;
+      for (const column in codeSets) {
+        if (!codeSets[column].has(this.columns[column][i])) continue outer;
+      }
+      out[n++] = i;
+    }
+    return out.subarray(0, n);
+  }
+
+  count(selection = null) {
+    return selection ? selection.length : this.length;
+  }
+
+  sum(column, selection = null) {
+    const values = this.columns[column];
+    let total = 0;
+    if (!selection) {
+      for (let i = 0; i < this.length; i++) total += values[i];
+    } else {
+      for (let k = 0; k < selection.length; k++) total += values[selection[k]];
+    }
+    return total;
+  }
+
+  /**
+   * Group by a dictionary column, returning counts and numeric sums per value
+   */
+  groupBy(column, selection = null, sumColumns = ['linesAdded', 'linesRemoved']) {
+    const codes = this.columns[column];
+    const dictionary = this.dictionaries[column];
+    if (!dictionary) throw new Error(`Cannot group by non-dictionary column: ${column}`);
+
+    const size = dictionary.values.length;
+    const counts = new Uint32Array(size);
+    const sums = Object.fromEntries(sumColumns.map((c) => [c, new Float64Array(size)]));
+    const rows = selection || this.all();
+
+    for (let k = 0; k < rows.length; k++) {
+      const i = rows[k];
+      const code = codes[i];
+      counts[code]++;
+      for (const c of sumColumns) sums[c][code] += this.columns[c][i];
+    }
+
+    const groups = {};
+    for (let code = 0; code < size; code++) {
+      if (counts[code] === 0) continue;
+      const group = { count: counts[code] };
+      for (const c of sumColumns) group[c] = sums[c][code];
+      groups[dictionary.decode(code)] = group;
+    }
+    return groups;
+  }
+
+  /**
+   * Bucket selected rows into fixed time windows
+   */
+  histogram(bucketMs, selection = null, sumColumn = null) {
+    const rows = selection || this.all();
+    if (rows.length === 0) return [];
+    const { timestamp } = this.columns;
+
+    let min = Infinity;
+    let max = -Infinity;
+    for (let k = 0; k < rows.length; k++) {
+      const t = timestamp[rows[k]];
+      if (t < min) min = t;
+      if (t > max) max = t;
+    }
+
+    const start = Math.floor(min / bucketMs) * bucketMs;
+    const bucketCount = Math.floor((max - start) / bucketMs) + 1;
+    const values = new Float64Array(bucketCount);
+    const sumValues = sumColumn ? this.columns[sumColumn] : null;
+
+    for (let k = 0; k < rows.length; k++) {
+      const i = rows[k];
+      const bucket = Math.floor((timestamp[i] - start) / bucketMs);
+      values[bucket] += sumValues ? sumValues[i] : 1;
+    }
+
+    return Array.from(values, (value, b) => ({ start: start + b * bucketMs, value }));
+  }
+
+  /**
+   * Materialize selected rows back into plain objects
+   */
+  rows(selection = null) {
+    const indices = selection || this.all();
+    return Array.from(indices, (i) => {
+      const row = {};
+      for (const name of Object.keys(this.numericColumns)) row[name] = this.columns[name][i];
+      for (const name of this.dictionaryColumns) {
+        row[name] = this.dictionaries[name].decode(this.columns[name][i]);
+      }
+      return row;
+    });
+  }
+}
+
+module.exports = {
+  EventColumns,
+  Dictionary,
+  defaultRowMapper,
+};
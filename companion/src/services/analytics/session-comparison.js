@@ -0,0 +1,232 @@
+/**
+ * Session Comparison
+ * Profiles two sessions (or two participants' pooled sessions) on the same
+ * axes - files touched, edit sizes, AI reliance, prompt-to-edit latency and
+ * pacing - and reports aligned metrics plus the differences worth a look,
+ * for between-participant analysis.
+ */
+
+const { alignTraces, addedLines } = require('../alignment/trace-alignment');
+const { toMillis } = require('../../utils/timestamps');
+const { parseDetails } = require('../../utils/event-details');
+
+const DEFAULT_OPTIONS = {
+  relativeThreshold: 0.5, // Flag scalar metrics differing by 50%+ of the larger value
+  overlapThreshold: 0.2, // Flag file overlap below 20%
+  alignment: {},
+};
+
+const CODE_CHANGE_TYPES = new Set(['code_change', 'file_change', 'entry_created', 'edit']);
+const AI_SOURCES = new Set(['ai', 'composer', 'agent', 'ai-generated', 'copilot', 'tab']);
+
+function countLines(text) {
+  return text ? String(text).split('\n').length : 0;
+}
+
+/**
+ * Flatten entries and code-change events into one edit list
+ */
+function collectEdits(session) {
+  const edits = [];
+
+  for (const entry of session.entries || []) {
+    const added = addedLines(entry.before_code, entry.after_code);
+    const removed = addedLines(entry.after_code, entry.before_code);
+    edits.push({
+      id: `entry:${entry.id}`,
+      timestamp: entry.timestamp,
+      time: toMillis(entry.timestamp),
+      file_path: entry.file_path || null,
+      workspace_path: entry.workspace_path || null,
+      before_code: entry.before_code,
+      after_code: entry.after_code,
+      size: (added ? countLines(added) : 0) + (removed ? countLines(removed) : 0),
+      ai: Boolean(entry.prompt_id) || AI_SOURCES.has(String(entry.source || '').toLowerCase()),
+    });
+  }
+
+  for (const event of session.events || []) {
+    if (!CODE_CHANGE_TYPES.has(event.type)) continue;
+    const details = parseDetails(event);
+    edits.push({
+      id: `event:${event.id}`,
+      timestamp: event.timestamp,
+      time: toMillis(event.timestamp),
+      file_path: details.file_path || details.file || null,
+      workspace_path: event.workspace_path || null,
+      details,
+      size: (details.lines_added || 0) + (details.lines_removed || 0),
+      ai: Boolean(event.ai_generated) || details.ai_generated === true,
+    });
+  }
+
+  return edits.filter((e) => e.time !== null).sort((a, b) => a.time - b.time);
+}
+
+function quantile(sorted, q) {
+  if (sorted.length === 0) return 0;
+  const pos = (sorted.length - 1) * q;
+  const lower = Math.floor(pos);
+  const upper = Math.ceil(pos);
+  return sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower);
+}
+
+function distribution(values) {
+  const sorted = values.filter((v) => Number.isFinite(v)).sort((a, b) => a - b);
+  const count = sorted.length;
+  return {
+    count,
+    mean: count > 0 ? sorted.reduce((sum, v) => sum + v, 0) / count : 0,
+    median: quantile(sorted, 0.5),
+    p90: quantile(sorted, 0.9),
+    max: count > 0 ? sorted[count - 1] : 0,
+  };
+}
+
+const SIZE_BUCKETS = [
+  ['1-5', 5],
+  ['6-20', 20],
+  ['21-100', 100],
+  ['100+', Infinity],
+];
+
+function sizeBuckets(sizes) {
+  const buckets = Object.fromEntries(SIZE_BUCKETS.map(([label]) => [label, 0]));
+  for (const size of sizes) {
+    const [label] = SIZE_BUCKETS.find(([, max]) => size <= max);
+    buckets[label]++;
+  }
+  const total = sizes.length || 1;
+  return Object.fromEntries(Object.entries(buckets).map(([label, n]) => [label, n / total]));
+}
+
+/**
+ * Build the comparable profile of one session
+ */
+function profileSession(session, options) {
+  const edits = collectEdits(session);
+  const prompts = (session.prompts || []).filter((p) => toMillis(p.timestamp) !== null);
+  const times = [...edits.map((e) => e.time), ...prompts.map((p) => toMillis(p.timestamp))].sort(
+    (a, b) => a - b
+  );
+  const spanMs = times.length > 1 ? times[times.length - 1] - times[0] : 0;
+  const hours = spanMs / 3600000;
+
+  const alignment = alignTraces(prompts, edits, options.alignment);
+  const aiEdits = edits.filter((e) => e.ai || alignment.editToPrompt[e.id] !== undefined);
+  const gaps = edits.slice(1).map((e, i) => e.time - edits[i].time);
+  const sizes = edits.map((e) => e.size);
+
+  return {
+    files: new Set(edits.map((e) => e.file_path).filter(Boolean)),
+    metrics: {
+      edits: edits.length,
+      prompts: prompts.length,
+      spanMinutes: spanMs / 60000,
+      editsPerHour: hours > 0 ? edits.length / hours : 0,
+      promptsPerHour: hours > 0 ? prompts.length / hours : 0,
+      aiEditShare: edits.length > 0 ? aiEdits.length / edits.length : 0,
+      aiLineShare:
+        sizes.length > 0
+          ? aiEdits.reduce((sum, e) => sum + e.size, 0) / (sizes.reduce((sum, s) => sum + s, 0) || 1)
+          : 0,
+      promptCoverage: prompts.length > 0 ? 1 - alignment.unmatchedPrompts.length / prompts.length : 0,
+    },
+    editSize: { ...distribution(sizes), buckets: sizeBuckets(sizes) },
+    latency: {
+      promptToEditMs: distribution(alignment.alignments.map((a) => Math.max(0, a.lagMs))),
+      interEditMs: distribution(gaps),
+    },
+  };
+}
+
+function format(value) {
+  return Number.isInteger(value) ? String(value) : value.toFixed(2);
+}
+
+function relativeDifference(a, b) {
+  const scale = Math.max(Math.abs(a), Math.abs(b));
+  return scale > 0 ? (b - a) / scale : 0;
+}
+
+function compareValue(a, b) {
+  return { a, b, delta: b - a, relative: relativeDifference(a, b) };
+}
+
+function compareScalars(a, b) {
+  const result = {};
+  for (const key of Object.keys(a)) result[key] = compareValue(a[key], b[key]);
+  return result;
+}
+
+/**
+ * Compare two sessions on aligned metrics
+ * @param {object} a - { events, entries, prompts } (or several sessions' records pooled)
+ * @param {object} b - Same shape as a
+ * @param {object} options - { relativeThreshold, overlapThreshold, alignment }
+ * @returns {object} { files, metrics, editSize, latency, differences }
+ */
+function compareSessions(a, b, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const profileA = profileSession(a || {}, opts);
+  const profileB = profileSession(b || {}, opts);
+
+  const shared = [...profileA.files].filter((f) => profileB.files.has(f));
+  const unionSize = new Set([...profileA.files, ...profileB.files]).size;
+  const files = {
+    a: profileA.files.size,
+    b: profileB.files.size,
+    shared,
+    onlyA: [...profileA.files].filter((f) => !profileB.files.has(f)),
+    onlyB: [...profileB.files].filter((f) => !profileA.files.has(f)),
+    jaccard: unionSize > 0 ? shared.length / unionSize : 0,
+  };
+
+  const metrics = compareScalars(profileA.metrics, profileB.metrics);
+  const editSize = {
+    a: profileA.editSize,
+    b: profileB.editSize,
+    median: compareValue(profileA.editSize.median, profileB.editSize.median),
+  };
+  const latency = {
+    a: profileA.latency,
+    b: profileB.latency,
+    promptToEditMedian: compareValue(profileA.latency.promptToEditMs.median, profileB.latency.promptToEditMs.median),
+  };
+
+  const differences = [];
+  const note = (metric, comparison, label) => {
+    if (Math.abs(comparison.relative) >= opts.relativeThreshold) {
+      differences.push({
+        metric,
+        a: comparison.a,
+        b: comparison.b,
+        relative: comparison.relative,
+        description: `${label} is ${comparison.relative > 0 ? 'higher' : 'lower'} in B (${format(comparison.a)} vs ${format(comparison.b)})`,
+      });
+    }
+  };
+
+  note('aiEditShare', metrics.aiEditShare, 'AI-attributed edit share');
+  note('editsPerHour', metrics.editsPerHour, 'Edit rate');
+  note('promptsPerHour', metrics.promptsPerHour, 'Prompt rate');
+  note('editSize.median', editSize.median, 'Median edit size');
+  note('latency.promptToEditMedian', latency.promptToEditMedian, 'Median prompt-to-edit latency');
+  if (unionSize > 0 && files.jaccard < opts.overlapThreshold) {
+    differences.push({
+      metric: 'files.jaccard',
+      a: files.a,
+      b: files.b,
+      relative: 1 - files.jaccard,
+      description: `Little file overlap (${shared.length} of ${unionSize} files shared)`,
+    });
+  }
+  differences.sort((x, y) => Math.abs(y.relative) - Math.abs(x.relative));
+
+  return { files, metrics, editSize, latency, differences };
+}
+
+module.exports = {
+  compareSessions,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,220 @@
+/**
+ * Session Quality Scoring
+ * Combines capture completeness, attribution coverage, redaction status and
+ * gap ratio into a single weighted score so low-quality sessions can be
+ * filtered out before analysis.
+ */
+
+const { toMillis } = require('../../utils/timestamps');
+const { parseDetails } = require('../../utils/event-details');
+
+const DEFAULT_WEIGHTS = {
+  completeness: 0.35,
+  attribution: 0.25,
+  redaction: 0.2,
+  gaps: 0.2,
+};
+
+const DEFAULT_OPTIONS = {
+  maxGapMs: 5 * 60 * 1000, // Gaps longer than 5 minutes count against the session
+  minAttributionConfidence: 0.5,
+  weights: DEFAULT_WEIGHTS,
+};
+
+const CODE_CHANGE_TYPES = new Set(['code_change', 'file_change', 'entry_created', 'edit']);
+
+/**
+ * Collect every capture record (events, entries, prompts) with a normalized timestamp
+ */
+function collectRecords(session) {
+  const records = [];
+  const push = (kind, item) => {
+    records.push({ kind, item, time: toMillis(item.timestamp), details: parseDetails(item) });
+  };
+
+  (session.events || []).forEach((e) => push('event', e));
+  (session.entries || []).forEach((e) => push('entry', e));
+  (session.prompts || []).forEach((p) => push('prompt', p));
+
+  return records;
+}
+
+function isCodeChange(record) {
+  if (record.kind === 'entry') return true;
+  if (record.kind !== 'event') return false;
+  return CODE_CHANGE_TYPES.has(record.item.type);
+}
+
+/**
+ * Fraction of records that carry everything downstream representations need
+ */
+function scoreCompleteness(records) {
+  if (records.length === 0) {
+    return { value: 0, detail: { total: 0, complete: 0, missingTimestamp: 0, missingContent: 0 } };
+  }
+
+  let complete = 0;
+  let missingTimestamp = 0;
+  let missingContent = 0;
+
+  for (const record of records) {
+    const hasTime = record.time !== null;
+    let hasContent = true;
+
+    if (record.kind === 'entry') {
+      hasContent = Boolean(record.item.file_path) && record.item.after_code !== undefined;
+    } else if (record.kind === 'prompt') {
+      hasContent = Boolean(record.item.text);
+    } else if (isCodeChange(record)) {
+      hasContent =
+        Boolean(record.details.file_path || record.details.file) &&
+        (record.details.after_content !== undefined || record.details.diff !== undefined);
+    }
+
+    if (!hasTime) missingTimestamp++;
+    if (!hasContent) missingContent++;
+    if (hasTime && hasContent) complete++;
+  }
+
+  return {
+    value: complete / records.length,
+    detail: { total: records.length, complete, missingTimestamp, missingContent },
+  };
+}
+
+/**
+ * Fraction of code changes with an attribution label above the confidence floor
+ */
+function scoreAttribution(records, minConfidence) {
+  const changes = records.filter(isCodeChange);
+  if (changes.length === 0) {
+    return { value: 1, detail: { codeChanges: 0, attributed: 0 } };
+  }
+
+  let attributed = 0;
+  for (const record of changes) {
+    const attribution = record.item.attribution || record.details.attribution;
+    if (attribution && typeof attribution === 'object') {
+      if ((attribution.confidence ?? 1) >= minConfidence) attributed++;
+    } else if (typeof attribution === 'string') {
+      attributed++;
+    } else if (record.item.ai_generated !== undefined || record.item.prompt_id) {
+      // Legacy rows: explicit ai_generated flag or a linked prompt counts as attributed
+      attributed++;
+    }
+  }
+
+  return {
+    value: attributed / changes.length,
+    detail: { codeChanges: changes.length, attributed },
+  };
+}
+
+/**
+ * Fraction of content-bearing records that went through PII redaction
+ */
+function scoreRedaction(session, records) {
+  if (session.redacted === true) {
+    return { value: 1, detail: { sessionRedacted: true, redacted: records.length, total: records.length } };
+  }
+
+  const contentRecords = records.filter((r) => r.kind !== 'event' || isCodeChange(r));
+  if (contentRecords.length === 0) {
+    return { value: 1, detail: { sessionRedacted: false, redacted: 0, total: 0 } };
+  }
+
+  const redacted = contentRecords.filter(
+    (r) => r.item.redacted === true || r.details.redacted === true || Boolean(r.details.redaction)
+  ).length;
+
+  return {
+    value: redacted / contentRecords.length,
+    detail: { sessionRedacted: false, redacted, total: contentRecords.length },
+  };
+}
+
+/**
+ * Share of the session's wall-clock span spent in gaps longer than maxGapMs
+ */
+function scoreGaps(session, records, maxGapMs) {
+  const times = records
+    .map((r) => r.time)
+    .filter((t) => t !== null)
+    .sort((a, b) => a - b);
+
+  const start = toMillis(session.start_time ?? session.startTime) ?? times[0];
+  const end = toMillis(session.end_time ?? session.endTime) ?? times[times.length - 1];
+
+  if (times.length === 0 || start === undefined || end === undefined || end <= start) {
+    return { value: times.length > 0 ? 1 : 0, detail: { spanMs: 0, gapMs: 0, gapRatio: 0, gaps: 0 } };
+  }
+
+  const points = [start, ...times.filter((t) => t >= start && t <= end), end];
+  let gapMs = 0;
+  let gaps = 0;
+  for (let i = 1; i < points.length; i++) {
+    const delta = points[i] - points[i - 1];
+    if (delta > maxGapMs) {
+      gapMs += delta;
+      gaps++;
+    }
+  }
+
+  const spanMs = end - start;
+  const gapRatio = Math.min(1, gapMs / spanMs);
+  return { value: 1 - gapRatio, detail: { spanMs, gapMs, gapRatio, gaps } };
+}
+
+/**
+ * Score a session's data quality
+ * @param {object} session - { events, entries, prompts, start_time?, end_time?, redacted? }
+ * @param {object} options - { weights, maxGapMs, minAttributionConfidence }
+ * @returns {object} { score, components, flags }
+ */
+function scoreSessionQuality(session, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const weights = { ...DEFAULT_WEIGHTS, ...(options.weights || {}) };
+  const records = collectRecords(session || {});
+
+  const results = {
+    completeness: scoreCompleteness(records),
+    attribution: scoreAttribution(records, opts.minAttributionConfidence),
+    redaction: scoreRedaction(session || {}, records),
+    gaps: scoreGaps(session || {}, records, opts.maxGapMs),
+  };
+
+  const totalWeight = Object.values(weights).reduce((sum, w) => sum + w, 0) || 1;
+  const components = {};
+  let score = 0;
+
+  for (const [name, result] of Object.entries(results)) {
+    const weight = (weights[name] || 0) / totalWeight;
+    const contribution = result.value * weight;
+    score += contribution;
+    components[name] = { value: result.value, weight, contribution, detail: result.detail };
+  }
+
+  const flags = [];
+  if (records.length === 0) flags.push('empty_session');
+  if (results.completeness.value < 0.8) flags.push('incomplete_capture');
+  if (results.attribution.value < 0.5) flags.push('low_attribution_coverage');
+  if (results.redaction.value < 1) flags.push('unredacted_content');
+  if (results.gaps.detail.gapRatio > 0.5) flags.push('mostly_gaps');
+
+  return { score, components, flags };
+}
+
+/**
+ * Keep sessions at or above a minimum quality score
+ */
+function filterSessionsByQuality(sessions, minScore = 0.6, options = {}) {
+  return sessions
+    .map((session) => ({ session, quality: scoreSessionQuality(session, options) }))
+    .filter(({ quality }) => quality.score >= minScore);
+}
+
+module.exports = {
+  scoreSessionQuality,
+  filterSessionsByQuality,
+  DEFAULT_WEIGHTS,
+};
@@ -0,0 +1,176 @@
+/**
+ * Timeline Aggregation
+ * Buckets edit events into fixed time windows, totalled overall and per
+ * file/language, with lines added/removed, AI-vs-manual counts and an
+ * activity intensity per bucket. Runs over the columnar event store so a
+ * month of events aggregates in one typed-array pass.
+ *
+ * Series are dense arrays aligned to bucket index; only the topN files or
+ * languages by churn get their own series, the rest are folded into "other".
+ */
+
+const { EventColumns } = require('./event-columns');
+
+const DEFAULT_OPTIONS = {
+  topN: 20,
+  groupBy: ['file', 'language'],
+  maxBuckets: 5000,
+};
+
+const UNIT_MS = { s: 1000, m: 60000, h: 3600000, d: 86400000, w: 604800000 };
+const OTHER = '(other)';
+
+/**
+ * Parse bucket sizes like 60000, '30s', '5m', '1h', '1d'
+ */
+function parseBucketSize(bucketSize) {
+  if (typeof bucketSize === 'number' && bucketSize > 0) return bucketSize;
+  const match = String(bucketSize || '').match(/^(\d+(?:\.\d+)?)\s*([smhdw])$/i);
+  if (!match) throw new Error(`Invalid bucket size: ${bucketSize}`);
+  return Math.round(Number(match[1]) * UNIT_MS[match[2].toLowerCase()]);
+}
+
+function emptySeries(bucketCount) {
+  return {
+    edits: new Uint32Array(bucketCount),
+    linesAdded: new Float64Array(bucketCount),
+    linesRemoved: new Float64Array(bucketCount),
+    ai: new Uint32Array(bucketCount),
+    manual: new Uint32Array(bucketCount),
+  };
+}
+
+function seriesToJSON(series) {
+  return Object.fromEntries(Object.entries(series).map(([key, values]) => [key, Array.from(values)]));
+}
+
+/**
+ * Pick the topN dictionary codes by churn; everything else maps to "other"
+ */
+function rankCodes(store, column, rows, topN) {
+  const codes = store.columns[column];
+  const { linesAdded, linesRemoved } = store.columns;
+  const churn = new Float64Array(store.dictionaries[column].values.length);
+  for (let k = 0; k < rows.length; k++) {
+    const i = rows[k];
+    churn[codes[i]] += linesAdded[i] + linesRemoved[i] + 1;
+  }
+
+  const ranked = Array.from(churn.keys())
+    .filter((code) => churn[code] > 0 && store.dictionaries[column].decode(code) !== '')
+    .sort((a, b) => churn[b] - churn[a]);
+  const kept = new Map(ranked.slice(0, topN).map((code, index) => [code, index]));
+  return { kept, hasOther: ranked.length > topN || churn[store.dictionaries[column].lookup('')] > 0 };
+}
+
+/**
+ * Aggregate events into a bucketed timeline
+ * @param {Array|EventColumns} events - Raw events/entries or a prebuilt columnar store
+ * @param {number|string} bucketSize - Bucket width in ms or as '5m', '1h', '1d'
+ * @param {object} options - { since, until, types, sessions, topN, groupBy, maxBuckets }
+ * @returns {object} { bucketMs, start, bucketCount, buckets, totals, byFile, byLanguage }
+ */
+function buildTimeline(events, bucketSize = '1h', options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  let bucketMs = parseBucketSize(bucketSize);
+  const store = events instanceof EventColumns ? events : EventColumns.fromEvents(events || []);
+  const rows = store.filter({
+    since: opts.since,
+    until: opts.until,
+    types: opts.types,
+    sessions: opts.sessions,
+  });
+
+  const { timestamp, linesAdded, linesRemoved, aiGenerated } = store.columns;
+  if (rows.length === 0) {
+    return {
+      bucketMs,
+      start: null,
+      bucketCount: 0,
+      buckets: [],
+      totals: seriesToJSON(emptySeries(0)),
+      intensity: [],
+      activeBuckets: 0,
+    };
+  }
+
+  let min = Infinity;
+  let max = -Infinity;
+  for (let k = 0; k < rows.length; k++) {
+    const t = timestamp[rows[k]];
+    if (t < min) min = t;
+    if (t > max) max = t;
+  }
+
+  // Widen buckets rather than returning an unrenderable number of them
+  while ((max - Math.floor(min / bucketMs) * bucketMs) / bucketMs + 1 > opts.maxBuckets) bucketMs *= 2;
+  const start = Math.floor(min / bucketMs) * bucketMs;
+  const bucketCount = Math.floor((max - start) / bucketMs) + 1;
+
+  const totals = emptySeries(bucketCount);
+  const groupings = {};
+  for (const column of opts.groupBy || []) {
+    const { kept, hasOther } = rankCodes(store, column, rows, opts.topN);
+    groupings[column] = {
+      kept,
+      series: Array.from({ length: kept.size }, () => emptySeries(bucketCount)),
+      other: hasOther ? emptySeries(bucketCount) : null,
+    };
+  }
+
+  const add = (series, b, i) => {
+    series.edits[b]++;
+    series.linesAdded[b] += linesAdded[i];
+    series.linesRemoved[b] += linesRemoved[i];
+    if (aiGenerated[i]) series.ai[b]++;
+    else series.manual[b]++;
+  };
+
+  for (let k = 0; k < rows.length; k++) {
+    const i = rows[k];
+    const b = Math.floor((timestamp[i] - start) / bucketMs);
+    add(totals, b, i);
+    for (const column in groupings) {
+      const grouping = groupings[column];
+      const index = grouping.kept.get(store.columns[column][i]);
+      if (index !== undefined) add(grouping.series[index], b, i);
+      else if (grouping.other) add(grouping.other, b, i);
+    }
+  }
+
+  // Intensity: churn per bucket relative to the busiest bucket
+  let peak = 0;
+  const churn = new Float64Array(bucketCount);
+  for (let b = 0; b < bucketCount; b++) {
+    churn[b] = totals.linesAdded[b] + totals.linesRemoved[b] + totals.edits[b];
+    if (churn[b] > peak) peak = churn[b];
+  }
+  const intensity = Array.from(churn, (value) => (peak > 0 ? Math.round((value / peak) * 1000) / 1000 : 0));
+
+  const result = {
+    bucketMs,
+    start,
+    bucketCount,
+    buckets: Array.from({ length: bucketCount }, (_, b) => start + b * bucketMs),
+    totals: seriesToJSON(totals),
+    intensity,
+    activeBuckets: Array.from(totals.edits).filter((n) => n > 0).length,
+  };
+
+  for (const column in groupings) {
+    const grouping = groupings[column];
+    const named = {};
+    for (const [code, index] of grouping.kept) {
+      named[store.dictionaries[column].decode(code)] = seriesToJSON(grouping.series[index]);
+    }
+    if (grouping.other) named[OTHER] = seriesToJSON(grouping.other);
+    result[column === 'file' ? 'byFile' : column === 'language' ? 'byLanguage' : `by_${column}`] = named;
+  }
+
+  return result;
+}
+
+module.exports = {
+  buildTimeline,
+  parseBucketSize,
+};
@@ -0,0 +1,243 @@
+/**
+ * Asset Cache
+ * Downloads, verifies and caches optional heavy assets (tokenizer vocabularies,
+ * tree-sitter grammars compiled to WASM, ONNX models) on demand, so the base
+ * install stays small.
+ *
+ * Every asset is verified against a pinned SHA-256. Assets registered without a
+ * pin are only accepted with `allowUnpinned`, in which case the first download's
+ * digest is recorded and enforced from then on (trust on first use).
+ */
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const crypto = require('crypto');
+
+const DEFAULT_CACHE_DIR = path.join(os.homedir(), '.cursor-telemetry', 'assets');
+const MANIFEST_FILE = 'manifest.json';
+const DEFAULT_TIMEOUT_MS = 60000;
+
+/**
+ * Built-in registry. Features that need grammars or models register them
+ * with registerAsset() before calling ensureAssets().
+ */
+const REGISTRY = {
+  'tiktoken/cl100k_base': {
+    kind: 'tokenizer-vocab',
+    version: '1',
+    url: 'https://openaipublic.blob.core.windows.net/encodings/cl100k_base.tiktoken',
+    filename: 'cl100k_base.tiktoken',
+    sha256: '223921b76ee99bde995b7ff738513eef100fb51d18c93597a113bcffe865b2a7',
+  },
+  'tiktoken/o200k_base': {
+    kind: 'tokenizer-vocab',
+    version: '1',
+    url: 'https://openaipublic.blob.core.windows.net/encodings/o200k_base.tiktoken',
+    filename: 'o200k_base.tiktoken',
+    sha256: '446a9538cb6c348e3516120d7c08b09f57c36495e2acfffe59a5bf8b0cfb1a2d',
+  },
+};
+
+/**
+ * Register (or override) an asset definition
+ * @param {string} name - Component name, e.g. 'grammar/python'
+ * @param {object} definition - { url, filename, sha256, version, kind }
+ */
+function registerAsset(name, definition) {
+  if (!definition || !definition.url) throw new Error(`Asset ${name} needs a url`);
+  REGISTRY[name] = {
+    kind: 'generic',
+    version: '1',
+    filename: path.basename(new URL(definition.url).pathname),
+    sha256: null,
+    ...definition,
+  };
+}
+
+function readManifest(cacheDir) {
+  try {
+    return JSON.parse(fs.readFileSync(path.join(cacheDir, MANIFEST_FILE), 'utf8'));
+  } catch {
+    return { assets: {} };
+  }
+}
+
+function writeManifest(cacheDir, manifest) {
+  const target = path.join(cacheDir, MANIFEST_FILE);
+  const tmp = `${target}.${process.pid}.tmp`;
+  fs.writeFileSync(tmp, JSON.stringify(manifest, null, 2));
+  fs.renameSync(tmp, target);
+}
+
+function assetPath(cacheDir, name, definition) {
+  return path.join(cacheDir, name.replace(/[^\w.-]+/g, '_'), definition.version, definition.filename);
+}
+
+function sha256File(filePath) {
+  return new Promise((resolve, reject) => {
+    const hash = crypto.createHash('sha256');
+    fs.createReadStream(filePath)
+      .on('data', (chunk) => hash.update(chunk))
+      .on('error', reject)
+      .on('end', () => resolve(hash.digest('hex')));
+  });
+}
+
+/**
+ * Stream a URL into `${destination}.<pid>.part`, which is removed again if the download fails
+ */
+async function download(url, destination, options) {
+  const fetch = (await import('node-fetch')).default;
+  const controller = new AbortController();
+  const timer = setTimeout(() => controller.abort(), options.timeoutMs || DEFAULT_TIMEOUT_MS);
+  const tmp = `${destination}.${process.pid}.part`;
+  let out = null;
+
+  try {
+    const response = await fetch(url, { signal: controller.signal });
+    if (!response.ok) throw new Error(`HTTP ${response.status} fetching ${url}`);
+
+    const total = Number(response.headers.get('content-length')) || null;
+    const hash = crypto.createHash('sha256');
+    out = fs.createWriteStream(tmp);
+    let received = 0;
+
+    for await (const chunk of response.body) {
+      hash.update(chunk);
+      received += chunk.length;
+      if (!out.write(chunk)) await new Promise((resolve) => out.once('drain', resolve));
+      if (options.onProgress) options.onProgress({ url, received, total });
+    }
+    await new Promise((resolve, reject) => {
+      out.on('error', reject);
+      out.end(resolve);
+    });
+
+    return { tmp, sha256: hash.digest('hex'), bytes: received };
+  } catch (error) {
+    if (out) {
+      out.destroy();
+      await fs.promises.unlink(tmp).catch(() => {});
+    }
+    throw error;
+  } finally {
+    clearTimeout(timer);
+  }
+}
+
+/**
+ * Make sure a single asset is present and verified
+ */
+async function ensureAsset(name, cacheDir, manifest, options) {
+  const definition = REGISTRY[name];
+  if (!definition) return { name, status: 'unknown', error: `No asset registered as ${name}` };
+
+  const target = assetPath(cacheDir, name, definition);
+  const record = manifest.assets[name];
+  const expected = definition.sha256 || (record && record.version === definition.version ? record.sha256 : null);
+
+  // Already cached: verify before trusting it
+  if (fs.existsSync(target) && expected) {
+    const actual = options.verifyCached === false ? expected : await sha256File(target);
+    if (actual === expected) return { name, status: 'cached', path: target, sha256: actual };
+    console.warn(`[ASSETS] Cached ${name} failed verification, re-downloading`);
+  }
+
+  const fallback = () => {
+    // Offline fallback: any previously verified copy, even of an older version
+    if (record && record.path && fs.existsSync(record.path)) {
+      return { name, status: 'offline-cached', path: record.path, sha256: record.sha256, stale: true };
+    }
+    return null;
+  };
+
+  if (!expected && !options.allowUnpinned) {
+    return (
+      fallback() || {
+        name,
+        status: 'unverifiable',
+        error: `Asset ${name} has no pinned checksum (pass allowUnpinned)`,
+      }
+    );
+  }
+
+  if (options.offline) {
+    return fallback() || { name, status: 'unavailable', error: 'Offline and not cached' };
+  }
+
+  let tmp = null;
+  try {
+    await fs.promises.mkdir(path.dirname(target), { recursive: true });
+    const result = await download(definition.url, target, options);
+    tmp = result.tmp;
+
+    if (expected && result.sha256 !== expected) {
+      throw new Error(`Checksum mismatch for ${name}: expected ${expected}, got ${result.sha256}`);
+    }
+
+    await fs.promises.rename(result.tmp, target);
+    tmp = null;
+    manifest.assets[name] = {
+      version: definition.version,
+      path: target,
+      sha256: result.sha256,
+      bytes: result.bytes,
+      pinned: Boolean(definition.sha256),
+      downloadedAt: new Date().toISOString(),
+    };
+    return { name, status: 'downloaded', path: target, sha256: result.sha256 };
+  } catch (error) {
+    if (tmp) await fs.promises.unlink(tmp).catch(() => {});
+    const cached = fallback();
+    if (cached) return { ...cached, error: error.message };
+    return { name, status: 'unavailable', error: error.message };
+  }
+}
+
+/**
+ * Ensure a set of optional components is available locally
+ * @param {string[]} components - Registered asset names
+ * @param {string} cacheDir - Cache directory (defaults to ~/.cursor-telemetry/assets)
+ * @param {object} options - { offline, allowUnpinned, timeoutMs, verifyCached, onProgress }
+ * @returns {Promise<object>} Map of component name to { status, path, sha256, error }
+ */
+async function ensureAssets(components, cacheDir = DEFAULT_CACHE_DIR, options = {}) {
+  await fs.promises.mkdir(cacheDir, { recursive: true });
+  const manifest = readManifest(cacheDir);
+  const results = {};
+
+  for (const name of components) {
+    results[name] = await ensureAsset(name, cacheDir, manifest, options);
+  }
+
+  writeManifest(cacheDir, manifest);
+  return results;
+}
+
+/**
+ * Resolve the local path of an asset without touching the network
+ */
+function getAssetPath(name, cacheDir = DEFAULT_CACHE_DIR) {
+  const record = readManifest(cacheDir).assets[name];
+  return record && fs.existsSync(record.path) ? record.path : null;
+}
+
+function listAssets(cacheDir = DEFAULT_CACHE_DIR) {
+  const manifest = readManifest(cacheDir);
+  return Object.entries(REGISTRY).map(([name, definition]) => ({
+    name,
+    kind: definition.kind,
+    version: definition.version,
+    pinned: Boolean(definition.sha256),
+    installed: Boolean(manifest.assets[name] && fs.existsSync(manifest.assets[name].path)),
+  }));
+}
+
+module.exports = {
+  ensureAssets,
+  registerAsset,
+  getAssetPath,
+  listAssets,
+  DEFAULT_CACHE_DIR,
+};
@@ -0,0 +1,435 @@
+/**
+ * Backfill Engine
+ * Re-runs analyzers (attribution, complexity, alignment scores, ...) over
+ * already-captured history so metric improvements apply retroactively.
+ *
+ * The requested range is covered by chunkMs-aligned time windows ("units"),
+ * so the same unit boundaries come out whatever range is asked for. Each unit
+ * loads the entries, prompts and events in its window and hands them to every
+ * analyzer; results are upserted into analysis_results and each finished
+ * (analyzer, version, unit) is checkpointed, so an interrupted run resumes
+ * where it stopped and bumping an analyzer's version recomputes everything.
+ * A unit with more than maxRowsPerUnit rows in a table is split in halves
+ * until each part fits; a unit still in progress (ending in the future) is
+ * analyzed but not checkpointed.
+ *
+ * Analyzers are { name, version, analyze(unit) } objects, or
+ * { name, version, module } to run analyze() from that module on a worker
+ * thread. analyze() returns [{ targetType, targetId, value }].
+ */
+
+const os = require('os');
+const { createWorker, resolveConcurrency } = require('../../utils/thread-pool');
+const { toMillis } = require('../../utils/timestamps');
+
+const DEFAULT_OPTIONS = {
+  chunkMs: 24 * 60 * 60 * 1000,
+  concurrency: Math.max(1, os.cpus().length - 1),
+  force: false,
+  maxRowsPerUnit: 100000, // Larger units are split, never cut short
+};
+
+const SCHEMA = [
+  `CREATE TABLE IF NOT EXISTS analysis_results (
+    analyzer TEXT NOT NULL,
+    version TEXT NOT NULL,
+    target_type TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    value TEXT,
+    computed_at INTEGER NOT NULL,
+    PRIMARY KEY (analyzer, target_type, target_id)
+  )`,
+  `CREATE TABLE IF NOT EXISTS backfill_checkpoints (
+    analyzer TEXT NOT NULL,
+    version TEXT NOT NULL,
+    unit_start INTEGER NOT NULL,
+    unit_end INTEGER NOT NULL,
+    run_id TEXT,
+    results INTEGER DEFAULT 0,
+    completed_at INTEGER NOT NULL,
+    PRIMARY KEY (analyzer, version, unit_start, unit_end)
+  )`,
+  'CREATE INDEX IF NOT EXISTS idx_analysis_results_target ON analysis_results(target_type, target_id)',
+];
+
+const WORKER_SOURCE = `
+const { parentPort } = require('worker_threads');
+const cache = new Map();
+parentPort.on('message', async ({ id, module, unit }) => {
+  try {
+    if (!cache.has(module)) cache.set(module, require(module));
+    const loaded = cache.get(module);
+    const analyze = typeof loaded === 'function' ? loaded : loaded.analyze;
+    parentPort.postMessage({ id, results: await analyze(unit) });
+  } catch (error) {
+    parentPort.postMessage({ id, error: error.message });
+  }
+});
+`;
+
+function sqlHandle(db) {
+  // Accept a PersistentDB (wrapping .db) or a raw sqlite3 Database
+  return db && db.db && typeof db.db.all === 'function' ? db.db : db;
+}
+
+function all(db, sql, params = []) {
+  return new Promise((resolve, reject) => {
+    sqlHandle(db).all(sql, params, (err, rows) => (err ? reject(err) : resolve(rows)));
+  });
+}
+
+function run(db, sql, params = []) {
+  return new Promise((resolve, reject) => {
+    sqlHandle(db).run(sql, params, (err) => (err ? reject(err) : resolve()));
+  });
+}
+
+async function ensureSchema(db) {
+  if (typeof db.init === 'function') await db.init();
+  for (const statement of SCHEMA) await run(db, statement);
+}
+
+/**
+ * Find the captured time span when the range is open-ended
+ */
+async function resolveRange(db, range = {}) {
+  let since = toMillis(range.since);
+  let until = toMillis(range.until);
+  if (since !== null && until !== null) return { since, until };
+
+  const rows = await all(
+    db,
+    `SELECT MIN(t) AS first, MAX(t) AS last FROM (
+       SELECT timestamp AS t FROM entries UNION ALL
+       SELECT timestamp AS t FROM prompts UNION ALL
+       SELECT timestamp AS t FROM events
+     ) WHERE t IS NOT NULL`
+  );
+  if (since === null) since = toMillis(rows[0] && rows[0].first);
+  if (until === null) until = toMillis(rows[0] && rows[0].last);
+  return { since, until };
+}
+
+/**
+ * Aligned chunks covering [since, until]; the first and last may reach outside the range
+ */
+function planUnits(since, until, chunkMs) {
+  const units = [];
+  if (since === null || until === null || until < since) return units;
+  const first = Math.floor(since / chunkMs) * chunkMs;
+  for (let start = first; start <= until; start += chunkMs) {
+    units.push({ start, end: start + chunkMs });
+  }
+  return units;
+}
+
+function splitUnit(unit) {
+  const middle = unit.start + Math.floor((unit.end - unit.start) / 2);
+  return [
+    { start: unit.start, end: middle },
+    { start: middle, end: unit.end },
+  ];
+}
+
+/**
+ * Load everything captured within a unit's window
+ * @returns {Promise<object|null>} The unit's data, or null when a table has more than
+ *   maxRowsPerUnit rows in it
+ */
+async function loadUnit(db, unit, options) {
+  const sinceISO = new Date(unit.start).toISOString();
+  const untilISO = new Date(unit.end).toISOString();
+  const params = [sinceISO, untilISO, options.maxRowsPerUnit + 1];
+  const window = 'WHERE timestamp >= ? AND timestamp < ? ORDER BY timestamp LIMIT ?';
+
+  const [entries, prompts, events] = await Promise.all([
+    all(db, `SELECT * FROM entries ${window}`, params),
+    all(db, `SELECT * FROM prompts ${window}`, params),
+    all(db, `SELECT * FROM events ${window}`, params),
+  ]);
+  if ([entries, prompts, events].some((rows) => rows.length > options.maxRowsPerUnit)) return null;
+  return { start: unit.start, end: unit.end, entries, prompts, events };
+}
+
+class WorkerPool {
+  constructor(size) {
+    this.size = size;
+    this.workers = [];
+    this.idle = [];
+    this.queue = [];
+    this.pending = new Map();
+    this.nextId = 0;
+  }
+
+  spawn() {
+    const worker = createWorker(WORKER_SOURCE);
+    worker.on('message', ({ id, results, error }) => {
+      const task = this.pending.get(id);
+      this.pending.delete(id);
+      if (error) task.reject(new Error(error));
+      else task.resolve(results);
+      this.release(worker);
+    });
+    worker.on('error', (error) => {
+      for (const [id, task] of this.pending) {
+        if (task.worker === worker) {
+          this.pending.delete(id);
+          task.reject(error);
+        }
+      }
+      this.workers = this.workers.filter((w) => w !== worker);
+    });
+    this.workers.push(worker);
+    return worker;
+  }
+
+  release(worker) {
+    const next = this.queue.shift();
+    if (next) this.dispatch(worker, next);
+    else this.idle.push(worker);
+  }
+
+  dispatch(worker, task) {
+    task.worker = worker;
+    this.pending.set(task.id, task);
+    worker.postMessage({ id: task.id, module: task.module, unit: task.unit });
+  }
+
+  exec(module, unit) {
+    return new Promise((resolve, reject) => {
+      const task = { id: this.nextId++, module, unit, resolve, reject };
+      const worker = this.idle.pop() || (this.workers.length < this.size ? this.spawn() : null);
+      if (worker) this.dispatch(worker, task);
+      else this.queue.push(task);
+    });
+  }
+
+  async close() {
+    await Promise.all(this.workers.map((w) => w.terminate()));
+    this.workers = [];
+    this.idle = [];
+  }
+}
+
+async function saveResults(db, analyzer, results, computedAt) {
+  if (!Array.isArray(results) || results.length === 0) return 0;
+  await run(db, 'BEGIN IMMEDIATE');
+  try {
+    for (const result of results) {
+      await run(
+        db,
+        `INSERT OR REPLACE INTO analysis_results
+          (analyzer, version, target_type, target_id, value, computed_at)
+         VALUES (?, ?, ?, ?, ?, ?)`,
+        [
+          analyzer.name,
+          String(analyzer.version),
+          result.targetType,
+          String(result.targetId),
+          JSON.stringify(result.value ?? null),
+          computedAt,
+        ]
+      );
+    }
+    await run(db, 'COMMIT');
+  } catch (error) {
+    await run(db, 'ROLLBACK').catch(() => {});
+    throw error;
+  }
+  return results.length;
+}
+
+/**
+ * Re-run analyzers over historical data
+ * @param {object} db - PersistentDB instance (or raw sqlite3 Database with the same tables)
+ * @param {Array<object>} analyzers - { name, version, analyze } or { name, version, module }
+ * @param {object} range - { since, until } (open ends default to the captured span)
+ * @param {object} options - { chunkMs, concurrency, force, runId, onProgress, signal, maxRowsPerUnit }
+ * @returns {Promise<object>} Run summary
+ */
+async function backfill(db, analyzers, range = {}, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const runId = opts.runId || `backfill-${Date.now()}`;
+  const started = Date.now();
+
+  for (const analyzer of analyzers) {
+    if (!analyzer.name || analyzer.version === undefined || !(analyzer.analyze || analyzer.module)) {
+      throw new Error('Analyzers need a name, a version and an analyze function or module');
+    }
+  }
+
+  await ensureSchema(db);
+  const { since, until } = await resolveRange(db, range);
+  const units = planUnits(since, until, opts.chunkMs);
+
+  // Resume: drop (analyzer, unit) pairs already checkpointed at this version
+  const done = new Set();
+  if (!opts.force) {
+    for (const analyzer of analyzers) {
+      const rows = await all(
+        db,
+        'SELECT unit_start, unit_end FROM backfill_checkpoints WHERE analyzer = ? AND version = ?',
+        [analyzer.name, String(analyzer.version)]
+      );
+      for (const row of rows) done.add(`${analyzer.name}:${row.unit_start}:${row.unit_end}`);
+    }
+  }
+
+  const summary = {
+    runId,
+    range: { since, until },
+    units: units.length,
+    processed: 0,
+    skipped: 0,
+    splits: 0,
+    failed: [],
+    results: Object.fromEntries(analyzers.map((a) => [a.name, 0])),
+    durationMs: 0,
+    cancelled: false,
+  };
+
+  const pool = analyzers.some((a) => a.module) ? new WorkerPool(resolveConcurrency(opts.concurrency)) : null;
+  let nextUnit = 0;
+
+  // Units share one connection, so their write transactions must not interleave
+  let writeChain = Promise.resolve();
+  const serialized = (fn) => {
+    const next = writeChain.then(fn);
+    writeChain = next.catch(() => {});
+    return next;
+  };
+
+  const key = (analyzer, unit) => `${analyzer.name}:${unit.start}:${unit.end}`;
+  const checkpoint = async (analyzer, unit, saved) => {
+    // A unit that hasn't ended yet will get more data
+    if (unit.end > Date.now()) return;
+    await run(
+      db,
+      `INSERT OR REPLACE INTO backfill_checkpoints
+        (analyzer, version, unit_start, unit_end, run_id, results, completed_at)
+       VALUES (?, ?, ?, ?, ?, ?, ?)`,
+      [analyzer.name, String(analyzer.version), unit.start, unit.end, runId, saved, Date.now()]
+    );
+    done.add(key(analyzer, unit));
+  };
+
+  /**
+   * @returns {Promise<boolean>} Whether there was anything left to do
+   */
+  const processUnit = async (unit) => {
+    const pendingAnalyzers = analyzers.filter((a) => !done.has(key(a, unit)));
+    if (pendingAnalyzers.length === 0) return false;
+
+    const data = await loadUnit(db, unit, opts);
+    if (!data) {
+      if (unit.end - unit.start <= 1) {
+        const error = `More than ${opts.maxRowsPerUnit} rows in one millisecond`;
+        for (const analyzer of pendingAnalyzers) {
+          summary.failed.push({ analyzer: analyzer.name, unit, error });
+        }
+        return true;
+      }
+      summary.splits++;
+      const halves = splitUnit(unit);
+      for (const half of halves) await processUnit(half);
+      // Both halves done stands for the whole unit on the next run
+      for (const analyzer of pendingAnalyzers) {
+        if (halves.every((half) => done.has(key(analyzer, half)))) {
+          await serialized(() => checkpoint(analyzer, unit, 0));
+        }
+      }
+      return true;
+    }
+
+    for (const analyzer of pendingAnalyzers) {
+      try {
+        const results = analyzer.module
+          ? await pool.exec(analyzer.module, data)
+          : await analyzer.analyze(data);
+        const count = await serialized(async () => {
+          const saved = await saveResults(db, analyzer, results, Date.now());
+          await checkpoint(analyzer, unit, saved);
+          return saved;
+        });
+        summary.results[analyzer.name] += count;
+      } catch (error) {
+        summary.failed.push({ analyzer: analyzer.name, unit, error: error.message });
+      }
+    }
+    return true;
+  };
+
+  const worker = async () => {
+    while (nextUnit < units.length) {
+      if (opts.signal && opts.signal.aborted) {
+        summary.cancelled = true;
+        return;
+      }
+      const unit = units[nextUnit++];
+      if (await processUnit(unit)) summary.processed++;
+      else summary.skipped++;
+      if (opts.onProgress) {
+        opts.onProgress({
+          runId,
+          completed: summary.processed + summary.skipped,
+          total: units.length,
+          failed: summary.failed.length,
+        });
+      }
+    }
+  };
+
+  try {
+    await Promise.all(Array.from({ length: Math.min(opts.concurrency, units.length) }, worker));
+  } finally {
+    if (pool) await pool.close();
+  }
+
+  summary.durationMs = Date.now() - started;
+  return summary;
+}
+
+/**
+ * Read stored analyzer output
+ * @param {object} options - { targetType, targetIds }
+ */
+async function getAnalysisResults(db, analyzerName, options = {}) {
+  await ensureSchema(db);
+  const clauses = ['analyzer = ?'];
+  const params = [analyzerName];
+  if (options.targetType) {
+    clauses.push('target_type = ?');
+    params.push(options.targetType);
+  }
+  if (options.targetIds && options.targetIds.length > 0) {
+    clauses.push(`target_id IN (${options.targetIds.map(() => '?').join(', ')})`);
+    params.push(...options.targetIds.map(String));
+  }
+
+  const rows = await all(db, `SELECT * FROM analysis_results WHERE ${clauses.join(' AND ')}`, params);
+  return rows.map((row) => ({
+    analyzer: row.analyzer,
+    version: row.version,
+    targetType: row.target_type,
+    targetId: row.target_id,
+    value: JSON.parse(row.value),
+    computedAt: row.computed_at,
+  }));
+}
+
+/**
+ * Forget checkpoints so the next backfill recomputes (optionally for one analyzer)
+ */
+async function resetCheckpoints(db, analyzerName = null) {
+  await ensureSchema(db);
+  if (analyzerName) await run(db, 'DELETE FROM backfill_checkpoints WHERE analyzer = ?', [analyzerName]);
+  else await run(db, 'DELETE FROM backfill_checkpoints');
+}
+
+module.exports = {
+  backfill,
+  getAnalysisResults,
+  resetCheckpoints,
+  planUnits,
+  DEFAULT_OPTIONS,
+};
@@ -8,6 +8,7 @@ const path = require('path');
 const { exec } = require('child_process');
 const { promisify } = require('util');
 const execAsync = promisify(exec);
+const { AdaptiveRateController } = require('./scheduling/adaptive-rate-controller');
 
 class DataCaptureService {
   constructor(
@@ -16,7 +17,8 @@ class DataCaptureService {
     cursorDbParser,
     ideStateCapture,
     promptCaptureSystem,
-    statusMessageTracker
+    statusMessageTracker,
+    rateController = null
   ) {
     this.rawData = rawData;
     this.persistentDB = persistentDB;
@@ -25,6 +27,10 @@ class DataCaptureService {
     this.promptCaptureSystem = promptCaptureSystem;
     this.statusMessageTracker = statusMessageTracker;
     this.captureIntervals = {};
+    // Shared with the file watcher when passed in, so both show up in one rates view
+    this.ownsRateController = !rateController;
+    this.rateController = rateController || new AdaptiveRateController();
+    this.lastAppState = null;
     this.syncedPromptIds = new Set();
     this.syncInProgress = false;
     this.initialSyncComplete = false;
@@ -139,6 +145,17 @@ class DataCaptureService {
 
       this.rawData.appleScript.appState.push(appState);
 
+      // Focus and window-count changes speed up polling; a steady state decays to idle
+      const last = this.lastAppState;
+      if (
+        !last ||
+        last.isActive !== appState.isActive ||
+        last.windowCount !== appState.windowCount
+      ) {
+        this.rateController.recordActivity('cursor-app-state', appState.timestamp);
+      }
+      this.lastAppState = appState;
+
       // Keep only last 500 entries
       if (this.rawData.appleScript.appState.length > 500) {
         this.rawData.appleScript.appState = this.rawData.appleScript.appState.slice(-500);
@@ -414,8 +431,11 @@ class DataCaptureService {
     // Git data every 30 seconds
     this.captureIntervals.gitStatus = setInterval(() => this.captureGitData(), 30000);
 
-    // AppleScript every 2 seconds
-    this.captureIntervals.appleScript = setInterval(() => this.captureCursorAppState(), 2000);
+    // AppleScript polling adapts to activity, load and battery (2 second base)
+    if (this.ownsRateController) this.rateController.start();
+    this.rateController.schedule('cursor-app-state', () => this.captureCursorAppState(), {
+      baseIntervalMs: 2000,
+    });
 
     // Cursor database every 5 seconds
     this.captureIntervals.cursorDatabase = setInterval(() => this.captureCursorDatabase(), 5000);
@@ -439,6 +459,15 @@ class DataCaptureService {
   stop() {
     Object.values(this.captureIntervals).forEach((interval) => clearInterval(interval));
     this.captureIntervals = {};
+    if (this.ownsRateController) this.rateController.stop();
+    else this.rateController.unschedule('cursor-app-state');
+  }
+
+  /**
+   * Effective polling rates, for display
+   */
+  getEffectiveRates() {
+    return this.rateController.getEffectiveRates();
   }
 }
 
@@ -0,0 +1,251 @@
+/**
+ * Edit Replay
+ * Replays fine-grained document change events (offset, removed length,
+ * inserted text, timestamp) — one per keystroke in the editor — and
+ *  - coalesces them into logical edits: a run of adjacent changes without a
+ *    pause becomes one replace of the pre-edit document, the way an undo
+ *    stop groups typing
+ *  - reconstructs the document at any timestamp from periodic full-content
+ *    checkpoints plus the changes since, so at-time queries cost at most
+ *    checkpointInterval change applications
+ *
+ * Changes are accepted as { offset, removedLength, text, timestamp } or in
+ * VS Code's contentChanges shape ({ rangeOffset, rangeLength, text }).
+ */
+
+const { toMillis } = require('../../utils/timestamps');
+
+const DEFAULT_OPTIONS = {
+  coalesceMs: 1000, // A pause longer than this starts a new logical edit
+  maxEditMs: 30000, // Never stretch one logical edit over more than 30s
+  splitOnDirectionChange: true, // Typing followed by backspacing is two edits
+  splitOnNewline: false, // Start a new edit after each inserted line break
+  checkpointInterval: 256,
+};
+
+function normalizeChange(change) {
+  const offset = change.offset ?? change.rangeOffset;
+  const removedLength = change.removedLength ?? change.rangeLength ?? 0;
+  const text = change.text ?? change.insertedText ?? '';
+  if (!Number.isInteger(offset) || offset < 0 || !Number.isInteger(removedLength) || removedLength < 0) {
+    throw new Error(`Invalid change: offset=${offset} removedLength=${removedLength}`);
+  }
+  return { offset, removedLength, text, timestamp: toMillis(change.timestamp) };
+}
+
+function finalizeEdit(edit) {
+  let kind = 'replace';
+  if (!edit.removedText) kind = 'insert';
+  else if (!edit.insertedText) kind = 'delete';
+  return {
+    offset: edit.offset,
+    removedText: edit.removedText,
+    insertedText: edit.insertedText,
+    kind,
+    startTime: edit.startTime,
+    endTime: edit.endTime,
+    firstChange: edit.firstChange,
+    lastChange: edit.lastChange,
+    changeCount: edit.changeCount,
+  };
+}
+
+class EditReplay {
+  /**
+   * @param {string} initialContent - Document content before the first change
+   * @param {object} options - See DEFAULT_OPTIONS
+   */
+  constructor(initialContent = '', options = {}) {
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.initialContent = initialContent;
+    this.content = initialContent;
+    this.changes = [];
+    this.times = [];
+    this.checkpoints = [{ index: 0, content: initialContent }];
+    this.edits = [];
+    this.openEdit = null;
+  }
+
+  /**
+   * Apply a batch of changes
+   */
+  applyChanges(changes) {
+    for (const change of changes) this.applyChange(change);
+    return this;
+  }
+
+  /**
+   * Apply one change to the live document and fold it into the current logical edit
+   */
+  applyChange(rawChange) {
+    const change = normalizeChange(rawChange);
+    if (change.offset + change.removedLength > this.content.length) {
+      throw new Error(
+        `Change at ${change.offset}+${change.removedLength} is outside the document (${this.content.length})`
+      );
+    }
+
+    // Keep the time index monotonic even when events arrive slightly out of order
+    const last = this.times.length > 0 ? this.times[this.times.length - 1] : -Infinity;
+    let timestamp = change.timestamp === null ? last : Math.max(change.timestamp, last);
+    if (timestamp === -Infinity) timestamp = Date.now();
+
+    this.coalesce(change, timestamp);
+
+    this.content =
+      this.content.slice(0, change.offset) + change.text + this.content.slice(change.offset + change.removedLength);
+    this.changes.push(change);
+    this.times.push(timestamp);
+
+    if (this.changes.length % this.options.checkpointInterval === 0) {
+      this.checkpoints.push({ index: this.changes.length, content: this.content });
+    }
+    return this;
+  }
+
+  /**
+   * Merge a change into the open logical edit when it touches it, else start a new one.
+   * A logical edit is a single replace on the document as it was before the edit began:
+   * [offset, offset + removedText.length) became insertedText.
+   */
+  coalesce(change, timestamp) {
+    const opts = this.options;
+    const doc = this.content;
+    const open = this.openEdit;
+    const changeEnd = change.offset + change.removedLength;
+
+    let merge = false;
+    if (open) {
+      const regionEnd = open.offset + open.insertedText.length;
+      const touches = change.offset <= regionEnd && changeEnd >= open.offset;
+      const paused = timestamp - open.endTime > opts.coalesceMs;
+      const tooLong = timestamp - open.startTime > opts.maxEditMs;
+      const direction = change.text ? (change.removedLength ? 'replace' : 'insert') : 'delete';
+      const flipped =
+        opts.splitOnDirectionChange &&
+        ((open.direction === 'insert' && direction === 'delete') ||
+          (open.direction === 'delete' && direction === 'insert'));
+      const afterNewline = opts.splitOnNewline && open.insertedText.endsWith('\n');
+      merge = touches && !paused && !tooLong && !flipped && !afterNewline;
+    }
+
+    if (!merge) {
+      this.closeEdit();
+      this.openEdit = {
+        offset: change.offset,
+        removedText: doc.slice(change.offset, changeEnd),
+        insertedText: change.text,
+        startTime: timestamp,
+        endTime: timestamp,
+        firstChange: this.changes.length,
+        lastChange: this.changes.length,
+        changeCount: 1,
+        direction: change.text ? (change.removedLength ? 'replace' : 'insert') : 'delete',
+      };
+      return;
+    }
+
+    // Widen the edit's region to cover the change; text outside the inserted region
+    // is unchanged since the edit began, so it can be read from the live document
+    const regionEnd = open.offset + open.insertedText.length;
+    const left = Math.max(0, open.offset - change.offset);
+    const right = Math.max(0, changeEnd - regionEnd);
+    const start = open.offset - left;
+    const region = doc.slice(start, regionEnd + right);
+    const relative = change.offset - start;
+
+    open.removedText = doc.slice(start, open.offset) + open.removedText + doc.slice(regionEnd, regionEnd + right);
+    open.insertedText = region.slice(0, relative) + change.text + region.slice(relative + change.removedLength);
+    open.offset = start;
+    open.endTime = timestamp;
+    open.lastChange = this.changes.length;
+    open.changeCount++;
+  }
+
+  closeEdit() {
+    const open = this.openEdit;
+    this.openEdit = null;
+    // Typing a character and deleting it again leaves nothing to report
+    if (!open || open.removedText === open.insertedText) return;
+    this.edits.push(finalizeEdit(open));
+  }
+
+  /**
+   * Logical edits so far (including the one still open)
+   */
+  getEdits() {
+    if (!this.openEdit || this.openEdit.removedText === this.openEdit.insertedText) return [...this.edits];
+    return [...this.edits, finalizeEdit(this.openEdit)];
+  }
+
+  /**
+   * Number of changes with timestamp <= t
+   */
+  countChangesAt(t) {
+    let lo = 0;
+    let hi = this.times.length;
+    while (lo < hi) {
+      const mid = (lo + hi) >> 1;
+      if (this.times[mid] <= t) lo = mid + 1;
+      else hi = mid;
+    }
+    return lo;
+  }
+
+  /**
+   * Document content after applying the first n changes
+   */
+  contentAfterChanges(n) {
+    if (n >= this.changes.length) return this.content;
+    const checkpoint = this.checkpoints[Math.floor(n / this.options.checkpointInterval)];
+    let content = checkpoint.content;
+    for (let i = checkpoint.index; i < n; i++) {
+      const { offset, removedLength, text } = this.changes[i];
+      content = content.slice(0, offset) + text + content.slice(offset + removedLength);
+    }
+    return content;
+  }
+
+  /**
+   * Reconstruct the document as it was at a timestamp
+   */
+  contentAt(timestamp) {
+    const t = toMillis(timestamp);
+    if (t === null) throw new Error(`Invalid timestamp: ${timestamp}`);
+    return this.contentAfterChanges(this.countChangesAt(t));
+  }
+
+  getStats() {
+    return {
+      changes: this.changes.length,
+      edits: this.getEdits().length,
+      checkpoints: this.checkpoints.length,
+      length: this.content.length,
+      start: this.times.length > 0 ? this.times[0] : null,
+      end: this.times.length > 0 ? this.times[this.times.length - 1] : null,
+    };
+  }
+}
+
+/**
+ * Coalesce raw change events into logical edits
+ * @returns {Array<object>} [{ offset, removedText, insertedText, kind, startTime, endTime,
+ *   firstChange, lastChange, changeCount }]
+ */
+function coalesceChanges(initialContent, changes, options = {}) {
+  return new EditReplay(initialContent, options).applyChanges(changes).getEdits();
+}
+
+/**
+ * Reconstruct the document at a timestamp from its initial content and change events
+ */
+function reconstructAt(initialContent, changes, timestamp) {
+  return new EditReplay(initialContent).applyChanges(changes).contentAt(timestamp);
+}
+
+module.exports = {
+  EditReplay,
+  coalesceChanges,
+  reconstructAt,
+  DEFAULT_OPTIONS,
+};
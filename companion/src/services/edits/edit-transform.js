@@ -0,0 +1,256 @@
+/**
+ * Edit Composition and Transformation
+ * Operational-transform primitives over position-based edits so that
+ * concurrent observations of the same file (file watcher snapshots vs.
+ * editor change events) can be reconciled into one consistent history.
+ *
+ * An edit is a replace { offset, removedLength, text } or an array of
+ * non-overlapping replaces against the same base document. EditReplay's
+ * logical edits ({ offset, removedText, insertedText }) are accepted too.
+ *
+ *  - composeEdits(a, b): one edit equivalent to applying a, then b
+ *  - transformEdits(a, b): [a', b'] for two edits made against the same
+ *    base, such that apply(apply(base, a), b') === apply(apply(base, b), a')
+ *
+ * Internally edits are op sequences: retain (positive number), insert
+ * (string) and delete (negative number), with an implicit retain to the end
+ * of the document so callers never need to know its length.
+ */
+
+function normalizeReplace(replace) {
+  const offset = replace.offset ?? replace.rangeOffset;
+  const removedLength =
+    replace.removedLength ?? replace.rangeLength ?? (replace.removedText ? replace.removedText.length : 0);
+  const text = replace.text ?? replace.insertedText ?? '';
+  if (!Number.isInteger(offset) || offset < 0 || !Number.isInteger(removedLength) || removedLength < 0) {
+    throw new Error(`Invalid edit: offset=${offset} removedLength=${removedLength}`);
+  }
+  return { offset, removedLength, text };
+}
+
+/**
+ * Convert replaces into an op sequence
+ */
+function toOps(edit) {
+  const replaces = (Array.isArray(edit) ? edit : [edit])
+    .map(normalizeReplace)
+    .sort((x, y) => x.offset - y.offset);
+  const ops = [];
+  let position = 0;
+  for (const { offset, removedLength, text } of replaces) {
+    if (offset < position) throw new Error(`Overlapping replaces at offset ${offset}`);
+    pushOp(ops, offset - position);
+    pushOp(ops, text);
+    pushOp(ops, -removedLength);
+    position = offset + removedLength;
+  }
+  return ops;
+}
+
+/**
+ * Append an op, merging with the previous one of the same kind and keeping inserts before deletes
+ */
+function pushOp(ops, op) {
+  if (op === 0 || op === '') return;
+  const last = ops[ops.length - 1];
+  if (typeof op === 'string') {
+    if (typeof last === 'string') ops[ops.length - 1] = last + op;
+    else if (typeof last === 'number' && last < 0) {
+      // Canonical order: insert before delete at the same position
+      const before = ops[ops.length - 2];
+      if (typeof before === 'string') ops[ops.length - 2] = before + op;
+      else ops.splice(ops.length - 1, 0, op);
+    } else ops.push(op);
+    return;
+  }
+  if (typeof last === 'number' && Math.sign(last) === Math.sign(op)) {
+    ops[ops.length - 1] = last + op;
+  } else {
+    ops.push(op);
+  }
+}
+
+/**
+ * Convert an op sequence back into replaces against its base
+ */
+function toReplaces(ops) {
+  const replaces = [];
+  let position = 0;
+  let open = null;
+  for (const op of ops) {
+    if (typeof op === 'number' && op > 0) {
+      if (open) replaces.push(open);
+      open = null;
+      position += op;
+      continue;
+    }
+    if (!open) open = { offset: position, removedLength: 0, text: '' };
+    if (typeof op === 'string') open.text += op;
+    else {
+      open.removedLength -= op;
+      position -= op;
+    }
+  }
+  if (open) replaces.push(open);
+  return replaces;
+}
+
+function opLength(op) {
+  return typeof op === 'string' ? op.length : Math.abs(op);
+}
+
+function isRetain(op) {
+  return typeof op === 'number' && op > 0;
+}
+
+function isDelete(op) {
+  return typeof op === 'number' && op < 0;
+}
+
+/**
+ * Split an op into its first n units and the rest
+ */
+function splitOp(op, n) {
+  if (typeof op === 'string') return [op.slice(0, n), op.slice(n)];
+  return op > 0 ? [n, op - n] : [-n, op + n];
+}
+
+/**
+ * Compose two edits: b is expressed against the result of a
+ * @returns {Array<object>} Replaces against a's base
+ */
+function composeEdits(a, b) {
+  const opsA = toOps(a);
+  const opsB = toOps(b);
+  const result = [];
+  let i = 0;
+  let j = 0;
+  let opA = opsA[i++];
+  let opB = opsB[j++];
+
+  while (opA !== undefined || opB !== undefined) {
+    if (isDelete(opA)) {
+      pushOp(result, opA);
+      opA = opsA[i++];
+      continue;
+    }
+    if (typeof opB === 'string') {
+      pushOp(result, opB);
+      opB = opsB[j++];
+      continue;
+    }
+    // One side exhausted: the other continues against the implicit trailing retain
+    if (opA === undefined) {
+      pushOp(result, opB);
+      opB = opsB[j++];
+      continue;
+    }
+    if (opB === undefined) {
+      pushOp(result, opA);
+      opA = opsA[i++];
+      continue;
+    }
+
+    const n = Math.min(opLength(opA), opLength(opB));
+    const [headA, restA] = splitOp(opA, n);
+    const [, restB] = splitOp(opB, n);
+
+    if (isRetain(opA) && isRetain(opB)) pushOp(result, n);
+    else if (typeof opA === 'string' && isRetain(opB)) pushOp(result, headA);
+    else if (isRetain(opA) && isDelete(opB)) pushOp(result, -n);
+    // Insert followed by delete of the same text cancels out
+
+    opA = opLength(restA) > 0 ? restA : opsA[i++];
+    opB = opLength(restB) > 0 ? restB : opsB[j++];
+  }
+
+  return toReplaces(result);
+}
+
+/**
+ * Transform two concurrent edits against each other
+ * @param {object|Array} a - Edit against the base
+ * @param {object|Array} b - Edit against the same base
+ * @param {object} options - { priority: 'a' | 'b' } whose insert goes first at the same position
+ * @returns {Array<Array<object>>} [a', b'] where a' applies after b and b' applies after a
+ */
+function transformEdits(a, b, options = {}) {
+  const aFirst = (options.priority || 'a') === 'a';
+  const opsA = toOps(a);
+  const opsB = toOps(b);
+  const primeA = [];
+  const primeB = [];
+  let i = 0;
+  let j = 0;
+  let opA = opsA[i++];
+  let opB = opsB[j++];
+
+  while (opA !== undefined || opB !== undefined) {
+    const insertA = typeof opA === 'string';
+    const insertB = typeof opB === 'string';
+    if (insertA && (aFirst || !insertB)) {
+      pushOp(primeA, opA);
+      pushOp(primeB, opA.length);
+      opA = opsA[i++];
+      continue;
+    }
+    if (insertB) {
+      pushOp(primeA, opB.length);
+      pushOp(primeB, opB);
+      opB = opsB[j++];
+      continue;
+    }
+    // Ops past the end of the other edit touch text it left alone
+    if (opA === undefined) {
+      pushOp(primeB, opB);
+      opB = opsB[j++];
+      continue;
+    }
+    if (opB === undefined) {
+      pushOp(primeA, opA);
+      opA = opsA[i++];
+      continue;
+    }
+
+    const n = Math.min(opLength(opA), opLength(opB));
+    const [, restA] = splitOp(opA, n);
+    const [, restB] = splitOp(opB, n);
+
+    if (isRetain(opA) && isRetain(opB)) {
+      pushOp(primeA, n);
+      pushOp(primeB, n);
+    } else if (isDelete(opA) && isRetain(opB)) {
+      pushOp(primeA, -n);
+    } else if (isRetain(opA) && isDelete(opB)) {
+      pushOp(primeB, -n);
+    }
+    // Both deleted the same text: neither needs to delete it again
+
+    opA = opLength(restA) > 0 ? restA : opsA[i++];
+    opB = opLength(restB) > 0 ? restB : opsB[j++];
+  }
+
+  return [toReplaces(primeA), toReplaces(primeB)];
+}
+
+/**
+ * Apply an edit to a document
+ */
+function applyEdits(content, edit) {
+  let result = '';
+  let position = 0;
+  for (const { offset, removedLength, text } of toReplaces(toOps(edit))) {
+    if (offset + removedLength > content.length) {
+      throw new Error(`Edit at ${offset}+${removedLength} is outside the document (${content.length})`);
+    }
+    result += content.slice(position, offset) + text;
+    position = offset + removedLength;
+  }
+  return result + content.slice(position);
+}
+
+module.exports = {
+  composeEdits,
+  transformEdits,
+  applyEdits,
+};
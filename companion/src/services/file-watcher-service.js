@@ -9,6 +9,8 @@ const path = require('path');
 const crypto = require('crypto');
 const { extractModelInfo } = require('../utils/model-detector.js');
 const diffEngine = require('../utils/diff-engine.js');
+const { isFormatOnlyChange } = require('../utils/code-format.js');
+const { AdaptiveRateController } = require('./scheduling/adaptive-rate-controller');
 
 function createFileWatcherService(deps) {
   const {
@@ -32,6 +34,13 @@ function createFileWatcherService(deps) {
   let watcher = null;
   const fileSnapshots = new Map();
 
+  // Per-file snapshot rate: a file being edited in a burst is snapshotted often,
+  // a file touched once an hour ago at most every maxIntervalMs
+  const ownsRateController = !deps.rateController;
+  const rateController = deps.rateController || new AdaptiveRateController();
+  const lastSnapshotAt = new Map(); // filePath -> ms
+  const pendingSnapshots = new Map(); // filePath -> { timeout, due }
+
   // Log performance info on initialization
   const perfInfo = diffEngine.getPerformanceInfo();
   console.log(`[FILE-WATCHER] Diff engine: ${perfInfo.implementation}`);
@@ -52,7 +61,46 @@ function createFileWatcherService(deps) {
     };
   }
 
+  /**
+   * Record the change and snapshot the file no more often than its adaptive
+   * interval; changes inside the interval coalesce into one trailing snapshot
+   */
+  function scheduleSnapshot(filePath) {
+    const now = Date.now();
+    rateController.recordActivity(filePath, now);
+
+    const last = lastSnapshotAt.get(filePath) ?? -Infinity;
+    const due = last + rateController.getInterval(filePath, now);
+    const pending = pendingSnapshots.get(filePath);
+    // A burst shortens the interval; pull an already pending snapshot forward
+    if (pending && pending.due <= due) return;
+    if (pending) clearTimeout(pending.timeout);
+
+    if (due <= now) {
+      pendingSnapshots.delete(filePath);
+      lastSnapshotAt.set(filePath, now);
+      processFileChange(filePath);
+      return;
+    }
+
+    const timeout = setTimeout(() => {
+      pendingSnapshots.delete(filePath);
+      lastSnapshotAt.set(filePath, Date.now());
+      processFileChange(filePath);
+    }, due - now);
+    timeout.unref?.();
+    pendingSnapshots.set(filePath, { timeout, due });
+  }
+
+  function forgetFile(filePath) {
+    clearTimeout(pendingSnapshots.get(filePath)?.timeout);
+    pendingSnapshots.delete(filePath);
+    lastSnapshotAt.delete(filePath);
+    fileSnapshots.delete(filePath);
+  }
+
   async function processFileChange(filePath) {
+    if (!fs.existsSync(filePath)) return;
 
     const workspacePath = await detectWorkspace(filePath);
     const workspaceSession = getWorkspaceSession(workspacePath);
@@ -87,7 +135,12 @@ function createFileWatcherService(deps) {
           ` Diff: ${diff.summary}, Significant: ${diff.isSignificant}, Threshold: ${config.diff_threshold}`
         );
 
-        if (diff.isSignificant) {
+        // Formatter runs (prettier, rustfmt, black) rewrite the file without changing the code
+        const formatOnly =
+          diff.isSignificant &&
+          isFormatOnlyChange(previousContent, content, path.extname(filePath).slice(1).toLowerCase());
+
+        if (diff.isSignificant && !formatOnly) {
           const modelInfo = extractModelInfo({
             file_path: relativePath,
             content: content,
@@ -265,6 +318,8 @@ function createFileWatcherService(deps) {
               console.warn(`[PLOT] Error in plot detection: ${err.message}`);
             }
           }
+        } else if (formatOnly) {
+          console.log(`Formatting-only change for ${relativePath}: ${diff.summary}`);
         } else {
           console.log(`Change too small for ${relativePath}: ${diff.summary}`);
         }
@@ -298,6 +353,8 @@ function createFileWatcherService(deps) {
     });
     console.log(` Ignoring: ${config.ignore.join(', ')}`);
 
+    if (ownsRateController) rateController.start();
+
     watcher = chokidar.watch(workspacesToWatch, {
       ignored: config.ignore,
       persistent: true,
@@ -310,13 +367,11 @@ function createFileWatcherService(deps) {
     });
 
     watcher
-      .on('add', (filePath) => {
-        processFileChange(filePath);
-      })
-      .on('change', processFileChange)
+      .on('add', scheduleSnapshot)
+      .on('change', scheduleSnapshot)
       .on('unlink', async (filePath) => {
+        forgetFile(filePath);
         const detectedWorkspace = await detectWorkspace(filePath);
-        fileSnapshots.delete(filePath);
         const relativePath = path.relative(detectedWorkspace || config.root_dir, filePath);
         console.log(` File deleted: ${relativePath} from workspace: ${detectedWorkspace}`);
 
@@ -369,11 +424,15 @@ function createFileWatcherService(deps) {
       watcher.close();
       watcher = null;
     }
+    for (const { timeout } of pendingSnapshots.values()) clearTimeout(timeout);
+    pendingSnapshots.clear();
+    if (ownsRateController) rateController.stop();
   }
 
   return {
     start,
     stop,
+    getEffectiveRates: () => rateController.getEffectiveRates(),
     calculateDiff, // Export for use elsewhere if needed
   };
 }
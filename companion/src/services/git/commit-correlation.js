@@ -0,0 +1,214 @@
+/**
+ * Commit Correlation
+ * Links captured edit events to the commits that eventually contained them,
+ * and flags edits that never made it into history.
+ *
+ * An edit matches a commit when the commit touches the same file within
+ * timeWindowMs after the edit and its diff adds (and removes) the same
+ * lines the edit did. Lines are compared trimmed, and trivial lines (`}`,
+ * `return;`, blank) don't count, since they'd match nearly any commit. The
+ * earliest commit covering at least minOverlap of the edit's lines wins;
+ * an edit whose lines were rewritten before committing covers less, and an
+ * abandoned experiment covers nothing.
+ *
+ * Commits come from one `git log --all -p -U0` over the events' time range,
+ * so edits later committed on another branch, or rebased, still match.
+ * An edit with no match is only reported as never committed once its window
+ * has passed; until then it is pending.
+ */
+
+const path = require('path');
+const diff = require('diff');
+const { findRepository, runGit } = require('./git-repository');
+const { normalizeLineEndings } = require('../../utils/file-reader');
+
+const DEFAULT_OPTIONS = {
+  minOverlap: 0.5, // Share of an edit's significant lines a commit must contain
+  minLineLength: 4, // Shorter trimmed lines are too generic to match on
+  maxCommits: 5000, // Commits read from the log
+  timeoutMs: 60000,
+};
+
+const DEFAULT_TIME_WINDOW_MS = 7 * 24 * 60 * 60 * 1000;
+
+function significant(line, minLength) {
+  const trimmed = line.trim();
+  return trimmed.length >= minLength && /[\p{L}\p{N}]/u.test(trimmed) ? trimmed : null;
+}
+
+/**
+ * Trimmed significant lines an edit added and removed
+ */
+function editLines(before, after, minLength) {
+  const added = new Set();
+  const removed = new Set();
+  for (const part of diff.diffLines(normalizeLineEndings(before || ''), normalizeLineEndings(after || ''))) {
+    if (!part.added && !part.removed) continue;
+    for (const line of part.value.split('\n')) {
+      const kept = significant(line, minLength);
+      if (kept) (part.added ? added : removed).add(kept);
+    }
+  }
+  return { added, removed };
+}
+
+/**
+ * Parse `git log -p -U0` output with a NUL-prefixed "%H %at %ct %s" header per commit
+ */
+function parseLog(output, minLength) {
+  const commits = [];
+  let commit = null;
+  let file = null;
+  let inHunk = false;
+  for (const line of output.split('\n')) {
+    if (line.startsWith('\0')) {
+      const [sha, authorTime, commitTime, ...subject] = line.slice(1).split(' ');
+      commit = {
+        sha,
+        authoredAt: Number(authorTime) * 1000,
+        committedAt: Number(commitTime) * 1000,
+        subject: subject.join(' '),
+        files: new Map(), // path -> { added, removed }
+      };
+      commits.push(commit);
+      file = null;
+      continue;
+    }
+    if (!commit) continue;
+    if (line.startsWith('diff --git ')) {
+      file = { added: new Set(), removed: new Set(), paths: [] };
+      inHunk = false;
+      continue;
+    }
+    if (!file) continue;
+    if (!inHunk) {
+      // Header lines; the file is listed under its old and new names, so pre-rename edits match
+      const name = /^(?:---|\+\+\+) (?:[ab]\/)?(.+)$/.exec(line);
+      if (name && name[1] !== '/dev/null') {
+        if (!file.paths.includes(name[1])) file.paths.push(name[1]);
+        commit.files.set(name[1], file);
+      }
+      if (line.startsWith('@@')) inHunk = true;
+      continue;
+    }
+    if (line.startsWith('@@')) continue;
+    const kept = (line[0] === '+' || line[0] === '-') && significant(line.slice(1), minLength);
+    if (kept) (line[0] === '+' ? file.added : file.removed).add(kept);
+  }
+  return commits;
+}
+
+function eventFields(event, root) {
+  const filePath = event.path || event.filePath || event.file_path || null;
+  const timestamp = typeof event.timestamp === 'number' ? event.timestamp : Date.parse(event.timestamp);
+  let relativePath = event.relativePath || null;
+  if (filePath && path.isAbsolute(filePath)) {
+    relativePath = path.relative(root, filePath).split(path.sep).join('/');
+  } else if (!relativePath && filePath) {
+    relativePath = filePath.split(path.sep).join('/');
+  }
+  return {
+    relativePath,
+    timestamp,
+    before: event.beforeContent ?? event.before_code ?? event.before ?? '',
+    after: event.afterContent ?? event.after_code ?? event.after ?? '',
+  };
+}
+
+/**
+ * Match edit events to the commits that contain their changes
+ * @param {string} repoPath - Any path inside the repository
+ * @param {Array<object>} events - Edit events with a path (path / filePath / file_path, absolute or
+ *   repo-relative), a timestamp, and before/after content (beforeContent / before_code, ...)
+ * @param {number} timeWindowMs - How long after an edit its commit may land (default 7 days)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Promise<object|null>} { results, commitsScanned }, or null outside a repository.
+ *   results[i] belongs to events[i]: { commit, committedAt, subject, overlap, neverCommitted,
+ *   pending, reason } - reason is 'no-lines' (nothing significant changed), 'outside-repo',
+ *   'no-timestamp' or null
+ */
+async function correlateCommits(repoPath, events, timeWindowMs = DEFAULT_TIME_WINDOW_MS, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const repo = findRepository(repoPath);
+  if (!repo) return null;
+  const window = timeWindowMs ?? DEFAULT_TIME_WINDOW_MS;
+  const fields = (events || []).map((event) => {
+    const field = eventFields(event, repo.root);
+    if (!Number.isFinite(field.timestamp)) return { ...field, skip: 'no-timestamp' };
+    if (!field.relativePath || field.relativePath.startsWith('..')) return { ...field, skip: 'outside-repo' };
+    const lines = editLines(field.before, field.after, opts.minLineLength);
+    if (lines.added.size + lines.removed.size === 0) return { ...field, skip: 'no-lines' };
+    return { ...field, ...lines, skip: null };
+  });
+
+  const times = fields.filter((field) => !field.skip).map((field) => field.timestamp);
+  let commits = [];
+  if (times.length) {
+    const since = new Date(Math.min(...times) - 1000).toISOString();
+    const until = new Date(Math.max(...times) + window + 1000).toISOString();
+    const output = await runGit(
+      repo.root,
+      [
+        'log',
+        '--all',
+        '--no-merges',
+        '-M',
+        '-p',
+        '--unified=0',
+        '--no-color',
+        '--no-ext-diff',
+        '--no-textconv',
+        `--max-count=${opts.maxCommits}`,
+        `--since=${since}`,
+        `--until=${until}`,
+        '--format=%x00%H %at %ct %s',
+      ],
+      opts
+    );
+    // Oldest first, so the first match is the earliest commit
+    commits = parseLog(output, opts.minLineLength).sort((a, b) => a.committedAt - b.committedAt);
+  }
+
+  const now = Date.now();
+  const results = fields.map((field) => {
+    const result = {
+      commit: null,
+      committedAt: null,
+      subject: null,
+      overlap: 0,
+      neverCommitted: false,
+      pending: false,
+      reason: null,
+    };
+    if (field.skip) return { ...result, reason: field.skip };
+    const { added, removed } = field;
+    const total = added.size + removed.size;
+
+    for (const commit of commits) {
+      if (commit.committedAt < field.timestamp) continue;
+      if (commit.committedAt > field.timestamp + window) break;
+      const file = commit.files.get(field.relativePath);
+      if (!file) continue;
+      let found = 0;
+      for (const line of added) if (file.added.has(line)) found++;
+      for (const line of removed) if (file.removed.has(line)) found++;
+      const overlap = found / total;
+      if (overlap >= opts.minOverlap) {
+        const { sha, committedAt, subject } = commit;
+        return { ...result, commit: sha, committedAt, subject, overlap };
+      }
+      result.overlap = Math.max(result.overlap, overlap);
+    }
+    if (field.timestamp + window <= now) result.neverCommitted = true;
+    else result.pending = true;
+    return result;
+  });
+
+  return { results, commitsScanned: commits.length };
+}
+
+module.exports = {
+  correlateCommits,
+  DEFAULT_OPTIONS,
+  DEFAULT_TIME_WINDOW_MS,
+};
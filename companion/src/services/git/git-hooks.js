@@ -0,0 +1,257 @@
+/**
+ * Git Hooks
+ * Installs post-commit and post-checkout hooks that report commits and
+ * branch switches, so repository events land in the same timeline as edits.
+ *
+ * The hooks are short shell scripts that note HEAD and the branch, then
+ * start this file as a helper in the background (git isn't kept waiting)
+ * with the absolute node path from install time, since GUI git clients
+ * often run hooks without node on PATH.
+ * The helper appends one JSON line per event to a spool file; appends of a
+ * short line are atomic, so hooks fired in quick succession (a rebase fires
+ * post-checkout and post-commit many times) don't need a lock. The companion
+ * moves spooled events into its trace log with importHookEvents.
+ *
+ * Hooks already in place are kept: they're renamed with ORIGINAL_SUFFIX and
+ * run first, and uninstalling puts them back. The hooks directory is asked
+ * from git, so core.hooksPath and linked worktrees are respected.
+ */
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { execFileSync } = require('child_process');
+const { findRepository, getHeadInfo, runGit } = require('./git-repository');
+
+const HOOKS = ['post-commit', 'post-checkout'];
+const MARKER = '# cursor-telemetry git hook';
+const ORIGINAL_SUFFIX = '.pre-cursor-telemetry';
+const SPOOL_ENV = 'CURSOR_TELEMETRY_GIT_SPOOL';
+const DEFAULT_SPOOL_PATH = path.join(os.homedir(), '.cursor-telemetry', 'git-events.jsonl');
+
+function spoolPath(override = null) {
+  return override || process.env[SPOOL_ENV] || DEFAULT_SPOOL_PATH;
+}
+
+function shellQuote(value) {
+  return `'${String(value).replace(/'/g, `'\\''`)}'`;
+}
+
+function hookScript(hook, options) {
+  const original = `"$0${ORIGINAL_SUFFIX}"`;
+  const env = options.spoolPath ? `${SPOOL_ENV}=${shellQuote(options.spoolPath)} ` : '';
+  return [
+    '#!/bin/sh',
+    MARKER,
+    `[ -x ${original} ] && ${original} "$@"`,
+    // HEAD is read now: by the time the helper runs, git may have moved on (rebase, chained commands)
+    'head=$(git rev-parse -q --verify HEAD); branch=$(git symbolic-ref -q --short HEAD)',
+    `${env}${shellQuote(options.nodePath)} ${shellQuote(__filename)} ${hook} "$head" "$branch" "$@" \\`,
+    '  >/dev/null 2>&1 &',
+    'exit 0',
+    '',
+  ].join('\n');
+}
+
+async function hooksDirectory(repoPath) {
+  const repo = findRepository(repoPath);
+  if (!repo) throw new Error(`Not inside a git repository: ${repoPath}`);
+  const relative = (await runGit(repo.root, ['rev-parse', '--git-path', 'hooks'])).trim();
+  return { root: repo.root, directory: path.resolve(repo.root, relative) };
+}
+
+function isManaged(filePath) {
+  try {
+    return fs.readFileSync(filePath, 'utf8').includes(MARKER);
+  } catch {
+    return false;
+  }
+}
+
+/**
+ * Install the hooks in a repository (idempotent)
+ * @param {string} repoPath - Any path inside the repository
+ * @param {object} options - { hooks, nodePath, spoolPath }
+ * @returns {Promise<object>} { directory, installed: [hook], preserved: [hook] }
+ *   preserved lists hooks that existed before and are now chained
+ */
+async function installGitHooks(repoPath, options = {}) {
+  const { directory } = await hooksDirectory(repoPath);
+  const opts = { hooks: HOOKS, nodePath: process.execPath, spoolPath: null, ...options };
+  await fs.promises.mkdir(directory, { recursive: true });
+  const result = { directory, installed: [], preserved: [] };
+  for (const hook of opts.hooks) {
+    const target = path.join(directory, hook);
+    if (fs.existsSync(target) && !isManaged(target)) {
+      await fs.promises.rename(target, `${target}${ORIGINAL_SUFFIX}`);
+      result.preserved.push(hook);
+    }
+    await fs.promises.writeFile(target, hookScript(hook, opts), { mode: 0o755 });
+    await fs.promises.chmod(target, 0o755);
+    result.installed.push(hook);
+  }
+  return result;
+}
+
+/**
+ * Remove the hooks and restore any that were there before
+ * @returns {Promise<object>} { directory, removed: [hook], restored: [hook] }
+ */
+async function uninstallGitHooks(repoPath, options = {}) {
+  const { directory } = await hooksDirectory(repoPath);
+  const hooks = options.hooks || HOOKS;
+  const result = { directory, removed: [], restored: [] };
+  for (const hook of hooks) {
+    const target = path.join(directory, hook);
+    if (!isManaged(target)) continue;
+    await fs.promises.rm(target);
+    result.removed.push(hook);
+    if (fs.existsSync(`${target}${ORIGINAL_SUFFIX}`)) {
+      await fs.promises.rename(`${target}${ORIGINAL_SUFFIX}`, target);
+      result.restored.push(hook);
+    }
+  }
+  return result;
+}
+
+/**
+ * Which hooks are installed
+ * @returns {Promise<object>} { directory, hooks: { [hook]: boolean } }
+ */
+async function gitHookStatus(repoPath) {
+  const { directory } = await hooksDirectory(repoPath);
+  const hooks = {};
+  for (const hook of HOOKS) hooks[hook] = isManaged(path.join(directory, hook));
+  return { directory, hooks };
+}
+
+// ---------------------------------------------------------------------------
+// Helper (runs inside the hook)
+// ---------------------------------------------------------------------------
+
+function git(root, args) {
+  return execFileSync('git', args, { cwd: root, encoding: 'utf8', timeout: 5000 }).trim();
+}
+
+/**
+ * Build the event for one hook invocation
+ * @param {string} hook - 'post-commit' | 'post-checkout'
+ * @param {Array<string>} args - HEAD and branch as the hook saw them, then the hook's own arguments
+ * @param {string} cwd - Where git ran the hook (the worktree root)
+ */
+function buildHookEvent(hook, args, cwd) {
+  const head = getHeadInfo(cwd);
+  if (!head) return null;
+  const [commit, branch, ...hookArgs] = args;
+  const base = {
+    timestamp: Date.now(),
+    repository: head.root,
+    branch: branch || null, // Empty when HEAD is detached
+    state: head.state,
+  };
+
+  if (hook === 'post-commit') {
+    if (!commit) return null;
+    const header = git(head.root, ['log', '-1', '--format=%H%x00%P%x00%s', commit]);
+    const [sha, parents, subject] = header.split('\0');
+    const stat = git(head.root, ['diff-tree', '--root', '--no-commit-id', '--numstat', '-r', commit]);
+    let linesAdded = 0;
+    let linesRemoved = 0;
+    let filesChanged = 0;
+    for (const line of stat.split('\n').filter(Boolean)) {
+      const [added, removed] = line.split('\t');
+      filesChanged++;
+      linesAdded += Number(added) || 0; // '-' for binary files
+      linesRemoved += Number(removed) || 0;
+    }
+    return {
+      type: 'git_commit',
+      ...base,
+      commit: sha,
+      parents: parents ? parents.split(' ') : [],
+      subject,
+      filesChanged,
+      linesAdded,
+      linesRemoved,
+    };
+  }
+
+  if (hook === 'post-checkout') {
+    const [previous, current, flag] = hookArgs;
+    return {
+      type: 'git_checkout',
+      ...base,
+      from: previous || null,
+      to: current || commit || null,
+      // 1: HEAD moved (branch switch); 0: files checked out from the index or a commit
+      kind: flag === '1' ? 'branch' : 'files',
+    };
+  }
+  return null;
+}
+
+/**
+ * Append a hook event to the spool
+ */
+function recordHookEvent(hook, args = [], options = {}) {
+  const event = buildHookEvent(hook, args, options.cwd || process.cwd());
+  if (!event) return null;
+  const target = spoolPath(options.spoolPath);
+  fs.mkdirSync(path.dirname(target), { recursive: true });
+  fs.appendFileSync(target, `${JSON.stringify(event)}\n`, { mode: 0o600 });
+  return event;
+}
+
+/**
+ * Move spooled hook events into a trace log
+ * @param {TraceWriter} writer - Open TraceWriter
+ * @param {object} options - { spoolPath }
+ * @returns {Promise<number>} Events imported
+ */
+async function importHookEvents(writer, options = {}) {
+  const source = spoolPath(options.spoolPath);
+  // Hooks that fire from here on start a new spool file
+  const claimed = `${source}.${process.pid}.importing`;
+  try {
+    await fs.promises.rename(source, claimed);
+  } catch (error) {
+    if (error.code === 'ENOENT') return 0;
+    throw error;
+  }
+  const lines = (await fs.promises.readFile(claimed, 'utf8')).split('\n').filter(Boolean);
+  let imported = 0;
+  for (const line of lines) {
+    let event;
+    try {
+      event = JSON.parse(line);
+    } catch {
+      continue; // A line cut short by a crash mid-append
+    }
+    writer.append(event);
+    imported++;
+  }
+  await writer.flush();
+  await fs.promises.rm(claimed, { force: true });
+  return imported;
+}
+
+if (require.main === module) {
+  const [hook, ...args] = process.argv.slice(2);
+  try {
+    recordHookEvent(hook, args);
+  } catch {
+    // Never let telemetry break a git operation
+  }
+}
+
+module.exports = {
+  installGitHooks,
+  uninstallGitHooks,
+  gitHookStatus,
+  recordHookEvent,
+  buildHookEvent,
+  importHookEvents,
+  HOOKS,
+  DEFAULT_SPOOL_PATH,
+  SPOOL_ENV,
+};
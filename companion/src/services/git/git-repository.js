@@ -0,0 +1,405 @@
+/**
+ * Git Repository
+ * Branch, commit, working-tree state and prior authorship for annotating
+ * captured edits.
+ *
+ * Head info is read straight from the .git directory (HEAD, loose refs,
+ * packed-refs, config and the merge/rebase marker files), so it costs a few
+ * small file reads and works in linked worktrees and submodules. Status and
+ * blame need git's own index and history walk; they run `git` with
+ * machine-readable output (status --porcelain=v2, blame --porcelain) and
+ * are cached: status per repository for statusTtlMs, so a burst of edit
+ * events shares one `git status`, and blame per file, range, HEAD commit and
+ * file mtime.
+ *
+ * Paths outside a repository give null rather than an error; a missing git
+ * binary gives an UNAVAILABLE CompanionError.
+ */
+
+const fs = require('fs');
+const path = require('path');
+const { execFile } = require('child_process');
+const { promisify } = require('util');
+const { CompanionError, ERROR_CODES, fromSystemError } = require('../../utils/errors');
+
+const execFileAsync = promisify(execFile);
+
+const DEFAULT_OPTIONS = {
+  timeoutMs: 10000, // Per git invocation
+  statusTtlMs: 1000, // getStatus results are reused this long
+  blameCacheSize: 200, // Blame results kept
+  untracked: true, // getStatus lists untracked files
+};
+
+const ZERO_COMMIT = '0000000000000000000000000000000000000000';
+
+const STATUS_CODES = {
+  M: 'modified',
+  T: 'type-changed',
+  A: 'added',
+  D: 'deleted',
+  R: 'renamed',
+  C: 'copied',
+  U: 'unmerged',
+};
+
+// In-progress operations, by the marker git leaves in the per-worktree git dir
+const STATE_MARKERS = [
+  ['rebase-merge', 'rebasing'],
+  ['rebase-apply', 'rebasing'],
+  ['MERGE_HEAD', 'merging'],
+  ['CHERRY_PICK_HEAD', 'cherry-picking'],
+  ['REVERT_HEAD', 'reverting'],
+  ['BISECT_LOG', 'bisecting'],
+];
+
+const statusCache = new Map(); // root -> { at, promise }
+const blameCache = new Map(); // key -> lines (Map order = LRU order)
+
+function readText(filePath) {
+  try {
+    return fs.readFileSync(filePath, 'utf8');
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * Locate the repository containing a path
+ * @returns {object|null} { root, gitDir, commonDir } - gitDir holds HEAD (per worktree),
+ *   commonDir the refs and config shared by all worktrees
+ */
+function findRepository(repoPath) {
+  let dir = path.resolve(repoPath);
+  try {
+    if (!fs.statSync(dir).isDirectory()) dir = path.dirname(dir);
+  } catch {
+    dir = path.dirname(dir);
+  }
+  for (;;) {
+    const dotGit = path.join(dir, '.git');
+    let gitDir = null;
+    try {
+      const stat = fs.statSync(dotGit);
+      if (stat.isDirectory()) gitDir = dotGit;
+      else {
+        // Worktrees and submodules: a file pointing at the real git dir
+        const pointer = /^gitdir:\s*(.+)$/m.exec(readText(dotGit) || '');
+        if (pointer) gitDir = path.resolve(dir, pointer[1].trim());
+      }
+    } catch {
+      // No .git here
+    }
+    if (gitDir) {
+      const common = readText(path.join(gitDir, 'commondir'));
+      const commonDir = common ? path.resolve(gitDir, common.trim()) : gitDir;
+      return { root: dir, gitDir, commonDir };
+    }
+    const parent = path.dirname(dir);
+    if (parent === dir) return null;
+    dir = parent;
+  }
+}
+
+function resolveRef(commonDir, ref) {
+  const loose = readText(path.join(commonDir, ref));
+  if (loose) return loose.trim();
+  const packed = readText(path.join(commonDir, 'packed-refs')) || '';
+  for (const line of packed.split('\n')) {
+    const [hash, name] = line.split(' ');
+    if (name && name.trim() === ref) return hash;
+  }
+  return null;
+}
+
+/**
+ * Values of one `[branch "name"]` section of a git config
+ */
+function branchConfig(commonDir, branch) {
+  const config = readText(path.join(commonDir, 'config')) || '';
+  const values = {};
+  let inSection = false;
+  for (const line of config.split('\n')) {
+    const section = /^\s*\[\s*branch\s+"(.*)"\s*\]/.exec(line);
+    if (section) inSection = section[1] === branch;
+    else if (/^\s*\[/.test(line)) inSection = false;
+    else if (inSection) {
+      const entry = /^\s*(\w+)\s*=\s*(.*?)\s*$/.exec(line);
+      if (entry) values[entry[1].toLowerCase()] = entry[2];
+    }
+  }
+  return values;
+}
+
+/**
+ * Current branch and commit, without running git
+ * @param {string} repoPath - Any path inside the repository
+ * @returns {object|null} { root, branch, commit, detached, unborn, upstream, state }
+ *   branch is null when detached; commit is null on an unborn branch (no commits yet);
+ *   upstream is e.g. 'origin/main'; state is the operation in progress
+ *   ('merging', 'rebasing', 'cherry-picking', 'reverting', 'bisecting') or null
+ */
+function getHeadInfo(repoPath) {
+  const repo = findRepository(repoPath);
+  if (!repo) return null;
+  const head = (readText(path.join(repo.gitDir, 'HEAD')) || '').trim();
+  const symbolic = /^ref:\s*(\S+)/.exec(head);
+
+  let branch = null;
+  let commit = null;
+  let upstream = null;
+  if (symbolic) {
+    const ref = symbolic[1];
+    branch = ref.replace(/^refs\/heads\//, '');
+    commit = resolveRef(repo.commonDir, ref);
+    const config = branchConfig(repo.commonDir, branch);
+    if (config.remote && config.merge) {
+      const remoteBranch = config.merge.replace(/^refs\/heads\//, '');
+      upstream = config.remote === '.' ? remoteBranch : `${config.remote}/${remoteBranch}`;
+    }
+  } else if (/^[0-9a-f]{40,64}$/.test(head)) {
+    commit = head;
+  }
+
+  const marker = STATE_MARKERS.find(([name]) => fs.existsSync(path.join(repo.gitDir, name)));
+  return {
+    root: repo.root,
+    branch,
+    commit,
+    detached: !symbolic,
+    unborn: Boolean(symbolic) && commit === null,
+    upstream,
+    state: marker ? marker[1] : null,
+  };
+}
+
+async function runGit(root, args, options = {}) {
+  try {
+    const { stdout } = await execFileAsync('git', args, {
+      cwd: root,
+      timeout: options.timeoutMs ?? DEFAULT_OPTIONS.timeoutMs,
+      maxBuffer: 64 * 1024 * 1024,
+      env: { ...process.env, GIT_OPTIONAL_LOCKS: '0' }, // Don't take index.lock just to refresh stat info
+    });
+    return stdout;
+  } catch (error) {
+    const stderr = String(error.stderr || '');
+    if (/no such path|does not exist|no such ref|has only \d+ lines?/i.test(stderr)) {
+      throw new CompanionError(ERROR_CODES.NOT_FOUND, stderr.trim().split('\n')[0], { cause: error });
+    }
+    throw fromSystemError(error, { command: 'git' });
+  }
+}
+
+function describeXY(code) {
+  return code === '.' ? null : STATUS_CODES[code] || code;
+}
+
+/**
+ * Parse `git status --porcelain=v2 --branch -z`
+ */
+function parseStatus(output) {
+  const result = { branch: null, commit: null, upstream: null, ahead: 0, behind: 0, files: [] };
+  const entries = output.split('\0');
+  for (let i = 0; i < entries.length; i++) {
+    const entry = entries[i];
+    if (!entry) continue;
+    if (entry.startsWith('# ')) {
+      const [key, ...rest] = entry.slice(2).split(' ');
+      const value = rest.join(' ');
+      if (key === 'branch.oid') result.commit = value === '(initial)' ? null : value;
+      else if (key === 'branch.head') result.branch = value === '(detached)' ? null : value;
+      else if (key === 'branch.upstream') result.upstream = value;
+      else if (key === 'branch.ab') {
+        const ab = /^\+(\d+) -(\d+)$/.exec(value);
+        if (ab) [result.ahead, result.behind] = [Number(ab[1]), Number(ab[2])];
+      }
+      continue;
+    }
+
+    const kind = entry[0];
+    if (kind === '?' || kind === '!') {
+      result.files.push({ path: entry.slice(2), status: kind === '?' ? 'untracked' : 'ignored' });
+      continue;
+    }
+    const fields = entry.split(' ');
+    const xy = fields[1];
+    if (kind === '1') {
+      result.files.push({
+        path: fields.slice(8).join(' '),
+        index: describeXY(xy[0]),
+        worktree: describeXY(xy[1]),
+        status: describeXY(xy[1]) || describeXY(xy[0]),
+      });
+    } else if (kind === '2') {
+      // The original path follows as its own NUL-separated entry
+      result.files.push({
+        path: fields.slice(9).join(' '),
+        originalPath: entries[++i],
+        index: describeXY(xy[0]),
+        worktree: describeXY(xy[1]),
+        status: xy[0] === 'C' ? 'copied' : 'renamed',
+        similarity: Number(fields[8].slice(1)),
+      });
+    } else if (kind === 'u') {
+      result.files.push({
+        path: fields.slice(10).join(' '),
+        index: describeXY(xy[0]),
+        worktree: describeXY(xy[1]),
+        status: 'conflicted',
+      });
+    }
+  }
+  return result;
+}
+
+/**
+ * Working tree status
+ * @param {string} repoPath - Any path inside the repository
+ * @param {object} options - { fresh (bypass the cache), untracked, statusTtlMs, timeoutMs }
+ * @returns {Promise<object|null>} { root, branch, commit, upstream, ahead, behind, dirty,
+ *   files: [{ path, status, index, worktree, originalPath? }],
+ *   counts: { staged, unstaged, untracked, conflicted } }
+ *   status is 'modified' | 'added' | 'deleted' | 'renamed' | 'copied' | 'type-changed' |
+ *   'untracked' | 'conflicted'; index / worktree give the staged and unstaged change
+ */
+async function getStatus(repoPath, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const repo = findRepository(repoPath);
+  if (!repo) return null;
+
+  const cacheKey = `${repo.root}\0${opts.untracked}`;
+  const cached = statusCache.get(cacheKey);
+  if (!opts.fresh && cached && Date.now() - cached.at < opts.statusTtlMs) return cached.promise;
+
+  const untracked = `--untracked-files=${opts.untracked ? 'all' : 'no'}`;
+  const args = ['status', '--porcelain=v2', '--branch', '-z', untracked];
+  const promise = runGit(repo.root, args, opts).then((output) => {
+    const status = parseStatus(output);
+    const counts = { staged: 0, unstaged: 0, untracked: 0, conflicted: 0 };
+    for (const file of status.files) {
+      if (file.status === 'untracked') counts.untracked++;
+      else if (file.status === 'conflicted') counts.conflicted++;
+      else {
+        if (file.index) counts.staged++;
+        if (file.worktree) counts.unstaged++;
+      }
+    }
+    return { root: repo.root, ...status, dirty: status.files.length > 0, counts };
+  });
+  statusCache.set(cacheKey, { at: Date.now(), promise });
+  promise.catch(() => statusCache.delete(cacheKey));
+  return promise;
+}
+
+/**
+ * Parse `git blame --porcelain`
+ */
+function parseBlame(output) {
+  const commits = new Map();
+  const lines = [];
+  let current = null;
+  for (const line of output.split('\n')) {
+    if (line.startsWith('\t')) {
+      const info = commits.get(current.commit);
+      lines.push({ ...current, ...info, content: line.slice(1) });
+      current = null;
+      continue;
+    }
+    const group = /^([0-9a-f]{40,64}) (\d+) (\d+)(?: \d+)?$/.exec(line);
+    if (group) {
+      current = { line: Number(group[3]), originalLine: Number(group[2]), commit: group[1] };
+      if (!commits.has(group[1])) commits.set(group[1], {});
+      continue;
+    }
+    if (!current) continue;
+    const space = line.indexOf(' ');
+    const key = space === -1 ? line : line.slice(0, space);
+    const value = space === -1 ? '' : line.slice(space + 1);
+    const info = commits.get(current.commit);
+    if (key === 'author') info.author = value;
+    else if (key === 'author-mail') info.authorEmail = value.replace(/^<|>$/g, '');
+    else if (key === 'author-time') info.authorTime = Number(value) * 1000;
+    else if (key === 'summary') info.summary = value;
+    else if (key === 'filename') info.originalPath = value;
+    else if (key === 'boundary') info.boundary = true;
+  }
+  return lines.map((entry) => {
+    // git reports working-tree lines as authored by "Not Committed Yet"
+    const committed = entry.commit !== ZERO_COMMIT;
+    return {
+      line: entry.line,
+      originalLine: entry.originalLine,
+      originalPath: entry.originalPath || null,
+      commit: committed ? entry.commit : null,
+      uncommitted: !committed,
+      author: committed ? (entry.author ?? null) : null,
+      authorEmail: committed ? (entry.authorEmail ?? null) : null,
+      authorTime: committed ? (entry.authorTime ?? null) : null,
+      summary: committed ? (entry.summary ?? null) : null,
+      content: entry.content,
+    };
+  });
+}
+
+/**
+ * Who last changed each line
+ * @param {string} repoPath - Any path inside the repository
+ * @param {string} file - File path (absolute, or relative to the repository root)
+ * @param {Array<number>} lineRange - [start, end], 1-based and inclusive (default: whole file)
+ * @param {object} options - { timeoutMs }
+ * @returns {Promise<Array|null>} [{ line, originalLine, originalPath, commit, uncommitted, author,
+ *   authorEmail, authorTime (epoch ms), summary, content }], or null outside a repository.
+ *   Lines changed in the working tree have uncommitted: true and null commit and author
+ */
+async function getBlame(repoPath, file, lineRange = null, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const repo = findRepository(repoPath);
+  if (!repo) return null;
+  const absolute = path.resolve(repo.root, file);
+  const relativePath = path.relative(repo.root, absolute).split(path.sep).join('/');
+  if (relativePath.startsWith('..')) {
+    throw new CompanionError(ERROR_CODES.INVALID_INPUT, `${file} is outside the repository ${repo.root}`);
+  }
+
+  const args = ['blame', '--porcelain'];
+  if (lineRange) {
+    const [start, end] = lineRange;
+    if (!(start >= 1 && end >= start)) {
+      throw new CompanionError(ERROR_CODES.INVALID_INPUT, `Invalid line range: ${start}-${end}`);
+    }
+    args.push('-L', `${start},${end}`);
+  }
+  args.push('--', relativePath);
+
+  let mtime = 0;
+  try {
+    mtime = fs.statSync(absolute).mtimeMs;
+  } catch {
+    // Deleted in the working tree; blame still works against HEAD
+  }
+  const head = getHeadInfo(repo.root)?.commit;
+  const key = [repo.root, relativePath, lineRange ? lineRange.join('-') : '', head, mtime].join('\0');
+  if (blameCache.has(key)) {
+    const hit = blameCache.get(key);
+    blameCache.delete(key);
+    blameCache.set(key, hit);
+    return hit;
+  }
+
+  const lines = parseBlame(await runGit(repo.root, args, opts));
+  blameCache.set(key, lines);
+  if (blameCache.size > opts.blameCacheSize) blameCache.delete(blameCache.keys().next().value);
+  return lines;
+}
+
+module.exports = {
+  findRepository,
+  getHeadInfo,
+  getStatus,
+  getBlame,
+  parseStatus,
+  parseBlame,
+  runGit,
+  DEFAULT_OPTIONS,
+};
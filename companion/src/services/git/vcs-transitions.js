@@ -0,0 +1,269 @@
+/**
+ * VCS Transitions
+ * Tells file changes made by git (checkout, rebase, reset, merge, pull,
+ * stash, cherry-pick, restore) apart from edits, so a branch switch that
+ * rewrites forty files isn't counted as forty authored changes.
+ *
+ * A change is a transition when the content it left behind is exactly what
+ * git put there: the file's blob in a commit HEAD moved to (per the HEAD
+ * reflog) up to toleranceMs before the change, or in the base of a stash
+ * pushed then. Content is compared by git object id, computed from the
+ * event's afterContent, so no file is read back. Applying a stash isn't
+ * logged, so any later change that matches a stash's own content counts as
+ * applying it; a popped stash is gone from the reflog, so changes from
+ * `stash pop` can't be told apart from edits.
+ *
+ * Without a reflog entry nearby, a change that puts the file back to its
+ * content at HEAD (`git restore`, `git checkout -- file`) is reported as a
+ * 'restore' with medium confidence, but only as part of a burst of changed
+ * files or when it touches at least minRestoreLines lines: an editor undo of
+ * a small edit looks the same.
+ */
+
+const crypto = require('crypto');
+const path = require('path');
+const diff = require('diff');
+const { findRepository, getHeadInfo, runGit } = require('./git-repository');
+const { normalizeLineEndings } = require('../../utils/file-reader');
+
+const DEFAULT_OPTIONS = {
+  toleranceMs: 5000, // How long after the reflog entry that caused it a change may be seen
+  maxReflogEntries: 2000,
+  burstFiles: 3, // Distinct files changed within burstWindowMs that make a burst
+  burstWindowMs: 2000,
+  minRestoreLines: 20, // Lines a restore outside a burst must touch
+  timeoutMs: 30000,
+};
+
+const ACTIONS = [
+  [/^checkout:/, 'checkout'],
+  [/^rebase/, 'rebase'],
+  [/^reset:/, 'reset'],
+  [/^merge /, 'merge'],
+  [/^pull/, 'pull'],
+  [/^cherry-pick/, 'cherry-pick'],
+  [/^revert/, 'revert'],
+];
+
+/**
+ * Git object id of a blob with this content (sha1, or sha256 for repositories using it)
+ */
+function blobId(content, algorithm = 'sha1') {
+  const body = Buffer.from(content, 'utf8');
+  return crypto
+    .createHash(algorithm)
+    .update(`blob ${body.length}\0`)
+    .update(body)
+    .digest('hex');
+}
+
+/**
+ * HEAD and stash reflog entries, oldest first
+ * @returns {Promise<Array>} [{ commit, treeish, time, kind, message }]; treeish is the revision
+ *   whose tree holds the content the entry left in the working tree
+ */
+async function readReflog(root, options) {
+  const read = async (ref) => {
+    try {
+      const args = ['log', '-g', `-n${options.maxReflogEntries}`, '--date=unix', '--format=%H%x00%gd%x00%gs'];
+      return await runGit(root, [...args, ref], options);
+    } catch {
+      return ''; // No reflog (e.g. no stash)
+    }
+  };
+  const entries = [];
+  for (const line of (await read('HEAD')).split('\n').filter(Boolean)) {
+    const [commit, selector, message] = line.split('\0');
+    const action = ACTIONS.find(([pattern]) => pattern.test(message));
+    const time = Number(/\{(\d+)\}/.exec(selector)?.[1]) * 1000;
+    // Commits don't change the working tree; they only matter as "HEAD at that time"
+    entries.push({ commit, treeish: commit, time, kind: action ? action[1] : null, message });
+  }
+  for (const line of (await read('refs/stash')).split('\n').filter(Boolean)) {
+    const [commit, selector, message] = line.split('\0');
+    const time = Number(/\{(\d+)\}/.exec(selector)?.[1]) * 1000;
+    // Pushing resets the working tree to the stash's base; applying (any time later) writes its own tree
+    entries.push({ commit, treeish: `${commit}^1`, time, kind: 'stash', message, stash: true });
+    entries.push({ commit, treeish: commit, base: `${commit}^1`, time, kind: 'stash', message, stash: true });
+  }
+  return entries.filter((entry) => Number.isFinite(entry.time)).sort((a, b) => a.time - b.time);
+}
+
+/**
+ * Blob ids of the given paths in a revision: path -> id, absent paths missing
+ */
+async function treeBlobs(root, treeish, paths, options) {
+  const blobs = new Map();
+  let output;
+  try {
+    output = await runGit(root, ['ls-tree', '-z', '--full-tree', treeish, '--', ...paths], options);
+  } catch {
+    return blobs; // Revision gone (pruned stash, root commit's ^1)
+  }
+  for (const record of output.split('\0').filter(Boolean)) {
+    // "<mode> <type> <id>\t<path>"
+    const tab = record.indexOf('\t');
+    const [, type, id] = record.slice(0, tab).split(' ');
+    if (type === 'blob') blobs.set(record.slice(tab + 1), id);
+  }
+  return blobs;
+}
+
+function eventFields(event, root) {
+  const filePath = event.path || event.filePath || event.file_path || null;
+  let relativePath = event.relativePath || null;
+  if (filePath && path.isAbsolute(filePath)) {
+    relativePath = path.relative(root, filePath).split(path.sep).join('/');
+  } else if (!relativePath && filePath) {
+    relativePath = filePath.split(path.sep).join('/');
+  }
+  const deleted = event.deleted === true || event.type === 'delete' || event.type === 'unlink';
+  return {
+    relativePath,
+    timestamp: typeof event.timestamp === 'number' ? event.timestamp : Date.parse(event.timestamp),
+    deleted,
+    before: event.beforeContent ?? event.before_code ?? event.before ?? null,
+    after: deleted ? null : (event.afterContent ?? event.after_code ?? event.after ?? null),
+  };
+}
+
+function changedLines(before, after) {
+  let count = 0;
+  for (const part of diff.diffLines(normalizeLineEndings(before || ''), normalizeLineEndings(after || ''))) {
+    if (part.added || part.removed) count += part.count;
+  }
+  return count;
+}
+
+/**
+ * Label file changes that git made rather than the user
+ * @param {Array<object>} fileEvents - Changes with a path (path / filePath / file_path, absolute or
+ *   repo-relative), a timestamp and afterContent (after_code); deletions carry deleted: true or
+ *   type 'delete'. beforeContent (before_code) is used to size restores
+ * @param {string} repoPath - Any path inside the repository
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Promise<object|null>} { results, reflogEntries }, or null outside a repository.
+ *   results[i] belongs to fileEvents[i]: { transition, kind, confidence, commit, reflog } -
+ *   kind 'checkout' | 'rebase' | 'reset' | 'merge' | 'pull' | 'cherry-pick' | 'revert' | 'stash' |
+ *   'restore' | null, confidence 'high' (reflog entry and content match) | 'medium' (restore) |
+ *   null, reflog { message, time } of the entry that explains the change
+ */
+async function detectVcsTransitions(fileEvents, repoPath, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const repo = findRepository(repoPath);
+  if (!repo) return null;
+  const head = getHeadInfo(repo.root);
+  const algorithm = head.commit && head.commit.length === 64 ? 'sha256' : 'sha1';
+  const reflog = await readReflog(repo.root, opts);
+
+  const fields = (fileEvents || []).map((event) => {
+    const field = eventFields(event, repo.root);
+    const usable =
+      Number.isFinite(field.timestamp) &&
+      field.relativePath &&
+      !field.relativePath.startsWith('..') &&
+      (field.deleted || typeof field.after === 'string');
+    if (!usable) return { ...field, usable: false };
+    // Worktree content may have CRLF where the blob has LF (core.autocrlf)
+    const ids = new Set();
+    if (!field.deleted) {
+      ids.add(blobId(field.after, algorithm));
+      const normalized = normalizeLineEndings(field.after);
+      if (normalized !== field.after) ids.add(blobId(normalized, algorithm));
+    }
+    return { ...field, usable: true, ids };
+  });
+
+  // Entries that could explain each change, closest first. Reflog times are whole seconds, and
+  // git writes files just before it moves the ref, so a change may appear up to a second "early"
+  const candidates = fields.map((field) => {
+    if (!field.usable) return [];
+    const seen = field.timestamp;
+    const near = reflog
+      .filter((entry) => entry.kind && !entry.base)
+      .filter((entry) => entry.time - 1000 <= seen && seen <= entry.time + opts.toleranceMs)
+      .sort(
+        (a, b) =>
+          Math.abs(a.time - field.timestamp) - Math.abs(b.time - field.timestamp) ||
+          Number(Boolean(b.stash)) - Number(Boolean(a.stash))
+      );
+    const applied = reflog.filter((entry) => entry.base && field.timestamp > entry.time + 1000).reverse();
+    return near.concat(applied);
+  });
+  // HEAD when each change happened: the last entry at or before it, else the current HEAD
+  const headAt = fields.map((field) => {
+    if (!field.usable) return null;
+    let commit = null;
+    for (const entry of reflog) {
+      if (entry.stash) continue;
+      if (entry.time > field.timestamp) break;
+      commit = entry.commit;
+    }
+    return commit || head.commit;
+  });
+
+  // One ls-tree per revision, for every path that needs it
+  const wanted = new Map(); // treeish -> Set(path)
+  const want = (treeish, relativePath) => {
+    if (!treeish) return;
+    if (!wanted.has(treeish)) wanted.set(treeish, new Set());
+    wanted.get(treeish).add(relativePath);
+  };
+  fields.forEach((field, index) => {
+    if (!field.usable) return;
+    for (const entry of candidates[index]) {
+      want(entry.treeish, field.relativePath);
+      want(entry.base, field.relativePath);
+    }
+    want(headAt[index], field.relativePath);
+  });
+  const trees = new Map();
+  for (const [treeish, paths] of wanted) {
+    trees.set(treeish, await treeBlobs(repo.root, treeish, [...paths], opts));
+  }
+  const blob = (treeish, field) => trees.get(treeish)?.get(field.relativePath);
+  const matches = (field, treeish) => {
+    const id = blob(treeish, field);
+    return field.deleted ? !id : Boolean(id) && field.ids.has(id);
+  };
+  // Applying a stash only writes the files the stash changed
+  const explains = (field, entry) =>
+    matches(field, entry.treeish) && (!entry.base || blob(entry.treeish, field) !== blob(entry.base, field));
+
+  const usable = fields.filter((field) => field.usable);
+  const inBurst = (field) => {
+    const paths = new Set();
+    for (const other of usable) {
+      if (Math.abs(other.timestamp - field.timestamp) <= opts.burstWindowMs) paths.add(other.relativePath);
+    }
+    return paths.size >= opts.burstFiles;
+  };
+
+  const results = fields.map((field, index) => {
+    const result = { transition: false, kind: null, confidence: null, commit: null, reflog: null };
+    if (!field.usable) return result;
+    const entry = candidates[index].find((candidate) => explains(field, candidate));
+    if (entry) {
+      return {
+        transition: true,
+        kind: entry.kind,
+        confidence: 'high',
+        commit: entry.commit,
+        reflog: { message: entry.message, time: entry.time },
+      };
+    }
+    const restored = headAt[index] && matches(field, headAt[index]);
+    if (restored && (inBurst(field) || changedLines(field.before, field.after) >= opts.minRestoreLines)) {
+      return { ...result, transition: true, kind: 'restore', confidence: 'medium', commit: headAt[index] };
+    }
+    return result;
+  });
+
+  return { results, reflogEntries: reflog.length };
+}
+
+module.exports = {
+  detectVcsTransitions,
+  blobId,
+  DEFAULT_OPTIONS,
+};
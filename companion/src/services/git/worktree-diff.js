@@ -0,0 +1,207 @@
+/**
+ * Worktree Diff
+ * Uncommitted state of a repository as structured per-file diffs: the
+ * working tree against HEAD, for "what was left uncommitted" snapshots at
+ * session start and end.
+ *
+ * HEAD is resolved once and every "before" side is read from that commit
+ * (one `git cat-file --batch` for all files), so a commit or checkout
+ * landing mid-capture can't mix two revisions into one snapshot. Per-file
+ * diffs are diff-engine DiffResults, plus linesAdded / linesRemoved counted
+ * from the actual line diff, the same shape snapshot events carry.
+ */
+
+const fs = require('fs');
+const path = require('path');
+const { spawn } = require('child_process');
+const diff = require('diff');
+const { findRepository, getHeadInfo, parseStatus, runGit } = require('./git-repository');
+const { calculateDiff } = require('../../utils/diff-engine');
+const { decodeFileBuffer, normalizeLineEndings } = require('../../utils/file-reader');
+const { fromSystemError } = require('../../utils/errors');
+
+const DEFAULT_OPTIONS = {
+  untracked: true, // Include untracked files (as added)
+  maxFileBytes: 2 * 1024 * 1024, // Larger files are listed with skipped: 'too-large' and no diff
+  diffThreshold: 10,
+  includeContent: false, // Attach beforeContent / afterContent
+  timeoutMs: 30000,
+};
+
+/**
+ * Read blobs for many `<rev>:<path>` specs with one git process
+ * @returns {Promise<Map>} spec -> Buffer (missing specs are absent)
+ */
+function readBlobs(root, specs, timeoutMs) {
+  return new Promise((resolve, reject) => {
+    const blobs = new Map();
+    if (specs.length === 0) {
+      resolve(blobs);
+      return;
+    }
+    const child = spawn('git', ['cat-file', '--batch'], { cwd: root });
+    const timer = setTimeout(() => child.kill(), timeoutMs);
+    const chunks = [];
+    child.stdout.on('data', (chunk) => chunks.push(chunk));
+    child.on('error', (error) => {
+      clearTimeout(timer);
+      reject(fromSystemError(error, { command: 'git' }));
+    });
+    child.on('close', (code, signal) => {
+      clearTimeout(timer);
+      if (code !== 0) {
+        const error = Object.assign(new Error(`git cat-file exited with ${code ?? signal}`), {
+          killed: Boolean(signal),
+          signal,
+        });
+        reject(fromSystemError(error, { command: 'git' }));
+        return;
+      }
+      // Each answer: "<oid> <type> <size>\n<bytes>\n", or "<spec> missing\n"
+      const output = Buffer.concat(chunks);
+      let offset = 0;
+      for (const spec of specs) {
+        const newline = output.indexOf(0x0a, offset);
+        if (newline === -1) break;
+        const header = output.subarray(offset, newline).toString('utf8');
+        offset = newline + 1;
+        const found = /^[0-9a-f]+ (\w+) (\d+)$/.exec(header);
+        if (!found) continue;
+        const size = Number(found[2]);
+        if (found[1] === 'blob') blobs.set(spec, output.subarray(offset, offset + size));
+        offset += size + 1;
+      }
+      resolve(blobs);
+    });
+    child.stdin.end(specs.map((spec) => `${spec}\n`).join(''));
+  });
+}
+
+/**
+ * The change from HEAD to the working tree, whatever is staged in between
+ */
+function changeFromHead(file) {
+  if (['untracked', 'conflicted', 'renamed', 'copied'].includes(file.status)) return file.status;
+  if (file.index === 'added') return 'added';
+  if (file.index === 'deleted' || file.worktree === 'deleted') return 'deleted';
+  return file.status;
+}
+
+async function statFile(filePath) {
+  try {
+    return await fs.promises.stat(filePath);
+  } catch {
+    return null; // Deleted in the working tree: the after side is empty
+  }
+}
+
+function decode(buffer) {
+  if (!buffer) return { text: '', binary: false };
+  const decoded = decodeFileBuffer(buffer, { normalizeLineEndings: false });
+  return { text: decoded.content, binary: decoded.binary };
+}
+
+function diffFile(before, after, options) {
+  const summary = calculateDiff(before, after, {
+    threshold: options.diffThreshold,
+    lineEndings: 'report',
+    includeAfterContent: false,
+  });
+  let linesAdded = 0;
+  let linesRemoved = 0;
+  for (const part of diff.diffLines(normalizeLineEndings(before), normalizeLineEndings(after))) {
+    if (part.added) linesAdded += part.count;
+    else if (part.removed) linesRemoved += part.count;
+  }
+  return { ...summary, linesAdded, linesRemoved };
+}
+
+/**
+ * Diff the working tree against HEAD
+ * @param {string} repoPath - Any path inside the repository
+ * @param {Array<string>} paths - Limit to these files or directories (default: the whole tree)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Promise<object|null>} { root, branch, commit, capturedAt, files, totals }, or null
+ *   outside a repository. files: [{ path, originalPath, status, binary, size, skipped, diff,
+ *   beforeContent?, afterContent? }] with status 'modified' | 'added' | 'deleted' | 'renamed' |
+ *   'copied' | 'type-changed' | 'untracked' | 'conflicted'; diff is null for binary and skipped
+ *   files. totals: { files, linesAdded, linesRemoved }
+ */
+async function diffWorktree(repoPath, paths = null, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const repo = findRepository(repoPath);
+  if (!repo) return null;
+  const head = getHeadInfo(repo.root);
+  const commit = head.commit; // Pinned: every before side comes from this commit
+  const capturedAt = Date.now();
+
+  const pathspec = (paths || []).map((entry) =>
+    path.relative(repo.root, path.resolve(repo.root, entry)).split(path.sep).join('/')
+  );
+  const untracked = `--untracked-files=${opts.untracked ? 'all' : 'no'}`;
+  const args = ['status', '--porcelain=v2', '-z', untracked];
+  if (pathspec.length) args.push('--', ...pathspec);
+  const status = parseStatus(await runGit(repo.root, args, opts));
+
+  const beforePath = (file) => file.originalPath || file.path;
+  const hasBefore = (file) => Boolean(commit) && !['untracked', 'added'].includes(changeFromHead(file));
+  const specs = status.files.filter(hasBefore).map((file) => `${commit}:${beforePath(file)}`);
+  const blobs = await readBlobs(repo.root, specs, opts.timeoutMs);
+
+  const files = [];
+  const totals = { files: 0, linesAdded: 0, linesRemoved: 0 };
+  for (const file of status.files) {
+    if (file.status === 'ignored') continue;
+    const entry = {
+      path: file.path,
+      originalPath: file.originalPath || null,
+      status: changeFromHead(file),
+      binary: false,
+      size: 0,
+      skipped: null,
+      diff: null,
+    };
+    files.push(entry);
+    totals.files++;
+
+    const beforeBytes = hasBefore(file) ? blobs.get(`${commit}:${beforePath(file)}`) || null : null;
+    let afterBytes = null;
+    // A file deleted from the index but still on disk is listed again as untracked, with its content
+    const stat = entry.status === 'deleted' ? null : await statFile(path.join(repo.root, file.path));
+    if (stat?.isDirectory()) {
+      entry.skipped = 'submodule';
+      continue;
+    }
+    if (stat) {
+      entry.size = stat.size;
+      if (stat.size > opts.maxFileBytes) {
+        entry.skipped = 'too-large';
+        continue;
+      }
+      afterBytes = await fs.promises.readFile(path.join(repo.root, file.path)).catch(() => null);
+    }
+    if (beforeBytes && beforeBytes.length > opts.maxFileBytes) {
+      entry.skipped = 'too-large';
+      continue;
+    }
+
+    const before = decode(beforeBytes);
+    const after = decode(afterBytes);
+    entry.binary = before.binary || after.binary;
+    if (entry.binary) continue;
+    entry.diff = diffFile(before.text, after.text, opts);
+    totals.linesAdded += entry.diff.linesAdded;
+    totals.linesRemoved += entry.diff.linesRemoved;
+    if (opts.includeContent) {
+      entry.beforeContent = beforeBytes ? before.text : null;
+      entry.afterContent = afterBytes ? after.text : null;
+    }
+  }
+
+  return { root: repo.root, branch: head.branch, commit, capturedAt, files, totals };
+}
+
+module.exports = {
+  diffWorktree,
+  DEFAULT_OPTIONS,
+};
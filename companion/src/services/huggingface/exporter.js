@@ -6,6 +6,9 @@
 const fs = require('fs');
 const path = require('path');
 const { promisify } = require('util');
+const { startOperation, throwIfCancelled } = require('../../utils/operation-handle');
+const { isCompanionError, ERROR_CODES } = require('../../utils/errors');
+const { anonymizePaths } = require('../../utils/path-anonymizer');
 const writeFileAsync = promisify(fs.writeFile);
 const mkdirAsync = promisify(fs.mkdir);
 
@@ -25,16 +28,28 @@ class HuggingFaceExporter {
 
   /**
    * Export telemetry data in Hugging Face Dataset format
+   * @param {object} options - { signal, onProgress }; a cancelled export removes its partial output
    */
-  async exportToHuggingFaceFormat(outputDir) {
+  async exportToHuggingFaceFormat(outputDir, options = {}) {
+    const { signal = null, onProgress = null } = options;
+    const stages = ['collect', 'write', 'card', 'script'];
+    const step = (stage) => {
+      throwIfCancelled(signal);
+      if (onProgress) onProgress({ completed: stages.indexOf(stage), total: stages.length, stage });
+    };
     console.log('[HF-EXPORT] Starting Hugging Face export...');
 
     try {
       await mkdirAsync(outputDir, { recursive: true });
+      step('collect');
       const splits = await this.createDatasetSplits();
+      step('write');
       await this.writeDatasetFiles(outputDir, splits);
+      step('card');
       await this.generateDatasetCard(outputDir, splits);
+      step('script');
       await this.generateDatasetScript(outputDir);
+      if (onProgress) onProgress({ completed: stages.length, total: stages.length, stage: 'done' });
 
       console.log(`[HF-EXPORT] Export complete! Output: ${outputDir}`);
       return {
@@ -49,11 +64,32 @@ class HuggingFaceExporter {
         ],
       };
     } catch (error) {
-      console.error('[HF-EXPORT] Export failed:', error);
+      if (isCompanionError(error, ERROR_CODES.CANCELLED)) {
+        console.log('[HF-EXPORT] Export cancelled, removing partial output');
+        await fs.promises.rm(outputDir, { recursive: true, force: true });
+      } else {
+        console.error('[HF-EXPORT] Export failed:', error);
+      }
       throw error;
     }
   }
 
+  /**
+   * Run exportToHuggingFaceFormat as a cancellable operation
+   * @returns {OperationHandle}
+   */
+  startExport(outputDir, options = {}) {
+    return startOperation(
+      'hf-export',
+      (handle) =>
+        this.exportToHuggingFaceFormat(outputDir, {
+          signal: handle.signal,
+          onProgress: (progress) => handle.report(progress),
+        }),
+      options
+    );
+  }
+
   async createDatasetSplits() {
     let entries;
     if (this.options.since || this.options.until) {
@@ -160,13 +196,9 @@ class HuggingFaceExporter {
     };
   }
 
+  // Keyed per-component pseudonyms, the same mapping every other exporter uses
   anonymizeFilePath(filePath) {
-    let anonymized = filePath
-      .replace(/\/Users\/[^\/]+\//g, '/Users/<username>/')
-      .replace(/\/home\/[^\/]+\//g, '/home/<username>/');
-    const parts = anonymized.split('/');
-    if (parts.length > 3) return `<workspace>/${parts.slice(-3).join('/')}`;
-    return anonymized;
+    return anonymizePaths(filePath);
   }
 
   detectFileType(filePath) {
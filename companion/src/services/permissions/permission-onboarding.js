@@ -0,0 +1,584 @@
+/**
+ * Permission Onboarding
+ * Sequences OS permission checks and requests for the onboarding wizard:
+ * check → trigger prompt → poll for grant → report, one step at a time,
+ * emitting progress events instead of leaving the wizard to juggle timers
+ * around subprocess calls.
+ *
+ * A step is either the id of a registered step or an inline definition:
+ *   { id, label, check: async () => status, request: async () => void,
+ *     settingsUrl, required, pollIntervalMs, timeoutMs }
+ * where status is 'granted' | 'denied' | 'not-determined' | 'unsupported',
+ * or { status, details } when the check has more to say (the session type,
+ * a limit and its recommended value).
+ *
+ * A check that throws is reported with its CompanionError code, so the wizard
+ * can tell a denied permission from a missing osascript or a hung prompt.
+ *
+ * Accessibility, Screen Recording and Input Monitoring are read with
+ * AXIsProcessTrusted, CGPreflightScreenCaptureAccess and IOHIDCheckAccess:
+ * none of them prompts, writes a file or sends an Apple event. Only
+ * request() prompts (AXIsProcessTrustedWithOptions,
+ * CGRequestScreenCaptureAccess, IOHIDRequestAccess).
+ * The checks are called in-process through koffi (an optional FFI package,
+ * prebuilt for macOS, so installing it needs no compiler) and cost
+ * microseconds. Without it they go through the JavaScript for Automation ObjC
+ * bridge, an osascript subprocess costing about 300ms; one call answers all
+ * three, and checks within TCC_CACHE_MS of each other share it.
+ * watchPermissions() polls every 2s in-process and every 5s through osascript.
+ * Full Disk Access has no API; it's granted exactly when the TCC database
+ * itself can be opened.
+ *
+ * watchPermissions() re-runs checks on an interval and reports changes, so a
+ * grant made in System Settings takes effect without a restart. Where the
+ * TCC database directories are readable (Full Disk Access), changes to them
+ * trigger an immediate re-check as well.
+ */
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { exec, execFile } = require('child_process');
+const { promisify } = require('util');
+const { CompanionError, ERROR_CODES, fromSystemError } = require('../../utils/errors');
+
+const execAsync = promisify(exec);
+const execFileAsync = promisify(execFile);
+
+const DEFAULT_POLL_INTERVAL_MS = 1000;
+const DEFAULT_TIMEOUT_MS = 120000;
+const DEFAULT_WATCH_INTERVAL_MS = 2000;
+const OSASCRIPT_WATCH_INTERVAL_MS = 5000; // Each pass spawns osascript on macOS
+const WATCH_DEBOUNCE_MS = 250;
+
+// Per-user (Full Disk Access, Automation, ...) and system-wide (Accessibility, Screen Recording) grants
+const TCC_DIRECTORIES = [
+  path.join(os.homedir(), 'Library', 'Application Support', 'com.apple.TCC'),
+  '/Library/Application Support/com.apple.TCC',
+];
+
+const STATUS = {
+  GRANTED: 'granted',
+  DENIED: 'denied',
+  NOT_DETERMINED: 'not-determined',
+  UNSUPPORTED: 'unsupported',
+};
+
+// AppleScript errors meaning the OS refused: -1743 (Apple events not authorized),
+// -25211 / -1719 (assistive access not allowed)
+const OSA_PERMISSION_ERRORS = /-1743|-25211|-1719/;
+
+/**
+ * Run an AppleScript snippet
+ * Rejects with a CompanionError: PERMISSION_DENIED, UNAVAILABLE (no osascript), TIMEOUT, or IO
+ */
+async function osascript(script) {
+  try {
+    return await execAsync(`osascript -e '${script.replace(/'/g, "'\\''")}'`, { timeout: 10000 });
+  } catch (error) {
+    const stderr = String(error.stderr || '');
+    if (OSA_PERMISSION_ERRORS.test(stderr || error.message)) {
+      throw new CompanionError(ERROR_CODES.PERMISSION_DENIED, stderr.trim() || error.message, {
+        cause: error,
+        reason: (stderr.match(/-\d{4,5}/) || [null])[0],
+      });
+    }
+    throw fromSystemError(error, { command: 'osascript' });
+  }
+}
+
+/**
+ * Run a JavaScript for Automation snippet (no shell, no temp file) and parse its JSON result
+ * Rejects with a CompanionError: UNAVAILABLE (no osascript), TIMEOUT, or IO
+ */
+async function jxa(script) {
+  try {
+    const args = ['-l', 'JavaScript', '-e', script];
+    const { stdout } = await execFileAsync('osascript', args, { timeout: 10000 });
+    return JSON.parse(stdout.trim() || 'null');
+  } catch (error) {
+    throw fromSystemError(error, { command: 'osascript -l JavaScript' });
+  }
+}
+
+// kIOHIDRequestTypeListenEvent, and the IOHIDAccessType answers: Granted (0), Denied (1), Unknown (2)
+const HID_LISTEN_EVENT = 1;
+
+const TCC_PREFLIGHT_SCRIPT = `
+ObjC.import('ApplicationServices');
+ObjC.import('CoreGraphics');
+ObjC.import('IOKit');
+let inputMonitoring = null;
+try {
+  ObjC.bindFunction('IOHIDCheckAccess', ['int', ['int']]);
+  inputMonitoring = $.IOHIDCheckAccess(${HID_LISTEN_EVENT});
+} catch (error) {}
+JSON.stringify({
+  accessibility: $.AXIsProcessTrusted(),
+  // Added in macOS 10.15, together with the permissions
+  screenRecording: typeof $.CGPreflightScreenCaptureAccess === 'function'
+    ? $.CGPreflightScreenCaptureAccess()
+    : null,
+  inputMonitoring,
+});
+`;
+
+let nativeTcc; // Preflight function, null without koffi; undefined until first loaded
+
+/**
+ * In-process AXIsProcessTrusted / CGPreflightScreenCaptureAccess / IOHIDCheckAccess, or null when
+ * koffi isn't installed (or this isn't macOS) and the osascript bridge has to be used instead
+ */
+function loadNativeTcc() {
+  if (nativeTcc !== undefined) return nativeTcc;
+  nativeTcc = null;
+  if (process.platform !== 'darwin') return null;
+  let koffi;
+  try {
+    koffi = require('koffi');
+  } catch {
+    return null;
+  }
+  try {
+    const framework = (name) => koffi.load(`/System/Library/Frameworks/${name}.framework/${name}`);
+    // Missing before macOS 10.15, like the permissions themselves
+    const optional = (name, prototype) => {
+      try {
+        return framework(name).func(prototype);
+      } catch {
+        return null;
+      }
+    };
+    const isTrusted = framework('ApplicationServices').func('bool AXIsProcessTrusted()');
+    const preflightScreen = optional('CoreGraphics', 'bool CGPreflightScreenCaptureAccess()');
+    const checkHidAccess = optional('IOKit', 'int IOHIDCheckAccess(int)');
+    nativeTcc = () => ({
+      accessibility: Boolean(isTrusted()),
+      screenRecording: preflightScreen ? Boolean(preflightScreen()) : null,
+      inputMonitoring: checkHidAccess ? checkHidAccess(HID_LISTEN_EVENT) : null,
+    });
+  } catch (error) {
+    console.warn('[PERMISSIONS] koffi is installed but the TCC functions could not be bound:', error.message);
+  }
+  return nativeTcc;
+}
+
+// Checks within this window share one bridge call (all three steps, or a check right after another)
+const TCC_CACHE_MS = 500;
+let tccPreflight = null; // { promise, at }
+
+/**
+ * Current Accessibility, Screen Recording and Input Monitoring grants, without prompting; in-process
+ * when koffi is available, otherwise one osascript subprocess (about 300ms) unless a call within
+ * TCC_CACHE_MS can be reused
+ * @returns {Promise<object>} { accessibility: boolean, screenRecording: boolean|null,
+ *   inputMonitoring: 0|1|2|null } - inputMonitoring is an IOHIDAccessType; screenRecording and
+ *   inputMonitoring are null before macOS 10.15, where there are no such permissions
+ */
+function readTccPreflight() {
+  const native = loadNativeTcc();
+  if (native) {
+    try {
+      return Promise.resolve(native());
+    } catch (error) {
+      return Promise.reject(new CompanionError(ERROR_CODES.IO, 'TCC preflight failed', { cause: error }));
+    }
+  }
+  if (tccPreflight && Date.now() - tccPreflight.at < TCC_CACHE_MS) return tccPreflight.promise;
+  const promise = jxa(TCC_PREFLIGHT_SCRIPT);
+  tccPreflight = { promise, at: Date.now() };
+  promise.catch(() => {
+    if (tccPreflight && tccPreflight.promise === promise) tccPreflight = null;
+  });
+  return promise;
+}
+
+/**
+ * Full Disk Access: the TCC database can only be opened with it
+ */
+function checkFullDiskAccess() {
+  let missing = 0;
+  for (const dir of TCC_DIRECTORIES) {
+    try {
+      fs.closeSync(fs.openSync(path.join(dir, 'TCC.db'), 'r'));
+      return STATUS.GRANTED;
+    } catch (error) {
+      if (error.code === 'EPERM' || error.code === 'EACCES') return STATUS.DENIED;
+      if (error.code !== 'ENOENT') throw fromSystemError(error, { path: path.join(dir, 'TCC.db') });
+      missing++;
+    }
+  }
+  return missing === TCC_DIRECTORIES.length ? STATUS.NOT_DETERMINED : STATUS.DENIED;
+}
+
+function openSettings(url) {
+  if (process.platform !== 'darwin' || !url) return Promise.resolve();
+  return execAsync(`open "${url}"`).catch(() => {});
+}
+
+/**
+ * Built-in steps. Platform-specific checks register additional steps
+ * with registerPermissionStep().
+ */
+const STEPS = {
+  accessibility: {
+    label: 'Accessibility',
+    platforms: ['darwin'],
+    settingsUrl: 'x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility',
+    async check() {
+      const { accessibility } = await readTccPreflight();
+      return accessibility ? STATUS.GRANTED : STATUS.DENIED;
+    },
+    async request() {
+      // Adds the app to the Accessibility list and shows the system prompt
+      const prompt = '$.AXIsProcessTrustedWithOptions($({ AXTrustedCheckOptionPrompt: true }))';
+      await jxa(`ObjC.import('ApplicationServices'); ${prompt};`).catch(() => {});
+      tccPreflight = null;
+      await openSettings(this.settingsUrl);
+    },
+  },
+  'screen-recording': {
+    label: 'Screen Recording',
+    platforms: ['darwin'],
+    settingsUrl: 'x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture',
+    async check() {
+      const { screenRecording } = await readTccPreflight();
+      if (screenRecording === null) return STATUS.UNSUPPORTED;
+      return screenRecording ? STATUS.GRANTED : STATUS.DENIED;
+    },
+    async request() {
+      // Prompts once per app; after that the grant can only be changed in System Settings
+      await jxa("ObjC.import('CoreGraphics'); $.CGRequestScreenCaptureAccess();").catch(() => {});
+      tccPreflight = null;
+      await openSettings(this.settingsUrl);
+    },
+  },
+  'input-monitoring': {
+    label: 'Input Monitoring',
+    platforms: ['darwin'],
+    settingsUrl: 'x-apple.systempreferences:com.apple.preference.security?Privacy_ListenEvent',
+    async check() {
+      const { inputMonitoring } = await readTccPreflight();
+      if (inputMonitoring === null) return STATUS.UNSUPPORTED;
+      if (inputMonitoring === 0) return STATUS.GRANTED;
+      return inputMonitoring === 1 ? STATUS.DENIED : STATUS.NOT_DETERMINED;
+    },
+    async request() {
+      // Prompts while the status is not determined; adds the app to the list either way
+      const bind = "ObjC.import('IOKit'); ObjC.bindFunction('IOHIDRequestAccess', ['bool', ['int']]);";
+      await jxa(`${bind} $.IOHIDRequestAccess(${HID_LISTEN_EVENT});`).catch(() => {});
+      tccPreflight = null;
+      await openSettings(this.settingsUrl);
+    },
+  },
+  'full-disk-access': {
+    label: 'Full Disk Access',
+    platforms: ['darwin'],
+    settingsUrl: 'x-apple.systempreferences:com.apple.preference.security?Privacy_AllFiles',
+    async check() {
+      return checkFullDiskAccess();
+    },
+    async request() {
+      // No prompt exists: the user adds the app in System Settings
+      await openSettings(this.settingsUrl);
+    },
+  },
+  automation: {
+    label: 'Automation (System Events)',
+    platforms: ['darwin'],
+    settingsUrl: 'x-apple.systempreferences:com.apple.preference.security?Privacy_Automation',
+    async check() {
+      try {
+        await osascript('tell application "System Events" to get name');
+        return STATUS.GRANTED;
+      } catch (error) {
+        if (error.code === ERROR_CODES.PERMISSION_DENIED) return STATUS.DENIED;
+        throw error;
+      }
+    },
+    async request() {
+      // The first Apple event to System Events triggers the consent prompt
+      await osascript('tell application "System Events" to get name').catch(() => {});
+    },
+  },
+};
+
+/**
+ * Register (or override) a permission step
+ */
+function registerPermissionStep(id, definition) {
+  if (!definition || typeof definition.check !== 'function') {
+    throw new CompanionError(ERROR_CODES.INVALID_INPUT, `Permission step ${id} needs a check() function`);
+  }
+  STEPS[id] = { label: id, ...definition };
+}
+
+function resolveStep(step) {
+  if (typeof step === 'string') {
+    const definition = STEPS[step];
+    if (!definition) throw new CompanionError(ERROR_CODES.NOT_FOUND, `Unknown permission step: ${step}`);
+    return { id: step, ...definition };
+  }
+  if (!step || !step.id || typeof step.check !== 'function') {
+    const message = 'Inline permission steps need an id and a check() function';
+    throw new CompanionError(ERROR_CODES.INVALID_INPUT, message);
+  }
+  return { label: step.id, ...step };
+}
+
+function sleep(ms, signal) {
+  return new Promise((resolve) => {
+    const timer = setTimeout(resolve, ms);
+    const onAbort = () => {
+      clearTimeout(timer);
+      resolve();
+    };
+    if (signal) signal.addEventListener('abort', onAbort, { once: true });
+  });
+}
+
+/**
+ * Run a step's check, turning a thrown error into a status plus its classified error
+ * @returns {Promise<object>} { status, details, error } where error is a CompanionError's toJSON()
+ *   or null
+ */
+async function safeCheck(step) {
+  try {
+    const result = await step.check();
+    if (result && typeof result === 'object') {
+      return { status: result.status, details: result.details || null, error: null };
+    }
+    return { status: result, details: null, error: null };
+  } catch (error) {
+    const classified = fromSystemError(error);
+    const status = classified.code === ERROR_CODES.UNAVAILABLE ? STATUS.UNSUPPORTED : STATUS.NOT_DETERMINED;
+    return { status, details: null, error: classified.toJSON() };
+  }
+}
+
+/**
+ * Run one step: check, prompt if needed, then poll until granted or timed out
+ */
+async function runStep(step, emit, options) {
+  const started = Date.now();
+  const report = (phase, extra = {}) =>
+    emit({ step: step.id, label: step.label, phase, elapsedMs: Date.now() - started, ...extra });
+
+  if (step.platforms && !step.platforms.includes(process.platform)) {
+    report('skipped', { status: STATUS.UNSUPPORTED });
+    return { id: step.id, status: STATUS.UNSUPPORTED, skipped: true };
+  }
+
+  report('checking');
+  let { status, details, error } = await safeCheck(step);
+  if (status === STATUS.GRANTED || status === STATUS.UNSUPPORTED) {
+    report(status === STATUS.GRANTED ? 'already-granted' : 'skipped', { status, details, error });
+    return { id: step.id, status, details, prompted: false, error };
+  }
+
+  if (options.checkOnly || typeof step.request !== 'function') {
+    report('denied', { status, details, error });
+    return { id: step.id, status, details, prompted: false, error };
+  }
+
+  report('requesting', { status, settingsUrl: step.settingsUrl || null });
+  try {
+    await step.request();
+  } catch (requestError) {
+    report('error', { status, error: fromSystemError(requestError).toJSON() });
+  }
+
+  const pollIntervalMs = step.pollIntervalMs || options.pollIntervalMs || DEFAULT_POLL_INTERVAL_MS;
+  const timeoutMs = step.timeoutMs || options.timeoutMs || DEFAULT_TIMEOUT_MS;
+  const deadline = started + timeoutMs;
+
+  while (Date.now() < deadline) {
+    if (options.signal && options.signal.aborted) {
+      report('cancelled', { status });
+      return { id: step.id, status, prompted: true, cancelled: true };
+    }
+    await sleep(pollIntervalMs, options.signal);
+    ({ status, details, error } = await safeCheck(step));
+    if (status === STATUS.GRANTED) {
+      report('granted', { status, details });
+      return { id: step.id, status, details, prompted: true };
+    }
+    report('waiting', { status, error, remainingMs: Math.max(0, deadline - Date.now()) });
+  }
+
+  report('timeout', { status, error });
+  return { id: step.id, status, prompted: true, timedOut: true, error };
+}
+
+/**
+ * Sequence permission checks/requests for onboarding
+ * @param {Array<string|object>} steps - Registered step ids or inline step definitions
+ * @param {Function} callback - Receives progress events { step, label, phase, status, elapsedMs, ... }
+ * @param {object} options - { checkOnly, pollIntervalMs, timeoutMs, stopOnRequiredFailure, signal }
+ * @returns {Promise<object>} { complete, granted, missing, results }
+ */
+async function runPermissionOnboarding(steps, callback = () => {}, options = {}) {
+  const resolved = steps.map(resolveStep);
+  const results = [];
+  const emit = (event) => {
+    try {
+      callback({ ...event, index: results.length, total: resolved.length });
+    } catch (error) {
+      console.warn('[PERMISSIONS] Onboarding callback threw:', error.message);
+    }
+  };
+
+  for (const step of resolved) {
+    const result = await runStep(step, emit, options);
+    results.push({ ...result, required: step.required !== false });
+
+    if (result.cancelled) break;
+    if (
+      options.stopOnRequiredFailure &&
+      step.required !== false &&
+      result.status !== STATUS.GRANTED &&
+      !result.skipped
+    ) {
+      break;
+    }
+  }
+
+  const granted = results.filter((r) => r.status === STATUS.GRANTED).map((r) => r.id);
+  const missing = results
+    .filter((r) => r.required && !r.skipped && r.status !== STATUS.GRANTED)
+    .map((r) => r.id);
+
+  const summary = {
+    complete: missing.length === 0 && results.length === resolved.length,
+    granted,
+    missing,
+    results,
+  };
+  emit({ step: null, phase: 'done', summary });
+  return summary;
+}
+
+/**
+ * Report permission status changes as they happen
+ * @param {Function} callback - Receives { permission, label, status, previous, details, error } for every
+ *   change; previous is null for the first check when emitInitial is set
+ * @param {object} options - { permissions: step ids (default Full Disk Access, Accessibility,
+ *   Screen Recording and Input Monitoring), intervalMs, emitInitial, signal }
+ * @returns {object} { stop(), refresh(): Promise, current(): { id: status } }
+ */
+function watchPermissions(callback, options = {}) {
+  const {
+    permissions = ['full-disk-access', 'accessibility', 'screen-recording', 'input-monitoring'],
+    intervalMs = loadNativeTcc() || process.platform !== 'darwin'
+      ? DEFAULT_WATCH_INTERVAL_MS
+      : OSASCRIPT_WATCH_INTERVAL_MS,
+    emitInitial = false,
+    signal = null,
+  } = options;
+  const steps = permissions
+    .map(resolveStep)
+    .filter((step) => !step.platforms || step.platforms.includes(process.platform));
+  const statuses = {};
+  const watchers = [];
+  let timer = null;
+  let debounce = null;
+  let running = null;
+  let rerun = false;
+  let stopped = false;
+
+  const emit = (event) => {
+    try {
+      callback(event);
+    } catch (error) {
+      console.warn('[PERMISSIONS] Permission watch callback threw:', error.message);
+    }
+  };
+
+  const checkAll = async () => {
+    for (const step of steps) {
+      const { status, details, error } = await safeCheck(step);
+      if (stopped) return;
+      const previous = step.id in statuses ? statuses[step.id] : null;
+      statuses[step.id] = status;
+      if (previous === status || (previous === null && !emitInitial)) continue;
+      emit({ permission: step.id, label: step.label, status, previous, details, error });
+    }
+  };
+
+  // One pass at a time; a request during a pass runs another right after it
+  const refresh = () => {
+    if (stopped) return Promise.resolve();
+    if (running) {
+      rerun = true;
+      return running;
+    }
+    running = checkAll()
+      .catch((error) => console.warn('[PERMISSIONS] Permission check failed:', error.message))
+      .finally(() => {
+        running = null;
+        if (rerun && !stopped) {
+          rerun = false;
+          refresh();
+        }
+      });
+    return running;
+  };
+
+  const watchTccDirectories = () => {
+    for (const dir of TCC_DIRECTORIES) {
+      if (watchers.some((watcher) => watcher.dir === dir)) continue;
+      try {
+        const watcher = fs.watch(dir, { persistent: false }, () => {
+          clearTimeout(debounce);
+          debounce = setTimeout(refresh, WATCH_DEBOUNCE_MS);
+        });
+        watcher.on('error', () => watcher.close());
+        watcher.dir = dir;
+        watchers.push(watcher);
+      } catch {
+        // Unreadable without Full Disk Access; polling covers it
+      }
+    }
+  };
+
+  const stop = () => {
+    stopped = true;
+    clearInterval(timer);
+    clearTimeout(debounce);
+    watchers.splice(0).forEach((watcher) => watcher.close());
+  };
+
+  if (steps.length > 0) {
+    if (process.platform === 'darwin') watchTccDirectories();
+    timer = setInterval(() => {
+      // Full Disk Access granted since the start makes the directories watchable
+      if (process.platform === 'darwin' && watchers.length < TCC_DIRECTORIES.length) watchTccDirectories();
+      refresh();
+    }, intervalMs);
+    timer.unref();
+    refresh();
+  }
+  if (signal) signal.addEventListener('abort', stop, { once: true });
+
+  return { stop, refresh, current: () => ({ ...statuses }) };
+}
+
+function listPermissionSteps() {
+  return Object.entries(STEPS).map(([id, step]) => ({
+    id,
+    label: step.label,
+    platforms: step.platforms || null,
+    settingsUrl: step.settingsUrl || null,
+  }));
+}
+
+module.exports = {
+  runPermissionOnboarding,
+  registerPermissionStep,
+  listPermissionSteps,
+  watchPermissions,
+  STATUS,
+};
+
+// Windows and Linux steps register themselves with registerPermissionStep()
+require('./platform-permissions');
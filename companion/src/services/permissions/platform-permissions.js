@@ -0,0 +1,226 @@
+/**
+ * Platform Permissions
+ * Permission and capability steps for Windows and Linux, registered with the
+ * onboarding steps so checks off macOS say something instead of being skipped.
+ *
+ *   windows-elevation   the process runs elevated (an administrator token);
+ *                       only needed to observe elevated editor windows
+ *   windows-ui-access   the token has UIAccess, which lets UI Automation read
+ *                       windows above the process's integrity level
+ *   editor-data         Cursor's user data directory exists and is readable
+ *                       (all platforms; unsupported when Cursor isn't installed)
+ *   screen-capture      Linux: an X11 session can be captured directly; under
+ *                       Wayland every capture goes through the ScreenCast
+ *                       portal, so the status is not-determined when the portal
+ *                       is there and denied when it isn't
+ *   file-watch-limits   Linux: inotify watch and instance limits high enough
+ *                       to watch a large workspace
+ *
+ * None of these has a prompt: elevation and UIAccess are decided when the
+ * process starts and limits are changed with sysctl, so details say what
+ * to do instead.
+ */
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { execFile } = require('child_process');
+const { promisify } = require('util');
+const { registerPermissionStep, STATUS } = require('./permission-onboarding');
+const { fromSystemError } = require('../../utils/errors');
+
+const execFileAsync = promisify(execFile);
+
+// Below this a workspace with node_modules runs out of watches
+const MIN_INOTIFY_WATCHES = 65536;
+const RECOMMENDED_INOTIFY_WATCHES = 524288;
+const MIN_INOTIFY_INSTANCES = 128;
+
+// Checks within this window share one PowerShell call
+const TOKEN_CACHE_MS = 500;
+let tokenInfo = null; // { promise, at }
+
+// TokenUIAccess is TOKEN_INFORMATION_CLASS 26; IsInRole(Administrator) is only true for an elevated token
+const TOKEN_SCRIPT = `
+$identity = [Security.Principal.WindowsIdentity]::GetCurrent()
+$principal = [Security.Principal.WindowsPrincipal]$identity
+$elevated = $principal.IsInRole([Security.Principal.WindowsBuiltInRole]::Administrator)
+Add-Type -Namespace CursorTelemetry -Name Token -MemberDefinition @'
+[DllImport("advapi32.dll", SetLastError = true)]
+public static extern bool GetTokenInformation(
+  IntPtr token, int infoClass, out int value, int length, out int returned);
+'@
+$value = 0
+$returned = 0
+$ok = [CursorTelemetry.Token]::GetTokenInformation($identity.Token, 26, [ref]$value, 4, [ref]$returned)
+@{ elevated = $elevated; uiAccess = ($ok -and $value -ne 0) } | ConvertTo-Json -Compress
+`;
+
+/**
+ * Elevation and UIAccess of the current process token
+ * @returns {Promise<object>} { elevated, uiAccess }
+ */
+function readTokenInfo() {
+  if (tokenInfo && Date.now() - tokenInfo.at < TOKEN_CACHE_MS) return tokenInfo.promise;
+  const args = ['-NoProfile', '-NonInteractive', '-Command', TOKEN_SCRIPT];
+  const promise = execFileAsync('powershell.exe', args, { timeout: 15000, windowsHide: true })
+    .then(({ stdout }) => JSON.parse(stdout.trim()))
+    .catch((error) => {
+      if (tokenInfo && tokenInfo.promise === promise) tokenInfo = null;
+      throw fromSystemError(error, { command: 'powershell.exe' });
+    });
+  tokenInfo = { promise, at: Date.now() };
+  return promise;
+}
+
+/**
+ * Cursor's user data directory on this platform
+ */
+function editorDataDirectory() {
+  const home = os.homedir();
+  if (process.platform === 'darwin') {
+    return path.join(home, 'Library', 'Application Support', 'Cursor', 'User');
+  }
+  if (process.platform === 'win32') {
+    return path.join(process.env.APPDATA || path.join(home, 'AppData', 'Roaming'), 'Cursor', 'User');
+  }
+  return path.join(process.env.XDG_CONFIG_HOME || path.join(home, '.config'), 'Cursor', 'User');
+}
+
+function readProcNumber(file) {
+  try {
+    const value = Number(fs.readFileSync(file, 'utf8').trim());
+    return Number.isFinite(value) ? value : null;
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * Whether the ScreenCast portal answers on the session bus
+ * @returns {Promise<number|null>} Its interface version, or null when it's missing
+ */
+async function screenCastPortalVersion() {
+  const args = [
+    'call',
+    '--session',
+    '--dest',
+    'org.freedesktop.portal.Desktop',
+    '--object-path',
+    '/org/freedesktop/portal/desktop',
+    '--method',
+    'org.freedesktop.DBus.Properties.Get',
+    'org.freedesktop.portal.ScreenCast',
+    'version',
+  ];
+  try {
+    const { stdout } = await execFileAsync('gdbus', args, { timeout: 5000 });
+    // "(<uint32 4>,)"
+    const match = /uint32\s+(\d+)/.exec(stdout);
+    return match ? Number(match[1]) : 1;
+  } catch (error) {
+    if (error.code === 'ENOENT') throw fromSystemError(error, { command: 'gdbus' });
+    return null;
+  }
+}
+
+const PLATFORM_STEPS = {
+  'windows-elevation': {
+    label: 'Administrator (elevated)',
+    platforms: ['win32'],
+    required: false,
+    async check() {
+      const { elevated } = await readTokenInfo();
+      return {
+        status: elevated ? STATUS.GRANTED : STATUS.DENIED,
+        details: elevated ? null : { hint: 'Start the companion from an elevated terminal' },
+      };
+    },
+  },
+  'windows-ui-access': {
+    label: 'UI Automation access (UIAccess)',
+    platforms: ['win32'],
+    required: false,
+    async check() {
+      const { uiAccess } = await readTokenInfo();
+      return {
+        status: uiAccess ? STATUS.GRANTED : STATUS.DENIED,
+        details: uiAccess ? null : { hint: 'UIAccess needs a signed build installed under Program Files' },
+      };
+    },
+  },
+  'editor-data': {
+    label: 'Editor data directory',
+    platforms: ['darwin', 'win32', 'linux'],
+    async check() {
+      const directory = editorDataDirectory();
+      try {
+        fs.accessSync(directory, fs.constants.R_OK);
+        fs.readdirSync(directory);
+        return { status: STATUS.GRANTED, details: { directory } };
+      } catch (error) {
+        if (error.code === 'ENOENT') {
+          return { status: STATUS.UNSUPPORTED, details: { directory, installed: false } };
+        }
+        if (error.code === 'EPERM' || error.code === 'EACCES') {
+          return { status: STATUS.DENIED, details: { directory } };
+        }
+        throw fromSystemError(error, { path: directory });
+      }
+    },
+  },
+  'screen-capture': {
+    label: 'Screen capture',
+    platforms: ['linux'],
+    required: false,
+    async check() {
+      const { XDG_SESSION_TYPE, WAYLAND_DISPLAY, DISPLAY } = process.env;
+      const session =
+        XDG_SESSION_TYPE === 'wayland' || (!XDG_SESSION_TYPE && WAYLAND_DISPLAY)
+          ? 'wayland'
+          : DISPLAY
+            ? 'x11'
+            : null;
+      if (session === 'x11') return { status: STATUS.GRANTED, details: { session, display: DISPLAY } };
+      if (session !== 'wayland') {
+        return { status: STATUS.UNSUPPORTED, details: { session: XDG_SESSION_TYPE || null } };
+      }
+
+      const portalVersion = await screenCastPortalVersion();
+      return {
+        // The portal asks the user on every capture
+        status: portalVersion ? STATUS.NOT_DETERMINED : STATUS.DENIED,
+        details: { session, portal: Boolean(portalVersion), portalVersion, xwayland: Boolean(DISPLAY) },
+      };
+    },
+  },
+  'file-watch-limits': {
+    label: 'File watch limits (inotify)',
+    platforms: ['linux'],
+    required: false,
+    async check() {
+      const maxUserWatches = readProcNumber('/proc/sys/fs/inotify/max_user_watches');
+      const maxUserInstances = readProcNumber('/proc/sys/fs/inotify/max_user_instances');
+      if (maxUserWatches === null) return { status: STATUS.UNSUPPORTED, details: null };
+      const enough =
+        maxUserWatches >= MIN_INOTIFY_WATCHES &&
+        (maxUserInstances === null || maxUserInstances >= MIN_INOTIFY_INSTANCES);
+      return {
+        status: enough ? STATUS.GRANTED : STATUS.DENIED,
+        details: {
+          maxUserWatches,
+          maxUserInstances,
+          recommendedWatches: RECOMMENDED_INOTIFY_WATCHES,
+          hint: enough ? null : `sudo sysctl fs.inotify.max_user_watches=${RECOMMENDED_INOTIFY_WATCHES}`,
+        },
+      };
+    },
+  },
+};
+
+for (const [id, definition] of Object.entries(PLATFORM_STEPS)) registerPermissionStep(id, definition);
+
+module.exports = {
+  editorDataDirectory,
+  PLATFORM_STEPS,
+};
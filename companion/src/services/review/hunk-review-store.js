@@ -0,0 +1,417 @@
+/**
+ * Hunk Review Store
+ * Persists review state for individual diff hunks (unreviewed / kept /
+ * reverted / modified) so the "review AI changes" panel survives restarts.
+ *
+ * Hunks are keyed by a content fingerprint (file path + removed lines +
+ * added lines, whitespace-normalized), not by position, so the key stays
+ * stable while surrounding code moves. Later snapshots of the file update
+ * states automatically: a hunk whose added lines disappear and whose removed
+ * lines return is reverted, one that is partially rewritten is modified, and
+ * one that stays intact for autoKeepAfterMs is kept.
+ */
+
+const sqlite3 = require('sqlite3');
+const fs = require('fs');
+const path = require('path');
+const diff = require('diff');
+const { hashContent } = require('../../utils/content-hash');
+
+const STATES = {
+  UNREVIEWED: 'unreviewed',
+  KEPT: 'kept',
+  REVERTED: 'reverted',
+  MODIFIED: 'modified',
+};
+
+const DEFAULT_OPTIONS = {
+  autoKeepAfterMs: 30 * 60 * 1000, // Intact for 30 minutes of snapshots counts as kept (0 disables)
+};
+
+const SCHEMA = [
+  `CREATE TABLE IF NOT EXISTS hunk_reviews (
+    fingerprint TEXT PRIMARY KEY,
+    file_path TEXT NOT NULL,
+    state TEXT NOT NULL,
+    origin TEXT,
+    prompt_id TEXT,
+    removed_text TEXT NOT NULL,
+    added_text TEXT NOT NULL,
+    start_line INTEGER,
+    presence REAL NOT NULL DEFAULT 1,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    last_checked_at INTEGER,
+    manual INTEGER NOT NULL DEFAULT 0
+  )`,
+  `CREATE TABLE IF NOT EXISTS hunk_review_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    fingerprint TEXT NOT NULL,
+    from_state TEXT,
+    to_state TEXT NOT NULL,
+    reason TEXT,
+    timestamp INTEGER NOT NULL
+  )`,
+  'CREATE INDEX IF NOT EXISTS idx_hunk_reviews_file ON hunk_reviews(file_path, state)',
+  'CREATE INDEX IF NOT EXISTS idx_hunk_reviews_state ON hunk_reviews(state, updated_at)',
+  'CREATE INDEX IF NOT EXISTS idx_hunk_review_history_fp ON hunk_review_history(fingerprint, timestamp)',
+];
+
+function normalizeLine(line) {
+  return line.trim().replace(/\s+/g, ' ');
+}
+
+function splitLines(text) {
+  if (!text) return [];
+  const lines = text.replace(/\r\n?/g, '\n').split('\n');
+  if (lines[lines.length - 1] === '') lines.pop();
+  return lines;
+}
+
+/**
+ * Fingerprint of a hunk: stable under whitespace changes and line shifts
+ */
+function fingerprintHunk(filePath, removedLines, addedLines) {
+  const removed = removedLines.map(normalizeLine).join('\n');
+  const added = addedLines.map(normalizeLine).join('\n');
+  return hashContent(`${filePath}\0${removed}\0${added}`).slice(0, 32);
+}
+
+/**
+ * Split a before/after pair into hunks of contiguous removed/added lines
+ */
+function computeHunks(filePath, before, after) {
+  const hunks = [];
+  let line = 1;
+  let current = null;
+
+  const flush = () => {
+    if (!current) return;
+    // Whitespace-only hunks are not worth reviewing
+    const meaningful = [...current.removed, ...current.added].some((l) => normalizeLine(l));
+    if (meaningful) {
+      hunks.push({
+        fingerprint: fingerprintHunk(filePath, current.removed, current.added),
+        filePath,
+        startLine: current.startLine,
+        removed: current.removed,
+        added: current.added,
+      });
+    }
+    current = null;
+  };
+
+  for (const part of diff.diffLines(before || '', after || '')) {
+    const lines = splitLines(part.value);
+    if (part.added || part.removed) {
+      if (!current) current = { startLine: line, removed: [], added: [] };
+      if (part.added) current.added.push(...lines);
+      else current.removed.push(...lines);
+    } else {
+      flush();
+    }
+    if (!part.removed) line += lines.length;
+  }
+  flush();
+
+  return hunks;
+}
+
+/**
+ * Fraction of `needle` lines (normalized, blank lines ignored) still present in `haystack`
+ */
+function linePresence(needle, haystack) {
+  const wanted = needle.map(normalizeLine).filter(Boolean);
+  if (wanted.length === 0) return null;
+
+  const available = new Map();
+  for (const line of haystack) available.set(line, (available.get(line) || 0) + 1);
+
+  let found = 0;
+  for (const line of wanted) {
+    const count = available.get(line) || 0;
+    if (count > 0) {
+      available.set(line, count - 1);
+      found++;
+    }
+  }
+  return found / wanted.length;
+}
+
+class HunkReviewStore {
+  constructor(dbPath, options = {}) {
+    this.dbPath = dbPath;
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.db = null;
+    this._initPromise = null;
+  }
+
+  async init() {
+    if (this._initPromise) return this._initPromise;
+
+    this._initPromise = new Promise((resolve, reject) => {
+      fs.mkdirSync(path.dirname(this.dbPath), { recursive: true });
+      this.db = new sqlite3.Database(this.dbPath, async (err) => {
+        if (err) {
+          console.error('[HUNK-REVIEW] Failed to open database:', err);
+          reject(err);
+          return;
+        }
+        try {
+          await this.run('PRAGMA journal_mode=WAL');
+          for (const statement of SCHEMA) await this.run(statement);
+          resolve();
+        } catch (schemaError) {
+          reject(schemaError);
+        }
+      });
+    });
+    return this._initPromise;
+  }
+
+  run(sql, params = []) {
+    return new Promise((resolve, reject) => {
+      this.db.run(sql, params, function onRun(err) {
+        if (err) reject(err);
+        else resolve({ changes: this.changes, lastID: this.lastID });
+      });
+    });
+  }
+
+  all(sql, params = []) {
+    return new Promise((resolve, reject) => {
+      this.db.all(sql, params, (err, rows) => (err ? reject(err) : resolve(rows)));
+    });
+  }
+
+  get(sql, params = []) {
+    return new Promise((resolve, reject) => {
+      this.db.get(sql, params, (err, row) => (err ? reject(err) : resolve(row || null)));
+    });
+  }
+
+  async transaction(fn) {
+    await this.run('BEGIN IMMEDIATE');
+    try {
+      const result = await fn();
+      await this.run('COMMIT');
+      return result;
+    } catch (error) {
+      await this.run('ROLLBACK').catch(() => {});
+      throw error;
+    }
+  }
+
+  /**
+   * Register the hunks of a change as unreviewed (existing fingerprints keep their state)
+   * @returns {Promise<Array>} Hunks with fingerprint and current state
+   */
+  async recordChange(filePath, before, after, meta = {}) {
+    await this.init();
+    const timestamp = meta.timestamp || Date.now();
+    const hunks = computeHunks(filePath, before, after);
+
+    return this.transaction(async () => {
+      const result = [];
+      for (const hunk of hunks) {
+        await this.run(
+          `INSERT OR IGNORE INTO hunk_reviews
+            (fingerprint, file_path, state, origin, prompt_id, removed_text, added_text, start_line,
+             created_at, updated_at, last_checked_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+          [
+            hunk.fingerprint,
+            filePath,
+            STATES.UNREVIEWED,
+            meta.origin || null,
+            meta.promptId !== undefined && meta.promptId !== null ? String(meta.promptId) : null,
+            hunk.removed.join('\n'),
+            hunk.added.join('\n'),
+            hunk.startLine,
+            timestamp,
+            timestamp,
+            timestamp,
+          ]
+        );
+        const row = await this.get('SELECT state FROM hunk_reviews WHERE fingerprint = ?', [hunk.fingerprint]);
+        result.push({ ...hunk, state: row.state });
+      }
+      return result;
+    });
+  }
+
+  async transition(row, toState, reason, timestamp, extra = {}) {
+    await this.run(
+      `UPDATE hunk_reviews SET state = ?, updated_at = ?, manual = ?,
+         presence = COALESCE(?, presence), last_checked_at = COALESCE(?, last_checked_at)
+       WHERE fingerprint = ?`,
+      [
+        toState,
+        timestamp,
+        extra.manual ? 1 : row.manual || 0,
+        extra.presence ?? null,
+        extra.checkedAt ?? null,
+        row.fingerprint,
+      ]
+    );
+    await this.run(
+      'INSERT INTO hunk_review_history (fingerprint, from_state, to_state, reason, timestamp) VALUES (?, ?, ?, ?, ?)',
+      [row.fingerprint, row.state, toState, reason, timestamp]
+    );
+  }
+
+  /**
+   * Set a hunk's state from the review panel; manual states are not overridden by snapshots
+   */
+  async setState(fingerprint, state, options = {}) {
+    await this.init();
+    if (!Object.values(STATES).includes(state)) throw new Error(`Unknown review state: ${state}`);
+    const row = await this.get('SELECT * FROM hunk_reviews WHERE fingerprint = ?', [fingerprint]);
+    if (!row) return false;
+    if (row.state !== state) {
+      await this.transition(row, state, options.reason || 'manual', options.timestamp || Date.now(), {
+        manual: state !== STATES.UNREVIEWED,
+      });
+    }
+    return true;
+  }
+
+  /**
+   * Re-evaluate open hunks of a file against a new snapshot of its content
+   * @returns {Promise<Array>} Transitions { fingerprint, from, to, presence }
+   */
+  async applySnapshot(filePath, content, meta = {}) {
+    await this.init();
+    const timestamp = meta.timestamp || Date.now();
+    const lines = splitLines(content).map(normalizeLine);
+    const rows = await this.all(
+      'SELECT * FROM hunk_reviews WHERE file_path = ? AND manual = 0 AND state IN (?, ?)',
+      [filePath, STATES.UNREVIEWED, STATES.MODIFIED]
+    );
+
+    return this.transaction(async () => {
+      const transitions = [];
+      for (const row of rows) {
+        const added = splitLines(row.added_text);
+        const removed = splitLines(row.removed_text);
+        const addedPresence = linePresence(added, lines);
+        const removedPresence = linePresence(removed, lines);
+
+        let next = row.state;
+        let presence = addedPresence ?? 1;
+        if (addedPresence === null) {
+          // Pure deletion: reverted once the removed lines come back
+          presence = removedPresence === 1 ? 0 : 1;
+          if (removedPresence === 1) next = STATES.REVERTED;
+        } else if (addedPresence === 0) {
+          next = removedPresence === null || removedPresence === 1 ? STATES.REVERTED : STATES.MODIFIED;
+        } else if (addedPresence < 1) {
+          next = STATES.MODIFIED;
+        }
+
+        if (
+          next === STATES.UNREVIEWED &&
+          this.options.autoKeepAfterMs > 0 &&
+          timestamp - row.created_at >= this.options.autoKeepAfterMs
+        ) {
+          next = STATES.KEPT;
+        }
+
+        if (next !== row.state) {
+          await this.transition(row, next, 'snapshot', timestamp, { presence, checkedAt: timestamp });
+          transitions.push({ fingerprint: row.fingerprint, from: row.state, to: next, presence });
+        } else {
+          await this.run('UPDATE hunk_reviews SET presence = ?, last_checked_at = ? WHERE fingerprint = ?', [
+            presence,
+            timestamp,
+            row.fingerprint,
+          ]);
+        }
+      }
+      return transitions;
+    });
+  }
+
+  async getState(fingerprint) {
+    await this.init();
+    const row = await this.get('SELECT state FROM hunk_reviews WHERE fingerprint = ?', [fingerprint]);
+    return row ? row.state : null;
+  }
+
+  /**
+   * Hunks for the review panel
+   * @param {object} options - { filePath, states, limit }
+   */
+  async getHunks(options = {}) {
+    await this.init();
+    const clauses = [];
+    const params = [];
+    if (options.filePath) {
+      clauses.push('file_path = ?');
+      params.push(options.filePath);
+    }
+    if (options.states && options.states.length > 0) {
+      clauses.push(`state IN (${options.states.map(() => '?').join(', ')})`);
+      params.push(...options.states);
+    }
+    const where = clauses.length > 0 ? `WHERE ${clauses.join(' AND ')}` : '';
+    const rows = await this.all(
+      `SELECT * FROM hunk_reviews ${where} ORDER BY file_path, start_line LIMIT ?`,
+      [...params, options.limit || 1000]
+    );
+
+    return rows.map((row) => ({
+      fingerprint: row.fingerprint,
+      filePath: row.file_path,
+      state: row.state,
+      origin: row.origin,
+      promptId: row.prompt_id,
+      startLine: row.start_line,
+      removed: splitLines(row.removed_text),
+      added: splitLines(row.added_text),
+      presence: row.presence,
+      manual: Boolean(row.manual),
+      createdAt: row.created_at,
+      updatedAt: row.updated_at,
+    }));
+  }
+
+  async getHistory(fingerprint) {
+    await this.init();
+    return this.all('SELECT * FROM hunk_review_history WHERE fingerprint = ? ORDER BY timestamp, id', [
+      fingerprint,
+    ]);
+  }
+
+  /**
+   * Counts per state, optionally for one file
+   */
+  async getSummary(filePath = null) {
+    await this.init();
+    const rows = await this.all(
+      `SELECT state, COUNT(*) AS count FROM hunk_reviews ${filePath ? 'WHERE file_path = ?' : ''} GROUP BY state`,
+      filePath ? [filePath] : []
+    );
+    const summary = Object.fromEntries(Object.values(STATES).map((state) => [state, 0]));
+    for (const row of rows) summary[row.state] = row.count;
+    return summary;
+  }
+
+  close() {
+    return new Promise((resolve) => {
+      if (!this.db) return resolve();
+      this.db.close(() => {
+        this.db = null;
+        this._initPromise = null;
+        resolve();
+      });
+    });
+  }
+}
+
+module.exports = {
+  HunkReviewStore,
+  computeHunks,
+  fingerprintHunk,
+  STATES,
+};
@@ -0,0 +1,296 @@
+/**
+ * Adaptive Rate Controller
+ * Chooses snapshot/polling intervals per file (or per capture source) from
+ * recent activity, system load and power state, instead of a fixed 2-second
+ * poll that is too slow during edit bursts and wasteful while idle.
+ *
+ *   burst  (>= burstEvents in burstWindowMs)  -> base / burstDivisor
+ *   active (activity within idleAfterMs)      -> base
+ *   idle                                      -> base doubled per idle period, up to max
+ *
+ * The result is then stretched under high load and on battery, and clamped
+ * to [minIntervalMs, maxIntervalMs].
+ */
+
+const EventEmitter = require('events');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { exec } = require('child_process');
+const { promisify } = require('util');
+
+const execAsync = promisify(exec);
+
+const DEFAULT_OPTIONS = {
+  baseIntervalMs: 2000,
+  minIntervalMs: 250,
+  maxIntervalMs: 30000,
+  burstWindowMs: 10000,
+  burstEvents: 3,
+  burstDivisor: 4,
+  idleAfterMs: 60000,
+  highLoad: 0.8, // 1-minute load average per core
+  loadMultiplier: 2,
+  batteryMultiplier: 1.5,
+  lowBatteryPercent: 20,
+  lowBatteryMultiplier: 3,
+  powerCheckIntervalMs: 60000,
+  maxTrackedKeys: 5000,
+};
+
+/**
+ * Best-effort power state: { onBattery, percent } (nulls when unknown)
+ */
+async function readPowerState() {
+  try {
+    if (process.platform === 'darwin') {
+      const { stdout } = await execAsync('pmset -g batt', { timeout: 5000 });
+      const percent = stdout.match(/(\d+)%/);
+      return { onBattery: /'Battery Power'/.test(stdout), percent: percent ? Number(percent[1]) : null };
+    }
+
+    if (process.platform === 'linux') {
+      const root = '/sys/class/power_supply';
+      const supplies = fs.readdirSync(root);
+      const read = (name, file) => fs.readFileSync(path.join(root, name, file), 'utf8').trim();
+      const battery = supplies.find((name) => name.startsWith('BAT'));
+      if (!battery) return { onBattery: false, percent: null };
+      const ac = supplies.find((name) => /^(AC|ADP|ACAD)/.test(name));
+      const onBattery = ac ? read(ac, 'online') === '0' : read(battery, 'status') === 'Discharging';
+      return { onBattery, percent: Number(read(battery, 'capacity')) };
+    }
+
+    if (process.platform === 'win32') {
+      const { stdout } = await execAsync(
+        'powershell -NoProfile -Command "Get-CimInstance Win32_Battery | Select-Object BatteryStatus,EstimatedChargeRemaining | ConvertTo-Json"',
+        { timeout: 10000 }
+      );
+      if (!stdout.trim()) return { onBattery: false, percent: null };
+      const info = [].concat(JSON.parse(stdout))[0];
+      // BatteryStatus 1 = discharging
+      return { onBattery: info.BatteryStatus === 1, percent: info.EstimatedChargeRemaining ?? null };
+    }
+  } catch {
+    // Unknown power state; treat as mains power
+  }
+  return { onBattery: null, percent: null };
+}
+
+class AdaptiveRateController extends EventEmitter {
+  constructor(options = {}) {
+    super();
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.activity = new Map(); // key -> { events: number[], lastActivity, baseIntervalMs }
+    this.power = { onBattery: null, percent: null, checkedAt: null };
+    this.powerTimer = null;
+    this.timers = new Map();
+    this.readPowerState = options.readPowerState || readPowerState;
+  }
+
+  /**
+   * Start periodic power-state checks
+   */
+  async start() {
+    await this.refreshPower();
+    if (!this.powerTimer) {
+      this.powerTimer = setInterval(() => this.refreshPower(), this.options.powerCheckIntervalMs);
+      this.powerTimer.unref?.();
+    }
+    return this;
+  }
+
+  async refreshPower() {
+    const previous = this.power.onBattery;
+    this.power = { ...(await this.readPowerState()), checkedAt: Date.now() };
+    if (previous !== null && previous !== this.power.onBattery) this.emit('power', this.power);
+  }
+
+  track(key) {
+    let state = this.activity.get(key);
+    if (!state) {
+      if (this.activity.size >= this.options.maxTrackedKeys) this.evictOldest();
+      state = { events: [], lastActivity: null, baseIntervalMs: null };
+      this.activity.set(key, state);
+    }
+    return state;
+  }
+
+  evictOldest() {
+    let oldestKey = null;
+    let oldest = Infinity;
+    for (const [key, state] of this.activity) {
+      if (!this.timers.has(key) && (state.lastActivity ?? 0) < oldest) {
+        oldest = state.lastActivity ?? 0;
+        oldestKey = key;
+      }
+    }
+    if (oldestKey !== null) this.activity.delete(oldestKey);
+  }
+
+  /**
+   * Record activity (a file change, editor focus, a new prompt) for a key
+   */
+  recordActivity(key, timestamp = Date.now()) {
+    const state = this.track(key);
+    const wasState = this.classify(state, timestamp);
+    state.events.push(timestamp);
+    state.lastActivity = timestamp;
+
+    const cutoff = timestamp - this.options.burstWindowMs;
+    while (state.events.length > 0 && state.events[0] < cutoff) state.events.shift();
+
+    const nowState = this.classify(state, timestamp);
+    if (nowState !== wasState) {
+      this.emit('state', { key, from: wasState, to: nowState });
+      // Pull a pending long idle timer forward so the new rate applies immediately
+      const timer = this.timers.get(key);
+      if (timer && wasState === 'idle') timer.reschedule();
+    }
+  }
+
+  classify(state, now = Date.now()) {
+    if (!state || state.lastActivity === null) return 'idle';
+    const recent = state.events.filter((t) => t >= now - this.options.burstWindowMs).length;
+    if (recent >= this.options.burstEvents) return 'burst';
+    return now - state.lastActivity <= this.options.idleAfterMs ? 'active' : 'idle';
+  }
+
+  systemMultipliers() {
+    const cores = os.cpus().length || 1;
+    const load = os.loadavg()[0] / cores;
+    const loadFactor = load > this.options.highLoad ? this.options.loadMultiplier : 1;
+
+    let powerFactor = 1;
+    if (this.power.onBattery) {
+      powerFactor =
+        this.power.percent !== null && this.power.percent <= this.options.lowBatteryPercent
+          ? this.options.lowBatteryMultiplier
+          : this.options.batteryMultiplier;
+    }
+
+    return { load, loadFactor, powerFactor };
+  }
+
+  /**
+   * Current polling interval for a key
+   */
+  getInterval(key, now = Date.now()) {
+    const state = this.activity.get(key);
+    const base = (state && state.baseIntervalMs) || this.options.baseIntervalMs;
+    const mode = this.classify(state, now);
+
+    let interval = base;
+    if (mode === 'burst') {
+      interval = base / this.options.burstDivisor;
+    } else if (mode === 'idle') {
+      const idleMs = state && state.lastActivity !== null ? now - state.lastActivity : this.options.idleAfterMs;
+      const idlePeriods = Math.max(1, Math.floor(idleMs / this.options.idleAfterMs));
+      interval = base * Math.pow(2, Math.min(idlePeriods, 16));
+    }
+
+    const { loadFactor, powerFactor } = this.systemMultipliers();
+    interval *= loadFactor * powerFactor;
+    return Math.round(Math.min(this.options.maxIntervalMs, Math.max(this.options.minIntervalMs, interval)));
+  }
+
+  /**
+   * Run fn repeatedly at the key's adaptive interval (replacement for setInterval)
+   * @returns {object} Handle with stop() and reschedule()
+   */
+  schedule(key, fn, options = {}) {
+    this.unschedule(key);
+    const state = this.track(key);
+    if (options.baseIntervalMs) state.baseIntervalMs = options.baseIntervalMs;
+
+    let timeout = null;
+    let stopped = false;
+    let running = false;
+
+    const tick = async () => {
+      timeout = null;
+      running = true;
+      try {
+        await fn();
+      } catch (error) {
+        if (this.listenerCount('error') > 0) this.emit('error', error);
+        else console.warn(`[RATE] Scheduled task ${key} failed:`, error.message);
+      } finally {
+        running = false;
+      }
+      if (!stopped) arm();
+    };
+    const arm = () => {
+      timeout = setTimeout(tick, this.getInterval(key));
+      timeout.unref?.();
+    };
+
+    const handle = {
+      key,
+      stop: () => {
+        stopped = true;
+        if (timeout) clearTimeout(timeout);
+        this.timers.delete(key);
+      },
+      reschedule: () => {
+        if (stopped || running) return;
+        if (timeout) clearTimeout(timeout);
+        arm();
+      },
+    };
+
+    this.timers.set(key, handle);
+    arm();
+    return handle;
+  }
+
+  unschedule(key) {
+    const handle = this.timers.get(key);
+    if (handle) handle.stop();
+  }
+
+  /**
+   * Effective rates for display
+   */
+  getEffectiveRates(now = Date.now()) {
+    const { load, loadFactor, powerFactor } = this.systemMultipliers();
+    const keys = [];
+
+    for (const [key, state] of this.activity) {
+      const intervalMs = this.getInterval(key, now);
+      keys.push({
+        key,
+        state: this.classify(state, now),
+        intervalMs,
+        hz: 1000 / intervalMs,
+        recentEvents: state.events.filter((t) => t >= now - this.options.burstWindowMs).length,
+        lastActivity: state.lastActivity,
+        scheduled: this.timers.has(key),
+      });
+    }
+    keys.sort((a, b) => a.intervalMs - b.intervalMs);
+
+    return {
+      defaultIntervalMs: this.getInterval(null, now),
+      system: {
+        loadPerCore: load,
+        onBattery: this.power.onBattery,
+        batteryPercent: this.power.percent,
+        multipliers: { load: loadFactor, power: powerFactor },
+      },
+      bounds: { minIntervalMs: this.options.minIntervalMs, maxIntervalMs: this.options.maxIntervalMs },
+      keys,
+    };
+  }
+
+  stop() {
+    for (const handle of [...this.timers.values()]) handle.stop();
+    if (this.powerTimer) clearInterval(this.powerTimer);
+    this.powerTimer = null;
+  }
+}
+
+module.exports = {
+  AdaptiveRateController,
+  readPowerState,
+  DEFAULT_OPTIONS,
+};
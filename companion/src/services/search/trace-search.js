@@ -0,0 +1,595 @@
+/**
+ * Trace Search
+ * Full-text index over trace events (prompts, responses, diff contents, file
+ * paths) so searching weeks of traces doesn't mean regex-scanning every log.
+ *
+ * Query syntax follows tantivy's QueryParser:
+ *   session timeout            both terms, in any default field
+ *   "retry with backoff"       phrase (positions must be consecutive)
+ *   prompt:refactor            field filter; path:src/db.js is a phrase over the path
+ *   type:prompt session:abc    exact keyword fields
+ *   timestamp:[2024-05-01 TO 2024-05-08}   inclusive/exclusive range; also ts:>=now-7d
+ *   pars* -test  OR  NOT       prefix terms, exclusion, disjunction (OR binds looser than AND)
+ *
+ * The index is an append-only set of segment files plus a manifest, held in
+ * memory once opened. commit() writes documents added since the last commit
+ * as a new segment; optimize() rewrites everything as one. Postings keep term
+ * positions per field; identifier parts (getUserSession -> get, user, session)
+ * share the position of the whole identifier, like synonyms.
+ *
+ * tantivy itself has no binding in this build, so the JS backend is the only
+ * one; its on-disk format is private to this module.
+ */
+
+const fs = require('fs');
+const path = require('path');
+const { TraceReader } = require('../trace-log/trace-reader');
+const { toMillis } = require('../../utils/timestamps');
+const { parseDetails } = require('../../utils/event-details');
+
+const DEFAULT_OPTIONS = {
+  k1: 1.2,
+  b: 0.75,
+  storeChars: 600, // Characters of each text field kept for result snippets (0 = none)
+  maxTermsPerField: 20000, // Cap on indexed terms per field per event (huge diffs)
+};
+
+const FORMAT = 'trace-search/1';
+const TEXT_FIELDS = ['prompt', 'response', 'diff', 'path'];
+const KEYWORD_FIELDS = ['type', 'session'];
+const FIELD_ALIASES = {
+  file: 'path',
+  file_path: 'path',
+  session_id: 'session',
+  ts: 'timestamp',
+  time: 'timestamp',
+};
+const DURATION_MS = { s: 1000, m: 60000, h: 3600000, d: 86400000, w: 604800000 };
+
+const WORD = /[A-Za-z_$][\w$]*|\d+(?:\.\d+)?|[^\s\w]/gu;
+const IDENTIFIER_PART = /[A-Z]+(?![a-z])|[A-Z]?[a-z]+|\d+/g;
+
+/**
+ * Terms with positions; punctuation only advances the position so phrases don't match across it
+ */
+function analyze(text, maxTerms = Infinity) {
+  const terms = [];
+  let position = 0;
+  for (const [word] of String(text ?? '').matchAll(WORD)) {
+    if (terms.length >= maxTerms) break;
+    if (!/[\w$]/.test(word)) {
+      position++;
+      continue;
+    }
+    terms.push([word.toLowerCase(), position]);
+    const parts = word.match(IDENTIFIER_PART) || [];
+    if (parts.length > 1) parts.forEach((part) => terms.push([part.toLowerCase(), position]));
+    position++;
+  }
+  return terms;
+}
+
+function firstString(...values) {
+  const found = values.find((value) => typeof value === 'string' && value.length > 0);
+  return found ?? '';
+}
+
+/**
+ * Pull the searchable fields out of a trace event (or a stored prompt/entry row)
+ */
+function extractDocument(event) {
+  const details = parseDetails(event);
+  const diffParts = [
+    event.diff,
+    details.diff,
+    details.unified_diff,
+    event.before_content ?? event.before_code,
+    event.after_content ?? event.after_code,
+    details.before_content,
+    details.after_content,
+    details.content,
+  ].filter((value) => typeof value === 'string' && value.length > 0);
+
+  return {
+    id: String(event.id ?? event.event_id ?? ''),
+    timestamp: toMillis(event.timestamp),
+    type: String(event.type ?? '').toLowerCase(),
+    session: String(event.session_id ?? event.sessionId ?? '').toLowerCase(),
+    prompt: firstString(
+      event.prompt,
+      event.text,
+      event.preview,
+      details.prompt,
+      details.text,
+      details.prompt_text
+    ),
+    response: firstString(event.response, details.response, details.response_text, details.completion),
+    diff: diffParts.join('\n'),
+    path: firstString(event.file_path, event.filePath, details.file_path, details.file),
+  };
+}
+
+// ---------------------------------------------------------------------------
+// Query parsing
+// ---------------------------------------------------------------------------
+
+function parseTimeBound(value, now) {
+  if (value === '*' || value === '') return null;
+  const relative = value.match(/^now(?:([+-])(\d+)([smhdw]))?$/);
+  if (relative) {
+    const [, sign, amount, unit] = relative;
+    const offset = sign ? Number(amount) * DURATION_MS[unit] : 0;
+    return sign === '-' ? now - offset : now + offset;
+  }
+  if (/^\d+$/.test(value) && value.length > 8) return Number(value);
+  const parsed = Date.parse(value);
+  if (Number.isNaN(parsed)) throw new Error(`Invalid time in query: ${value}`);
+  return parsed;
+}
+
+function splitQuery(query) {
+  const tokens = [];
+  const pattern = /\s*([+-]?)(?:([A-Za-z_]+):)?("(?:[^"\\]|\\.)*"|[[{][^\]}]*[\]}]|(?:>=|<=|>|<)?[^\s"]+)/gy;
+  let match;
+  while ((match = pattern.exec(query)) !== null) {
+    const [, modifier, field, value] = match;
+    tokens.push({ modifier, field: field ? field.toLowerCase() : null, value });
+    if (pattern.lastIndex >= query.length) break;
+  }
+  const rest = query.slice(pattern.lastIndex).trim();
+  if (rest) throw new Error(`Unparseable query near: ${rest}`);
+  return tokens;
+}
+
+/**
+ * Parse a query into OR-groups of clauses
+ * @returns {Array<Array<object>>} Each group: [{ kind, field, negate, ... }]
+ */
+function parseQuery(query, now = Date.now()) {
+  const groups = [[]];
+  let negateNext = false;
+
+  for (const token of splitQuery(String(query ?? ''))) {
+    if (!token.field && !token.modifier && (token.value === 'OR' || token.value === '||')) {
+      if (groups[groups.length - 1].length) groups.push([]);
+      continue;
+    }
+    if (!token.field && !token.modifier && (token.value === 'AND' || token.value === '&&')) continue;
+    if (!token.field && !token.modifier && token.value === 'NOT') {
+      negateNext = true;
+      continue;
+    }
+
+    const field = token.field ? FIELD_ALIASES[token.field] || token.field : null;
+    if (field && field !== 'timestamp' && !TEXT_FIELDS.includes(field) && !KEYWORD_FIELDS.includes(field)) {
+      throw new Error(`Unknown field in query: ${token.field}`);
+    }
+    const clause = { field, negate: negateNext || token.modifier === '-' };
+    negateNext = false;
+    let value = token.value;
+
+    if (field === 'timestamp') {
+      const range = value.match(/^([[{])\s*(\S+)\s+TO\s+(\S+)\s*([\]}])$/);
+      const comparison = value.match(/^(>=|<=|>|<)(.+)$/);
+      clause.kind = 'range';
+      if (range) {
+        clause.min = parseTimeBound(range[2], now);
+        clause.max = parseTimeBound(range[3], now);
+        clause.minInclusive = range[1] === '[';
+        clause.maxInclusive = range[4] === ']';
+      } else if (comparison) {
+        const bound = parseTimeBound(comparison[2], now);
+        const inclusive = comparison[1].endsWith('=');
+        if (comparison[1].startsWith('>')) Object.assign(clause, { min: bound, minInclusive: inclusive });
+        else Object.assign(clause, { max: bound, maxInclusive: inclusive });
+      } else {
+        const at = parseTimeBound(value, now);
+        Object.assign(clause, { min: at, max: at, minInclusive: true, maxInclusive: true });
+      }
+    } else if (KEYWORD_FIELDS.includes(field)) {
+      clause.kind = 'keyword';
+      clause.value = value.replace(/^"|"$/g, '').toLowerCase();
+    } else {
+      const quoted = value.startsWith('"');
+      if (quoted) value = value.slice(1, -1).replace(/\\(.)/g, '$1');
+      const prefix = !quoted && value.length > 1 && value.endsWith('*');
+      if (prefix) value = value.slice(0, -1);
+      // Identifier parts aren't needed for matching: the whole words are indexed too
+      const terms = analyze(value).filter(([, position], i, all) => i === 0 || position !== all[i - 1][1]);
+      if (terms.length === 0) continue;
+      if (prefix && terms.length === 1) {
+        Object.assign(clause, { kind: 'prefix', term: terms[0][0] });
+      } else if (terms.length === 1) {
+        Object.assign(clause, { kind: 'term', term: terms[0][0] });
+      } else {
+        const first = terms[0][1];
+        clause.kind = 'phrase';
+        clause.terms = terms.map(([term, position]) => [term, position - first]);
+      }
+    }
+    groups[groups.length - 1].push(clause);
+  }
+
+  return groups.filter((group) => group.length > 0);
+}
+
+// ---------------------------------------------------------------------------
+// Index
+// ---------------------------------------------------------------------------
+
+class TraceSearchIndex {
+  /**
+   * @param {string} dir - Index directory (created on commit)
+   * @param {object} options - See DEFAULT_OPTIONS
+   */
+  constructor(dir, options = {}) {
+    this.dir = dir;
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.docs = []; // doc -> { id, timestamp, type, session, path, lengths, stored }
+    this.idToDoc = new Map();
+    // field -> term -> Map(doc -> positions), and field -> value -> Set(doc) for keywords
+    this.postings = Object.fromEntries(TEXT_FIELDS.map((field) => [field, new Map()]));
+    this.keywords = Object.fromEntries(KEYWORD_FIELDS.map((field) => [field, new Map()]));
+    this.totalLengths = Object.fromEntries(TEXT_FIELDS.map((field) => [field, 0]));
+    this.segments = [];
+    this.committedDocs = 0;
+    this.lastTimestamp = null;
+  }
+
+  get size() {
+    return this.docs.length;
+  }
+
+  /**
+   * Open an index directory (an empty index if it doesn't exist yet)
+   */
+  static open(dir, options = {}) {
+    const index = new TraceSearchIndex(dir, options);
+    const manifestPath = path.join(dir, 'manifest.json');
+    if (!fs.existsSync(manifestPath)) return index;
+
+    const manifest = JSON.parse(fs.readFileSync(manifestPath, 'utf8'));
+    if (manifest.format !== FORMAT) throw new Error(`${dir} is not a trace search index`);
+    for (const name of manifest.segments) {
+      index.loadSegment(JSON.parse(fs.readFileSync(path.join(dir, name), 'utf8')));
+    }
+    index.segments = manifest.segments;
+    index.committedDocs = index.docs.length;
+    index.lastTimestamp = manifest.lastTimestamp ?? null;
+    return index;
+  }
+
+  loadSegment(segment) {
+    const base = this.docs.length;
+    for (const doc of segment.docs) {
+      this.docs.push(doc);
+      this.idToDoc.set(doc.id, this.docs.length - 1);
+      for (const field of TEXT_FIELDS) this.totalLengths[field] += doc.lengths[field] || 0;
+      for (const field of KEYWORD_FIELDS) this.addKeyword(field, doc[field], this.docs.length - 1);
+    }
+    for (const field of TEXT_FIELDS) {
+      for (const [term, list] of Object.entries(segment.postings[field] || {})) {
+        if (!this.postings[field].has(term)) this.postings[field].set(term, new Map());
+        const posting = this.postings[field].get(term);
+        for (const [doc, ...positions] of list) posting.set(base + doc, positions);
+      }
+    }
+  }
+
+  addKeyword(field, value, doc) {
+    if (!value) return;
+    if (!this.keywords[field].has(value)) this.keywords[field].set(value, new Set());
+    this.keywords[field].get(value).add(doc);
+  }
+
+  /**
+   * Index one trace event; events already indexed (by id) are skipped
+   * @returns {boolean} Whether the event was added
+   */
+  add(event) {
+    const fields = extractDocument(event);
+    if (!fields.id) fields.id = `${fields.timestamp}:${fields.type}:${this.docs.length}`;
+    if (this.idToDoc.has(fields.id)) return false;
+
+    const doc = this.docs.length;
+    const entry = {
+      id: fields.id,
+      timestamp: fields.timestamp,
+      type: fields.type,
+      session: fields.session,
+      path: fields.path || null,
+      lengths: {},
+      stored: {},
+    };
+
+    for (const field of TEXT_FIELDS) {
+      const text = fields[field];
+      if (!text) continue;
+      const terms = analyze(text, this.options.maxTermsPerField);
+      entry.lengths[field] = terms.length;
+      this.totalLengths[field] += terms.length;
+      for (const [term, position] of terms) {
+        if (!this.postings[field].has(term)) this.postings[field].set(term, new Map());
+        const posting = this.postings[field].get(term);
+        if (!posting.has(doc)) posting.set(doc, []);
+        posting.get(doc).push(position);
+      }
+      if (this.options.storeChars > 0) entry.stored[field] = text.slice(0, this.options.storeChars);
+    }
+
+    this.docs.push(entry);
+    this.idToDoc.set(entry.id, doc);
+    for (const field of KEYWORD_FIELDS) this.addKeyword(field, entry[field], doc);
+    if (entry.timestamp !== null && (this.lastTimestamp === null || entry.timestamp > this.lastTimestamp)) {
+      this.lastTimestamp = entry.timestamp;
+    }
+    return true;
+  }
+
+  addAll(events) {
+    let added = 0;
+    for (const event of events || []) if (this.add(event)) added++;
+    return added;
+  }
+
+  /**
+   * Index events from trace logs, resuming after the newest event already indexed
+   * @param {string} source - Trace file or directory of rotated trace files
+   * @param {object} options - { since, until, types, commitEvery }
+   * @returns {Promise<object>} { added, skipped }
+   */
+  async indexTraces(source, options = {}) {
+    const { commitEvery = 50000, ...filter } = options;
+    const reader = new TraceReader(source);
+    let added = 0;
+    let skipped = 0;
+    for await (const event of reader.events({ since: this.lastTimestamp ?? undefined, ...filter })) {
+      if (this.add(event)) {
+        added++;
+        if (this.docs.length - this.committedDocs >= commitEvery) this.commit();
+      } else {
+        skipped++;
+      }
+    }
+    this.commit();
+    return { added, skipped };
+  }
+
+  serializeDocs(from, to) {
+    const postings = Object.fromEntries(TEXT_FIELDS.map((field) => [field, {}]));
+    for (const field of TEXT_FIELDS) {
+      for (const [term, posting] of this.postings[field]) {
+        const list = [];
+        for (const [doc, positions] of posting) {
+          if (doc >= from && doc < to) list.push([doc - from, ...positions]);
+        }
+        if (list.length) postings[field][term] = list;
+      }
+    }
+    return { format: FORMAT, docs: this.docs.slice(from, to), postings };
+  }
+
+  writeManifest() {
+    const manifest = {
+      format: FORMAT,
+      segments: this.segments,
+      docs: this.docs.length,
+      lastTimestamp: this.lastTimestamp,
+    };
+    const manifestPath = path.join(this.dir, 'manifest.json');
+    const tmp = `${manifestPath}.${process.pid}.tmp`;
+    fs.writeFileSync(tmp, JSON.stringify(manifest));
+    fs.renameSync(tmp, manifestPath);
+  }
+
+  writeSegment(from, to) {
+    const name = `seg-${Date.now().toString(36)}-${from}.json`;
+    const tmp = path.join(this.dir, `${name}.${process.pid}.tmp`);
+    fs.writeFileSync(tmp, JSON.stringify(this.serializeDocs(from, to)));
+    fs.renameSync(tmp, path.join(this.dir, name));
+    return name;
+  }
+
+  /**
+   * Persist documents added since the last commit as a new segment
+   * @returns {number} Documents written
+   */
+  commit() {
+    const pending = this.docs.length - this.committedDocs;
+    if (pending === 0) return 0;
+    fs.mkdirSync(this.dir, { recursive: true });
+    this.segments.push(this.writeSegment(this.committedDocs, this.docs.length));
+    this.committedDocs = this.docs.length;
+    this.writeManifest();
+    return pending;
+  }
+
+  /**
+   * Rewrite all segments as one (fewer files to open, smaller postings)
+   */
+  optimize() {
+    this.committedDocs = this.docs.length;
+    fs.mkdirSync(this.dir, { recursive: true });
+    const previous = this.segments;
+    this.segments = this.docs.length ? [this.writeSegment(0, this.docs.length)] : [];
+    this.writeManifest();
+    for (const name of previous) fs.rmSync(path.join(this.dir, name), { force: true });
+  }
+
+  // -------------------------------------------------------------------------
+  // Search
+  // -------------------------------------------------------------------------
+
+  /**
+   * Documents matching a clause, with a BM25 score contribution per document
+   * @returns {Map<number, number>} doc -> score
+   */
+  matchClause(clause) {
+    const matches = new Map();
+    const add = (doc, score) => matches.set(doc, (matches.get(doc) || 0) + score);
+
+    if (clause.kind === 'range') {
+      this.docs.forEach((doc, i) => {
+        const ts = doc.timestamp;
+        if (ts === null) return;
+        if (clause.min != null && (clause.minInclusive ? ts < clause.min : ts <= clause.min)) return;
+        if (clause.max != null && (clause.maxInclusive ? ts > clause.max : ts >= clause.max)) return;
+        matches.set(i, 0);
+      });
+      return matches;
+    }
+    if (clause.kind === 'keyword') {
+      for (const doc of this.keywords[clause.field].get(clause.value) || []) matches.set(doc, 0);
+      return matches;
+    }
+
+    const fields = clause.field ? [clause.field] : TEXT_FIELDS;
+    for (const field of fields) {
+      if (clause.kind === 'term') {
+        const posting = this.postings[field].get(clause.term);
+        if (posting) this.scorePosting(field, posting, add);
+      } else if (clause.kind === 'prefix') {
+        for (const [term, posting] of this.postings[field]) {
+          if (term.startsWith(clause.term)) this.scorePosting(field, posting, add);
+        }
+      } else if (clause.kind === 'phrase') {
+        this.matchPhrase(field, clause.terms, add);
+      }
+    }
+    return matches;
+  }
+
+  /**
+   * BM25 over a posting (doc -> positions) or a phrase's doc -> frequency map
+   */
+  scorePosting(field, posting, add) {
+    const { k1, b } = this.options;
+    const n = this.docs.length;
+    const df = posting.size;
+    const idf = Math.log(1 + (n - df + 0.5) / (df + 0.5));
+    const averageLength = this.totalLengths[field] / Math.max(1, n) || 1;
+    for (const [doc, value] of posting) {
+      const tf = Array.isArray(value) ? value.length : value;
+      const length = this.docs[doc].lengths[field] || 0;
+      add(doc, (idf * tf * (k1 + 1)) / (tf + k1 * (1 - b + (b * length) / averageLength)));
+    }
+  }
+
+  matchPhrase(field, terms, add) {
+    const postings = terms.map(([term]) => this.postings[field].get(term));
+    if (postings.some((posting) => !posting)) return;
+
+    // Drive from the rarest term, verify the others at the expected offsets
+    const order = postings.map((posting, i) => i).sort((x, y) => postings[x].size - postings[y].size);
+    const driver = order[0];
+    const frequencies = new Map();
+    for (const [doc, positions] of postings[driver]) {
+      const others = order.slice(1).map((i) => [i, postings[i].get(doc)]);
+      if (others.some(([, list]) => !list)) continue;
+      const sets = others.map(([i, list]) => [terms[i][1], new Set(list)]);
+      let count = 0;
+      for (const position of positions) {
+        const start = position - terms[driver][1];
+        if (sets.every(([offset, set]) => set.has(start + offset))) count++;
+      }
+      if (count > 0) frequencies.set(doc, count);
+    }
+    if (frequencies.size) this.scorePosting(field, frequencies, add);
+  }
+
+  snippet(entry, terms) {
+    const needles = terms.filter(Boolean);
+    for (const field of TEXT_FIELDS) {
+      const text = entry.stored?.[field];
+      if (!text) continue;
+      const lower = text.toLowerCase();
+      const at = needles.map((term) => lower.indexOf(term)).filter((i) => i >= 0).sort((x, y) => x - y)[0];
+      if (at === undefined) continue;
+      const start = Math.max(0, at - 60);
+      return { field, text: `${start > 0 ? '…' : ''}${text.slice(start, at + 140).replace(/\s+/g, ' ')}` };
+    }
+    return null;
+  }
+
+  /**
+   * Search the index
+   * @param {string} query - Query string (see header)
+   * @param {object} options - { limit, offset, sort: 'score'|'newest'|'oldest', now, snippets }
+   * @returns {object} { total, hits: [{ id, score, timestamp, type, session_id, file_path, snippet }] }
+   */
+  search(query, options = {}) {
+    const { limit = 20, offset = 0, sort = 'score', now = Date.now(), snippets = true } = options;
+    const groups = parseQuery(query, now);
+    const scores = new Map();
+
+    for (const group of groups) {
+      const positive = group.filter((clause) => !clause.negate);
+      const negative = group.filter((clause) => clause.negate);
+      let candidates = null;
+      if (positive.length === 0) {
+        candidates = new Map(this.docs.map((doc, i) => [i, 0]));
+      } else {
+        // Intersect smallest-first so large clauses only filter
+        const matched = positive.map((clause) => this.matchClause(clause)).sort((x, y) => x.size - y.size);
+        candidates = matched[0];
+        for (const other of matched.slice(1)) {
+          const next = new Map();
+          for (const [doc, score] of candidates) if (other.has(doc)) next.set(doc, score + other.get(doc));
+          candidates = next;
+          if (candidates.size === 0) break;
+        }
+      }
+      for (const clause of negative) {
+        for (const doc of this.matchClause(clause).keys()) candidates.delete(doc);
+      }
+      for (const [doc, score] of candidates) scores.set(doc, Math.max(scores.get(doc) ?? -1, score));
+    }
+
+    const ranked = Array.from(scores.entries());
+    const timeOf = ([doc]) => this.docs[doc].timestamp ?? 0;
+    if (sort === 'newest' || sort === 'oldest') {
+      const direction = sort === 'newest' ? -1 : 1;
+      ranked.sort((x, y) => direction * (timeOf(x) - timeOf(y)));
+    } else {
+      ranked.sort((x, y) => y[1] - x[1] || timeOf(y) - timeOf(x));
+    }
+
+    const highlight = groups
+      .flat()
+      .filter((clause) => !clause.negate)
+      .flatMap((clause) => (clause.kind === 'phrase' ? clause.terms.map(([term]) => term) : [clause.term]));
+
+    return {
+      total: ranked.length,
+      hits: ranked.slice(offset, offset + limit).map(([doc, score]) => {
+        const entry = this.docs[doc];
+        return {
+          id: entry.id,
+          score,
+          timestamp: entry.timestamp,
+          type: entry.type,
+          session_id: entry.session || null,
+          file_path: entry.path,
+          snippet: snippets ? this.snippet(entry, highlight) : null,
+        };
+      }),
+    };
+  }
+
+  getStats() {
+    return {
+      docs: this.docs.length,
+      uncommitted: this.docs.length - this.committedDocs,
+      segments: this.segments.length,
+      terms: Object.fromEntries(TEXT_FIELDS.map((field) => [field, this.postings[field].size])),
+      lastTimestamp: this.lastTimestamp,
+    };
+  }
+}
+
+module.exports = {
+  TraceSearchIndex,
+  parseQuery,
+  extractDocument,
+  DEFAULT_OPTIONS,
+};
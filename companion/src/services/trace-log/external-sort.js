@@ -0,0 +1,267 @@
+/**
+ * External Sort and Dedup
+ * Orders and deduplicates JSONL event exports that are too large for memory.
+ *
+ * Each pass reads its input in bounded runs, sorts each run in memory and
+ * spills it to a temp file, then k-way merges the runs with a min-heap. The
+ * first pass orders by dedup key, so copies of an event are adjacent wherever
+ * they sit in time and all but the earliest are dropped; the second orders by
+ * the sort field. Events without a dedup key are never treated as copies.
+ */
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const readline = require('readline');
+
+const DEFAULT_OPTIONS = {
+  sortBy: 'timestamp',
+  maxRunBytes: 64 * 1024 * 1024, // 64MB of raw JSON per in-memory run
+  tempDir: os.tmpdir(),
+};
+
+function getPath(obj, fieldPath) {
+  let value = obj;
+  for (const part of fieldPath.split('.')) {
+    if (value === null || value === undefined) return undefined;
+    value = value[part];
+  }
+  return value;
+}
+
+/**
+ * Build an extractor from a field name, list of field names, or function
+ */
+function makeExtractor(key) {
+  if (typeof key === 'function') return key;
+  if (Array.isArray(key)) {
+    return (event) => {
+      const parts = key.map((k) => getPath(event, k));
+      return parts.every((part) => part === undefined || part === null) ? undefined : parts;
+    };
+  }
+  return (event) => getPath(event, key);
+}
+
+function sortValue(raw) {
+  if (typeof raw === 'number') return raw;
+  if (typeof raw === 'string') {
+    const parsed = Date.parse(raw);
+    if (!Number.isNaN(parsed) && /\d{4}-\d{2}-\d{2}/.test(raw)) return parsed;
+    return raw;
+  }
+  return raw === undefined || raw === null ? '' : String(raw);
+}
+
+/**
+ * Identity for deduplication: null (never a duplicate) when the key is missing
+ */
+function dedupValue(raw) {
+  return raw === undefined || raw === null ? null : JSON.stringify(raw);
+}
+
+function compareValues(a, b) {
+  if (a === b) return 0;
+  if (typeof a === 'number' && typeof b === 'number') return a - b;
+  // Numbers sort before strings so mixed exports stay deterministic
+  if (typeof a === 'number') return -1;
+  if (typeof b === 'number') return 1;
+  return a < b ? -1 : 1;
+}
+
+function bySortValue(a, b) {
+  return compareValues(a.sort, b.sort) || compareValues(a.dedup ?? '', b.dedup ?? '');
+}
+
+// Keyless records first; within a key, earliest first so that's the copy kept
+function byDedupKey(a, b) {
+  if (a.dedup !== b.dedup) {
+    if (a.dedup === null) return -1;
+    if (b.dedup === null) return 1;
+    return a.dedup < b.dedup ? -1 : 1;
+  }
+  return compareValues(a.sort, b.sort);
+}
+
+class MinHeap {
+  constructor(compare) {
+    this.items = [];
+    this.compare = compare;
+  }
+
+  get size() {
+    return this.items.length;
+  }
+
+  push(item) {
+    const items = this.items;
+    items.push(item);
+    let i = items.length - 1;
+    while (i > 0) {
+      const parent = (i - 1) >> 1;
+      if (this.compare(items[i], items[parent]) >= 0) break;
+      [items[i], items[parent]] = [items[parent], items[i]];
+      i = parent;
+    }
+  }
+
+  pop() {
+    const items = this.items;
+    const top = items[0];
+    const last = items.pop();
+    if (items.length > 0) {
+      items[0] = last;
+      let i = 0;
+      for (;;) {
+        const left = 2 * i + 1;
+        const right = left + 1;
+        let smallest = i;
+        if (left < items.length && this.compare(items[left], items[smallest]) < 0) smallest = left;
+        if (right < items.length && this.compare(items[right], items[smallest]) < 0) smallest = right;
+        if (smallest === i) break;
+        [items[i], items[smallest]] = [items[smallest], items[i]];
+        i = smallest;
+      }
+    }
+    return top;
+  }
+}
+
+async function writeLines(filePath, lines) {
+  const stream = fs.createWriteStream(filePath);
+  for (const line of lines) {
+    if (!stream.write(line + '\n')) {
+      await new Promise((resolve) => stream.once('drain', resolve));
+    }
+  }
+  await new Promise((resolve, reject) => {
+    stream.on('error', reject);
+    stream.end(resolve);
+  });
+}
+
+/**
+ * Split the input into run files sorted by compare
+ */
+async function createSortedRuns(pathIn, compare, toRecord, runDir, stats) {
+  const runs = [];
+  let buffer = [];
+  let bufferBytes = 0;
+
+  const spill = async () => {
+    if (buffer.length === 0) return;
+    buffer.sort(compare);
+    const runPath = path.join(runDir, `run-${stats.runs++}.jsonl`);
+    await writeLines(runPath, buffer.map((r) => r.line));
+    runs.push(runPath);
+    buffer = [];
+    bufferBytes = 0;
+  };
+
+  const rl = readline.createInterface({ input: fs.createReadStream(pathIn), crlfDelay: Infinity });
+  for await (const line of rl) {
+    if (line.trim().length === 0) continue;
+    let event;
+    try {
+      event = JSON.parse(line);
+    } catch {
+      stats.invalidLines++;
+      continue;
+    }
+    buffer.push(toRecord(line, event));
+    bufferBytes += line.length;
+    if (bufferBytes >= stats.maxRunBytes) await spill();
+  }
+  await spill();
+  return runs;
+}
+
+/**
+ * k-way merge of sorted runs, writing the records keep() accepts
+ * @returns {Promise<number>} Records written
+ */
+async function mergeRuns(runs, pathOut, compare, toRecord, keep) {
+  const out = fs.createWriteStream(pathOut);
+  const readers = runs.map((runPath) =>
+    readline.createInterface({ input: fs.createReadStream(runPath), crlfDelay: Infinity })[Symbol.asyncIterator]()
+  );
+  const heap = new MinHeap(compare);
+
+  const advance = async (source) => {
+    const next = await readers[source].next();
+    if (next.done) return;
+    heap.push({ ...toRecord(next.value, JSON.parse(next.value)), source });
+  };
+
+  await Promise.all(readers.map((_, i) => advance(i)));
+
+  let written = 0;
+  while (heap.size > 0) {
+    const record = heap.pop();
+    if (keep(record)) {
+      written++;
+      if (!out.write(record.line + '\n')) {
+        await new Promise((resolve) => out.once('drain', resolve));
+      }
+    }
+    await advance(record.source);
+  }
+
+  await new Promise((resolve, reject) => {
+    out.on('error', reject);
+    out.end(resolve);
+  });
+  return written;
+}
+
+/**
+ * Sort a JSONL event export and remove duplicates using bounded memory
+ * @param {string} pathIn - Input JSONL path
+ * @param {string} pathOut - Output JSONL path
+ * @param {string|string[]|function} key - Dedup key (field, field list, or extractor); events
+ *   where it is missing are all kept
+ * @param {object} options - { sortBy, maxRunBytes, tempDir }
+ * @returns {Promise<object>} Stats
+ */
+async function sortAndDedupEvents(pathIn, pathOut, key = 'id', options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const sortKey = makeExtractor(opts.sortBy);
+  const dedupKey = makeExtractor(key);
+  const toRecord = (line, event) => ({
+    line,
+    sort: sortValue(sortKey(event)),
+    dedup: dedupValue(dedupKey(event)),
+  });
+  const stats = { inputEvents: 0, outputEvents: 0, duplicatesRemoved: 0, invalidLines: 0, runs: 0 };
+  const passStats = { ...stats, maxRunBytes: opts.maxRunBytes };
+
+  const runDir = await fs.promises.mkdtemp(path.join(opts.tempDir, 'event-sort-'));
+  try {
+    // Pass 1: by dedup key, keeping the earliest of each key
+    const byKeyRuns = await createSortedRuns(pathIn, byDedupKey, toRecord, runDir, passStats);
+    const uniquePath = path.join(runDir, 'unique.jsonl');
+    let lastKey = null;
+    const unique = await mergeRuns(byKeyRuns, uniquePath, byDedupKey, toRecord, (record) => {
+      stats.inputEvents++;
+      if (record.dedup !== null && record.dedup === lastKey) return false;
+      lastKey = record.dedup;
+      return true;
+    });
+    stats.invalidLines = passStats.invalidLines;
+    stats.duplicatesRemoved = stats.inputEvents - unique;
+    await Promise.all(byKeyRuns.map((runPath) => fs.promises.rm(runPath, { force: true })));
+
+    // Pass 2: by sort value
+    const sortRuns = await createSortedRuns(uniquePath, bySortValue, toRecord, runDir, passStats);
+    stats.outputEvents = await mergeRuns(sortRuns, pathOut, bySortValue, toRecord, () => true);
+    stats.runs = passStats.runs;
+  } finally {
+    await fs.promises.rm(runDir, { recursive: true, force: true });
+  }
+  return stats;
+}
+
+module.exports = {
+  sortAndDedupEvents,
+  MinHeap,
+};
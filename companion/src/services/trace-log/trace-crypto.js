@@ -0,0 +1,304 @@
+/**
+ * Trace Encryption
+ * XChaCha20-Poly1305 for trace data at rest.
+ *
+ * Node ships ChaCha20-Poly1305 with a 12-byte nonce only; XChaCha20 extends
+ * it to a 24-byte nonce by deriving a subkey with HChaCha20 from the key and
+ * the first 16 nonce bytes (draft-irtf-cfrg-xchacha). 24 bytes are enough to
+ * pick every nonce at random, so no counter has to survive restarts.
+ *
+ * Two uses:
+ *   - TraceWriter's encryptionKey option seals each frame's payload (see
+ *     trace-format.js), so nothing readable is written during capture
+ *   - encryptTrace / decryptTrace convert whole files, e.g. logs written
+ *     before encryption was enabled, or exports
+ *
+ * Whole-file format (STREAM construction: every chunk is its own AEAD
+ * message, so files are processed in constant memory):
+ *
+ *   magic      4B  'TRCX'
+ *   version    u8
+ *   reserved   3B
+ *   chunkSize  u32 plaintext bytes per chunk
+ *   noncePre   19B random
+ *   chunks     ceil(size / chunkSize) chunks (at least one) of
+ *              ciphertext + 16B tag
+ *
+ * Chunk i uses nonce noncePre || u32be(i) || last, where last is 1 for the
+ * final chunk only, and the file header as associated data. Reordered,
+ * dropped or appended chunks fail authentication, and so does a file cut at
+ * a chunk boundary.
+ */
+
+const fs = require('fs');
+const crypto = require('crypto');
+
+const MAGIC = Buffer.from('TRCX');
+const VERSION = 1;
+const KEY_BYTES = 32;
+const NONCE_BYTES = 24;
+const TAG_BYTES = 16;
+const NONCE_PREFIX_BYTES = 19;
+const HEADER_BYTES = 4 + 1 + 3 + 4 + NONCE_PREFIX_BYTES;
+const DEFAULT_CHUNK_SIZE = 64 * 1024;
+
+// 'expand 32-byte k'
+const SIGMA = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+function rotl(value, bits) {
+  return ((value << bits) | (value >>> (32 - bits))) >>> 0;
+}
+
+function quarterRound(s, a, b, c, d) {
+  s[a] = (s[a] + s[b]) >>> 0;
+  s[d] = rotl(s[d] ^ s[a], 16);
+  s[c] = (s[c] + s[d]) >>> 0;
+  s[b] = rotl(s[b] ^ s[c], 12);
+  s[a] = (s[a] + s[b]) >>> 0;
+  s[d] = rotl(s[d] ^ s[a], 8);
+  s[c] = (s[c] + s[d]) >>> 0;
+  s[b] = rotl(s[b] ^ s[c], 7);
+}
+
+/**
+ * HChaCha20: 32-byte subkey from a key and a 16-byte nonce
+ */
+function hchacha20(key, nonce16) {
+  const s = new Uint32Array(16);
+  s.set(SIGMA, 0);
+  for (let i = 0; i < 8; i++) s[4 + i] = key.readUInt32LE(i * 4);
+  for (let i = 0; i < 4; i++) s[12 + i] = nonce16.readUInt32LE(i * 4);
+  for (let round = 0; round < 10; round++) {
+    quarterRound(s, 0, 4, 8, 12);
+    quarterRound(s, 1, 5, 9, 13);
+    quarterRound(s, 2, 6, 10, 14);
+    quarterRound(s, 3, 7, 11, 15);
+    quarterRound(s, 0, 5, 10, 15);
+    quarterRound(s, 1, 6, 11, 12);
+    quarterRound(s, 2, 7, 8, 13);
+    quarterRound(s, 3, 4, 9, 14);
+  }
+  const out = Buffer.alloc(32);
+  for (let i = 0; i < 4; i++) {
+    out.writeUInt32LE(s[i], i * 4);
+    out.writeUInt32LE(s[12 + i], 16 + i * 4);
+  }
+  return out;
+}
+
+function subkeyAndNonce(key, nonce) {
+  const subNonce = Buffer.alloc(12);
+  nonce.copy(subNonce, 4, 16, 24);
+  return { subkey: hchacha20(key, nonce.subarray(0, 16)), subNonce };
+}
+
+/**
+ * Parse a 32-byte key from a Buffer, 64 hex characters or base64
+ */
+function parseTraceKey(key) {
+  let parsed = null;
+  if (Buffer.isBuffer(key) || key instanceof Uint8Array) parsed = Buffer.from(key);
+  else if (typeof key === 'string') {
+    const text = key.trim();
+    parsed = /^[0-9a-f]{64}$/i.test(text) ? Buffer.from(text, 'hex') : Buffer.from(text, 'base64');
+  }
+  if (!parsed || parsed.length !== KEY_BYTES) {
+    throw new Error('Trace encryption key must be 32 bytes (a Buffer, 64 hex characters or base64)');
+  }
+  return parsed;
+}
+
+/**
+ * Fresh random key, hex-encoded
+ */
+function generateTraceKey() {
+  return crypto.randomBytes(KEY_BYTES).toString('hex');
+}
+
+/**
+ * XChaCha20-Poly1305 encryption
+ * @returns {Buffer} ciphertext || tag
+ */
+function seal(key, nonce, plaintext, aad = null) {
+  const { subkey, subNonce } = subkeyAndNonce(parseTraceKey(key), nonce);
+  const cipher = crypto.createCipheriv('chacha20-poly1305', subkey, subNonce, { authTagLength: TAG_BYTES });
+  if (aad) cipher.setAAD(aad, { plaintextLength: plaintext.length });
+  return Buffer.concat([cipher.update(plaintext), cipher.final(), cipher.getAuthTag()]);
+}
+
+/**
+ * XChaCha20-Poly1305 decryption; throws when authentication fails
+ */
+function open(key, nonce, sealed, aad = null) {
+  if (sealed.length < TAG_BYTES) throw new Error('Encrypted trace data is truncated');
+  const { subkey, subNonce } = subkeyAndNonce(parseTraceKey(key), nonce);
+  const decipher = crypto.createDecipheriv('chacha20-poly1305', subkey, subNonce, {
+    authTagLength: TAG_BYTES,
+  });
+  const body = sealed.subarray(0, sealed.length - TAG_BYTES);
+  decipher.setAuthTag(sealed.subarray(sealed.length - TAG_BYTES));
+  if (aad) decipher.setAAD(aad, { plaintextLength: body.length });
+  try {
+    return Buffer.concat([decipher.update(body), decipher.final()]);
+  } catch {
+    throw new Error('Encrypted trace data failed authentication (wrong key or tampered data)');
+  }
+}
+
+/**
+ * Seal with a random nonce
+ * @returns {Buffer} nonce || ciphertext || tag
+ */
+function sealBuffer(key, plaintext, aad = null) {
+  const nonce = crypto.randomBytes(NONCE_BYTES);
+  return Buffer.concat([nonce, seal(key, nonce, plaintext, aad)]);
+}
+
+/**
+ * Inverse of sealBuffer
+ */
+function openBuffer(key, data, aad = null) {
+  if (data.length < NONCE_BYTES + TAG_BYTES) throw new Error('Encrypted trace data is truncated');
+  return open(key, data.subarray(0, NONCE_BYTES), data.subarray(NONCE_BYTES), aad);
+}
+
+// ---------------------------------------------------------------------------
+// Whole files
+// ---------------------------------------------------------------------------
+
+function chunkNonce(prefix, index, last) {
+  const nonce = Buffer.alloc(NONCE_BYTES);
+  prefix.copy(nonce, 0);
+  nonce.writeUInt32BE(index, NONCE_PREFIX_BYTES);
+  nonce[NONCE_BYTES - 1] = last ? 1 : 0;
+  return nonce;
+}
+
+/**
+ * Read a file in fixed-size pieces, holding one back so the last is known
+ */
+async function* pieces(filePath, size) {
+  let pending = Buffer.alloc(0);
+  for await (const data of fs.createReadStream(filePath, { highWaterMark: size })) {
+    pending = pending.length ? Buffer.concat([pending, data]) : data;
+    while (pending.length > size) {
+      yield { piece: pending.subarray(0, size), last: false };
+      pending = pending.subarray(size);
+    }
+  }
+  yield { piece: pending, last: true };
+}
+
+async function writeAtomically(outputPath, produce) {
+  const temporary = `${outputPath}.tmp-${process.pid}`;
+  const handle = await fs.promises.open(temporary, 'w', 0o600);
+  try {
+    await produce(handle);
+    await handle.sync();
+  } catch (error) {
+    await handle.close();
+    await fs.promises.rm(temporary, { force: true });
+    throw error;
+  }
+  await handle.close();
+  await fs.promises.rename(temporary, outputPath);
+}
+
+/**
+ * Encrypt a trace file
+ * @param {string} inputPath - Plaintext file
+ * @param {string|Buffer} key - 32-byte key (see parseTraceKey)
+ * @param {object} options - { outputPath (default: inputPath + '.enc'), chunkSize }
+ * @returns {Promise<object>} { outputPath, bytesIn, bytesOut, chunks }
+ */
+async function encryptTrace(inputPath, key, options = {}) {
+  const secret = parseTraceKey(key);
+  const outputPath = options.outputPath || `${inputPath}.enc`;
+  const chunkSize = options.chunkSize || DEFAULT_CHUNK_SIZE;
+  const header = Buffer.alloc(HEADER_BYTES);
+  MAGIC.copy(header, 0);
+  header.writeUInt8(VERSION, 4);
+  header.writeUInt32LE(chunkSize, 8);
+  const prefix = crypto.randomBytes(NONCE_PREFIX_BYTES);
+  prefix.copy(header, 12);
+
+  const result = { outputPath, bytesIn: 0, bytesOut: HEADER_BYTES, chunks: 0 };
+  await writeAtomically(outputPath, async (handle) => {
+    await handle.write(header);
+    for await (const { piece, last } of pieces(inputPath, chunkSize)) {
+      const sealed = seal(secret, chunkNonce(prefix, result.chunks, last), piece, header);
+      await handle.write(sealed);
+      result.bytesIn += piece.length;
+      result.bytesOut += sealed.length;
+      result.chunks++;
+    }
+  });
+  return result;
+}
+
+/**
+ * Decrypt a file written by encryptTrace; nothing is written unless every
+ * chunk authenticates
+ * @param {string} inputPath - Encrypted file
+ * @param {string|Buffer} key - The key it was encrypted with
+ * @param {object} options - { outputPath (default: inputPath without '.enc') }
+ * @returns {Promise<object>} { outputPath, bytesIn, bytesOut, chunks }
+ */
+async function decryptTrace(inputPath, key, options = {}) {
+  const secret = parseTraceKey(key);
+  const outputPath =
+    options.outputPath || (inputPath.endsWith('.enc') ? inputPath.slice(0, -4) : `${inputPath}.dec`);
+  if (outputPath === inputPath) throw new Error('decryptTrace needs an outputPath distinct from the input');
+
+  const handle = await fs.promises.open(inputPath, 'r');
+  const header = Buffer.alloc(HEADER_BYTES);
+  let chunkSize;
+  try {
+    const { bytesRead } = await handle.read(header, 0, HEADER_BYTES, 0);
+    if (bytesRead < HEADER_BYTES || !header.subarray(0, 4).equals(MAGIC)) {
+      throw new Error(`Not an encrypted trace file: ${inputPath}`);
+    }
+    if (header.readUInt8(4) !== VERSION) {
+      throw new Error(`Unsupported encrypted trace version: ${header.readUInt8(4)}`);
+    }
+    chunkSize = header.readUInt32LE(8);
+  } finally {
+    await handle.close();
+  }
+  const prefix = header.subarray(12, HEADER_BYTES);
+  const sealedSize = chunkSize + TAG_BYTES;
+
+  const result = { outputPath, bytesIn: HEADER_BYTES, bytesOut: 0, chunks: 0 };
+  await writeAtomically(outputPath, async (output) => {
+    const body = fs.createReadStream(inputPath, { start: HEADER_BYTES, highWaterMark: sealedSize });
+    let pending = Buffer.alloc(0);
+    const decryptPiece = async (piece, last) => {
+      const plaintext = open(secret, chunkNonce(prefix, result.chunks, last), piece, header);
+      await output.write(plaintext);
+      result.bytesIn += piece.length;
+      result.bytesOut += plaintext.length;
+      result.chunks++;
+    };
+    for await (const data of body) {
+      pending = pending.length ? Buffer.concat([pending, data]) : data;
+      while (pending.length > sealedSize) {
+        await decryptPiece(pending.subarray(0, sealedSize), false);
+        pending = pending.subarray(sealedSize);
+      }
+    }
+    await decryptPiece(pending, true);
+  });
+  return result;
+}
+
+module.exports = {
+  encryptTrace,
+  decryptTrace,
+  sealBuffer,
+  openBuffer,
+  parseTraceKey,
+  generateTraceKey,
+  hchacha20,
+  NONCE_BYTES,
+  TAG_BYTES,
+};
@@ -0,0 +1,271 @@
+/**
+ * Trace Log Frame Format
+ * Shared encoding for the append-only trace log written by TraceWriter and
+ * read back by TraceReader.
+ *
+ * Each frame is self-describing so readers can skip it without decompressing:
+ *
+ *   magic      4B  'TRCF'
+ *   version    u8
+ *   codec      u8  (0 = none, 1 = gzip, 2 = zstd)
+ *   flags      u16 (bit 0 = encrypted)
+ *   count      u32 number of events
+ *   minTs      f64 earliest event timestamp (epoch ms)
+ *   maxTs      f64 latest event timestamp (epoch ms)
+ *   typesLen   u16 length of the JSON-encoded event type list
+ *   rawLen     u32 uncompressed payload length
+ *   dataLen    u32 compressed payload length
+ *   checksum   u32 xxh32 of the compressed payload
+ *   types      typesLen bytes
+ *   payload    dataLen bytes (JSONL, one event per line)
+ *
+ * Encrypted frames carry nonce || ciphertext || tag as their payload: the
+ * compressed JSONL sealed with XChaCha20-Poly1305 (see trace-crypto.js),
+ * with the header and type list as associated data. The header itself stays
+ * readable, so recovery and time/type skipping work without the key; event
+ * counts, time ranges and event types are therefore not secret.
+ */
+
+const zlib = require('zlib');
+const { createHasher } = require('../../utils/content-hash');
+const { sealBuffer, openBuffer, NONCE_BYTES, TAG_BYTES } = require('./trace-crypto');
+
+const MAGIC = Buffer.from('TRCF');
+const NEWLINE = Buffer.from('\n');
+const VERSION = 1;
+const FIXED_HEADER_BYTES = 4 + 1 + 1 + 2 + 4 + 8 + 8 + 2 + 4 + 4 + 4;
+const FLAG_ENCRYPTED = 0x1;
+
+const CODECS = {
+  none: 0,
+  gzip: 1,
+  zstd: 2,
+};
+
+function zstdAvailable() {
+  return typeof zlib.zstdCompressSync === 'function';
+}
+
+/**
+ * Pick the best codec this runtime supports
+ */
+function resolveCodec(requested = 'zstd') {
+  if (requested === 'zstd' && !zstdAvailable()) return 'gzip';
+  if (!(requested in CODECS)) throw new Error(`Unknown trace codec: ${requested}`);
+  return requested;
+}
+
+function compress(codec, raw, level) {
+  switch (codec) {
+    case 'zstd':
+      return zlib.zstdCompressSync(raw, {
+        params: { [zlib.constants.ZSTD_c_compressionLevel]: level ?? 3 },
+      });
+    case 'gzip':
+      return zlib.gzipSync(raw, { level: level ?? 6 });
+    default:
+      return raw;
+  }
+}
+
+function decompress(codecId, data) {
+  switch (codecId) {
+    case CODECS.zstd:
+      if (!zstdAvailable()) throw new Error('Trace frame uses zstd but this runtime cannot decompress it');
+      return zlib.zstdDecompressSync(data);
+    case CODECS.gzip:
+      return zlib.gunzipSync(data);
+    case CODECS.none:
+      return data;
+    default:
+      throw new Error(`Unknown trace codec id: ${codecId}`);
+  }
+}
+
+function toBuffer(data) {
+  if (typeof data === 'string') return Buffer.from(data, 'utf8');
+  if (Buffer.isBuffer(data)) return data;
+  if (data instanceof ArrayBuffer) return Buffer.from(data);
+  if (ArrayBuffer.isView(data)) return Buffer.from(data.buffer, data.byteOffset, data.byteLength);
+  throw new TypeError('Expected string, Buffer, or TypedArray input');
+}
+
+/**
+ * Compress arbitrary bytes with a trace codec
+ * @param {string|Buffer|TypedArray} data
+ * @param {object} options - { codec, level }
+ * @returns {Buffer}
+ */
+function compressBuffer(data, options = {}) {
+  return compress(resolveCodec(options.codec), toBuffer(data), options.level);
+}
+
+/**
+ * Inverse of compressBuffer
+ * @param {Buffer|TypedArray} data
+ * @param {string} codec - 'zstd' | 'gzip' | 'none'
+ * @returns {Buffer}
+ */
+function decompressBuffer(data, codec) {
+  if (!(codec in CODECS)) throw new Error(`Unknown trace codec: ${codec}`);
+  return decompress(CODECS[codec], toBuffer(data));
+}
+
+function checksum(data) {
+  return createHasher('xxh32').update(data).digest().readUInt32BE(0);
+}
+
+/**
+ * Associated data for an encrypted frame: the fixed header up to the
+ * checksum, and the type list
+ */
+function frameAad(buffer, offset, header) {
+  const fixed = buffer.subarray(offset, offset + FIXED_HEADER_BYTES - 4);
+  const types = buffer.subarray(offset + FIXED_HEADER_BYTES, offset + FIXED_HEADER_BYTES + header.typesLen);
+  return Buffer.concat([fixed, types]);
+}
+
+/**
+ * Encode a batch of serialized events into one frame
+ * @param {Array<{line: string|Buffer, timestamp: number, type: string}>} batch - Lines may be
+ *   pre-serialized Buffers, which are copied into the frame without a string round trip
+ * @param {object} options - { codec, level, key } - key (32 bytes) encrypts the payload
+ * @returns {Buffer}
+ */
+function encodeFrame(batch, options = {}) {
+  const codec = resolveCodec(options.codec);
+  let minTs = Infinity;
+  let maxTs = -Infinity;
+  const types = new Set();
+
+  for (const item of batch) {
+    if (Number.isFinite(item.timestamp)) {
+      minTs = Math.min(minTs, item.timestamp);
+      maxTs = Math.max(maxTs, item.timestamp);
+    }
+    if (item.type) types.add(item.type);
+  }
+
+  const raw = batch.some((item) => typeof item.line !== 'string')
+    ? Buffer.concat(batch.flatMap((item) => [toBuffer(item.line), NEWLINE]))
+    : Buffer.from(batch.map((item) => item.line).join('\n') + '\n', 'utf8');
+  const compressed = compress(codec, raw, options.level);
+  const flags = options.key ? FLAG_ENCRYPTED : 0;
+  const dataLen = options.key ? NONCE_BYTES + compressed.length + TAG_BYTES : compressed.length;
+
+  const typesBytes = Buffer.from(JSON.stringify([...types]), 'utf8');
+  const header = Buffer.alloc(FIXED_HEADER_BYTES);
+  let o = 0;
+  MAGIC.copy(header, o);
+  o += 4;
+  header.writeUInt8(VERSION, o++);
+  header.writeUInt8(CODECS[codec], o++);
+  header.writeUInt16LE(flags, o);
+  o += 2;
+  header.writeUInt32LE(batch.length, o);
+  o += 4;
+  header.writeDoubleLE(Number.isFinite(minTs) ? minTs : 0, o);
+  o += 8;
+  header.writeDoubleLE(Number.isFinite(maxTs) ? maxTs : 0, o);
+  o += 8;
+  header.writeUInt16LE(typesBytes.length, o);
+  o += 2;
+  header.writeUInt32LE(raw.length, o);
+  o += 4;
+  header.writeUInt32LE(dataLen, o);
+  o += 4;
+
+  const data = options.key
+    ? sealBuffer(options.key, compressed, Buffer.concat([header.subarray(0, o), typesBytes]))
+    : compressed;
+  header.writeUInt32LE(checksum(data), o);
+
+  return Buffer.concat([header, typesBytes, data]);
+}
+
+/**
+ * Decode a frame header
+ * @returns {object|null} Header fields, or null if the buffer does not hold a full header
+ */
+function decodeHeader(buffer, offset = 0) {
+  if (buffer.length - offset < FIXED_HEADER_BYTES) return null;
+  if (!buffer.subarray(offset, offset + 4).equals(MAGIC)) {
+    throw new Error(`Corrupt trace frame at offset ${offset}: bad magic`);
+  }
+
+  let o = offset + 4;
+  const version = buffer.readUInt8(o++);
+  const codecId = buffer.readUInt8(o++);
+  const flags = buffer.readUInt16LE(o);
+  o += 2;
+  const count = buffer.readUInt32LE(o);
+  o += 4;
+  const minTs = buffer.readDoubleLE(o);
+  o += 8;
+  const maxTs = buffer.readDoubleLE(o);
+  o += 8;
+  const typesLen = buffer.readUInt16LE(o);
+  o += 2;
+  const rawLen = buffer.readUInt32LE(o);
+  o += 4;
+  const dataLen = buffer.readUInt32LE(o);
+  o += 4;
+  const crc = buffer.readUInt32LE(o);
+
+  return {
+    version,
+    codecId,
+    flags,
+    count,
+    minTs,
+    maxTs,
+    typesLen,
+    rawLen,
+    dataLen,
+    checksum: crc,
+    headerBytes: FIXED_HEADER_BYTES + typesLen,
+    frameBytes: FIXED_HEADER_BYTES + typesLen + dataLen,
+  };
+}
+
+function decodeTypes(buffer, offset, header) {
+  const start = offset + FIXED_HEADER_BYTES;
+  return JSON.parse(buffer.subarray(start, start + header.typesLen).toString('utf8'));
+}
+
+/**
+ * Decode a frame payload into serialized event lines
+ * @param {object} options - { key, aad } for encrypted frames (aad from frameAad)
+ */
+function decodePayload(data, header, options = {}) {
+  if (checksum(data) !== header.checksum) {
+    throw new Error('Corrupt trace frame: checksum mismatch');
+  }
+  let compressed = data;
+  if (header.flags & FLAG_ENCRYPTED) {
+    if (!options.key) throw new Error('Trace frame is encrypted; a key is required to read it');
+    compressed = openBuffer(options.key, data, options.aad);
+  }
+  const raw = decompress(header.codecId, compressed);
+  return raw
+    .toString('utf8')
+    .split('\n')
+    .filter((line) => line.length > 0);
+}
+
+module.exports = {
+  MAGIC,
+  VERSION,
+  CODECS,
+  FIXED_HEADER_BYTES,
+  FLAG_ENCRYPTED,
+  resolveCodec,
+  compressBuffer,
+  decompressBuffer,
+  encodeFrame,
+  decodeHeader,
+  decodeTypes,
+  decodePayload,
+  frameAad,
+  checksum,
+};
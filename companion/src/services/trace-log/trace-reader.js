@@ -0,0 +1,237 @@
+/**
+ * Trace Reader
+ * Streams events back out of TraceWriter logs with time-range and event-type
+ * filters. Frame headers carry min/max timestamps and the set of event types,
+ * so non-matching frames are skipped without reading or decompressing them.
+ * Encrypted logs (TraceWriter's encryptionKey) need the same key to decode.
+ */
+
+const fs = require('fs');
+const path = require('path');
+const {
+  decodeHeader,
+  decodeTypes,
+  decodePayload,
+  frameAad,
+  FIXED_HEADER_BYTES,
+  FLAG_ENCRYPTED,
+} = require('./trace-format');
+const { parseTraceKey } = require('./trace-crypto');
+const { listTraceFiles } = require('./trace-writer');
+const { toMillis } = require('../../utils/timestamps');
+
+const HEADER_READ_BYTES = 4096;
+
+class TraceReader {
+  /**
+   * @param {string} source - A trace file or a directory of rotated trace files
+   * @param {object} options - { prefix, key } - key decrypts encrypted frames
+   */
+  constructor(source, options = {}) {
+    this.source = source;
+    this.options = options;
+    this.key = options.key ? parseTraceKey(options.key) : null;
+    this.index = null;
+    this.stats = { framesIndexed: 0, framesSkipped: 0, framesDecoded: 0, corruptFrames: 0 };
+  }
+
+  resolveFiles() {
+    const stat = fs.statSync(this.source);
+    if (stat.isDirectory()) return listTraceFiles(this.source, this.options.prefix);
+    return [this.source];
+  }
+
+  /**
+   * Build the frame index by reading headers only
+   * @returns {Promise<Array>} Frame descriptors in file order
+   */
+  async open() {
+    const index = [];
+    for (const file of this.resolveFiles()) {
+      const handle = await fs.promises.open(file, 'r');
+      try {
+        const { size } = await handle.stat();
+        let offset = 0;
+        while (offset + FIXED_HEADER_BYTES <= size) {
+          const probe = Buffer.alloc(Math.min(HEADER_READ_BYTES, size - offset));
+          await handle.read(probe, 0, probe.length, offset);
+
+          let header;
+          try {
+            header = decodeHeader(probe);
+          } catch {
+            this.stats.corruptFrames++;
+            break;
+          }
+          if (!header || offset + header.frameBytes > size) break; // Torn tail frame
+
+          let headerBuf = probe;
+          if (header.headerBytes > probe.length) {
+            headerBuf = Buffer.alloc(header.headerBytes);
+            await handle.read(headerBuf, 0, header.headerBytes, offset);
+          }
+
+          index.push({
+            file,
+            offset,
+            header,
+            types: new Set(decodeTypes(headerBuf, 0, header)),
+            aad: header.flags & FLAG_ENCRYPTED ? frameAad(headerBuf, 0, header) : null,
+          });
+          offset += header.frameBytes;
+        }
+      } finally {
+        await handle.close();
+      }
+    }
+
+    this.index = index;
+    this.stats.framesIndexed = index.length;
+    return index;
+  }
+
+  async ensureIndex() {
+    if (!this.index) await this.open();
+    return this.index;
+  }
+
+  /**
+   * Summary of the indexed trace without decoding any payloads
+   */
+  async describe() {
+    const index = await this.ensureIndex();
+    const types = new Set();
+    let events = 0;
+    let minTs = Infinity;
+    let maxTs = -Infinity;
+    for (const frame of index) {
+      events += frame.header.count;
+      if (frame.header.count > 0) {
+        minTs = Math.min(minTs, frame.header.minTs);
+        maxTs = Math.max(maxTs, frame.header.maxTs);
+      }
+      frame.types.forEach((t) => types.add(t));
+    }
+    return {
+      files: [...new Set(index.map((f) => f.file))].map((f) => path.basename(f)),
+      frames: index.length,
+      events,
+      start: Number.isFinite(minTs) ? minTs : null,
+      end: Number.isFinite(maxTs) ? maxTs : null,
+      types: [...types],
+    };
+  }
+
+  /**
+   * Position of the first frame that may contain events at or after `timestamp`
+   */
+  async seek(timestamp) {
+    const index = await this.ensureIndex();
+    const target = toMillis(timestamp);
+    const position = index.findIndex((frame) => frame.header.maxTs >= target);
+    return position === -1 ? index.length : position;
+  }
+
+  frameMatches(frame, since, until, types) {
+    const { header } = frame;
+    if (header.count === 0) return false;
+    if (since !== null && header.maxTs < since) return false;
+    if (until !== null && header.minTs > until) return false;
+    if (types && frame.types.size > 0 && ![...types].some((t) => frame.types.has(t))) return false;
+    return true;
+  }
+
+  /**
+   * Stream matching events
+   * @param {object} filter - { since, until, types, limit, raw, fromFrame }
+   *   raw: yield serialized lines instead of parsed objects when no per-event filtering is needed
+   */
+  async *events(filter = {}) {
+    const index = await this.ensureIndex();
+    const since = toMillis(filter.since);
+    const until = toMillis(filter.until);
+    const types = filter.types ? new Set(filter.types) : null;
+    const limit = filter.limit ?? Infinity;
+    let start = filter.fromFrame ?? 0;
+    if (since !== null && filter.fromFrame === undefined) start = await this.seek(since);
+
+    let emitted = 0;
+    let handle = null;
+    let handleFile = null;
+
+    try {
+      for (let f = start; f < index.length && emitted < limit; f++) {
+        const frame = index[f];
+        if (!this.frameMatches(frame, since, until, types)) {
+          this.stats.framesSkipped++;
+          continue;
+        }
+
+        if (frame.aad && !this.key) {
+          throw new Error(`${frame.file} is encrypted; pass the key to TraceReader`);
+        }
+
+        if (handleFile !== frame.file) {
+          if (handle) await handle.close();
+          handle = await fs.promises.open(frame.file, 'r');
+          handleFile = frame.file;
+        }
+
+        const data = Buffer.alloc(frame.header.dataLen);
+        await handle.read(data, 0, data.length, frame.offset + frame.header.headerBytes);
+
+        let lines;
+        try {
+          lines = decodePayload(data, frame.header, { key: this.key, aad: frame.aad });
+        } catch (error) {
+          this.stats.corruptFrames++;
+          console.warn(`[TRACE] Skipping unreadable frame in ${frame.file}@${frame.offset}: ${error.message}`);
+          continue;
+        }
+        this.stats.framesDecoded++;
+
+        // A frame fully inside the window with a single matching type needs no per-event checks
+        const fullyInside =
+          (since === null || frame.header.minTs >= since) &&
+          (until === null || frame.header.maxTs <= until) &&
+          (!types || [...frame.types].every((t) => types.has(t)));
+
+        for (const line of lines) {
+          if (emitted >= limit) break;
+          if (fullyInside) {
+            emitted++;
+            yield filter.raw ? line : JSON.parse(line);
+            continue;
+          }
+
+          const event = JSON.parse(line);
+          const ts = toMillis(event.timestamp);
+          if (since !== null && ts !== null && ts < since) continue;
+          if (until !== null && ts !== null && ts > until) continue;
+          if (types && !types.has(event.type)) continue;
+          emitted++;
+          yield filter.raw ? line : event;
+        }
+      }
+    } finally {
+      if (handle) await handle.close();
+    }
+  }
+
+  /**
+   * Collect matching events into an array
+   */
+  async readAll(filter = {}) {
+    const events = [];
+    for await (const event of this.events(filter)) events.push(event);
+    return events;
+  }
+
+  getStats() {
+    return { ...this.stats };
+  }
+}
+
+module.exports = {
+  TraceReader,
+};
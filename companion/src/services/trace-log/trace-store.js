@@ -0,0 +1,363 @@
+/**
+ * Trace Store
+ * Indexed SQLite store for trace events, file snapshots and sessions, so
+ * queries like "all edits to foo.rs" hit an index instead of scanning logs.
+ *
+ * Snapshot contents are stored once per SHA-256 digest (content-addressed), so
+ * repeated snapshots of an unchanged file cost a single row.
+ */
+
+const sqlite3 = require('sqlite3');
+const fs = require('fs');
+const path = require('path');
+const { hashContent } = require('../../utils/content-hash');
+const { toMillis } = require('../../utils/timestamps');
+
+const SCHEMA = [
+  `CREATE TABLE IF NOT EXISTS trace_sessions (
+    id TEXT PRIMARY KEY,
+    workspace_path TEXT,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER,
+    metadata TEXT
+  )`,
+  `CREATE TABLE IF NOT EXISTS trace_events (
+    id TEXT PRIMARY KEY,
+    session_id TEXT,
+    timestamp INTEGER NOT NULL,
+    type TEXT,
+    file_path TEXT,
+    payload TEXT
+  )`,
+  `CREATE TABLE IF NOT EXISTS trace_blobs (
+    hash TEXT PRIMARY KEY,
+    size INTEGER NOT NULL,
+    content BLOB NOT NULL
+  )`,
+  `CREATE TABLE IF NOT EXISTS trace_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT,
+    event_id TEXT,
+    file_path TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    content_hash TEXT NOT NULL,
+    size INTEGER NOT NULL
+  )`,
+  'CREATE INDEX IF NOT EXISTS idx_trace_events_time ON trace_events(timestamp)',
+  'CREATE INDEX IF NOT EXISTS idx_trace_events_file ON trace_events(file_path, timestamp)',
+  'CREATE INDEX IF NOT EXISTS idx_trace_events_session ON trace_events(session_id, timestamp)',
+  'CREATE INDEX IF NOT EXISTS idx_trace_events_type ON trace_events(type, timestamp)',
+  'CREATE INDEX IF NOT EXISTS idx_trace_snapshots_file ON trace_snapshots(file_path, timestamp)',
+  'CREATE INDEX IF NOT EXISTS idx_trace_sessions_time ON trace_sessions(started_at)',
+];
+
+function eventFilePath(event) {
+  const details = event.details && typeof event.details === 'object' ? event.details : {};
+  return event.file_path || event.filePath || details.file_path || details.file || null;
+}
+
+function rowToEvent(row) {
+  return {
+    ...JSON.parse(row.payload),
+    id: row.id,
+    session_id: row.session_id,
+    timestamp: row.timestamp,
+    type: row.type,
+    file_path: row.file_path,
+  };
+}
+
+class TraceStore {
+  constructor(dbPath) {
+    this.dbPath = dbPath;
+    this.db = null;
+    this._initPromise = null;
+  }
+
+  /**
+   * Open the database and create tables
+   */
+  async init() {
+    if (this._initPromise) return this._initPromise;
+
+    this._initPromise = new Promise((resolve, reject) => {
+      fs.mkdirSync(path.dirname(this.dbPath), { recursive: true });
+      this.db = new sqlite3.Database(this.dbPath, async (err) => {
+        if (err) {
+          console.error('[TRACE-STORE] Failed to open database:', err);
+          reject(err);
+          return;
+        }
+        try {
+          await this.run('PRAGMA journal_mode=WAL');
+          await this.run('PRAGMA synchronous=NORMAL');
+          for (const statement of SCHEMA) await this.run(statement);
+          resolve();
+        } catch (schemaError) {
+          reject(schemaError);
+        }
+      });
+    });
+    return this._initPromise;
+  }
+
+  run(sql, params = []) {
+    return new Promise((resolve, reject) => {
+      this.db.run(sql, params, function onRun(err) {
+        if (err) reject(err);
+        else resolve({ changes: this.changes, lastID: this.lastID });
+      });
+    });
+  }
+
+  all(sql, params = []) {
+    return new Promise((resolve, reject) => {
+      this.db.all(sql, params, (err, rows) => (err ? reject(err) : resolve(rows)));
+    });
+  }
+
+  get(sql, params = []) {
+    return new Promise((resolve, reject) => {
+      this.db.get(sql, params, (err, row) => (err ? reject(err) : resolve(row || null)));
+    });
+  }
+
+  async transaction(fn) {
+    await this.run('BEGIN IMMEDIATE');
+    try {
+      const result = await fn();
+      await this.run('COMMIT');
+      return result;
+    } catch (error) {
+      await this.run('ROLLBACK').catch(() => {});
+      throw error;
+    }
+  }
+
+  // ---------------------------------------------------------------------------
+  // Sessions
+  // ---------------------------------------------------------------------------
+
+  async upsertSession(session) {
+    await this.init();
+    await this.run(
+      `INSERT INTO trace_sessions (id, workspace_path, started_at, ended_at, metadata)
+       VALUES (?, ?, ?, ?, ?)
+       ON CONFLICT(id) DO UPDATE SET
+         workspace_path = COALESCE(excluded.workspace_path, workspace_path),
+         ended_at = COALESCE(excluded.ended_at, ended_at),
+         metadata = COALESCE(excluded.metadata, metadata)`,
+      [
+        session.id,
+        session.workspace_path || null,
+        toMillis(session.started_at) ?? Date.now(),
+        toMillis(session.ended_at),
+        session.metadata ? JSON.stringify(session.metadata) : null,
+      ]
+    );
+    return session;
+  }
+
+  async endSession(sessionId, endedAt = Date.now()) {
+    await this.init();
+    return this.run('UPDATE trace_sessions SET ended_at = ? WHERE id = ?', [toMillis(endedAt), sessionId]);
+  }
+
+  async getSessions(options = {}) {
+    await this.init();
+    const rows = await this.all(
+      `SELECT * FROM trace_sessions
+       WHERE started_at >= ? AND started_at <= ?
+       ORDER BY started_at DESC LIMIT ?`,
+      [toMillis(options.since) ?? 0, toMillis(options.until) ?? Number.MAX_SAFE_INTEGER, options.limit || 1000]
+    );
+    return rows.map((row) => ({ ...row, metadata: row.metadata ? JSON.parse(row.metadata) : null }));
+  }
+
+  // ---------------------------------------------------------------------------
+  // Events
+  // ---------------------------------------------------------------------------
+
+  /**
+   * Insert a batch of events in one transaction
+   */
+  async insertEvents(events) {
+    await this.init();
+    return this.transaction(async () => {
+      for (const event of events) {
+        await this.run(
+          `INSERT OR REPLACE INTO trace_events (id, session_id, timestamp, type, file_path, payload)
+           VALUES (?, ?, ?, ?, ?, ?)`,
+          [
+            String(event.id),
+            event.session_id || null,
+            toMillis(event.timestamp) ?? Date.now(),
+            event.type || null,
+            eventFilePath(event),
+            JSON.stringify(event),
+          ]
+        );
+      }
+      return events.length;
+    });
+  }
+
+  async insertEvent(event) {
+    return this.insertEvents([event]);
+  }
+
+  buildEventQuery(where, params, options) {
+    const clauses = [...where];
+    const values = [...params];
+    if (options.since !== undefined) {
+      clauses.push('timestamp >= ?');
+      values.push(toMillis(options.since));
+    }
+    if (options.until !== undefined) {
+      clauses.push('timestamp <= ?');
+      values.push(toMillis(options.until));
+    }
+    if (options.types && options.types.length > 0) {
+      clauses.push(`type IN (${options.types.map(() => '?').join(',')})`);
+      values.push(...options.types);
+    }
+    const order = options.order === 'desc' ? 'DESC' : 'ASC';
+    values.push(options.limit || 10000, options.offset || 0);
+    return {
+      sql: `SELECT * FROM trace_events ${clauses.length ? `WHERE ${clauses.join(' AND ')}` : ''}
+            ORDER BY timestamp ${order} LIMIT ? OFFSET ?`,
+      values,
+    };
+  }
+
+  /**
+   * Events in a time range
+   * @param {number|string} since - Start (inclusive)
+   * @param {number|string} until - End (inclusive)
+   * @param {object} options - { types, limit, offset, order }
+   */
+  async getEventsInRange(since, until, options = {}) {
+    await this.init();
+    const { sql, values } = this.buildEventQuery([], [], { ...options, since, until });
+    return (await this.all(sql, values)).map(rowToEvent);
+  }
+
+  async getEventsByFile(filePath, options = {}) {
+    await this.init();
+    const { sql, values } = this.buildEventQuery(['file_path = ?'], [filePath], options);
+    return (await this.all(sql, values)).map(rowToEvent);
+  }
+
+  async getEventsBySession(sessionId, options = {}) {
+    await this.init();
+    const { sql, values } = this.buildEventQuery(['session_id = ?'], [sessionId], options);
+    return (await this.all(sql, values)).map(rowToEvent);
+  }
+
+  /**
+   * Per-file event counts, most active first
+   */
+  async getFileActivity(options = {}) {
+    await this.init();
+    return this.all(
+      `SELECT file_path, COUNT(*) AS events, MIN(timestamp) AS first_seen, MAX(timestamp) AS last_seen
+       FROM trace_events
+       WHERE file_path IS NOT NULL AND timestamp >= ? AND timestamp <= ?
+       GROUP BY file_path ORDER BY events DESC LIMIT ?`,
+      [toMillis(options.since) ?? 0, toMillis(options.until) ?? Number.MAX_SAFE_INTEGER, options.limit || 100]
+    );
+  }
+
+  // ---------------------------------------------------------------------------
+  // Snapshots
+  // ---------------------------------------------------------------------------
+
+  /**
+   * Store a file snapshot; content is deduplicated by hash
+   * @returns {Promise<object>} { id, content_hash, deduplicated }
+   */
+  async saveSnapshot(filePath, content, meta = {}) {
+    await this.init();
+    const buffer = Buffer.isBuffer(content) ? content : Buffer.from(content, 'utf8');
+    const contentHash = hashContent(buffer);
+
+    return this.transaction(async () => {
+      const inserted = await this.run('INSERT OR IGNORE INTO trace_blobs (hash, size, content) VALUES (?, ?, ?)', [
+        contentHash,
+        buffer.length,
+        buffer,
+      ]);
+      const { lastID } = await this.run(
+        `INSERT INTO trace_snapshots (session_id, event_id, file_path, timestamp, content_hash, size)
+         VALUES (?, ?, ?, ?, ?, ?)`,
+        [
+          meta.session_id || null,
+          meta.event_id || null,
+          filePath,
+          toMillis(meta.timestamp) ?? Date.now(),
+          contentHash,
+          buffer.length,
+        ]
+      );
+      return { id: lastID, content_hash: contentHash, deduplicated: inserted.changes === 0 };
+    });
+  }
+
+  async getSnapshots(filePath, options = {}) {
+    await this.init();
+    const rows = await this.all(
+      `SELECT s.*${options.includeContent ? ', b.content' : ''}
+       FROM trace_snapshots s
+       ${options.includeContent ? 'JOIN trace_blobs b ON b.hash = s.content_hash' : ''}
+       WHERE s.file_path = ? AND s.timestamp >= ? AND s.timestamp <= ?
+       ORDER BY s.timestamp ASC LIMIT ?`,
+      [
+        filePath,
+        toMillis(options.since) ?? 0,
+        toMillis(options.until) ?? Number.MAX_SAFE_INTEGER,
+        options.limit || 1000,
+      ]
+    );
+    if (!options.includeContent) return rows;
+    return rows.map((row) => ({ ...row, content: row.content.toString('utf8') }));
+  }
+
+  async getSnapshotContent(contentHash) {
+    await this.init();
+    const row = await this.get('SELECT content FROM trace_blobs WHERE hash = ?', [contentHash]);
+    return row ? row.content : null;
+  }
+
+  async getStats() {
+    await this.init();
+    const [events, snapshots, blobs, sessions] = await Promise.all([
+      this.get('SELECT COUNT(*) AS count FROM trace_events'),
+      this.get('SELECT COUNT(*) AS count, COALESCE(SUM(size), 0) AS bytes FROM trace_snapshots'),
+      this.get('SELECT COUNT(*) AS count, COALESCE(SUM(size), 0) AS bytes FROM trace_blobs'),
+      this.get('SELECT COUNT(*) AS count FROM trace_sessions'),
+    ]);
+    return {
+      events: events.count,
+      sessions: sessions.count,
+      snapshots: snapshots.count,
+      logicalSnapshotBytes: snapshots.bytes,
+      storedBlobBytes: blobs.bytes,
+      uniqueBlobs: blobs.count,
+    };
+  }
+
+  close() {
+    return new Promise((resolve) => {
+      if (!this.db) return resolve();
+      this.db.close(() => {
+        this.db = null;
+        this._initPromise = null;
+        resolve();
+      });
+    });
+  }
+}
+
+module.exports = {
+  TraceStore,
+};
@@ -0,0 +1,412 @@
+/**
+ * Trace Writer
+ * Append-only, crash-safe trace event log.
+ *
+ * Events are batched in memory and written as self-contained compressed frames
+ * (see trace-format.js). A crash can at worst leave a partial frame at the end
+ * of the active file; it is detected by length/checksum and truncated the next
+ * time the writer opens the file. Files rotate by size and age.
+ *
+ * With the redact option, every event is redacted (see utils/redaction) in
+ * append(), before it is batched, so unredacted data never reaches disk.
+ * With encryptionKey, every frame's payload is encrypted (see trace-crypto);
+ * read such logs with TraceReader's key option.
+ */
+
+const fs = require('fs');
+const path = require('path');
+const EventEmitter = require('events');
+const { encodeFrame, decodeHeader, checksum, resolveCodec } = require('./trace-format');
+const { parseTraceKey } = require('./trace-crypto');
+const { redactValue } = require('../../utils/redaction');
+
+const FILE_EXTENSION = '.trc';
+
+const DEFAULT_OPTIONS = {
+  prefix: 'trace',
+  codec: 'zstd',
+  level: undefined,
+  maxBatchEvents: 500,
+  maxBatchBytes: 1024 * 1024, // 1MB
+  flushIntervalMs: 1000,
+  fsync: 'interval', // 'always' | 'interval' | 'never'
+  fsyncIntervalMs: 5000,
+  maxFileBytes: 64 * 1024 * 1024, // 64MB
+  maxFileAgeMs: 60 * 60 * 1000, // 1 hour
+  highWaterMark: 10000, // Pending events before append() signals backpressure
+  redact: null, // Redaction policy (see utils/redaction), or a function(event) => event
+  encryptionKey: null, // 32-byte key (Buffer, hex or base64) to encrypt frame payloads
+};
+
+function formatFileStamp(date) {
+  const pad = (n) => String(n).padStart(2, '0');
+  return (
+    `${date.getUTCFullYear()}${pad(date.getUTCMonth() + 1)}${pad(date.getUTCDate())}-` +
+    `${pad(date.getUTCHours())}${pad(date.getUTCMinutes())}${pad(date.getUTCSeconds())}`
+  );
+}
+
+/**
+ * When a trace file was created, from the stamp in its name (prefix-YYYYMMDD-HHMMSS-seq.trc)
+ * @returns {number|null} Epoch ms, or null for a name without a stamp
+ */
+function parseFileStamp(filePath) {
+  const match = /-(\d{4})(\d{2})(\d{2})-(\d{2})(\d{2})(\d{2})-\d+\.trc$/.exec(path.basename(filePath));
+  if (!match) return null;
+  const [, year, month, day, hours, minutes, seconds] = match.map(Number);
+  return Date.UTC(year, month - 1, day, hours, minutes, seconds);
+}
+
+/**
+ * List trace files for a prefix in chronological order
+ */
+function listTraceFiles(directory, prefix = DEFAULT_OPTIONS.prefix) {
+  if (!fs.existsSync(directory)) return [];
+  return fs
+    .readdirSync(directory)
+    .filter((name) => name.startsWith(`${prefix}-`) && name.endsWith(FILE_EXTENSION))
+    .sort()
+    .map((name) => path.join(directory, name));
+}
+
+/**
+ * Scan a trace file and truncate any partial or corrupt tail frame
+ * @returns {object} { validBytes, frames, events, truncatedBytes, firstTimestamp }
+ */
+function recoverTraceFile(filePath) {
+  const fd = fs.openSync(filePath, 'r+');
+  try {
+    const size = fs.fstatSync(fd).size;
+    let offset = 0;
+    let frames = 0;
+    let events = 0;
+    let firstTs = null;
+    const headerBuf = Buffer.alloc(64 * 1024);
+
+    while (offset < size) {
+      const read = fs.readSync(fd, headerBuf, 0, Math.min(headerBuf.length, size - offset), offset);
+      let header;
+      try {
+        header = decodeHeader(headerBuf.subarray(0, read));
+      } catch {
+        break; // Bad magic: treat the rest of the file as garbage
+      }
+      if (!header || offset + header.frameBytes > size) break;
+
+      // Only the last frame can be torn by a crash, so only verify it fully
+      if (offset + header.frameBytes === size) {
+        const data = Buffer.alloc(header.dataLen);
+        fs.readSync(fd, data, 0, header.dataLen, offset + header.headerBytes);
+        if (checksum(data) !== header.checksum) break;
+      }
+
+      if (firstTs === null && header.count > 0) firstTs = header.minTs;
+      frames++;
+      events += header.count;
+      offset += header.frameBytes;
+    }
+
+    const truncatedBytes = size - offset;
+    if (truncatedBytes > 0) {
+      fs.ftruncateSync(fd, offset);
+      fs.fsyncSync(fd);
+    }
+    return { validBytes: offset, frames, events, truncatedBytes, firstTimestamp: firstTs };
+  } finally {
+    fs.closeSync(fd);
+  }
+}
+
+function eventMeta(event, meta) {
+  let parsed = null;
+  if (typeof event === 'string' && (!meta || meta.timestamp === undefined || meta.type === undefined)) {
+    try {
+      parsed = JSON.parse(event);
+    } catch {
+      throw new Error('TraceWriter.append expects a JSON-serialized event or an object');
+    }
+  }
+  const source = meta || parsed || event;
+  const rawTs = source.timestamp ?? parsed?.timestamp ?? event.timestamp;
+  const timestamp = typeof rawTs === 'number' ? rawTs : Date.parse(rawTs);
+  return {
+    timestamp: Number.isFinite(timestamp) ? timestamp : Date.now(),
+    type: source.type ?? parsed?.type ?? event.type ?? null,
+  };
+}
+
+class TraceWriter extends EventEmitter {
+  /**
+   * @param {string} directory - Directory to hold trace files
+   * @param {object} options - See DEFAULT_OPTIONS
+   */
+  constructor(directory, options = {}) {
+    super();
+    this.directory = directory;
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.options.codec = resolveCodec(this.options.codec);
+    this.encryptionKey = this.options.encryptionKey ? parseTraceKey(this.options.encryptionKey) : null;
+
+    this.batch = [];
+    this.batchBytes = 0;
+    this.pendingEvents = 0;
+    this.needsDrain = false;
+
+    this.handle = null;
+    this.currentFile = null;
+    this.fileBytes = 0;
+    this.fileOpenedAt = 0;
+    this.fileSeq = 0;
+
+    this.writeChain = Promise.resolve();
+    this.flushTimer = null;
+    this.fsyncTimer = null;
+    this.dirty = false;
+    this.closed = false;
+    this.lastWriteError = null;
+
+    this.stats = {
+      eventsAppended: 0,
+      eventsWritten: 0,
+      framesWritten: 0,
+      bytesWritten: 0,
+      rotations: 0,
+      recoveredBytesTruncated: 0,
+      writeErrors: 0,
+      redactions: 0,
+    };
+  }
+
+  /**
+   * Open (or recover and resume) the active trace file
+   */
+  async open() {
+    await fs.promises.mkdir(this.directory, { recursive: true });
+
+    const existing = listTraceFiles(this.directory, this.options.prefix);
+    const last = existing[existing.length - 1];
+    if (last) {
+      const recovery = recoverTraceFile(last);
+      this.stats.recoveredBytesTruncated += recovery.truncatedBytes;
+      if (recovery.truncatedBytes > 0) {
+        console.warn(`[TRACE] Truncated ${recovery.truncatedBytes} bytes of partial frame from ${last}`);
+      }
+      // Age counts from the file's creation, not its first event: backfilled or clock-skewed
+      // events carry old timestamps and would rotate a fresh file straight away
+      const stat = fs.statSync(last);
+      const openedAt = parseFileStamp(last) ?? (stat.birthtimeMs || stat.mtimeMs);
+      if (!this.shouldRotate(recovery.validBytes, openedAt)) {
+        await this.openFile(last, recovery.validBytes, openedAt);
+      }
+    }
+    if (!this.handle) await this.openNewFile();
+
+    this.flushTimer = setInterval(() => {
+      this.flush().catch(() => {});
+    }, this.options.flushIntervalMs);
+    this.flushTimer.unref?.();
+
+    if (this.options.fsync === 'interval') {
+      this.fsyncTimer = setInterval(() => this.sync().catch(() => {}), this.options.fsyncIntervalMs);
+      this.fsyncTimer.unref?.();
+    }
+    return this;
+  }
+
+  async openFile(filePath, size, openedAt) {
+    this.handle = await fs.promises.open(filePath, 'a');
+    this.currentFile = filePath;
+    this.fileBytes = size;
+    this.fileOpenedAt = openedAt;
+  }
+
+  async openNewFile() {
+    const stamp = formatFileStamp(new Date());
+    let filePath;
+    do {
+      filePath = path.join(
+        this.directory,
+        `${this.options.prefix}-${stamp}-${String(this.fileSeq++).padStart(4, '0')}${FILE_EXTENSION}`
+      );
+    } while (fs.existsSync(filePath));
+    await this.openFile(filePath, 0, Date.now());
+  }
+
+  shouldRotate(size = this.fileBytes, openedAt = this.fileOpenedAt) {
+    if (size === 0) return false;
+    return size >= this.options.maxFileBytes || Date.now() - openedAt >= this.options.maxFileAgeMs;
+  }
+
+  async rotate() {
+    const previous = this.currentFile;
+    if (this.handle) {
+      await this.handle.sync();
+      await this.handle.close();
+      this.handle = null;
+    }
+    await this.openNewFile();
+    this.stats.rotations++;
+    this.emit('rotate', { previous, current: this.currentFile });
+  }
+
+  /**
+   * Queue an event for writing
+   * @param {string|object} event - JSON-serialized event or plain object
+   * @param {object} meta - Optional { timestamp, type } to avoid re-parsing serialized events
+   * @returns {boolean} false when the caller should wait for 'drain' before appending more
+   */
+  append(event, meta = null) {
+    if (this.closed) throw new Error('TraceWriter is closed');
+    const { timestamp, type } = eventMeta(event, meta);
+    let line;
+    if (this.options.redact) line = JSON.stringify(this.redact(event));
+    else line = typeof event === 'string' ? event : JSON.stringify(event);
+    if (line.includes('\n')) throw new Error('Serialized trace events must not contain raw newlines');
+
+    this.batch.push({ line, timestamp, type });
+    this.batchBytes += line.length;
+    this.pendingEvents++;
+    this.stats.eventsAppended++;
+
+    if (this.batch.length >= this.options.maxBatchEvents || this.batchBytes >= this.options.maxBatchBytes) {
+      this.flush().catch(() => {});
+    }
+
+    if (this.pendingEvents >= this.options.highWaterMark) {
+      this.needsDrain = true;
+      return false;
+    }
+    return true;
+  }
+
+  redact(event) {
+    const value = typeof event === 'string' ? JSON.parse(event) : event;
+    if (typeof this.options.redact === 'function') return this.options.redact(value);
+    const result = redactValue(value, this.options.redact);
+    for (const count of Object.values(result.counts)) this.stats.redactions += count;
+    return result.value;
+  }
+
+  /**
+   * Write the current batch as one frame
+   */
+  flush() {
+    if (this.batch.length === 0) return this.writeChain;
+
+    const batch = this.batch;
+    this.batch = [];
+    this.batchBytes = 0;
+
+    this.writeChain = this.writeChain.then(async () => {
+      try {
+        // No handle: a failed write couldn't be rolled back, so that file was abandoned
+        if (!this.handle || this.shouldRotate()) await this.rotate();
+        const frame = encodeFrame(batch, {
+          codec: this.options.codec,
+          level: this.options.level,
+          key: this.encryptionKey,
+        });
+        await this.handle.write(frame);
+        if (this.options.fsync === 'always') await this.handle.sync();
+        else this.dirty = true;
+
+        this.fileBytes += frame.length;
+        this.stats.framesWritten++;
+        this.stats.eventsWritten += batch.length;
+        this.stats.bytesWritten += frame.length;
+        this.emit('flush', { events: batch.length, bytes: frame.length, file: this.currentFile });
+      } catch (error) {
+        // Keep the events: put them back at the front of the next batch
+        this.batch = batch.concat(this.batch);
+        this.batchBytes = this.batch.reduce((sum, item) => sum + item.line.length, 0);
+        this.stats.writeErrors++;
+        this.lastWriteError = error;
+        await this.rollBack();
+        if (this.listenerCount('error') > 0) this.emit('error', error);
+        else console.warn(`[TRACE] Trace write failed, will retry: ${error.message}`);
+        return;
+      }
+
+      this.pendingEvents -= batch.length;
+      if (this.needsDrain && this.pendingEvents < this.options.highWaterMark / 2) {
+        this.needsDrain = false;
+        this.emit('drain');
+      }
+    });
+
+    return this.writeChain;
+  }
+
+  /**
+   * Cut a torn frame off the end of the active file, so frames written after it stay readable
+   * (recovery drops everything after the first bad frame). When that fails too, the file is
+   * closed and the next flush starts a new one
+   */
+  async rollBack() {
+    if (!this.handle) return;
+    try {
+      await this.handle.truncate(this.fileBytes);
+    } catch {
+      await this.handle.close().catch(() => {});
+      this.handle = null;
+    }
+  }
+
+  /**
+   * fsync the active file if anything was written since the last sync
+   */
+  async sync() {
+    await this.writeChain;
+    if (this.handle && this.dirty) {
+      this.dirty = false;
+      await this.handle.sync();
+    }
+  }
+
+  /**
+   * Flush, fsync and close the active file
+   * Rejects when the final batch can't be written; the error's `events` holds the serialized
+   * events that never reached disk, so the caller can keep them somewhere else
+   */
+  async close() {
+    if (this.closed) return;
+    this.closed = true;
+    clearInterval(this.flushTimer);
+    clearInterval(this.fsyncTimer);
+    await this.flush();
+    if (this.handle) {
+      await this.handle.sync();
+      await this.handle.close();
+      this.handle = null;
+    }
+    if (this.batch.length > 0) {
+      const unwritten = this.batch;
+      this.batch = [];
+      this.batchBytes = 0;
+      const error = new Error(`TraceWriter closed with ${unwritten.length} events unwritten`, {
+        cause: this.lastWriteError,
+      });
+      error.events = unwritten.map((item) => item.line);
+      throw error;
+    }
+  }
+
+  getStats() {
+    return {
+      ...this.stats,
+      pendingEvents: this.pendingEvents,
+      currentFile: this.currentFile,
+      currentFileBytes: this.fileBytes,
+      codec: this.options.codec,
+      encrypted: Boolean(this.encryptionKey),
+    };
+  }
+}
+
+module.exports = {
+  TraceWriter,
+  listTraceFiles,
+  recoverTraceFile,
+  parseFileStamp,
+  FILE_EXTENSION,
+};
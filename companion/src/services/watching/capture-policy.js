@@ -0,0 +1,204 @@
+/**
+ * Capture Policy
+ * One place that decides, for every path (and its content), whether and how
+ * it may be captured. The policy is a JSON file the participant agrees to:
+ *
+ *   {
+ *     "consent": { "granted": true, "at": "2026-01-12T09:00:00Z", "expires": null },
+ *     "include": ["**"],
+ *     "exclude": [".env*", "secrets/**"],
+ *     "maxFileBytes": 1048576,
+ *     "redaction": "standard",
+ *     "languages": {
+ *       "python": { "capture": "full" },
+ *       "sql": { "capture": "metadata", "redaction": "strict" },
+ *       "unknown": { "capture": "none" }
+ *     }
+ *   }
+ *
+ * Nothing is captured without granted, unexpired consent. Otherwise a path
+ * must match an include glob and no exclude glob; its language rule (by
+ * extension, see code-lexer) then picks the capture mode:
+ *   full      content, diffs and snapshots
+ *   metadata  that the file changed (path, size, hash), never its content
+ *   none      nothing
+ * and the redaction level applied to captured content ('none', 'standard',
+ * 'strict', or a redaction policy object; see utils/redaction).
+ *
+ * Globs are compiled into GlobSets once and path decisions are cached, so
+ * evaluate() costs a map lookup for paths seen before. Capture code calls
+ * evaluate() at its entry point rather than re-checking rules itself, so a
+ * new capture path can't quietly skip them.
+ */
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { GlobSet } = require('../../utils/ignore-rules');
+const { getSyntax } = require('../../utils/code-lexer');
+const { redactText, DEFAULT_POLICY: STANDARD_REDACTION } = require('../../utils/redaction');
+
+const DEFAULT_POLICY_PATH = path.join(os.homedir(), '.cursor-telemetry', 'capture-policy.json');
+
+const DEFAULT_POLICY = {
+  consent: null, // { granted, at, expires }; capture is denied without it
+  include: ['**'],
+  exclude: [],
+  maxFileBytes: null, // Larger content is captured as metadata only
+  redaction: 'standard', // 'none' | 'standard' | 'strict' | redaction policy object
+  languages: {}, // language -> { capture, redaction, maxFileBytes }
+  cacheSize: 10000, // Path decisions kept
+};
+
+const CAPTURE_MODES = ['full', 'metadata', 'none'];
+
+const REDACTION_LEVELS = {
+  none: null,
+  standard: STANDARD_REDACTION,
+  strict: { emails: 'remove', ipAddresses: 'remove', userPaths: 'remove', secrets: 'remove' },
+};
+
+function resolveRedaction(level) {
+  if (level && typeof level === 'object') return level;
+  if (!(level in REDACTION_LEVELS)) {
+    const expected = Object.keys(REDACTION_LEVELS).join(', ');
+    throw new Error(`Unknown redaction level: ${level} (expected ${expected}, or a redaction policy)`);
+  }
+  return REDACTION_LEVELS[level];
+}
+
+function checkMode(mode, where) {
+  if (!CAPTURE_MODES.includes(mode)) {
+    throw new Error(`Unknown capture mode for ${where}: ${mode} (expected ${CAPTURE_MODES.join(', ')})`);
+  }
+  return mode;
+}
+
+class CapturePolicy {
+  /**
+   * @param {object} policy - See DEFAULT_POLICY
+   * @param {object} options - { root } to evaluate absolute paths relative to
+   */
+  constructor(policy = {}, options = {}) {
+    this.policy = { ...DEFAULT_POLICY, ...policy };
+    this.root = options.root || null;
+    this.include = new GlobSet(this.policy.include, { caseInsensitive: process.platform === 'win32' });
+    this.exclude = new GlobSet(this.policy.exclude, { caseInsensitive: process.platform === 'win32' });
+    this.redaction = resolveRedaction(this.policy.redaction);
+    this.languages = new Map();
+    for (const [language, rule] of Object.entries(this.policy.languages || {})) {
+      const name = language === 'unknown' ? language : getSyntax(language).language;
+      this.languages.set(name, {
+        capture: checkMode(rule.capture || 'full', language),
+        redaction: rule.redaction !== undefined ? resolveRedaction(rule.redaction) : this.redaction,
+        maxFileBytes: rule.maxFileBytes ?? this.policy.maxFileBytes,
+      });
+    }
+    this.decisions = new Map();
+    this.stats = { evaluated: 0, cached: 0, denied: 0, redacted: 0 };
+  }
+
+  /**
+   * Load a policy file (JSON)
+   * @returns {CapturePolicy}
+   */
+  static load(filePath = DEFAULT_POLICY_PATH, options = {}) {
+    let policy;
+    try {
+      policy = JSON.parse(fs.readFileSync(filePath, 'utf8'));
+    } catch (error) {
+      throw new Error(`Cannot load capture policy ${filePath}: ${error.message}`);
+    }
+    return new CapturePolicy(policy, options);
+  }
+
+  hasConsent(now = Date.now()) {
+    const consent = this.policy.consent;
+    if (!consent || consent.granted !== true) return false;
+    return !consent.expires || Date.parse(consent.expires) > now;
+  }
+
+  /**
+   * Path-only part of a decision, cached per path
+   */
+  decidePath(filePath) {
+    const cached = this.decisions.get(filePath);
+    if (cached) {
+      this.stats.cached++;
+      return cached;
+    }
+
+    let relativePath = String(filePath);
+    if (this.root && path.isAbsolute(relativePath)) relativePath = path.relative(this.root, relativePath);
+    relativePath = relativePath.split(path.sep).join('/');
+    const extension = path.extname(relativePath).slice(1).toLowerCase();
+    const language = extension ? getSyntax(extension).language : 'unknown';
+    const rule = this.languages.get(language) || {
+      capture: 'full',
+      redaction: this.redaction,
+      maxFileBytes: this.policy.maxFileBytes,
+    };
+
+    let decision;
+    if (!this.include.isMatch(relativePath)) decision = { mode: 'none', reason: 'not-included' };
+    else if (this.exclude.isMatch(relativePath)) decision = { mode: 'none', reason: 'excluded' };
+    else if (rule.capture === 'none') decision = { mode: 'none', reason: 'language' };
+    else decision = { mode: rule.capture, reason: rule.capture === 'full' ? null : 'language' };
+    decision = { ...decision, language, redaction: rule.redaction, maxFileBytes: rule.maxFileBytes };
+
+    if (this.decisions.size >= this.policy.cacheSize) {
+      this.decisions.delete(this.decisions.keys().next().value);
+    }
+    this.decisions.set(filePath, decision);
+    return decision;
+  }
+
+  /**
+   * Decide whether and how a path may be captured
+   * @param {string} filePath - Path (relative to the policy root, or absolute with options.root)
+   * @param {string|Buffer} content - Content about to be captured (optional)
+   * @returns {object} { allowed, mode: 'full'|'metadata'|'none', reason, language, content }
+   *   reason says why the mode is below 'full' ('no-consent', 'not-included', 'excluded',
+   *   'language', 'too-large'); content is the redacted text when content was given and
+   *   the mode is 'full', else null
+   */
+  evaluate(filePath, content = null) {
+    this.stats.evaluated++;
+    if (!this.hasConsent()) {
+      this.stats.denied++;
+      return { allowed: false, mode: 'none', reason: 'no-consent', language: null, content: null };
+    }
+
+    const decision = this.decidePath(filePath);
+    let { mode, reason } = decision;
+    if (mode === 'full' && content !== null && decision.maxFileBytes) {
+      const bytes = Buffer.isBuffer(content) ? content.length : Buffer.byteLength(String(content));
+      if (bytes > decision.maxFileBytes) {
+        mode = 'metadata';
+        reason = 'too-large';
+      }
+    }
+    if (mode === 'none') this.stats.denied++;
+
+    let text = null;
+    if (mode === 'full' && content !== null) {
+      text = Buffer.isBuffer(content) ? content.toString('utf8') : String(content);
+      if (decision.redaction) {
+        const result = redactText(text, decision.redaction);
+        text = result.text;
+        for (const count of Object.values(result.counts)) this.stats.redacted += count;
+      }
+    }
+    return { allowed: mode !== 'none', mode, reason, language: decision.language, content: text };
+  }
+
+  getStats() {
+    return { ...this.stats, cachedPaths: this.decisions.size, consent: this.hasConsent() };
+  }
+}
+
+module.exports = {
+  CapturePolicy,
+  DEFAULT_POLICY,
+  DEFAULT_POLICY_PATH,
+};
@@ -0,0 +1,399 @@
+/**
+ * File Watcher
+ * Watches workspace roots and reports add / change / unlink / rename events,
+ * with debouncing, rename tracking and ignore patterns, as a replacement for
+ * chokidar, whose overhead and missed renames leave gaps in traces.
+ *
+ * Backends:
+ *   macOS, Windows  one recursive fs.watch per root (FSEvents / ReadDirectoryChangesW)
+ *   Linux, others   one inotify watch per non-ignored directory, so ignored
+ *                   trees (node_modules, build output) are never watched
+ *   usePolling      periodic rescans, for network and container filesystems
+ *
+ * Raw notifications only mark paths dirty; after debounceMs of quiet each
+ * dirty path is stat'ed and compared with the file index built at start.
+ * Renames are recognised by inode: a new path whose (dev, ino) belongs to a
+ * known path that no longer exists is reported as a rename, in whichever
+ * order the platform delivered the two halves. A renamed directory yields
+ * one rename per file under it.
+ */
+
+const EventEmitter = require('events');
+const fs = require('fs');
+const path = require('path');
+const { GlobSet, IgnoreMatcher } = require('../../utils/ignore-rules');
+
+const DEFAULT_OPTIONS = {
+  ignore: ['node_modules/**', 'dist/**', 'build/**', '.git/**', '*.log', '*.tmp', '.DS_Store'],
+  gitignore: true,
+  debounceMs: 100,
+  maxDelayMs: 1000, // Flush at least this often while changes keep arriving (builds, bulk writes)
+  renameWindowMs: 250, // How long an unlink waits for the matching add of a rename
+  usePolling: false,
+  pollIntervalMs: 2000,
+  followSymlinks: false,
+};
+
+const RECURSIVE_PLATFORMS = new Set(['darwin', 'win32']);
+
+function inodeKey(stat) {
+  return stat && stat.ino ? `${stat.dev}:${stat.ino}` : null;
+}
+
+class FileWatcher extends EventEmitter {
+  /**
+   * @param {string|Array<string>} roots - Directories to watch
+   * @param {object} options - See DEFAULT_OPTIONS
+   */
+  constructor(roots, options = {}) {
+    super();
+    this.roots = (Array.isArray(roots) ? roots : [roots]).map((root) => path.resolve(root));
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.globs = new GlobSet(this.options.ignore || []);
+    this.ignoreMatchers = new Map(this.roots.map((root) => [root, new IgnoreMatcher(root)]));
+
+    this.files = new Map(); // absolute path -> { size, mtimeMs, inode }
+    this.dirs = new Map(); // absolute path -> inode
+    this.byInode = new Map(); // inode -> absolute path (files and directories)
+    this.watchers = new Map(); // watched directory or root -> fs.FSWatcher
+    this.dirty = new Set();
+    this.pendingUnlinks = new Map(); // path -> { entry, timer }
+    this.debounceTimer = null;
+    this.dirtySince = null;
+    this.pollTimer = null;
+    this.running = false;
+    this.stats = { rawEvents: 0, emitted: 0, renames: 0, rescans: 0 };
+  }
+
+  rootOf(filePath) {
+    return this.roots.find((root) => filePath === root || filePath.startsWith(root + path.sep)) || null;
+  }
+
+  /**
+   * Whether a path is excluded by the ignore globs or the root's .gitignore files
+   */
+  isIgnored(filePath, isDir = false) {
+    const root = this.rootOf(filePath);
+    if (!root || filePath === root) return false;
+    const relativePath = path.relative(root, filePath).split(path.sep).join('/');
+    if (relativePath.split('/').includes('.git')) return true;
+    if (this.globs.isMatch(relativePath) || (isDir && this.globs.isMatch(`${relativePath}/`))) return true;
+    return this.options.gitignore ? this.ignoreMatchers.get(root).isIgnored(relativePath, isDir) : false;
+  }
+
+  async start() {
+    if (this.running) return this;
+    this.running = true;
+    for (const root of this.roots) await this.scan(root, false);
+
+    if (this.options.usePolling) {
+      this.pollTimer = setInterval(() => this.rescan(), this.options.pollIntervalMs);
+      this.pollTimer.unref?.();
+    } else {
+      for (const root of this.roots) this.watchRoot(root);
+    }
+    this.emit('ready', { files: this.files.size, directories: this.dirs.size });
+    return this;
+  }
+
+  async stop() {
+    this.running = false;
+    for (const watcher of this.watchers.values()) watcher.close();
+    this.watchers.clear();
+    clearTimeout(this.debounceTimer);
+    clearInterval(this.pollTimer);
+    for (const { timer } of this.pendingUnlinks.values()) clearTimeout(timer);
+    this.pendingUnlinks.clear();
+    this.dirty.clear();
+  }
+
+  // ---------------------------------------------------------------------------
+  // Index
+  // ---------------------------------------------------------------------------
+
+  async statPath(filePath) {
+    try {
+      if (this.options.followSymlinks) return await fs.promises.stat(filePath);
+      return await fs.promises.lstat(filePath);
+    } catch {
+      return null;
+    }
+  }
+
+  /**
+   * Walk a directory into the index; with emit = true new files are reported as adds
+   */
+  async scan(dir, emit) {
+    const stack = [dir];
+    while (stack.length) {
+      const current = stack.pop();
+      const dirStat = await this.statPath(current);
+      if (!dirStat || !dirStat.isDirectory()) continue;
+      this.trackDir(current, dirStat);
+      if (this.backend() === 'per-directory') this.watchDir(current);
+
+      let entries;
+      try {
+        entries = await fs.promises.readdir(current, { withFileTypes: true });
+      } catch {
+        continue;
+      }
+      for (const entry of entries) {
+        const fullPath = path.join(current, entry.name);
+        if (entry.isDirectory()) {
+          if (!this.isIgnored(fullPath, true)) stack.push(fullPath);
+        } else if (entry.isFile() || (entry.isSymbolicLink() && this.options.followSymlinks)) {
+          if (this.isIgnored(fullPath) || this.files.has(fullPath)) continue;
+          const stat = await this.statPath(fullPath);
+          if (!stat || !stat.isFile()) continue;
+          if (emit) await this.addFile(fullPath, stat);
+          else this.trackFile(fullPath, stat);
+        }
+      }
+    }
+  }
+
+  trackFile(filePath, stat) {
+    const inode = inodeKey(stat);
+    this.files.set(filePath, { size: stat.size, mtimeMs: stat.mtimeMs, inode });
+    if (inode) this.byInode.set(inode, filePath);
+  }
+
+  trackDir(dirPath, stat) {
+    const inode = inodeKey(stat);
+    this.dirs.set(dirPath, inode);
+    if (inode) this.byInode.set(inode, dirPath);
+  }
+
+  untrack(filePath) {
+    const entry = this.files.get(filePath);
+    if (entry) {
+      this.files.delete(filePath);
+      if (entry.inode && this.byInode.get(entry.inode) === filePath) this.byInode.delete(entry.inode);
+      return entry;
+    }
+    return null;
+  }
+
+  // ---------------------------------------------------------------------------
+  // Backends
+  // ---------------------------------------------------------------------------
+
+  watchRoot(root) {
+    // Per-directory watchers are opened as directories are scanned
+    if (this.backend() === 'recursive') this.openWatcher(root, { recursive: true });
+  }
+
+  watchDir(dir) {
+    if (this.watchers.has(dir) || !this.running) return;
+    this.openWatcher(dir, {});
+  }
+
+  openWatcher(dir, watchOptions) {
+    let watcher;
+    try {
+      watcher = fs.watch(dir, { persistent: true, ...watchOptions }, (eventType, filename) => {
+        this.stats.rawEvents++;
+        if (!filename) {
+          // Some platforms drop the name on overflow; rescan the directory
+          this.markDirty(dir);
+          return;
+        }
+        this.markDirty(path.join(dir, filename.toString()));
+      });
+    } catch (error) {
+      this.fail(error);
+      return;
+    }
+    watcher.on('error', (error) => {
+      watcher.close();
+      this.watchers.delete(dir);
+      // A watched directory that disappeared is an ordinary unlink, not an error
+      if (error.code !== 'EPERM' && error.code !== 'ENOENT') this.fail(error);
+      this.markDirty(dir);
+    });
+    this.watchers.set(dir, watcher);
+  }
+
+  fail(error) {
+    if (this.listenerCount('error') > 0) this.emit('error', error);
+    else console.warn('[WATCHER] Watch error:', error.message);
+  }
+
+  markDirty(filePath) {
+    if (!this.running) return;
+    this.dirty.add(filePath);
+    clearTimeout(this.debounceTimer);
+    const now = Date.now();
+    if (this.dirtySince === null) this.dirtySince = now;
+    const remaining = Math.max(0, this.dirtySince + this.options.maxDelayMs - now);
+    const delay = Math.min(this.options.debounceMs, remaining);
+    this.debounceTimer = setTimeout(() => this.flush(), delay);
+  }
+
+  /**
+   * Compare the whole index against disk (polling backend, and recovery after overflow)
+   */
+  async rescan() {
+    this.stats.rescans++;
+    for (const filePath of this.files.keys()) this.dirty.add(filePath);
+    for (const dirPath of this.dirs.keys()) this.dirty.add(dirPath);
+    await this.flush();
+  }
+
+  // ---------------------------------------------------------------------------
+  // Event resolution
+  // ---------------------------------------------------------------------------
+
+  async flush() {
+    const paths = Array.from(this.dirty).sort();
+    this.dirty.clear();
+    this.dirtySince = null;
+    for (const filePath of paths) {
+      if (!this.running) return;
+      await this.resolve(filePath);
+    }
+  }
+
+  async resolve(filePath) {
+    const stat = await this.statPath(filePath);
+    const known = this.files.get(filePath);
+
+    if (!stat) {
+      if (known) this.holdUnlink(filePath, this.untrack(filePath));
+      else if (this.dirs.has(filePath)) this.removeDir(filePath);
+      return;
+    }
+
+    if (stat.isDirectory()) {
+      if (this.isIgnored(filePath, true)) return;
+      if (!this.dirs.has(filePath)) {
+        const from = this.byInode.get(inodeKey(stat));
+        if (from && from !== filePath && this.dirs.has(from) && !(await this.statPath(from))) {
+          this.renameDir(from, filePath);
+        }
+      }
+      // New or changed directory: pick up entries whose own events were coalesced away
+      await this.scan(filePath, true);
+      return;
+    }
+
+    if (!stat.isFile() || this.isIgnored(filePath)) return;
+    if (known) {
+      if (known.size !== stat.size || known.mtimeMs !== stat.mtimeMs) {
+        this.trackFile(filePath, stat);
+        this.report({ type: 'change', path: filePath, size: stat.size, mtimeMs: stat.mtimeMs });
+      }
+      return;
+    }
+
+    await this.addFile(filePath, stat);
+  }
+
+  /**
+   * Report a path new to the index: a rename if its inode belongs to a known path that's gone
+   */
+  async addFile(filePath, stat) {
+    const inode = inodeKey(stat);
+    const pending = inode && [...this.pendingUnlinks].find(([, held]) => held.entry.inode === inode);
+    let from = pending ? pending[0] : null;
+    if (!from && inode) {
+      const previous = this.byInode.get(inode);
+      if (previous && previous !== filePath && this.files.has(previous) && !(await this.statPath(previous))) {
+        from = previous;
+        this.untrack(previous);
+      }
+    }
+    if (pending) {
+      clearTimeout(pending[1].timer);
+      this.pendingUnlinks.delete(from);
+    }
+    this.trackFile(filePath, stat);
+    if (from) {
+      this.stats.renames++;
+      this.report({ type: 'rename', path: filePath, oldPath: from, size: stat.size, mtimeMs: stat.mtimeMs });
+    } else {
+      this.report({ type: 'add', path: filePath, size: stat.size, mtimeMs: stat.mtimeMs });
+    }
+  }
+
+  holdUnlink(filePath, entry) {
+    const timer = setTimeout(() => {
+      this.pendingUnlinks.delete(filePath);
+      this.report({ type: 'unlink', path: filePath });
+    }, this.options.renameWindowMs);
+    timer.unref?.();
+    this.pendingUnlinks.set(filePath, { entry, timer });
+  }
+
+  renameDir(from, to) {
+    const prefix = from + path.sep;
+    for (const [filePath, entry] of Array.from(this.files)) {
+      if (!filePath.startsWith(prefix)) continue;
+      const target = to + filePath.slice(from.length);
+      this.untrack(filePath);
+      this.files.set(target, entry);
+      if (entry.inode) this.byInode.set(entry.inode, target);
+      this.stats.renames++;
+      const { size, mtimeMs } = entry;
+      this.report({ type: 'rename', path: target, oldPath: filePath, size, mtimeMs });
+    }
+    for (const dirPath of Array.from(this.dirs.keys())) {
+      if (dirPath === from || dirPath.startsWith(prefix)) {
+        this.dirs.delete(dirPath);
+        this.watchers.get(dirPath)?.close();
+        this.watchers.delete(dirPath);
+      }
+    }
+  }
+
+  removeDir(dirPath) {
+    const prefix = dirPath + path.sep;
+    for (const filePath of Array.from(this.files.keys())) {
+      if (filePath.startsWith(prefix)) this.holdUnlink(filePath, this.untrack(filePath));
+    }
+    for (const known of Array.from(this.dirs.keys())) {
+      if (known !== dirPath && !known.startsWith(prefix)) continue;
+      this.dirs.delete(known);
+      this.watchers.get(known)?.close();
+      this.watchers.delete(known);
+    }
+  }
+
+  report(event) {
+    const root = this.rootOf(event.path);
+    const full = {
+      ...event,
+      root,
+      relativePath: root ? path.relative(root, event.path).split(path.sep).join('/') : event.path,
+      timestamp: Date.now(),
+    };
+    this.stats.emitted++;
+    this.emit(event.type, full);
+    this.emit('all', full);
+  }
+
+  getWatched() {
+    return Array.from(this.files.keys()).sort();
+  }
+
+  getStats() {
+    return {
+      ...this.stats,
+      files: this.files.size,
+      directories: this.dirs.size,
+      watchers: this.watchers.size,
+      backend: this.backend(),
+    };
+  }
+
+  backend() {
+    if (this.options.usePolling) return 'polling';
+    return RECURSIVE_PLATFORMS.has(process.platform) ? 'recursive' : 'per-directory';
+  }
+}
+
+module.exports = {
+  FileWatcher,
+  DEFAULT_OPTIONS,
+};
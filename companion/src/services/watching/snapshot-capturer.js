@@ -0,0 +1,384 @@
+/**
+ * Snapshot Capturer
+ * Turns FileWatcher events into one consolidated snapshot event per settled
+ * file change: the file is read as a Buffer, hashed, skipped when the content
+ * is unchanged, and diffed against the previous snapshot.
+ *
+ * Changes to a file are debounced (debounceMs of quiet, but never longer than
+ * maxWaitMs during continuous writes) and queued per path, so a rebuild that
+ * touches thousands of files queues each file once instead of dropping
+ * events; a file changed again while it's being captured is simply captured
+ * again afterwards. Previous contents are cached up to maxCachedBytes; when a
+ * file's content has been evicted, its next snapshot carries no diff.
+ * diff.formatOnly marks changes that only reformatted the file (see code-format).
+ *
+ * Generated artifacts (lockfiles, minified bundles, codegen output; see
+ * code-generated) carry a `generated` kind on their snapshots. With
+ * generatedFiles 'skip' they're reported as skipped instead, and with
+ * 'sample' a generated file is captured at most once per generatedSampleMs;
+ * the next capture diffs against the last one emitted.
+ *
+ * With a CapturePolicy (see capture-policy), every path is checked before it
+ * is read and its content before it is diffed: denied paths are reported as
+ * skipped, 'metadata' paths get snapshots without diff or content, and
+ * content is redacted per the policy before anything else sees it.
+ *
+ * With gitInfo, snapshots of files inside a git repository carry the branch,
+ * HEAD commit and any operation in progress (see git-repository).
+ *
+ * Events:
+ *   snapshot  { type: 'add'|'change', path, relativePath, hash, previousHash, size, diff, generated,
+ *               coalesced, git? }
+ *   rename    { path, oldPath, relativePath }
+ *   delete    { path, relativePath, previousHash }
+ *   skipped   { path, reason: 'too-large'|'unreadable'|'generated'|'policy', kind?, policyReason? }
+ *   idle      the capture queue has drained
+ */
+
+const EventEmitter = require('events');
+const fs = require('fs');
+const path = require('path');
+const diff = require('diff');
+const { FileWatcher } = require('./file-watcher');
+const { CapturePolicy } = require('./capture-policy');
+const { getHeadInfo } = require('../git/git-repository');
+const { hashContent, DEFAULT_ALGORITHM } = require('../../utils/content-hash');
+const { calculateDiff } = require('../../utils/diff-engine');
+const { isFormatOnlyChange } = require('../../utils/code-format');
+const { detectGenerated } = require('../../utils/code-generated');
+const { decodeFileBuffer, normalizeLineEndings } = require('../../utils/file-reader');
+
+const DEFAULT_OPTIONS = {
+  debounceMs: 300,
+  maxWaitMs: 5000,
+  concurrency: 4,
+  maxFileBytes: 2 * 1024 * 1024,
+  maxCachedBytes: 64 * 1024 * 1024,
+  binaryProbeBytes: 8192,
+  diffThreshold: 10,
+  includeContent: false, // Attach beforeContent/afterContent to snapshot events
+  seed: false, // Hash and cache every watched file at start, so first changes get diffs
+  generatedFiles: 'capture', // 'capture' | 'skip' | 'sample' for lockfiles, bundles and codegen output
+  generatedSampleMs: 60000, // With 'sample', the shortest interval between a generated file's snapshots
+  hashAlgorithm: DEFAULT_ALGORITHM,
+  policy: null, // CapturePolicy (or a policy object) checked before every capture
+  gitInfo: false, // Attach { branch, commit, state } from the file's git repository
+  watcher: {}, // FileWatcher options when the capturer creates its own watcher
+};
+
+class SnapshotCapturer extends EventEmitter {
+  /**
+   * @param {FileWatcher|string|Array<string>} source - A watcher to consume, or roots to watch
+   * @param {object} options - See DEFAULT_OPTIONS
+   */
+  constructor(source, options = {}) {
+    super();
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.ownsWatcher = !(source instanceof FileWatcher);
+    this.watcher = this.ownsWatcher ? new FileWatcher(source, this.options.watcher) : source;
+    const { policy } = this.options;
+    this.policy = policy && !(policy instanceof CapturePolicy) ? new CapturePolicy(policy) : policy;
+
+    this.snapshots = new Map(); // path -> { hash, content, bytes } (Map order = LRU order)
+    this.cachedBytes = 0;
+    this.debouncing = new Map(); // path -> { timer, firstAt, events }
+    this.queue = new Map(); // path -> coalesced event count, waiting for a capture slot
+    this.inFlight = new Set();
+    this.rerun = new Map(); // path -> events that arrived during its capture
+    this.active = 0;
+    this.running = false;
+    this.sampledAt = new Map(); // generated path -> last capture time, for generatedFiles: 'sample'
+    this.stats = { events: 0, captured: 0, unchanged: 0, skipped: 0, sampledOut: 0, evicted: 0, maxQueue: 0 };
+
+    this.listeners = {
+      add: (event) => this.onChange(event),
+      change: (event) => this.onChange(event),
+      unlink: (event) => this.onUnlink(event),
+      rename: (event) => this.onRename(event),
+    };
+  }
+
+  async start() {
+    if (this.running) return this;
+    this.running = true;
+    for (const [type, listener] of Object.entries(this.listeners)) this.watcher.on(type, listener);
+    if (this.ownsWatcher) await this.watcher.start();
+    if (this.options.seed) await this.seed();
+    return this;
+  }
+
+  async stop() {
+    this.running = false;
+    for (const [type, listener] of Object.entries(this.listeners)) this.watcher.off(type, listener);
+    for (const { timer } of this.debouncing.values()) clearTimeout(timer);
+    this.debouncing.clear();
+    this.queue.clear();
+    this.rerun.clear();
+    if (this.ownsWatcher) await this.watcher.stop();
+  }
+
+  /**
+   * Record hashes and contents of all currently watched files without emitting
+   */
+  async seed() {
+    const files = this.watcher.getWatched();
+    let next = 0;
+    const workers = Array.from({ length: Math.min(this.options.concurrency, files.length) }, async () => {
+      while (next < files.length && this.running) {
+        const filePath = files[next++];
+        const read = await this.read(filePath);
+        if (read && !read.skipped) this.remember(filePath, read.hash, read.text);
+      }
+    });
+    await Promise.all(workers);
+  }
+
+  // ---------------------------------------------------------------------------
+  // Debounce and queue
+  // ---------------------------------------------------------------------------
+
+  onChange(event) {
+    if (!this.running) return;
+    this.stats.events++;
+    const now = Date.now();
+    const pending = this.debouncing.get(event.path) || { timer: null, firstAt: now, events: 0 };
+    pending.events++;
+    clearTimeout(pending.timer);
+
+    const waited = now - pending.firstAt;
+    if (waited >= this.options.maxWaitMs) {
+      this.debouncing.delete(event.path);
+      this.enqueue(event.path, pending.events);
+      return;
+    }
+    const delay = Math.min(this.options.debounceMs, this.options.maxWaitMs - waited);
+    pending.timer = setTimeout(() => {
+      this.debouncing.delete(event.path);
+      this.enqueue(event.path, pending.events);
+    }, delay);
+    this.debouncing.set(event.path, pending);
+  }
+
+  enqueue(filePath, events) {
+    if (this.inFlight.has(filePath)) {
+      this.rerun.set(filePath, (this.rerun.get(filePath) || 0) + events);
+      return;
+    }
+    this.queue.set(filePath, (this.queue.get(filePath) || 0) + events);
+    this.stats.maxQueue = Math.max(this.stats.maxQueue, this.queue.size);
+    this.drain();
+  }
+
+  drain() {
+    while (this.running && this.active < this.options.concurrency && this.queue.size > 0) {
+      const [filePath, events] = this.queue.entries().next().value;
+      this.queue.delete(filePath);
+      this.active++;
+      this.inFlight.add(filePath);
+      this.capture(filePath, events)
+        .catch((error) => this.fail(error))
+        .finally(() => {
+          this.active--;
+          this.inFlight.delete(filePath);
+          const again = this.rerun.get(filePath);
+          if (again) {
+            this.rerun.delete(filePath);
+            this.enqueue(filePath, again);
+          }
+          this.drain();
+          if (this.active === 0 && this.queue.size === 0) this.emit('idle');
+        });
+    }
+  }
+
+  // ---------------------------------------------------------------------------
+  // Capture
+  // ---------------------------------------------------------------------------
+
+  async read(filePath) {
+    let buffer;
+    try {
+      const stat = await fs.promises.stat(filePath);
+      if (stat.size > this.options.maxFileBytes) return { skipped: 'too-large', size: stat.size };
+      buffer = await fs.promises.readFile(filePath);
+    } catch {
+      return { skipped: 'unreadable' };
+    }
+    // Decode per the file's own encoding; line endings are kept so the diff can report CRLF flips
+    const decoded = decodeFileBuffer(buffer, {
+      binaryProbeBytes: this.options.binaryProbeBytes,
+      normalizeLineEndings: false,
+    });
+    return {
+      hash: hashContent(buffer, this.options.hashAlgorithm),
+      size: buffer.length,
+      binary: decoded.binary,
+      encoding: decoded.encoding,
+      text: decoded.content,
+    };
+  }
+
+  async capture(filePath, coalesced) {
+    const root = this.watcher.rootOf(filePath);
+    const relativePath = root ? path.relative(root, filePath).split(path.sep).join('/') : filePath;
+    if (this.policy) {
+      const decision = this.policy.evaluate(relativePath);
+      if (!decision.allowed) {
+        this.stats.skipped++;
+        this.emit('skipped', { path: filePath, reason: 'policy', policyReason: decision.reason, size: null });
+        return;
+      }
+    }
+
+    const read = await this.read(filePath);
+    if (!this.running) return;
+    if (read.skipped) {
+      this.stats.skipped++;
+      this.emit('skipped', { path: filePath, reason: read.skipped, size: read.size ?? null });
+      return;
+    }
+
+    const previous = this.snapshots.get(filePath);
+    if (previous && previous.hash === read.hash) {
+      this.stats.unchanged++;
+      return;
+    }
+
+    // From here on only the policy's view of the content is used: redacted, or none at all
+    let text = read.text;
+    if (this.policy) {
+      const decision = this.policy.evaluate(relativePath, read.text);
+      if (!decision.allowed) {
+        this.stats.skipped++;
+        const { reason } = decision;
+        this.emit('skipped', { path: filePath, reason: 'policy', policyReason: reason, size: read.size });
+        return;
+      }
+      text = decision.mode === 'full' ? decision.content : null;
+    }
+
+    const generated = detectGenerated(text, filePath);
+    if (generated.generated && this.options.generatedFiles === 'skip') {
+      this.stats.skipped++;
+      this.emit('skipped', { path: filePath, reason: 'generated', kind: generated.kind, size: read.size });
+      return;
+    }
+    if (generated.generated && this.options.generatedFiles === 'sample') {
+      const last = this.sampledAt.get(filePath);
+      if (previous && last !== undefined && Date.now() - last < this.options.generatedSampleMs) {
+        this.stats.sampledOut++;
+        return;
+      }
+      this.sampledAt.set(filePath, Date.now());
+    }
+
+    const before = previous?.content ?? null;
+    let diffResult = null;
+    if (before !== null && text !== null) {
+      const summary = calculateDiff(before, text, {
+        threshold: this.options.diffThreshold,
+        lineEndings: 'report',
+        includeAfterContent: false,
+      });
+      let linesAdded = 0;
+      let linesRemoved = 0;
+      for (const part of diff.diffLines(normalizeLineEndings(before), normalizeLineEndings(text))) {
+        if (part.added) linesAdded += part.count;
+        else if (part.removed) linesRemoved += part.count;
+      }
+      const formatOnly = isFormatOnlyChange(before, text, path.extname(filePath).slice(1).toLowerCase());
+      diffResult = { ...summary, linesAdded, linesRemoved, formatOnly };
+    }
+
+    this.remember(filePath, read.hash, text);
+    this.stats.captured++;
+    const head = this.options.gitInfo ? getHeadInfo(filePath) : null;
+    this.emit('snapshot', {
+      type: previous ? 'change' : 'add',
+      path: filePath,
+      relativePath,
+      hash: read.hash,
+      previousHash: previous?.hash ?? null,
+      size: read.size,
+      binary: read.binary,
+      encoding: read.encoding,
+      diff: diffResult,
+      generated: generated.generated ? generated.kind : null,
+      coalesced,
+      ...(head ? { git: { branch: head.branch, commit: head.commit, state: head.state } } : {}),
+      timestamp: Date.now(),
+      ...(this.options.includeContent ? { beforeContent: before, afterContent: text } : {}),
+    });
+  }
+
+  remember(filePath, hash, content) {
+    this.forget(filePath);
+    const bytes = content ? content.length * 2 : 0;
+    this.snapshots.set(filePath, { hash, content, bytes });
+    this.cachedBytes += bytes;
+
+    // Evict oldest contents (hashes stay, so unchanged detection keeps working)
+    for (const [key, entry] of this.snapshots) {
+      if (this.cachedBytes <= this.options.maxCachedBytes) break;
+      if (key === filePath || entry.content === null) continue;
+      this.cachedBytes -= entry.bytes;
+      entry.content = null;
+      entry.bytes = 0;
+      this.stats.evicted++;
+    }
+  }
+
+  forget(filePath) {
+    const entry = this.snapshots.get(filePath);
+    if (!entry) return null;
+    this.cachedBytes -= entry.bytes;
+    this.snapshots.delete(filePath);
+    return entry;
+  }
+
+  onUnlink(event) {
+    if (!this.running) return;
+    const pending = this.debouncing.get(event.path);
+    if (pending) clearTimeout(pending.timer);
+    this.debouncing.delete(event.path);
+    this.queue.delete(event.path);
+    const entry = this.forget(event.path);
+    this.sampledAt.delete(event.path);
+    const previousHash = entry?.hash ?? null;
+    this.emit('delete', { path: event.path, relativePath: event.relativePath, previousHash });
+  }
+
+  onRename(event) {
+    if (!this.running) return;
+    const entry = this.forget(event.oldPath);
+    if (entry) {
+      this.snapshots.set(event.path, entry);
+      this.cachedBytes += entry.bytes;
+    }
+    this.emit('rename', { path: event.path, oldPath: event.oldPath, relativePath: event.relativePath });
+    // A rename can come with new content (editors that save via temp file + rename)
+    this.onChange(event);
+  }
+
+  fail(error) {
+    if (this.listenerCount('error') > 0) this.emit('error', error);
+    else console.warn('[SNAPSHOT] Capture failed:', error.message);
+  }
+
+  getStats() {
+    return {
+      ...this.stats,
+      ...(this.policy ? { policy: this.policy.getStats() } : {}),
+      queued: this.queue.size,
+      debouncing: this.debouncing.size,
+      active: this.active,
+      tracked: this.snapshots.size,
+      cachedBytes: this.cachedBytes,
+    };
+  }
+}
+
+module.exports = {
+  SnapshotCapturer,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,269 @@
+/**
+ * BM25 Index
+ * Lexical retrieval over code snippets: an inverted index scored with
+ * Okapi BM25 (or plain TF-IDF), as a non-ML alternative to the vector index
+ * for matching prompt keywords to file contents.
+ *
+ * Identifiers are indexed whole and split into their camelCase/snake_case
+ * parts, so a prompt mentioning "user session" finds `getUserSession` and
+ * `user_session_id`. Postings are Maps keyed by document number; removal
+ * drops a document's postings so collection statistics stay exact.
+ *
+ * The index persists as a JSON file written atomically (tmp + rename).
+ */
+
+const fs = require('fs');
+
+const DEFAULT_OPTIONS = {
+  scoring: 'bm25', // 'bm25' | 'tfidf'
+  k1: 1.2, // Term frequency saturation
+  b: 0.75, // Document length normalization
+  splitIdentifiers: true, // Also index camelCase / snake_case parts
+  minTokenLength: 2,
+  stopWords: null, // Set/array overriding STOP_WORDS
+};
+
+const FORMAT = 'bm25-index/1';
+
+// Prompt filler and the most common keywords, which carry no signal in either prompts or code
+const STOP_WORDS = new Set(
+  `a an and are as at be but by can do does for from how i if in is it its me my of on or please should
+  so that the then this to use using want we what when where which why will with you your let var const
+  return function def new`
+    .split(/\s+/)
+    .filter(Boolean)
+);
+
+const WORD = /[A-Za-z_$][\w$]*|\d+(?:\.\d+)?/g;
+const IDENTIFIER_PART = /[A-Z]+(?![a-z])|[A-Z]?[a-z]+|\d+/g;
+
+/**
+ * Split text into index terms (lowercased)
+ * @param {string} text - Prompt or code
+ * @param {object} options - { splitIdentifiers, minTokenLength, stopWords }
+ * @returns {Array<string>} Terms in order, with repeats
+ */
+function tokenize(text, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const stopWords = opts.stopWords ? new Set(opts.stopWords) : STOP_WORDS;
+  const terms = [];
+  const keep = (term) => {
+    if (term.length >= opts.minTokenLength && !stopWords.has(term)) terms.push(term);
+  };
+
+  for (const [word] of String(text ?? '').matchAll(WORD)) {
+    const lower = word.toLowerCase();
+    keep(lower);
+    if (!opts.splitIdentifiers) continue;
+    const parts = word.match(IDENTIFIER_PART) || [];
+    if (parts.length > 1) parts.forEach((part) => keep(part.toLowerCase()));
+  }
+  return terms;
+}
+
+class BM25Index {
+  constructor(options = {}) {
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    if (this.options.stopWords instanceof Set) this.options.stopWords = Array.from(this.options.stopWords);
+    this.postings = new Map(); // term -> Map(doc -> term frequency)
+    this.docs = []; // doc -> { id, length, terms, metadata } | null once removed
+    this.idToDoc = new Map();
+    this.totalLength = 0;
+  }
+
+  get size() {
+    return this.idToDoc.size;
+  }
+
+  get averageLength() {
+    return this.size ? this.totalLength / this.size : 0;
+  }
+
+  /**
+   * Index a snippet; an existing id is replaced
+   * @param {string} id - Snippet id
+   * @param {string} text - Snippet text
+   * @param {object} metadata - Returned with search hits (filePath, startLine, ...)
+   */
+  add(id, text, metadata = null) {
+    if (this.idToDoc.has(id)) this.remove(id);
+    const terms = tokenize(text, this.options);
+    const frequencies = new Map();
+    for (const term of terms) frequencies.set(term, (frequencies.get(term) || 0) + 1);
+
+    const doc = this.docs.length;
+    this.docs.push({ id, length: terms.length, terms: Array.from(frequencies.keys()), metadata });
+    this.idToDoc.set(id, doc);
+    this.totalLength += terms.length;
+    for (const [term, tf] of frequencies) {
+      if (!this.postings.has(term)) this.postings.set(term, new Map());
+      this.postings.get(term).set(doc, tf);
+    }
+    return this;
+  }
+
+  /**
+   * Index many snippets
+   * @param {Array<object>} snippets - [{ id, text|content, ...metadata }]
+   */
+  addAll(snippets) {
+    for (const snippet of snippets || []) {
+      const { id, text, content, ...metadata } = snippet;
+      this.add(id, text ?? content ?? '', Object.keys(metadata).length ? metadata : null);
+    }
+    return this;
+  }
+
+  remove(id) {
+    const doc = this.idToDoc.get(id);
+    if (doc === undefined) return false;
+    const entry = this.docs[doc];
+    for (const term of entry.terms) {
+      const posting = this.postings.get(term);
+      posting.delete(doc);
+      if (posting.size === 0) this.postings.delete(term);
+    }
+    this.totalLength -= entry.length;
+    this.docs[doc] = null;
+    this.idToDoc.delete(id);
+    return true;
+  }
+
+  has(id) {
+    return this.idToDoc.has(id);
+  }
+
+  /**
+   * Inverse document frequency (BM25's non-negative variant)
+   */
+  idf(term) {
+    const df = this.postings.get(term)?.size || 0;
+    if (this.options.scoring === 'tfidf') return df ? Math.log(this.size / df) + 1 : 0;
+    return Math.log(1 + (this.size - df + 0.5) / (df + 0.5));
+  }
+
+  /**
+   * Rank snippets against a query
+   * @param {string} query - Prompt text or keywords
+   * @param {number} k - Number of results
+   * @param {object} options - { filter(id, metadata) => boolean, minScore }
+   * @returns {Array<object>} [{ id, score, matchedTerms, metadata }] best first
+   */
+  search(query, k = 10, options = {}) {
+    const { filter = null, minScore = 0 } = options;
+    const { scoring, k1, b } = this.options;
+    const averageLength = this.averageLength || 1;
+
+    // Repeated query terms weigh more, as in the original BM25 query-term frequency
+    const queryTerms = new Map();
+    for (const term of tokenize(query, this.options)) {
+      if (this.postings.has(term)) queryTerms.set(term, (queryTerms.get(term) || 0) + 1);
+    }
+
+    const scores = new Map();
+    const matched = new Map();
+    for (const [term, qtf] of queryTerms) {
+      const idf = this.idf(term);
+      for (const [doc, tf] of this.postings.get(term)) {
+        let weight;
+        if (scoring === 'tfidf') {
+          weight = (1 + Math.log(tf)) * idf;
+        } else {
+          const norm = k1 * (1 - b + (b * this.docs[doc].length) / averageLength);
+          weight = (idf * tf * (k1 + 1)) / (tf + norm);
+        }
+        scores.set(doc, (scores.get(doc) || 0) + weight * qtf);
+        if (!matched.has(doc)) matched.set(doc, []);
+        matched.get(doc).push(term);
+      }
+    }
+
+    const results = [];
+    for (const [doc, score] of scores) {
+      const entry = this.docs[doc];
+      if (score <= minScore) continue;
+      if (filter && !filter(entry.id, entry.metadata)) continue;
+      results.push({ id: entry.id, score, matchedTerms: matched.get(doc), metadata: entry.metadata });
+    }
+    results.sort((x, y) => y.score - x.score || this.idToDoc.get(x.id) - this.idToDoc.get(y.id));
+    return results.slice(0, k);
+  }
+
+  /**
+   * Run several queries against the index
+   */
+  searchBatch(queries, k = 10, options = {}) {
+    return queries.map((query) => this.search(query, k, options));
+  }
+
+  toJSON() {
+    // Compact document numbers so removed slots don't persist
+    const live = this.docs.filter(Boolean);
+    const renumber = new Map(live.map((entry) => [this.idToDoc.get(entry.id), null]));
+    let next = 0;
+    for (const doc of renumber.keys()) renumber.set(doc, next++);
+
+    const postings = {};
+    for (const [term, posting] of this.postings) {
+      postings[term] = Array.from(posting, ([doc, tf]) => [renumber.get(doc), tf]);
+    }
+    return {
+      format: FORMAT,
+      options: this.options,
+      docs: live.map(({ id, length, metadata }) => ({ id, length, metadata })),
+      postings,
+    };
+  }
+
+  static fromJSON(data) {
+    if (data?.format !== FORMAT) throw new Error('Not a BM25 index');
+    const index = new BM25Index(data.options);
+    index.docs = data.docs.map((entry) => ({ ...entry, terms: [] }));
+    index.docs.forEach((entry, doc) => {
+      index.idToDoc.set(entry.id, doc);
+      index.totalLength += entry.length;
+    });
+    for (const [term, list] of Object.entries(data.postings)) {
+      const posting = new Map();
+      for (const [doc, tf] of list) {
+        posting.set(doc, tf);
+        index.docs[doc].terms.push(term);
+      }
+      index.postings.set(term, posting);
+    }
+    return index;
+  }
+
+  /**
+   * Persist the index as JSON
+   */
+  save(filePath) {
+    const tmp = `${filePath}.${process.pid}.tmp`;
+    fs.writeFileSync(tmp, JSON.stringify(this.toJSON()));
+    fs.renameSync(tmp, filePath);
+    return filePath;
+  }
+
+  /**
+   * Load an index written by save()
+   */
+  static load(filePath) {
+    return BM25Index.fromJSON(JSON.parse(fs.readFileSync(filePath, 'utf8')));
+  }
+
+  /**
+   * Build an index in one call
+   * @param {Array<object>} snippets - [{ id, text|content, ...metadata }]
+   * @param {object} options - See DEFAULT_OPTIONS
+   */
+  static build(snippets, options = {}) {
+    return new BM25Index(options).addAll(snippets);
+  }
+}
+
+module.exports = {
+  BM25Index,
+  tokenize,
+  STOP_WORDS,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,348 @@
+/**
+ * BPE Tokenizer
+ * tiktoken-compatible byte-pair encoding for cl100k_base and o200k_base, so
+ * cost estimation and context budgeting count the tokens the model will
+ * actually see instead of guessing from characters.
+ *
+ * Vocabularies (.tiktoken rank files) are optional assets fetched through the
+ * asset cache; loadEncoding() downloads them once, after which countTokens()
+ * and encodeTokens() work synchronously and offline. Text is split with the
+ * encoding's pre-tokenizer regex, each piece is UTF-8 encoded and merged by
+ * rank exactly like tiktoken's byte_pair_merge. Encoded pieces are memoized.
+ */
+
+const fs = require('fs');
+const { createWorker, resolveConcurrency } = require('./thread-pool');
+const { ensureAssets, getAssetPath, DEFAULT_CACHE_DIR } = require('../services/assets/asset-cache');
+
+// JS regexes have no inline (?i:...), so the case-insensitive contractions are spelled out
+const CONTRACTIONS = "'(?:[sS]|[tT]|[rR][eE]|[vV][eE]|[mM]|[lL][lL]|[dD])";
+const UPPER = '[\\p{Lu}\\p{Lt}\\p{Lm}\\p{Lo}\\p{M}]';
+const LOWER = '[\\p{Ll}\\p{Lm}\\p{Lo}\\p{M}]';
+
+const ENCODINGS = {
+  cl100k_base: {
+    asset: 'tiktoken/cl100k_base',
+    pattern: new RegExp(
+      [
+        CONTRACTIONS,
+        '[^\\r\\n\\p{L}\\p{N}]?\\p{L}+',
+        '\\p{N}{1,3}',
+        ' ?[^\\s\\p{L}\\p{N}]+[\\r\\n]*',
+        '\\s*[\\r\\n]+',
+        '\\s+(?!\\S)',
+        '\\s+',
+      ].join('|'),
+      'gu'
+    ),
+    specialTokens: {
+      '<|endoftext|>': 100257,
+      '<|fim_prefix|>': 100258,
+      '<|fim_middle|>': 100259,
+      '<|fim_suffix|>': 100260,
+      '<|endofprompt|>': 100276,
+    },
+  },
+  o200k_base: {
+    asset: 'tiktoken/o200k_base',
+    pattern: new RegExp(
+      [
+        `[^\\r\\n\\p{L}\\p{N}]?${UPPER}*${LOWER}+(?:${CONTRACTIONS})?`,
+        `[^\\r\\n\\p{L}\\p{N}]?${UPPER}+${LOWER}*(?:${CONTRACTIONS})?`,
+        '\\p{N}{1,3}',
+        ' ?[^\\s\\p{L}\\p{N}]+[\\r\\n/]*',
+        '\\s*[\\r\\n]+',
+        '\\s+(?!\\S)',
+        '\\s+',
+      ].join('|'),
+      'gu'
+    ),
+    specialTokens: {
+      '<|endoftext|>': 199999,
+      '<|endofprompt|>': 200018,
+    },
+  },
+};
+
+const DEFAULT_ENCODING = 'cl100k_base';
+const PIECE_CACHE_LIMIT = 50000;
+const loaded = new Map();
+
+/**
+ * Parse a .tiktoken rank file: one "base64(bytes) rank" per line
+ */
+function parseRanks(data) {
+  const ranks = new Map();
+  const decoder = [];
+  for (const line of data.split('\n')) {
+    if (!line) continue;
+    const space = line.indexOf(' ');
+    const bytes = Buffer.from(line.slice(0, space), 'base64').toString('latin1');
+    const rank = Number(line.slice(space + 1));
+    ranks.set(bytes, rank);
+    decoder[rank] = bytes;
+  }
+  return { ranks, decoder };
+}
+
+function buildEncoding(name, data, vocabPath) {
+  const spec = ENCODINGS[name];
+  const { ranks, decoder } = parseRanks(data);
+  const specialDecoder = new Map(Object.entries(spec.specialTokens).map(([token, id]) => [id, token]));
+  return {
+    name,
+    vocabPath,
+    pattern: spec.pattern,
+    specialTokens: spec.specialTokens,
+    specialDecoder,
+    ranks,
+    decoder,
+    cache: new Map(),
+  };
+}
+
+/**
+ * Load an encoding's vocabulary, downloading it through the asset cache if needed
+ * @param {string} name - 'cl100k_base' | 'o200k_base'
+ * @param {object} options - Passed to ensureAssets ({ cacheDir, offline, onProgress, ... }); the
+ *   vocabulary is checked against the digest pinned in the asset registry
+ */
+async function loadEncoding(name = DEFAULT_ENCODING, options = {}) {
+  if (loaded.has(name)) return loaded.get(name);
+  const spec = ENCODINGS[name];
+  if (!spec) throw new Error(`Unknown encoding: ${name}`);
+
+  const cacheDir = options.cacheDir || DEFAULT_CACHE_DIR;
+  const results = await ensureAssets([spec.asset], cacheDir, options);
+  const result = results[spec.asset];
+  if (!result.path) throw new Error(`Vocabulary for ${name} unavailable: ${result.error || result.status}`);
+
+  const encoding = buildEncoding(name, await fs.promises.readFile(result.path, 'utf8'), result.path);
+  loaded.set(name, encoding);
+  return encoding;
+}
+
+/**
+ * Get a loaded encoding, reading an already-cached vocabulary synchronously if necessary
+ * @returns {object|null} Encoding, or null when the vocabulary has not been downloaded yet
+ */
+function getEncoding(name = DEFAULT_ENCODING, cacheDir = DEFAULT_CACHE_DIR) {
+  if (loaded.has(name)) return loaded.get(name);
+  const spec = ENCODINGS[name];
+  if (!spec) throw new Error(`Unknown encoding: ${name}`);
+  const vocabPath = getAssetPath(spec.asset, cacheDir);
+  return vocabPath ? loadEncodingFile(name, vocabPath) : null;
+}
+
+/**
+ * Load an encoding from a specific .tiktoken file (bypassing the asset cache)
+ */
+function loadEncodingFile(name, vocabPath) {
+  if (!ENCODINGS[name]) throw new Error(`Unknown encoding: ${name}`);
+  const encoding = buildEncoding(name, fs.readFileSync(vocabPath, 'utf8'), vocabPath);
+  loaded.set(name, encoding);
+  return encoding;
+}
+
+function requireEncoding(name) {
+  const encoding = getEncoding(name);
+  if (!encoding) throw new Error(`Vocabulary for ${name} is not cached yet; call loadEncoding('${name}') first`);
+  return encoding;
+}
+
+/**
+ * tiktoken's byte_pair_merge: repeatedly merge the adjacent pair with the lowest rank
+ */
+function bytePairEncode(piece, ranks) {
+  const direct = ranks.get(piece);
+  if (direct !== undefined) return [direct];
+
+  // parts[i] is the start offset of the i-th current symbol; the last entry is the end
+  const parts = Array.from({ length: piece.length + 1 }, (_, i) => i);
+  const pairRank = (i) => {
+    if (i + 2 >= parts.length) return Infinity;
+    const rank = ranks.get(piece.slice(parts[i], parts[i + 2]));
+    return rank === undefined ? Infinity : rank;
+  };
+  const pairRanks = parts.map((_, i) => pairRank(i));
+
+  while (parts.length > 2) {
+    let min = Infinity;
+    let at = -1;
+    for (let i = 0; i < pairRanks.length - 2; i++) {
+      if (pairRanks[i] < min) {
+        min = pairRanks[i];
+        at = i;
+      }
+    }
+    if (at === -1) break;
+    parts.splice(at + 1, 1);
+    pairRanks.splice(at + 1, 1);
+    pairRanks[at] = pairRank(at);
+    if (at > 0) pairRanks[at - 1] = pairRank(at - 1);
+  }
+
+  const tokens = [];
+  for (let i = 0; i < parts.length - 1; i++) {
+    const rank = ranks.get(piece.slice(parts[i], parts[i + 1]));
+    if (rank === undefined) throw new Error('Vocabulary is missing a single-byte token');
+    tokens.push(rank);
+  }
+  return tokens;
+}
+
+function encodeOrdinary(text, encoding, out) {
+  encoding.pattern.lastIndex = 0;
+  for (const match of text.matchAll(encoding.pattern)) {
+    const piece = Buffer.from(match[0], 'utf8').toString('latin1');
+    let tokens = encoding.cache.get(piece);
+    if (!tokens) {
+      tokens = bytePairEncode(piece, encoding.ranks);
+      if (encoding.cache.size >= PIECE_CACHE_LIMIT) encoding.cache.clear();
+      encoding.cache.set(piece, tokens);
+    }
+    for (const token of tokens) out.push(token);
+  }
+  return out;
+}
+
+function specialPattern(encoding, allowedSpecial) {
+  const names = allowedSpecial === 'all' ? Object.keys(encoding.specialTokens) : allowedSpecial || [];
+  if (names.length === 0) return null;
+  return new RegExp(names.map((n) => n.replace(/[.*+?^${}()|[\]\\]/g, '\\$&')).join('|'), 'g');
+}
+
+/**
+ * Encode text into token ids
+ * @param {string} text - Input text
+ * @param {string} encodingName - 'cl100k_base' | 'o200k_base'
+ * @param {object} options - { allowedSpecial: 'all' | string[] } special tokens to honour
+ *   (by default they are encoded as ordinary text)
+ * @returns {number[]} Token ids
+ */
+function encodeTokens(text, encodingName = DEFAULT_ENCODING, options = {}) {
+  const encoding = requireEncoding(encodingName);
+  const source = String(text ?? '');
+  const out = [];
+  const special = specialPattern(encoding, options.allowedSpecial);
+  if (!special) return encodeOrdinary(source, encoding, out);
+
+  let position = 0;
+  for (const match of source.matchAll(special)) {
+    encodeOrdinary(source.slice(position, match.index), encoding, out);
+    out.push(encoding.specialTokens[match[0]]);
+    position = match.index + match[0].length;
+  }
+  return encodeOrdinary(source.slice(position), encoding, out);
+}
+
+/**
+ * Decode token ids back into text
+ */
+function decodeTokens(tokens, encodingName = DEFAULT_ENCODING) {
+  const encoding = requireEncoding(encodingName);
+  const chunks = [];
+  for (const token of tokens) {
+    const special = encoding.specialDecoder.get(token);
+    if (special !== undefined) chunks.push(Buffer.from(special, 'utf8'));
+    else if (encoding.decoder[token] !== undefined) chunks.push(Buffer.from(encoding.decoder[token], 'latin1'));
+    else throw new Error(`Unknown token id ${token}`);
+  }
+  return Buffer.concat(chunks).toString('utf8');
+}
+
+/**
+ * Count the tokens a text encodes to
+ */
+function countTokens(text, encodingName = DEFAULT_ENCODING, options = {}) {
+  return encodeTokens(text, encodingName, options).length;
+}
+
+function isEncodingAvailable(name = DEFAULT_ENCODING) {
+  return getEncoding(name) !== null;
+}
+
+const BATCH_WORKER_SOURCE = `
+const { parentPort, workerData } = require('worker_threads');
+const { loadEncodingFile, countTokens } = require(workerData.modulePath);
+loadEncodingFile(workerData.encoding, workerData.vocabPath);
+parentPort.on('message', (texts) => {
+  const counts = new Uint32Array(texts.length);
+  for (let i = 0; i < texts.length; i++) counts[i] = countTokens(texts[i], workerData.encoding);
+  parentPort.postMessage(counts, [counts.buffer]);
+});
+`;
+
+/**
+ * Count tokens for many texts at once, spreading the work over worker threads.
+ * Identical texts are counted once; small batches are counted inline.
+ * @param {Array<string>} texts - Texts to count
+ * @param {string} encodingName - 'cl100k_base' | 'o200k_base'
+ * @param {object} options - { concurrency, minParallelChars, batchSize }
+ * @returns {Promise<Uint32Array>} Counts aligned with texts
+ */
+async function batchCountTokens(texts, encodingName = DEFAULT_ENCODING, options = {}) {
+  const encoding = requireEncoding(encodingName);
+  const concurrency = resolveConcurrency(options.concurrency);
+  const minParallelChars = options.minParallelChars ?? 1000000;
+  const batchSize = options.batchSize ?? 500;
+
+  const input = (texts || []).map((text) => String(text ?? ''));
+  const distinct = new Map();
+  for (const text of input) if (!distinct.has(text)) distinct.set(text, distinct.size);
+  const unique = Array.from(distinct.keys());
+  const uniqueCounts = new Uint32Array(unique.length);
+  const totalChars = unique.reduce((sum, text) => sum + text.length, 0);
+
+  if (concurrency <= 1 || totalChars < minParallelChars) {
+    unique.forEach((text, i) => {
+      uniqueCounts[i] = countTokens(text, encodingName);
+    });
+  } else {
+    const batches = [];
+    for (let i = 0; i < unique.length; i += batchSize) batches.push(i);
+    const workers = Array.from(
+      { length: Math.min(concurrency, batches.length) },
+      () =>
+        createWorker(BATCH_WORKER_SOURCE, {
+          workerData: { modulePath: __filename, encoding: encodingName, vocabPath: encoding.vocabPath },
+        })
+    );
+
+    let next = 0;
+    try {
+      await Promise.all(
+        workers.map(async (worker) => {
+          while (next < batches.length) {
+            const start = batches[next++];
+            const slice = unique.slice(start, start + batchSize);
+            const counts = await new Promise((resolve, reject) => {
+              worker.once('message', resolve);
+              worker.once('error', reject);
+              worker.postMessage(slice);
+            });
+            worker.removeAllListeners('error');
+            uniqueCounts.set(counts, start);
+          }
+        })
+      );
+    } finally {
+      await Promise.all(workers.map((worker) => worker.terminate()));
+    }
+  }
+
+  return Uint32Array.from(input, (text) => uniqueCounts[distinct.get(text)]);
+}
+
+module.exports = {
+  countTokens,
+  batchCountTokens,
+  encodeTokens,
+  decodeTokens,
+  loadEncoding,
+  getEncoding,
+  loadEncodingFile,
+  isEncodingAvailable,
+  ENCODINGS,
+  DEFAULT_ENCODING,
+};
@@ -0,0 +1,129 @@
+/**
+ * Call Metrics
+ * Per-function call counts, cumulative time and latency percentiles for the
+ * functions wrapped by logger.instrument(), plus process peak memory, for
+ * the diagnostics page.
+ *
+ * Latencies go into a fixed log-scale histogram per function (four buckets
+ * per power of two, from 1µs up), so recording a call is a couple of array
+ * writes and memory stays constant however many calls are made; percentiles
+ * are accurate to the bucket width (about ±10%).
+ */
+
+const BUCKETS_PER_DOUBLING = 4;
+const BUCKET_COUNT = 128; // 1µs .. ~2^32µs (over an hour)
+const HEAP_SAMPLE_INTERVAL_MS = 5000;
+
+const functions = new Map(); // 'MODULE.name' -> stats
+let peakHeapUsed = 0;
+let heapSampler = null;
+let since = Date.now();
+
+function bucketOf(durationMs) {
+  const micros = durationMs * 1000;
+  if (micros <= 1) return 0;
+  return Math.min(BUCKET_COUNT - 1, Math.ceil(Math.log2(micros) * BUCKETS_PER_DOUBLING));
+}
+
+function bucketUpperMs(index) {
+  return 2 ** (index / BUCKETS_PER_DOUBLING) / 1000;
+}
+
+function sampleHeap() {
+  peakHeapUsed = Math.max(peakHeapUsed, process.memoryUsage().heapUsed);
+}
+
+function startHeapSampler() {
+  if (heapSampler) return;
+  sampleHeap();
+  heapSampler = setInterval(sampleHeap, HEAP_SAMPLE_INTERVAL_MS);
+  heapSampler.unref();
+}
+
+/**
+ * Record one call
+ * @param {string} module - Module tag, e.g. 'DIFF'
+ * @param {string} name - Function name
+ * @param {number} durationMs - Wall time, including time to settle for async functions
+ * @param {boolean} failed - Whether it threw or rejected
+ */
+function recordCall(module, name, durationMs, failed = false) {
+  const key = `${module}.${name}`;
+  let stats = functions.get(key);
+  if (!stats) {
+    const histogram = new Uint32Array(BUCKET_COUNT);
+    stats = { module, name, calls: 0, errors: 0, totalMs: 0, maxMs: 0, histogram };
+    functions.set(key, stats);
+    startHeapSampler();
+  }
+  stats.calls++;
+  if (failed) stats.errors++;
+  stats.totalMs += durationMs;
+  if (durationMs > stats.maxMs) stats.maxMs = durationMs;
+  stats.histogram[bucketOf(durationMs)]++;
+}
+
+function percentile(stats, p) {
+  const target = Math.ceil(stats.calls * p);
+  let seen = 0;
+  for (let i = 0; i < BUCKET_COUNT; i++) {
+    seen += stats.histogram[i];
+    if (seen >= target) return Math.min(bucketUpperMs(i), stats.maxMs);
+  }
+  return stats.maxMs;
+}
+
+const round = (value) => Math.round(value * 1000) / 1000;
+
+/**
+ * Snapshot of all recorded calls
+ * @param {object} options - { module, reset }
+ * @returns {object} { since, functions: [{ module, name, calls, errors, totalMs, meanMs, p50Ms, p95Ms,
+ *   p99Ms, maxMs }], memory: { rss, peakRss, heapUsed, peakHeapUsed } }
+ */
+function getCallMetrics(options = {}) {
+  const memory = process.memoryUsage();
+  peakHeapUsed = Math.max(peakHeapUsed, memory.heapUsed);
+  const rows = [];
+  for (const stats of functions.values()) {
+    if (options.module && stats.module !== options.module) continue;
+    rows.push({
+      module: stats.module,
+      name: stats.name,
+      calls: stats.calls,
+      errors: stats.errors,
+      totalMs: round(stats.totalMs),
+      meanMs: round(stats.totalMs / stats.calls),
+      p50Ms: round(percentile(stats, 0.5)),
+      p95Ms: round(percentile(stats, 0.95)),
+      p99Ms: round(percentile(stats, 0.99)),
+      maxMs: round(stats.maxMs),
+    });
+  }
+  rows.sort((a, b) => b.totalMs - a.totalMs);
+
+  const snapshot = {
+    since,
+    functions: rows,
+    memory: {
+      rss: memory.rss,
+      peakRss: process.resourceUsage().maxRSS * 1024,
+      heapUsed: memory.heapUsed,
+      peakHeapUsed,
+    },
+  };
+  if (options.reset) resetCallMetrics();
+  return snapshot;
+}
+
+function resetCallMetrics() {
+  functions.clear();
+  peakHeapUsed = 0;
+  since = Date.now();
+}
+
+module.exports = {
+  recordCall,
+  getCallMetrics,
+  resetCallMetrics,
+};
@@ -0,0 +1,295 @@
+/**
+ * Chunked Result Transfer
+ * Moves very large array results out of band instead of materializing one
+ * giant JSON value on the main thread.
+ *
+ * Results under the size threshold are returned inline. Larger results are
+ * serialized incrementally (yielding to the event loop between batches) into a
+ * temp file or a SharedArrayBuffer, and a small reference object is returned.
+ * Either way writeJsonArray() and pipeResultToResponse() send them a batch of
+ * items at a time.
+ *
+ * Only arrays are accepted: a single object has no batch boundary to yield
+ * at, so it would still be stringified in one go.
+ */
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const readline = require('readline');
+const { v4: uuidv4 } = require('uuid');
+const { CompanionError, ERROR_CODES } = require('./errors');
+
+const DEFAULT_THRESHOLD_BYTES = 8 * 1024 * 1024; // 8MB
+const ITEMS_PER_TICK = 500;
+const RESULT_DIR = path.join(os.tmpdir(), 'cursor-companion-results');
+
+const liveRefs = new Map();
+
+function isResultRef(value) {
+  return Boolean(value && typeof value === 'object' && value.__resultRef === true);
+}
+
+function yieldToEventLoop() {
+  return new Promise((resolve) => setImmediate(resolve));
+}
+
+/**
+ * Serialize items to newline-delimited JSON chunks without blocking for long
+ */
+async function* serializeItems(items) {
+  let batch = [];
+  for (let i = 0; i < items.length; i++) {
+    batch.push(JSON.stringify(items[i]));
+    if (batch.length >= ITEMS_PER_TICK) {
+      yield batch.join('\n') + '\n';
+      batch = [];
+      await yieldToEventLoop();
+    }
+  }
+  if (batch.length > 0) yield batch.join('\n') + '\n';
+}
+
+function checkArray(value) {
+  if (!Array.isArray(value)) {
+    throw new CompanionError(ERROR_CODES.INVALID_INPUT, 'Chunked results must be arrays', {
+      details: { type: value === null ? 'null' : typeof value },
+    });
+  }
+}
+
+/**
+ * Cheap upper-bound size estimate from a sample of items
+ */
+function estimateSize(value) {
+  if (value.length === 0) return 2;
+
+  const sampleSize = Math.min(value.length, 50);
+  const step = value.length / sampleSize;
+  let sampled = 0;
+  for (let i = 0; i < sampleSize; i++) {
+    sampled += Buffer.byteLength(JSON.stringify(value[Math.floor(i * step)]) || '');
+  }
+  return Math.ceil((sampled / sampleSize) * value.length);
+}
+
+async function writeToFile(items, id) {
+  await fs.promises.mkdir(RESULT_DIR, { recursive: true });
+  const filePath = path.join(RESULT_DIR, `${id}.jsonl`);
+  const stream = fs.createWriteStream(filePath);
+  let bytes = 0;
+
+  for await (const chunk of serializeItems(items)) {
+    bytes += Buffer.byteLength(chunk);
+    if (!stream.write(chunk)) {
+      await new Promise((resolve) => stream.once('drain', resolve));
+    }
+  }
+
+  await new Promise((resolve, reject) => {
+    stream.on('error', reject);
+    stream.end(resolve);
+  });
+
+  return { path: filePath, bytes };
+}
+
+async function writeToSharedBuffer(items, estimatedBytes) {
+  let buffer = new SharedArrayBuffer(Math.max(1024, Math.ceil(estimatedBytes * 1.25)));
+  let view = new Uint8Array(buffer);
+  let bytes = 0;
+
+  for await (const chunk of serializeItems(items)) {
+    const encoded = Buffer.from(chunk);
+    if (bytes + encoded.length > view.length) {
+      const grown = new SharedArrayBuffer(Math.ceil((bytes + encoded.length) * 1.5));
+      new Uint8Array(grown).set(view.subarray(0, bytes));
+      buffer = grown;
+      view = new Uint8Array(buffer);
+    }
+    view.set(encoded, bytes);
+    bytes += encoded.length;
+  }
+
+  return { buffer, bytes };
+}
+
+/**
+ * Return a result inline or as an out-of-band reference depending on its size
+ * @param {Array} items - Result items (stored one per line)
+ * @param {object} options - { thresholdBytes, transport: 'file'|'shared' }
+ * @returns {Promise<Array|object>} The original array, or a result reference
+ */
+async function wrapResult(items, options = {}) {
+  checkArray(items);
+  const thresholdBytes = options.thresholdBytes ?? DEFAULT_THRESHOLD_BYTES;
+  const estimated = estimateSize(items);
+  if (estimated <= thresholdBytes) return items;
+
+  const id = uuidv4();
+  const base = {
+    __resultRef: true,
+    id,
+    format: 'jsonl',
+    count: items.length,
+    createdAt: Date.now(),
+  };
+
+  let ref;
+  if (options.transport === 'shared') {
+    const { buffer, bytes } = await writeToSharedBuffer(items, estimated);
+    ref = { ...base, kind: 'shared', buffer, bytes };
+  } else {
+    const { path: filePath, bytes } = await writeToFile(items, id);
+    ref = { ...base, kind: 'file', path: filePath, bytes };
+  }
+
+  liveRefs.set(id, ref);
+  return ref;
+}
+
+/**
+ * Iterate the items of a result reference without loading it all at once
+ */
+async function* iterateResult(ref) {
+  if (!isResultRef(ref)) {
+    checkArray(ref);
+    yield* ref;
+    return;
+  }
+
+  if (ref.kind === 'shared') {
+    const view = new Uint8Array(ref.buffer, 0, ref.bytes);
+    let start = 0;
+    for (let i = 0; i < view.length; i++) {
+      if (view[i] === 0x0a) {
+        if (i > start) yield JSON.parse(Buffer.from(view.subarray(start, i)).toString('utf8'));
+        start = i + 1;
+      }
+    }
+    return;
+  }
+
+  const rl = readline.createInterface({ input: fs.createReadStream(ref.path), crlfDelay: Infinity });
+  for await (const line of rl) {
+    if (line.length > 0) yield JSON.parse(line);
+  }
+}
+
+/**
+ * Materialize a result reference back into an array
+ */
+async function resolveResult(ref) {
+  if (!isResultRef(ref)) return ref;
+  const items = [];
+  for await (const item of iterateResult(ref)) items.push(item);
+  return items;
+}
+
+function checkWritable(out) {
+  if (out.destroyed || out.writableEnded) {
+    throw new CompanionError(ERROR_CODES.CANCELLED, 'Stream closed before the result was written');
+  }
+}
+
+/**
+ * Wait for 'drain'; a stream that closes or errors first (a client that went away) never drains
+ */
+function waitForDrain(out) {
+  return new Promise((resolve, reject) => {
+    const done = (error) => {
+      out.off('drain', onDrain);
+      out.off('close', onClose);
+      out.off('error', onError);
+      if (error) reject(error);
+      else resolve();
+    };
+    const onDrain = () => done();
+    const onClose = () =>
+      done(new CompanionError(ERROR_CODES.CANCELLED, 'Stream closed before the result was written'));
+    const onError = (cause) =>
+      done(new CompanionError(ERROR_CODES.IO, 'Stream failed while writing the result', { cause }));
+    out.on('drain', onDrain);
+    out.on('close', onClose);
+    out.on('error', onError);
+  });
+}
+
+/**
+ * Write a result (inline or referenced) to a writable stream as a JSON array, yielding to
+ * the event loop between batches of items
+ * Rejects with a CompanionError: CANCELLED when the stream closes first, IO when it errors
+ */
+async function writeJsonArray(out, ref) {
+  checkWritable(out);
+  out.write('[');
+  let count = 0;
+  for await (const item of iterateResult(ref)) {
+    checkWritable(out);
+    const json = JSON.stringify(item) ?? 'null';
+    if (!out.write(count === 0 ? json : ',' + json)) await waitForDrain(out);
+    if (++count % ITEMS_PER_TICK === 0) await yieldToEventLoop();
+  }
+  checkWritable(out);
+  out.write(']');
+}
+
+/**
+ * Stream a result (inline or referenced) to an Express response as {...fields, "<key>":[...]}
+ * @param {object} options - { key (default 'data'), fields: small values written before it }
+ */
+async function pipeResultToResponse(res, ref, options = {}) {
+  const { key = 'data', fields = {} } = options;
+  res.setHeader('Content-Type', 'application/json');
+  const head = JSON.stringify(fields).slice(0, -1);
+  res.write(`${head}${head.length > 1 ? ',' : ''}${JSON.stringify(key)}:`);
+  await writeJsonArray(res, ref);
+  res.end('}');
+}
+
+/**
+ * Release the storage behind a result reference
+ */
+async function releaseResult(ref) {
+  if (!isResultRef(ref)) return false;
+  liveRefs.delete(ref.id);
+  if (ref.kind === 'file') {
+    try {
+      await fs.promises.unlink(ref.path);
+    } catch (error) {
+      if (error.code !== 'ENOENT') throw error;
+    }
+  }
+  return true;
+}
+
+function getLiveResultStats() {
+  let bytes = 0;
+  for (const ref of liveRefs.values()) bytes += ref.bytes;
+  return { count: liveRefs.size, bytes };
+}
+
+// Temp files must not outlive the process
+process.once('exit', () => {
+  for (const ref of liveRefs.values()) {
+    if (ref.kind === 'file') {
+      try {
+        fs.unlinkSync(ref.path);
+      } catch {
+        // Already gone
+      }
+    }
+  }
+});
+
+module.exports = {
+  wrapResult,
+  iterateResult,
+  resolveResult,
+  writeJsonArray,
+  pipeResultToResponse,
+  releaseResult,
+  isResultRef,
+  getLiveResultStats,
+  DEFAULT_THRESHOLD_BYTES,
+};
@@ -0,0 +1,172 @@
+/**
+ * Code Annotations
+ * TODO / FIXME / HACK / NOTE / XXX markers in comments, with the author or
+ * issue they name and the lines around them, so a trace can record whether
+ * an edit introduced or resolved one.
+ *
+ * Only comment text is searched (code-lexer segments), so a "TODO" inside a
+ * string literal or an identifier like `todoList` isn't reported. Languages
+ * without comment syntax (plain text) are searched as a whole.
+ *
+ * Recognized forms:
+ *   TODO: text            TODO(alice): text      TODO(#123): text
+ *   TODO @alice text      TODO [alice] text      FIXME #42 text (bob)
+ */
+
+const { scanCode, getSyntax, hasCommentSyntax } = require('./code-lexer');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  tags: ['TODO', 'FIXME', 'HACK', 'NOTE', 'XXX'],
+  contextLines: 2, // Lines of context before and after each annotation
+  caseSensitive: true, // Lowercase `todo` in prose is usually not a marker
+};
+
+const BLOCK_CLOSER = /\s*(\*\/|-->|#\}|-\}|\*\))\s*$/;
+const ISSUE = /^(?:#\d+|[A-Z][A-Z0-9]+-\d+|https?:\/\/\S+)$/;
+
+function lineStarts(content) {
+  const starts = [0];
+  for (let i = content.indexOf('\n'); i >= 0; i = content.indexOf('\n', i + 1)) starts.push(i + 1);
+  return starts;
+}
+
+function lineAt(starts, offset) {
+  let low = 0;
+  let high = starts.length - 1;
+  while (low < high) {
+    const mid = (low + high + 1) >> 1;
+    if (starts[mid] <= offset) low = mid;
+    else high = mid - 1;
+  }
+  return low;
+}
+
+/**
+ * Split what follows a tag into author / issue hints and the message
+ */
+function parseRemainder(rest) {
+  let text = rest;
+  let author = null;
+  let issue = null;
+  const hint = (value) => {
+    for (const part of value.split(/[,;\s]+/).filter(Boolean)) {
+      if (ISSUE.test(part)) issue = issue || part;
+      else author = author || part.replace(/^@/, '');
+    }
+  };
+
+  // TODO(alice): / TODO[#12] / TODO <bob>
+  const bracketed = /^\s*[([<]([^)\]>]{1,60})[)\]>]/.exec(text);
+  if (bracketed) {
+    hint(bracketed[1]);
+    text = text.slice(bracketed[0].length);
+  }
+  text = text.replace(/^\s*[:\-–—]?\s*/, '');
+  // FIXME #42 text
+  const reference = /^(\S+)\s*[:\-]?\s*/.exec(text);
+  if (reference && !issue && ISSUE.test(reference[1])) {
+    issue = reference[1];
+    text = text.slice(reference[0].length);
+  }
+  // TODO @alice text
+  const mention = /^@([\w.-]+)\s*[:\-]?\s*/.exec(text);
+  if (mention && !author) {
+    author = mention[1];
+    text = text.slice(mention[0].length);
+  }
+  // TODO: text (bob)
+  const trailing = /\s*\((@?[\w.-]+)\)\s*$/.exec(text);
+  if (trailing && !author) {
+    hint(trailing[1]);
+    text = text.slice(0, trailing.index);
+  }
+  return { text: text.trim(), author, issue };
+}
+
+/**
+ * Find annotations in comments
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias; unknown uses code-lexer's generic syntax
+ * @param {object} options - { tags, contextLines, caseSensitive }
+ * @returns {Array<object>} [{ tag, text, author, issue, line, column, context: { before, after } }],
+ *   lines 1-based; context holds the neighbouring source lines
+ */
+function extractAnnotations(content, language = 'unknown', options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const text = String(content || '');
+  if (!text || !opts.tags.length) return [];
+
+  const pattern = new RegExp(
+    `(?<![\\w$])(${opts.tags.map((tag) => tag.replace(/[.*+?^${}()|[\]\\]/g, '\\$&')).join('|')})(?![\\w$])`,
+    opts.caseSensitive ? 'g' : 'gi'
+  );
+  const regions = hasCommentSyntax(getSyntax(language).language)
+    ? scanCode(text, language).filter((segment) => segment.type === 'comment')
+    : [{ start: 0, end: text.length }];
+
+  const starts = lineStarts(text);
+  const lines = text.split('\n');
+  const annotations = [];
+  for (const region of regions) {
+    const comment = text.slice(region.start, region.end);
+    pattern.lastIndex = 0;
+    let found;
+    while ((found = pattern.exec(comment))) {
+      const offset = region.start + found.index;
+      const line = lineAt(starts, offset);
+      // The annotation runs to the end of its line (or comment), without a block comment's closer
+      const lineEnd = Math.min(region.end, line + 1 < starts.length ? starts[line + 1] - 1 : text.length);
+      const rest = text.slice(offset + found[0].length, lineEnd);
+      const { text: message, author, issue } = parseRemainder(rest.replace(BLOCK_CLOSER, ''));
+      annotations.push({
+        tag: found[1].toUpperCase(),
+        text: message,
+        author,
+        issue,
+        line: line + 1,
+        column: offset - starts[line] + 1,
+        context: {
+          before: lines.slice(Math.max(0, line - opts.contextLines), line),
+          after: lines.slice(line + 1, line + 1 + opts.contextLines),
+        },
+      });
+    }
+  }
+  return annotations;
+}
+
+/**
+ * Annotations an edit introduced and resolved
+ * Matched on tag and text rather than line, so moving a TODO isn't a change.
+ * @returns {object} { added: [annotation], resolved: [annotation], kept }
+ */
+function diffAnnotations(before, after, language = 'unknown', options = {}) {
+  const key = (annotation) => `${annotation.tag}\u0000${annotation.text}`;
+  const old = extractAnnotations(before, language, options);
+  const current = extractAnnotations(after, language, options);
+
+  const remaining = new Map();
+  for (const annotation of old) remaining.set(key(annotation), (remaining.get(key(annotation)) || 0) + 1);
+  const added = [];
+  for (const annotation of current) {
+    const count = remaining.get(key(annotation)) || 0;
+    if (count > 0) remaining.set(key(annotation), count - 1);
+    else added.push(annotation);
+  }
+  const resolved = [];
+  for (const annotation of [...old].reverse()) {
+    const count = remaining.get(key(annotation)) || 0;
+    if (count > 0) {
+      remaining.set(key(annotation), count - 1);
+      resolved.unshift(annotation);
+    }
+  }
+  return { added, resolved, kept: current.length - added.length };
+}
+
+module.exports = instrument('CODE', {
+  extractAnnotations,
+  diffAnnotations,
+  DEFAULT_OPTIONS,
+});
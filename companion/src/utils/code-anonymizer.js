@@ -0,0 +1,134 @@
+/**
+ * Code Anonymizer
+ * Rewrites source so it can be shared without revealing the code: names are
+ * replaced with keyed pseudonyms, string and number literals with
+ * placeholders, and comments are removed, while keywords, operators, brackets
+ * and layout stay exactly where they were.
+ *
+ * Replacements are consistent: under one seed, the same identifier or literal
+ * always becomes the same pseudonym, in every file and every snapshot. So
+ * anonymized snapshots still diff line for line, token similarity and
+ * duplication are unchanged, and a rename still reads as a rename. A
+ * different seed gives unrelated pseudonyms; without one, the dataset key
+ * from pseudonymize is used.
+ *
+ * Line structure is kept: a removed comment leaves its line breaks behind,
+ * and a Python docstring becomes an empty string so the block stays valid.
+ */
+
+const { scanCode, getSyntax, isKeyword } = require('./code-lexer');
+const { pseudonymize } = require('./pseudonymize');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  identifiers: true, // Rename identifiers
+  strings: 'hash', // 'hash' (consistent placeholder per value) | 'constant' (one placeholder) | 'keep'
+  numbers: 'hash', // 'hash' | 'constant' | 'keep'
+  comments: 'strip', // 'strip' | 'keep'
+  keepBuiltins: true, // Leave well-known library names (console, print, Vec, ...) readable
+  keep: [], // Further names to leave as is
+  length: 8, // Pseudonym length
+};
+
+// Names that identify a language or its standard library, not the project
+const BUILTINS = new Set(
+  (
+    // JavaScript / TypeScript
+    'console window document globalThis process require module exports Object Array String Number ' +
+    'Boolean Symbol Map Set WeakMap Promise JSON Math Date RegExp Error undefined NaN Infinity length ' +
+    'prototype ' +
+    // Python
+    'self cls print len range str int float bool dict list set tuple open super isinstance enumerate ' +
+    'zip map filter __init__ __name__ __main__ ' +
+    // Rust, Go, Java, C
+    'Self Some None Ok Err Vec Option Result Box std fmt nil err make append System main printf ' +
+    'malloc free NULL size_t u8 u16 u32 u64 usize i8 i16 i32 i64 isize f32 f64'
+  ).split(' ')
+);
+
+// Numbers (group 1, with any type suffix in group 2) and identifiers
+const WORD =
+  /(0[xX][\da-fA-F_]+|0[bBoO][0-7_]+|\d[\d_]*(?:\.\d[\d_]*)?(?:[eE][+-]?\d+)?)([A-Za-z_]\w*)?|[A-Za-z_$\u00C0-\uFFFF][\w$\u00C0-\uFFFF]*/g;
+
+function styleOf(name) {
+  if (/^[A-Z0-9_]+$/.test(name) && /[A-Z]/.test(name)) return 'ID';
+  return /^_*[A-Z]/.test(name) ? 'Id' : 'id';
+}
+
+/**
+ * Replace a string literal's body, keeping its prefix and delimiters
+ */
+function replaceLiteral(literal, body) {
+  const open = /^[^"'`]*(?:"""|'''|["'`])/.exec(literal);
+  if (!open) return literal;
+  const quote = open[0][open[0].length - 1];
+  const delimiter = open[0].endsWith(quote.repeat(3)) ? quote.repeat(3) : quote;
+  const close = literal.lastIndexOf(delimiter);
+  if (close <= open[0].length - 1 || close === open[0].length) return literal; // Unterminated, or empty
+  return literal.slice(0, open[0].length) + body + literal.slice(close);
+}
+
+/**
+ * Anonymize source code
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias
+ * @param {string|Buffer} seed - Key for the pseudonyms (default: the pseudonymization dataset key)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {string} The anonymized source, with the same lines as the input
+ */
+function anonymizeCode(content, language, seed = null, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const text = String(content ?? '');
+  const resolved = getSyntax(language).language;
+  const keep = new Set(opts.keep);
+  const key = seed ?? undefined;
+  const cache = new Map();
+  const pseudonym = (value, namespace, prefix) => {
+    const cacheKey = `${namespace}\u0000${value}`;
+    if (!cache.has(cacheKey)) {
+      cache.set(cacheKey, pseudonymize(value, namespace, { key, prefix, length: opts.length }));
+    }
+    return cache.get(cacheKey);
+  };
+
+  const renameWord = (word, number, suffix, offset) => {
+    if (number !== undefined) {
+      if (opts.numbers === 'keep') return word;
+      if (opts.numbers === 'constant') return `0${suffix || ''}`;
+      // Digits only, so the literal stays a number
+      const digits = [...pseudonym(number, 'number', '')].map((char) => char.charCodeAt(0) % 10);
+      return digits.join('').slice(0, 6).replace(/^0/, '1') + (suffix || '');
+    }
+    if (!opts.identifiers || keep.has(word) || isKeyword(resolved, word)) return word;
+    if (opts.keepBuiltins && BUILTINS.has(word)) return word;
+    // String prefixes (r"", b'', f"") belong to the literal that follows
+    if (/^[A-Za-z]{1,3}$/.test(word) && /["'`]/.test(text[offset + word.length] || '')) return word;
+    return pseudonym(word, 'identifier', styleOf(word));
+  };
+
+  let out = '';
+  for (const segment of scanCode(text, resolved)) {
+    const slice = text.slice(segment.start, segment.end);
+    if (segment.type === 'comment') {
+      if (resolved === 'python' && segment.doc) {
+        out += opts.comments === 'keep' ? slice : `""${slice.replace(/[^\n]/g, '')}`;
+      } else {
+        out += opts.comments === 'keep' ? slice : slice.replace(/[^\n]/g, '');
+      }
+    } else if (segment.type === 'string') {
+      if (opts.strings === 'keep') out += slice;
+      else if (opts.strings === 'constant') out += replaceLiteral(slice, 'str');
+      else out += replaceLiteral(slice, pseudonym(slice, 'string', 'str'));
+    } else {
+      const { start } = segment;
+      out += slice.replace(WORD, (word, number, suffix, at) => renameWord(word, number, suffix, start + at));
+    }
+  }
+  // Lines left holding only the indentation of a removed comment
+  return opts.comments === 'keep' ? out : out.replace(/[ \t]+$/gm, '');
+}
+
+module.exports = instrument('CODE', {
+  anonymizeCode,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,156 @@
+/**
+ * Code Block Extraction
+ * Parses fenced code blocks out of assistant Markdown (CommonMark fence rules:
+ * ``` or ~~~, up to three spaces of indent, closing fence at least as long as
+ * the opening one, unclosed fences run to the end of the document) and
+ * recovers language tags and file-path hints.
+ *
+ * Path hints are taken, in order of preference, from the info string
+ * (```ts src/a.ts, ```ts:src/a.ts, ```12:40:src/a.ts, title="src/a.ts"),
+ * from the line just before the fence (**`src/a.ts`**), or from a leading
+ * filename comment inside the block (// src/a.ts).
+ */
+
+const FENCE_OPEN = /^( {0,3})(`{3,}|~{3,})(.*)$/;
+const PATH_LIKE = /^(?:[\w.@~-]+[\\/])*[\w.@-]+\.[\w]+$|^(?:[\w.@~-]+[\\/])+[\w.@-]+$/;
+const PRECEDING_PATH = /^\s*(?:[-*]\s+)?(?:\*\*|__)?`?((?:[\w.@~-]+[\\/])*[\w.@-]+\.\w+)`?(?:\*\*|__)?:?\s*$/;
+const COMMENT_PATH = /^\s*(?:\/\/|#|--|;|\/\*|<!--)\s*(?:file(?:name)?:\s*)?((?:[\w.@~-]+[\\/])*[\w.@-]+\.\w+)\s*(?:\*\/|-->)?\s*$/i;
+
+function looksLikePath(value) {
+  return Boolean(value) && value.length < 260 && PATH_LIKE.test(value) && /[\\/.]/.test(value);
+}
+
+/**
+ * Split an info string into language, path hint and line range
+ */
+function parseInfoString(info) {
+  const result = { language: null, path: null, lineRange: null };
+  const trimmed = info.trim();
+  if (!trimmed) return result;
+
+  // Cursor code citations: ```startLine:endLine:path
+  const citation = trimmed.match(/^(\d+):(\d+):(\S+)$/);
+  if (citation) {
+    result.lineRange = [Number(citation[1]), Number(citation[2])];
+    result.path = citation[3];
+    return result;
+  }
+
+  const attr = trimmed.match(/\b(?:title|file|filename|path)=["']?([^"'\s}]+)["']?/);
+  if (attr) result.path = attr[1];
+
+  const tokens = trimmed.replace(/\{[^}]*\}/g, ' ').split(/\s+/).filter(Boolean);
+  for (const token of tokens) {
+    if (/^\w[\w+#-]*$/.test(token) && !result.language && !looksLikePath(token)) {
+      result.language = token.toLowerCase();
+    } else if (token.includes(':') && !result.language) {
+      // ```lang:path
+      const [language, ...rest] = token.split(':');
+      result.language = language.toLowerCase() || null;
+      if (!result.path && looksLikePath(rest.join(':'))) result.path = rest.join(':');
+    } else if (!result.path && looksLikePath(token.replace(/^["']|["']$/g, ''))) {
+      result.path = token.replace(/^["']|["']$/g, '');
+    }
+  }
+
+  return result;
+}
+
+/**
+ * Extract fenced code blocks from Markdown
+ * @param {string} markdown - Assistant response text
+ * @returns {Array<object>} Blocks with { language, path, pathSource, info, code, start, end,
+ *   contentStart, contentEnd, startLine, endLine, lineRange, closed }. Offsets are UTF-8 byte
+ *   offsets into the input; start/end cover the fences, contentStart/contentEnd the code.
+ */
+function extractCodeBlocks(markdown) {
+  const text = String(markdown || '');
+  const blocks = [];
+  const lines = text.split('\n');
+
+  let byteOffset = 0;
+  let open = null;
+  let previousLine = '';
+
+  for (let i = 0; i < lines.length; i++) {
+    const line = lines[i];
+    const lineBytes = Buffer.byteLength(line, 'utf8');
+    const nextOffset = byteOffset + lineBytes + (i < lines.length - 1 ? 1 : 0);
+
+    if (!open) {
+      const match = line.match(FENCE_OPEN);
+      // Backtick fences cannot have backticks in their info string
+      if (match && !(match[2][0] === '`' && match[3].includes('`'))) {
+        open = {
+          fence: match[2],
+          indent: match[1].length,
+          info: match[3].trim(),
+          start: byteOffset,
+          contentStart: nextOffset,
+          startLine: i + 1,
+          preceding: previousLine,
+          body: [],
+        };
+      }
+    } else {
+      const close = line.match(/^ {0,3}(`{3,}|~{3,})\s*$/);
+      if (close && close[1][0] === open.fence[0] && close[1].length >= open.fence.length) {
+        blocks.push(finishBlock(open, byteOffset, nextOffset, i + 1, true));
+        open = null;
+      } else {
+        // Strip up to the opening fence's indentation from content lines
+        open.body.push(open.indent > 0 ? line.replace(new RegExp(`^ {0,${open.indent}}`), '') : line);
+      }
+    }
+
+    if (line.trim()) previousLine = line;
+    byteOffset = nextOffset;
+  }
+
+  if (open) blocks.push(finishBlock(open, byteOffset, byteOffset, lines.length, false));
+  return blocks;
+}
+
+function finishBlock(open, contentEnd, end, endLine, closed) {
+  const code = open.body.join('\n');
+  const info = parseInfoString(open.info);
+  let path = info.path;
+  let pathSource = path ? 'info' : null;
+
+  if (!path) {
+    const preceding = open.preceding.match(PRECEDING_PATH);
+    if (preceding) {
+      path = preceding[1];
+      pathSource = 'preceding-line';
+    }
+  }
+  if (!path && open.body.length > 0) {
+    const comment = open.body[0].match(COMMENT_PATH);
+    if (comment) {
+      path = comment[1];
+      pathSource = 'comment';
+    }
+  }
+
+  return {
+    language: info.language,
+    path,
+    pathSource,
+    info: open.info,
+    code,
+    start: open.start,
+    end,
+    contentStart: open.contentStart,
+    // The newline before the closing fence belongs to the fence, not the code
+    contentEnd: closed ? Math.max(open.contentStart, contentEnd - 1) : contentEnd,
+    startLine: open.startLine,
+    endLine,
+    lineRange: info.lineRange,
+    closed,
+  };
+}
+
+module.exports = {
+  extractCodeBlocks,
+  parseInfoString,
+};
@@ -0,0 +1,285 @@
+/**
+ * Code Call Graph
+ * Which functions defined in a file call which others, and what they call
+ * outside it, so an edit can be attributed to the code downstream of it.
+ *
+ * Built on code-symbols: every `name(` / `receiver.name(` call site is
+ * assigned to the innermost function whose body contains it, then resolved
+ * against the file's own definitions by scope:
+ *   bare calls          nested / top-level functions, and for languages with
+ *                       implicit `this` (Java, C#, C++, Kotlin, ...) methods
+ *                       of the caller's own class first
+ *   this / self / Self  methods of the caller's class
+ *   Type.name, Type::   methods of a class, struct or impl defined here
+ *   new Type(...)       the class's constructor, when it declares one
+ * Calls through any other receiver (`obj.save()`, `fs.readFile()`) can't be
+ * resolved without types and are reported as external, as are Ruby calls
+ * written without parentheses and macros.
+ */
+
+const { parseSymbols } = require('./code-symbols');
+const { instrument } = require('./logger');
+
+const FUNCTION_KINDS = new Set(['function', 'method']);
+const TYPE_KINDS = new Set(['class', 'interface', 'struct', 'enum', 'trait', 'impl', 'module', 'object']);
+
+// Languages where a bare `name()` inside a method may call another method of the same class
+const IMPLICIT_THIS = new Set(['java', 'csharp', 'cpp', 'kotlin', 'scala', 'swift', 'dart', 'ruby']);
+
+const SELF_RECEIVERS = new Set(['this', 'self', 'Self', 'cls', '$this']);
+
+// Tokens that make `name(` a declaration or something other than a call
+const NOT_A_CALL = new Set(['function', 'def', 'fn', 'func', 'fun', 'class', 'struct', 'macro_rules']);
+
+const CONSTRUCTORS = new Set(['constructor', '__init__', 'initialize', 'init', 'new']);
+
+const MEMBER_ACCESS = new Set(['.', '?.', '::', '->']);
+
+function is(token, value) {
+  return Boolean(token) && token.value === value;
+}
+
+/**
+ * The receiver expression written before a call, e.g. `this`, `Foo`, `a.b`
+ * @returns {string|null} null for bare calls, '(...)' for computed receivers
+ */
+function receiverOf(tokens, nameIndex) {
+  const parts = [];
+  let i = nameIndex - 1;
+  while (i > 0 && MEMBER_ACCESS.has(tokens[i].value)) {
+    let previous = i - 1;
+    if (is(tokens[previous], '>') && tokens[i].value === '::') {
+      // Generic path segment: Vec::<T>::new, Vec<T>::new
+      let depth = 0;
+      for (; previous > 0; previous--) {
+        if (tokens[previous].value === '>') depth++;
+        else if (tokens[previous].value === '<' && --depth === 0) break;
+      }
+      previous--;
+      if (is(tokens[previous], '::')) {
+        i = previous;
+        continue;
+      }
+    }
+    const token = tokens[previous];
+    if (!token || (token.type !== 'identifier' && token.type !== 'keyword')) {
+      parts.unshift('(...)', '.');
+      break;
+    }
+    parts.unshift(token.value, tokens[i].value === '::' ? '::' : '.');
+    i = previous - 1;
+  }
+  return parts.length ? parts.slice(0, -1).join('') : null;
+}
+
+/**
+ * The token index of the `(` that opens a call at `i`, or -1
+ * Handles Rust turbofish calls (`parse::<u32>(...)`).
+ */
+function callOpen(tokens, i) {
+  const next = tokens[i + 1];
+  if (is(next, '(')) return i + 1;
+  if (is(next, '::') && is(tokens[i + 2], '<')) {
+    let depth = 0;
+    for (let k = i + 2; k < tokens.length && k < i + 100; k++) {
+      if (tokens[k].value === '<') depth++;
+      else if (tokens[k].value === '>') depth--;
+      else if (tokens[k].value === '>>') depth -= 2;
+      if (depth <= 0) return is(tokens[k + 1], '(') ? k + 1 : -1;
+    }
+  }
+  return -1;
+}
+
+// ---------------------------------------------------------------------------
+// Definitions
+// ---------------------------------------------------------------------------
+
+function lastSegment(name) {
+  const parts = String(name).split(/::|\./);
+  return parts[parts.length - 1];
+}
+
+/**
+ * Index the file's functions by short name, with the type each belongs to
+ */
+function indexFunctions(parsed) {
+  const { tokens, symbols, language } = parsed;
+  const byQualified = new Map(symbols.map((symbol) => [symbol.qualifiedName, symbol]));
+  const functions = [];
+  const seen = new Map();
+
+  for (const symbol of symbols) {
+    if (!FUNCTION_KINDS.has(symbol.kind)) continue;
+    const short = lastSegment(symbol.name);
+    const parent = byQualified.get(symbol.parent);
+    let owner = null;
+    if (symbol.receiver) owner = symbol.receiver;
+    else if (symbol.name.includes('::')) owner = symbol.name.slice(0, symbol.name.lastIndexOf('::'));
+    else if (TYPE_KINDS.has(parent?.kind)) owner = parent.name;
+
+    // Go methods name their receiver: func (s *Server) ... uses s like self
+    const selfNames = new Set(SELF_RECEIVERS);
+    if (language === 'go' && symbol.receiver && is(tokens[symbol.first + 1], '(')) {
+      selfNames.add(tokens[symbol.first + 2].value);
+    }
+
+    const count = (seen.get(symbol.qualifiedName) || 0) + 1;
+    seen.set(symbol.qualifiedName, count);
+    functions.push({
+      id: count > 1 ? `${symbol.qualifiedName}:${symbol.startLine}` : symbol.qualifiedName,
+      symbol,
+      short,
+      owner: owner ? lastSegment(owner) : null,
+      selfNames,
+      // C / C++ prototypes: calls resolve to the definition when the file has one
+      prototype: symbol.body == null && is(tokens[symbol.last], ';'),
+    });
+  }
+  // Duplicate names (overloads) all get line-qualified ids, not just the later ones
+  for (const fn of functions) {
+    if (seen.get(fn.symbol.qualifiedName) > 1 && fn.id === fn.symbol.qualifiedName) {
+      fn.id = `${fn.symbol.qualifiedName}:${fn.symbol.startLine}`;
+    }
+  }
+
+  const byName = new Map();
+  for (const fn of [...functions].sort((a, b) => a.prototype - b.prototype)) {
+    if (!byName.has(fn.short)) byName.set(fn.short, []);
+    byName.get(fn.short).push(fn);
+  }
+  const types = new Set(symbols.filter((symbol) => TYPE_KINDS.has(symbol.kind)).map((symbol) => symbol.name));
+  return { functions, byName, types };
+}
+
+/**
+ * The innermost function containing token `i`
+ */
+function enclosingFunction(functions, i) {
+  let best = null;
+  for (const fn of functions) {
+    const { first, last } = fn.symbol;
+    if (first <= i && i <= last && (!best || fn.symbol.first >= best.symbol.first)) best = fn;
+  }
+  return best;
+}
+
+function isWithin(fn, qualifiedName) {
+  const name = fn.symbol.qualifiedName;
+  return name === qualifiedName || name.startsWith(`${qualifiedName}.`);
+}
+
+/**
+ * Resolve a call to one of the file's functions
+ * @returns {object|null} The function entry, or null for an external call
+ */
+function resolveCall(index, language, caller, name, receiver, isNew) {
+  const { byName, types } = index;
+  const ownerOf = (type) => (byName.get(name) || []).filter((fn) => fn.owner === type);
+  const constructorOf = (type) => {
+    for (const fn of index.functions) {
+      if (fn.owner === type && (CONSTRUCTORS.has(fn.short) || fn.short === type)) return fn;
+    }
+    return null;
+  };
+
+  if (isNew) return types.has(name) ? constructorOf(name) : null;
+  if (receiver === null) {
+    if (types.has(name) && !byName.has(name)) return constructorOf(name); // Python / Kotlin: Foo()
+    const candidates = byName.get(name) || [];
+    if (caller?.owner && IMPLICIT_THIS.has(language)) {
+      const member = candidates.find((fn) => fn.owner === caller.owner);
+      if (member) return member;
+    }
+    // Free functions visible from the caller: top level, or nested in one of its enclosing functions
+    const visible = candidates.filter((fn) => {
+      if (fn.owner) return false;
+      const parent = fn.symbol.parent;
+      if (!parent) return true;
+      return Boolean(caller) && isWithin(caller, parent);
+    });
+    visible.sort((a, b) => b.symbol.depth - a.symbol.depth);
+    return visible[0] || null;
+  }
+
+  const last = lastSegment(receiver);
+  if (caller && caller.selfNames.has(receiver)) {
+    return caller.owner ? ownerOf(caller.owner)[0] || null : null;
+  }
+  if (types.has(last)) {
+    if (name === 'new' && language === 'ruby') return constructorOf(last);
+    return ownerOf(last)[0] || null;
+  }
+  return null;
+}
+
+// ---------------------------------------------------------------------------
+// Call graph
+// ---------------------------------------------------------------------------
+
+function addCall(map, key, entry, line) {
+  let existing = map.get(key);
+  if (!existing) {
+    existing = { ...entry, count: 0, lines: [] };
+    map.set(key, existing);
+  }
+  existing.count++;
+  if (existing.lines[existing.lines.length - 1] !== line) existing.lines.push(line);
+}
+
+/**
+ * Calls between the functions defined in a file, and the calls they make outside it
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias (see code-symbols for the supported set)
+ * @returns {object} { language, functions: [{ id, name, kind, startLine, endLine }],
+ *   edges: [{ caller, callee, count, lines }], external: [{ caller, callee, receiver, count, lines }] }
+ *   Ids are qualified names (`Class.method`, with `:line` appended for overloads); a null caller
+ *   is code outside any function.
+ */
+function extractCallGraph(content, language) {
+  const parsed = parseSymbols(content, language);
+  const { tokens } = parsed;
+  const index = indexFunctions(parsed);
+  const declared = new Set(parsed.symbols.map((symbol) => symbol.nameIndex).filter((i) => i !== undefined));
+
+  const edges = new Map();
+  const external = new Map();
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (token.type !== 'identifier' || declared.has(i)) continue;
+    if (callOpen(tokens, i) < 0) continue;
+    const previous = tokens[i - 1];
+    if (previous && NOT_A_CALL.has(previous.value)) continue;
+    if (is(previous, '@') && parsed.language !== 'python') continue; // Annotations, not decorators
+
+    const receiver = receiverOf(tokens, i);
+    const isNew = receiver === null && is(previous, 'new');
+    const caller = enclosingFunction(index.functions, i);
+    const callerId = caller ? caller.id : null;
+    const target = resolveCall(index, parsed.language, caller, token.value, receiver, isNew);
+    if (target) {
+      addCall(edges, `${callerId}\u0000${target.id}`, { caller: callerId, callee: target.id }, token.line);
+    } else {
+      const separator = is(previous, '::') ? '::' : '.';
+      const callee = receiver ? `${receiver}${separator}${token.value}` : token.value;
+      addCall(external, `${callerId}\u0000${callee}`, { caller: callerId, callee, receiver }, token.line);
+    }
+  }
+
+  return {
+    language: parsed.language,
+    functions: index.functions.map((fn) => ({
+      id: fn.id,
+      name: fn.symbol.name,
+      kind: fn.symbol.kind,
+      startLine: fn.symbol.startLine,
+      endLine: fn.symbol.endLine,
+    })),
+    edges: [...edges.values()],
+    external: [...external.values()],
+  };
+}
+
+module.exports = instrument('CODE', {
+  extractCallGraph,
+});
@@ -0,0 +1,480 @@
+/**
+ * Code Dependencies
+ * Per-file imports, and a dependency graph over a set of captured files:
+ * which file imports which, what each pulls in from outside the set, and
+ * the import cycles.
+ *
+ * Resolution works on the file set itself (paths and contents, as captured),
+ * never the disk, so it gives the same answer when replaying a trace:
+ *   JS / TS     relative specifiers with extension and index probing,
+ *               tsconfig / jsconfig `paths` aliases and `baseUrl`
+ *   Python      relative (`from ..pkg import x`) and absolute module paths
+ *   Rust        `mod name;` declarations and `crate::` / `super::` / `self::` paths
+ *   Go          package import paths under the module named in go.mod
+ *   C / C++     quoted #include, relative to the including file first
+ *   Ruby, PHP, Dart: relative requires; Java / Kotlin / Scala: class imports
+ * Anything that doesn't resolve to a file in the set is reported as external.
+ */
+
+const path = require('path').posix;
+const { parseSymbols, extractSymbols } = require('./code-symbols');
+const { getSyntax, stripComments } = require('./code-lexer');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  paths: null, // TypeScript-style aliases, e.g. { '@/*': ['src/*'] }; read from tsconfig.json when null
+  baseUrl: null, // Directory `paths` targets (and bare specifiers) are relative to
+};
+
+const JS_EXTENSIONS = ['.ts', '.tsx', '.d.ts', '.js', '.jsx', '.mjs', '.cjs', '.mts', '.cts', '.json'];
+const JS_INDEX = JS_EXTENSIONS.filter((ext) => ext !== '.json').map((ext) => `/index${ext}`);
+const CLASS_EXTENSIONS = { java: ['.java'], kotlin: ['.kt', '.kts'], scala: ['.scala'] };
+const CRATE_ROOTS = ['lib.rs', 'main.rs'];
+
+function normalizePath(filePath) {
+  const normalized = path.normalize(String(filePath).replace(/\\/g, '/'));
+  return normalized.startsWith('./') ? normalized.slice(2) : normalized;
+}
+
+function languageOf(filePath) {
+  const ext = path.extname(filePath).slice(1).toLowerCase();
+  return ext ? getSyntax(ext).language : 'unknown';
+}
+
+// ---------------------------------------------------------------------------
+// Imports
+// ---------------------------------------------------------------------------
+
+/**
+ * Imports of one file, including Rust `mod name;` declarations
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias
+ * @returns {Array<object>} [{ source, names, line, ... }], as in code-symbols.extractSymbols; Rust module
+ *   declarations have module: true
+ */
+function extractImports(content, language) {
+  const { imports } = extractSymbols(content, language);
+  if (getSyntax(language).language !== 'rust') return imports;
+
+  // `mod name;` loads name.rs / name/mod.rs; an inline `mod name { ... }` doesn't
+  const { tokens, symbols } = parseSymbols(content, language);
+  for (const symbol of symbols) {
+    if (symbol.kind !== 'module' || symbol.depth !== 0 || tokens[symbol.last]?.value !== ';') continue;
+    imports.push({ source: symbol.name, names: [symbol.name], line: symbol.startLine, module: true });
+  }
+  return imports.sort((a, b) => a.line - b.line);
+}
+
+// ---------------------------------------------------------------------------
+// Resolution
+// ---------------------------------------------------------------------------
+
+/**
+ * The file set, indexed for exact and suffix lookups
+ */
+function createIndex(files) {
+  const byPath = new Map(files.map((file) => [file.path, file]));
+  const bySuffix = new Map(); // 'b/c.py' -> ['a/b/c.py', ...] for every directory suffix
+  for (const file of files) {
+    const parts = file.path.split('/');
+    for (let i = 0; i < parts.length; i++) {
+      const suffix = parts.slice(i).join('/');
+      if (!bySuffix.has(suffix)) bySuffix.set(suffix, []);
+      bySuffix.get(suffix).push(file.path);
+    }
+  }
+  return { byPath, bySuffix };
+}
+
+function firstExisting(index, candidates) {
+  return candidates.find((candidate) => index.byPath.has(candidate)) || null;
+}
+
+/**
+ * A file whose path ends with `suffix` at a directory boundary, preferring the
+ * one closest to the importing file
+ */
+function bySuffix(index, suffix, from) {
+  const matches = index.bySuffix.get(suffix);
+  if (!matches) return null;
+  if (matches.length === 1) return matches[0];
+  const shared = (other) => {
+    const a = from.split('/');
+    const b = other.split('/');
+    let n = 0;
+    while (n < a.length && n < b.length && a[n] === b[n]) n++;
+    return n;
+  };
+  return [...matches].sort((a, b) => shared(b) - shared(a) || a.length - b.length)[0];
+}
+
+function probeScript(index, base) {
+  const stripped = base.replace(/\.(m|c)?js$/, '');
+  return firstExisting(index, [
+    base,
+    ...JS_EXTENSIONS.map((ext) => base + ext),
+    // ESM TypeScript imports name the compiled file: './util.js' means util.ts
+    ...(stripped !== base ? JS_EXTENSIONS.map((ext) => stripped + ext) : []),
+    ...JS_INDEX.map((index) => base + index),
+  ]);
+}
+
+function parseJsonWithComments(content) {
+  try {
+    const json = stripComments(content, 'javascript').replace(/,(\s*[}\]])/g, '$1');
+    return JSON.parse(json);
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * `paths` / `baseUrl` from tsconfig.json or jsconfig.json in the file set, unless given
+ */
+function loadAliases(files, options) {
+  if (options.paths || options.baseUrl) {
+    return [{ dir: '', paths: options.paths || {}, baseUrl: options.baseUrl }];
+  }
+  const configs = [];
+  for (const file of files) {
+    const name = path.basename(file.path);
+    if (!/^(tsconfig|jsconfig)(\..+)?\.json$/.test(name)) continue;
+    const compilerOptions = parseJsonWithComments(file.content)?.compilerOptions;
+    if (!compilerOptions || (!compilerOptions.paths && !compilerOptions.baseUrl)) continue;
+    const dir = path.dirname(file.path) === '.' ? '' : path.dirname(file.path);
+    configs.push({ dir, paths: compilerOptions.paths || {}, baseUrl: compilerOptions.baseUrl || null });
+  }
+  // Deepest config first: it applies to the files under it
+  return configs.sort((a, b) => b.dir.length - a.dir.length);
+}
+
+function resolveScript(index, aliases, from, source) {
+  if (source.startsWith('.')) return probeScript(index, path.join(path.dirname(from), source));
+  if (source.startsWith('/')) return probeScript(index, source.slice(1));
+
+  for (const config of aliases) {
+    if (config.dir && !from.startsWith(`${config.dir}/`)) continue;
+    const base = path.join(config.dir, config.baseUrl || '.');
+    for (const [pattern, targets] of Object.entries(config.paths)) {
+      const star = pattern.indexOf('*');
+      const prefix = star >= 0 ? pattern.slice(0, star) : pattern;
+      const suffix = star >= 0 ? pattern.slice(star + 1) : '';
+      const matched = star >= 0 ? source.startsWith(prefix) && source.endsWith(suffix) : source === pattern;
+      if (!matched || source.length < prefix.length + suffix.length) continue;
+      const wildcard = star >= 0 ? source.slice(prefix.length, source.length - suffix.length) : '';
+      for (const target of [].concat(targets)) {
+        const resolved = probeScript(index, normalizePath(path.join(base, target.replace('*', wildcard))));
+        if (resolved) return resolved;
+      }
+    }
+    if (config.baseUrl) {
+      const resolved = probeScript(index, normalizePath(path.join(base, source)));
+      if (resolved) return resolved;
+    }
+  }
+  return null;
+}
+
+function resolvePython(index, from, entry) {
+  const { source } = entry;
+  const candidates = (module) => [`${module}.py`, `${module}.pyi`, `${module}/__init__.py`];
+  if (source.startsWith('.')) {
+    const level = source.match(/^\.+/)[0].length;
+    let dir = path.dirname(from);
+    for (let i = 1; i < level; i++) dir = path.dirname(dir);
+    const rest = source.slice(level).split('.').filter(Boolean).join('/');
+    const module = normalizePath(path.join(dir, rest || '.'));
+    // `from . import name` may name a submodule rather than something in __init__
+    if (!rest && entry.names.length) {
+      const submodules = entry.names.map((name) => firstExisting(index, candidates(path.join(module, name))));
+      const found = submodules.filter(Boolean);
+      if (found.length) return found;
+    }
+    return firstExisting(index, candidates(module === '.' ? '__init__' : module).map(normalizePath));
+  }
+  const module = source.replace(/\./g, '/');
+  for (const candidate of candidates(module)) {
+    const resolved = bySuffix(index, candidate, from);
+    if (resolved) return resolved;
+  }
+  return null;
+}
+
+/**
+ * The directory a Rust file's child modules live in: src/lib.rs -> src,
+ * src/net.rs -> src/net, src/net/mod.rs -> src/net
+ */
+function rustModuleDir(file) {
+  const base = path.basename(file);
+  const dir = path.dirname(file);
+  if (base === 'mod.rs' || CRATE_ROOTS.includes(base)) return dir;
+  return path.join(dir, base.slice(0, -3));
+}
+
+function rustModuleFile(index, dir, name) {
+  const candidates = [path.join(dir, `${name}.rs`), path.join(dir, name, 'mod.rs')];
+  return firstExisting(index, candidates.map(normalizePath));
+}
+
+/**
+ * The file that defines the module whose children live in `dir`
+ */
+function rustDirModule(index, dir) {
+  const candidates = [path.join(dir, 'mod.rs'), ...CRATE_ROOTS.map((name) => path.join(dir, name))];
+  candidates.push(`${dir}.rs`);
+  return firstExisting(index, candidates.map(normalizePath));
+}
+
+function resolveRust(index, from, entry) {
+  if (entry.module) return rustModuleFile(index, rustModuleDir(from), entry.source);
+
+  const segments = entry.source.split('::');
+  let dir;
+  if (segments[0] === 'crate') {
+    // The crate root is the nearest lib.rs / main.rs above the file
+    for (let at = path.dirname(from); !dir; at = path.dirname(at)) {
+      const root = firstExisting(index, CRATE_ROOTS.map((name) => normalizePath(path.join(at, name))));
+      if (root) dir = path.dirname(root);
+      else if (at === '.' || at === '/') return null;
+    }
+  } else if (segments[0] === 'self') {
+    dir = rustModuleDir(from);
+  } else if (segments[0] === 'super') {
+    dir = path.dirname(rustModuleDir(from));
+    while (segments[1] === 'super') {
+      segments.shift();
+      dir = path.dirname(dir);
+    }
+  } else {
+    return null; // Another crate
+  }
+
+  // The deepest module file along the path; the remaining segments are items inside it
+  let resolved = segments[0] === 'self' ? from : rustDirModule(index, dir);
+  for (const segment of [...segments.slice(1), ...entry.names.filter((name) => name !== 'self')]) {
+    const file = rustModuleFile(index, dir, segment);
+    if (!file) break;
+    resolved = file;
+    dir = path.join(dir, segment);
+  }
+  return resolved;
+}
+
+function goModules(files) {
+  const modules = [];
+  for (const file of files) {
+    if (path.basename(file.path) !== 'go.mod') continue;
+    const match = /^\s*module\s+(\S+)/m.exec(file.content);
+    if (match) modules.push({ name: match[1], dir: path.dirname(file.path) });
+  }
+  return modules;
+}
+
+function resolveGo(index, modules, entry) {
+  for (const module of modules) {
+    if (entry.source !== module.name && !entry.source.startsWith(`${module.name}/`)) continue;
+    const dir = normalizePath(path.join(module.dir, entry.source.slice(module.name.length)));
+    const files = [];
+    for (const file of index.byPath.keys()) {
+      if (path.dirname(file) === dir && file.endsWith('.go') && !file.endsWith('_test.go')) files.push(file);
+    }
+    return files.length ? files : null;
+  }
+  return null;
+}
+
+/**
+ * Resolve one import to files in the set
+ * @returns {Array<string>} Resolved paths (empty when external; Go packages resolve to every file)
+ */
+function resolveImport(context, file, entry) {
+  const { index } = context;
+  const from = file.path;
+  const source = entry.source;
+  let resolved = null;
+  switch (file.language) {
+    case 'javascript':
+    case 'typescript':
+      resolved = resolveScript(index, context.aliases, from, source);
+      break;
+    case 'python':
+      resolved = resolvePython(index, from, entry);
+      break;
+    case 'rust':
+      resolved = resolveRust(index, from, entry);
+      break;
+    case 'go':
+      resolved = resolveGo(index, context.goModules, entry);
+      break;
+    case 'c':
+    case 'cpp':
+      if (!entry.system) {
+        const local = normalizePath(path.join(path.dirname(from), source));
+        resolved = firstExisting(index, [local]) || bySuffix(index, normalizePath(source), from);
+      }
+      break;
+    case 'ruby': {
+      const relative = source.startsWith('.') || entry.relative;
+      const target = source.endsWith('.rb') ? source : `${source}.rb`;
+      resolved = relative
+        ? firstExisting(index, [normalizePath(path.join(path.dirname(from), target))])
+        : bySuffix(index, normalizePath(target), from);
+      break;
+    }
+    case 'php':
+    case 'dart':
+      if (source.includes('/') || source.includes('.')) {
+        resolved = firstExisting(index, [normalizePath(path.join(path.dirname(from), source))]);
+      }
+      if (!resolved && file.language === 'php' && source.includes('\\')) {
+        resolved = bySuffix(index, `${source.replace(/\\/g, '/')}.php`, from);
+      }
+      break;
+    case 'java':
+    case 'kotlin':
+    case 'scala':
+      for (const ext of CLASS_EXTENSIONS[file.language]) {
+        resolved = resolved || bySuffix(index, `${source.replace(/\./g, '/')}${ext}`, from);
+      }
+      break;
+    default:
+      break;
+  }
+  return resolved ? [].concat(resolved) : [];
+}
+
+// ---------------------------------------------------------------------------
+// Graph
+// ---------------------------------------------------------------------------
+
+/**
+ * Strongly connected components with more than one file (Tarjan, iterative)
+ */
+function findCycles(nodes, adjacency) {
+  const indexOf = new Map();
+  const lowLink = new Map();
+  const onStack = new Set();
+  const stack = [];
+  const cycles = [];
+  let counter = 0;
+
+  for (const start of nodes) {
+    if (indexOf.has(start)) continue;
+    const work = [[start, 0]];
+    indexOf.set(start, counter);
+    lowLink.set(start, counter++);
+    stack.push(start);
+    onStack.add(start);
+
+    while (work.length) {
+      const frame = work[work.length - 1];
+      const [node, next] = frame;
+      const neighbors = adjacency.get(node) || [];
+      if (next < neighbors.length) {
+        frame[1]++;
+        const neighbor = neighbors[next];
+        if (!indexOf.has(neighbor)) {
+          indexOf.set(neighbor, counter);
+          lowLink.set(neighbor, counter++);
+          stack.push(neighbor);
+          onStack.add(neighbor);
+          work.push([neighbor, 0]);
+        } else if (onStack.has(neighbor)) {
+          lowLink.set(node, Math.min(lowLink.get(node), indexOf.get(neighbor)));
+        }
+        continue;
+      }
+      work.pop();
+      if (work.length) {
+        const parent = work[work.length - 1][0];
+        lowLink.set(parent, Math.min(lowLink.get(parent), lowLink.get(node)));
+      }
+      if (lowLink.get(node) === indexOf.get(node)) {
+        const component = [];
+        let member;
+        do {
+          member = stack.pop();
+          onStack.delete(member);
+          component.push(member);
+        } while (member !== node);
+        if (component.length > 1) cycles.push(component.sort());
+      }
+    }
+  }
+  return cycles;
+}
+
+/**
+ * Build the dependency graph of a set of files
+ * @param {Array<object>|object} files - [{ path, content, language? }] or { path: content }
+ * @param {object} options - { paths, baseUrl }
+ * @returns {object} { nodes: [{ id, language, imports, dependents, external: [source] }],
+ *   edges: [{ from, to, source, line }], cycles: [[path, ...]], unresolved: [{ from, source, line }] }
+ *   unresolved lists relative imports (./x, ../x, from .x) that point at files not in the set.
+ */
+function buildDependencyGraph(files, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const entries = Array.isArray(files)
+    ? files
+    : Object.entries(files || {}).map(([filePath, content]) => ({ path: filePath, content }));
+  const list = entries
+    .filter((file) => file && file.path)
+    .map((file) => {
+      const filePath = normalizePath(file.path);
+      const language = file.language ? getSyntax(file.language).language : languageOf(filePath);
+      return { path: filePath, content: String(file.content || ''), language };
+    });
+
+  const context = {
+    index: createIndex(list),
+    aliases: loadAliases(list, opts),
+    goModules: goModules(list),
+  };
+
+  const nodes = new Map();
+  const edges = [];
+  const unresolved = [];
+  const adjacency = new Map();
+  for (const file of list) {
+    nodes.set(file.path, { id: file.path, language: file.language, imports: 0, dependents: 0, external: [] });
+    adjacency.set(file.path, []);
+  }
+
+  for (const file of list) {
+    if (file.language === 'unknown') continue;
+    const node = nodes.get(file.path);
+    for (const entry of extractImports(file.content, file.language)) {
+      const targets = resolveImport(context, file, entry);
+      if (!targets.length) {
+        const relative = file.language === 'python' ? /^\./ : /^\.\.?(\/|$)/;
+        if (relative.test(entry.source)) {
+          unresolved.push({ from: file.path, source: entry.source, line: entry.line });
+        } else if (!node.external.includes(entry.source)) {
+          node.external.push(entry.source);
+        }
+        continue;
+      }
+      for (const target of targets) {
+        // One edge per file pair; `self::` paths resolve to the importing file itself
+        if (target === file.path || adjacency.get(file.path).includes(target)) continue;
+        adjacency.get(file.path).push(target);
+        edges.push({ from: file.path, to: target, source: entry.source, line: entry.line });
+        node.imports++;
+        nodes.get(target).dependents++;
+      }
+    }
+  }
+
+  return {
+    nodes: [...nodes.values()],
+    edges,
+    cycles: findCycles([...nodes.keys()], adjacency),
+    unresolved,
+  };
+}
+
+module.exports = instrument('CODE', {
+  extractImports,
+  buildDependencyGraph,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,243 @@
+/**
+ * Code Duplication
+ * Duplicated blocks across a set of files (or a snapshot of a workspace),
+ * grouped by content with each occurrence's file and line range, so a
+ * trace can measure whether duplication grows over time.
+ *
+ * Token-based, so formatting and comments don't matter; with
+ * normalizeIdentifiers, identifiers and literals are abstracted too and a
+ * copy-pasted block with renamed variables still matches. Candidate matches
+ * come from winnowing (Schleimer et al., "Winnowing: Local Algorithms for
+ * Document Fingerprinting"): every k-gram of tokens is hashed and the minimum
+ * hash in each window of w consecutive k-grams is kept as a fingerprint, which
+ * guarantees any shared run of k + w - 1 tokens is found while storing a
+ * fraction of the hashes. Shared fingerprints are then verified and extended
+ * token by token to the full duplicated run.
+ */
+
+const path = require('path').posix;
+const { tokenize, getSyntax } = require('./code-lexer');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  minLines: 6, // Shortest duplicate reported, in lines
+  minTokens: 50, // ... and in tokens, so six lines of `}` don't count
+  kgram: 20, // Tokens per hashed k-gram (noise threshold)
+  window: 8, // k-grams per winnowing window; runs of kgram + window - 1 tokens are always found
+  normalizeIdentifiers: true, // Match blocks that differ only in names and literal values
+};
+
+// Fingerprints shared by more places than this are boilerplate (long runs of `}` / `end`); comparing
+// every pair of them would be quadratic
+const MAX_POSTINGS = 256;
+
+const LITERAL_TYPES = new Set(['identifier', 'number', 'string']);
+
+function languageOf(filePath) {
+  const ext = path.extname(filePath).slice(1).toLowerCase();
+  return ext ? getSyntax(ext).language : 'unknown';
+}
+
+function lastLine(token) {
+  return token.line + (token.value.match(/\n/g) || []).length;
+}
+
+/**
+ * Tokens as small integers, abstracted per options
+ */
+function encode(tokens, vocabulary, normalize) {
+  const ids = new Int32Array(tokens.length);
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+    const key = normalize && LITERAL_TYPES.has(token.type) ? `\u0000${token.type}` : token.value;
+    let id = vocabulary.get(key);
+    if (id === undefined) {
+      id = vocabulary.size + 1;
+      vocabulary.set(key, id);
+    }
+    ids[i] = id;
+  }
+  return ids;
+}
+
+/**
+ * Winnowed fingerprints: [{ hash, position }] with position the k-gram's first token
+ */
+function winnow(ids, k, w) {
+  const count = ids.length - k + 1;
+  if (count <= 0) return [];
+  const hashes = new Uint32Array(count);
+  // Polynomial rolling hash over the k-gram
+  const BASE = 0x01000193;
+  let power = 1;
+  for (let i = 0; i < k - 1; i++) power = Math.imul(power, BASE);
+  let hash = 0;
+  for (let i = 0; i < k; i++) hash = (Math.imul(hash, BASE) + ids[i]) | 0;
+  hashes[0] = hash >>> 0;
+  for (let i = 1; i < count; i++) {
+    hash = (hash - Math.imul(ids[i - 1], power)) | 0;
+    hash = (Math.imul(hash, BASE) + ids[i + k - 1]) | 0;
+    hashes[i] = hash >>> 0;
+  }
+
+  const fingerprints = [];
+  let previous = -1;
+  const windows = Math.max(1, count - w + 1);
+  for (let start = 0; start < windows; start++) {
+    // Rightmost minimum in the window, recorded once per position
+    let min = start;
+    for (let i = start + 1; i < Math.min(count, start + w); i++) {
+      if (hashes[i] <= hashes[min]) min = i;
+    }
+    if (min !== previous) {
+      fingerprints.push({ hash: hashes[min], position: min });
+      previous = min;
+    }
+  }
+  return fingerprints;
+}
+
+function sameRun(a, i, b, j, length) {
+  for (let n = 0; n < length; n++) if (a[i + n] !== b[j + n]) return false;
+  return true;
+}
+
+function runKey(ids, start, length) {
+  // Content key for grouping: FNV-1a over the run, plus its length
+  let hash = 0x811c9dc5;
+  for (let n = 0; n < length; n++) hash = Math.imul(hash ^ ids[start + n], 0x01000193);
+  return `${length}:${(hash >>> 0).toString(16)}`;
+}
+
+/**
+ * Find duplicated code blocks across files
+ * @param {Array<object>|object} files - [{ path, content, language? }] or { path: content }
+ * @param {number|object} minLines - Shortest block reported, or an options object (see DEFAULT_OPTIONS)
+ * @returns {object} { groups: [{ lines, tokens, occurrences: [{ file, startLine, endLine }] }],
+ *   stats: { files, totalLines, duplicatedLines, duplication (percent of lines in some duplicate) } }
+ *   Groups are sorted by size; a block repeated within one file has several occurrences in it.
+ */
+function findDuplicateBlocks(files, minLines = DEFAULT_OPTIONS.minLines, options = {}) {
+  const opts =
+    typeof minLines === 'object' && minLines !== null
+      ? { ...DEFAULT_OPTIONS, ...minLines }
+      : { ...DEFAULT_OPTIONS, ...options, minLines };
+  const k = Math.max(1, Math.min(opts.kgram, opts.minTokens));
+  const w = Math.max(1, opts.window);
+
+  const entries = Array.isArray(files)
+    ? files
+    : Object.entries(files || {}).map(([filePath, content]) => ({ path: filePath, content }));
+  const vocabulary = new Map();
+  const sources = entries
+    .filter((file) => file && file.path)
+    .map((file) => {
+      const content = String(file.content || '');
+      const tokens = tokenize(content, file.language || languageOf(file.path));
+      return {
+        path: file.path,
+        tokens,
+        ids: encode(tokens, vocabulary, opts.normalizeIdentifiers),
+        lines: content ? content.split('\n').length : 0,
+      };
+    });
+
+  // Fingerprint index: hash -> [[source, position], ...]
+  const index = new Map();
+  sources.forEach((source, s) => {
+    for (const { hash, position } of winnow(source.ids, k, w)) {
+      if (!index.has(hash)) index.set(hash, []);
+      index.get(hash).push([s, position]);
+    }
+  });
+
+  // Verify and extend every shared fingerprint to a maximal run, once per diagonal
+  const covered = new Map(); // 'a:b:offset' -> token index in a the diagonal is extended to
+  const groups = new Map();
+  const addOccurrence = (key, s, start, length) => {
+    const source = sources[s];
+    if (!groups.has(key)) groups.set(key, { tokens: length, occurrences: new Map() });
+    const first = source.tokens[start];
+    const last = source.tokens[start + length - 1];
+    groups.get(key).occurrences.set(`${s}:${start}`, {
+      file: source.path,
+      startLine: first.line,
+      endLine: lastLine(last),
+    });
+  };
+
+  for (const postings of index.values()) {
+    if (postings.length < 2 || postings.length > MAX_POSTINGS) continue;
+    for (let x = 0; x < postings.length; x++) {
+      for (let y = x + 1; y < postings.length; y++) {
+        let [a, i] = postings[x];
+        let [b, j] = postings[y];
+        if (a > b || (a === b && i > j)) [a, i, b, j] = [b, j, a, i];
+        const A = sources[a].ids;
+        const B = sources[b].ids;
+        const diagonal = `${a}:${b}:${i - j}`;
+        if ((covered.get(diagonal) ?? -1) > i) continue;
+        if (!sameRun(A, i, B, j, k)) continue; // Hash collision
+
+        // Extend; within one file the copies mustn't overlap
+        let start = 0;
+        while (i - start > 0 && j - start > 0 && A[i - start - 1] === B[j - start - 1]) start++;
+        let length = start + k;
+        const limit = a === b ? j - (i - start) : Infinity;
+        while (i - start + length < A.length && j - start + length < B.length && length < limit) {
+          if (A[i - start + length] !== B[j - start + length]) break;
+          length++;
+        }
+        length = Math.min(length, limit);
+        covered.set(diagonal, i - start + length);
+        if (length < opts.minTokens) continue;
+
+        const from = sources[a].tokens[i - start];
+        const to = sources[a].tokens[i - start + length - 1];
+        if (lastLine(to) - from.line + 1 < opts.minLines) continue;
+        const key = runKey(A, i - start, length);
+        addOccurrence(key, a, i - start, length);
+        addOccurrence(key, b, j - start, length);
+      }
+    }
+  }
+
+  const result = [...groups.values()]
+    .map((group) => {
+      const occurrences = [...group.occurrences.values()].sort(
+        (p, q) => (p.file < q.file ? -1 : p.file > q.file ? 1 : p.startLine - q.startLine)
+      );
+      return {
+        lines: Math.max(...occurrences.map((o) => o.endLine - o.startLine + 1)),
+        tokens: group.tokens,
+        occurrences,
+      };
+    })
+    .sort((p, q) => q.tokens * q.occurrences.length - p.tokens * p.occurrences.length);
+
+  // Lines inside at least one occurrence, per file
+  const duplicated = new Map();
+  for (const group of result) {
+    for (const occurrence of group.occurrences) {
+      if (!duplicated.has(occurrence.file)) duplicated.set(occurrence.file, new Set());
+      const lines = duplicated.get(occurrence.file);
+      for (let line = occurrence.startLine; line <= occurrence.endLine; line++) lines.add(line);
+    }
+  }
+  const totalLines = sources.reduce((sum, source) => sum + source.lines, 0);
+  const duplicatedLines = [...duplicated.values()].reduce((sum, lines) => sum + lines.size, 0);
+  return {
+    groups: result,
+    stats: {
+      files: sources.length,
+      totalLines,
+      duplicatedLines,
+      duplication: totalLines ? Math.round((duplicatedLines / totalLines) * 10000) / 100 : 0,
+    },
+  };
+}
+
+module.exports = instrument('CODE', {
+  findDuplicateBlocks,
+  DEFAULT_OPTIONS,
+});
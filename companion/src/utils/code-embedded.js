@@ -0,0 +1,325 @@
+/**
+ * Embedded Languages
+ * Finds code in another language inside a host file - fenced code blocks and
+ * front matter in Markdown, <script>/<style> elements in HTML and templates,
+ * top-level blocks of Vue/Svelte/Astro components - with byte ranges, so
+ * stats and symbol extraction can run on each region in its own language.
+ *
+ * Languages come from the region's own tag (fence info string, lang=, type=)
+ * resolved through code-language; elements without one get the host's
+ * default (JavaScript for <script>, CSS for <style>, HTML for a Vue
+ * <template>). Untagged fences are guessed from their content, and stay
+ * null when nothing is confident. <script>/<style> inside HTML comments
+ * don't count. Regions aren't searched recursively: a ```html fence in
+ * Markdown is one html region, not an html region plus its scripts.
+ */
+
+const { extractCodeBlocks, parseInfoString } = require('./code-blocks');
+const { detectLanguages, resolveLanguageName } = require('./code-language');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  includeEmpty: false, // Report regions with only whitespace (e.g. <script src=...></script>)
+  guessUntagged: true, // Guess the language of untagged fences from their content
+};
+
+const MARKDOWN_HOSTS = new Set(['markdown', 'mdx', 'rmarkdown']);
+const SFC_HOSTS = new Set(['vue']);
+const FRONTMATTER_HOSTS = new Set(['markdown', 'mdx', 'rmarkdown', 'astro']);
+const ELEMENT_HOSTS = new Set([
+  'html',
+  'xml',
+  'svg',
+  'svelte',
+  'astro',
+  'handlebars',
+  'jinja',
+  'twig',
+  'ejs',
+  'erb',
+  'liquid',
+  'razor',
+  'php',
+]);
+
+// <script type="..."> values that aren't a language name
+const SCRIPT_TYPES = {
+  module: 'javascript',
+  'text/javascript': 'javascript',
+  'application/javascript': 'javascript',
+  'text/ecmascript': 'javascript',
+  'text/babel': 'javascript',
+  'text/jsx': 'javascript',
+  'text/typescript': 'typescript',
+  'application/typescript': 'typescript',
+  importmap: 'json',
+  speculationrules: 'json',
+  'application/json': 'json',
+  'application/ld+json': 'json',
+  'application/manifest+json': 'json',
+  'text/x-template': 'html',
+  'text/template': 'html',
+  'text/html': 'html',
+  'text/ng-template': 'html',
+  'text/x-handlebars-template': 'handlebars',
+  'text/x-handlebars': 'handlebars',
+  'text/markdown': 'markdown',
+  'text/coffeescript': 'coffeescript',
+};
+
+// Vue custom blocks conventionally written without lang=
+const SFC_BLOCK_DEFAULTS = {
+  template: 'html',
+  script: 'javascript',
+  style: 'css',
+  i18n: 'json',
+  docs: 'markdown',
+};
+
+const TAG_OR_COMMENT = /<!--[\s\S]*?(?:-->|$)|<([a-zA-Z][\w:-]*)((?:"[^"]*"|'[^']*'|[^>"'])*)>/g;
+const ELEMENT_OR_COMMENT = /<!--[\s\S]*?(?:-->|$)|<(script|style)\b((?:"[^"]*"|'[^']*'|[^>"'])*)>/gi;
+const ATTRIBUTE = /([^\s=/>"']+)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>"']+)))?/g;
+
+function parseAttributes(source) {
+  const attributes = {};
+  for (const match of source.matchAll(ATTRIBUTE)) {
+    attributes[match[1].toLowerCase()] = match[2] ?? match[3] ?? match[4] ?? true;
+  }
+  return attributes;
+}
+
+/**
+ * Character index -> UTF-8 byte offset and 1-based line, for increasing indexes
+ */
+function createLocator(text) {
+  let index = 0;
+  let bytes = 0;
+  let line = 1;
+  return (target) => {
+    if (target < index) {
+      index = 0;
+      bytes = 0;
+      line = 1;
+    }
+    const slice = text.slice(index, target);
+    bytes += Buffer.byteLength(slice, 'utf8');
+    for (let i = slice.indexOf('\n'); i !== -1; i = slice.indexOf('\n', i + 1)) line++;
+    index = target;
+    return { bytes, line };
+  };
+}
+
+/**
+ * Language of a <script>/<style>/custom block from its attributes
+ * @returns {object} { language, languageSource }
+ */
+function elementLanguage(tag, attributes) {
+  if (typeof attributes.lang === 'string') {
+    return { language: resolveLanguageName(attributes.lang), languageSource: 'attribute' };
+  }
+  if (typeof attributes.type === 'string' && attributes.type.trim()) {
+    const type = attributes.type.trim().toLowerCase();
+    // Otherwise the subtype names the language: text/less, text/x-scss, ...
+    const subtype = type.split('/').pop().replace(/^x-/, '');
+    const language = (tag === 'script' && SCRIPT_TYPES[type]) || resolveLanguageName(subtype);
+    return { language, languageSource: 'attribute' };
+  }
+  const language = SFC_BLOCK_DEFAULTS[tag] || null;
+  return { language, languageSource: language ? 'default' : null };
+}
+
+/**
+ * End of an element's content: the first closing tag for raw-text elements
+ * (script, style), the matching one otherwise
+ * @returns {object} { contentEnd, end, closed } as character indexes
+ */
+function findClose(text, tag, from) {
+  const name = tag.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+  const raw = tag === 'script' || tag === 'style';
+  const pattern = raw
+    ? new RegExp(`</${name}\\s*>`, 'gi')
+    : new RegExp(`<(/?)${name}\\b((?:"[^"]*"|'[^']*'|[^>"'])*)>`, 'g');
+  pattern.lastIndex = from;
+  let depth = 0;
+  for (let match = pattern.exec(text); match; match = pattern.exec(text)) {
+    if (!raw && !match[1]) {
+      if (!match[2].trim().endsWith('/')) depth++;
+      continue;
+    }
+    if (depth > 0) {
+      depth--;
+      continue;
+    }
+    return { contentEnd: match.index, end: match.index + match[0].length, closed: true };
+  }
+  return { contentEnd: text.length, end: text.length, closed: false };
+}
+
+/**
+ * Elements in the host: <script>/<style> anywhere, or (topLevel) every top-level block
+ */
+function elementRegions(text, topLevel) {
+  const regions = [];
+  const source = topLevel ? TAG_OR_COMMENT : ELEMENT_OR_COMMENT;
+  const pattern = new RegExp(source.source, source.flags);
+  for (let match = pattern.exec(text); match; match = pattern.exec(text)) {
+    if (!match[1] || match[2].trim().endsWith('/')) continue; // Comment or self-closing
+    const tag = match[1].toLowerCase();
+    const attributes = parseAttributes(match[2]);
+    const contentStart = match.index + match[0].length;
+    const close = findClose(text, topLevel ? match[1] : tag, contentStart);
+    regions.push({
+      kind: tag === 'script' || tag === 'style' ? tag : 'block',
+      tag,
+      attributes,
+      ...elementLanguage(tag, attributes),
+      start: match.index,
+      contentStart,
+      contentEnd: close.contentEnd,
+      end: close.end,
+      closed: close.closed,
+    });
+    pattern.lastIndex = close.end;
+  }
+  return regions;
+}
+
+/**
+ * Front matter fenced by --- (YAML, or the component script in Astro) or +++ (TOML) on the first line
+ */
+function frontMatterRegion(text, host) {
+  const match = /^(---|\+\+\+)[ \t]*(\w*)[ \t]*\r?\n/.exec(text);
+  if (!match) return null;
+  const fence = match[1];
+  const close = new RegExp(`^${fence.replace(/\+/g, '\\+')}[ \\t]*\\r?$`, 'm');
+  const rest = text.slice(match[0].length);
+  const closing = close.exec(rest);
+  if (!closing) return null;
+  const contentStart = match[0].length;
+  const contentEnd = contentStart + closing.index;
+  let language = fence === '+++' ? 'toml' : host === 'astro' ? 'typescript' : 'yaml';
+  if (match[2]) language = resolveLanguageName(match[2]) || language; // ---json
+  return {
+    kind: 'frontmatter',
+    tag: fence,
+    attributes: {},
+    language,
+    languageSource: match[2] ? 'tag' : 'default',
+    start: 0,
+    contentStart,
+    // The newline before the closing fence belongs to the fence
+    contentEnd: Math.max(contentStart, contentEnd - (rest[closing.index - 1] === '\n' ? 1 : 0)),
+    end: contentEnd + closing[0].length,
+    closed: true,
+  };
+}
+
+/**
+ * Fence info language; R Markdown chunks are tagged {r}, {python echo=FALSE}, ...
+ */
+function fenceLanguage(info) {
+  const chunk = /^\{\s*([\w+#-]+)/.exec(info);
+  const tag = chunk ? chunk[1] : parseInfoString(info).language;
+  return tag ? resolveLanguageName(tag) : null;
+}
+
+/**
+ * Find regions of embedded code
+ * @param {string} content - Host file content
+ * @param {string} hostLanguage - Host language (name, alias or extension, see code-language)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Array<object>} Regions in document order with { language, languageSource, kind, tag,
+ *   attributes, code, start, end, contentStart, contentEnd, startLine, endLine, closed }.
+ *   kind is 'fence' | 'frontmatter' | 'script' | 'style' | 'block' (other Vue blocks); language is
+ *   null when unknown; languageSource 'tag' | 'attribute' | 'default' | 'content'. Offsets are
+ *   UTF-8 byte offsets into the content: start/end cover the fences or tags, contentStart/contentEnd
+ *   the code. Lines are 1-based, of the opening and closing fence or tag.
+ */
+function detectEmbeddedLanguages(content, hostLanguage, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const text = String(content || '');
+  const host = resolveLanguageName(hostLanguage);
+  if (!text || !host) return [];
+
+  const found = [];
+  const frontMatter = FRONTMATTER_HOSTS.has(host) ? frontMatterRegion(text, host) : null;
+  if (frontMatter) found.push(frontMatter);
+  const body = frontMatter ? frontMatter.end : 0;
+
+  if (MARKDOWN_HOSTS.has(host)) {
+    // extractCodeBlocks already reports byte offsets and lines
+    const skip = frontMatter ? Buffer.byteLength(text.slice(0, body), 'utf8') : 0;
+    const regions = [];
+    for (const block of extractCodeBlocks(text)) {
+      if (block.start < skip) continue;
+      let language = fenceLanguage(block.info);
+      let languageSource = language ? 'tag' : null;
+      if (!language && !block.info && opts.guessUntagged) {
+        language = detectLanguages(block.code, null, { maxResults: 1 })[0]?.language || null;
+        languageSource = language ? 'content' : null;
+      }
+      regions.push({
+        language,
+        languageSource,
+        kind: 'fence',
+        tag: block.info,
+        attributes: {},
+        code: block.code,
+        start: block.start,
+        end: block.end,
+        contentStart: block.contentStart,
+        contentEnd: block.contentEnd,
+        startLine: block.startLine,
+        endLine: block.endLine,
+        closed: block.closed,
+      });
+    }
+    const located = frontMatter ? locate(text, [frontMatter]) : [];
+    return [...located, ...regions].filter((region) => opts.includeEmpty || region.code.trim() !== '');
+  }
+
+  if (SFC_HOSTS.has(host)) {
+    found.push(...elementRegions(text, true));
+  } else if (ELEMENT_HOSTS.has(host)) {
+    const regions = elementRegions(text.slice(body), false);
+    for (const region of regions) {
+      for (const key of ['start', 'contentStart', 'contentEnd', 'end']) region[key] += body;
+    }
+    found.push(...regions);
+  }
+  return locate(text, found).filter((region) => opts.includeEmpty || region.code.trim() !== '');
+}
+
+/**
+ * Turn character-indexed regions into byte offsets and lines, with their code
+ */
+function locate(text, regions) {
+  const at = createLocator(text);
+  return regions.map((region) => {
+    const start = at(region.start);
+    const contentStart = at(region.contentStart);
+    const contentEnd = at(region.contentEnd);
+    const end = at(region.end);
+    const lastLine = region.end > region.start && text[region.end - 1] === '\n' ? end.line - 1 : end.line;
+    return {
+      language: region.language,
+      languageSource: region.languageSource,
+      kind: region.kind,
+      tag: region.tag,
+      attributes: region.attributes,
+      code: text.slice(region.contentStart, region.contentEnd),
+      start: start.bytes,
+      end: end.bytes,
+      contentStart: contentStart.bytes,
+      contentEnd: contentEnd.bytes,
+      startLine: start.line,
+      endLine: lastLine,
+      closed: region.closed,
+    };
+  });
+}
+
+module.exports = instrument('CODE', {
+  detectEmbeddedLanguages,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,161 @@
+/**
+ * Code Format
+ * Whether an edit only reformatted a file: whitespace, line breaks, quote
+ * style, trailing commas or comment layout changed, but the code didn't.
+ * Formatter runs (prettier, rustfmt, black) touch every line of a file and
+ * otherwise read as large significant edits.
+ *
+ * Both versions are tokenized with code-lexer and the token streams compared,
+ * after the normalizations formatters make:
+ *   quotes           'a' and "a" are the same string where the language
+ *                    doesn't give them different meanings
+ *   trailing commas  `[a, b,]` is `[a, b]` (but a one-element tuple's comma
+ *                    stays in Python and Rust)
+ *   indentation      only where it's syntax (Python, YAML...): the block
+ *                    depth each line is at, not its width
+ * Comments are compared as words, so rewrapping or re-indenting a comment is
+ * formatting but rewording one is not.
+ */
+
+const { scanCode, tokenize, getSyntax } = require('./code-lexer');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  ignoreComments: false, // Treat comment edits as formatting too
+  normalizeQuotes: true,
+  ignoreTrailingCommas: true,
+};
+
+// Single and double quotes delimit the same kind of string
+const QUOTE_INSENSITIVE = new Set(['javascript', 'typescript', 'python', 'css', 'scss', 'lua', 'dart']);
+// Where `(a,)` is a tuple rather than `(a)`
+const TUPLE_COMMAS = new Set(['python', 'rust']);
+const INDENTED = new Set(['python', 'yaml', 'nim', 'haskell', 'fsharp']);
+
+const OPENERS = new Set(['(', '[', '{']);
+const CLOSERS = new Set([')', ']', '}']);
+const COMMENT_MARKER = /^\s*(?:\/\/+|\/\*+|\*+(?!\/)|#+|--+|;+|<!--|\(\*|\{-)!?/;
+const COMMENT_CLOSER = /(?:\*+\/|-->|\*\)|-\})\s*$/;
+
+function normalizeString(token, previous) {
+  const { value } = token;
+  const quote = value[0];
+  if ((quote !== '"' && quote !== "'") || value.length < 2 || value[value.length - 1] !== quote) return value;
+  // Prefixed strings (r'...', b"...") keep their spelling: escapes mean different things in them
+  if (previous && previous.type === 'identifier' && previous.start + previous.value.length === token.start) {
+    return value;
+  }
+  if (value.startsWith(quote.repeat(3))) return value;
+  const body = value.slice(1, -1).replace(/\\(['"])/g, '$1');
+  return `"${body.replace(/"/g, '\\"')}"`;
+}
+
+/**
+ * Commas directly before a closing bracket that a formatter may add or remove
+ */
+function trailingCommas(tokens, language) {
+  const removable = new Set();
+  const groups = []; // Per open bracket: { value, commas }
+  for (let i = 0; i < tokens.length; i++) {
+    const { value } = tokens[i];
+    if (OPENERS.has(value)) groups.push({ value, commas: 0 });
+    else if (CLOSERS.has(value)) groups.pop();
+    else if (value === ',' && groups.length) {
+      const group = groups[groups.length - 1];
+      group.commas++;
+      const next = tokens[i + 1];
+      if (!next || !CLOSERS.has(next.value)) continue;
+      const tuple = TUPLE_COMMAS.has(language) && group.value === '(' && group.commas === 1;
+      if (!tuple) removable.add(i);
+    }
+  }
+  return removable;
+}
+
+/**
+ * Block depth of each logical line's first token, for indentation-sensitive languages
+ */
+function indentDepths(content, tokens) {
+  const depths = new Map();
+  const indents = [0];
+  let brackets = 0;
+  let previous = null;
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (!previous || (token.line > previous.line && brackets === 0 && previous.value !== '\\')) {
+      const lineStart = content.lastIndexOf('\n', token.start - 1) + 1;
+      const indent = content.slice(lineStart, token.start).replace(/\t/g, '        ').length;
+      while (indents.length > 1 && indents[indents.length - 1] > indent) indents.pop();
+      if (indent > indents[indents.length - 1]) indents.push(indent);
+      depths.set(i, indents.length - 1);
+    }
+    if (OPENERS.has(token.value)) brackets++;
+    else if (CLOSERS.has(token.value) && brackets > 0) brackets--;
+    previous = token;
+  }
+  return depths;
+}
+
+/**
+ * The code as a list of normalized tokens
+ */
+function codeStream(content, language, opts) {
+  const tokens = tokenize(content, language);
+  const removable = opts.ignoreTrailingCommas ? trailingCommas(tokens, language) : new Set();
+  const depths = INDENTED.has(language) ? indentDepths(content, tokens) : null;
+  const quotes = opts.normalizeQuotes && QUOTE_INSENSITIVE.has(language);
+  const stream = [];
+  for (let i = 0; i < tokens.length; i++) {
+    if (removable.has(i)) continue;
+    if (depths && depths.has(i)) stream.push(`\u0000${depths.get(i)}`);
+    const token = tokens[i];
+    stream.push(quotes && token.type === 'string' ? normalizeString(token, tokens[i - 1]) : token.value);
+  }
+  return stream;
+}
+
+/**
+ * Comment text as words, without markers or layout
+ */
+function commentWords(content, language) {
+  const words = [];
+  for (const segment of scanCode(content, language)) {
+    if (segment.type !== 'comment') continue;
+    for (const line of content.slice(segment.start, segment.end).split('\n')) {
+      const text = line.replace(COMMENT_MARKER, '').replace(COMMENT_CLOSER, '');
+      words.push(...text.split(/\s+/).filter(Boolean));
+    }
+  }
+  return words;
+}
+
+function sameList(a, b) {
+  if (a.length !== b.length) return false;
+  for (let i = 0; i < a.length; i++) if (a[i] !== b[i]) return false;
+  return true;
+}
+
+/**
+ * Whether two versions of a file differ only in formatting
+ * @param {string} text1 - Before
+ * @param {string} text2 - After
+ * @param {string} language - Language name or alias; text and unknown languages compare words
+ * @param {object} options - { ignoreComments, normalizeQuotes, ignoreTrailingCommas }
+ * @returns {boolean} true when the token streams (and comment words) are equal; identical texts
+ *   are not a formatting change
+ */
+function isFormatOnlyChange(text1, text2, language, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const before = String(text1 || '');
+  const after = String(text2 || '');
+  if (before === after) return false;
+  const resolved = getSyntax(language).language;
+
+  if (!sameList(codeStream(before, resolved, opts), codeStream(after, resolved, opts))) return false;
+  return opts.ignoreComments || sameList(commentWords(before, resolved), commentWords(after, resolved));
+}
+
+module.exports = instrument('CODE', {
+  isFormatOnlyChange,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,163 @@
+/**
+ * Code Generated
+ * Whether a file is a generated artifact rather than something a person
+ * edits: lockfiles, minified bundles, source maps and codegen output. These
+ * dominate trace volume (a dependency bump rewrites thousands of lockfile
+ * lines) without saying anything about how the developer works, so the
+ * capture pipeline flags, skips or downsamples them.
+ *
+ * Heuristics, strongest first:
+ *   lockfile   the file name (package-lock.json, Cargo.lock, go.sum, ...)
+ *   sourcemap  a .map file, or a bundle ending in a sourceMappingURL comment
+ *   minified   .min.js / .min.css, or long lines: the average line length
+ *              or a single line far beyond what anyone types
+ *   generated  a "DO NOT EDIT" / "@generated" / "<auto-generated>" marker in
+ *              the header, or a codegen file name (*.pb.go, *_pb2.py, ...)
+ */
+
+const path = require('path').posix;
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  headerLines: 20, // Lines searched for a generated marker
+  maxAverageLineLength: 200, // Longer on average reads as minified
+  maxLineLength: 2000, // Any line this long reads as minified...
+  minifiedLineShare: 0.5, // ...when it holds at least this share of the file
+};
+
+const LOCKFILES = new Set([
+  'package-lock.json',
+  'npm-shrinkwrap.json',
+  'yarn.lock',
+  'pnpm-lock.yaml',
+  'bun.lockb',
+  'bun.lock',
+  'deno.lock',
+  'cargo.lock',
+  'gemfile.lock',
+  'poetry.lock',
+  'pipfile.lock',
+  'pdm.lock',
+  'uv.lock',
+  'composer.lock',
+  'go.sum',
+  'flake.lock',
+  'mix.lock',
+  'pubspec.lock',
+  'podfile.lock',
+  'packages.lock.json',
+  'gradle.lockfile',
+  'package.resolved',
+  'paket.lock',
+  'conan.lock',
+  '.terraform.lock.hcl',
+]);
+
+// Codegen output, by name
+const GENERATED_PATHS = [
+  /\.pb\.(go|cc|h)$/,
+  /_pb2(_grpc)?\.pyi?$/,
+  /\.pb\.(js|ts)$/,
+  /_grpc_pb\.(js|d\.ts)$/,
+  /\.g\.(dart|cs)$/,
+  /\.freezed\.dart$/,
+  /\.designer\.cs$/i,
+  /\.generated\.\w+$/,
+  /(^|\/)zz_generated[^/]*\.go$/,
+];
+
+const MINIFIED_PATHS = /[.-]min\.(js|mjs|css)$/;
+
+// Searched in the first headerLines lines only, so docs that mention the marker don't count
+const GENERATED_MARKERS = [
+  /@generated\b/,
+  /\bDO NOT EDIT\b/i,
+  /<auto-generated\b/i,
+  /\b(?:this|the) (?:file|code) (?:is|was|has been) (?:automatically |auto-?)?generated\b/i,
+  /\bauto-?generated (?:file|code)\b/i,
+  /\bgenerated by (?:the )?[\w.@/-]+/i,
+];
+
+const SOURCE_MAP_URL = /(?:\/\/|\/\*)[#@] sourceMappingURL=\S+\s*(?:\*\/)?\s*$/;
+
+// Prose and data with naturally long lines
+const LONG_LINE_EXTENSIONS = new Set(['md', 'markdown', 'txt', 'rst', 'csv', 'tsv', 'svg', 'ipynb']);
+
+function lineStats(text) {
+  let lines = 0;
+  let longest = 0;
+  let start = 0;
+  while (start <= text.length) {
+    let end = text.indexOf('\n', start);
+    if (end < 0) end = text.length;
+    longest = Math.max(longest, end - start);
+    lines++;
+    start = end + 1;
+  }
+  const averageLineLength = lines ? Math.round((text.length - (lines - 1)) / lines) : 0;
+  return { lines, averageLineLength, maxLineLength: longest };
+}
+
+/**
+ * Classify a file as generated or hand-written
+ * @param {string} content - File content (may be null when only the path is known)
+ * @param {string} filePath - Path, for name-based rules
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {object} { generated, kind: 'lockfile'|'sourcemap'|'minified'|'generated'|null,
+ *   reasons: [string], stats: { lines, averageLineLength, maxLineLength } }
+ *   kind is the strongest rule that matched; reasons lists every rule that did
+ */
+function detectGenerated(content, filePath = '', options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const text = String(content ?? '').replace(/\r\n?/g, '\n');
+  const normalized = String(filePath || '').replace(/\\/g, '/');
+  const name = path.basename(normalized).toLowerCase();
+  const ext = path.extname(name).slice(1);
+  const stats = lineStats(text);
+  const matched = [];
+  const match = (kind, reason) => matched.push({ kind, reason });
+
+  if (LOCKFILES.has(name) || name.endsWith('.lock') || name.endsWith('.lockfile')) {
+    match('lockfile', `lockfile name: ${name}`);
+  }
+
+  if (ext === 'map' && /^\s*\{\s*"version"\s*:\s*3\b/.test(text.slice(0, 200))) {
+    match('sourcemap', 'source map');
+  } else if (ext === 'map' && /\.(js|mjs|cjs|css)\.map$/.test(name)) {
+    match('sourcemap', 'source map file name');
+  }
+  if (SOURCE_MAP_URL.test(text.slice(-500))) match('sourcemap', 'sourceMappingURL comment');
+
+  if (MINIFIED_PATHS.test(name)) match('minified', 'minified file name');
+  if (text && !LONG_LINE_EXTENSIONS.has(ext)) {
+    if (stats.averageLineLength > opts.maxAverageLineLength) {
+      match('minified', `average line length ${stats.averageLineLength}`);
+    } else if (
+      stats.maxLineLength >= opts.maxLineLength &&
+      stats.maxLineLength >= text.length * opts.minifiedLineShare
+    ) {
+      match('minified', `line of ${stats.maxLineLength} characters`);
+    }
+  }
+
+  const header = text.split('\n', opts.headerLines).join('\n');
+  const marker = GENERATED_MARKERS.find((pattern) => pattern.test(header));
+  if (marker) match('generated', `header marker: ${marker.exec(header)[0].trim()}`);
+  if (GENERATED_PATHS.some((pattern) => pattern.test(normalized.toLowerCase()))) {
+    match('generated', 'generated file name');
+  }
+
+  const order = ['lockfile', 'sourcemap', 'minified', 'generated'];
+  const kind = order.find((candidate) => matched.some((entry) => entry.kind === candidate)) || null;
+  return {
+    generated: kind !== null,
+    kind,
+    reasons: matched.map((entry) => entry.reason),
+    stats,
+  };
+}
+
+module.exports = instrument('CODE', {
+  detectGenerated,
+  DEFAULT_OPTIONS,
+});
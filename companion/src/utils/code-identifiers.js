@@ -0,0 +1,195 @@
+/**
+ * Code Identifiers
+ * Identifier usage in a file, and the renames between two versions of it,
+ * so a rename refactor can be recorded as "a → b" instead of a diff that
+ * touches every line using the name.
+ *
+ * Renames are found by position: both versions' lines are reduced to their
+ * token shape (identifiers masked), the shapes are diffed, and in each pair
+ * of lines with the same shape the identifiers at the same positions vote
+ * for old → new. A pair is reported when its votes are consistent (the old
+ * name mostly became the same new name) and agree with usage: the old
+ * name's count fell and the new name's rose by about as much.
+ */
+
+const diff = require('diff');
+const { tokenize, getSyntax } = require('./code-lexer');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  minConfidence: 0.5, // Renames reported at or above this score (0..1)
+  minVotes: 1, // Aligned occurrences needed before a pair is considered
+};
+
+/**
+ * Identifier occurrences, by name
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias
+ * @returns {object} { language, total, identifiers: [{ name, count, lines }] } sorted by count
+ */
+function extractIdentifiers(content, language) {
+  const byName = new Map();
+  let total = 0;
+  for (const token of tokenize(String(content || ''), language)) {
+    if (token.type !== 'identifier') continue;
+    total++;
+    let entry = byName.get(token.value);
+    if (!entry) {
+      entry = { name: token.value, count: 0, lines: [] };
+      byName.set(token.value, entry);
+    }
+    entry.count++;
+    if (entry.lines[entry.lines.length - 1] !== token.line) entry.lines.push(token.line);
+  }
+  const identifiers = [...byName.values()].sort((a, b) => b.count - a.count || (a.name < b.name ? -1 : 1));
+  return { language: getSyntax(language).language, total, identifiers };
+}
+
+/**
+ * Tokens grouped by line, with each line's shape (identifiers masked)
+ */
+function tokenLines(content, language) {
+  const lines = [];
+  for (const token of tokenize(content, language)) {
+    const last = lines[lines.length - 1];
+    if (last && last.line === token.line) last.tokens.push(token);
+    else lines.push({ line: token.line, tokens: [token] });
+  }
+  const mask = (token) => (token.type === 'identifier' ? '\u0001' : token.value);
+  for (const entry of lines) entry.shape = entry.tokens.map(mask).join(' ');
+  return lines;
+}
+
+/**
+ * Pairs of lines with the same shape, in order
+ */
+function alignLines(before, after) {
+  const pairs = [];
+  const text = (lines) => lines.map((entry) => entry.shape).join('\n') + '\n';
+  let i = 0;
+  let j = 0;
+  for (const change of diff.diffLines(text(before), text(after))) {
+    const count = change.count ?? change.value.split('\n').length - 1;
+    if (change.added) j += count;
+    else if (change.removed) i += count;
+    else {
+      for (let n = 0; n < count; n++) pairs.push([before[i + n], after[j + n]]);
+      i += count;
+      j += count;
+    }
+  }
+  return pairs;
+}
+
+function countNames(lines) {
+  const counts = new Map();
+  for (const entry of lines) {
+    for (const token of entry.tokens) {
+      if (token.type === 'identifier') counts.set(token.value, (counts.get(token.value) || 0) + 1);
+    }
+  }
+  return counts;
+}
+
+/**
+ * Compare identifiers between two versions and detect renames
+ * @param {string} text1 - Before
+ * @param {string} text2 - After
+ * @param {string} language - Language name or alias
+ * @param {object} options - { minConfidence, minVotes }
+ * @returns {object} { language, renames: [{ from, to, occurrences, confidence, complete, lines }],
+ *   added: [name], removed: [name], explainedLines }
+ *   occurrences counts aligned uses; complete means the old name no longer appears; lines are the
+ *   after-version lines with a renamed use; explainedLines counts changed lines whose only change is
+ *   a reported rename. added / removed exclude names accounted for by a complete rename.
+ */
+function diffIdentifiers(text1, text2, language, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const before = tokenLines(String(text1 || ''), language);
+  const after = tokenLines(String(text2 || ''), language);
+  const oldCounts = countNames(before);
+  const newCounts = countNames(after);
+  const pairs = alignLines(before, after);
+
+  // Votes: old name -> new name -> { votes, lines }
+  const votes = new Map();
+  const votesFrom = new Map();
+  for (const [old, current] of pairs) {
+    for (let n = 0; n < old.tokens.length; n++) {
+      const from = old.tokens[n];
+      const to = current.tokens[n];
+      if (from.type !== 'identifier' || from.value === to.value) continue;
+      if (!votes.has(from.value)) votes.set(from.value, new Map());
+      const targets = votes.get(from.value);
+      const entry = targets.get(to.value) || { votes: 0, lines: [] };
+      entry.votes++;
+      if (entry.lines[entry.lines.length - 1] !== current.line) entry.lines.push(current.line);
+      targets.set(to.value, entry);
+      votesFrom.set(from.value, (votesFrom.get(from.value) || 0) + 1);
+    }
+  }
+
+  // Score every candidate pair, then pick one-to-one greedily
+  const candidates = [];
+  for (const [from, targets] of votes) {
+    const decrease = (oldCounts.get(from) || 0) - (newCounts.get(from) || 0);
+    for (const [to, entry] of targets) {
+      const increase = (newCounts.get(to) || 0) - (oldCounts.get(to) || 0);
+      if (entry.votes < opts.minVotes || decrease <= 0 || increase <= 0) continue;
+      const consistency = entry.votes / votesFrom.get(from);
+      const usage = Math.min(decrease, increase) / Math.max(decrease, increase);
+      const coverage = Math.min(1, entry.votes / Math.min(decrease, increase));
+      const confidence = Math.round(consistency * usage * coverage * 1000) / 1000;
+      if (confidence >= opts.minConfidence) candidates.push({ from, to, entry, confidence });
+    }
+  }
+  candidates.sort((a, b) => b.confidence - a.confidence || b.entry.votes - a.entry.votes);
+  const usedFrom = new Set();
+  const usedTo = new Set();
+  const renames = [];
+  for (const { from, to, entry, confidence } of candidates) {
+    if (usedFrom.has(from) || usedTo.has(to)) continue;
+    usedFrom.add(from);
+    usedTo.add(to);
+    renames.push({
+      from,
+      to,
+      occurrences: entry.votes,
+      confidence,
+      complete: !newCounts.has(from),
+      lines: entry.lines,
+    });
+  }
+
+  // Changed lines that the renames fully account for
+  const mapping = new Map(renames.map((rename) => [rename.from, rename.to]));
+  let explainedLines = 0;
+  for (const [old, current] of pairs) {
+    let changed = false;
+    let explained = true;
+    for (let n = 0; n < old.tokens.length && explained; n++) {
+      const from = old.tokens[n].value;
+      const to = current.tokens[n].value;
+      if (from === to) continue;
+      changed = true;
+      explained = old.tokens[n].type === 'identifier' && mapping.get(from) === to;
+    }
+    if (changed && explained) explainedLines++;
+  }
+
+  const renamedAway = new Set(renames.filter((r) => r.complete).map((r) => r.from));
+  const renamedTo = new Set(renames.filter((r) => r.complete).map((r) => r.to));
+  return {
+    language: getSyntax(language).language,
+    renames,
+    added: [...newCounts.keys()].filter((name) => !oldCounts.has(name) && !renamedTo.has(name)).sort(),
+    removed: [...oldCounts.keys()].filter((name) => !newCounts.has(name) && !renamedAway.has(name)).sort(),
+    explainedLines,
+  };
+}
+
+module.exports = instrument('CODE', {
+  extractIdentifiers,
+  diffIdentifiers,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,742 @@
+/**
+ * Code Language
+ * Which language a file is written in, as a ranked list of candidates with
+ * confidences rather than a single guess.
+ *
+ * Evidence, each adding weight to the languages it names:
+ *   modeline    an Emacs `-*- mode: ruby -*-` line or Local Variables
+ *               block, or a Vim `vim: set ft=python :` line (parseModeline)
+ *   filename    names that identify the language alone (Makefile, Gemfile,
+ *               Dockerfile, CMakeLists.txt, .bashrc, ...)
+ *   shebang     the interpreter on a `#!` line (parseShebang): env and its
+ *               options, version suffixes (python3.11 is python), nix-shell
+ *               -i and `exec tclsh "$0"` shell trampolines are looked through
+ *   extension   from a table of 100+ languages; ambiguous extensions (.h,
+ *               .m, .pl, .v, ...) split their weight between candidates
+ *   content     a small token model per language: distinctive keywords and
+ *               constructs, each match adding a little. It breaks ties
+ *               between an extension's candidates and names the language of
+ *               extensionless files when nothing else does
+ * Modelines and shebangs outweigh extensions, so extensionless scripts
+ * (bin/deploy, git hooks) and misnamed ones get the language they run as.
+ * A language's confidence is its share of all the weight, with some weight
+ * held back for "none of these", so a lone extension match reads as likely
+ * rather than certain.
+ *
+ * Language names are lowercase ids (javascript, cpp, objective-c, shell,
+ * ...); code-lexer's getSyntax resolves the ones it has comment and string
+ * rules for.
+ */
+
+const path = require('path').posix;
+const { instrument } = require('./logger');
+const { escapeRegExp } = require('./regexp');
+
+const DEFAULT_OPTIONS = {
+  maxContentBytes: 32 * 1024, // Content examined for modelines, shebang and tokens
+  maxResults: 5,
+  minConfidence: 0.05, // Candidates below this are dropped
+};
+
+// Weight each kind of evidence adds
+const WEIGHTS = {
+  modeline: 5,
+  filename: 4.5,
+  shebang: 4,
+  extension: 3,
+  contentCap: 3, // Most a content model can add
+  unexplained: 1, // Held back for "some other language"
+};
+
+// Minimum content score for a language no other evidence names
+const MIN_CONTENT_ONLY = 1.5;
+
+// ---------------------------------------------------------------------------
+// Language table
+// ---------------------------------------------------------------------------
+
+// extensions are lowercase without the dot; aliases are names used by modelines and fences
+const LANGUAGES = {
+  javascript: {
+    extensions: ['js', 'mjs', 'cjs', 'jsx'],
+    interpreters: ['node', 'nodejs', 'bun'],
+    aliases: ['js'],
+  },
+  typescript: {
+    extensions: ['ts', 'tsx', 'mts', 'cts'],
+    interpreters: ['deno', 'ts-node', 'tsx'],
+    aliases: ['ts'],
+  },
+  coffeescript: { extensions: ['coffee'], interpreters: ['coffee'] },
+  livescript: { extensions: ['ls'], interpreters: ['lsc'] },
+  python: {
+    extensions: ['py', 'pyw', 'pyi'],
+    filenames: ['sconstruct', 'sconscript'],
+    interpreters: ['python', 'pypy'],
+    aliases: ['py'],
+  },
+  cython: { extensions: ['pyx', 'pxd'] },
+  'jupyter-notebook': { extensions: ['ipynb'] },
+  ruby: {
+    extensions: ['rb', 'rake', 'gemspec', 'ru', 'rbw', 'podspec'],
+    filenames: [
+      'gemfile',
+      'rakefile',
+      'guardfile',
+      'podfile',
+      'vagrantfile',
+      'brewfile',
+      'fastfile',
+      'appfile',
+    ],
+    interpreters: ['ruby', 'jruby', 'rbx'],
+    aliases: ['rb'],
+  },
+  perl: { extensions: ['pl', 'pm', 'pod', 't'], interpreters: ['perl'] },
+  raku: { extensions: ['raku', 'rakumod', 'p6', 'pm6'], interpreters: ['raku', 'perl6'] },
+  php: { extensions: ['php', 'phtml', 'php3', 'php4', 'php5', 'php7', 'php8'], interpreters: ['php'] },
+  shell: {
+    extensions: ['sh', 'bash', 'zsh', 'ksh', 'bats', 'command'],
+    filenames: [
+      '.bashrc',
+      '.bash_profile',
+      '.bash_login',
+      '.bash_logout',
+      '.bash_aliases',
+      '.zshrc',
+      '.zshenv',
+      '.zprofile',
+      '.zlogin',
+      '.profile',
+      '.kshrc',
+      'pkgbuild',
+    ],
+    interpreters: ['sh', 'bash', 'zsh', 'ksh', 'dash', 'ash', 'mksh', 'bats'],
+    aliases: ['sh', 'bash', 'zsh', 'shell-script', 'shellscript', 'console'],
+  },
+  fish: { extensions: ['fish'], interpreters: ['fish'] },
+  powershell: {
+    extensions: ['ps1', 'psm1', 'psd1'],
+    interpreters: ['pwsh', 'powershell'],
+    aliases: ['ps1', 'pwsh'],
+  },
+  batch: { extensions: ['bat', 'cmd'], aliases: ['dosbatch', 'bat'] },
+  c: { extensions: ['c', 'h'] },
+  cpp: {
+    extensions: ['cpp', 'cc', 'cxx', 'c++', 'hpp', 'hh', 'hxx', 'h++', 'ipp', 'tpp', 'inl', 'ino', 'h'],
+    aliases: ['c++'],
+  },
+  'objective-c': { extensions: ['m', 'h'], aliases: ['objc', 'objective-c'] },
+  'objective-cpp': { extensions: ['mm'], aliases: ['objcpp', 'objc++'] },
+  cuda: { extensions: ['cu', 'cuh'] },
+  csharp: { extensions: ['cs', 'csx'], aliases: ['cs', 'c#'] },
+  fsharp: { extensions: ['fs', 'fsi', 'fsx'], aliases: ['f#'] },
+  vb: { extensions: ['vb'], aliases: ['vbnet', 'vb.net'] },
+  vbscript: { extensions: ['vbs'] },
+  java: { extensions: ['java'] },
+  kotlin: { extensions: ['kt', 'kts'], interpreters: ['kotlin'] },
+  scala: { extensions: ['scala', 'sc'], interpreters: ['scala'] },
+  groovy: { extensions: ['groovy', 'gvy', 'gradle'], filenames: ['jenkinsfile'], interpreters: ['groovy'] },
+  clojure: { extensions: ['clj', 'cljs', 'cljc', 'edn'], interpreters: ['clojure', 'bb'] },
+  go: { extensions: ['go'], aliases: ['golang'] },
+  rust: { extensions: ['rs'], aliases: ['rs'] },
+  swift: { extensions: ['swift'], interpreters: ['swift'] },
+  dart: { extensions: ['dart'], interpreters: ['dart'] },
+  zig: { extensions: ['zig'] },
+  nim: { extensions: ['nim', 'nims', 'nimble'] },
+  crystal: { extensions: ['cr'], interpreters: ['crystal'] },
+  d: { extensions: ['d', 'di'] },
+  odin: { extensions: ['odin'] },
+  vala: { extensions: ['vala'] },
+  gleam: { extensions: ['gleam'] },
+  mojo: { extensions: ['mojo', '🔥'] },
+  elixir: { extensions: ['ex', 'exs'], interpreters: ['elixir'] },
+  erlang: { extensions: ['erl', 'hrl'], filenames: ['rebar.config'], interpreters: ['escript'] },
+  haskell: { extensions: ['hs', 'lhs'], interpreters: ['runhaskell', 'runghc', 'stack'] },
+  elm: { extensions: ['elm'] },
+  purescript: { extensions: ['purs'] },
+  idris: { extensions: ['idr'] },
+  agda: { extensions: ['agda'] },
+  lean: { extensions: ['lean'] },
+  coq: { extensions: ['v'] },
+  ocaml: { extensions: ['ml', 'mli'], interpreters: ['ocaml'] },
+  reason: { extensions: ['re', 'rei'], aliases: ['reasonml'] },
+  sml: { extensions: ['sml'], aliases: ['standard-ml'] },
+  julia: { extensions: ['jl'], interpreters: ['julia'] },
+  r: { extensions: ['r', 'rd'], interpreters: ['rscript'] },
+  rmarkdown: { extensions: ['rmd'] },
+  matlab: { extensions: ['m'], aliases: ['octave'] },
+  lua: { extensions: ['lua'], interpreters: ['lua', 'luajit'] },
+  tcl: { extensions: ['tcl', 'tk'], interpreters: ['tclsh', 'wish'] },
+  awk: { extensions: ['awk'], interpreters: ['awk', 'gawk', 'mawk', 'nawk'] },
+  lisp: {
+    extensions: ['lisp', 'lsp', 'cl'],
+    interpreters: ['sbcl', 'clisp', 'ecl'],
+    aliases: ['common-lisp'],
+  },
+  scheme: { extensions: ['scm', 'ss'], interpreters: ['guile', 'chicken', 'csi'] },
+  racket: { extensions: ['rkt'], interpreters: ['racket'] },
+  'emacs-lisp': { extensions: ['el'], filenames: ['.emacs'], aliases: ['elisp', 'emacs'] },
+  fortran: { extensions: ['f', 'f90', 'f95', 'f03', 'f08', 'for'] },
+  cobol: { extensions: ['cob', 'cbl', 'cpy'] },
+  pascal: { extensions: ['pas', 'dpr', 'lpr', 'pp'], aliases: ['delphi'] },
+  ada: { extensions: ['adb', 'ads', 'ada'] },
+  prolog: { extensions: ['pro', 'pl', 'prolog'], interpreters: ['swipl'] },
+  assembly: { extensions: ['asm', 's', 'nasm'], aliases: ['asm', 'nasm', 'gas'] },
+  verilog: { extensions: ['v', 'vh'] },
+  systemverilog: { extensions: ['sv', 'svh'] },
+  vhdl: { extensions: ['vhd', 'vhdl'] },
+  solidity: { extensions: ['sol'] },
+  vyper: { extensions: ['vy'] },
+  cairo: { extensions: ['cairo'] },
+  glsl: { extensions: ['glsl', 'vert', 'frag', 'geom', 'comp', 'tesc', 'tese'] },
+  hlsl: { extensions: ['hlsl'] },
+  wgsl: { extensions: ['wgsl'] },
+  haxe: { extensions: ['hx'] },
+  gdscript: { extensions: ['gd'] },
+  abap: { extensions: ['abap'] },
+  'q#': { extensions: ['qs'], aliases: ['qsharp'] },
+  applescript: { extensions: ['applescript', 'scpt'], interpreters: ['osascript'] },
+  autohotkey: { extensions: ['ahk'] },
+  sas: { extensions: ['sas'] },
+  stata: { extensions: ['do', 'ado'] },
+  mathematica: { extensions: ['wl', 'nb'], aliases: ['wolfram'] },
+  puppet: { extensions: ['pp'] },
+  m4: { extensions: ['m4'] },
+  'vim-script': { extensions: ['vim'], filenames: ['.vimrc', '_vimrc', '.gvimrc'], aliases: ['vim', 'viml'] },
+  sql: { extensions: ['sql', 'ddl', 'dml'], aliases: ['mysql', 'postgresql', 'plsql', 'tsql'] },
+  graphql: { extensions: ['graphql', 'gql'] },
+  protobuf: { extensions: ['proto'], aliases: ['proto'] },
+  thrift: { extensions: ['thrift'] },
+  prisma: { extensions: ['prisma'] },
+  html: { extensions: ['html', 'htm', 'xhtml', 'shtml'] },
+  xml: {
+    extensions: [
+      'xml',
+      'xsd',
+      'xsl',
+      'xslt',
+      'plist',
+      'rss',
+      'atom',
+      'wsdl',
+      'xaml',
+      'csproj',
+      'fsproj',
+      'vcxproj',
+    ],
+    filenames: ['pom.xml'],
+  },
+  svg: { extensions: ['svg'] },
+  css: { extensions: ['css'] },
+  scss: { extensions: ['scss'] },
+  sass: { extensions: ['sass'] },
+  less: { extensions: ['less'] },
+  stylus: { extensions: ['styl'] },
+  vue: { extensions: ['vue'] },
+  svelte: { extensions: ['svelte'] },
+  astro: { extensions: ['astro'] },
+  handlebars: { extensions: ['hbs', 'handlebars', 'mustache'] },
+  jinja: { extensions: ['j2', 'jinja', 'jinja2'] },
+  twig: { extensions: ['twig'] },
+  ejs: { extensions: ['ejs'] },
+  erb: { extensions: ['erb'] },
+  haml: { extensions: ['haml'] },
+  pug: { extensions: ['pug', 'jade'] },
+  liquid: { extensions: ['liquid'] },
+  razor: { extensions: ['cshtml', 'razor'] },
+  markdown: { extensions: ['md', 'markdown', 'mdown', 'mkd', 'mkdn'], aliases: ['md', 'gfm'] },
+  mdx: { extensions: ['mdx'] },
+  restructuredtext: { extensions: ['rst'], aliases: ['rst'] },
+  asciidoc: { extensions: ['adoc', 'asciidoc'] },
+  org: { extensions: ['org'] },
+  latex: { extensions: ['tex', 'sty', 'cls', 'ltx'], aliases: ['tex'] },
+  bibtex: { extensions: ['bib'] },
+  typst: { extensions: ['typ'] },
+  roff: { extensions: ['roff', 'man', 'mdoc'], aliases: ['nroff', 'troff', 'groff'] },
+  text: { extensions: ['txt', 'text'], filenames: ['license', 'copying', 'authors'], aliases: ['plaintext'] },
+  json: {
+    extensions: ['json', 'geojson', 'webmanifest'],
+    filenames: ['.babelrc', '.prettierrc', 'composer.lock'],
+  },
+  jsonc: {
+    extensions: ['jsonc'],
+    filenames: ['tsconfig.json', 'jsconfig.json', '.eslintrc.json', 'devcontainer.json', '.jshintrc'],
+  },
+  json5: { extensions: ['json5'] },
+  yaml: { extensions: ['yml', 'yaml'], filenames: ['.clang-format', '.clang-tidy'] },
+  toml: { extensions: ['toml'], filenames: ['cargo.lock', 'pipfile', 'poetry.lock', 'uv.lock'] },
+  ini: {
+    extensions: ['ini', 'cfg', 'conf', 'inf'],
+    filenames: ['.editorconfig', '.gitconfig', '.npmrc', '.pylintrc', 'setup.cfg', 'tox.ini'],
+    aliases: ['dosini'],
+  },
+  properties: { extensions: ['properties'] },
+  dotenv: { extensions: ['env'], filenames: ['.env'] },
+  csv: { extensions: ['csv'] },
+  tsv: { extensions: ['tsv'] },
+  dockerfile: { extensions: ['dockerfile'], filenames: ['dockerfile', 'containerfile'], aliases: ['docker'] },
+  makefile: {
+    extensions: ['mk', 'mak', 'make'],
+    filenames: ['makefile', 'gnumakefile'],
+    interpreters: ['make'],
+    aliases: ['make'],
+  },
+  cmake: { extensions: ['cmake'], filenames: ['cmakelists.txt'] },
+  starlark: {
+    extensions: ['bzl', 'star'],
+    filenames: ['build', 'build.bazel', 'workspace', 'workspace.bazel', 'module.bazel', 'tiltfile'],
+    aliases: ['bazel'],
+  },
+  nix: { extensions: ['nix'] },
+  hcl: { extensions: ['hcl', 'tf', 'tfvars'], aliases: ['terraform'] },
+  bicep: { extensions: ['bicep'] },
+  jsonnet: { extensions: ['jsonnet', 'libsonnet'] },
+  dhall: { extensions: ['dhall'] },
+  cue: { extensions: ['cue'] },
+  rego: { extensions: ['rego'] },
+  nginx: { filenames: ['nginx.conf'] },
+  diff: { extensions: ['diff', 'patch'] },
+  'ignore-list': {
+    filenames: ['.gitignore', '.dockerignore', '.npmignore', '.prettierignore', '.eslintignore'],
+  },
+  'git-attributes': { filenames: ['.gitattributes'] },
+  'git-config': { filenames: ['.gitmodules'] },
+  postscript: { extensions: ['ps', 'eps'] },
+  xquery: { extensions: ['xq', 'xquery'] },
+  qml: { extensions: ['qml'] },
+};
+
+const BY_EXTENSION = new Map(); // ext -> [language]
+const BY_FILENAME = new Map(); // lowercase basename -> language
+const BY_INTERPRETER = new Map(); // interpreter -> language
+const BY_NAME = new Map(); // lowercase name, alias or extension -> language
+for (const [language, spec] of Object.entries(LANGUAGES)) {
+  for (const ext of spec.extensions || []) {
+    if (!BY_EXTENSION.has(ext)) BY_EXTENSION.set(ext, []);
+    BY_EXTENSION.get(ext).push(language);
+  }
+  for (const name of spec.filenames || []) BY_FILENAME.set(name, language);
+  for (const name of spec.interpreters || []) BY_INTERPRETER.set(name, language);
+}
+for (const [language, spec] of Object.entries(LANGUAGES)) {
+  for (const ext of spec.extensions || []) if (!BY_NAME.has(ext)) BY_NAME.set(ext, language);
+  for (const alias of spec.aliases || []) BY_NAME.set(alias, language);
+  BY_NAME.set(language, language);
+}
+
+/**
+ * Resolve a language name, alias or extension (as used by modelines and code fences)
+ * @returns {string|null}
+ */
+function resolveLanguageName(name) {
+  const key = String(name || '')
+    .trim()
+    .toLowerCase()
+    .replace(/-mode$/, '');
+  return BY_NAME.get(key) || null;
+}
+
+// ---------------------------------------------------------------------------
+// Content models
+// ---------------------------------------------------------------------------
+
+// [pattern, weight]: every match (up to 3 per pattern) adds its weight
+const CONTENT_MODELS = {
+  c: [
+    [/^#include\s*<(?:stdio|stdlib|string|unistd|stdint|stddef)\.h>/m, 1],
+    [/\b(?:malloc|free|printf|memcpy|sizeof)\s*\(/, 0.4],
+    [/^\s*typedef\s+struct\b/m, 0.6],
+  ],
+  cpp: [
+    [/^#include\s*<(?:iostream|vector|string|memory|map|algorithm|utility)>/m, 1.2],
+    [/\bstd::\w+/, 0.8],
+    [/^\s*(?:template\s*<|namespace\s+\w+\s*\{|class\s+\w+\s*(?::\s*public|\{))/m, 0.8],
+    [/\b(?:nullptr|constexpr|override|virtual)\b/, 0.5],
+  ],
+  'objective-c': [
+    [/^\s*@(?:interface|implementation|protocol|property|synthesize|end)\b/m, 1.2],
+    [/^#import\s*[<"]/m, 1],
+    [/\[\s*\w+\s+\w+(?::[^\]]*)?\]/, 0.3],
+    [/\bNS(?:String|Object|Array|Dictionary)\b/, 0.8],
+  ],
+  matlab: [
+    [/^\s*function\s+(?:\[[^\]]*\]|\w+)\s*=\s*\w+\s*\(/m, 1],
+    [/^\s*%[^\n]*$/m, 0.3],
+    [/^\s*end\s*$/m, 0.3],
+    [/\b(?:zeros|ones|disp|fprintf|plot|numel)\s*\(/, 0.5],
+  ],
+  perl: [
+    [/^\s*use\s+(?:strict|warnings)\s*;/m, 1.2],
+    [/\bmy\s+[$@%]\w+/, 0.8],
+    [/^\s*sub\s+\w+\s*\{/m, 0.6],
+    [/=~\s*[ms]?\//, 0.5],
+  ],
+  prolog: [
+    [/^\s*[a-z]\w*(?:\([^)]*\))?\s*:-/m, 1],
+    [/^:-\s*(?:module|use_module|dynamic)\b/m, 1],
+    [/\.\s*$/m, 0.1],
+  ],
+  verilog: [
+    [/^\s*module\s+\w+\s*(?:#\s*)?\(/m, 1],
+    [/\b(?:always\s*@|assign\s+\w+|reg\s*\[|wire\s*\[|endmodule)\b/, 0.8],
+  ],
+  coq: [
+    [/^\s*(?:Theorem|Lemma|Definition|Fixpoint|Inductive|Proof|Qed)\b/m, 1],
+    [/^\s*Require\s+(?:Import|Export)\b/m, 0.8],
+  ],
+  d: [
+    [/^\s*(?:module|import)\s+std\.\w+/m, 1],
+    [/\b(?:writeln|immutable|@safe|@nogc)\b/, 0.6],
+  ],
+  makefile: [
+    [/^[\w.%/-]+(?:\s+[\w.%/-]+)*\s*:(?!=)[^\n]*\n\t/m, 1],
+    [/^\.PHONY\s*:/m, 1],
+    [/\$\((?:CC|MAKE|shell|wildcard|patsubst)\b/, 0.6],
+  ],
+  pascal: [
+    [/^\s*(?:program|unit)\s+\w+\s*;/im, 1],
+    [/^\s*(?:begin|end\.)\s*$/im, 0.5],
+    [/\b(?:procedure|function)\s+\w+[^;]*;/i, 0.4],
+  ],
+  puppet: [
+    [/^\s*(?:class|define|node)\s+[\w:]+\s*(?:\(|\{|inherits)/m, 0.8],
+    [/\b(?:package|service|file|exec)\s*\{\s*['"]/, 1],
+    [/=>\s*(?:present|absent|running|file|directory)\b/, 0.6],
+  ],
+  lisp: [
+    [/^\s*\((?:defun|defmacro|defvar|defparameter|defpackage|in-package)\b/m, 1],
+    [/^\s*;;/m, 0.3],
+  ],
+  python: [
+    [/^\s*def\s+\w+\s*\([^)]*\)\s*(?:->\s*[^:]+)?:\s*$/m, 0.8],
+    [/^\s*(?:from\s+[\w.]+\s+import\s+\w|import\s+[\w.]+\s*$)/m, 0.6],
+    [/^\s*class\s+\w+(?:\([^)]*\))?:\s*$/m, 0.6],
+    [/\bif\s+__name__\s*==\s*['"]__main__['"]/, 1.2],
+    [/\b(?:self|None|True|False|elif)\b/, 0.2],
+  ],
+  javascript: [
+    [/\b(?:const|let)\s+\w+\s*=\s*require\(/, 1],
+    [/\bmodule\.exports\b|\bexport\s+(?:default|const|function|class)\b/, 0.8],
+    [/\bfunction\s*\w*\s*\([^)]*\)\s*\{/, 0.3],
+    [/=>\s*[{(]?/, 0.2],
+    [/\b(?:console\.log|document\.|window\.|addEventListener)\b/, 0.5],
+  ],
+  typescript: [
+    [/^\s*(?:export\s+)?(?:interface|type)\s+\w+(?:<[^>]*>)?\s*(?:=|\{|extends)/m, 1],
+    [/:\s*(?:string|number|boolean|void|unknown|any)\b/, 0.6],
+    [/\b(?:readonly|implements|namespace|enum)\s+\w+/, 0.4],
+    [/\bimport\s+type\b|\bas\s+const\b/, 0.8],
+  ],
+  ruby: [
+    [/^\s*def\s+[\w?!.]+(?:\([^)]*\))?\s*$/m, 0.6],
+    [/^\s*end\s*$/m, 0.3],
+    [/^\s*require(?:_relative)?\s+['"]/m, 0.8],
+    [/\bdo\s*\|[^|]*\|/, 0.8],
+    [/\battr_(?:reader|writer|accessor)\b|\bputs\b/, 0.6],
+  ],
+  php: [
+    [/<\?php\b/, 2],
+    [/\$this->\w+|\bfunction\s+\w+\s*\(\s*(?:\$|\))/, 0.8],
+    [/\bnamespace\s+[\w\\]+;|\buse\s+[\w\\]+;/, 0.6],
+  ],
+  shell: [
+    [/^\s*(?:if|while)\s+\[\[?\s/m, 0.8],
+    [/^\s*(?:fi|done|esac)\s*$/m, 0.8],
+    [/\$\{?\w+\}?|\$\(/, 0.2],
+    [/^\s*(?:export|local|set\s+-[euxo])\s/m, 0.6],
+    [/^\s*(?:echo|cd|mkdir|rm|cp|mv)\s/m, 0.3],
+  ],
+  go: [
+    [/^package\s+\w+\s*$/m, 1],
+    [/^\s*func\s+(?:\([^)]*\)\s*)?\w+\s*\(/m, 0.8],
+    [/:=/, 0.3],
+    [/^import\s+(?:\(|")/m, 0.6],
+  ],
+  rust: [
+    [/^\s*(?:pub\s+)?fn\s+\w+\s*(?:<[^>]*>)?\s*\(/m, 0.8],
+    [/^\s*(?:use\s+(?:std|crate|super)::|impl\b|mod\s+\w+;)/m, 0.8],
+    [/\blet\s+mut\b|&mut\b|\bSome\(|\bOk\(/, 0.6],
+    [/#\[derive\(/, 1],
+  ],
+  java: [
+    [/^\s*package\s+[\w.]+\s*;/m, 0.8],
+    [/^\s*import\s+(?:java|javax|org|com)\.[\w.*]+\s*;/m, 0.8],
+    [/\bpublic\s+(?:static\s+)?(?:final\s+)?(?:class|void|interface)\b/, 0.6],
+    [/\bSystem\.out\.print/, 0.8],
+  ],
+  csharp: [
+    [/^\s*using\s+System(?:\.[\w.]+)?\s*;/m, 1.2],
+    [/^\s*namespace\s+[\w.]+/m, 0.4],
+    [/\b(?:public|private|internal)\s+(?:async\s+)?(?:static\s+)?\w+(?:<[^>]*>)?\s+\w+\s*\(/, 0.3],
+    [/\{\s*get;\s*(?:set;)?\s*\}/, 1],
+  ],
+  kotlin: [
+    [/^\s*(?:fun|val|var)\s+\w+/m, 0.5],
+    [/^\s*(?:data\s+class|object\s+\w+|companion\s+object)\b/m, 0.8],
+  ],
+  swift: [
+    [/^\s*import\s+(?:Foundation|UIKit|SwiftUI)\b/m, 1.2],
+    [/^\s*(?:func|guard|let|var)\s+\w+/m, 0.3],
+  ],
+  lua: [
+    [/^\s*local\s+(?:function\s+)?\w+/m, 0.8],
+    [/\bthen\b[\s\S]*?\bend\b/, 0.3],
+    [/^\s*function\s+[\w.:]+\s*\([^)]*\)\s*$/m, 0.5],
+  ],
+  sql: [
+    [/^\s*(?:SELECT|INSERT\s+INTO|UPDATE|DELETE\s+FROM|CREATE\s+(?:TABLE|INDEX|VIEW))\b/im, 1],
+    [/\b(?:FROM|WHERE|JOIN|GROUP\s+BY|ORDER\s+BY)\b/i, 0.3],
+  ],
+  html: [
+    [/^\s*<!DOCTYPE\s+html/im, 2],
+    [/<(?:html|head|body|div|span|script|meta)\b/i, 0.6],
+  ],
+  xml: [
+    [/^\s*<\?xml\s/, 2],
+    [/<\/?[\w:-]+(?:\s+[\w:-]+="[^"]*")*\s*\/?>/, 0.2],
+  ],
+  json: [[/^\s*[{[]\s*"[^"]+"\s*:/, 1.2]],
+  yaml: [
+    [/^---\s*$/m, 0.5],
+    [/^[\w-]+:\s*(?:\S.*)?$/m, 0.4],
+    [/^\s*-\s+[\w-]+:\s/m, 0.5],
+  ],
+  toml: [
+    [/^\s*\[[\w.-]+\]\s*$/m, 0.6],
+    [/^\s*[\w.-]+\s*=\s*(?:"|'|\d|true|false|\[)/m, 0.5],
+  ],
+  ini: [
+    [/^\s*\[[\w .-]+\]\s*$/m, 0.6],
+    [/^\s*[\w.-]+\s*=\s*[^"'\[\n][^\n]*$/m, 0.4],
+  ],
+  markdown: [
+    [/^#{1,6}\s+\S/m, 0.6],
+    [/^```/m, 0.8],
+    [/\[[^\]]+\]\([^)]+\)/, 0.5],
+    [/^\s*[-*]\s+\S/m, 0.2],
+  ],
+  css: [[/^\s*[.#]?[\w-]+(?:\s*[,>+~]?\s*[.#]?[\w-]+)*\s*\{[^}]*:\s*[^}]+;/m, 1]],
+  dockerfile: [
+    [/^\s*FROM\s+\S+/m, 1],
+    [/^\s*(?:RUN|COPY|WORKDIR|ENTRYPOINT|CMD|EXPOSE)\s/m, 0.8],
+  ],
+};
+
+// Global copies, for counting matches
+for (const model of Object.values(CONTENT_MODELS)) {
+  model.forEach(([pattern, weight], index) => {
+    model[index] = [new RegExp(pattern.source, `${pattern.flags}g`), weight];
+  });
+}
+
+/**
+ * Score a language's content model
+ */
+function contentScore(language, text) {
+  const model = CONTENT_MODELS[language];
+  if (!model) return 0;
+  let score = 0;
+  for (const [pattern, weight] of model) {
+    pattern.lastIndex = 0;
+    let count = 0;
+    while (count < 3 && pattern.exec(text)) count++;
+    score += weight * count;
+  }
+  return Math.min(WEIGHTS.contentCap, score);
+}
+
+// ---------------------------------------------------------------------------
+// Shebangs and modelines
+// ---------------------------------------------------------------------------
+
+const SHELLS = new Set(['sh', 'bash', 'zsh', 'ksh', 'dash', 'ash', 'mksh']);
+
+function interpreterLanguage(interpreter) {
+  const name = path.basename(interpreter).toLowerCase();
+  // Versioned interpreters: python3.11, ruby2.7, php8.1, perl5.36
+  return BY_INTERPRETER.get(name) || BY_INTERPRETER.get(name.replace(/[\d.]+$/, '')) || null;
+}
+
+/**
+ * The interpreter after `env` and its options (-S, -i, -u NAME, NAME=value assignments)
+ */
+function skipEnv(words) {
+  let i = 0;
+  while (i < words.length) {
+    const word = words[i];
+    if (word === '-S' || word === '--split-string' || word === '-i' || word === '--ignore-environment') i++;
+    else if (word === '-u' || word === '--unset' || word === '-C' || word === '--chdir') i += 2;
+    else if (/^-S./.test(word)) return [word.slice(2), ...words.slice(i + 1)]; // -Snode --flag
+    else if (word.startsWith('-') || /^\w+=/.test(word)) i++;
+    else break;
+  }
+  return words.slice(i);
+}
+
+/**
+ * Parse a `#!` interpreter line. Looks through env (with -S and assignments), version suffixes,
+ * nix-shell's `#! nix-shell -i python3` second line and shell trampolines that re-exec the
+ * script under another interpreter (`exec tclsh "$0" "$@"`, `"exec" "python3" "$0" "$@"`)
+ * @param {string} text - File content (only the first lines are read)
+ * @returns {object|null} { interpreter, args, language, via: 'direct'|'env'|'nix-shell'|'exec' },
+ *   language null when the interpreter isn't known; null without a shebang
+ */
+function parseShebang(text) {
+  const lines = String(text || '')
+    .replace(/^\uFEFF/, '')
+    .split('\n', 8)
+    .map((line) => line.replace(/\r$/, ''));
+  const match = /^#!\s*(.*)$/.exec(lines[0]);
+  if (!match || !match[1].trim()) return null;
+  let [interpreter, ...args] = match[1].trim().split(/\s+/);
+  let via = 'direct';
+  if (path.basename(interpreter) === 'env') {
+    [interpreter, ...args] = skipEnv(args);
+    if (!interpreter) return null;
+    via = 'env';
+  }
+  interpreter = path.basename(interpreter);
+
+  if (interpreter === 'nix-shell') {
+    for (const line of lines.slice(1)) {
+      const option = /^#!\s*nix-shell\b.*?\s-i\s+(\S+)/.exec(line);
+      if (option) {
+        return { interpreter: option[1], args, language: interpreterLanguage(option[1]), via: 'nix-shell' };
+      }
+    }
+  }
+  if (SHELLS.has(interpreter.toLowerCase())) {
+    // A shell line that hands the file itself ("$0") to another interpreter
+    for (const line of lines.slice(1)) {
+      const exec = /\bexec['"]*\s+['"]?(?:\/usr\/bin\/env\s+)?([\w./+-]+)['"]?[^\n]*["']?\$0\b/.exec(line);
+      const language = exec && interpreterLanguage(exec[1]);
+      if (language && language !== 'shell') {
+        return { interpreter: path.basename(exec[1]), args: [], language, via: 'exec' };
+      }
+    }
+  }
+  return { interpreter, args, language: interpreterLanguage(interpreter), via };
+}
+
+const EMACS_LOCAL_VARIABLES = /^(.*?)Local Variables:\s*$([\s\S]*?)^\1End:/im;
+
+/**
+ * Parse an editor modeline: Emacs `-*- mode: ruby -*-` / `-*- ruby -*-` on the first line (the
+ * second after a shebang) or a `mode:` in a trailing Local Variables block, or a Vim/vi/ex
+ * `vim: set ft=python :` / `vim: syntax=sh` line among the first and last five
+ * @param {string} text - File content
+ * @returns {object|null} { editor: 'emacs'|'vim', mode, language }, language null when the mode
+ *   isn't a known language; null without a modeline naming a mode
+ */
+function parseModeline(text) {
+  const lines = String(text || '')
+    .replace(/^\uFEFF/, '')
+    .replace(/\r\n?/g, '\n')
+    .split('\n');
+  const result = (editor, mode) => ({ editor, mode, language: resolveLanguageName(mode) });
+
+  const first = lines[0].startsWith('#!') ? lines[1] || '' : lines[0];
+  const emacs = /-\*-(.*?)-\*-/.exec(first);
+  if (emacs) {
+    const body = emacs[1].trim();
+    const mode = body.includes(':') ? /(?:^|;)\s*mode\s*:\s*([\w+#-]+)/i.exec(body)?.[1] : body;
+    if (mode && /^[\w+#-]+$/.test(mode)) return result('emacs', mode);
+  }
+
+  const tail = lines.slice(-200).join('\n').slice(-3000);
+  const block = EMACS_LOCAL_VARIABLES.exec(tail);
+  if (block) {
+    const mode = new RegExp(`^${escapeRegExp(block[1])}\\s*mode\\s*:\\s*([\\w+#-]+)`, 'im').exec(block[2]);
+    if (mode) return result('emacs', mode[1]);
+  }
+
+  const ends = lines.length > 10 ? [...lines.slice(0, 5), ...lines.slice(-5)] : lines;
+  for (const line of ends) {
+    // "vim: set ft=python :", "vi: ts=4 filetype=sh", "vim600: syn=perl", " ex: ft=lua"
+    const vim = /(?:^|\s)(?:vim?(?:[<=>]?\d+)?|ex):\s*(?:set?\s+)?(.*)$/.exec(line);
+    const mode = vim && /(?:^|[\s:])(?:ft|filetype|syn|syntax)=([\w+#-]+)/.exec(vim[1]);
+    if (mode) return result('vim', mode[1]);
+  }
+  return null;
+}
+
+// ---------------------------------------------------------------------------
+// Detection
+// ---------------------------------------------------------------------------
+
+/**
+ * Rank the languages a file could be written in
+ * @param {string} content - File content (may be null when only the path is known)
+ * @param {string} filename - File name or path
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Array<object>} [{ language, confidence, signals: ['modeline'|'filename'|'shebang'|
+ *   'extension'|'content'] }], most likely first; empty when nothing points anywhere
+ */
+function detectLanguages(content, filename = null, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const text = String(content ?? '')
+    .slice(0, opts.maxContentBytes)
+    .replace(/\r\n?/g, '\n');
+  const name = path.basename(String(filename || '').replace(/\\/g, '/')).toLowerCase();
+  const candidates = new Map(); // language -> { score, signals }
+  const add = (language, weight, signal) => {
+    if (!language || weight <= 0) return;
+    const entry = candidates.get(language) || { score: 0, signals: [] };
+    entry.score += weight;
+    if (!entry.signals.includes(signal)) entry.signals.push(signal);
+    candidates.set(language, entry);
+  };
+
+  add(parseModeline(text)?.language, WEIGHTS.modeline, 'modeline');
+  add(parseShebang(text)?.language, WEIGHTS.shebang, 'shebang');
+  if (name) {
+    let byName = BY_FILENAME.get(name);
+    if (!byName && /^\.env(?:\.|$)/.test(name)) byName = 'dotenv';
+    if (!byName && /^(?:docker|container)file\./.test(name)) byName = 'dockerfile';
+    add(byName, WEIGHTS.filename, 'filename');
+    const ext = path.extname(name).slice(1);
+    if (!byName && ext) {
+      const languages = BY_EXTENSION.get(ext) || [];
+      for (const language of languages) add(language, WEIGHTS.extension / languages.length, 'extension');
+    }
+  }
+
+  // Content: among the candidates when there are any, otherwise across every model
+  if (text.trim()) {
+    const pool = candidates.size ? [...candidates.keys()] : Object.keys(CONTENT_MODELS);
+    for (const language of pool) {
+      const score = contentScore(language, text);
+      if (candidates.size === 0 && score < MIN_CONTENT_ONLY) continue;
+      add(language, score, 'content');
+    }
+  }
+
+  const total = [...candidates.values()].reduce((sum, entry) => sum + entry.score, 0) + WEIGHTS.unexplained;
+  return [...candidates.entries()]
+    .map(([language, entry]) => ({
+      language,
+      confidence: Math.round((entry.score / total) * 1000) / 1000,
+      signals: entry.signals,
+    }))
+    .filter((entry) => entry.confidence >= opts.minConfidence)
+    .sort((a, b) => b.confidence - a.confidence)
+    .slice(0, opts.maxResults);
+}
+
+/**
+ * Languages a file extension can mean (empty when unknown)
+ */
+function languagesForExtension(ext) {
+  return [...(BY_EXTENSION.get(String(ext || '').replace(/^\./, '').toLowerCase()) || [])];
+}
+
+module.exports = instrument('CODE', {
+  detectLanguages,
+  parseShebang,
+  parseModeline,
+  resolveLanguageName,
+  languagesForExtension,
+  LANGUAGES,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,653 @@
+/**
+ * Code Lexer
+ * Splits source text into code, comment and string segments using each
+ * language's comment and string syntax, so analysis never mistakes a `#`
+ * inside a Rust string for a comment or stops a block comment at the end of
+ * its first line.
+ *
+ * This is a lexer, not a parser: it knows line and block comments (nested
+ * where the language nests them), doc comments, docstrings, and the string
+ * forms that can hide comment markers (escapes, raw strings, triple quotes,
+ * doubled quotes, JavaScript regex literals). Unknown languages use a generic //, # and slash-star
+ * syntax, which matches how file stats treated everything before.
+ *
+ * Segments: { type: 'code' | 'comment' | 'string', start, end, doc? }
+ * with end exclusive; they cover the input without gaps.
+ */
+
+const { escapeRegExp } = require('./regexp');
+
+// ---------------------------------------------------------------------------
+// Rule builders
+// ---------------------------------------------------------------------------
+
+const line = (open, extra = {}) => ({ type: 'comment', kind: 'line', open, ...extra });
+const block = (open, close, extra = {}) => ({ type: 'comment', kind: 'block', open, close, ...extra });
+const str = (open, extra = {}) => ({ type: 'string', open, close: open, escape: true, ...extra });
+const pattern = (type, opener, regex, extra = {}) => ({ type, opener, pattern: regex, ...extra });
+
+// A rule with `after` only applies when the preceding character matches it
+const WORD_BOUNDARY = /[\s;&|(){}]/;
+const NOT_ESCAPED = /[^\\]/;
+
+const C_DOC_BLOCK = /\/\*(?:\*(?![*/])|!)/y;
+const C_DOC_LINE = /\/\/(?:\/(?!\/)|!)/y;
+
+const C_COMMENTS = [block('/*', '*/', { doc: C_DOC_BLOCK }), line('//', { doc: C_DOC_LINE })];
+const NESTED_C_COMMENTS = [
+  block('/*', '*/', { doc: C_DOC_BLOCK, nested: true }),
+  line('//', { doc: C_DOC_LINE }),
+];
+const C_STRINGS = [str('"'), str("'")];
+const HASH = [line('#', { after: WORD_BOUNDARY })];
+const MARKUP = [block('<!--', '-->')];
+
+const TRIPLE = (quote, extra = {}) => str(quote.repeat(3), { multiline: true, ...extra });
+
+// A slash starts a regex literal only where an expression can start: at the start of a line, after
+// an operator or opening bracket, or after a keyword like return; elsewhere it divides
+const JS_REGEX_OPENER =
+  '(?<=(?:^|[\\n(\\[{,;:=!&|?+\\-*%~^]|=>|\\b(?:return|typeof|case|do|else|in|of|new|delete|void|' +
+  'throw|yield|await))[ \\t]*)/(?![*/])';
+const JS_REGEX = /\/(?![*/])(?:\\.|\[(?:\\.|[^\]\\\n])*\]|[^/\\\n[])+\/[a-z]*/y;
+
+const JS = [
+  ...C_COMMENTS,
+  str('"'),
+  str("'"),
+  str('`', { multiline: true }),
+  pattern('string', JS_REGEX_OPENER, JS_REGEX),
+];
+
+const SYNTAX = {
+  javascript: JS,
+  typescript: JS,
+  c: [...C_COMMENTS, ...C_STRINGS],
+  cpp: [
+    ...C_COMMENTS,
+    pattern('string', '(?<![\\w])(?:u8|u|U|L)?R"', /(?:u8|u|U|L)?R"([^()\\\s]{0,16})\([\s\S]*?\)\1"/y),
+    ...C_STRINGS,
+  ],
+  csharp: [
+    ...C_COMMENTS,
+    TRIPLE('"'),
+    pattern('string', '\\$?@\\$?"', /\$?@\$?"(?:[^"]|"")*"/y),
+    ...C_STRINGS,
+  ],
+  java: [...C_COMMENTS, TRIPLE('"'), ...C_STRINGS],
+  go: [...C_COMMENTS, ...C_STRINGS, str('`', { escape: false, multiline: true })],
+  rust: [
+    ...NESTED_C_COMMENTS,
+    pattern('string', '(?<![\\w])b?r#*"', /b?r(#*)"[\s\S]*?"\1/y),
+    str('"', { multiline: true }),
+    // Char literals only; a lone quote is a lifetime
+    pattern('string', "'", /'(?:\\(?:u\{[0-9a-fA-F]{1,6}\}|x[0-9a-fA-F]{2}|.)|[^\\'\n])'/y),
+  ],
+  swift: [...NESTED_C_COMMENTS, TRIPLE('"'), str('"')],
+  kotlin: [...NESTED_C_COMMENTS, TRIPLE('"', { escape: false }), ...C_STRINGS],
+  scala: [...NESTED_C_COMMENTS, TRIPLE('"', { escape: false }), ...C_STRINGS],
+  dart: [...NESTED_C_COMMENTS, TRIPLE('"'), TRIPLE("'"), ...C_STRINGS],
+  zig: [line('//', { doc: /\/\/[/!](?!\/)/y }), str('"'), str("'")],
+  php: [
+    ...C_COMMENTS,
+    line('#', { after: /[^\w$]/ }),
+    str('"', { multiline: true }),
+    str("'", { multiline: true }),
+  ],
+  python: [...HASH, TRIPLE('"', { docstring: true }), TRIPLE("'", { docstring: true }), str('"'), str("'")],
+  ruby: [
+    block('=begin', '\n=end', { lineStart: true, closeToLineEnd: true }),
+    ...HASH,
+    str('"', { multiline: true }),
+    str("'", { multiline: true }),
+  ],
+  perl: [...HASH, str('"', { multiline: true }), str("'", { multiline: true })],
+  shell: [...HASH, str('"', { multiline: true }), str("'", { escape: false, multiline: true })],
+  powershell: [
+    block('<#', '#>'),
+    ...HASH,
+    str('"', { multiline: true }),
+    str("'", { escape: false, doubled: true, multiline: true }),
+  ],
+  r: [...HASH, str('"', { multiline: true }), str("'", { multiline: true })],
+  julia: [block('#=', '=#', { nested: true }), line('#'), TRIPLE('"', { docstring: true }), str('"')],
+  nim: [block('#[', ']#', { nested: true }), line('#'), TRIPLE('"', { escape: false }), str('"')],
+  elixir: [line('#'), TRIPLE('"'), TRIPLE("'"), str('"', { multiline: true }), str("'", { multiline: true })],
+  yaml: [...HASH, str('"'), str("'", { escape: false, doubled: true })],
+  toml: [line('#'), TRIPLE('"'), TRIPLE("'", { escape: false }), str('"'), str("'", { escape: false })],
+  ini: [line(';', { lineStart: true }), line('#', { lineStart: true })],
+  dockerfile: [line('#', { lineStart: true })],
+  makefile: [line('#', { after: NOT_ESCAPED })],
+  hcl: [...C_COMMENTS, line('#'), str('"')],
+  nix: [block('/*', '*/'), line('#'), str("''", { close: "''", escape: false, multiline: true }), str('"')],
+  graphql: [line('#'), TRIPLE('"', { docstring: true }), str('"')],
+  sql: [
+    block('/*', '*/'),
+    line('--'),
+    str("'", { escape: false, doubled: true, multiline: true }),
+    str('"', { escape: false, doubled: true }),
+  ],
+  lua: [
+    pattern('comment', '--\\[=*\\[', /--\[(=*)\[[\s\S]*?(?:\]\1\]|$)/y),
+    line('--', { doc: /---(?!-)/y }),
+    pattern('string', '\\[=*\\[', /\[(=*)\[[\s\S]*?\]\1\]/y),
+    str('"'),
+    str("'"),
+  ],
+  haskell: [
+    block('{-', '-}', { nested: true, doc: /\{-\s*[|^]/y }),
+    line('--', { doc: /--\s*[|^]/y }),
+    str('"'),
+  ],
+  ocaml: [block('(*', '*)', { nested: true, doc: /\(\*\*(?!\))/y }), str('"', { multiline: true })],
+  fsharp: [block('(*', '*)', { nested: true }), line('//', { doc: C_DOC_LINE }), TRIPLE('"'), str('"')],
+  lisp: [block('#|', '|#', { nested: true }), line(';'), str('"', { multiline: true })],
+  clojure: [line(';'), str('"', { multiline: true })],
+  erlang: [line('%'), str('"', { multiline: true })],
+  latex: [line('%', { after: NOT_ESCAPED })],
+  matlab: [line('%'), str('"'), str("'", { escape: false, doubled: true, after: /[\s=,;([{]/ })],
+  fortran: [
+    line('!'),
+    str('"', { escape: false, doubled: true }),
+    str("'", { escape: false, doubled: true }),
+  ],
+  vb: [line("'"), str('"', { escape: false, doubled: true })],
+  assembly: [line(';'), line('#', { lineStart: true }), str('"')],
+  html: MARKUP,
+  xml: [...MARKUP, pattern('string', '<!\\[CDATA\\[', /<!\[CDATA\[[\s\S]*?(?:\]\]>|$)/y)],
+  markdown: MARKUP,
+  css: [block('/*', '*/'), ...C_STRINGS],
+  scss: [block('/*', '*/'), line('//', { after: /[^:]/ }), ...C_STRINGS],
+  json: [str('"')],
+  jsonc: [...C_COMMENTS, str('"')],
+  text: [],
+  unknown: [block('/*', '*/'), line('//'), line('#', { after: WORD_BOUNDARY }), str('"'), str("'")],
+};
+
+const ALIASES = {
+  js: 'javascript',
+  jsx: 'javascript',
+  mjs: 'javascript',
+  cjs: 'javascript',
+  ts: 'typescript',
+  tsx: 'typescript',
+  mts: 'typescript',
+  cts: 'typescript',
+  'objective-c': 'c',
+  objc: 'c',
+  h: 'c',
+  'c++': 'cpp',
+  cc: 'cpp',
+  hpp: 'cpp',
+  hh: 'cpp',
+  hxx: 'cpp',
+  cxx: 'cpp',
+  cs: 'csharp',
+  'c#': 'csharp',
+  groovy: 'java',
+  solidity: 'javascript',
+  protobuf: 'c',
+  proto: 'c',
+  rs: 'rust',
+  py: 'python',
+  pyi: 'python',
+  rb: 'ruby',
+  kt: 'kotlin',
+  kts: 'kotlin',
+  pl: 'perl',
+  sh: 'shell',
+  bash: 'shell',
+  zsh: 'shell',
+  fish: 'shell',
+  ps1: 'powershell',
+  ex: 'elixir',
+  exs: 'elixir',
+  yml: 'yaml',
+  terraform: 'hcl',
+  tf: 'hcl',
+  mysql: 'sql',
+  postgresql: 'sql',
+  plsql: 'sql',
+  elm: 'haskell',
+  purescript: 'haskell',
+  ml: 'ocaml',
+  fs: 'fsharp',
+  'common-lisp': 'lisp',
+  scheme: 'lisp',
+  racket: 'lisp',
+  emacs: 'lisp',
+  'emacs-lisp': 'lisp',
+  elisp: 'lisp',
+  tex: 'latex',
+  octave: 'matlab',
+  vbnet: 'vb',
+  asm: 'assembly',
+  nasm: 'assembly',
+  htm: 'html',
+  vue: 'html',
+  svelte: 'html',
+  svg: 'xml',
+  md: 'markdown',
+  less: 'scss',
+  sass: 'scss',
+  json5: 'jsonc',
+  plaintext: 'text',
+  txt: 'text',
+  restructuredtext: 'text',
+  asciidoc: 'text',
+  org: 'text',
+  make: 'makefile',
+  docker: 'dockerfile',
+  cfg: 'ini',
+  properties: 'ini',
+};
+
+// Keywords per language; tokenize() marks these as 'keyword' rather than 'identifier'
+const words = (text) => new Set(text.split(/\s+/).filter(Boolean));
+const C_KEYWORDS =
+  'auto break case char const continue default do double else enum extern float for goto if inline ' +
+  'int long register restrict return short signed sizeof static struct switch typedef union ' +
+  'unsigned void volatile while';
+const KEYWORDS = {
+  javascript: words(
+    'async await break case catch class const continue debugger default delete do else export ' +
+      'extends false finally for from function if import in instanceof let new null of return ' +
+      'static super switch this throw true try typeof undefined var void while with yield'
+  ),
+  python: words(
+    'False None True and as assert async await break class continue def del elif else except ' +
+      'finally for from global if import in is lambda match case nonlocal not or pass raise return ' +
+      'try while with yield'
+  ),
+  rust: words(
+    'as async await break const continue crate dyn else enum extern false fn for if impl in let ' +
+      'loop match mod move mut pub ref return self Self static struct super trait true type unsafe ' +
+      'use where while'
+  ),
+  go: words(
+    'break case chan const continue default defer else fallthrough for func go goto if import ' +
+      'interface map package range return select struct switch type var nil true false iota'
+  ),
+  java: words(
+    'abstract assert boolean break byte case catch char class const continue default do double ' +
+      'else enum extends final finally float for goto if implements import instanceof int interface ' +
+      'long native new package private protected public return short static strictfp super switch ' +
+      'synchronized this throw throws transient try void volatile while var record yield true false ' +
+      'null'
+  ),
+  c: words(C_KEYWORDS),
+  cpp: words(
+    '`${C_KEYWORDS} bool catch class constexpr delete explicit false friend mutable namespace new ' +
+      'noexcept ` + nullptr operator override private protected public template this throw true try ' +
+      'typename using virtual'
+  ),
+  csharp: words(
+    'abstract as async await base bool break byte case catch char checked class const continue ' +
+      'decimal default delegate do double else enum event explicit extern false finally fixed float ' +
+      'for foreach goto if implicit in int interface internal is lock long namespace new null ' +
+      'object operator out override params private protected public readonly record ref return ' +
+      'sealed short sizeof static string struct switch this throw true try typeof uint ulong using ' +
+      'var virtual void volatile while yield'
+  ),
+  ruby: words(
+    'BEGIN END alias and begin break case class def defined? do else elsif end ensure false for ' +
+      'if in module next nil not or redo rescue retry return self super then true undef unless ' +
+      'until when while yield'
+  ),
+  php: words(
+    'abstract and array as break callable case catch class clone const continue declare default ' +
+      'do echo else elseif empty enum extends final finally fn for foreach function global if ' +
+      'implements include instanceof interface isset list match namespace new null or print private ' +
+      'protected public readonly require return static switch throw trait true false try unset use ' +
+      'var while yield'
+  ),
+  swift: words(
+    'associatedtype as break case catch class continue default defer deinit do else enum ' +
+      'extension fallthrough false fileprivate for func guard if import in init inout internal is ' +
+      'let nil open operator private protocol public repeat return self Self static struct ' +
+      'subscript super switch throw throws true try typealias var where while async await'
+  ),
+  kotlin: words(
+    'as break class continue do else false for fun if in interface is null object package return ' +
+      'super this throw true try typealias typeof val var when while by catch constructor finally ' +
+      'get import init set where abstract companion data enum final inline internal open override ' +
+      'private protected public sealed suspend'
+  ),
+  shell: words(
+    'if then else elif fi case esac for select while until do done in function time return local ' +
+      'export readonly declare'
+  ),
+};
+KEYWORDS.typescript = new Set([
+  ...KEYWORDS.javascript,
+  ...words('abstract any as boolean declare enum implements interface keyof namespace never number private ' +
+    'protected public readonly string symbol type unknown'),
+]);
+KEYWORDS.scala = KEYWORDS.kotlin;
+KEYWORDS.dart = KEYWORDS.java;
+
+const compiled = new Map();
+
+/**
+ * Resolve a language name (or alias) to its compiled syntax
+ * @returns {object} { language, rules, finder }
+ */
+function getSyntax(language) {
+  const key = String(language || 'unknown').toLowerCase();
+  const name = SYNTAX[key] ? key : ALIASES[key] || 'unknown';
+  if (!compiled.has(name)) {
+    const rules = SYNTAX[name];
+    const source = rules.map((rule) => rule.opener || escapeRegExp(rule.open)).join('|');
+    compiled.set(name, { language: name, rules, finder: rules.length ? new RegExp(source, 'g') : null });
+  }
+  return compiled.get(name);
+}
+
+function isKeyword(language, word) {
+  const keywords = KEYWORDS[getSyntax(language).language];
+  return Boolean(keywords && keywords.has(word));
+}
+
+function hasCommentSyntax(language) {
+  return getSyntax(language).rules.some((rule) => rule.type === 'comment');
+}
+
+// ---------------------------------------------------------------------------
+// Scanning
+// ---------------------------------------------------------------------------
+
+function lineEnd(content, from) {
+  const end = content.indexOf('\n', from);
+  return end === -1 ? content.length : end;
+}
+
+// Block and string scanners return { end, closed }; closed is false when the
+// content ends (or, for single-line strings, the line ends) before the closer
+
+function scanBlock(content, at, rule) {
+  let depth = 1;
+  let i = at + rule.open.length;
+  while (i < content.length) {
+    if (content.startsWith(rule.close, i)) {
+      i += rule.close.length;
+      if (--depth === 0) return { end: rule.closeToLineEnd ? lineEnd(content, i) : i, closed: true };
+    } else if (rule.nested && content.startsWith(rule.open, i)) {
+      depth++;
+      i += rule.open.length;
+    } else {
+      i++;
+    }
+  }
+  return { end: content.length, closed: false }; // Unterminated: runs to the end
+}
+
+function scanString(content, at, rule) {
+  const close = rule.close;
+  let i = at + rule.open.length;
+  while (i < content.length) {
+    const ch = content[i];
+    if (rule.escape && ch === '\\') {
+      i += 2;
+    } else if (content.startsWith(close, i)) {
+      if (!rule.doubled || !content.startsWith(close, i + close.length)) {
+        return { end: i + close.length, closed: true };
+      }
+      i += close.length * 2;
+    } else if (ch === '\n' && !rule.multiline) {
+      return { end: i, closed: false }; // Unterminated single-line string ends at the line break
+    } else {
+      i++;
+    }
+  }
+  return { end: Math.min(i, content.length), closed: false };
+}
+
+function testSticky(regex, content, at) {
+  regex.lastIndex = at;
+  const match = regex.exec(content);
+  return match ? match[0].length : -1;
+}
+
+/**
+ * A docstring is a string that is the only thing on its lines, e.g. a
+ * Python triple-quoted string opening a module, class or function body
+ */
+function isDocstring(content, start, end) {
+  const lineStart = content.lastIndexOf('\n', start - 1) + 1;
+  if (!/^[ \t]*[rRuUbBfF]{0,2}$/.test(content.slice(lineStart, start))) return false;
+  return /^[ \t]*(?:\r?\n|$)/.test(content.slice(end, lineEnd(content, end) + 1));
+}
+
+function matchRule(syntax, content, at) {
+  for (const rule of syntax.rules) {
+    if (at > 0) {
+      const previous = content[at - 1];
+      if (rule.lineStart && previous !== '\n') continue;
+      if (rule.after && !rule.after.test(previous)) continue;
+    }
+
+    let end;
+    let closed = true;
+    if (rule.pattern) {
+      const length = testSticky(rule.pattern, content, at);
+      if (length < 0) continue;
+      end = at + length;
+    } else {
+      if (!content.startsWith(rule.open, at)) continue;
+      if (rule.kind === 'line') {
+        end = lineEnd(content, at);
+      } else {
+        const scanned = rule.type === 'string' ? scanString(content, at, rule) : scanBlock(content, at, rule);
+        ({ end, closed } = scanned);
+      }
+    }
+
+    if (rule.docstring && isDocstring(content, at, end)) {
+      return { type: 'comment', start: at, end, doc: true, ...(closed ? {} : { unterminated: true }) };
+    }
+    const segment = { type: rule.type, start: at, end };
+    if (rule.doc && testSticky(rule.doc, content, at) > 0) segment.doc = true;
+    if (!closed) segment.unterminated = true;
+    return segment;
+  }
+  return null;
+}
+
+/**
+ * Split content into code, comment and string segments
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias (see SYNTAX / ALIASES); unknown uses a generic syntax
+ * @returns {Array<object>} [{ type, start, end, doc?, unterminated? }]
+ */
+function scanCode(content, language) {
+  const syntax = getSyntax(language);
+  const { finder } = syntax;
+  const segments = [];
+  let codeStart = 0;
+
+  if (finder) {
+    let i = 0;
+    for (;;) {
+      finder.lastIndex = i;
+      const found = finder.exec(content);
+      if (!found) break;
+      const segment = matchRule(syntax, content, found.index);
+      if (!segment) {
+        i = found.index + 1;
+        continue;
+      }
+      if (segment.start > codeStart) segments.push({ type: 'code', start: codeStart, end: segment.start });
+      segments.push(segment);
+      i = codeStart = Math.max(segment.end, segment.start + 1);
+    }
+  }
+  if (codeStart < content.length) segments.push({ type: 'code', start: codeStart, end: content.length });
+  return segments;
+}
+
+/**
+ * Count comment and code lines and characters
+ * A line is a comment line when it has comment text but no code; string
+ * literals count as code. Characters exclude whitespace on both sides, so
+ * indentation doesn't skew the ratio.
+ * @returns {object} { language, codeLines, commentLines, docCommentLines, mixedLines, codeChars,
+ *   commentChars, docChars }
+ */
+function measureComments(content, language) {
+  const segments = scanCode(content, language);
+  const stats = {
+    language: getSyntax(language).language,
+    codeLines: 0,
+    commentLines: 0,
+    docCommentLines: 0,
+    mixedLines: 0,
+    codeChars: 0,
+    commentChars: 0,
+    docChars: 0,
+  };
+
+  let hasCode = false;
+  let hasComment = false;
+  let hasDoc = false;
+  const endLine = () => {
+    if (hasCode && hasComment) stats.mixedLines++;
+    if (hasCode) stats.codeLines++;
+    else if (hasComment) {
+      stats.commentLines++;
+      if (hasDoc) stats.docCommentLines++;
+    }
+    hasCode = hasComment = hasDoc = false;
+  };
+
+  for (const segment of segments) {
+    const comment = segment.type === 'comment';
+    for (let i = segment.start; i < segment.end; i++) {
+      const code = content.charCodeAt(i);
+      if (code === 10) {
+        endLine();
+        continue;
+      }
+      if (code === 32 || code === 9 || code === 13) continue;
+      if (comment) {
+        hasComment = true;
+        stats.commentChars++;
+        if (segment.doc) {
+          hasDoc = true;
+          stats.docChars++;
+        }
+      } else {
+        hasCode = true;
+        stats.codeChars++;
+      }
+    }
+  }
+  endLine();
+  return stats;
+}
+
+/**
+ * Content with comments removed (replaced by spaces, keeping line breaks),
+ * so offsets and line numbers still line up with the original
+ */
+function stripComments(content, language) {
+  let result = '';
+  for (const segment of scanCode(content, language)) {
+    const text = content.slice(segment.start, segment.end);
+    result += segment.type === 'comment' ? text.replace(/[^\n]/g, ' ') : text;
+  }
+  return result;
+}
+
+// ---------------------------------------------------------------------------
+// Tokens
+// ---------------------------------------------------------------------------
+
+// Numbers, identifiers, multi-char operators (longest first), then any other character
+const TOKEN = new RegExp(
+  [
+    /0[xXbBoO][0-9a-fA-F_]+[a-zA-Z0-9]*/.source,
+    /(?:\d[\d_]*\.?[\d_]*|\.\d[\d_]*)(?:[eE][+-]?\d+)?[a-zA-Z0-9]*/.source,
+    /[\p{L}_$][\p{L}\p{N}_$]*[?!]?/.source,
+    />>>=|<<=|>>=|>>>|===|!==|\*\*=|\.\.\.|\.\.=|\/\/=|&&=|\|\|=|\?\?=|<=>/.source,
+    /==|!=|<=|>=|=>|->|::|&&|\|\||\+\+|--|\+=|-=|\*=|\/=|%=|&=|\|=|\^=|<<|>>|\*\*|\?\?|\?\.|\.\.|:=|<-|\/\//
+      .source,
+    /\S/.source,
+  ].join('|'),
+  'gu'
+);
+const OPENERS = new Set(['(', '[', '{']);
+const CLOSERS = new Set([')', ']', '}']);
+
+function tokenType(language, value) {
+  const first = value.charCodeAt(0);
+  if (first >= 48 && first <= 57) return 'number';
+  if (first === 46 && value.length > 1 && /\d/.test(value[1])) return 'number';
+  if (/^[\p{L}_$]/u.test(value)) {
+    return isKeyword(language, value) ? 'keyword' : 'identifier';
+  }
+  if (OPENERS.has(value) || CLOSERS.has(value)) return 'bracket';
+  if (value === ',' || value === ';') return 'punctuation';
+  return 'operator';
+}
+
+/**
+ * Split content into tokens, skipping whitespace and comments
+ * String literals (including raw and multi-line ones) are single tokens.
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias
+ * @returns {Array<object>} [{ type: 'keyword' | 'identifier' | 'number' | 'string' | 'operator' | 'bracket' |
+ *   'punctuation', value, start, line }] with 1-based lines
+ */
+function tokenize(content, language) {
+  const trailingMarks = getSyntax(language).language === 'ruby';
+  const tokens = [];
+  let line = 1;
+  let counted = 0; // Offset up to which newlines are counted into `line`
+  const lineAt = (offset) => {
+    for (; counted < offset; counted++) {
+      if (content.charCodeAt(counted) === 10) line++;
+    }
+    return line;
+  };
+
+  for (const segment of scanCode(content, language)) {
+    if (segment.type === 'comment') continue;
+    if (segment.type === 'string') {
+      tokens.push({
+        type: 'string',
+        value: content.slice(segment.start, segment.end),
+        start: segment.start,
+        line: lineAt(segment.start),
+      });
+      continue;
+    }
+    TOKEN.lastIndex = segment.start;
+    let match;
+    while ((match = TOKEN.exec(content)) && match.index < segment.end) {
+      let value = match[0];
+      if (match.index + value.length > segment.end) value = value.slice(0, segment.end - match.index);
+      // Ruby-style trailing ? / ! only belongs to identifiers in Ruby
+      if (!trailingMarks && /[?!]$/.test(value) && value.length > 1 && /^[\p{L}_$]/u.test(value)) {
+        value = value.slice(0, -1);
+        TOKEN.lastIndex = match.index + value.length;
+      }
+      tokens.push({ type: tokenType(language, value), value, start: match.index, line: lineAt(match.index) });
+    }
+  }
+  return tokens;
+}
+
+function supportedLanguages() {
+  return Object.keys(SYNTAX).filter((name) => name !== 'unknown');
+}
+
+module.exports = {
+  scanCode,
+  tokenize,
+  measureComments,
+  stripComments,
+  getSyntax,
+  isKeyword,
+  hasCommentSyntax,
+  supportedLanguages,
+};
@@ -0,0 +1,265 @@
+/**
+ * Code Metrics
+ * Size and complexity measures computed from code-lexer tokens, for the
+ * "edit effort" models.
+ *
+ * Halstead counts follow the usual convention: keywords, operators,
+ * punctuation and bracket pairs are operators; identifiers and literals
+ * (including literal keywords such as true / None / nil) are operands.
+ * A bracket pair counts once, as its opening bracket.
+ *
+ * Documentation coverage checks each declaration from code-symbols for a doc
+ * comment directly above it (above its attributes / decorators), or for
+ * Python a docstring as the first statement of its body.
+ */
+
+const { tokenize, getSyntax, scanCode } = require('./code-lexer');
+const { parseSymbols, attributesStart } = require('./code-symbols');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  histogramSize: 100, // Most frequent tokens returned in the histogram; Infinity for all
+  docKinds: ['class', 'interface', 'struct', 'enum', 'trait', 'type', 'module', 'function', 'method'],
+  exportedOnly: false, // Only count exported / public declarations
+  plainComments: false, // Count any leading comment as documentation, not just doc syntax
+};
+
+// Languages whose documentation convention is a plain comment above the declaration
+const PLAIN_COMMENT_DOCS = new Set(['go', 'ruby', 'shell', 'perl', 'elixir']);
+
+const LITERAL_KEYWORDS = new Set([
+  'true',
+  'false',
+  'True',
+  'False',
+  'null',
+  'nil',
+  'None',
+  'undefined',
+  'nullptr',
+  'this',
+  'self',
+  'Self',
+  'super',
+  'iota',
+]);
+
+const CLOSERS = new Set([')', ']', '}']);
+
+function classify(token) {
+  switch (token.type) {
+    case 'identifier':
+    case 'number':
+    case 'string':
+      return 'operand';
+    case 'keyword':
+      return LITERAL_KEYWORDS.has(token.value) ? 'operand' : 'operator';
+    case 'bracket':
+      return CLOSERS.has(token.value) ? null : 'operator';
+    default:
+      return 'operator';
+  }
+}
+
+const round = (value) => Math.round(value * 100) / 100;
+const log2 = (value) => (value > 0 ? Math.log2(value) : 0);
+
+/**
+ * Halstead metrics and token frequencies
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias (see code-lexer)
+ * @param {object} options - { histogramSize }
+ * @returns {object} { language, distinctOperators, distinctOperands, totalOperators, totalOperands,
+ *   vocabulary, length, estimatedLength, volume, difficulty, effort, timeSeconds, bugs,
+ *   histogram: [{ token, kind, count }] }
+ */
+function computeHalstead(content, language, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const operators = new Map();
+  const operands = new Map();
+  let totalOperators = 0;
+  let totalOperands = 0;
+
+  for (const token of tokenize(String(content || ''), language)) {
+    const kind = classify(token);
+    if (kind === 'operator') {
+      operators.set(token.value, (operators.get(token.value) || 0) + 1);
+      totalOperators++;
+    } else if (kind === 'operand') {
+      operands.set(token.value, (operands.get(token.value) || 0) + 1);
+      totalOperands++;
+    }
+  }
+
+  const n1 = operators.size;
+  const n2 = operands.size;
+  const vocabulary = n1 + n2;
+  const length = totalOperators + totalOperands;
+  const volume = length * log2(vocabulary);
+  const difficulty = n2 > 0 ? (n1 / 2) * (totalOperands / n2) : 0;
+  const effort = difficulty * volume;
+
+  const histogram = [];
+  for (const [token, count] of operators) histogram.push({ token, kind: 'operator', count });
+  for (const [token, count] of operands) histogram.push({ token, kind: 'operand', count });
+  histogram.sort((a, b) => b.count - a.count || (a.token < b.token ? -1 : 1));
+  if (histogram.length > opts.histogramSize) histogram.length = opts.histogramSize;
+
+  return {
+    language: getSyntax(language).language,
+    distinctOperators: n1,
+    distinctOperands: n2,
+    totalOperators,
+    totalOperands,
+    vocabulary,
+    length,
+    estimatedLength: round(n1 * log2(n1) + n2 * log2(n2)),
+    volume: round(volume),
+    difficulty: round(difficulty),
+    effort: round(effort),
+    timeSeconds: round(effort / 18),
+    bugs: round(volume / 3000),
+    histogram,
+  };
+}
+
+// ---------------------------------------------------------------------------
+// Documentation coverage
+// ---------------------------------------------------------------------------
+
+/**
+ * The doc comment (or docstring) attached to a declaration; the comments
+ * it's made of are added to `claimed`
+ * @returns {object|null} { style: 'doc'|'comment', lines }
+ */
+function docCommentOf(parsed, comments, symbol, opts, claimed) {
+  const { tokens, match, text, language } = parsed;
+
+  if (language === 'python') {
+    // Docstring: the first statement after the header's colon
+    let k = symbol.nameIndex + 1;
+    while (k < tokens.length && tokens[k].value !== ':') k = match[k] > k ? match[k] + 1 : k + 1;
+    const after = tokens[k]?.start ?? text.length;
+    const next = tokens[k + 1]?.start ?? text.length;
+    const docstring = comments.find((c) => c.doc && c.start > after && c.start < next);
+    if (docstring) {
+      return { style: 'doc', lines: text.slice(docstring.start, docstring.end).split('\n').length };
+    }
+  }
+
+  const start = attributesStart(tokens, match, symbol.first);
+  const limit = start > 0 ? tokens[start - 1].start + tokens[start - 1].value.length : 0;
+  const previousLine = start > 0 ? tokens[start - 1].line : 0;
+  let at = tokens[start].start;
+  let style = null;
+  let lines = 0;
+  // Walk up through the adjacent comment block (several `///` lines are separate segments)
+  for (let c = comments.length - 1; c >= 0; c--) {
+    const comment = comments[c];
+    if (comment.end > at) continue;
+    if (comment.start < limit) break;
+    const gap = text.slice(comment.end, at);
+    if ((gap.match(/\n/g) || []).length > 1) break;
+    const body = text.slice(comment.start, comment.end);
+    if (body.startsWith('//!') || body.startsWith('/*!')) break; // Inner doc: the enclosing module's
+    const line = text.slice(0, comment.start).split('\n').length;
+    if (line === previousLine && tokens[start - 1].value !== '{') break; // Trailing comment on a statement
+    const isDoc = comment.doc || PLAIN_COMMENT_DOCS.has(language) || opts.plainComments;
+    if (!isDoc) break;
+    style = style === 'doc' || comment.doc ? 'doc' : 'comment';
+    lines += body.split('\n').length;
+    at = comment.start;
+    claimed.add(comment.start);
+  }
+  return style ? { style, lines } : null;
+}
+
+/**
+ * Which declarations have doc comments, and the share that do
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias (see code-symbols for the supported set)
+ * @param {object} options - { docKinds, exportedOnly, plainComments }
+ * @returns {object} { language, total, documented, coverage (percent), moduleDoc,
+ *   byKind: { kind: { total, documented } }, symbols: [{ kind, name, qualifiedName, line,
+ *   exported, documented, style: 'doc'|'comment'|null, docLines }] }
+ *   Functions nested in other functions aren't counted.
+ */
+function computeDocCoverage(content, language, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const parsed = parseSymbols(content, language);
+  const comments = scanCode(parsed.text, parsed.language).filter((segment) => segment.type === 'comment');
+  const kinds = new Set(opts.docKinds);
+  const byName = new Map(parsed.symbols.map((symbol) => [symbol.qualifiedName, symbol]));
+
+  const symbols = [];
+  const byKind = {};
+  const claimed = new Set();
+  for (const symbol of parsed.symbols) {
+    if (!kinds.has(symbol.kind) || (opts.exportedOnly && !symbol.exported)) continue;
+    const parentKind = byName.get(symbol.parent)?.kind;
+    if (parentKind === 'function' || parentKind === 'method') continue;
+
+    const doc = docCommentOf(parsed, comments, symbol, opts, claimed);
+    symbols.push({
+      kind: symbol.kind,
+      name: symbol.name,
+      qualifiedName: symbol.qualifiedName,
+      line: symbol.startLine,
+      exported: symbol.exported,
+      documented: Boolean(doc),
+      style: doc ? doc.style : null,
+      docLines: doc ? doc.lines : 0,
+    });
+    byKind[symbol.kind] = byKind[symbol.kind] || { total: 0, documented: 0 };
+    byKind[symbol.kind].total++;
+    if (doc) byKind[symbol.kind].documented++;
+  }
+
+  // A doc comment before any code that isn't the first declaration's: Python module docstring,
+  // Rust //!, a file header block
+  const firstCode = parsed.tokens.length ? parsed.tokens[0].start : parsed.text.length;
+  const moduleDoc = comments.some((c) => c.doc && c.start < firstCode && !claimed.has(c.start));
+  const documented = symbols.filter((symbol) => symbol.documented).length;
+  return {
+    language: parsed.language,
+    total: symbols.length,
+    documented,
+    coverage: symbols.length ? round((documented / symbols.length) * 100) : null,
+    moduleDoc,
+    byKind,
+    symbols,
+  };
+}
+
+/**
+ * Documentation an edit added and removed
+ * @returns {object} { before, after, delta (coverage points), added, removed }; added / removed are the
+ *   qualified names of declarations present on both sides that gained or lost their doc comment
+ */
+function diffDocCoverage(before, after, language, options = {}) {
+  const old = computeDocCoverage(before, language, options);
+  const current = computeDocCoverage(after, language, options);
+  const oldDocs = new Map(old.symbols.map((symbol) => [symbol.qualifiedName, symbol.documented]));
+  const added = [];
+  const removed = [];
+  for (const symbol of current.symbols) {
+    if (!oldDocs.has(symbol.qualifiedName)) continue;
+    const was = oldDocs.get(symbol.qualifiedName);
+    if (symbol.documented && !was) added.push(symbol.qualifiedName);
+    else if (!symbol.documented && was) removed.push(symbol.qualifiedName);
+  }
+  return {
+    before: old.coverage,
+    after: current.coverage,
+    delta: old.coverage === null || current.coverage === null ? null : round(current.coverage - old.coverage),
+    added,
+    removed,
+  };
+}
+
+module.exports = instrument('CODE', {
+  computeHalstead,
+  computeDocCoverage,
+  diffDocCoverage,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,1482 @@
+/**
+ * Code Symbols
+ * Structural extraction over code-lexer tokens: classes, interfaces,
+ * structs / enums / traits, functions and methods with line ranges and
+ * nesting, plus each file's imports and exports.
+ *
+ * There's no full parser here, but tokens already have strings and comments
+ * resolved, which is what line regexes got wrong. Three strategies cover the
+ * supported languages:
+ *   brace   declarations by keyword (and, for C-family languages, by the
+ *           `name(...) {` shape), bodies found by bracket matching
+ *   python  `def` / `class` bodies found by indentation
+ *   ruby    `def` / `class` / `module` bodies closed by the matching `end`
+ *
+ * Symbols: { kind, name, qualifiedName, parent, depth, startLine, endLine,
+ * exported, modifiers }, with kind one of class | interface | struct | enum |
+ * trait | impl | module | namespace | type | macro | function | method.
+ * Functions directly inside a class-like symbol are reported as methods.
+ */
+
+const { tokenize, getSyntax } = require('./code-lexer');
+const { hashContent } = require('./content-hash');
+const { instrument } = require('./logger');
+
+const CLASS_LIKE = new Set(['class', 'interface', 'struct', 'enum', 'trait', 'impl']);
+
+// Declaration keyword -> symbol kind, per language
+const DECLARATIONS = {
+  javascript: { class: 'class', function: 'function' },
+  typescript: {
+    class: 'class',
+    interface: 'interface',
+    enum: 'enum',
+    function: 'function',
+    namespace: 'namespace',
+    module: 'namespace',
+    type: 'type',
+  },
+  rust: {
+    fn: 'function',
+    struct: 'struct',
+    enum: 'enum',
+    union: 'struct',
+    trait: 'trait',
+    impl: 'impl',
+    mod: 'module',
+    type: 'type',
+    macro_rules: 'macro',
+  },
+  go: { func: 'function', type: 'type' },
+  java: { class: 'class', interface: 'interface', enum: 'enum', record: 'class' },
+  csharp: {
+    class: 'class',
+    interface: 'interface',
+    enum: 'enum',
+    struct: 'struct',
+    record: 'class',
+    namespace: 'namespace',
+  },
+  c: { struct: 'struct', enum: 'enum', union: 'struct' },
+  cpp: { class: 'class', struct: 'struct', enum: 'enum', union: 'struct', namespace: 'namespace' },
+  swift: {
+    class: 'class',
+    struct: 'struct',
+    enum: 'enum',
+    protocol: 'interface',
+    extension: 'impl',
+    actor: 'class',
+    func: 'function',
+  },
+  kotlin: { class: 'class', interface: 'interface', object: 'class', fun: 'function' },
+  scala: { class: 'class', trait: 'trait', object: 'class', def: 'function' },
+  dart: { class: 'class', enum: 'enum', mixin: 'class', extension: 'impl' },
+  php: {
+    class: 'class',
+    interface: 'interface',
+    trait: 'trait',
+    enum: 'enum',
+    function: 'function',
+    namespace: 'namespace',
+  },
+};
+
+// Languages where `name(...) {` declares a function without a keyword
+const IMPLICIT_FUNCTIONS = new Set(['javascript', 'typescript', 'java', 'csharp', 'c', 'cpp', 'dart']);
+// Languages where `name = (...) => {}` / `name: function () {}` declare functions
+const ASSIGNED_FUNCTIONS = new Set(['javascript', 'typescript']);
+// struct / enum / class also start variable declarations here (`struct point p;`), so they only
+// declare a type when a body follows the name
+const BODY_REQUIRED = new Set(['c', 'cpp']);
+
+const MODIFIERS = new Set([
+  'export',
+  'default',
+  'declare',
+  'pub',
+  'public',
+  'private',
+  'protected',
+  'internal',
+  'fileprivate',
+  'open',
+  'static',
+  'async',
+  'abstract',
+  'final',
+  'sealed',
+  'virtual',
+  'override',
+  'readonly',
+  'unsafe',
+  'const',
+  'extern',
+  'inline',
+  'partial',
+  'data',
+  'suspend',
+  'synchronized',
+  'native',
+  'strictfp',
+  'get',
+  'set',
+  'mut',
+  'enum',
+  'case',
+  'implicit',
+  'lazy',
+  'companion',
+  'inner',
+  'operator',
+  'infix',
+  'tailrec',
+  'external',
+  'mutating',
+  'nonmutating',
+  'required',
+  'convenience',
+  'dynamic',
+  'constexpr',
+  'explicit',
+  'friend',
+  'typename',
+]);
+
+// Tokens that can't precede a keyword-less function declaration
+const NOT_BEFORE_DECLARATION = new Set([
+  '.',
+  '->',
+  '?.',
+  'new',
+  'return',
+  'throw',
+  'await',
+  'yield',
+  'typeof',
+  'else',
+  'case',
+  'in',
+  'of',
+  'do',
+  'delete',
+  'sizeof',
+]);
+
+// Punctuation that can end whatever precedes a keyword-less declaration (a previous
+// statement or block, a return type like `Foo<T>`, `int*`, `Foo&`, a C++ qualifier)
+const DECLARATION_PREFIX = new Set(['{', '}', ';', '>', ']', '*', '&', '::', '~']);
+
+// A prototype without a body needs a return type in front: `int f(void);`, not `f(x);`
+const PROTOTYPE_PREFIX = new Set(['>', '*', '&', ']']);
+
+// ---------------------------------------------------------------------------
+// Token helpers
+// ---------------------------------------------------------------------------
+
+const OPEN_FOR = { ')': '(', ']': '[', '}': '{' };
+
+/**
+ * Pair brackets: match[i] is the index of the bracket closing / opening
+ * tokens[i], or -1. depth[i] is the bracket depth before tokens[i].
+ */
+function matchBrackets(tokens) {
+  const match = new Int32Array(tokens.length).fill(-1);
+  const depth = new Int32Array(tokens.length);
+  const stack = [];
+  tokens.forEach((token, i) => {
+    depth[i] = stack.length;
+    if (token.type !== 'bracket') return;
+    const opener = OPEN_FOR[token.value];
+    if (!opener) {
+      stack.push(i);
+      return;
+    }
+    // Tolerate unbalanced code mid-edit: close the nearest matching opener
+    for (let s = stack.length - 1; s >= 0; s--) {
+      if (tokens[stack[s]].value !== opener) continue;
+      match[stack[s]] = i;
+      match[i] = stack[s];
+      stack.length = s;
+      break;
+    }
+  });
+  return { match, depth };
+}
+
+function lastLine(token) {
+  let line = token.line;
+  for (let i = token.value.indexOf('\n'); i !== -1; i = token.value.indexOf('\n', i + 1)) line++;
+  return line;
+}
+
+const isName = (token) => token && (token.type === 'identifier' || token.type === 'keyword');
+const is = (token, value) => Boolean(token) && token.value === value;
+
+/**
+ * Skip a generic parameter list starting at tokens[i] === '<'
+ * @returns {number} Index after the closing '>' (or i when it isn't one)
+ */
+function skipAngles(tokens, i) {
+  if (!is(tokens[i], '<')) return i;
+  let depth = 0;
+  for (let j = i; j < tokens.length && j < i + 200; j++) {
+    const value = tokens[j].value;
+    if (value === '<') depth++;
+    else if (value === '>') depth--;
+    else if (value === '>>') depth -= 2;
+    else if (value === ';' || value === '{') return i;
+    if (depth <= 0) return j + 1;
+  }
+  return i;
+}
+
+function createSymbol(kind, name, tokens, first, last, extra = {}) {
+  return {
+    kind,
+    name,
+    startLine: tokens[first].line,
+    endLine: lastLine(tokens[last]),
+    first,
+    last,
+    modifiers: [],
+    ...extra,
+  };
+}
+
+// ---------------------------------------------------------------------------
+// Brace languages
+// ---------------------------------------------------------------------------
+
+/**
+ * Collect modifier tokens before a declaration (`pub(crate) async`, `export default`, ...)
+ * @returns {number} Index of the first modifier (or `at` when there are none)
+ */
+function modifiersBefore(tokens, match, at, modifiers) {
+  let i = at - 1;
+  while (i >= 0) {
+    const token = tokens[i];
+    if (is(token, ')') && match[i] > 0 && is(tokens[match[i] - 1], 'pub')) {
+      i = match[i] - 1; // pub(crate), pub(super)
+      continue;
+    }
+    if (!isName(token) || !MODIFIERS.has(token.value)) break;
+    modifiers.unshift(token.value);
+    i--;
+  }
+  return i + 1;
+}
+
+/**
+ * Scan from a declaration's name to its body
+ * @returns {object|null} { body: index of '{' | null, end: last header token }
+ */
+function findBody(tokens, match, from, options = {}) {
+  let i = from;
+  const limit = Math.min(tokens.length, from + 400);
+  while (i < limit) {
+    const token = tokens[i];
+    const value = token.value;
+    if (value === '{') return { body: i, end: match[i] >= 0 ? match[i] : tokens.length - 1 };
+    if (value === ';') return { body: null, end: i };
+    if (options.stopAt && options.stopAt.has(value)) return { body: null, end: i - 1, stopped: value };
+    if ((value === '(' || value === '[') && match[i] > i) {
+      i = match[i] + 1;
+      continue;
+    }
+    if (value === '<' && options.generics) {
+      const next = skipAngles(tokens, i);
+      if (next > i) {
+        i = next;
+        continue;
+      }
+    }
+    if (value === '}' || value === ')') return { body: null, end: i - 1 };
+    i++;
+  }
+  return { body: null, end: Math.max(from, i - 1) };
+}
+
+/**
+ * End of a statement without a body (type aliases, expression-bodied functions):
+ * the next ';' at this depth, or the last token before a line that starts a new declaration
+ */
+function statementEnd(tokens, match, from, declarations) {
+  for (let i = from; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (token.value === ';' || token.value === ',') return i;
+    if (token.value === '}' || token.value === ')') return i - 1;
+    if ((token.value === '(' || token.value === '[' || token.value === '{') && match[i] > i) {
+      i = match[i];
+      continue;
+    }
+    const next = tokens[i + 1];
+    if (next && next.line > token.line && (MODIFIERS.has(next.value) || declarations[next.value])) return i;
+  }
+  return tokens.length - 1;
+}
+
+function qualifiedPath(tokens, from, separators) {
+  const parts = [];
+  let i = from;
+  while (isName(tokens[i])) {
+    parts.push(tokens[i].value);
+    if (!separators.has(tokens[i + 1]?.value) || !isName(tokens[i + 2])) break;
+    parts.push(tokens[i + 1].value);
+    i += 2;
+  }
+  return { name: parts.join(''), next: i + 1 };
+}
+
+function keywordDeclaration(language, tokens, match, i, declarations) {
+  const token = tokens[i];
+  let kind = declarations[token.value];
+  const previous = tokens[i - 1];
+  if (is(previous, '.') || is(previous, '::') || is(previous, '?.')) return null;
+
+  let nameAt = i + 1;
+  // C++ `enum class Name`, JS `function* name`
+  if (kind === 'enum' && (is(tokens[nameAt], 'class') || is(tokens[nameAt], 'struct'))) nameAt++;
+  if (kind === 'function' && is(tokens[nameAt], '*')) nameAt++;
+
+  let name;
+  let extra = {};
+  if (language === 'go' && kind === 'function' && is(tokens[nameAt], '(') && match[nameAt] > nameAt) {
+    // Method with a receiver: func (s *Server) Name(...)
+    const receiver = tokens.slice(nameAt + 1, match[nameAt]).filter((t) => t.type === 'identifier');
+    extra = { receiver: receiver.length ? receiver[receiver.length - 1].value : null };
+    nameAt = match[nameAt] + 1;
+  }
+
+  if (kind === 'impl') {
+    // impl<T> Trait for Type / extension Type: Protocol -- named after the header text
+    const start = skipAngles(tokens, nameAt);
+    const header = findBody(tokens, match, start, { generics: true });
+    if (header.body === null) return null;
+    name = tokens
+      .slice(start, header.body)
+      .map((t) => t.value)
+      .join(' ')
+      .replace(/\s*<[^{]*?>/g, '')
+      .replace(/\s*(::|\.)\s*/g, '$1')
+      .replace(/\s+where\s.*$/, '');
+    return createSymbol(kind, name, tokens, i, header.end, { nameIndex: start, body: header.body });
+  }
+
+  const nameToken = tokens[nameAt];
+  if (kind === 'namespace' || kind === 'module') {
+    if (nameToken && nameToken.type === 'string') {
+      name = nameToken.value.slice(1, -1); // declare module 'x'
+    } else {
+      const path = qualifiedPath(tokens, nameAt, new Set(['.', '::']));
+      name = path.name;
+      nameAt = path.next - 1;
+    }
+  } else if (isName(nameToken)) {
+    name = nameToken.value;
+  } else if (language === 'rust' && kind === 'macro' && is(nameToken, '!') && isName(tokens[nameAt + 1])) {
+    nameAt++;
+    name = tokens[nameAt].value;
+  }
+  if (!name) return null;
+
+  if (kind === 'type') {
+    const after = tokens[skipAngles(tokens, nameAt + 1)];
+    if (language === 'go' && (is(after, 'struct') || is(after, 'interface'))) {
+      kind = after.value === 'struct' ? 'struct' : 'interface';
+      const header = findBody(tokens, match, nameAt + 1);
+      return createSymbol(kind, name, tokens, i, header.end, { nameIndex: nameAt, body: header.body });
+    }
+    if (language !== 'go' && !is(after, '=')) return null;
+    return createSymbol(kind, name, tokens, i, statementEnd(tokens, match, nameAt + 1, declarations), {
+      nameIndex: nameAt,
+      body: null,
+    });
+  }
+
+  if (BODY_REQUIRED.has(language) && kind !== 'namespace') {
+    const next = tokens[nameAt + 1]?.value;
+    if (next !== '{' && next !== ':' && next !== 'final') return null;
+  }
+  const header = findBody(tokens, match, nameAt + 1, {
+    generics: true,
+    stopAt: kind === 'function' ? new Set(['=']) : null,
+  });
+  if (header.stopped === '=' && kind === 'function') {
+    // Expression-bodied function: fun f() = expr, def f = expr
+    const end = statementEnd(tokens, match, header.end + 2, declarations);
+    return createSymbol(kind, name, tokens, i, end, { nameIndex: nameAt, body: null, ...extra });
+  }
+  if (header.body === null && language === 'javascript') return null;
+  return createSymbol(kind, name, tokens, i, header.end, { nameIndex: nameAt, body: header.body, ...extra });
+}
+
+/**
+ * Walk back from a keyword-less declaration's name over its return type and
+ * modifiers (`public static <T> List<T>`, `const Foo&`), stopping at the
+ * previous statement or an annotation
+ * @returns {number} Index of the declaration's first token
+ */
+function declarationStart(tokens, i) {
+  let angles = 0;
+  let k = i - 1;
+  for (; k >= 0; k--) {
+    const token = tokens[k];
+    const value = token.value;
+    if (value === '>' || value === '>>') angles += value.length;
+    else if (value === '<') angles--;
+    else if (value === ',' && angles > 0) continue;
+    else if (value === ']' && is(tokens[k - 1], '[')) k--; // int[], not a C# [Attribute]
+    else if (isName(token)) {
+      if (is(tokens[k - 1], '@')) break; // @Override
+      continue;
+    } else if (!['*', '&', '&&', '::', '.', '?', '~'].includes(value)) break;
+  }
+  return k + 1;
+}
+
+/**
+ * `name(...) {` in C-family languages, and class / object members in JS;
+ * outside JS, `Type name(...);` declares a function without a body
+ */
+function implicitFunction(language, tokens, match, i) {
+  const token = tokens[i];
+  const open = tokens[i + 1];
+  if (token.type !== 'identifier' || !is(open, '(') || match[i + 1] < 0) return null;
+  const js = ASSIGNED_FUNCTIONS.has(language);
+  const previous = tokens[i - 1];
+  if (previous) {
+    if (NOT_BEFORE_DECLARATION.has(previous.value) || (js && previous.value === 'void')) return null;
+    const member = js && previous.value === ','; // { a() {}, b() {} }
+    const allowed = isName(previous) || DECLARATION_PREFIX.has(previous.value);
+    if (!allowed && !member) return null;
+  }
+
+  // Between ')' and '{': return types, qualifiers, throws clauses, initializer lists
+  let j = match[i + 1] + 1;
+  const limit = Math.min(tokens.length, j + 60);
+  while (j < limit) {
+    const value = tokens[j].value;
+    if (value === '{' || value === ';') break;
+    if (value === '(' || value === '[') {
+      if (match[j] < j) return null;
+      j = match[j] + 1;
+      continue;
+    }
+    const type = tokens[j].type;
+    if (type === 'identifier' || type === 'keyword') {
+      if (['if', 'for', 'while', 'switch', 'return', 'do', 'else'].includes(value)) return null;
+      j++;
+      continue;
+    }
+    if (['::', ':', ',', '<', '>', '>>', '*', '&', '&&', '.', '?', '|', '->', '=>'].includes(value)) {
+      if (value === '=>' && language !== 'dart') return null;
+      j++;
+      continue;
+    }
+    return null;
+  }
+  // JS has nothing between ) and {; TypeScript only a return type annotation
+  const annotated = language === 'typescript' && is(tokens[match[i + 1] + 1], ':');
+  if (js && j > match[i + 1] + 1 && !annotated) return null;
+  let body = null;
+  let last;
+  if (is(tokens[j], '{') && match[j] > j) {
+    body = j;
+    last = match[j];
+  } else if (is(tokens[j], ';') && !js && (isName(previous) || PROTOTYPE_PREFIX.has(previous?.value))) {
+    last = j;
+  } else {
+    return null;
+  }
+
+  let name = token.value;
+  if (is(previous, '::') && isName(tokens[i - 2])) name = `${tokens[i - 2].value}::${name}`; // Foo::bar
+  if (is(previous, '~')) name = `~${name}`;
+  const first = js ? i : declarationStart(tokens, i);
+  return createSymbol('function', name, tokens, first, last, { nameIndex: i, body, typed: !js });
+}
+
+/**
+ * `const name = (...) => {}`, `name: function () {}`, class fields `name = async () => {}`
+ */
+function assignedFunction(tokens, match, i, declarations) {
+  const token = tokens[i];
+  let nameAt;
+  let first = i;
+  if ((token.value === 'const' || token.value === 'let' || token.value === 'var') && isName(tokens[i + 1])) {
+    nameAt = i + 1;
+  } else if (token.type === 'identifier' && (is(tokens[i + 1], ':') || is(tokens[i + 1], '='))) {
+    const previous = tokens[i - 1];
+    const start = !previous || ['{', '}', ',', ';'].includes(previous.value) || MODIFIERS.has(previous.value);
+    if (!start) return null;
+    if (previous && previous.value === ',' && is(tokens[i + 1], '=')) return null;
+    nameAt = i;
+  } else {
+    return null;
+  }
+
+  let j = nameAt + 1;
+  if (is(tokens[j], ':') && nameAt !== i) return null; // const x: Type = ...; too ambiguous to follow
+  if (!is(tokens[j], '=') && !is(tokens[j], ':')) return null;
+  j++;
+  if (is(tokens[j], 'async')) j++;
+
+  let arrow = false;
+  if (is(tokens[j], 'function')) {
+    j++;
+    if (is(tokens[j], '*')) j++;
+    if (isName(tokens[j])) j++;
+    if (!is(tokens[j], '(') || match[j] < j) return null;
+    j = match[j] + 1;
+  } else if (is(tokens[j], '(') && match[j] > j) {
+    j = match[j] + 1;
+    if (is(tokens[j], ':')) {
+      // Return type annotation
+      while (j < tokens.length && !is(tokens[j], '=>') && !is(tokens[j], ';') && !is(tokens[j], '{')) j++;
+    }
+    if (!is(tokens[j], '=>')) return null;
+    arrow = true;
+    j++;
+  } else if (tokens[j]?.type === 'identifier' && is(tokens[j + 1], '=>')) {
+    arrow = true;
+    j += 2;
+  } else {
+    return null;
+  }
+
+  const name = tokens[nameAt].value;
+  if (is(tokens[j], '{') && match[j] > j) {
+    return createSymbol('function', name, tokens, first, match[j], { nameIndex: nameAt, body: j });
+  }
+  // Property forms must have a block body; otherwise `x: (a) => void` in a type would count
+  if (!arrow || nameAt === i) return null;
+  const end = statementEnd(tokens, match, j, declarations);
+  return createSymbol('function', name, tokens, first, end, { nameIndex: nameAt, body: null });
+}
+
+function braceSymbols(language, tokens, match) {
+  const declarations = DECLARATIONS[language] || {};
+  const implicit = IMPLICIT_FUNCTIONS.has(language);
+  const assigned = ASSIGNED_FUNCTIONS.has(language);
+  const symbols = [];
+  const claimed = new Set(); // Body '{' indices already owned by a symbol
+
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (token.type !== 'keyword' && token.type !== 'identifier') continue;
+
+    let symbol = null;
+    if (declarations[token.value]) symbol = keywordDeclaration(language, tokens, match, i, declarations);
+    if (!symbol && assigned) symbol = assignedFunction(tokens, match, i, declarations);
+    if (!symbol && implicit) symbol = implicitFunction(language, tokens, match, i);
+    if (!symbol || (symbol.body !== null && claimed.has(symbol.body))) continue;
+
+    const modifiers = [];
+    const first = modifiersBefore(tokens, match, symbol.first, modifiers);
+    for (let k = symbol.first; symbol.typed && k < symbol.nameIndex; k++) {
+      if (isName(tokens[k]) && MODIFIERS.has(tokens[k].value)) modifiers.push(tokens[k].value);
+    }
+    symbol.modifiers = modifiers;
+    symbol.first = first;
+    symbol.startLine = tokens[first].line;
+    if (symbol.body !== null) claimed.add(symbol.body);
+    symbols.push(symbol);
+    // Continue inside the declaration so nested members are found
+    i = Math.max(i, symbol.nameIndex ?? i);
+  }
+  return symbols;
+}
+
+// ---------------------------------------------------------------------------
+// Python
+// ---------------------------------------------------------------------------
+
+function pythonSymbols(content, tokens, match, depth) {
+  const column = (token) => token.start - content.lastIndexOf('\n', token.start - 1) - 1;
+  const startsLine = (i) => depth[i] === 0 && (i === 0 || tokens[i - 1].line < tokens[i].line);
+  const symbols = [];
+
+  for (let i = 0; i < tokens.length; i++) {
+    let at = i;
+    if (is(tokens[i], 'async') && startsLine(i) && is(tokens[i + 1], 'def')) at = i + 1;
+    else if (!startsLine(i)) continue;
+    const keyword = tokens[at].value;
+    if ((keyword !== 'def' && keyword !== 'class') || !isName(tokens[at + 1])) continue;
+
+    const indent = column(tokens[i]);
+    let last = at + 1;
+    for (let j = at + 2; j < tokens.length; j++) {
+      if (startsLine(j) && column(tokens[j]) <= indent && tokens[j].line > tokens[at].line) break;
+      last = j;
+    }
+    // Decorators directly above belong to the definition
+    let first = i;
+    let startLine = tokens[i].line;
+    for (let k = i - 1; k >= 0; k--) {
+      if (!startsLine(k)) continue;
+      if (!is(tokens[k], '@') || column(tokens[k]) !== indent) break;
+      first = k;
+      startLine = tokens[k].line;
+    }
+    const kind = keyword === 'def' ? 'function' : 'class';
+    const name = tokens[at + 1].value;
+    const symbol = createSymbol(kind, name, tokens, i, last, { nameIndex: at + 1, body: null });
+    symbol.first = first;
+    symbol.startLine = startLine;
+    if (at > i) symbol.modifiers = ['async'];
+    symbols.push(symbol);
+  }
+  return symbols;
+}
+
+// ---------------------------------------------------------------------------
+// Ruby
+// ---------------------------------------------------------------------------
+
+const RUBY_OPENERS = new Set(['class', 'module', 'def', 'do', 'begin', 'case', 'for']);
+const RUBY_CONDITIONALS = new Set(['if', 'unless', 'while', 'until']);
+
+function rubySymbols(tokens) {
+  const symbols = [];
+  const stack = []; // { symbol, line, visibility }
+  const firstOnLine = (i) => i === 0 || tokens[i - 1].line < tokens[i].line;
+
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (token.type !== 'keyword' && token.type !== 'identifier') continue;
+    const value = token.value;
+    const previous = tokens[i - 1];
+    if (is(previous, '.') || is(previous, '::') || is(previous, ':')) continue; // obj.class, :def
+
+    if (value === 'end' && token.type === 'keyword') {
+      const open = stack.pop();
+      if (open && open.symbol) {
+        open.symbol.last = i;
+        open.symbol.endLine = token.line;
+      }
+      continue;
+    }
+
+    if (['private', 'protected', 'public'].includes(value) && firstOnLine(i)) {
+      const next = tokens[i + 1];
+      const scope = stack[stack.length - 1];
+      if (scope && (!next || next.line > token.line)) scope.visibility = value;
+      continue;
+    }
+
+    if (RUBY_CONDITIONALS.has(value)) {
+      const opens = firstOnLine(i) || (previous && (previous.type === 'operator' || is(previous, '(')));
+      if (opens) stack.push({ symbol: null, line: token.line, loop: value === 'while' || value === 'until' });
+      continue;
+    }
+    if (!RUBY_OPENERS.has(value)) continue;
+    if (value === 'do') {
+      const top = stack[stack.length - 1];
+      if (top && top.loop && top.line === token.line) continue; // while x do
+      stack.push({ symbol: null, line: token.line });
+      continue;
+    }
+    if (value === 'for') {
+      stack.push({ symbol: null, line: token.line, loop: true });
+      continue;
+    }
+    if (value !== 'class' && value !== 'module' && value !== 'def') {
+      stack.push({ symbol: null, line: token.line });
+      continue;
+    }
+
+    let name = null;
+    let nameAt = i + 1;
+    if (value === 'def') {
+      if (is(tokens[nameAt], 'self') && is(tokens[nameAt + 1], '.')) {
+        name = `self.${tokens[nameAt + 2]?.value}`;
+        nameAt += 2;
+      } else {
+        name = tokens[nameAt]?.value;
+      }
+      // Endless method: def name(args) = expr
+      let after = nameAt + 1;
+      if (is(tokens[after], '(')) {
+        while (after < tokens.length && !is(tokens[after], ')')) after++;
+        after++;
+      }
+      if (is(tokens[after], '=') && tokens[after].line === token.line) {
+        symbols.push(createSymbol('function', name, tokens, i, after, { nameIndex: nameAt, body: null }));
+        continue;
+      }
+    } else if (is(tokens[nameAt], '<<')) {
+      name = null; // class << self
+    } else {
+      const path = qualifiedPath(tokens, nameAt, new Set(['::']));
+      name = path.name;
+    }
+
+    const scope = stack[stack.length - 1];
+    const kind = value === 'def' ? 'function' : value;
+    const symbol = name ? createSymbol(kind, name, tokens, i, i, { nameIndex: nameAt, body: null }) : null;
+    if (symbol && value === 'def' && scope && scope.visibility && scope.visibility !== 'public') {
+      symbol.modifiers = [scope.visibility];
+    }
+    if (symbol) symbols.push(symbol);
+    stack.push({ symbol, line: token.line });
+  }
+  return symbols;
+}
+
+// ---------------------------------------------------------------------------
+// Imports and exports
+// ---------------------------------------------------------------------------
+
+const unquote = (value) => value.replace(/^[a-zA-Z]*(['"`])([\s\S]*)\1$/, '$2');
+
+/**
+ * Names listed directly inside a brace group: { a, b as c, d: e } -> local names
+ */
+function braceNames(tokens, match, open) {
+  const names = [];
+  const close = match[open];
+  for (let i = open + 1; i < close; i++) {
+    if (tokens[i].value === '{' || tokens[i].value === '(' || tokens[i].value === '[') {
+      i = Math.max(i, match[i]);
+      continue;
+    }
+    if (!isName(tokens[i])) continue;
+    const before = tokens[i - 1].value;
+    if (before !== '{' && before !== ',' && before !== 'as' && before !== '::') continue;
+    const next = tokens[i + 1]?.value;
+    if (next === 'as') continue; // `a as b` binds b
+    names.push(tokens[i].value);
+  }
+  return names;
+}
+
+function statementTokens(tokens, from) {
+  let end = from;
+  while (end < tokens.length && tokens[end].value !== ';' && tokens[end].line === tokens[from].line) end++;
+  return end;
+}
+
+function jsImports(tokens, match) {
+  const imports = [];
+  const statementStart = (i) => i === 0 || tokens[i - 1].line < tokens[i].line || is(tokens[i - 1], ';');
+
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (is(tokens[i - 1], '.')) continue;
+
+    if ((token.value === 'require' || token.value === 'import') && is(tokens[i + 1], '(')) {
+      // require('x') / dynamic import('x'), with the names bound by `const x =` / `const { a } =`
+      const source = tokens[i + 2];
+      if (!source || source.type !== 'string') continue;
+      const names = [];
+      if (is(tokens[i - 1], '=') && isName(tokens[i - 2])) names.push(tokens[i - 2].value);
+      if (is(tokens[i - 1], '=') && is(tokens[i - 2], '}') && match[i - 2] >= 0) {
+        names.push(...braceNames(tokens, match, match[i - 2]));
+      }
+      const dynamic = token.value === 'import';
+      imports.push({ source: unquote(source.value), names, line: token.line, dynamic });
+      continue;
+    }
+
+    if (token.value === 'export' && (is(tokens[i + 1], '{') || is(tokens[i + 1], '*'))) {
+      // export { a } from 'x' / export * as ns from 'x'
+      let j = i + 1;
+      const names = [];
+      if (is(tokens[j], '{') && match[j] > j) {
+        names.push(...braceNames(tokens, match, j));
+        j = match[j] + 1;
+      } else {
+        j++;
+        if (is(tokens[j], 'as') && isName(tokens[j + 1])) j += 2;
+      }
+      if (is(tokens[j], 'from') && tokens[j + 1]?.type === 'string') {
+        imports.push({ source: unquote(tokens[j + 1].value), names, line: token.line, reexport: true });
+      }
+      continue;
+    }
+
+    if (token.value !== 'import' || !statementStart(i)) continue;
+    // import x, { a as b } from 'y' / import * as ns from 'y' / import 'y' / import type { T } from 'y'
+    let j = i + 1;
+    const names = [];
+    if (is(tokens[j], 'type')) j++;
+    const limit = Math.min(tokens.length, j + 200);
+    while (j < limit && !is(tokens[j], 'from') && tokens[j].type !== 'string') {
+      if (is(tokens[j], '{') && match[j] > j) {
+        names.push(...braceNames(tokens, match, j));
+        j = match[j] + 1;
+        continue;
+      }
+      if (is(tokens[j], ';') || is(tokens[j], '=')) break; // import x = require('y') is handled above
+      if (is(tokens[j], 'as') && isName(tokens[j + 1])) {
+        names.push(tokens[j + 1].value);
+        j += 2;
+        continue;
+      }
+      if (isName(tokens[j]) && !is(tokens[j + 1], 'as')) names.push(tokens[j].value);
+      j++;
+    }
+    if (is(tokens[j], 'from')) j++;
+    if (tokens[j]?.type === 'string') {
+      imports.push({ source: unquote(tokens[j].value), names, line: token.line });
+    }
+  }
+  return imports;
+}
+
+function pathImports(tokens, match, keyword, separators, options = {}) {
+  const imports = [];
+  for (let i = 0; i < tokens.length; i++) {
+    if (tokens[i].value !== keyword || is(tokens[i - 1], '.')) continue;
+    if (options.lineStart && i > 0 && tokens[i - 1].line === tokens[i].line && !is(tokens[i - 1], 'pub')) {
+      continue;
+    }
+    let j = i + 1;
+    while (isName(tokens[j]) && options.skip && options.skip.has(tokens[j].value)) j++;
+    if (!isName(tokens[j])) continue;
+    if (options.requireSemicolon && is(tokens[j + 1], '(')) continue; // C# using (var x = ...)
+
+    const parts = [];
+    const names = [];
+    let alias = null;
+    while (j < tokens.length) {
+      const token = tokens[j];
+      if (isName(token) || token.value === '*' || separators.has(token.value)) {
+        parts.push(token.value);
+        j++;
+      } else if (token.value === '{' && match[j] > j) {
+        names.push(...braceNames(tokens, match, j));
+        j = match[j] + 1;
+      } else {
+        break;
+      }
+    }
+    if (is(tokens[j], 'as') && isName(tokens[j + 1])) alias = tokens[j + 1].value;
+    if (options.requireSemicolon && !is(tokens[alias ? j + 2 : j], ';')) continue;
+    let source = parts.join('');
+    for (const separator of separators) {
+      if (source.endsWith(separator)) source = source.slice(0, -separator.length);
+    }
+    if (!source) continue;
+    if (alias) names.push(alias);
+    else if (!names.length) names.push(source.split(/::|\.|\\/).pop());
+    imports.push({
+      source,
+      names: names.filter((name) => name !== '*'),
+      line: tokens[i].line,
+      exported: is(tokens[i - 1], 'pub'),
+    });
+  }
+  return imports;
+}
+
+function stringImports(tokens, keywords) {
+  const imports = [];
+  for (let i = 0; i < tokens.length; i++) {
+    if (!keywords.has(tokens[i].value) || is(tokens[i - 1], '.')) continue;
+    let j = i + 1;
+    if (is(tokens[j], '(')) j++;
+    if (tokens[j]?.type === 'string') {
+      const kind = tokens[i].value;
+      imports.push({ source: unquote(tokens[j].value), names: [], line: tokens[i].line, kind });
+    }
+  }
+  return imports;
+}
+
+function goImports(tokens, match) {
+  const imports = [];
+  for (let i = 0; i < tokens.length; i++) {
+    if (tokens[i].value !== 'import') continue;
+    const group = is(tokens[i + 1], '(') && match[i + 1] > i ? [i + 2, match[i + 1]] : [i + 1, i + 3];
+    for (let j = group[0]; j < group[1] && j < tokens.length; j++) {
+      if (tokens[j].type !== 'string') continue;
+      const alias = isName(tokens[j - 1]) && tokens[j - 1].value !== 'import' ? tokens[j - 1].value : null;
+      const source = unquote(tokens[j].value);
+      imports.push({ source, names: [alias || source.split('/').pop()], line: tokens[j].line });
+    }
+  }
+  return imports;
+}
+
+function pythonImports(tokens, match) {
+  const imports = [];
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (i > 0 && tokens[i - 1].line === token.line && !is(tokens[i - 1], ';')) continue;
+    if (token.value === 'import') {
+      // import a.b as c, d
+      let j = i + 1;
+      while (j < tokens.length && tokens[j].line === token.line) {
+        const path = qualifiedPath(tokens, j, new Set(['.']));
+        if (!path.name) break;
+        j = path.next;
+        let name = path.name.split('.')[0];
+        if (is(tokens[j], 'as') && isName(tokens[j + 1])) {
+          name = tokens[j + 1].value;
+          j += 2;
+        }
+        imports.push({ source: path.name, names: [name], line: token.line });
+        if (!is(tokens[j], ',')) break;
+        j++;
+      }
+    } else if (token.value === 'from') {
+      // from ..pkg.mod import (a, b as c)
+      let j = i + 1;
+      let source = '';
+      while (['.', '..', '...'].includes(tokens[j]?.value)) source += tokens[j++].value;
+      const path = is(tokens[j], 'import') ? { name: '' } : qualifiedPath(tokens, j, new Set(['.']));
+      source += path.name;
+      j = path.name ? path.next : j;
+      if (!is(tokens[j], 'import')) continue;
+      j++;
+      const end = is(tokens[j], '(') && match[j] > j ? match[j] : statementTokens(tokens, j);
+      const names = [];
+      for (let k = j; k < end; k++) {
+        if (!isName(tokens[k]) && tokens[k].value !== '*') continue;
+        if (is(tokens[k + 1], 'as')) continue;
+        if (tokens[k].value !== 'as') names.push(tokens[k].value);
+      }
+      imports.push({ source, names, line: token.line });
+    }
+  }
+  return imports;
+}
+
+function cIncludes(content) {
+  const imports = [];
+  const pattern = /^[ \t]*#[ \t]*(include|import)[ \t]*([<"])([^>"\n]+)[>"]/gm;
+  let match;
+  while ((match = pattern.exec(content))) {
+    const line = content.slice(0, match.index).split('\n').length;
+    imports.push({ source: match[3], names: [], line, system: match[2] === '<' });
+  }
+  return imports;
+}
+
+function extractImportList(content, language, tokens, match) {
+  switch (language) {
+    case 'javascript':
+    case 'typescript':
+      return jsImports(tokens, match);
+    case 'python':
+      return pythonImports(tokens, match);
+    case 'rust':
+      return [
+        ...pathImports(tokens, match, 'use', new Set(['::']), { lineStart: true }),
+        ...stringImports(tokens, new Set(['include_str', 'include_bytes'])),
+      ];
+    case 'go':
+      return goImports(tokens, match);
+    case 'java':
+    case 'kotlin':
+    case 'scala':
+      return pathImports(tokens, match, 'import', new Set(['.']), { skip: new Set(['static']) });
+    case 'csharp':
+      return pathImports(tokens, match, 'using', new Set(['.']), {
+        skip: new Set(['static', 'global']),
+        requireSemicolon: true,
+      });
+    case 'swift':
+      return pathImports(tokens, match, 'import', new Set(['.']), {
+        skip: new Set(['class', 'struct', 'func']),
+      });
+    case 'php':
+      return [
+        ...pathImports(tokens, match, 'use', new Set(['\\']), { lineStart: true }),
+        ...stringImports(tokens, new Set(['require', 'require_once', 'include', 'include_once'])),
+      ];
+    case 'ruby':
+      return stringImports(tokens, new Set(['require', 'require_relative', 'load']));
+    case 'dart':
+      return stringImports(tokens, new Set(['import', 'export']));
+    case 'c':
+    case 'cpp':
+      return cIncludes(content);
+    default:
+      return [];
+  }
+}
+
+function isExported(language, symbol, topLevel) {
+  const modifiers = new Set(symbol.modifiers);
+  switch (language) {
+    case 'javascript':
+    case 'typescript':
+      return modifiers.has('export');
+    case 'rust':
+      return modifiers.has('pub');
+    case 'go':
+      return /^[A-Z]/.test(symbol.name);
+    case 'python':
+    case 'dart':
+      return !symbol.name.startsWith('_');
+    case 'kotlin':
+    case 'scala':
+      return !modifiers.has('private') && !modifiers.has('protected') && !modifiers.has('internal');
+    case 'swift':
+      return modifiers.has('public') || modifiers.has('open');
+    case 'c':
+    case 'cpp':
+      return topLevel && !modifiers.has('static');
+    case 'ruby':
+      return !modifiers.has('private') && !modifiers.has('protected');
+    default:
+      return modifiers.has('public');
+  }
+}
+
+/**
+ * Names exported by statements rather than declarations:
+ * export { a, b }, module.exports = { a }, exports.a = ..., __all__ = [...]
+ */
+function exportStatements(language, tokens, match) {
+  const exports = [];
+  if (language === 'python') {
+    const at = tokens.findIndex((t, i) => t.value === '__all__' && is(tokens[i + 1], '='));
+    if (at >= 0 && (is(tokens[at + 2], '[') || is(tokens[at + 2], '(')) && match[at + 2] > at) {
+      for (let i = at + 3; i < match[at + 2]; i++) {
+        if (tokens[i].type !== 'string') continue;
+        exports.push({ name: unquote(tokens[i].value), line: tokens[i].line });
+      }
+      return { exports, explicit: true };
+    }
+    return { exports, explicit: false };
+  }
+  if (language !== 'javascript' && language !== 'typescript') return { exports, explicit: false };
+
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (token.value === 'export' && is(tokens[i + 1], '{') && match[i + 1] > i) {
+      const close = match[i + 1];
+      if (is(tokens[close + 1], 'from')) continue; // Re-export, reported as an import
+      for (let j = i + 2; j < close; j++) {
+        if (!isName(tokens[j]) || is(tokens[j + 1], 'as')) continue;
+        if (tokens[j].value !== 'as') exports.push({ name: tokens[j].value, line: tokens[j].line });
+      }
+    } else if (token.value === 'export' && is(tokens[i + 1], 'default')) {
+      const next = tokens[i + 2];
+      if (next && !['class', 'function', 'async', 'abstract'].includes(next.value)) {
+        exports.push({ name: 'default', line: token.line, local: isName(next) ? next.value : null });
+      }
+    } else if (token.value === 'module' && is(tokens[i + 1], '.') && is(tokens[i + 2], 'exports')) {
+      if (is(tokens[i + 3], '.') && isName(tokens[i + 4])) {
+        exports.push({ name: tokens[i + 4].value, line: token.line });
+        continue;
+      }
+      if (!is(tokens[i + 3], '=')) continue;
+      // module.exports = { a, b } or = wrap('X', { a, b }) or = Name
+      const end = statementEnd(tokens, match, i + 4, {});
+      const open = tokens.findIndex((t, k) => k > i + 3 && k <= end && t.value === '{');
+      if (open >= 0 && match[open] > open) {
+        for (const name of braceNames(tokens, match, open)) exports.push({ name, line: token.line });
+      } else if (isName(tokens[i + 4]) && (end === i + 4 || (end === i + 5 && is(tokens[end], ';')))) {
+        exports.push({ name: 'default', line: token.line, local: tokens[i + 4].value });
+      }
+    } else if (token.value === 'exports' && !is(tokens[i - 1], '.') && is(tokens[i + 1], '.')) {
+      if (!isName(tokens[i + 2]) || !is(tokens[i + 3], '=')) continue;
+      exports.push({ name: tokens[i + 2].value, line: token.line });
+    }
+  }
+  return { exports, explicit: exports.length > 0 };
+}
+
+// ---------------------------------------------------------------------------
+// Attributes
+// ---------------------------------------------------------------------------
+
+/**
+ * Index of the first token of a declaration's attributes / annotations:
+ * #[derive(...)], @Override, @app.route(...), [Serializable]
+ */
+function attributesStart(tokens, match, first) {
+  let i = first;
+  for (;;) {
+    let k = i - 1;
+    if (k < 0) return i;
+    if (tokens[k].value === ')' && match[k] >= 0) k = match[k] - 1; // @name(...)
+    if (tokens[k]?.value === ']' && match[k] >= 0) {
+      const open = match[k];
+      const before = tokens[open - 1];
+      if (before && (before.value === '#' || before.value === '#!')) {
+        i = open - 1;
+        continue;
+      }
+      // C# [Attribute] on a line of its own
+      if (!before || before.line < tokens[open].line) {
+        i = open;
+        continue;
+      }
+      return i;
+    }
+    // @a.b.c
+    let j = k;
+    while (j > 1 && tokens[j].type === 'identifier' && tokens[j - 1].value === '.') j -= 2;
+    const named = tokens[j].type === 'identifier' || tokens[j].type === 'keyword';
+    if (j > 0 && named && tokens[j - 1].value === '@') {
+      i = j - 1;
+      continue;
+    }
+    return i;
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Function details
+// ---------------------------------------------------------------------------
+
+// Parameters written `name: Type` (otherwise `Type name`, or untyped)
+const COLON_TYPED = new Set(['typescript', 'python', 'rust', 'kotlin', 'scala', 'swift']);
+const TYPE_FIRST = new Set(['java', 'csharp', 'c', 'cpp', 'dart', 'php']);
+const ANGLE_GENERICS = new Set([
+  'typescript',
+  'rust',
+  'java',
+  'csharp',
+  'cpp',
+  'kotlin',
+  'scala',
+  'swift',
+  'dart',
+]);
+
+/**
+ * Join tokens back into compact source text: a space only between words
+ * and after commas, e.g. `Map<String, List<T>>`, `unsigned int`, `&mut self`
+ */
+function joinTokens(tokens) {
+  let text = '';
+  tokens.forEach((token, i) => {
+    const previous = tokens[i - 1];
+    const word = (t) => t.type !== 'operator' && t.type !== 'bracket' && t.type !== 'punctuation';
+    if (previous && ((word(previous) && word(token)) || previous.value === ',')) text += ' ';
+    text += token.value;
+  });
+  return text;
+}
+
+/**
+ * Split tokens at top-level separators, tracking (), [], {} and, where the
+ * language has them, generic <>
+ */
+function splitTopLevel(tokens, separator, angles) {
+  const parts = [[]];
+  let depth = 0;
+  for (const token of tokens) {
+    const value = token.value;
+    if (value === '(' || value === '[' || value === '{' || (angles && value === '<')) depth++;
+    else if (value === ')' || value === ']' || value === '}' || (angles && value === '>')) depth--;
+    else if (angles && value === '>>') depth -= 2;
+    if (value === separator && depth === 0) parts.push([]);
+    else parts[parts.length - 1].push(token);
+  }
+  return parts.filter((part) => part.length > 0);
+}
+
+function topLevelIndex(tokens, value, angles) {
+  const parts = splitTopLevel(tokens, value, angles);
+  return parts.length > 1 ? parts[0].length : -1;
+}
+
+function parseParameter(language, tokens) {
+  const angles = ANGLE_GENERICS.has(language);
+  let rest = tokens;
+  let defaultValue = null;
+  const eq = topLevelIndex(rest, '=', angles);
+  if (eq >= 0) {
+    defaultValue = joinTokens(rest.slice(eq + 1));
+    rest = rest.slice(0, eq);
+  }
+  // Drop annotations / decorators: @Nullable, @Param("x")
+  while (is(rest[0], '@') && isName(rest[1])) {
+    rest = rest.slice(is(rest[2], '(') ? rest.findIndex((t) => t.value === ')') + 1 : 2);
+  }
+
+  const colon = topLevelIndex(rest, ':', angles);
+  if (COLON_TYPED.has(language) && colon >= 0) {
+    const before = rest.slice(0, colon);
+    const names = before.filter((token) => token.type === 'identifier' || token.value === 'self');
+    const prefix = before.filter((token) => ['...', '*', '**'].includes(token.value)).map((t) => t.value);
+    const name = prefix.join('') + (names.length ? names[names.length - 1].value : joinTokens(before));
+    return { name, type: joinTokens(rest.slice(colon + 1)) || null, default: defaultValue };
+  }
+  if (TYPE_FIRST.has(language) && rest.length > 1) {
+    let at = rest.length - 1;
+    while (at > 0 && (is(rest[at], ']') || is(rest[at], '['))) at--; // int a[]
+    const type = joinTokens([...rest.slice(0, at), ...rest.slice(at + 1)]);
+    return { name: rest[at].value, type, default: defaultValue };
+  }
+  if (language === 'go' && rest.length > 1) {
+    return { name: rest[0].value, type: joinTokens(rest.slice(1)), default: null };
+  }
+  return { name: joinTokens(rest), type: null, default: defaultValue };
+}
+
+function parseParameters(language, tokens) {
+  const parameters = splitTopLevel(tokens, ',', ANGLE_GENERICS.has(language))
+    .map((part) => parseParameter(language, part))
+    .filter((param) => !(language === 'python' && (param.name === '*' || param.name === '/')));
+  if (TYPE_FIRST.has(language) && parameters.length === 1 && parameters[0].name === 'void') return [];
+  if (language === 'go') {
+    // a, b int: names without a type share the next parameter's type; with no types at all,
+    // the entries are unnamed types (func(int, string))
+    if (parameters.every((param) => param.type === null)) {
+      return parameters.map((param) => ({ name: null, type: param.name, default: null }));
+    }
+    for (let i = parameters.length - 2; i >= 0; i--) {
+      if (parameters[i].type === null) parameters[i].type = parameters[i + 1].type;
+    }
+  }
+  return parameters;
+}
+
+/**
+ * Locate a function's parameter list, header end and body tokens
+ */
+function functionLayout(parsed, symbol) {
+  const { tokens, match, language } = parsed;
+  const limit = symbol.body ?? symbol.last;
+  let params = null;
+  for (let k = symbol.nameIndex + 1; k <= limit && k < tokens.length; k++) {
+    if (is(tokens[k], '<')) {
+      const next = skipAngles(tokens, k);
+      if (next > k) {
+        k = next - 1;
+        continue;
+      }
+    }
+    if (is(tokens[k], '(') && match[k] > k) {
+      params = [k + 1, match[k]];
+      break;
+    }
+    if (is(tokens[k], '{') || is(tokens[k], ';') || (is(tokens[k], ':') && language === 'python')) break;
+    if (is(tokens[k], '=>') && tokens[k - 1].type === 'identifier') {
+      params = [k - 1, k]; // x => ...
+      break;
+    }
+    if (language === 'ruby' && tokens[k].line > tokens[symbol.nameIndex].line) break;
+  }
+  if (!params && language === 'ruby') {
+    // def name a, b
+    let end = symbol.nameIndex + 1;
+    const line = tokens[symbol.nameIndex].line;
+    while (end < tokens.length && tokens[end].line === line && !is(tokens[end], ';')) end++;
+    params = [symbol.nameIndex + 1, end];
+  }
+  const afterParams = params ? params[1] + (is(tokens[params[1]], ')') ? 1 : 0) : symbol.nameIndex + 1;
+
+  let headerEnd; // Exclusive
+  let bodyStart;
+  let bodyEnd; // Exclusive
+  if (symbol.body !== null && symbol.body !== undefined && language !== 'python' && language !== 'ruby') {
+    headerEnd = symbol.body;
+    bodyStart = symbol.body + 1;
+    bodyEnd = symbol.last;
+  } else if (language === 'python') {
+    headerEnd = afterParams;
+    while (headerEnd < symbol.last && !is(tokens[headerEnd], ':')) headerEnd++;
+    bodyStart = headerEnd + 1;
+    bodyEnd = symbol.last + 1;
+  } else if (language === 'ruby') {
+    headerEnd = afterParams;
+    while (headerEnd <= symbol.last && tokens[headerEnd].line === tokens[symbol.nameIndex].line) {
+      if (is(tokens[headerEnd], ';') || is(tokens[headerEnd], '=')) break;
+      headerEnd++;
+    }
+    bodyStart = headerEnd + (is(tokens[headerEnd], ';') || is(tokens[headerEnd], '=') ? 1 : 0);
+    bodyEnd = is(tokens[symbol.last], 'end') ? symbol.last : symbol.last + 1;
+  } else {
+    // No block: a prototype (`;`) or an expression body (`= expr`, `=> expr`)
+    headerEnd = afterParams;
+    while (headerEnd <= symbol.last && !['=', '=>', ';'].includes(tokens[headerEnd].value)) headerEnd++;
+    bodyStart = headerEnd + 1;
+    bodyEnd = is(tokens[symbol.last], ';') || is(tokens[symbol.last], ',') ? symbol.last : symbol.last + 1;
+  }
+  return { params, afterParams, headerEnd, bodyStart, bodyEnd: Math.max(bodyStart, bodyEnd) };
+}
+
+function returnTypeOf(parsed, symbol, layout) {
+  const { tokens, language } = parsed;
+  const trailing = tokens.slice(layout.afterParams, layout.headerEnd);
+  const arrow = trailing.findIndex((t) => t.value === '->');
+  if (arrow >= 0) {
+    // Python, Rust, Swift, C++ trailing return; Rust `where` clauses aren't part of it
+    const end = trailing.findIndex((t, k) => k > arrow && (t.value === 'where' || t.value === 'throws'));
+    return joinTokens(trailing.slice(arrow + 1, end >= 0 ? end : undefined)) || null;
+  }
+  if (is(trailing[0], ':') && language !== 'cpp') {
+    const end = trailing.findIndex((t) => t.value === '=>' || t.value === 'where');
+    return joinTokens(trailing.slice(1, end >= 0 ? end : undefined)) || null;
+  }
+  if (language === 'go') {
+    return joinTokens(trailing) || null;
+  }
+  if (symbol.typed) {
+    let leading = tokens
+      .slice(symbol.first, symbol.nameIndex)
+      .filter((token) => !(isName(token) && MODIFIERS.has(token.value)));
+    if (is(leading[0], '<')) leading = leading.slice(skipAngles(leading, 0)); // <T> List<T>
+    if (is(leading[leading.length - 1], '::')) leading = leading.slice(0, -2); // Foo::bar
+    if (is(leading[leading.length - 1], '~')) leading = leading.slice(0, -1);
+    return joinTokens(leading) || null;
+  }
+  return null;
+}
+
+/**
+ * Signatures, parameters, return types and body hashes for every function
+ * and method, so trace alignment can tell a signature change from a body change
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias
+ * @returns {Array<object>} [{ name, qualifiedName, kind, startLine, endLine, signature, parameters: [{ name,
+ *   type, default }], returnType, modifiers, bodyStartLine, bodyHash, signatureHash }]. Hashes are over
+ *   tokens, so whitespace and comment changes don't alter them.
+ */
+function extractFunctionDetails(content, language) {
+  const parsed = parseSymbols(content, language);
+  const { tokens, text } = parsed;
+  const functions = parsed.symbols.filter((symbol) => symbol.kind === 'function' || symbol.kind === 'method');
+
+  return functions.map((symbol) => {
+    const layout = functionLayout(parsed, symbol);
+    const header = tokens.slice(symbol.first, layout.headerEnd);
+    const body = tokens.slice(layout.bodyStart, layout.bodyEnd);
+    const signatureEnd = header.length ? header[header.length - 1] : tokens[symbol.nameIndex];
+    const signature = text
+      .slice(tokens[symbol.first].start, signatureEnd.start + signatureEnd.value.length)
+      .replace(/\s+/g, ' ')
+      .trim();
+    const parameters = layout.params ? parseParameters(parsed.language, tokens.slice(...layout.params)) : [];
+    return {
+      name: symbol.name,
+      qualifiedName: symbol.qualifiedName,
+      kind: symbol.kind,
+      startLine: symbol.startLine,
+      endLine: symbol.endLine,
+      signature,
+      parameters,
+      returnType: returnTypeOf(parsed, symbol, layout),
+      modifiers: symbol.modifiers,
+      bodyStartLine: body.length ? body[0].line : null,
+      bodyHash: hashContent(body.map((t) => t.value).join(' ')),
+      signatureHash: hashContent(header.map((t) => t.value).join(' ')),
+    };
+  });
+}
+
+// ---------------------------------------------------------------------------
+// Assembly
+// ---------------------------------------------------------------------------
+
+/**
+ * Nest symbols by token range and qualify their names
+ */
+function nest(symbols) {
+  symbols.sort((a, b) => a.first - b.first || b.last - a.last);
+  const stack = [];
+  for (const symbol of symbols) {
+    while (stack.length && stack[stack.length - 1].last < symbol.first) stack.pop();
+    const parent = stack[stack.length - 1] || null;
+    symbol.parent = parent ? parent.qualifiedName : null;
+    symbol.depth = stack.length;
+    symbol.qualifiedName = parent ? `${parent.qualifiedName}.${symbol.name}` : symbol.name;
+    if (symbol.kind === 'function' && symbol.receiver) {
+      // Go methods are declared at top level; qualify them by receiver type
+      symbol.kind = 'method';
+      symbol.parent = symbol.receiver;
+      symbol.qualifiedName = `${symbol.receiver}.${symbol.name}`;
+    } else if (symbol.kind === 'function' && (CLASS_LIKE.has(parent?.kind) || symbol.name.includes('::'))) {
+      symbol.kind = 'method';
+    }
+    stack.push(symbol);
+  }
+  return symbols;
+}
+
+function familyOf(language) {
+  if (language === 'python') return 'python';
+  if (language === 'ruby') return 'ruby';
+  return DECLARATIONS[language] ? 'brace' : null;
+}
+
+/**
+ * Parse symbols and keep token positions, for extractors that need to
+ * look inside declarations (signatures, bodies, calls)
+ * @returns {object} { language, tokens, match, symbols } where each symbol also
+ *   has first / last (token indices), nameIndex and body (index of '{' or null)
+ */
+function parseSymbols(content, language) {
+  const text = String(content || '');
+  const resolved = getSyntax(language).language;
+  const tokens = tokenize(text, resolved);
+  const { match, depth } = matchBrackets(tokens);
+  let symbols = [];
+  switch (familyOf(resolved)) {
+    case 'brace':
+      symbols = braceSymbols(resolved, tokens, match);
+      break;
+    case 'python':
+      symbols = pythonSymbols(text, tokens, match, depth);
+      break;
+    case 'ruby':
+      symbols = rubySymbols(tokens);
+      break;
+    default:
+      break;
+  }
+  nest(symbols);
+  for (const symbol of symbols) symbol.exported = isExported(resolved, symbol, symbol.depth === 0);
+  return { language: resolved, text, tokens, match, depth, symbols };
+}
+
+function publicSymbol(symbol) {
+  const { kind, name, qualifiedName, parent, depth, startLine, endLine, exported, modifiers } = symbol;
+  return { kind, name, qualifiedName, parent, depth, startLine, endLine, exported, modifiers };
+}
+
+/**
+ * Extract classes, methods, structs / enums / traits, imports and exports
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias (JS/TS, Python, Rust, Go, Java, C/C++, C#, Ruby,
+ *   PHP, Swift, Kotlin, Scala, Dart); others return only what the lexer can tell (nothing)
+ * @returns {object} { language, symbols: [{ kind, name, qualifiedName, parent, depth, startLine, endLine,
+ *   exported, modifiers }], imports: [{ source, names, line }], exports: [{ name, kind, line }] }
+ */
+function extractSymbols(content, language) {
+  const parsed = parseSymbols(content, language);
+  const { tokens, match, symbols } = parsed;
+
+  const statements = exportStatements(parsed.language, tokens, match);
+  const exports = statements.exports.map((entry) => {
+    const local = symbols.find((s) => s.depth === 0 && s.name === (entry.local || entry.name));
+    return { name: entry.name, kind: local ? local.kind : null, line: entry.line };
+  });
+  const listed = new Set(exports.map((entry) => entry.name));
+  for (const symbol of symbols) {
+    if (symbol.depth !== 0 || listed.has(symbol.name)) continue;
+    if (statements.explicit && parsed.language === 'python') continue; // __all__ is authoritative
+    if (symbol.exported) exports.push({ name: symbol.name, kind: symbol.kind, line: symbol.startLine });
+  }
+  const imports = extractImportList(parsed.text, parsed.language, tokens, match);
+  for (const entry of imports) {
+    if (entry.exported || entry.reexport) {
+      for (const name of entry.names) exports.push({ name, kind: 'import', line: entry.line });
+    }
+  }
+  exports.sort((a, b) => a.line - b.line);
+
+  return {
+    language: parsed.language,
+    symbols: symbols.map(publicSymbol),
+    imports,
+    exports,
+  };
+}
+
+module.exports = instrument('CODE', {
+  extractSymbols,
+  extractFunctionDetails,
+  parseSymbols,
+  attributesStart,
+  matchBrackets,
+});
@@ -0,0 +1,337 @@
+/**
+ * Code Syntax
+ * Whether a snapshot is syntactically well-formed, and where it isn't, so
+ * snapshots captured mid-edit (a half-typed call, an unclosed string) can be
+ * told apart from stable states.
+ *
+ * There's no parser per language here; these are the structural checks
+ * that catch the breakage mid-edit snapshots actually have:
+ *   all         unterminated strings and block comments, unbalanced or
+ *               mismatched brackets, a trailing operator at end of file
+ *   JSON        a full parse (comments and trailing commas allowed for jsonc)
+ *   Python      indentation: unexpected indents, dedents to no enclosing
+ *               level, a `:` header with no indented block
+ *   Ruby        def / class / do / if ... blocks balanced against `end`
+ * A file that passes may still not compile (types, unknown names); one that
+ * fails almost certainly doesn't parse.
+ */
+
+const { scanCode, tokenize, getSyntax, stripComments } = require('./code-lexer');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  maxErrors: 50,
+};
+
+const PAIRS = { '(': ')', '[': ']', '{': '}' };
+const CLOSERS = new Set([')', ']', '}']);
+
+// A file ending in one of these was cut off mid-expression
+const TRAILING_OPERATORS = new Set([
+  '=',
+  '+',
+  '-',
+  '*',
+  '/',
+  '%',
+  '.',
+  ',',
+  '&&',
+  '||',
+  '=>',
+  '->',
+  '?',
+  '::',
+  '+=',
+  '-=',
+  '==',
+  '===',
+  '!=',
+  '!==',
+  '<=',
+  '>=',
+  '&',
+  '|',
+  '^',
+  '??',
+  '?.',
+  ':=',
+]);
+
+const RUBY_OPENERS = new Set(['def', 'class', 'module', 'do', 'begin', 'case']);
+const RUBY_STATEMENT_OPENERS = new Set(['if', 'unless', 'while', 'until', 'for']);
+
+function lineStarts(content) {
+  const starts = [0];
+  for (let i = content.indexOf('\n'); i >= 0; i = content.indexOf('\n', i + 1)) starts.push(i + 1);
+  return starts;
+}
+
+function position(starts, offset) {
+  let low = 0;
+  let high = starts.length - 1;
+  while (low < high) {
+    const mid = (low + high + 1) >> 1;
+    if (starts[mid] <= offset) low = mid;
+    else high = mid - 1;
+  }
+  return { line: low + 1, column: offset - starts[low] + 1 };
+}
+
+// ---------------------------------------------------------------------------
+// Checks
+// ---------------------------------------------------------------------------
+
+function checkSegments(content, language, starts, errors) {
+  for (const segment of scanCode(content, language)) {
+    if (!segment.unterminated) continue;
+    // Python docstrings are comment segments, but to the user they're strings
+    const what = segment.type === 'string' || (language === 'python' && segment.doc) ? 'string' : 'comment';
+    errors.push({
+      type: `unterminated-${what}`,
+      message: `Unterminated ${what}`,
+      ...position(starts, segment.start),
+    });
+  }
+}
+
+function checkBrackets(tokens, starts, errors) {
+  const stack = [];
+  for (const token of tokens) {
+    if (token.type !== 'bracket') continue;
+    if (PAIRS[token.value]) {
+      stack.push(token);
+      continue;
+    }
+    if (!CLOSERS.has(token.value)) continue;
+    const top = stack[stack.length - 1];
+    if (top && PAIRS[top.value] === token.value) {
+      stack.pop();
+      continue;
+    }
+    // A closer for a bracket further down: the ones above it were never closed
+    const depth = stack.map((open) => PAIRS[open.value]).lastIndexOf(token.value);
+    if (depth < 0) {
+      errors.push({
+        type: 'unexpected-bracket',
+        message: `Unexpected '${token.value}'`,
+        ...position(starts, token.start),
+      });
+      continue;
+    }
+    while (stack.length > depth + 1) {
+      const open = stack.pop();
+      errors.push({
+        type: 'mismatched-bracket',
+        message: `'${open.value}' closed by '${token.value}'`,
+        ...position(starts, open.start),
+        endLine: token.line,
+      });
+    }
+    stack.pop();
+  }
+  for (const open of stack) {
+    errors.push({
+      type: 'unclosed-bracket',
+      message: `Unclosed '${open.value}'`,
+      ...position(starts, open.start),
+    });
+  }
+}
+
+function checkTrailing(tokens, language, starts, errors) {
+  const last = tokens[tokens.length - 1];
+  if (!last || !TRAILING_OPERATORS.has(last.value)) return;
+  if (language === 'shell' || language === 'makefile') return; // `&`, `|` and `.` mean other things there
+  errors.push({
+    type: 'incomplete',
+    message: `File ends with '${last.value}'`,
+    ...position(starts, last.start),
+  });
+}
+
+function checkJson(content, language, starts, errors) {
+  // Comments and trailing commas blanked out rather than removed, so error positions still line up
+  const text =
+    language === 'jsonc' ? stripComments(content, language).replace(/,(\s*[}\]])/g, ' $1') : content;
+  if (!text.trim()) return;
+  try {
+    JSON.parse(text);
+  } catch (error) {
+    // "... at position 12", "... (line 2 column 5)", or no position at all (newer V8): report the end
+    const at = /position (\d+)/.exec(error.message);
+    const lineColumn = /line (\d+) column (\d+)/.exec(error.message);
+    const where = lineColumn
+      ? { line: Number(lineColumn[1]), column: Number(lineColumn[2]) }
+      : position(starts, at ? Number(at[1]) : text.trimEnd().length);
+    // Newer V8 quotes the whole input back in the message
+    const message = error.message.replace(/^JSON\.parse: /, '').replace(/, ".*" is not valid JSON$/s, '');
+    errors.push({ type: 'invalid-json', message, ...where });
+  }
+}
+
+/**
+ * Indentation of logical lines: statements outside brackets and line continuations
+ */
+function checkPythonIndentation(content, tokens, starts, errors) {
+  // Docstrings are comment segments to the lexer but statements here: a docstring-only body is a block
+  const docstrings = scanCode(content, 'python')
+    .filter((segment) => segment.type === 'comment' && segment.doc)
+    .map((segment) => ({
+      type: 'string',
+      value: 'docstring',
+      start: segment.start,
+      ...position(starts, segment.start),
+    }));
+  const statements = [...tokens, ...docstrings].sort((a, b) => a.start - b.start);
+  const indents = [0];
+  let depth = 0;
+  let previous = null;
+  let expectBlock = null; // The `:` token that opened a block
+  for (const token of statements) {
+    const startsLine = !previous || (token.line > previous.line && depth === 0 && previous.value !== '\\');
+    if (startsLine) {
+      const lineStart = starts[token.line - 1];
+      const indent = content.slice(lineStart, token.start).replace(/\t/g, '        ').length;
+      const top = indents[indents.length - 1];
+      if (expectBlock) {
+        if (indent > top) indents.push(indent);
+        else {
+          errors.push({
+            type: 'expected-block',
+            message: 'Expected an indented block',
+            ...position(starts, expectBlock.start),
+          });
+        }
+      } else if (indent > top) {
+        errors.push({
+          type: 'unexpected-indent',
+          message: 'Unexpected indent',
+          ...position(starts, token.start),
+        });
+      } else if (indent < top) {
+        while (indents.length > 1 && indents[indents.length - 1] > indent) indents.pop();
+        if (indents[indents.length - 1] !== indent) {
+          errors.push({
+            type: 'inconsistent-dedent',
+            message: 'Dedent does not match any outer indentation level',
+            ...position(starts, token.start),
+          });
+          indents.push(indent);
+        }
+      }
+      expectBlock = null;
+    }
+    if (token.type === 'bracket') depth += PAIRS[token.value] ? 1 : CLOSERS.has(token.value) ? -1 : 0;
+    if (depth < 0) depth = 0;
+    previous = token;
+    // A header: `def f():`, `if x:`, `else:`; one-line bodies (`if x: y`) don't open a block
+    if (token.value === ':' && depth === 0) expectBlock = token;
+    else if (expectBlock && token.line === expectBlock.line) expectBlock = null;
+  }
+  if (expectBlock) {
+    errors.push({
+      type: 'expected-block',
+      message: 'Expected an indented block',
+      ...position(starts, expectBlock.start),
+    });
+  }
+}
+
+function checkRubyBlocks(tokens, starts, errors) {
+  const stack = [];
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (token.type !== 'keyword' && token.type !== 'identifier') continue;
+    const previous = tokens[i - 1];
+    if (previous && (previous.value === '.' || previous.value === '::' || previous.value === ':')) continue;
+    const statementStart =
+      !previous || previous.line < token.line || ['=', '(', ';', '||=', 'then'].includes(previous.value);
+
+    if (token.value === 'def') {
+      // Endless method: def name(args) = expr
+      let k = i + 1;
+      let parens = 0;
+      let endless = false;
+      while (tokens[k] && tokens[k].line === token.line) {
+        if (tokens[k].value === '(') parens++;
+        else if (tokens[k].value === ')') parens--;
+        else if (tokens[k].value === '=' && parens === 0 && k > i + 1) endless = true;
+        k++;
+      }
+      if (!endless) stack.push(token);
+    } else if (token.value === 'do') {
+      // `while x do` / `for a in b do`: the loop already opened the block
+      const loop = stack[stack.length - 1];
+      const sameLoop = loop && loop.line === token.line && ['while', 'until', 'for'].includes(loop.value);
+      if (!sameLoop) stack.push(token);
+    } else if (RUBY_OPENERS.has(token.value)) {
+      stack.push(token);
+    } else if (RUBY_STATEMENT_OPENERS.has(token.value) && statementStart) {
+      stack.push(token);
+    } else if (token.value === 'end') {
+      if (!stack.pop()) {
+        errors.push({
+          type: 'unexpected-end',
+          message: "Unexpected 'end'",
+          ...position(starts, token.start),
+        });
+      }
+    }
+  }
+  for (const open of stack) {
+    errors.push({
+      type: 'unclosed-block',
+      message: `'${open.value}' without 'end'`,
+      ...position(starts, open.start),
+    });
+  }
+}
+
+/**
+ * Check a snapshot for syntax errors
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias
+ * @param {object} options - { maxErrors }
+ * @returns {object} { language, valid, errors: [{ type, message, line, column, endLine? }], checks }
+ *   errors are sorted by position and capped at maxErrors; checks lists what ran
+ */
+function checkSyntax(content, language, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const text = String(content || '');
+  const resolved = getSyntax(language).language;
+  const starts = lineStarts(text);
+  const errors = [];
+  const checks = [];
+
+  if (resolved === 'json' || resolved === 'jsonc') {
+    checks.push('json');
+    checkJson(text, resolved, starts, errors);
+  } else if (!['text', 'markdown', 'unknown'].includes(resolved)) {
+    const tokens = tokenize(text, resolved);
+    checks.push('strings', 'brackets', 'trailing');
+    checkSegments(text, resolved, starts, errors);
+    checkBrackets(tokens, starts, errors);
+    checkTrailing(tokens, resolved, starts, errors);
+    if (resolved === 'python') {
+      checks.push('indentation');
+      checkPythonIndentation(text, tokens, starts, errors);
+    } else if (resolved === 'ruby') {
+      checks.push('blocks');
+      checkRubyBlocks(tokens, starts, errors);
+    }
+  }
+
+  errors.sort((a, b) => a.line - b.line || a.column - b.column);
+  return {
+    language: resolved,
+    valid: errors.length === 0,
+    errors: errors.slice(0, opts.maxErrors),
+    checks,
+  };
+}
+
+module.exports = instrument('CODE', {
+  checkSyntax,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,399 @@
+/**
+ * Code Tests
+ * Whether a file is test code, and the test cases in it with their line
+ * ranges, so an edit can be classified as touching tests or not.
+ *
+ * A file is a test file by its path (foo.test.ts, test_foo.py, foo_test.go,
+ * FooTest.java, spec/, __tests__/, src/test/, ...) or, where the path says
+ * nothing, by containing tests. Test cases come from code-symbols plus each
+ * framework's markers:
+ *   JS / TS     describe / context / suite and it / test / specify calls,
+ *               with .skip / .only / .todo / .each and x / f prefixes
+ *   Python      test_* functions, Test* classes and unittest.TestCase subclasses
+ *   Go          TestXxx / BenchmarkXxx / FuzzXxx / ExampleXxx and t.Run subtests
+ *   Rust        #[test]-style attributes, #[cfg(test)] modules
+ *   Java, Kotlin, C#, Swift, PHP: @Test / [Fact] / [Test] annotations and test* methods
+ *   Ruby        RSpec describe / context / it blocks and minitest test_* methods
+ */
+
+const { parseSymbols, attributesStart } = require('./code-symbols');
+const { instrument } = require('./logger');
+
+// Path patterns, checked against the forward-slash path
+const TEST_PATHS = [
+  /(^|\/)(__tests__|__mocks__|tests?|spec|specs|e2e|cypress|testing)\//i,
+  /(^|\/)src\/(test|androidTest|testFixtures)\//,
+  /\.(test|spec|e2e|cy)\.[cm]?[jt]sx?$/,
+  /(^|\/)(test_[^/]*|[^/]*_test)\.py$/,
+  /(^|\/)conftest\.py$/,
+  /_test\.go$/,
+  /(^|\/)[^/]*(Test|Tests|IT|Spec)\.(java|kt|kts|scala|groovy|cs|swift|php)$/,
+  /(_spec|_test)\.rb$/,
+  /(^|\/)test_[^/]*\.rb$/,
+  /_test\.(dart|exs)$/,
+];
+
+const JS_SUITES = new Set(['describe', 'context', 'suite', 'xdescribe', 'fdescribe', 'xcontext']);
+const JS_TESTS = new Set(['it', 'test', 'specify', 'xit', 'fit', 'xtest', 'xspecify', 'bench']);
+const JS_MODIFIERS = new Set(['skip', 'only', 'todo', 'concurrent', 'each', 'failing', 'serial']);
+const JS_FRAMEWORKS = [
+  ['vitest', 'vitest'],
+  ['@jest/globals', 'jest'],
+  ['node:test', 'node:test'],
+  ['mocha', 'mocha'],
+  ['ava', 'ava'],
+  ['tap', 'tap'],
+  ['@playwright/test', 'playwright'],
+];
+
+const RUBY_SUITES = new Set(['describe', 'context', 'feature', 'shared_examples', 'shared_context']);
+const RUBY_TESTS = new Set(['it', 'specify', 'scenario', 'example', 'xit', 'fit']);
+const RUBY_OPENERS = new Set(['do', 'def', 'class', 'module', 'begin', 'case']);
+const RUBY_CONDITIONALS = new Set(['if', 'unless', 'while', 'until']);
+
+// Attributes / annotations that mark a test method, by language family
+const TEST_ATTRIBUTES = new Set([
+  'test',
+  'Test',
+  'ParameterizedTest',
+  'RepeatedTest',
+  'TestFactory',
+  'TestTemplate',
+  'Fact',
+  'Theory',
+  'TestMethod',
+  'TestCase',
+  'TestCaseSource',
+  'DataTestMethod',
+  'rstest',
+  'quickcheck',
+  'proptest',
+]);
+const BENCH_ATTRIBUTES = new Set(['bench', 'Benchmark']);
+const SKIP_ATTRIBUTES = new Set(['ignore', 'Ignore', 'Disabled', 'skip', 'Skip', 'xfail']);
+
+const TEST_CASE_BASES = new Set(['TestCase', 'XCTestCase', 'IsolatedAsyncioTestCase']);
+
+const GO_PREFIXES = [
+  ['Test', 'test'],
+  ['Benchmark', 'benchmark'],
+  ['Fuzz', 'test'],
+  ['Example', 'example'],
+];
+
+function unquote(value) {
+  const match = /^(?:[a-zA-Z]*)(["'`])([\s\S]*)\1$/.exec(value);
+  return match ? match[2] : value;
+}
+
+function lastLine(token) {
+  return token.line + (token.value.match(/\n/g) || []).length;
+}
+
+/**
+ * Names of the attributes / annotations / decorators before a declaration:
+ * #[tokio::test] -> 'tokio::test', @Test -> 'Test', @pytest.mark.skip -> 'pytest.mark.skip'
+ */
+function attributeNames(tokens, from, to) {
+  const names = [];
+  for (let i = from; i < to; i++) {
+    const value = tokens[i].value;
+    if (value !== '@' && value !== '[' && value !== '#' && value !== '#!') continue;
+    let j = value === '#' || value === '#!' ? i + 2 : i + 1; // #[name], [Name], @name
+    let name = '';
+    while (j < to && (tokens[j].type === 'identifier' || tokens[j].type === 'keyword')) {
+      name += tokens[j].value;
+      if (tokens[j + 1]?.value !== '.' && tokens[j + 1]?.value !== '::') break;
+      name += tokens[j + 1].value;
+      j += 2;
+    }
+    if (name) names.push(name);
+  }
+  return names;
+}
+
+function lastSegment(name) {
+  const parts = name.split(/::|\./);
+  return parts[parts.length - 1];
+}
+
+// ---------------------------------------------------------------------------
+// Call-style frameworks (JS, Ruby, Go subtests)
+// ---------------------------------------------------------------------------
+
+/**
+ * describe / it calls, nested by their argument ranges
+ */
+function jsTests(tokens, match) {
+  const tests = [];
+  const suites = []; // Open suites: { name, end }
+  for (let i = 0; i < tokens.length; i++) {
+    while (suites.length && suites[suites.length - 1].end < i) suites.pop();
+    const token = tokens[i];
+    const isSuite = JS_SUITES.has(token.value);
+    if (token.type !== 'identifier' || (!isSuite && !JS_TESTS.has(token.value))) continue;
+    if (tokens[i - 1]?.value === '.') continue; // foo.it(...)
+
+    let j = i + 1;
+    const modifiers = [];
+    while (tokens[j]?.value === '.' && JS_MODIFIERS.has(tokens[j + 1]?.value)) {
+      modifiers.push(tokens[j + 1].value);
+      j += 2;
+      const table = modifiers[modifiers.length - 1] === 'each' && tokens[j]?.value === '(' && match[j] > j;
+      if (table) j = match[j] + 1;
+    }
+    // it.each`table` template form
+    if (tokens[j]?.type === 'string' && modifiers.includes('each')) j++;
+    if (tokens[j]?.value !== '(' || match[j] < j || tokens[j + 1]?.type !== 'string') continue;
+
+    const name = unquote(tokens[j + 1].value);
+    tests.push({
+      name,
+      kind: isSuite ? 'suite' : token.value === 'bench' ? 'benchmark' : 'test',
+      suite: suites.length ? suites.map((suite) => suite.name).join(' > ') : null,
+      startLine: token.line,
+      endLine: lastLine(tokens[match[j]]),
+      skipped: modifiers.includes('skip') || modifiers.includes('todo') || token.value.startsWith('x'),
+      only: modifiers.includes('only') || token.value === 'fit' || token.value === 'fdescribe',
+    });
+    if (isSuite) suites.push({ name, end: match[j] });
+  }
+  return tests;
+}
+
+/**
+ * The `end` closing a Ruby `do` block
+ */
+function rubyBlockEnd(tokens, open) {
+  let depth = 0;
+  for (let i = open; i < tokens.length; i++) {
+    const token = tokens[i];
+    if (token.type !== 'keyword' && token.type !== 'identifier') continue;
+    const statementStart = i === 0 || tokens[i - 1].line < token.line || tokens[i - 1].value === '=';
+    if (RUBY_OPENERS.has(token.value) || (RUBY_CONDITIONALS.has(token.value) && statementStart)) depth++;
+    else if (token.value === 'end' && --depth === 0) return i;
+  }
+  return tokens.length - 1;
+}
+
+function rubySpecs(tokens, match) {
+  const tests = [];
+  const suites = [];
+  for (let i = 0; i < tokens.length; i++) {
+    while (suites.length && suites[suites.length - 1].end < i) suites.pop();
+    const token = tokens[i];
+    const isSuite = RUBY_SUITES.has(token.value);
+    if (!isSuite && !RUBY_TESTS.has(token.value)) continue;
+    if (tokens[i - 1]?.value === '.' && tokens[i - 2]?.value !== 'RSpec') continue;
+
+    // describe "x" do / it("x") { } / describe User, type: :model do
+    let j = i + 1;
+    const parenthesized = tokens[j]?.value === '(' && tokens[j].line === token.line;
+    if (parenthesized) j++;
+    const argument = tokens[j];
+    if (!argument || argument.line !== token.line) continue;
+    let name = argument.type === 'string' ? unquote(argument.value) : null;
+    if (!name && isSuite && argument.type === 'identifier') {
+      name = argument.value;
+      while (tokens[j + 1]?.value === '::' && tokens[j + 2]) {
+        name += `::${tokens[j + 2].value}`;
+        j += 2;
+      }
+    }
+    if (!name) continue;
+
+    let k = parenthesized && match[i + 1] > i ? match[i + 1] + 1 : j + 1;
+    const opensBlock = (t) => t.value === 'do' || t.value === '{';
+    while (tokens[k] && tokens[k].line === token.line && !opensBlock(tokens[k])) k++;
+    let end;
+    if (tokens[k]?.value === 'do') end = rubyBlockEnd(tokens, k);
+    else if (tokens[k]?.value === '{' && match[k] > k) end = match[k];
+    else continue;
+
+    tests.push({
+      name,
+      kind: isSuite ? 'suite' : 'test',
+      suite: suites.length ? suites.map((suite) => suite.name).join(' > ') : null,
+      startLine: token.line,
+      endLine: lastLine(tokens[end]),
+      skipped: token.value.startsWith('x'),
+      only: token.value === 'fit',
+    });
+    if (isSuite) suites.push({ name, end });
+  }
+  return tests;
+}
+
+/**
+ * t.Run("name", ...) subtests inside a Go test function
+ */
+function goSubtests(tokens, match, symbol, suite) {
+  const tests = [];
+  for (let i = symbol.body ?? symbol.first; i < symbol.last; i++) {
+    if (tokens[i].value !== 'Run' || tokens[i - 1]?.value !== '.' || tokens[i + 1]?.value !== '(') continue;
+    if (tokens[i + 2]?.type !== 'string' || match[i + 1] < 0) continue;
+    tests.push({
+      name: unquote(tokens[i + 2].value),
+      kind: 'test',
+      suite,
+      startLine: tokens[i].line,
+      endLine: lastLine(tokens[match[i + 1]]),
+      skipped: false,
+      only: false,
+    });
+  }
+  return tests;
+}
+
+// ---------------------------------------------------------------------------
+// Declaration-style frameworks
+// ---------------------------------------------------------------------------
+
+function declaredTests(parsed) {
+  const { tokens, match, symbols, language } = parsed;
+  const byName = new Map(symbols.map((symbol) => [symbol.qualifiedName, symbol]));
+  const tests = [];
+  const suites = new Set();
+
+  for (const symbol of symbols) {
+    const from = language === 'python' ? symbol.first : attributesStart(tokens, match, symbol.first);
+    const to = symbol.nameIndex; // C# [Attribute]s can fall inside the declaration's own range
+    const attributes = attributeNames(tokens, from, to).map(lastSegment);
+    const parent = byName.get(symbol.parent);
+    const skipped = attributes.some((name) => SKIP_ATTRIBUTES.has(name));
+    let kind = null;
+
+    if (symbol.kind === 'module' && attributeNames(tokens, from, to).includes('cfg')) {
+      // #[cfg(test)] mod tests
+      const cfg = tokens.slice(from, to).some((token) => token.value === 'test');
+      if (cfg) {
+        suites.add(symbol.qualifiedName);
+        tests.push({ symbol, kind: 'suite', skipped: false });
+      }
+      continue;
+    }
+    if (symbol.kind !== 'function' && symbol.kind !== 'method') {
+      const base = tokens.slice(symbol.nameIndex + 1, symbol.body ?? symbol.last);
+      const testCase = base.some((token) => TEST_CASE_BASES.has(token.value));
+      if ((language === 'python' && /^Test/.test(symbol.name)) || testCase) {
+        suites.add(symbol.qualifiedName);
+        tests.push({ symbol, kind: 'suite', skipped });
+      }
+      continue;
+    }
+
+    if (attributes.some((name) => BENCH_ATTRIBUTES.has(name))) kind = 'benchmark';
+    else if (attributes.some((name) => TEST_ATTRIBUTES.has(name))) kind = 'test';
+    else if (language === 'go' && !symbol.receiver) {
+      // TestXxx, not Testify: the prefix must not run into a lowercase letter
+      const { name } = symbol;
+      const prefix = GO_PREFIXES.find(([p]) => name.startsWith(p) && !/^[a-z]/.test(name.slice(p.length)));
+      if (prefix) kind = prefix[1];
+    } else if (['python', 'ruby'].includes(language) && /^test_?/.test(symbol.name)) {
+      // pytest collects top-level test_* and methods of Test* classes
+      if (!parent || suites.has(parent.qualifiedName) || language === 'ruby') kind = 'test';
+    } else if (['swift', 'php', 'java', 'kotlin'].includes(language) && /^test[A-Z_]/.test(symbol.name)) {
+      if (parent && suites.has(parent.qualifiedName)) kind = 'test';
+      else if (language === 'php' || language === 'swift') kind = 'test';
+    }
+    if (!kind) continue;
+    if (parent && !suites.has(parent.qualifiedName) && parent.kind !== 'function') {
+      suites.add(parent.qualifiedName);
+      tests.push({ symbol: parent, kind: 'suite', skipped: false });
+    }
+    tests.push({ symbol, kind, skipped });
+    if (language === 'go' && kind === 'test') {
+      tests.push(...goSubtests(tokens, match, symbol, symbol.name).map((test) => ({ test })));
+    }
+  }
+
+  return tests.map((entry) => {
+    if (entry.test) return entry.test;
+    const { symbol } = entry;
+    const parent = symbol.parent && suites.has(symbol.parent) ? symbol.parent : null;
+    return {
+      name: symbol.name,
+      kind: entry.kind,
+      suite: parent ? parent.split('.').join(' > ') : null,
+      startLine: symbol.startLine,
+      endLine: symbol.endLine,
+      skipped: entry.skipped,
+      only: false,
+    };
+  });
+}
+
+// ---------------------------------------------------------------------------
+// Detection
+// ---------------------------------------------------------------------------
+
+function frameworkOf(language, content, tests) {
+  switch (language) {
+    case 'javascript':
+    case 'typescript': {
+      for (const [module, framework] of JS_FRAMEWORKS) {
+        if (content.includes(`'${module}'`) || content.includes(`"${module}"`)) return framework;
+      }
+      return tests.length ? 'jest' : null; // Globals: jest / mocha / jasmine style
+    }
+    case 'python':
+      return /\bunittest\b|\bTestCase\b/.test(content) ? 'unittest' : tests.length ? 'pytest' : null;
+    case 'go':
+      return tests.length ? 'testing' : null;
+    case 'rust':
+      return tests.length ? 'cargo' : null;
+    case 'java':
+    case 'kotlin':
+      if (/org\.junit\.jupiter/.test(content)) return 'junit5';
+      return /org\.junit|junit\./.test(content) ? 'junit' : null;
+    case 'csharp':
+      if (/\bXunit\b/.test(content)) return 'xunit';
+      if (/\bNUnit\b/.test(content)) return 'nunit';
+      return /MSTest|VisualStudio\.TestTools/.test(content) ? 'mstest' : null;
+    case 'ruby':
+      return /\bRSpec\b|\bdescribe\b/.test(content) ? 'rspec' : tests.length ? 'minitest' : null;
+    case 'swift':
+      return /\bXCTest\b/.test(content) ? 'xctest' : null;
+    case 'php':
+      return /PHPUnit/.test(content) ? 'phpunit' : null;
+    default:
+      return null;
+  }
+}
+
+/**
+ * Classify a file as test code and list its test cases
+ * @param {string} content - Source text
+ * @param {string} language - Language name or alias
+ * @param {string} filePath - Path, used for naming conventions (optional)
+ * @returns {object} { language, isTestFile, byPath, framework, tests: [{ name, kind, suite, startLine,
+ *   endLine, skipped, only }] }; kind is suite | test | benchmark | example, suite the enclosing suites
+ *   joined by ' > '. A source file with an inline test module (Rust #[cfg(test)]) has tests but isn't
+ *   a test file.
+ */
+function detectTests(content, language, filePath = null) {
+  const parsed = parseSymbols(content, language);
+  const { tokens, match } = parsed;
+  const normalized = filePath ? String(filePath).replace(/\\/g, '/') : '';
+  const byPath = Boolean(normalized) && TEST_PATHS.some((pattern) => pattern.test(normalized));
+
+  let tests;
+  if (parsed.language === 'javascript' || parsed.language === 'typescript') tests = jsTests(tokens, match);
+  else if (parsed.language === 'ruby') tests = [...rubySpecs(tokens, match), ...declaredTests(parsed)];
+  else tests = declaredTests(parsed);
+  tests.sort((a, b) => a.startLine - b.startLine);
+
+  const cases = tests.filter((test) => test.kind !== 'suite');
+  const inlineOnly = parsed.language === 'rust' && !/(^|\/)(tests|benches)\//.test(normalized);
+  return {
+    language: parsed.language,
+    isTestFile: byPath || (cases.length > 0 && !inlineOnly),
+    byPath,
+    framework: frameworkOf(parsed.language, parsed.text, cases),
+    tests,
+  };
+}
+
+module.exports = instrument('CODE', {
+  detectTests,
+});
@@ -0,0 +1,189 @@
+/**
+ * Content-Defined Chunking
+ * FastCDC (gear-hash rolling fingerprint with normalized chunking) that splits
+ * content into variable-size chunks whose boundaries survive insertions, so
+ * successive versions of a large file share most of their chunk hashes.
+ */
+
+const fs = require('fs');
+const { hashContent, DEFAULT_ALGORITHM } = require('./content-hash');
+
+const DEFAULT_OPTIONS = {
+  minSize: 2 * 1024,
+  avgSize: 8 * 1024,
+  maxSize: 64 * 1024,
+  hashAlgorithm: DEFAULT_ALGORITHM,
+};
+
+/**
+ * Deterministic gear table (splitmix32) so chunk boundaries are stable across runs
+ */
+const GEAR = (() => {
+  const table = new Uint32Array(256);
+  let state = 0x9e3779b9;
+  for (let i = 0; i < 256; i++) {
+    state = (state + 0x9e3779b9) | 0;
+    let z = state;
+    z = Math.imul(z ^ (z >>> 16), 0x85ebca6b);
+    z = Math.imul(z ^ (z >>> 13), 0xc2b2ae35);
+    table[i] = (z ^ (z >>> 16)) >>> 0;
+  }
+  return table;
+})();
+
+function topBitsMask(bits) {
+  const clamped = Math.max(1, Math.min(31, bits));
+  return (0xffffffff << (32 - clamped)) >>> 0;
+}
+
+function validateOptions(options) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  if (!(opts.minSize > 0 && opts.minSize <= opts.avgSize && opts.avgSize <= opts.maxSize)) {
+    throw new Error('Chunk sizes must satisfy 0 < minSize <= avgSize <= maxSize');
+  }
+  const bits = Math.round(Math.log2(opts.avgSize));
+  // Normalized chunking: stricter mask before the average size, looser after it
+  opts.maskS = topBitsMask(bits + 1);
+  opts.maskL = topBitsMask(bits - 1);
+  return opts;
+}
+
+/**
+ * Find the next cut point in data[start, end)
+ * @returns {number} Length of the chunk starting at `start`
+ */
+function findCutPoint(data, start, end, opts) {
+  const remaining = end - start;
+  if (remaining <= opts.minSize) return remaining;
+
+  const limit = Math.min(remaining, opts.maxSize);
+  const normal = Math.min(limit, opts.avgSize);
+  let fp = 0;
+  let i = opts.minSize;
+
+  for (; i < normal; i++) {
+    fp = ((fp << 1) + GEAR[data[start + i]]) >>> 0;
+    if ((fp & opts.maskS) === 0) return i + 1;
+  }
+  for (; i < limit; i++) {
+    fp = ((fp << 1) + GEAR[data[start + i]]) >>> 0;
+    if ((fp & opts.maskL) === 0) return i + 1;
+  }
+  return limit;
+}
+
+/**
+ * Incremental chunker for streamed input
+ */
+class ContentChunker {
+  constructor(options = {}) {
+    this.opts = validateOptions(options);
+    this.pending = Buffer.alloc(0);
+    this.offset = 0;
+  }
+
+  emit(data, length) {
+    const chunk = {
+      offset: this.offset,
+      length,
+      hash: hashContent(data.subarray(0, length), this.opts.hashAlgorithm),
+    };
+    this.offset += length;
+    return chunk;
+  }
+
+  /**
+   * Feed more bytes; returns chunks that are now final
+   */
+  push(data) {
+    const input = typeof data === 'string' ? Buffer.from(data, 'utf8') : Buffer.from(data);
+    this.pending = this.pending.length > 0 ? Buffer.concat([this.pending, input]) : input;
+
+    const chunks = [];
+    let start = 0;
+    // A cut is only final once maxSize bytes are buffered past the chunk start
+    while (this.pending.length - start >= this.opts.maxSize) {
+      const length = findCutPoint(this.pending, start, this.pending.length, this.opts);
+      chunks.push(this.emit(this.pending.subarray(start), length));
+      start += length;
+    }
+    this.pending = this.pending.subarray(start);
+    return chunks;
+  }
+
+  /**
+   * Flush the remaining buffered bytes
+   */
+  finish() {
+    const chunks = [];
+    let start = 0;
+    while (start < this.pending.length) {
+      const length = findCutPoint(this.pending, start, this.pending.length, this.opts);
+      chunks.push(this.emit(this.pending.subarray(start), length));
+      start += length;
+    }
+    this.pending = Buffer.alloc(0);
+    return chunks;
+  }
+}
+
+/**
+ * Split content into content-defined chunks
+ * @param {string|Buffer} content - Content to chunk
+ * @param {object} options - { minSize, avgSize, maxSize, hashAlgorithm }
+ * @returns {Array<{offset, length, hash}>}
+ */
+function chunkContent(content, options = {}) {
+  const chunker = new ContentChunker(options);
+  return [...chunker.push(content), ...chunker.finish()];
+}
+
+/**
+ * Chunk a file by streaming it from disk
+ */
+function chunkFile(filePath, options = {}) {
+  return new Promise((resolve, reject) => {
+    const chunker = new ContentChunker(options);
+    const chunks = [];
+    const stream = fs.createReadStream(filePath, { highWaterMark: 256 * 1024 });
+    stream.on('data', (data) => chunks.push(...chunker.push(data)));
+    stream.on('error', reject);
+    stream.on('end', () => resolve([...chunks, ...chunker.finish()]));
+  });
+}
+
+/**
+ * Compare two chunk lists and report how much of `next` is already stored
+ */
+function compareChunks(previous, next) {
+  const known = new Set(previous.map((c) => c.hash));
+  let reusedBytes = 0;
+  let newBytes = 0;
+  const newChunks = [];
+
+  for (const chunk of next) {
+    if (known.has(chunk.hash)) {
+      reusedBytes += chunk.length;
+    } else {
+      newBytes += chunk.length;
+      newChunks.push(chunk);
+    }
+  }
+
+  const total = reusedBytes + newBytes;
+  return {
+    reusedChunks: next.length - newChunks.length,
+    newChunks,
+    reusedBytes,
+    newBytes,
+    dedupRatio: total > 0 ? reusedBytes / total : 1,
+  };
+}
+
+module.exports = {
+  chunkContent,
+  chunkFile,
+  compareChunks,
+  ContentChunker,
+  DEFAULT_OPTIONS,
+};
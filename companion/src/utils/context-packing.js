@@ -0,0 +1,115 @@
+/**
+ * Context Packing
+ * Chooses which candidate snippets fit a prompt's token budget so that the
+ * total relevance is maximal (0/1 knapsack), then orders the selection the
+ * way the context would be laid out. Used to reconstruct what context the
+ * IDE most likely sent to the model.
+ *
+ * The DP runs over token cost; when items x budget would be too large, costs
+ * are bucketed (rounded up, so the budget is never exceeded) and the result
+ * is flagged as approximate.
+ */
+
+const { countTokens, isEncodingAvailable, DEFAULT_ENCODING } = require('./bpe-tokenizer');
+const { estimateTokens } = require('./diff-engine');
+
+const DEFAULT_OPTIONS = {
+  order: 'score', // 'score' | 'file' (by path, then line) | 'input'
+  perSnippetOverhead: 0, // Tokens spent on separators/headers per included snippet
+  pinned: [], // Snippet ids that must be included
+  maxCells: 5000000, // Upper bound on DP table size before costs are bucketed
+  encoding: DEFAULT_ENCODING,
+};
+
+function snippetCost(snippet, opts) {
+  if (Number.isFinite(snippet.tokens)) return snippet.tokens;
+  if (Number.isFinite(snippet.tokenCount)) return snippet.tokenCount;
+  const text = String(snippet.text ?? snippet.content ?? '');
+  return isEncodingAvailable(opts.encoding) ? countTokens(text, opts.encoding) : estimateTokens(text);
+}
+
+function orderSelection(selected, order) {
+  if (order === 'input') return selected.sort((a, b) => a.index - b.index);
+  if (order === 'file') {
+    return selected.sort((a, b) => {
+      const pa = String(a.snippet.filePath ?? a.snippet.file_path ?? '');
+      const pb = String(b.snippet.filePath ?? b.snippet.file_path ?? '');
+      if (pa !== pb) return pa < pb ? -1 : 1;
+      return (a.snippet.startLine ?? 0) - (b.snippet.startLine ?? 0) || a.index - b.index;
+    });
+  }
+  return selected.sort((a, b) => b.score - a.score || a.index - b.index);
+}
+
+/**
+ * Select the highest-relevance subset of snippets that fits a token budget
+ * @param {Array<object>} snippets - [{ id, score, tokens?, text?, filePath?, startLine? }]
+ * @param {number} budgetTokens - Total token budget
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {object} { selected, dropped, totalTokens, totalScore, budget, approximate }
+ */
+function packContext(snippets, budgetTokens, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const pinnedIds = new Set(opts.pinned || []);
+  const items = (snippets || []).map((snippet, index) => ({
+    snippet,
+    index,
+    id: snippet.id ?? index,
+    score: Number(snippet.score ?? snippet.relevance ?? 0),
+    cost: snippetCost(snippet, opts) + opts.perSnippetOverhead,
+  }));
+
+  // Pinned snippets are taken first; the rest compete for what's left
+  const pinned = items.filter((item) => pinnedIds.has(item.id));
+  const totalBudget = Math.max(0, Math.floor(budgetTokens));
+  const budget = totalBudget - pinned.reduce((sum, item) => sum + item.cost, 0);
+  if (budget < 0) throw new Error('Pinned snippets alone exceed the token budget');
+
+  // Only positive-score items that fit at all are worth considering
+  const candidates = items.filter((item) => !pinnedIds.has(item.id) && item.score > 0 && item.cost <= budget);
+  const free = candidates.filter((item) => item.cost === 0);
+  const costly = candidates.filter((item) => item.cost > 0);
+
+  const scale = Math.max(1, Math.ceil((costly.length * (budget + 1)) / opts.maxCells));
+  const capacity = Math.floor(budget / scale);
+  const weights = costly.map((item) => Math.ceil(item.cost / scale));
+
+  const best = new Float64Array(capacity + 1);
+  const taken = new Uint8Array(costly.length * (capacity + 1));
+  costly.forEach((item, i) => {
+    const w = weights[i];
+    for (let c = capacity; c >= w; c--) {
+      const value = best[c - w] + item.score;
+      if (value > best[c]) {
+        best[c] = value;
+        taken[i * (capacity + 1) + c] = 1;
+      }
+    }
+  });
+
+  const chosen = [...pinned, ...free];
+  let c = capacity;
+  for (let i = costly.length - 1; i >= 0; i--) {
+    if (taken[i * (capacity + 1) + c]) {
+      chosen.push(costly[i]);
+      c -= weights[i];
+    }
+  }
+
+  const chosenIds = new Set(chosen.map((item) => item.index));
+  const ordered = orderSelection(chosen, opts.order);
+
+  return {
+    selected: ordered.map((item) => ({ ...item.snippet, id: item.id, tokens: item.cost - opts.perSnippetOverhead })),
+    dropped: items.filter((item) => !chosenIds.has(item.index)).map((item) => item.id),
+    totalTokens: chosen.reduce((sum, item) => sum + item.cost, 0),
+    totalScore: chosen.reduce((sum, item) => sum + item.score, 0),
+    budget: totalBudget,
+    approximate: scale > 1,
+  };
+}
+
+module.exports = {
+  packContext,
+  DEFAULT_OPTIONS,
+};
@@ -6,6 +6,17 @@
  */
 
 const diff = require('diff');
+const { countWords, isProse, proseStats } = require('./text-segmentation');
+const { countTokens, isEncodingAvailable } = require('./bpe-tokenizer');
+const { detectEncoding, decodeBuffer, detectLineEndings, normalizeLineEndings } = require('./file-reader');
+const { startOperation } = require('./operation-handle');
+const { measureComments, tokenize } = require('./code-lexer');
+const { extractSymbols } = require('./code-symbols');
+const { detectLanguages } = require('./code-language');
+const { CompanionError, ERROR_CODES } = require('./errors');
+const { createLogger, instrument } = require('./logger');
+
+const log = createLogger('DIFF');
 
 // Try to load native module
 let native = null;
@@ -18,19 +29,108 @@ try {
   // Fallback to JS
 }
 
+// ---------------------------------------------------------------------------
+// Line endings and BOMs
+// ---------------------------------------------------------------------------
+
+const BOM = '\uFEFF';
+const LINE_ENDING_MODES = ['normalize', 'preserve', 'report'];
+
+/**
+ * Accept Buffers / TypedArrays wherever text is expected: bytes are decoded
+ * with their detected encoding (the BOM is kept, so the lineEndings mode
+ * decides what happens to it) and binary data maps byte-for-char via Latin-1
+ */
+function toText(input) {
+  if (typeof input === 'string') return input;
+  if (!isBytes(input)) return input;
+  const bytes = asBuffer(input);
+  const { encoding, binary } = detectEncoding(bytes);
+  return binary ? bytes.toString('latin1') : decodeBuffer(bytes, encoding);
+}
+
+function isBytes(input) {
+  return input instanceof ArrayBuffer || ArrayBuffer.isView(input);
+}
+
+function asBuffer(input) {
+  if (Buffer.isBuffer(input)) return input;
+  if (input instanceof ArrayBuffer) return Buffer.from(input);
+  return Buffer.from(input.buffer, input.byteOffset, input.byteLength);
+}
+
+function describeText(text) {
+  const { lineEnding, counts } = detectLineEndings(text);
+  return { lineEnding, counts, bom: text.startsWith(BOM) };
+}
+
+/**
+ * Apply the lineEndings option to a text before it is diffed or measured
+ *   normalize (default) - strip the BOM and convert CRLF / CR to LF, so a
+ *     Windows checkout diffed against an LF copy doesn't read as a rewrite
+ *   preserve - compare the texts byte for byte
+ *   report - normalize, and also describe each side's line endings and BOM
+ */
+function prepareText(input, mode = 'normalize') {
+  const text = toText(input);
+  if (!LINE_ENDING_MODES.includes(mode)) {
+    throw new CompanionError(
+      ERROR_CODES.INVALID_INPUT,
+      `Unknown lineEndings mode: ${mode} (expected ${LINE_ENDING_MODES.join(', ')})`
+    );
+  }
+  if (mode === 'preserve') return { text, info: null };
+  const info = mode === 'report' ? describeText(text) : null;
+  const stripped = text.startsWith(BOM) ? text.slice(1) : text;
+  return { text: normalizeLineEndings(stripped), info };
+}
+
+function lineEndingReport(before, after) {
+  return {
+    before: before.info,
+    after: after.info,
+    changed: before.info.lineEnding !== after.info.lineEnding || before.info.bom !== after.info.bom,
+  };
+}
+
 /**
  * Calculate diff between two texts
  * Automatically uses native Rust implementation when available
+ * Either side may be a string or a Buffer / TypedArray; identical byte
+ * inputs short-circuit without being decoded.
+ * @param {object} options - { threshold, includeUnified, lineEndings: 'normalize'|'preserve'|'report',
+ *   includeAfterContent (default true) }
+ *   afterContent is the caller's own text2 (the same Buffer, if one was passed), whatever the
+ *   lineEndings mode; pass includeAfterContent: false when the caller already holds it
  */
 function calculateDiff(text1, text2, options = {}) {
   const threshold = options.threshold || options.diffThreshold || 10;
+  const afterContent = options.includeAfterContent === false ? {} : { afterContent: text2 };
+  if (isBytes(text1) && isBytes(text2) && Buffer.compare(asBuffer(text1), asBuffer(text2)) === 0) {
+    return {
+      diffSize: 0,
+      isSignificant: false,
+      summary: '+0 chars',
+      linesAdded: 0,
+      linesRemoved: 0,
+      charsAdded: 0,
+      charsDeleted: 0,
+      ...afterContent,
+    };
+  }
+  const before = prepareText(text1, options.lineEndings);
+  const after = prepareText(text2, options.lineEndings);
+  text1 = before.text;
+  text2 = after.text;
 
   if (useNative && native) {
     try {
       // Use Rust implementation (5-10x faster)
-      return native.calculateDiff(text1, text2, threshold, options.includeUnified || false);
+      const result = native.calculateDiff(text1, text2, threshold, options.includeUnified || false);
+      if (options.includeAfterContent === false) delete result.afterContent;
+      return result;
     } catch (error) {
-      console.warn('[DIFF] Native diff failed, falling back to JS:', error.message);
+      log.warn('Native diff failed, falling back to JS', { error: error.message });
       // Fall through to JS implementation
     }
   }
@@ -54,19 +154,28 @@ function calculateDiff(text1, text2, options = {}) {
     linesRemoved,
     charsAdded,
     charsDeleted,
-    afterContent: text2,
+    ...(before.info ? { lineEndings: lineEndingReport(before, after) } : {}),
+    ...afterContent,
   };
 }
 
 /**
  * Get detailed line-by-line changes
+ * Accepts strings or Buffers / TypedArrays, as with calculateDiff
+ * @param {object} options - { lineEndings: 'normalize'|'preserve'|'report' }; with 'report' the
+ *   changes array carries a lineEndings property describing both sides
  */
-function getLineChanges(text1, text2) {
+function getLineChanges(text1, text2, options = {}) {
+  const before = prepareText(text1, options.lineEndings);
+  const after = prepareText(text2, options.lineEndings);
+  text1 = before.text;
+  text2 = after.text;
+
   if (useNative && native) {
     try {
       return native.getLineChanges(text1, text2);
     } catch (error) {
-      console.warn('[DIFF] Native line changes failed:', error.message);
+      log.warn('Native line changes failed', { error: error.message });
     }
   }
 
@@ -95,18 +204,36 @@ function getLineChanges(text1, text2) {
     }
   }
 
+  if (before.info) changes.lineEndings = lineEndingReport(before, after);
   return changes;
 }
 
 /**
  * Calculate file statistics
+ * Words are counted with Unicode word segmentation; prose files (Markdown,
+ * plain text) additionally report sentences, paragraphs and graphemes.
+ * Comments are found with the language's own syntax (see code-lexer), so
+ * commentLines / commentChars ignore markers inside strings and include
+ * multi-line block comments and docstrings.
+ * @param {string|Buffer} content - File content
+ * @param {object} options - { filename, language, locale, lineEndings: 'normalize'|'preserve'|'report' }
+ *   language defaults to detectLanguage(filename); 'report' adds lineEnding, lineEndingCounts and bom
  */
-function calculateFileStats(content) {
+function calculateFileStats(content, options = {}) {
+  const prepared = prepareText(content, options.lineEndings);
+  content = prepared.text;
+
+  const prose = isProse(options.filename, options.language);
+  const markdown = prose && !/\.(txt|rst|adoc|org|tex)$/i.test(options.filename || '');
+  let language = options.language;
+  if (!language && prose) language = markdown ? 'markdown' : 'text';
+  if (!language && options.filename) language = detectLanguage(content, options.filename);
+
   if (useNative && native) {
     try {
-      return native.calculateFileStats(content);
+      return native.calculateFileStats(content, language);
     } catch (error) {
-      console.warn('[DIFF] Native stats failed:', error.message);
+      log.warn('Native stats failed', { error: error.message });
     }
   }
 
@@ -114,42 +241,110 @@ function calculateFileStats(content) {
   const lines = content.split('\n');
   const totalLines = lines.length;
   let blankLines = 0;
-  let commentLines = 0;
-  let words = 0;
-
   for (const line of lines) {
-    const trimmed = line.trim();
-    if (trimmed.length === 0) {
-      blankLines++;
-    } else if (trimmed.startsWith('//') || trimmed.startsWith('#') || trimmed.startsWith('/*')) {
-      commentLines++;
-    }
-    words += trimmed.split(/\s+/).length;
+    if (line.trim().length === 0) blankLines++;
   }
+  const comments = measureComments(content, language);
 
-  return {
+  const stats = {
     lines: totalLines,
     chars: content.length,
-    words,
+    words: countWords(content, options.locale),
     blankLines,
-    commentLines,
+    commentLines: comments.commentLines,
+    codeLines: comments.codeLines,
+    docCommentLines: comments.docCommentLines,
+    commentChars: comments.commentChars,
+    codeChars: comments.codeChars,
+    language: comments.language,
   };
+  if (prepared.info) {
+    stats.lineEnding = prepared.info.lineEnding;
+    stats.lineEndingCounts = prepared.info.counts;
+    stats.bom = prepared.info.bom;
+  }
+
+  if (prose) {
+    stats.prose = proseStats(content, { locale: options.locale, markdown });
+    stats.words = stats.prose.words;
+  }
+
+  return stats;
 }
 
 /**
  * Batch calculate diffs (parallel in Rust)
+ * Pairs may mix strings and Buffers, as with calculateDiff
+ * @param {object} options - Passed through to calculateDiff (lineEndings, ...), plus
+ *   includeAfterContent: true (default) | false | 'changed'. With 'changed', pairs whose sides are
+ *   identical come back as { unchanged: true } with no afterContent, so a batch over a mostly
+ *   untouched workspace doesn't carry every file's contents back
  */
-function batchCalculateDiffs(pairs, threshold = 10) {
-  if (useNative && native) {
+function batchCalculateDiffs(pairs, threshold = 10, options = {}) {
+  if (options.includeAfterContent === 'changed') {
+    return pairs.map(([text1, text2]) => {
+      const unchanged =
+        text1 === text2 || (isBytes(text1) && isBytes(text2) && asBuffer(text1).equals(asBuffer(text2)));
+      const result = calculateDiff(text1, text2, {
+        ...options,
+        threshold,
+        includeAfterContent: !unchanged,
+      });
+      return unchanged ? { ...result, unchanged: true } : result;
+    });
+  }
+
+  if (useNative && native && options.includeAfterContent !== false) {
+    // Native takes plain strings: decode and apply lineEndings first, as calculateDiff does
+    const prepared = pairs.map(([text1, text2]) => [
+      prepareText(text1, options.lineEndings),
+      prepareText(text2, options.lineEndings),
+    ]);
     try {
-      return native.batchCalculateDiffs(pairs, threshold);
+      const results = native.batchCalculateDiffs(
+        prepared.map(([before, after]) => [before.text, after.text]),
+        threshold
+      );
+      return results.map((result, i) => {
+        const [before, after] = prepared[i];
+        return {
+          ...result,
+          ...(before.info ? { lineEndings: lineEndingReport(before, after) } : {}),
+          afterContent: pairs[i][1],
+        };
+      });
     } catch (error) {
-      console.warn('[DIFF] Native batch diff failed:', error.message);
+      log.warn('Native batch diff failed', { error: error.message });
     }
   }
 
   // JavaScript fallback - sequential processing
-  return pairs.map(([text1, text2]) => calculateDiff(text1, text2, { threshold }));
+  return pairs.map(([text1, text2]) => calculateDiff(text1, text2, { ...options, threshold }));
+}
+
+/**
+ * batchCalculateDiffs as a cancellable operation with progress
+ * Pairs are diffed in chunks, yielding to the event loop between chunks; a
+ * cancelled run resolves with the results computed so far.
+ * @param {object} options - batchCalculateDiffs options, plus { chunkSize, signal, onProgress }
+ * @returns {OperationHandle} Resolves with the results array
+ */
+function startBatchDiffs(pairs, threshold = 10, options = {}) {
+  const { chunkSize = 200, signal, onProgress, ...diffOptions } = options;
+  return startOperation(
+    'batch-diffs',
+    async (handle) => {
+      const results = [];
+      handle.report({ completed: 0, total: pairs.length });
+      for (let i = 0; i < pairs.length && !handle.cancelled; i += chunkSize) {
+        results.push(...batchCalculateDiffs(pairs.slice(i, i + chunkSize), threshold, diffOptions));
+        handle.report({ completed: results.length, total: pairs.length });
+        await new Promise((resolve) => setImmediate(resolve));
+      }
+      return results;
+    },
+    { signal, onProgress }
+  );
 }
 
 /**
@@ -160,7 +355,7 @@ function calculateSimilarity(text1, text2) {
     try {
       return native.calculateSimilarity(text1, text2);
     } catch (error) {
-      console.warn('[DIFF] Native similarity failed:', error.message);
+      log.warn('Native similarity failed', { error: error.message });
     }
   }
 
@@ -181,71 +376,173 @@ function calculateSimilarity(text1, text2) {
 }
 
 /**
- * Detect language from content
+ * Levenshtein distance over integer sequences. Strips the common prefix and
+ * suffix, then runs a DP restricted to the diagonal band |i - j| <= bound and
+ * stops as soon as every cell in a row exceeds the bound.
+ * Returns bound + 1 when the distance is larger than the bound.
  */
-function detectLanguage(content, filename = null) {
+function sequenceDistance(a, b, bound = Infinity) {
+  let start = 0;
+  let endA = a.length;
+  let endB = b.length;
+  while (start < endA && start < endB && a[start] === b[start]) start++;
+  while (endA > start && endB > start && a[endA - 1] === b[endB - 1]) {
+    endA--;
+    endB--;
+  }
+  const n = endA - start;
+  const m = endB - start;
+  if (n === 0 || m === 0) return Math.min(Math.max(n, m), bound + 1);
+  if (Math.abs(n - m) > bound) return bound + 1;
+
+  const band = Number.isFinite(bound) ? bound : Math.max(n, m);
+  const exceeded = bound + 1;
+  let previous = new Uint32Array(m + 1);
+  let current = new Uint32Array(m + 1);
+  for (let j = 0; j <= m; j++) previous[j] = j <= band ? j : exceeded;
+
+  for (let i = 1; i <= n; i++) {
+    const from = Math.max(1, i - band);
+    const to = Math.min(m, i + band);
+    current[0] = i <= band ? i : exceeded;
+    if (from > 1) current[from - 1] = exceeded;
+    let rowMin = current[0];
+    const ai = a[start + i - 1];
+    for (let j = from; j <= to; j++) {
+      const cost = ai === b[start + j - 1] ? 0 : 1;
+      let value = previous[j - 1] + cost;
+      if (previous[j] + 1 < value) value = previous[j] + 1;
+      if (current[j - 1] + 1 < value) value = current[j - 1] + 1;
+      current[j] = value > exceeded ? exceeded : value;
+      if (current[j] < rowMin) rowMin = current[j];
+    }
+    if (to < m) current[to + 1] = exceeded;
+    if (rowMin > bound) return exceeded;
+    [previous, current] = [current, previous];
+  }
+  return Math.min(previous[m], exceeded);
+}
+
+function codePoints(text) {
+  const points = new Uint32Array(text.length);
+  let length = 0;
+  for (const char of text) points[length++] = char.codePointAt(0);
+  return points.subarray(0, length);
+}
+
+/**
+ * Levenshtein edit distance between two texts (by code point)
+ * @param {object} options - { maxDistance } stop early once the distance is known to exceed it
+ * @returns {number} Distance, or maxDistance + 1 when it exceeds maxDistance
+ */
+function editDistance(text1, text2, options = {}) {
+  const maxDistance = options.maxDistance ?? Infinity;
   if (useNative && native) {
     try {
-      return native.detectLanguage(content, filename);
+      return native.editDistance(text1, text2, maxDistance);
     } catch (error) {
-      console.warn('[DIFF] Native language detection failed:', error.message);
+      log.warn('Native edit distance failed', { error: error.message });
     }
   }
 
-  // JavaScript fallback
-  if (filename) {
-    if (filename.endsWith('.js') || filename.endsWith('.jsx')) return 'javascript';
-    if (filename.endsWith('.ts') || filename.endsWith('.tsx')) return 'typescript';
-    if (filename.endsWith('.py')) return 'python';
-    if (filename.endsWith('.rs')) return 'rust';
-    if (filename.endsWith('.go')) return 'go';
-    if (filename.endsWith('.java')) return 'java';
+  if (text1 === text2) return 0;
+  return sequenceDistance(codePoints(text1 || ''), codePoints(text2 || ''), maxDistance);
+}
+
+/**
+ * Split code into token values, dropping whitespace and comments (see code-lexer.tokenize)
+ * @param {string} language - Language name or alias; picks the comment and string syntax
+ */
+function tokenizeCode(text, language = null) {
+  return tokenize(String(text || ''), language).map((token) => token.value);
+}
+
+/**
+ * Similarity of two code texts over tokens: 1 - tokenEditDistance / max(tokenCount)
+ * Insensitive to whitespace, formatting and comments.
+ * @param {object} options - { maxDistance } token-distance bound for early exit (returns 0 beyond it)
+ * @returns {number} Similarity in [0, 1]
+ */
+function tokenSimilarity(text1, text2, language = null, options = {}) {
+  if (useNative && native) {
+    try {
+      return native.tokenSimilarity(text1, text2, language);
+    } catch (error) {
+      log.warn('Native token similarity failed', { error: error.message });
+    }
   }
 
-  return 'unknown';
+  const tokens1 = tokenizeCode(text1, language);
+  const tokens2 = tokenizeCode(text2, language);
+  const longer = Math.max(tokens1.length, tokens2.length);
+  if (longer === 0) return 1.0;
+
+  const ids = new Map();
+  const encode = (tokens) => Uint32Array.from(tokens, (token) => {
+    if (!ids.has(token)) ids.set(token, ids.size);
+    return ids.get(token);
+  });
+  const maxDistance = options.maxDistance ?? Infinity;
+  const distance = sequenceDistance(encode(tokens1), encode(tokens2), maxDistance);
+  return distance > maxDistance ? 0 : 1 - distance / longer;
 }
 
 /**
- * Extract function signatures
+ * Detect language from content and file name
+ * See code-language.detectLanguages for the ranked candidates with confidences.
  */
-function extractFunctions(content, language) {
+function detectLanguage(content, filename = null) {
   if (useNative && native) {
     try {
-      return native.extractFunctions(content, language);
+      return native.detectLanguage(content, filename);
     } catch (error) {
-      console.warn('[DIFF] Native function extraction failed:', error.message);
+      log.warn('Native language detection failed', { error: error.message });
     }
   }
 
-  // JavaScript fallback - simple regex
-  const functions = [];
-  const lines = content.split('\n');
+  // JavaScript fallback: the most likely candidate
+  const [best] = detectLanguages(content, filename, { maxResults: 1 });
+  return best ? best.language : 'unknown';
+}
 
-  for (const line of lines) {
-    if (language === 'javascript' || language === 'typescript') {
-      const match = line.match(/^\s*(?:function|const|let|var)\s+(\w+)\s*[=\(]/);
-      if (match) functions.push(match[1]);
-    } else if (language === 'python') {
-      const match = line.match(/^\s*def\s+(\w+)\s*\(/);
-      if (match) functions.push(match[1]);
+/**
+ * Extract function and method names, in source order
+ * See code-symbols.extractSymbols for classes, nesting, line ranges, imports and exports.
+ */
+function extractFunctions(content, language) {
+  if (useNative && native) {
+    try {
+      return native.extractFunctions(content, language);
+    } catch (error) {
+      log.warn('Native function extraction failed', { error: error.message });
     }
   }
 
-  return functions;
+  return extractSymbols(content, language)
+    .symbols.filter((symbol) => symbol.kind === 'function' || symbol.kind === 'method')
+    .map((symbol) => symbol.name);
 }
 
 /**
  * Estimate token count
+ * Uses the real BPE tokenizer when the encoding's vocabulary is cached
+ * (see bpe-tokenizer.loadEncoding); otherwise falls back to a heuristic.
  */
-function estimateTokens(text) {
+function estimateTokens(text, encoding = 'cl100k_base') {
   if (useNative && native) {
     try {
       return native.estimateTokens(text);
     } catch (error) {
-      console.warn('[DIFF] Native token estimation failed:', error.message);
+      log.warn('Native token estimation failed', { error: error.message });
     }
   }
 
+  try {
+    if (isEncodingAvailable(encoding)) return countTokens(text, encoding);
+  } catch (error) {
+    log.warn('BPE token count failed', { error: error.message });
+  }
+
   // JavaScript fallback
   const words = text.split(/\s+/).length;
   const chars = text.length;
@@ -273,22 +570,29 @@ function getPerformanceInfo() {
       lineChanges: true,
       fileStats: true,
       similarity: true,
+      editDistance: true,
+      tokenSimilarity: true,
       languageDetection: true,
       functionExtraction: true,
+      symbolExtraction: true,
       tokenEstimation: true,
     },
   };
 }
 
-module.exports = {
+module.exports = instrument('DIFF', {
   calculateDiff,
   getLineChanges,
   calculateFileStats,
   batchCalculateDiffs,
+  startBatchDiffs,
   calculateSimilarity,
+  editDistance,
+  tokenSimilarity,
+  tokenizeCode,
   detectLanguage,
   extractFunctions,
   estimateTokens,
   isNativeAvailable,
   getPerformanceInfo,
-};
+});
@@ -0,0 +1,294 @@
+/**
+ * Directory Search
+ * ripgrep-style content search over a workspace: walks the tree honouring
+ * .gitignore/.ignore files, skips binary and oversized files, searches files
+ * on worker threads and streams each file's matches back as soon as its batch
+ * completes, so callers never hold every file's contents in JS.
+ *
+ * Workers read files straight into Buffers and only decode the ones that
+ * pass the binary check. Literal pattern sets go through the Aho-Corasick
+ * matcher; anything else runs as regexes via searchPatterns.
+ */
+
+const fs = require('fs');
+const { createWorker, resolveConcurrency } = require('./thread-pool');
+const { walkFiles } = require('./ignore-rules');
+const { startOperation } = require('./operation-handle');
+const { instrument } = require('./logger');
+const {
+  searchPatterns,
+  searchLiterals,
+  LineIndex,
+  describeMatch,
+  LiteralMatcher,
+} = require('./pattern-search');
+
+const DEFAULT_OPTIONS = {
+  literal: false, // Treat patterns as literal strings (Aho-Corasick) instead of regexes
+  caseInsensitive: false,
+  context: 0, // Lines of context around each match
+  maxFileBytes: 8 * 1024 * 1024,
+  maxMatchesPerFile: 1000,
+  binaryProbeBytes: 8192, // A NUL byte in this prefix marks a file as binary
+  hidden: false,
+  followSymlinks: false,
+  gitignore: true,
+  extraRules: [], // Additional gitignore-syntax excludes applied at the root
+  concurrency: null, // null = thread pool default (utils/thread-pool)
+  batchSize: 64,
+  minParallelFiles: 128,
+};
+
+/**
+ * Search one file
+ * @returns {object} { file, relativePath, count, matches, truncated }, or { file, relativePath, skipped }
+ *   / { file, relativePath, error } for files that weren't searched
+ */
+function searchFileContent(file, relativePath, patterns, opts, matcher = null) {
+  let buffer;
+  try {
+    const stat = fs.statSync(file);
+    if (stat.size > opts.maxFileBytes) return { file, relativePath, skipped: 'too-large' };
+    buffer = fs.readFileSync(file);
+  } catch (error) {
+    return { file, relativePath, error: error.message };
+  }
+  if (buffer.subarray(0, opts.binaryProbeBytes).includes(0)) return { file, relativePath, skipped: 'binary' };
+
+  const text = buffer.toString('utf8');
+  const matches = [];
+  let count = 0;
+
+  if (opts.literal) {
+    const result = searchLiterals(text, matcher || patterns, {
+      caseInsensitive: opts.caseInsensitive,
+      maxMatches: opts.maxMatchesPerFile,
+    });
+    count = result.counts.reduce((sum, entry) => sum + entry.count, 0);
+    if (count > 0) {
+      const lines = new LineIndex(text);
+      for (const match of result.matches) {
+        const described = describeMatch(lines, match.start, match.end, opts.context);
+        matches.push({ pattern: match.pattern, ...described });
+      }
+    }
+  } else {
+    const results = searchPatterns(text, patterns, {
+      flags: opts.caseInsensitive ? 'i' : '',
+      positions: true,
+      context: opts.context,
+      maxMatches: opts.maxMatchesPerFile,
+    });
+    for (const result of results) {
+      if (result.error) continue;
+      count += result.count;
+      for (const match of result.matches) matches.push({ pattern: result.pattern, ...match });
+    }
+    matches.sort((a, b) => a.start - b.start);
+    if (matches.length > opts.maxMatchesPerFile) matches.length = opts.maxMatchesPerFile;
+  }
+
+  return { file, relativePath, count, matches, truncated: matches.length < count };
+}
+
+const SEARCH_WORKER_SOURCE = `
+const { parentPort, workerData } = require('worker_threads');
+const { searchFileContent } = require(workerData.modulePath);
+const { LiteralMatcher } = require(workerData.patternSearchPath);
+const { patterns, options } = workerData;
+const matcher = options.literal ? new LiteralMatcher(patterns, options) : null;
+parentPort.on('message', (files) => {
+  const results = files.map(([file, relativePath]) =>
+    searchFileContent(file, relativePath, patterns, options, matcher)
+  );
+  parentPort.postMessage(results);
+});
+`;
+
+/**
+ * Search every non-ignored file under a directory
+ * @param {string} root - Directory to search
+ * @param {Array<string>} patterns - Regex sources, or literals with { literal: true }
+ * @param {object} options - See DEFAULT_OPTIONS, plus:
+ *   onMatch(match) - per match: { file, relativePath, pattern, line, column, start, end, lineText, ... }
+ *   onFile(result) - called per searched file, matching or not
+ *   onProgress(summary) - called after every file with the running summary counts
+ *   signal - AbortSignal; stops dispatching new batches
+ * @returns {Promise<object>} { filesSearched, filesMatched, matchCount, skipped, errors, invalidPatterns,
+ *   cancelled }, plus files (matching file results, by path) when neither onMatch nor onFile is given
+ */
+async function searchDirectory(root, patterns, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const concurrency = resolveConcurrency(opts.concurrency);
+  const { onMatch = null, onFile = null, onProgress = null, signal = null } = options;
+  const collect = !onMatch && !onFile;
+  const summary = {
+    filesSearched: 0,
+    filesMatched: 0,
+    matchCount: 0,
+    skipped: 0,
+    errors: [],
+    invalidPatterns: [],
+    cancelled: false,
+  };
+  const files = [];
+
+  // Report bad regexes once up front rather than per file
+  if (!opts.literal) {
+    summary.invalidPatterns = searchPatterns('', patterns).filter((result) => result.error);
+  }
+
+  const progress = () => {
+    if (!onProgress) return;
+    const { filesSearched, filesMatched, matchCount, skipped } = summary;
+    onProgress({ filesSearched, filesMatched, matchCount, skipped, errors: summary.errors.length });
+  };
+  const handle = (result) => {
+    if (result.error) {
+      summary.errors.push({ file: result.file, error: result.error });
+      progress();
+      return;
+    }
+    if (result.skipped) {
+      summary.skipped++;
+      progress();
+      return;
+    }
+    summary.filesSearched++;
+    if (result.count > 0) {
+      summary.filesMatched++;
+      summary.matchCount += result.count;
+      if (collect) files.push(result);
+    }
+    if (onFile) onFile(result);
+    if (onMatch) {
+      for (const match of result.matches) {
+        onMatch({ file: result.file, relativePath: result.relativePath, ...match });
+      }
+    }
+    progress();
+  };
+
+  const workerOptions = {
+    literal: opts.literal,
+    caseInsensitive: opts.caseInsensitive,
+    context: opts.context,
+    maxFileBytes: opts.maxFileBytes,
+    maxMatchesPerFile: opts.maxMatchesPerFile,
+    binaryProbeBytes: opts.binaryProbeBytes,
+  };
+  const matcher = opts.literal ? new LiteralMatcher(patterns, workerOptions) : null;
+
+  // Walk first with a small in-process buffer; only spin up workers when there's enough to search
+  const pending = [];
+  const walker = walkFiles(root, opts);
+  let exhausted = false;
+  while (pending.length < opts.minParallelFiles) {
+    const { value, done } = await walker.next();
+    if (done) {
+      exhausted = true;
+      break;
+    }
+    pending.push([value.path, value.relativePath]);
+  }
+
+  if (exhausted || concurrency <= 1) {
+    for (const [file, relativePath] of pending) {
+      if (signal?.aborted) break;
+      handle(searchFileContent(file, relativePath, patterns, workerOptions, matcher));
+    }
+    if (!exhausted) {
+      for await (const entry of walker) {
+        if (signal?.aborted) break;
+        handle(searchFileContent(entry.path, entry.relativePath, patterns, workerOptions, matcher));
+      }
+    }
+  } else {
+    const workers = Array.from(
+      { length: concurrency },
+      () =>
+        createWorker(SEARCH_WORKER_SOURCE, {
+          workerData: {
+            modulePath: __filename,
+            patternSearchPath: require.resolve('./pattern-search'),
+            patterns,
+            options: workerOptions,
+          },
+        })
+    );
+
+    // Batches are pulled from the walker lazily, so walking and searching overlap
+    let walking = Promise.resolve();
+    const nextBatch = () => {
+      const result = walking.then(async () => {
+        const batch = pending.splice(0, opts.batchSize);
+        while (!exhausted && batch.length < opts.batchSize) {
+          const { value, done } = await walker.next();
+          if (done) exhausted = true;
+          else batch.push([value.path, value.relativePath]);
+        }
+        return batch;
+      });
+      walking = result;
+      return result;
+    };
+
+    try {
+      await Promise.all(
+        workers.map(async (worker) => {
+          for (;;) {
+            if (signal?.aborted) return;
+            const batch = await nextBatch();
+            if (batch.length === 0) return;
+            const results = await new Promise((resolve, reject) => {
+              worker.once('message', resolve);
+              worker.once('error', reject);
+              worker.postMessage(batch);
+            });
+            worker.removeAllListeners('error');
+            results.forEach(handle);
+          }
+        })
+      );
+    } finally {
+      await Promise.all(workers.map((worker) => worker.terminate()));
+    }
+  }
+
+  summary.cancelled = Boolean(signal?.aborted);
+  if (!collect) return summary;
+  files.sort((a, b) => (a.relativePath < b.relativePath ? -1 : a.relativePath > b.relativePath ? 1 : 0));
+  return { ...summary, files };
+}
+
+/**
+ * searchDirectory as a cancellable operation
+ * Progress reports { completed: files searched or skipped, filesMatched, matchCount }; the total
+ * isn't known up front since files are discovered while searching.
+ * @returns {OperationHandle} Resolves with searchDirectory's summary
+ */
+function startDirectorySearch(root, patterns, options = {}) {
+  const { signal, onProgress: onOperationProgress, ...searchOptions } = options;
+  return startOperation(
+    'directory-search',
+    (operation) =>
+      searchDirectory(root, patterns, {
+        ...searchOptions,
+        signal: operation.signal,
+        onProgress: (summary) =>
+          operation.report({
+            completed: summary.filesSearched + summary.skipped + summary.errors,
+            filesMatched: summary.filesMatched,
+            matchCount: summary.matchCount,
+          }),
+      }),
+    { signal, onProgress: onOperationProgress }
+  );
+}
+
+module.exports = instrument('SEARCH', {
+  searchDirectory,
+  startDirectorySearch,
+  searchFileContent,
+  DEFAULT_OPTIONS,
+});
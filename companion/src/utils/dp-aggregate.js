@@ -0,0 +1,227 @@
+/**
+ * Differentially Private Aggregation
+ * Noise for counters and histograms before they leave the machine (edits
+ * per hour, language breakdowns, ...), so exported statistics come with an
+ * epsilon-differential-privacy guarantee instead of ad hoc rounding.
+ *
+ * Mechanisms:
+ *   geometric  two-sided geometric noise, P(k) ~ exp(-epsilon |k| / sensitivity):
+ *              integer-valued, the discrete analogue of Laplace and the
+ *              right choice for counts (default)
+ *   laplace    Laplace noise with scale sensitivity / epsilon, for real values
+ *
+ * sensitivity is how much one person's data can change the whole input, in
+ * L1 norm: 1 for a single count, and also 1 for a histogram where each
+ * person lands in one bin. A person who can add to k bins gives sensitivity k.
+ * Randomness comes from crypto.randomBytes; Math.random is predictable.
+ *
+ * A histogram over keys that aren't known in advance (file types, app names)
+ * can't release every key it sees: a key only one person produced gives that
+ * person away by being there at all. dpHistogram either takes a public
+ * domain of keys, or drops keys whose noisy count falls under a threshold
+ * set by delta, the chance the release is allowed to expose someone
+ * ((epsilon, delta)-differential privacy).
+ *
+ * Every release spends its epsilon, and epsilons add up across releases of
+ * the same data: a PrivacyBudget tracks the total and refuses releases past
+ * its limit. Clamping and rounding after the noise (post-processing) don't
+ * weaken the guarantee.
+ */
+
+const crypto = require('crypto');
+
+const DEFAULT_OPTIONS = {
+  mechanism: 'geometric', // 'geometric' | 'laplace'
+  sensitivity: 1, // L1 sensitivity of the whole input
+  clamp: true, // Clamp noisy values to >= 0 (counts can't be negative)
+  budget: null, // PrivacyBudget to charge
+  label: null, // What the release is, for the budget's ledger
+};
+
+const MECHANISMS = ['geometric', 'laplace'];
+
+/**
+ * Uniform double in (0, 1), from 53 random bits
+ */
+function uniform() {
+  for (;;) {
+    const bytes = crypto.randomBytes(8);
+    const high = bytes.readUInt32BE(0) >>> 5; // 27 bits
+    const low = bytes.readUInt32BE(4) >>> 6; // 26 bits
+    const value = (high * 67108864 + low) / 9007199254740992;
+    if (value > 0) return value;
+  }
+}
+
+/**
+ * Laplace noise with the given scale (sensitivity / epsilon)
+ */
+function laplaceNoise(scale) {
+  const u = uniform() - 0.5;
+  return -scale * Math.sign(u) * Math.log(1 - 2 * Math.abs(u));
+}
+
+/**
+ * Two-sided geometric noise: the difference of two geometric variables with
+ * success probability 1 - exp(-epsilon / sensitivity)
+ */
+function geometricNoise(epsilon, sensitivity = 1) {
+  const logAlpha = -epsilon / sensitivity;
+  const draw = () => Math.floor(Math.log(uniform()) / logAlpha);
+  return draw() - draw();
+}
+
+/**
+ * Half-width of the interval that holds the noise with the given probability
+ */
+function noiseBound(epsilon, options = {}) {
+  const { mechanism = 'geometric', sensitivity = 1, confidence = 0.95 } = options;
+  const bound = (sensitivity / epsilon) * Math.log(1 / (1 - confidence));
+  return mechanism === 'geometric' ? Math.ceil(bound) : bound;
+}
+
+function checkEpsilon(epsilon) {
+  if (!(typeof epsilon === 'number' && epsilon > 0 && Number.isFinite(epsilon))) {
+    throw new Error(`epsilon must be a positive number, got ${epsilon}`);
+  }
+}
+
+/**
+ * Tracks epsilon spent on releases of the same data (sequential composition)
+ */
+class PrivacyBudget {
+  /**
+   * @param {number} total - Total epsilon allowed across releases
+   */
+  constructor(total) {
+    checkEpsilon(total);
+    this.total = total;
+    this.spent = 0;
+    this.ledger = []; // [{ label, epsilon, at }]
+  }
+
+  remaining() {
+    return Math.max(0, this.total - this.spent);
+  }
+
+  /**
+   * Record a release; throws when it would exceed the budget
+   */
+  charge(epsilon, label = null) {
+    checkEpsilon(epsilon);
+    // Tolerate float drift from summing many small epsilons
+    if (this.spent + epsilon > this.total + 1e-12) {
+      throw new Error(
+        `Privacy budget exhausted: ${label || 'release'} needs epsilon ${epsilon}, ${this.remaining()} left`
+      );
+    }
+    this.spent += epsilon;
+    this.ledger.push({ label, epsilon, at: new Date().toISOString() });
+    return this.remaining();
+  }
+}
+
+/**
+ * Add calibrated noise to a count, an array of counts or a histogram
+ * @param {number|Array<number>|object} counts - A count, a list, or { key: count }
+ * @param {number} epsilon - Privacy parameter for this release (smaller = more private, noisier)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {object} { counts (same shape as the input), epsilon, mechanism, sensitivity, scale,
+ *   bound95 } - bound95: 95% of noisy values are within this distance of the true ones
+ */
+function dpAggregate(counts, epsilon, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  checkEpsilon(epsilon);
+  if (!MECHANISMS.includes(opts.mechanism)) {
+    throw new Error(`Unknown DP mechanism: ${opts.mechanism} (expected ${MECHANISMS.join(', ')})`);
+  }
+  if (!(opts.sensitivity > 0)) throw new Error(`sensitivity must be positive, got ${opts.sensitivity}`);
+
+  const scale = opts.sensitivity / epsilon;
+  const noisy = (value) => {
+    const number = Number(value);
+    if (!Number.isFinite(number)) throw new Error(`Cannot add noise to non-numeric value: ${value}`);
+    const result =
+      opts.mechanism === 'geometric'
+        ? Math.round(number) + geometricNoise(epsilon, opts.sensitivity)
+        : number + laplaceNoise(scale);
+    return opts.clamp ? Math.max(0, result) : result;
+  };
+
+  let released;
+  if (typeof counts === 'number') released = noisy(counts);
+  else if (Array.isArray(counts)) released = counts.map(noisy);
+  else if (counts && typeof counts === 'object') {
+    released = Object.fromEntries(Object.entries(counts).map(([key, value]) => [key, noisy(value)]));
+  } else {
+    throw new Error('dpAggregate expects a number, an array or an object of counts');
+  }
+
+  // Charge only once the release is known to succeed
+  if (opts.budget) opts.budget.charge(epsilon, opts.label);
+  return {
+    counts: released,
+    epsilon,
+    mechanism: opts.mechanism,
+    sensitivity: opts.sensitivity,
+    scale,
+    bound95: noiseBound(epsilon, { mechanism: opts.mechanism, sensitivity: opts.sensitivity }),
+  };
+}
+
+/**
+ * Smallest noisy count a key seen only once can reach with probability above
+ * delta, with each person in at most `sensitivity` bins (delta split between them)
+ */
+function keyThreshold(epsilon, delta, options = {}) {
+  const { mechanism = 'geometric', sensitivity = 1 } = options;
+  const perBin = delta / sensitivity;
+  if (mechanism === 'laplace') return 1 + (sensitivity / epsilon) * Math.log(1 / (2 * perBin));
+  // P(noise >= k) = alpha^k / (1 + alpha) for two-sided geometric noise
+  const alpha = Math.exp(-epsilon / sensitivity);
+  return 1 + Math.max(0, Math.ceil(Math.log(perBin * (1 + alpha)) / Math.log(alpha)));
+}
+
+/**
+ * Count occurrences per key and release the histogram with noise
+ *
+ * With `domain`, the bins are exactly those public keys: empty ones are
+ * released too and values outside it are dropped. Without one, `delta` is
+ * required and only observed keys whose noisy count reaches keyThreshold()
+ * are released, the rest left out
+ * @param {Array} values - Raw values (one per contribution)
+ * @param {number} epsilon
+ * @param {object} options - dpAggregate options plus { domain, delta, key(value) }
+ * @returns {object} dpAggregate result with counts as { key: count }, plus delta and
+ *   threshold when keys were thresholded
+ */
+function dpHistogram(values, epsilon, options = {}) {
+  const { domain = null, delta = null, key = (value) => value, ...rest } = options;
+  if (!domain && !(typeof delta === 'number' && delta > 0 && delta < 1)) {
+    throw new Error('dpHistogram needs a public domain of keys, or a delta in (0, 1) to threshold them');
+  }
+  const counts = {};
+  for (const bin of domain || []) counts[bin] = 0;
+  for (const value of values || []) {
+    const bin = key(value);
+    if (domain && !(bin in counts)) continue; // Outside the public domain: dropped, not a new bin
+    counts[bin] = (counts[bin] || 0) + 1;
+  }
+  const result = dpAggregate(counts, epsilon, rest);
+  if (domain) return result;
+
+  const threshold = keyThreshold(epsilon, delta, result);
+  result.counts = Object.fromEntries(Object.entries(result.counts).filter(([, count]) => count >= threshold));
+  return { ...result, delta, threshold };
+}
+
+module.exports = {
+  dpAggregate,
+  dpHistogram,
+  laplaceNoise,
+  geometricNoise,
+  noiseBound,
+  keyThreshold,
+  PrivacyBudget,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,94 @@
+/**
+ * Embedding Normalization
+ * Canonical text form of a code snippet for embedding, so snippets that
+ * differ only in comments, formatting, quote style or (optionally) local
+ * names embed identically and match across traces.
+ *
+ * Built on the diff engine's code tokenizer, which already drops comments
+ * and whitespace; tokens are re-joined with single spaces.
+ */
+
+const { tokenizeCode } = require('./diff-engine');
+
+const DEFAULT_OPTIONS = {
+  strings: 'normalize', // 'normalize' (one quote style) | 'placeholder' (all strings -> "S") | 'keep'
+  alphaRename: false, // Rename identifiers to v0, v1, ... in order of first appearance
+  keepMemberNames: true, // With alphaRename, keep names after '.', '::' and '->' (API surface)
+};
+
+// Keywords and common builtins across the languages we capture; never renamed
+const RESERVED = new Set(
+  `abstract and as assert async await break case catch class const continue def default defer del delete do
+  elif else enum except export extends false final finally fn for from func function go goto if impl
+  implements import in instanceof interface is lambda let loop match mod module mut namespace new nil
+  none not null of or package pass private protected pub public raise return self Self static struct
+  super switch this throw throws trait true try type typeof undefined union unsafe use var void where
+  while with yield True False None int float str bool string number boolean any unknown never object
+  char double long short byte u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 usize isize Vec String Option Result
+  Some Ok Err print println console require len range list dict set tuple Array Object Map Set Promise`
+    .split(/\s+/)
+    .filter(Boolean)
+);
+
+const STRING_TOKEN = /^(["'`])([\s\S]*)\1$/;
+const IDENTIFIER = /^[A-Za-z_$][\w$]*$/;
+const MEMBER_ACCESS = new Set(['.', '::', '->', '?.']);
+
+/**
+ * Re-quote a string literal with double quotes, keeping its value
+ */
+function normalizeString(token) {
+  const match = token.match(STRING_TOKEN);
+  if (!match) return token;
+  const [, quote, body] = match;
+  if (quote === '"') return token;
+  // Unescape the original quote, escape double quotes, keep every other escape as-is
+  let value = '';
+  for (let i = 0; i < body.length; i++) {
+    const char = body[i];
+    if (char === '\\' && i + 1 < body.length) {
+      value += body[i + 1] === quote ? quote : char + body[i + 1];
+      i++;
+    } else {
+      value += char === '"' ? '\\"' : char;
+    }
+  }
+  return `"${value}"`;
+}
+
+/**
+ * Normalize code for embedding
+ * @param {string} code - Snippet
+ * @param {string} language - Comment syntax selector for the tokenizer ('python', 'sql', ...)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {string} Canonical single-line form
+ */
+function normalizeForEmbedding(code, language = null, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const tokens = tokenizeCode(code, language);
+  const names = new Map();
+  const out = [];
+
+  for (let i = 0; i < tokens.length; i++) {
+    let token = tokens[i];
+    if (STRING_TOKEN.test(token)) {
+      if (opts.strings === 'placeholder') token = '"S"';
+      else if (opts.strings === 'normalize') token = normalizeString(token);
+    } else if (IDENTIFIER.test(token)) {
+      const reserved = RESERVED.has(token);
+      const isMember = opts.keepMemberNames && i > 0 && MEMBER_ACCESS.has(tokens[i - 1]);
+      if (opts.alphaRename && !reserved && !isMember) {
+        if (!names.has(token)) names.set(token, `v${names.size}`);
+        token = names.get(token);
+      }
+    }
+    out.push(token);
+  }
+
+  return out.join(' ');
+}
+
+module.exports = {
+  normalizeForEmbedding,
+  DEFAULT_OPTIONS,
+};
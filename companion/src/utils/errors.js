@@ -0,0 +1,138 @@
+/**
+ * Companion Errors
+ * One error type with a small, stable set of codes, so callers can tell
+ * "permission denied" from "tool not installed" from "timed out" without
+ * matching on message text. Raw Node / child_process errors are classified
+ * with fromSystemError; the original error is kept as cause.
+ *
+ * Codes:
+ *   INVALID_INPUT      bad argument or option
+ *   NOT_FOUND          file, step or record doesn't exist
+ *   IO                 read/write failure
+ *   TIMEOUT            operation or subprocess ran out of time
+ *   PERMISSION_DENIED  the OS refused (file mode, TCC, Apple events)
+ *   UNAVAILABLE        required tool or platform feature missing (e.g. no osascript)
+ *   CANCELLED          aborted by the caller
+ *   LIMIT_EXCEEDED     over a configured size or queue limit
+ */
+
+const ERROR_CODES = {
+  INVALID_INPUT: 'INVALID_INPUT',
+  NOT_FOUND: 'NOT_FOUND',
+  IO: 'IO',
+  TIMEOUT: 'TIMEOUT',
+  PERMISSION_DENIED: 'PERMISSION_DENIED',
+  UNAVAILABLE: 'UNAVAILABLE',
+  CANCELLED: 'CANCELLED',
+  LIMIT_EXCEEDED: 'LIMIT_EXCEEDED',
+};
+
+const HTTP_STATUS = {
+  INVALID_INPUT: 400,
+  NOT_FOUND: 404,
+  IO: 500,
+  TIMEOUT: 504,
+  PERMISSION_DENIED: 403,
+  UNAVAILABLE: 503,
+  CANCELLED: 499,
+  LIMIT_EXCEEDED: 413,
+};
+
+class CompanionError extends Error {
+  /**
+   * @param {string} code - One of ERROR_CODES
+   * @param {string} message - Human-readable description
+   * @param {object} options - { cause, reason (finer-grained machine-readable reason), details }
+   */
+  constructor(code, message, options = {}) {
+    super(message, options.cause ? { cause: options.cause } : undefined);
+    this.name = 'CompanionError';
+    this.code = ERROR_CODES[code] ? code : ERROR_CODES.IO;
+    this.reason = options.reason || null;
+    this.details = options.details || null;
+  }
+
+  toJSON() {
+    return {
+      code: this.code,
+      reason: this.reason,
+      message: this.message,
+      ...(this.details ? { details: this.details } : {}),
+    };
+  }
+}
+
+function isCompanionError(error, code = null) {
+  return error instanceof CompanionError && (code === null || error.code === code);
+}
+
+const ERRNO_CODES = {
+  ENOENT: ERROR_CODES.NOT_FOUND,
+  ENOTDIR: ERROR_CODES.NOT_FOUND,
+  EACCES: ERROR_CODES.PERMISSION_DENIED,
+  EPERM: ERROR_CODES.PERMISSION_DENIED,
+  ETIMEDOUT: ERROR_CODES.TIMEOUT,
+  ABORT_ERR: ERROR_CODES.CANCELLED,
+  EFBIG: ERROR_CODES.LIMIT_EXCEEDED,
+  ENOSPC: ERROR_CODES.LIMIT_EXCEEDED,
+};
+
+/**
+ * Classify an error thrown by fs, child_process or fetch
+ * @param {Error} error - The raw error (returned unchanged if it's already a CompanionError)
+ * @param {object} context - { command } for subprocess failures, so a missing binary reads as UNAVAILABLE
+ * @returns {CompanionError}
+ */
+function fromSystemError(error, context = {}) {
+  if (error instanceof CompanionError) return error;
+  const message = error?.message || String(error);
+
+  if (error?.name === 'AbortError') {
+    return new CompanionError(ERROR_CODES.CANCELLED, message, { cause: error });
+  }
+  if (context.command) {
+    // exec() reports a missing binary as exit code 127 from the shell; execFile/spawn as ENOENT
+    const stderr = String(error?.stderr || '');
+    if (error?.code === 127 || error?.code === 'ENOENT' || /command not found|not found$/m.test(stderr)) {
+      return new CompanionError(ERROR_CODES.UNAVAILABLE, `${context.command} is not available`, {
+        cause: error,
+        reason: 'command-missing',
+        details: { command: context.command },
+      });
+    }
+    if (error?.killed && error?.signal) {
+      return new CompanionError(ERROR_CODES.TIMEOUT, `${context.command} timed out`, {
+        cause: error,
+        details: { command: context.command, signal: error.signal },
+      });
+    }
+  }
+
+  const code = ERRNO_CODES[error?.code] || ERROR_CODES.IO;
+  return new CompanionError(code, message, {
+    cause: error,
+    reason: typeof error?.code === 'string' ? error.code : null,
+  });
+}
+
+/**
+ * Send an error as JSON with a status matching its code
+ */
+function sendError(res, error) {
+  const classified = fromSystemError(error);
+  res.status(HTTP_STATUS[classified.code] || 500).json({
+    success: false,
+    error: classified.message,
+    code: classified.code,
+    reason: classified.reason,
+  });
+}
+
+module.exports = {
+  CompanionError,
+  ERROR_CODES,
+  HTTP_STATUS,
+  isCompanionError,
+  fromSystemError,
+  sendError,
+};
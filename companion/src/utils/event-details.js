@@ -0,0 +1,23 @@
+/**
+ * Event Details
+ * Events and entries store their details as a JSON string in SQLite and as an
+ * object once loaded or built in memory; parseDetails() accepts either.
+ */
+
+/**
+ * The details of an event or entry as an object
+ * @returns {object} Parsed details; {} when missing or not valid JSON
+ */
+function parseDetails(item) {
+  if (!item || item.details === undefined || item.details === null) return {};
+  if (typeof item.details === 'object') return item.details;
+  try {
+    return JSON.parse(item.details);
+  } catch {
+    return {};
+  }
+}
+
+module.exports = {
+  parseDetails,
+};
@@ -0,0 +1,256 @@
+/**
+ * File Category
+ * What role a file plays in a project - source, test, config, data, markup,
+ * lockfile or binary asset - for capture policies and analytics that care
+ * about "was this a config change" more than about the language.
+ *
+ * Rules, first match wins:
+ *   lockfile  the lockfile names code-generated knows
+ *   asset     binary media, fonts, archives and executables by extension,
+ *             or any file whose content is binary
+ *   test      a test path or test cases in the content (see code-tests),
+ *             for source languages only: fixtures stay data
+ *   config    tool and build configuration by name (package.json,
+ *             tsconfig.json, *.config.js, .eslintrc, Dockerfile, CI
+ *             workflows, ...) or by language (ini, toml, dotenv, hcl, ...)
+ *   data      json, csv, xml and binary data formats (parquet, sqlite, ...)
+ *   markup    prose and templates (markdown, html, rst, latex, jinja, ...)
+ *   source    everything else code-language recognizes
+ * YAML is config unless it sits under a data directory; JSON is data unless
+ * its name says config. Files nothing recognizes are 'unknown'.
+ */
+
+const path = require('path').posix;
+const { detectLanguages } = require('./code-language');
+const { detectGenerated } = require('./code-generated');
+const { detectTests } = require('./code-tests');
+const { instrument } = require('./logger');
+
+const CATEGORIES = ['source', 'test', 'config', 'data', 'markup', 'lockfile', 'asset', 'unknown'];
+
+const DEFAULT_OPTIONS = {
+  binaryProbeBytes: 8192, // A NUL byte in this prefix marks content as binary
+  detectTestCases: true, // Look for test cases in content when the path isn't a test path
+};
+
+const ASSET_EXTENSIONS = new Set(
+  (
+    'png jpg jpeg gif bmp ico icns webp avif tif tiff heic psd ai sketch fig xcf svg svgz ' +
+    'woff woff2 ttf otf eot ' +
+    'mp3 wav ogg flac aac m4a mp4 m4v mov avi mkv webm ' +
+    'pdf doc docx xls xlsx ppt pptx key numbers pages ' +
+    'zip tar gz tgz bz2 xz 7z rar jar war ear whl gem nupkg dmg iso apk ipa ' +
+    'exe dll so dylib a lib o obj class pyc pyo wasm bin dat ' +
+    'blend fbx glb gltf stl unitypackage'
+  ).split(' ')
+);
+
+const DATA_EXTENSIONS = new Set(
+  (
+    'json ndjson jsonl geojson csv tsv xml parquet arrow feather avro orc npy npz pkl pickle h5 hdf5 ' +
+    'sqlite sqlite3 db mdb dbf xlsb sav dta rds rdata mat nc'
+  ).split(' ')
+);
+
+// Config by file name (lowercase)
+const CONFIG_NAMES = new Set([
+  'package.json',
+  'tsconfig.json',
+  'jsconfig.json',
+  'composer.json',
+  'deno.json',
+  'deno.jsonc',
+  'bower.json',
+  'lerna.json',
+  'nx.json',
+  'turbo.json',
+  'vercel.json',
+  'netlify.toml',
+  'app.json',
+  'manifest.json',
+  'settings.json',
+  'launch.json',
+  'tasks.json',
+  'extensions.json',
+  'devcontainer.json',
+  'renovate.json',
+  'angular.json',
+  'firebase.json',
+  'cargo.toml',
+  'pyproject.toml',
+  'setup.py',
+  'setup.cfg',
+  'tox.ini',
+  'pytest.ini',
+  'requirements.txt',
+  'pipfile',
+  'go.mod',
+  'go.work',
+  'gemfile',
+  'rakefile',
+  'podfile',
+  'build.gradle',
+  'settings.gradle',
+  'build.gradle.kts',
+  'settings.gradle.kts',
+  'gradle.properties',
+  'pom.xml',
+  'cmakelists.txt',
+  'makefile',
+  'gnumakefile',
+  'dockerfile',
+  'containerfile',
+  'docker-compose.yml',
+  'docker-compose.yaml',
+  'compose.yml',
+  'compose.yaml',
+  'procfile',
+  'vagrantfile',
+  'jenkinsfile',
+  'build',
+  'build.bazel',
+  'workspace',
+  'module.bazel',
+  'flake.nix',
+  'default.nix',
+  'shell.nix',
+  'mix.exs',
+  'pubspec.yaml',
+  'package.swift',
+  'nginx.conf',
+]);
+
+// Config by name pattern: dotfile rc files, *.config.*, CI and editor directories
+const CONFIG_PATTERNS = [
+  /(?:^|\/)\.[\w-]+rc(?:\.(?:json|js|cjs|mjs|ya?ml|toml))?$/,
+  /(?:^|\/)[\w-]+\.config\.[cm]?[jt]s$/,
+  /(?:^|\/)[\w-]+\.config\.json$/,
+  /(?:^|\/)\.(?:github|gitlab|circleci|husky|vscode|idea|devcontainer|buildkite)\//,
+  /(?:^|\/)\.(?:gitlab-ci|travis|drone|pre-commit-config)\.ya?ml$/,
+  /(?:^|\/)(?:azure-pipelines|bitbucket-pipelines|cloudbuild|appveyor|codecov|mkdocs)\.ya?ml$/,
+  /\.(?:csproj|fsproj|vbproj|vcxproj|sln|props|targets|xcconfig|entitlements|plist)$/,
+  /(?:^|\/)(?:k8s|kubernetes|helm|charts|terraform|ansible)\//,
+];
+
+const DATA_DIRECTORIES = /(?:^|\/)(?:data|datasets?|fixtures?|samples?|seeds?|i18n|locales?)\//i;
+
+const CONFIG_LANGUAGES = new Set([
+  'ini',
+  'toml',
+  'properties',
+  'dotenv',
+  'ignore-list',
+  'git-attributes',
+  'git-config',
+  'nginx',
+  'hcl',
+  'bicep',
+  'dockerfile',
+  'makefile',
+  'cmake',
+  'starlark',
+  'yaml',
+  'jsonc',
+  'json5',
+  'cue',
+  'dhall',
+  'jsonnet',
+  'rego',
+]);
+
+const DATA_LANGUAGES = new Set(['json', 'csv', 'tsv', 'xml']);
+
+const MARKUP_LANGUAGES = new Set([
+  'markdown',
+  'mdx',
+  'rmarkdown',
+  'restructuredtext',
+  'asciidoc',
+  'org',
+  'latex',
+  'bibtex',
+  'typst',
+  'roff',
+  'text',
+  'html',
+  'handlebars',
+  'jinja',
+  'twig',
+  'ejs',
+  'erb',
+  'haml',
+  'pug',
+  'liquid',
+  'razor',
+  'postscript',
+]);
+
+function isBinary(content, probeBytes) {
+  if (content === null || content === undefined) return false;
+  if (Buffer.isBuffer(content)) return content.subarray(0, probeBytes).includes(0);
+  return String(content).slice(0, probeBytes).includes('\0');
+}
+
+/**
+ * Put a file into a category
+ * @param {string} filePath - Path (relative paths let directory rules apply)
+ * @param {string|Buffer} content - File content; null classifies by path alone
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {object} { category, language, generated, reason } - category is one of CATEGORIES,
+ *   language the most likely language (or null), generated the code-generated kind (or null),
+ *   reason what decided the category
+ */
+function classifyFile(filePath, content = null, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const normalized = String(filePath || '').replace(/\\/g, '/');
+  const name = path.basename(normalized).toLowerCase();
+  const ext = path.extname(name).slice(1);
+  const binary = isBinary(content, opts.binaryProbeBytes);
+  const text = content === null || content === undefined || binary ? null : String(content);
+  const generated = detectGenerated(text, normalized);
+  const result = (category, reason, language = null) => ({
+    category,
+    language,
+    generated: generated.kind,
+    reason,
+  });
+
+  if (generated.kind === 'lockfile') return result('lockfile', 'lockfile name');
+  if (ASSET_EXTENSIONS.has(ext)) return result('asset', `asset extension .${ext}`);
+  if (binary) {
+    return DATA_EXTENSIONS.has(ext)
+      ? result('data', `binary data extension .${ext}`)
+      : result('asset', 'binary content');
+  }
+
+  const [best] = detectLanguages(text, normalized, { maxResults: 1 });
+  const language = best ? best.language : null;
+
+  if (CONFIG_NAMES.has(name)) return result('config', 'config file name', language);
+  if (CONFIG_PATTERNS.some((pattern) => pattern.test(normalized))) {
+    return result('config', 'config path', language);
+  }
+
+  if (language && MARKUP_LANGUAGES.has(language)) return result('markup', 'markup language', language);
+  if (language && !CONFIG_LANGUAGES.has(language) && !DATA_LANGUAGES.has(language)) {
+    const tests = detectTests(opts.detectTestCases ? text || '' : '', language, normalized);
+    if (tests.isTestFile) return result('test', tests.byPath ? 'test path' : 'test cases', language);
+    return result('source', 'source language', language);
+  }
+
+  if (language === 'yaml') {
+    return DATA_DIRECTORIES.test(normalized)
+      ? result('data', 'yaml under a data directory', language)
+      : result('config', 'yaml', language);
+  }
+  if (language && CONFIG_LANGUAGES.has(language)) return result('config', 'config language', language);
+  if (language && DATA_LANGUAGES.has(language)) return result('data', 'data language', language);
+  if (DATA_EXTENSIONS.has(ext)) return result('data', `data extension .${ext}`, language);
+  return result('unknown', 'unrecognized', language);
+}
+
+module.exports = instrument('CODE', {
+  classifyFile,
+  CATEGORIES,
+  DEFAULT_OPTIONS,
+});
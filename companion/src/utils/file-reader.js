@@ -0,0 +1,253 @@
+/**
+ * Smart File Reader
+ * Reads source files as Buffers and decodes them with the right encoding
+ * instead of assuming UTF-8: BOMs are detected and stripped, BOM-less UTF-16
+ * is recognised from its NUL byte pattern, and anything that isn't valid UTF-8
+ * falls back to Latin-1 rather than being mangled into U+FFFD.
+ *
+ * Node has no mmap, so large files are bounded the other way round: only the
+ * first maxBytes are ever read off disk. Oversized files are either truncated
+ * (on a character boundary) or refused outright.
+ */
+
+const fs = require('fs');
+const { isUtf8 } = require('buffer');
+const { CompanionError, ERROR_CODES, fromSystemError } = require('./errors');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  maxBytes: 8 * 1024 * 1024,
+  onTooLarge: 'truncate', // 'truncate' | 'refuse' (throws LIMIT_EXCEEDED)
+  normalizeLineEndings: true, // Convert CRLF / CR to LF in the returned content
+  binaryProbeBytes: 8192,
+};
+
+const BOMS = [
+  { encoding: 'utf-8', bytes: [0xef, 0xbb, 0xbf] },
+  { encoding: 'utf-16le', bytes: [0xff, 0xfe] },
+  { encoding: 'utf-16be', bytes: [0xfe, 0xff] },
+];
+
+// ---------------------------------------------------------------------------
+// Detection
+// ---------------------------------------------------------------------------
+
+function detectBom(buffer) {
+  for (const bom of BOMS) {
+    if (buffer.length >= bom.bytes.length && bom.bytes.every((byte, i) => buffer[i] === byte)) {
+      return bom;
+    }
+  }
+  return null;
+}
+
+/**
+ * Guess UTF-16 without a BOM: ASCII-heavy UTF-16 text has a NUL in every
+ * other byte, on the odd side for little-endian and the even side for big-endian
+ */
+function detectUtf16(buffer, probeBytes) {
+  const end = Math.min(buffer.length, probeBytes) & ~1;
+  if (end < 4) return null;
+  let evenNuls = 0;
+  let oddNuls = 0;
+  for (let i = 0; i < end; i += 2) {
+    if (buffer[i] === 0) evenNuls++;
+    if (buffer[i + 1] === 0) oddNuls++;
+  }
+  const pairs = end / 2;
+  if (oddNuls > pairs * 0.4 && evenNuls < pairs * 0.05) return 'utf-16le';
+  if (evenNuls > pairs * 0.4 && oddNuls < pairs * 0.05) return 'utf-16be';
+  return null;
+}
+
+/**
+ * Length of the buffer without a trailing, incomplete UTF-8 sequence
+ */
+function completeUtf8Length(buffer) {
+  let i = buffer.length - 1;
+  let continuation = 0;
+  while (i >= 0 && continuation < 3 && (buffer[i] & 0xc0) === 0x80) {
+    i--;
+    continuation++;
+  }
+  if (i < 0) return buffer.length;
+  const lead = buffer[i];
+  const expected = lead >= 0xf0 ? 4 : lead >= 0xe0 ? 3 : lead >= 0xc0 ? 2 : 1;
+  return expected > continuation + 1 ? i : buffer.length;
+}
+
+/**
+ * Detect the encoding of raw file bytes
+ * @param {Buffer} buffer - File contents (or a prefix of them)
+ * @param {object} options - { truncated, binaryProbeBytes }
+ * @returns {object} { encoding: 'utf-8'|'utf-16le'|'utf-16be'|'latin1'|null, bom, binary }
+ */
+function detectEncoding(buffer, options = {}) {
+  const probeBytes = options.binaryProbeBytes ?? DEFAULT_OPTIONS.binaryProbeBytes;
+  const bom = detectBom(buffer);
+  if (bom) return { encoding: bom.encoding, bom: true, binary: false };
+
+  const utf16 = detectUtf16(buffer, probeBytes);
+  if (utf16) return { encoding: utf16, bom: false, binary: false };
+  if (buffer.subarray(0, probeBytes).includes(0)) return { encoding: null, bom: false, binary: true };
+
+  const checked = options.truncated ? buffer.subarray(0, completeUtf8Length(buffer)) : buffer;
+  return { encoding: isUtf8(checked) ? 'utf-8' : 'latin1', bom: false, binary: false };
+}
+
+// ---------------------------------------------------------------------------
+// Decoding and line endings
+// ---------------------------------------------------------------------------
+
+/**
+ * Count line terminators by kind
+ * @returns {object} { lineEnding: 'lf'|'crlf'|'cr'|'mixed'|null, counts: { lf, crlf, cr } }
+ */
+function detectLineEndings(text) {
+  const counts = { lf: 0, crlf: 0, cr: 0 };
+  for (let i = 0; i < text.length; i++) {
+    const code = text.charCodeAt(i);
+    if (code === 13) {
+      if (text.charCodeAt(i + 1) === 10) {
+        counts.crlf++;
+        i++;
+      } else {
+        counts.cr++;
+      }
+    } else if (code === 10) {
+      counts.lf++;
+    }
+  }
+  const kinds = Object.keys(counts).filter((kind) => counts[kind] > 0);
+  return { lineEnding: kinds.length === 0 ? null : kinds.length === 1 ? kinds[0] : 'mixed', counts };
+}
+
+function normalizeLineEndings(text) {
+  return text.includes('\r') ? text.replace(/\r\n?/g, '\n') : text;
+}
+
+/**
+ * Decode bytes with a detected encoding, dropping the BOM and any partial
+ * trailing character left by truncation
+ */
+function decodeBuffer(buffer, encoding, options = {}) {
+  const bom = options.bom ? detectBom(buffer) : null;
+  let bytes = bom ? buffer.subarray(bom.bytes.length) : buffer;
+
+  if (encoding === 'utf-16le' || encoding === 'utf-16be') {
+    bytes = bytes.subarray(0, bytes.length & ~1);
+    if (encoding === 'utf-16be') bytes = Buffer.from(bytes).swap16();
+    let text = bytes.toString('utf16le');
+    // A truncated surrogate pair leaves a lone high surrogate at the end
+    if (options.truncated && /[\ud800-\udbff]$/.test(text)) text = text.slice(0, -1);
+    return text;
+  }
+  if (encoding === 'latin1') return bytes.toString('latin1');
+  if (options.truncated) bytes = bytes.subarray(0, completeUtf8Length(bytes));
+  return bytes.toString('utf8');
+}
+
+/**
+ * Decode a file's bytes and collect metadata
+ * @param {Buffer} buffer - File bytes, possibly only the first part of the file
+ * @param {object} options - { size, truncated, normalizeLineEndings, binaryProbeBytes }
+ */
+function decodeFileBuffer(buffer, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const truncated = Boolean(opts.truncated);
+  const { encoding, bom, binary } = detectEncoding(buffer, {
+    truncated,
+    binaryProbeBytes: opts.binaryProbeBytes,
+  });
+  const size = opts.size ?? buffer.length;
+  const meta = { size, bytesRead: buffer.length, truncated, encoding, bom, binary };
+  if (binary) return { content: null, ...meta, lineEnding: null, lineEndings: null, lines: 0 };
+
+  const raw = decodeBuffer(buffer, encoding, { bom, truncated });
+  const { lineEnding, counts } = detectLineEndings(raw);
+  const content = opts.normalizeLineEndings ? normalizeLineEndings(raw) : raw;
+  const terminators = counts.lf + counts.crlf + counts.cr;
+  const lines = raw.length === 0 ? 0 : terminators + (/[\r\n]$/.test(raw) ? 0 : 1);
+  return { content, ...meta, lineEnding, lineEndings: counts, lines };
+}
+
+// ---------------------------------------------------------------------------
+// Reading
+// ---------------------------------------------------------------------------
+
+function tooLarge(filePath, size, maxBytes) {
+  const message = `${filePath} is ${size} bytes, over the ${maxBytes} byte limit`;
+  return new CompanionError(ERROR_CODES.LIMIT_EXCEEDED, message, {
+    reason: 'file-too-large',
+    details: { path: filePath, size, maxBytes },
+  });
+}
+
+/**
+ * Read and decode a text file
+ * @param {string} filePath - File to read
+ * @param {number} maxBytes - Read at most this many bytes (default 8MB)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Promise<object>} { content, encoding, bom, binary, lineEnding, lineEndings, lines,
+ *   size, bytesRead, truncated }; content is null for binary files
+ */
+async function readFileSmart(filePath, maxBytes = DEFAULT_OPTIONS.maxBytes, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options, maxBytes };
+  const handle = await fs.promises.open(filePath, 'r').catch((error) => {
+    throw fromSystemError(error);
+  });
+  try {
+    const { size } = await handle.stat();
+    if (size > maxBytes && opts.onTooLarge === 'refuse') throw tooLarge(filePath, size, maxBytes);
+    const length = Math.min(size, maxBytes);
+    const buffer = Buffer.allocUnsafe(length);
+    let offset = 0;
+    while (offset < length) {
+      const { bytesRead } = await handle.read(buffer, offset, length - offset, offset);
+      if (bytesRead === 0) break;
+      offset += bytesRead;
+    }
+    return decodeFileBuffer(buffer.subarray(0, offset), { ...opts, size, truncated: size > offset });
+  } finally {
+    await handle.close();
+  }
+}
+
+/**
+ * Synchronous readFileSmart
+ */
+function readFileSmartSync(filePath, maxBytes = DEFAULT_OPTIONS.maxBytes, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options, maxBytes };
+  let fd;
+  try {
+    fd = fs.openSync(filePath, 'r');
+  } catch (error) {
+    throw fromSystemError(error);
+  }
+  try {
+    const { size } = fs.fstatSync(fd);
+    if (size > maxBytes && opts.onTooLarge === 'refuse') throw tooLarge(filePath, size, maxBytes);
+    const length = Math.min(size, maxBytes);
+    const buffer = Buffer.allocUnsafe(length);
+    let offset = 0;
+    while (offset < length) {
+      const bytesRead = fs.readSync(fd, buffer, offset, length - offset, offset);
+      if (bytesRead === 0) break;
+      offset += bytesRead;
+    }
+    return decodeFileBuffer(buffer.subarray(0, offset), { ...opts, size, truncated: size > offset });
+  } finally {
+    fs.closeSync(fd);
+  }
+}
+
+module.exports = instrument('FILE', {
+  readFileSmart,
+  readFileSmartSync,
+  decodeFileBuffer,
+  decodeBuffer,
+  detectEncoding,
+  detectLineEndings,
+  normalizeLineEndings,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,322 @@
+/**
+ * Fuzzy Match
+ * fzf-style fuzzy matching for the trace viewer's file picker.
+ *
+ * Scoring follows fzf's v2 algorithm: every query character earns a base
+ * score, gaps cost a start penalty plus a per-character extension, and matches
+ * at word boundaries (after '/', '_', '-', '.', whitespace or a camelCase
+ * hump) earn bonuses, doubled for the first query character. Consecutive
+ * matches inherit the bonus of the run's first character. The best alignment
+ * is found by dynamic programming over the span between the first and last
+ * possible match, after a cheap subsequence check rejects most candidates.
+ *
+ * Space-separated query terms must all match (scores add). Matching is
+ * case-insensitive unless the term contains an uppercase letter (smart case).
+ */
+
+const { createWorker, resolveConcurrency } = require('./thread-pool');
+const { instrument } = require('./logger');
+
+const SCORE_MATCH = 16;
+const SCORE_GAP_START = -3;
+const SCORE_GAP_EXTENSION = -1;
+const BONUS_BOUNDARY = SCORE_MATCH / 2;
+const BONUS_NON_WORD = SCORE_MATCH / 2;
+const BONUS_CAMEL_123 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+const BONUS_CONSECUTIVE = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER = 2;
+const BONUS_BOUNDARY_WHITE = BONUS_BOUNDARY + 2;
+const BONUS_BOUNDARY_DELIMITER = BONUS_BOUNDARY + 1;
+
+const CLASS_WHITE = 0;
+const CLASS_NON_WORD = 1;
+const CLASS_DELIMITER = 2;
+const CLASS_LOWER = 3;
+const CLASS_UPPER = 4;
+const CLASS_LETTER = 5;
+const CLASS_NUMBER = 6;
+
+const DEFAULT_OPTIONS = {
+  caseSensitive: null, // null = smart case
+  minParallel: 20000, // fuzzyMatchParallel ranks inline below this many candidates
+  concurrency: null, // null = thread pool default (utils/thread-pool)
+};
+
+function charClass(char) {
+  if (char >= 'a' && char <= 'z') return CLASS_LOWER;
+  if (char >= 'A' && char <= 'Z') return CLASS_UPPER;
+  if (char >= '0' && char <= '9') return CLASS_NUMBER;
+  if (char === ' ' || char === '\t' || char === '\n' || char === '\r') return CLASS_WHITE;
+  if (char === '/' || char === '\\' || char === ',' || char === ':' || char === ';' || char === '|') {
+    return CLASS_DELIMITER;
+  }
+  if (char === '_' || char === '-' || char === '.') return CLASS_NON_WORD;
+  const lower = char.toLowerCase();
+  if (lower !== char.toUpperCase()) return lower === char ? CLASS_LOWER : CLASS_UPPER;
+  return /\p{L}/u.test(char) ? CLASS_LETTER : CLASS_NON_WORD;
+}
+
+// Lowercase per UTF-16 unit so positions in the folded text line up with the original
+function foldCase(text) {
+  let folded = '';
+  for (let i = 0; i < text.length; i++) {
+    const lower = text[i].toLowerCase();
+    folded += lower.length === 1 ? lower : text[i];
+  }
+  return folded;
+}
+
+function bonusFor(previous, current) {
+  if (current > CLASS_DELIMITER) {
+    if (previous === CLASS_WHITE) return BONUS_BOUNDARY_WHITE;
+    if (previous === CLASS_DELIMITER) return BONUS_BOUNDARY_DELIMITER;
+    if (previous === CLASS_NON_WORD) return BONUS_BOUNDARY;
+  }
+  if (previous === CLASS_LOWER && current === CLASS_UPPER) return BONUS_CAMEL_123;
+  if (previous !== CLASS_NUMBER && current === CLASS_NUMBER) return BONUS_CAMEL_123;
+  if (current === CLASS_NON_WORD || current === CLASS_DELIMITER) return BONUS_NON_WORD;
+  if (current === CLASS_WHITE) return BONUS_BOUNDARY_WHITE;
+  return 0;
+}
+
+// Scratch buffers reused across candidates; grown on demand
+let scratchSize = 0;
+let scores = null;
+let runBonus = null;
+let fromGap = null;
+
+function ensureScratch(size) {
+  if (size <= scratchSize) return;
+  scratchSize = Math.max(size, scratchSize * 2, 1024);
+  scores = new Int32Array(scratchSize);
+  runBonus = new Int32Array(scratchSize);
+  fromGap = new Uint8Array(scratchSize);
+}
+
+const NEG = -1e9;
+
+/**
+ * Best alignment of one query term in a text
+ * @returns {object|null} { score, positions } or null when the term isn't a subsequence
+ */
+function matchTerm(term, text, caseSensitive) {
+  const pattern = caseSensitive ? term : foldCase(term);
+  const haystack = caseSensitive ? text : foldCase(text);
+  const m = pattern.length;
+  if (m === 0) return { score: 0, positions: [] };
+
+  // Subsequence check forward for the earliest start, backward for the latest end
+  let first = -1;
+  let k = 0;
+  for (let j = 0; j < haystack.length && k < m; j++) {
+    if (haystack[j] === pattern[k]) {
+      if (k === 0) first = j;
+      k++;
+    }
+  }
+  if (k < m) return null;
+  let last = haystack.length - 1;
+  k = m - 1;
+  for (let j = haystack.length - 1; j >= first; j--) {
+    if (haystack[j] === pattern[k]) {
+      if (k === m - 1) last = j;
+      if (--k < 0) break;
+    }
+  }
+
+  const n = last - first + 1;
+  ensureScratch(n * m);
+  const bonus = new Int32Array(n);
+  let previous = first > 0 ? charClass(text[first - 1]) : CLASS_WHITE;
+  for (let j = 0; j < n; j++) {
+    const current = charClass(text[first + j]);
+    bonus[j] = bonusFor(previous, current);
+    previous = current;
+  }
+
+  // scores[i*n + j]: best score with query char i matched at text position first + j
+  for (let i = 0; i < m; i++) {
+    let gapBest = NEG; // Best predecessor score reaching j through a gap, penalties applied
+    for (let j = 0; j < n; j++) {
+      const cell = i * n + j;
+      if (i > 0 && j >= 2) {
+        const startGap = scores[(i - 1) * n + j - 2] + SCORE_GAP_START;
+        gapBest = Math.max(gapBest + SCORE_GAP_EXTENSION, startGap);
+      }
+      scores[cell] = NEG;
+      if (haystack[first + j] !== pattern[i]) continue;
+
+      if (i === 0) {
+        scores[cell] = SCORE_MATCH + bonus[j] * BONUS_FIRST_CHAR_MULTIPLIER;
+        runBonus[cell] = bonus[j];
+        fromGap[cell] = 1;
+        continue;
+      }
+      let best = NEG;
+      if (j >= 1 && scores[cell - n - 1] > NEG) {
+        const chain = Math.max(bonus[j], BONUS_CONSECUTIVE, runBonus[cell - n - 1]);
+        best = scores[cell - n - 1] + SCORE_MATCH + chain;
+        runBonus[cell] = chain;
+        fromGap[cell] = 0;
+      }
+      if (gapBest > NEG / 2 && gapBest + SCORE_MATCH + bonus[j] > best) {
+        best = gapBest + SCORE_MATCH + bonus[j];
+        runBonus[cell] = bonus[j];
+        fromGap[cell] = 1;
+      }
+      scores[cell] = best;
+    }
+  }
+
+  let end = -1;
+  let score = NEG;
+  for (let j = 0; j < n; j++) {
+    const value = scores[(m - 1) * n + j];
+    if (value > score) {
+      score = value;
+      end = j;
+    }
+  }
+  if (end === -1 || score <= NEG / 2) return null;
+
+  // Backtrack: a gap step resumes at the best predecessor left of j - 1
+  const positions = new Array(m);
+  let j = end;
+  for (let i = m - 1; i >= 0; i--) {
+    positions[i] = first + j;
+    if (i === 0) break;
+    if (!fromGap[i * n + j]) {
+      j -= 1;
+      continue;
+    }
+    const target = scores[i * n + j] - SCORE_MATCH - bonus[j];
+    let chosen = -1;
+    for (let p = j - 2; p >= 0; p--) {
+      const value = scores[(i - 1) * n + p];
+      if (value > NEG / 2 && value + SCORE_GAP_START + SCORE_GAP_EXTENSION * (j - p - 2) === target) {
+        chosen = p;
+        break;
+      }
+    }
+    j = chosen === -1 ? j - 2 : chosen;
+  }
+
+  return { score, positions };
+}
+
+function parseQuery(query, caseSensitive) {
+  return String(query ?? '')
+    .split(/\s+/)
+    .filter(Boolean)
+    .map((term) => ({ term, caseSensitive: caseSensitive ?? /[A-Z]/.test(term) }));
+}
+
+/**
+ * Score one candidate against a query
+ * @returns {object|null} { score, positions } (positions sorted, deduplicated) or null
+ */
+function scoreCandidate(query, candidate, options = {}) {
+  const terms = Array.isArray(query) ? query : parseQuery(query, options.caseSensitive ?? null);
+  const text = String(candidate ?? '');
+  let score = 0;
+  const positions = new Set();
+  for (const { term, caseSensitive } of terms) {
+    const result = matchTerm(term, text, caseSensitive);
+    if (!result) return null;
+    score += result.score;
+    result.positions.forEach((position) => positions.add(position));
+  }
+  return { score, positions: Array.from(positions).sort((a, b) => a - b) };
+}
+
+function compareResults(a, b) {
+  return b.score - a.score || a.candidate.length - b.candidate.length || a.index - b.index;
+}
+
+/**
+ * Rank candidates against a query
+ * @param {string} query - Query; space-separated terms must all match
+ * @param {Array<string>} candidates - Paths or other strings
+ * @param {number} limit - Maximum results
+ * @param {object} options - { caseSensitive, offset } offset shifts reported indices (used by workers)
+ * @returns {Array<object>} [{ candidate, index, score, positions }] best first;
+ *   ties prefer shorter candidates
+ */
+function fuzzyMatch(query, candidates, limit = 50, options = {}) {
+  const terms = parseQuery(query, options.caseSensitive ?? null);
+  const offset = options.offset || 0;
+  const list = candidates || [];
+  if (terms.length === 0) {
+    return list
+      .slice(0, limit)
+      .map((candidate, i) => ({ candidate: String(candidate), index: offset + i, score: 0, positions: [] }));
+  }
+
+  // Keep only the running top `limit` so huge candidate lists don't build a huge result array
+  const top = [];
+  let worst = null;
+  list.forEach((candidate, i) => {
+    const result = scoreCandidate(terms, candidate);
+    if (!result) return;
+    const entry = { candidate: String(candidate), index: offset + i, ...result };
+    if (top.length >= limit && compareResults(entry, worst) >= 0) return;
+    top.push(entry);
+    if (top.length > limit * 2) {
+      top.sort(compareResults);
+      top.length = limit;
+    }
+    if (top.length >= limit) worst = top.reduce((a, b) => (compareResults(a, b) > 0 ? a : b));
+  });
+  top.sort(compareResults);
+  return top.slice(0, limit);
+}
+
+const RANK_WORKER_SOURCE = `
+const { parentPort, workerData } = require('worker_threads');
+const { fuzzyMatch } = require(workerData.modulePath);
+parentPort.on('message', ({ query, candidates, limit, options }) => {
+  parentPort.postMessage(fuzzyMatch(query, candidates, limit, options));
+});
+`;
+
+/**
+ * fuzzyMatch over worker threads for very large candidate lists
+ * @returns {Promise<Array<object>>} Same shape as fuzzyMatch
+ */
+async function fuzzyMatchParallel(query, candidates, limit = 50, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const list = candidates || [];
+  const concurrency = resolveConcurrency(opts.concurrency);
+  if (concurrency <= 1 || list.length < opts.minParallel) return fuzzyMatch(query, list, limit, opts);
+
+  const chunkSize = Math.ceil(list.length / concurrency);
+  const workers = [];
+  try {
+    const parts = await Promise.all(
+      Array.from({ length: concurrency }, (_, w) => {
+        const worker = createWorker(RANK_WORKER_SOURCE, { workerData: { modulePath: __filename } });
+        workers.push(worker);
+        return new Promise((resolve, reject) => {
+          worker.once('message', resolve);
+          worker.once('error', reject);
+          worker.postMessage({
+            query,
+            candidates: list.slice(w * chunkSize, (w + 1) * chunkSize),
+            limit,
+            options: { caseSensitive: opts.caseSensitive, offset: w * chunkSize },
+          });
+        });
+      })
+    );
+    return parts.flat().sort(compareResults).slice(0, limit);
+  } finally {
+    await Promise.all(workers.map((worker) => worker.terminate()));
+  }
+}
+
+module.exports = instrument('FUZZY', {
+  fuzzyMatch,
+  fuzzyMatchParallel,
+  scoreCandidate,
+  DEFAULT_OPTIONS,
+});
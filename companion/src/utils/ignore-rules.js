@@ -0,0 +1,344 @@
+/**
+ * Ignore Rules
+ * .gitignore semantics for walking and filtering workspace paths: per-directory
+ * ignore files, negation, directory-only rules, anchored vs. basename
+ * patterns and `**`. Paths are relative to the matcher's root and use '/'.
+ *
+ * GlobSet/matchGlob cover the plain glob lists in config (watcher ignores,
+ * exporter filters) with the same translation, compiled once per set.
+ */
+
+const fs = require('fs');
+const path = require('path');
+
+const IGNORE_FILES = ['.gitignore', '.ignore'];
+const REGEX_SPECIAL = /[.+^${}()|\\]/;
+
+/**
+ * Translate a glob into a regex source (unanchored)
+ * @param {string} glob - Pattern; '/' separates segments
+ * @param {object} options - { braces } expand {a,b} alternatives (not part of gitignore syntax)
+ */
+function globToRegexSource(glob, options = {}) {
+  let source = '';
+  let braceDepth = 0;
+  for (let i = 0; i < glob.length; i++) {
+    const char = glob[i];
+    if (char === '\\' && i + 1 < glob.length) {
+      source += `\\${glob[++i]}`;
+    } else if (char === '*') {
+      if (glob[i + 1] === '*') {
+        const atStart = i === 0 || glob[i - 1] === '/';
+        const atEnd = i + 2 === glob.length || glob[i + 2] === '/';
+        i++;
+        if (atStart && glob[i + 1] === '/') {
+          source += '(?:.*/)?'; // "**/" matches zero or more directories
+          i++;
+        } else if (atStart && atEnd) {
+          source += '.*';
+        } else {
+          source += '[^/]*';
+        }
+      } else {
+        source += '[^/]*';
+      }
+    } else if (char === '?') {
+      source += '[^/]';
+    } else if (char === '[') {
+      const close = glob.indexOf(']', i + 2);
+      if (close === -1) {
+        source += '\\[';
+        continue;
+      }
+      let body = glob.slice(i + 1, close);
+      if (body[0] === '!') body = `^${body.slice(1)}`;
+      source += `[${body.replace(/\//g, '')}]`;
+      i = close;
+    } else if (options.braces && char === '{') {
+      braceDepth++;
+      source += '(?:';
+    } else if (options.braces && char === '}' && braceDepth > 0) {
+      braceDepth--;
+      source += ')';
+    } else if (options.braces && char === ',' && braceDepth > 0) {
+      source += '|';
+    } else {
+      source += REGEX_SPECIAL.test(char) ? `\\${char}` : char;
+    }
+  }
+  return source;
+}
+
+/**
+ * Parse the contents of a .gitignore file
+ * @returns {Array<object>} [{ pattern, regex, negate, dirOnly }]
+ */
+function parseIgnoreFile(content) {
+  const rules = [];
+  for (const rawLine of String(content ?? '').split(/\r?\n/)) {
+    let line = rawLine.replace(/(?<!\\)\s+$/, '');
+    if (!line || line.startsWith('#')) continue;
+
+    let negate = false;
+    if (line.startsWith('!')) {
+      negate = true;
+      line = line.slice(1);
+    } else if (line.startsWith('\\!') || line.startsWith('\\#')) {
+      line = line.slice(1);
+    }
+
+    const dirOnly = line.endsWith('/');
+    if (dirOnly) line = line.slice(0, -1);
+    if (!line) continue;
+
+    // A slash anywhere but the end anchors the pattern to the ignore file's directory
+    const anchored = line.includes('/');
+    if (line.startsWith('/')) line = line.slice(1);
+    const body = globToRegexSource(line);
+    const regex = new RegExp(`^${anchored ? '' : '(?:.*/)?'}${body}$`);
+    rules.push({ pattern: rawLine.trim(), regex, negate, dirOnly });
+  }
+  return rules;
+}
+
+class IgnoreMatcher {
+  /**
+   * @param {string} root - Directory the relative paths are resolved against
+   * @param {object} options - { ignoreFiles, extraRules } extraRules: gitignore lines applied at the root
+   */
+  constructor(root, options = {}) {
+    this.root = root;
+    this.ignoreFiles = options.ignoreFiles || IGNORE_FILES;
+    this.ruleSets = new Map(); // relative dir ('' = root) -> rules
+    this.loaded = new Set();
+    const rootRules = parseIgnoreFile((options.extraRules || []).join('\n'));
+    if (rootRules.length) this.ruleSets.set('', rootRules);
+  }
+
+  /**
+   * Read the ignore files of a directory (once); called by walkers as they descend
+   */
+  loadDirectory(relativeDir) {
+    if (this.loaded.has(relativeDir)) return;
+    this.loaded.add(relativeDir);
+    const files = relativeDir === '' ? ['.git/info/exclude', ...this.ignoreFiles] : this.ignoreFiles;
+    for (const name of files) {
+      let content;
+      try {
+        content = fs.readFileSync(path.join(this.root, relativeDir, name), 'utf8');
+      } catch {
+        continue;
+      }
+      const rules = parseIgnoreFile(content);
+      if (rules.length) this.ruleSets.set(relativeDir, [...(this.ruleSets.get(relativeDir) || []), ...rules]);
+    }
+  }
+
+  /**
+   * Whether the path itself matches, ignoring its ancestors (last matching rule wins)
+   * @param {string} relativePath - Path relative to root, '/'-separated
+   * @param {boolean} isDir - Directory-only rules apply only to directories
+   * @returns {boolean|null} true ignored, false re-included, null no rule matched
+   */
+  match(relativePath, isDir = false) {
+    let result = null;
+    const segments = relativePath.split('/');
+    for (let depth = 0; depth < segments.length; depth++) {
+      const dir = segments.slice(0, depth).join('/');
+      const rules = this.ruleSets.get(dir);
+      if (!rules) continue;
+      const local = segments.slice(depth).join('/');
+      for (const rule of rules) {
+        if (rule.dirOnly && !isDir) continue;
+        if (rule.regex.test(local)) result = !rule.negate;
+      }
+    }
+    return result;
+  }
+
+  /**
+   * Whether the path is ignored, including through an ignored ancestor directory
+   */
+  isIgnored(relativePath, isDir = false) {
+    const normalized = relativePath.split(path.sep).join('/').replace(/^\.\//, '').replace(/\/$/, '');
+    const segments = normalized.split('/');
+    for (let depth = 0; depth < segments.length; depth++) {
+      this.loadDirectory(segments.slice(0, depth).join('/'));
+      const prefix = segments.slice(0, depth + 1).join('/');
+      const last = depth === segments.length - 1;
+      if (this.match(prefix, last ? isDir : true) === true) return true;
+    }
+    return false;
+  }
+}
+
+/**
+ * Walk a directory tree, skipping .git and anything the ignore files exclude
+ * @param {string} root - Directory to walk
+ * @param {object} options - { hidden, followSymlinks, maxDepth, extraRules, ignoreFiles, gitignore }
+ *   gitignore: false disables ignore-file handling entirely
+ * @returns {AsyncGenerator<object>} { path, relativePath } in sorted, depth-first order
+ */
+async function* walkFiles(root, options = {}) {
+  const { hidden = false, followSymlinks = false, maxDepth = Infinity, gitignore = true } = options;
+  const matcher = gitignore ? new IgnoreMatcher(root, options) : null;
+  const stack = [['', 0]];
+
+  while (stack.length) {
+    const [relativeDir, depth] = stack.pop();
+    if (matcher) matcher.loadDirectory(relativeDir);
+    let entries;
+    try {
+      entries = await fs.promises.readdir(path.join(root, relativeDir), { withFileTypes: true });
+    } catch {
+      continue;
+    }
+    entries.sort((a, b) => (a.name < b.name ? -1 : a.name > b.name ? 1 : 0));
+    const subdirectories = [];
+
+    for (const entry of entries) {
+      if (entry.name === '.git') continue;
+      if (!hidden && entry.name.startsWith('.')) continue;
+      const relativePath = relativeDir ? `${relativeDir}/${entry.name}` : entry.name;
+      const fullPath = path.join(root, relativePath);
+
+      let isDir = entry.isDirectory();
+      let isFile = entry.isFile();
+      if (entry.isSymbolicLink()) {
+        if (!followSymlinks) continue;
+        try {
+          const stat = await fs.promises.stat(fullPath);
+          isDir = stat.isDirectory();
+          isFile = stat.isFile();
+        } catch {
+          continue;
+        }
+      }
+      if (matcher && matcher.match(relativePath, isDir) === true) continue;
+
+      if (isDir) {
+        if (depth + 1 <= maxDepth) subdirectories.push([relativePath, depth + 1]);
+      } else if (isFile) {
+        yield { path: fullPath, relativePath };
+      }
+    }
+    stack.push(...subdirectories.reverse());
+  }
+}
+
+/**
+ * Compiled set of globs, matched against many paths (globset-style).
+ * Patterns of the common shapes are bucketed so most paths never reach a regex:
+ * exact paths, extension globs (`*.ext` at any depth) and basenames.
+ */
+class GlobSet {
+  /**
+   * @param {Array<string>} patterns - Globs with *, **, ?, [...] and {a,b}
+   * @param {object} options - { matchBase, caseInsensitive }
+   *   matchBase: patterns without '/' match the basename at any depth (default true)
+   */
+  constructor(patterns, options = {}) {
+    const { matchBase = true, caseInsensitive = false } = options;
+    this.patterns = (patterns || []).map(String);
+    this.caseInsensitive = caseInsensitive;
+    this.exact = new Map(); // path -> [pattern index]
+    this.basenames = new Map();
+    this.extensions = new Map(); // '.ext' -> [pattern index], for *.ext at any depth
+    this.regexes = []; // [pattern index, regex]
+
+    const add = (map, key, index) => {
+      const folded = caseInsensitive ? key.toLowerCase() : key;
+      if (!map.has(folded)) map.set(folded, []);
+      map.get(folded).push(index);
+    };
+
+    this.patterns.forEach((raw, index) => {
+      let pattern = raw.replace(/^\.\//, '');
+      const anyDepth = (matchBase && !pattern.includes('/')) || pattern.startsWith('**/');
+      if (pattern.startsWith('**/')) pattern = pattern.slice(3);
+      if (pattern.startsWith('/')) pattern = pattern.slice(1);
+
+      const literal = !/[*?[{\\]/.test(pattern);
+      if (literal && !anyDepth) {
+        add(this.exact, pattern, index);
+        return;
+      }
+      if (literal && !pattern.includes('/')) {
+        add(this.basenames, pattern, index);
+        return;
+      }
+      const extension = pattern.match(/^\*(\.[^*?[{\\/]+)$/);
+      if (anyDepth && extension) {
+        add(this.extensions, extension[1], index);
+        return;
+      }
+      const source = globToRegexSource(pattern, { braces: true });
+      const flags = caseInsensitive ? 'i' : '';
+      this.regexes.push([index, new RegExp(`^${anyDepth ? '(?:.*/)?' : ''}${source}$`, flags)]);
+    });
+  }
+
+  /**
+   * Indices of the patterns matching a path, in pattern order
+   */
+  matches(filePath) {
+    const normalized = String(filePath).split(path.sep).join('/').replace(/^\.\//, '');
+    const key = this.caseInsensitive ? normalized.toLowerCase() : normalized;
+    const basename = key.slice(key.lastIndexOf('/') + 1);
+    const found = [...(this.exact.get(key) || []), ...(this.basenames.get(basename) || [])];
+
+    if (this.extensions.size) {
+      // Every dotted suffix, so compound extensions (*.d.ts) match too
+      for (let i = basename.indexOf('.'); i !== -1; i = basename.indexOf('.', i + 1)) {
+        found.push(...(this.extensions.get(basename.slice(i)) || []));
+      }
+    }
+    for (const [index, regex] of this.regexes) if (regex.test(normalized)) found.push(index);
+    return found.length > 1 ? Array.from(new Set(found)).sort((a, b) => a - b) : found;
+  }
+
+  isMatch(filePath) {
+    return this.matches(filePath).length > 0;
+  }
+}
+
+/**
+ * Match many paths against a set of globs
+ * @param {Array<string>} paths - Paths ('/' or platform separators)
+ * @param {Array<string>|GlobSet} patterns - Globs, or a prebuilt GlobSet
+ * @param {object} options - GlobSet options
+ * @returns {Array<Array<number>>} Matching pattern indices per path (empty when none match)
+ */
+function matchGlob(paths, patterns, options = {}) {
+  const set = patterns instanceof GlobSet ? patterns : new GlobSet(patterns, options);
+  return (paths || []).map((filePath) => set.matches(filePath));
+}
+
+/**
+ * Drop the paths a workspace's ignore files exclude
+ * @param {string} root - Workspace root holding the .gitignore files
+ * @param {Array<string>} paths - Absolute paths or paths relative to root
+ * @param {object} options - { extraRules, ignoreFiles }
+ * @returns {Array<string>} The paths that aren't ignored, in input order (paths outside root are kept)
+ */
+function filterIgnored(root, paths, options = {}) {
+  const matcher = new IgnoreMatcher(root, options);
+  return (paths || []).filter((filePath) => {
+    const relativePath = path.isAbsolute(filePath) ? path.relative(root, filePath) : filePath;
+    if (relativePath.startsWith('..') || path.isAbsolute(relativePath)) return true;
+    const segments = relativePath.split(path.sep).join('/').split('/');
+    if (segments.includes('.git')) return false;
+    return !matcher.isIgnored(relativePath);
+  });
+}
+
+module.exports = {
+  GlobSet,
+  matchGlob,
+  filterIgnored,
+  IgnoreMatcher,
+  parseIgnoreFile,
+  globToRegexSource,
+  walkFiles,
+  IGNORE_FILES,
+};
@@ -0,0 +1,212 @@
+/**
+ * Language Breakdown
+ * Per-language bytes, lines and files for a whole project, the way
+ * github-linguist reports them: ignored, vendored, generated and
+ * documentation files don't count.
+ *
+ * The tree is walked once honouring .gitignore/.ignore files (see
+ * ignore-rules); vendored directories (node_modules, vendor, third_party,
+ * Pods, ...) are added as ignore rules so they're never descended into,
+ * which is where most of the time goes on large repositories. Files are
+ * measured on worker threads in batches: each is read as a Buffer, skipped
+ * when binary or oversized, checked for generated markers (see
+ * code-generated) and given its most likely language (see code-language).
+ */
+
+const fs = require('fs');
+const { createWorker, resolveConcurrency } = require('./thread-pool');
+const { walkFiles } = require('./ignore-rules');
+const { detectLanguages } = require('./code-language');
+const { detectGenerated } = require('./code-generated');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  maxFileBytes: 2 * 1024 * 1024, // Larger files are skipped as too large
+  binaryProbeBytes: 8192, // A NUL byte in this prefix marks a file as binary
+  vendored: false, // Count vendored files
+  generated: false, // Count generated files (lockfiles, minified bundles, codegen output)
+  documentation: false, // Count files under docs/ and the like
+  hidden: false,
+  followSymlinks: false,
+  gitignore: true,
+  concurrency: null, // null = thread pool default (utils/thread-pool)
+  batchSize: 64,
+  minParallelFiles: 256,
+};
+
+// Vendored directories, as gitignore rules so the walk prunes them
+const VENDORED_DIRECTORIES = [
+  'node_modules/',
+  'bower_components/',
+  'jspm_packages/',
+  'vendor/',
+  'vendors/',
+  'third_party/',
+  'third-party/',
+  'thirdparty/',
+  'Godeps/',
+  'Pods/',
+  'Carthage/',
+  '.yarn/',
+  'site-packages/',
+  '.venv/',
+  'venv/',
+  'dist/',
+];
+
+// Vendored single files (copied-in libraries)
+const VENDORED_FILES = [
+  /(?:^|\/)jquery(?:[-.][\w.-]*)?\.js$/i,
+  /(?:^|\/)bootstrap(?:[-.][\w.-]*)?\.(?:js|css)$/i,
+  /(?:^|\/)(?:d3|underscore|lodash|backbone|angular|react(?:-dom)?)(?:[-.][\w.-]*)?\.min\.js$/i,
+  /(?:^|\/)gradlew(?:\.bat)?$/,
+  /(?:^|\/)(?:mvnw|mvnw\.cmd)$/,
+];
+
+const DOCUMENTATION = [
+  /(?:^|\/)(?:docs?|Documentation|man|examples?|samples?)\//i,
+  /(?:^|\/)(?:README|CHANGELOG|CHANGES|CONTRIBUTING|LICENSE|LICENCE|COPYING|AUTHORS|NOTICE)(?:\.\w+)?$/i,
+];
+
+/**
+ * Measure one file
+ * @returns {object} { relativePath, language, bytes, lines }, or { relativePath, skipped } /
+ *   { relativePath, error } for files that don't count
+ */
+function measureFile(file, relativePath, opts) {
+  if (!opts.vendored && VENDORED_FILES.some((pattern) => pattern.test(relativePath))) {
+    return { relativePath, skipped: 'vendored' };
+  }
+  if (!opts.documentation && DOCUMENTATION.some((pattern) => pattern.test(relativePath))) {
+    return { relativePath, skipped: 'documentation' };
+  }
+  let buffer;
+  try {
+    const stat = fs.statSync(file);
+    if (stat.size > opts.maxFileBytes) return { relativePath, skipped: 'too-large' };
+    buffer = fs.readFileSync(file);
+  } catch (error) {
+    return { relativePath, error: error.message };
+  }
+  if (buffer.subarray(0, opts.binaryProbeBytes).includes(0)) return { relativePath, skipped: 'binary' };
+
+  const text = buffer.toString('utf8');
+  if (!opts.generated && detectGenerated(text, relativePath).generated) {
+    return { relativePath, skipped: 'generated' };
+  }
+  const [best] = detectLanguages(text, relativePath, { maxResults: 1 });
+  if (!best) return { relativePath, skipped: 'unknown' };
+
+  let lines = 0;
+  for (let i = buffer.indexOf(0x0a); i !== -1; i = buffer.indexOf(0x0a, i + 1)) lines++;
+  if (buffer.length > 0 && buffer[buffer.length - 1] !== 0x0a) lines++;
+  return { relativePath, language: best.language, bytes: buffer.length, lines };
+}
+
+const MEASURE_WORKER_SOURCE = `
+const { parentPort, workerData } = require('worker_threads');
+const { measureFile } = require(workerData.modulePath);
+parentPort.on('message', (files) => {
+  const results = files.map(([file, relativePath]) => measureFile(file, relativePath, workerData.options));
+  parentPort.postMessage(results);
+});
+`;
+
+/**
+ * Per-language breakdown of a project
+ * @param {string} root - Directory to scan
+ * @param {Array<string>} ignorePatterns - Additional gitignore-syntax excludes applied at the root
+ * @param {object} options - See DEFAULT_OPTIONS, plus signal (AbortSignal)
+ * @returns {Promise<object>} { languages: [{ language, files, bytes, lines, share }] by bytes,
+ *   largest first (share of all counted bytes), totals: { files, bytes, lines },
+ *   skipped: { vendored, generated, documentation, binary, 'too-large', unknown }, errors, cancelled }
+ *   Vendored directories are never walked, so skipped.vendored only counts vendored single files
+ */
+async function detectLanguagesInDir(root, ignorePatterns = [], options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const { signal = null } = options;
+  const extraRules = [...(opts.extraRules || []), ...(ignorePatterns || [])];
+  if (!opts.vendored) extraRules.push(...VENDORED_DIRECTORIES);
+
+  const files = [];
+  for await (const entry of walkFiles(root, { ...opts, extraRules })) {
+    if (signal?.aborted) break;
+    files.push([entry.path, entry.relativePath]);
+  }
+
+  const byLanguage = new Map();
+  const totals = { files: 0, bytes: 0, lines: 0 };
+  const skipped = { vendored: 0, generated: 0, documentation: 0, binary: 0, 'too-large': 0, unknown: 0 };
+  const errors = [];
+  const handle = (result) => {
+    if (result.error) {
+      errors.push({ file: result.relativePath, error: result.error });
+      return;
+    }
+    if (result.skipped) {
+      skipped[result.skipped]++;
+      return;
+    }
+    if (!byLanguage.has(result.language)) {
+      byLanguage.set(result.language, { language: result.language, files: 0, bytes: 0, lines: 0 });
+    }
+    const entry = byLanguage.get(result.language);
+    entry.files++;
+    entry.bytes += result.bytes;
+    entry.lines += result.lines;
+    totals.files++;
+    totals.bytes += result.bytes;
+    totals.lines += result.lines;
+  };
+
+  const workerOptions = {
+    maxFileBytes: opts.maxFileBytes,
+    binaryProbeBytes: opts.binaryProbeBytes,
+    vendored: opts.vendored,
+    generated: opts.generated,
+    documentation: opts.documentation,
+  };
+  const concurrency = resolveConcurrency(opts.concurrency);
+  if (files.length < opts.minParallelFiles || concurrency <= 1) {
+    for (const [file, relativePath] of files) {
+      if (signal?.aborted) break;
+      handle(measureFile(file, relativePath, workerOptions));
+    }
+  } else {
+    const workers = Array.from({ length: concurrency }, () =>
+      createWorker(MEASURE_WORKER_SOURCE, { workerData: { modulePath: __filename, options: workerOptions } })
+    );
+    let next = 0;
+    try {
+      await Promise.all(
+        workers.map(async (worker) => {
+          while (next < files.length && !signal?.aborted) {
+            const batch = files.slice(next, next + opts.batchSize);
+            next += batch.length;
+            const results = await new Promise((resolve, reject) => {
+              worker.once('message', resolve);
+              worker.once('error', reject);
+              worker.postMessage(batch);
+            });
+            worker.removeAllListeners('error');
+            results.forEach(handle);
+          }
+        })
+      );
+    } finally {
+      await Promise.all(workers.map((worker) => worker.terminate()));
+    }
+  }
+
+  const languages = [...byLanguage.values()]
+    .map((entry) => ({ ...entry, share: totals.bytes > 0 ? entry.bytes / totals.bytes : 0 }))
+    .sort((a, b) => b.bytes - a.bytes || (a.language < b.language ? -1 : 1));
+  return { languages, totals, skipped, errors, cancelled: Boolean(signal?.aborted) };
+}
+
+module.exports = instrument('CODE', {
+  detectLanguagesInDir,
+  measureFile,
+  VENDORED_DIRECTORIES,
+  DEFAULT_OPTIONS,
+});
@@ -0,0 +1,251 @@
+/**
+ * Logger
+ * Leveled, per-module logging for the capture and analysis hot paths (diff,
+ * hashing, search, file reading), kept in an in-memory ring buffer so slow
+ * or failing calls can be inspected at runtime instead of by adding
+ * console.log and restarting.
+ *
+ * Levels follow tracing's: error < warn < info < debug < trace. Each module
+ * (the same tag used in console output, e.g. DIFF) can have its own level;
+ * COMPANION_LOG uses the RUST_LOG syntax, e.g. "info,DIFF=trace,SEARCH=debug".
+ *
+ * instrument() wraps a module's exported functions in timing spans, recorded
+ * at trace level (debug once they exceed slowSpanMs), and always feeds the
+ * call counts and latency histograms in call-metrics. Worker threads
+ * load their own copy of this module, so work done inside workers shows up
+ * as the span of the call that dispatched it.
+ */
+
+const { recordCall } = require('./call-metrics');
+
+const LEVELS = { off: -1, error: 0, warn: 1, info: 2, debug: 3, trace: 4 };
+
+const DEFAULT_OPTIONS = {
+  level: 'info', // Default level for modules without their own
+  modules: {}, // { DIFF: 'trace', ... }
+  bufferSize: 2000, // Entries kept in the ring buffer
+  consoleLevel: 'warn', // Entries at or above this level are also written to the console
+  slowSpanMs: 100,
+};
+
+let options = { ...DEFAULT_OPTIONS };
+let buffer = new Array(options.bufferSize);
+let written = 0; // Total entries ever written; also the next entry's seq
+const subscribers = new Set();
+const moduleLevels = new Map();
+
+function parseFilter(filter) {
+  const parsed = { level: null, modules: {} };
+  for (const part of String(filter || '').split(',')) {
+    const item = part.trim();
+    if (!item) continue;
+    const [name, level] = item.includes('=') ? item.split('=') : [null, item];
+    const normalized = level.trim().toLowerCase();
+    if (!(normalized in LEVELS)) continue;
+    if (name) parsed.modules[name.trim().toUpperCase()] = normalized;
+    else parsed.level = normalized;
+  }
+  return parsed;
+}
+
+/**
+ * Configure levels and the ring buffer
+ * @param {object|string} config - Options (see DEFAULT_OPTIONS) or a COMPANION_LOG-style filter string
+ * @returns {object} The effective options
+ */
+function configureLogging(config = {}) {
+  const next = typeof config === 'string' ? parseFilter(config) : config;
+  options = {
+    ...options,
+    ...next,
+    level: next.level || options.level,
+    modules: { ...options.modules, ...(next.modules || {}) },
+  };
+  moduleLevels.clear();
+  for (const [name, level] of Object.entries(options.modules)) {
+    moduleLevels.set(name, LEVELS[level] ?? LEVELS.info);
+  }
+
+  if (buffer.length !== options.bufferSize) {
+    const kept = getLogs({ limit: options.bufferSize });
+    buffer = new Array(options.bufferSize);
+    for (const entry of kept) buffer[entry.seq % buffer.length] = entry;
+  }
+  return { ...options, modules: { ...options.modules } };
+}
+
+function levelOf(module) {
+  return moduleLevels.get(module) ?? LEVELS[options.level];
+}
+
+function isEnabled(module, level) {
+  return LEVELS[level] <= levelOf(module);
+}
+
+function write(module, level, message, fields) {
+  const entry = {
+    seq: written,
+    timestamp: Date.now(),
+    level,
+    module,
+    message,
+    ...(fields ? { fields } : {}),
+  };
+  buffer[written % buffer.length] = entry;
+  written++;
+
+  if (LEVELS[level] <= LEVELS[options.consoleLevel]) {
+    const method = level === 'error' ? 'error' : level === 'warn' ? 'warn' : 'log';
+    if (fields) console[method](`[${module}] ${message}`, fields);
+    else console[method](`[${module}] ${message}`);
+  }
+  for (const subscriber of subscribers) {
+    try {
+      subscriber(entry);
+    } catch {
+      // A failing subscriber must not break the code that logged
+    }
+  }
+  return entry;
+}
+
+/**
+ * Read entries from the ring buffer, oldest first
+ * @param {object} filter - { since (exclusive seq), level (max verbosity), module, limit }
+ * @returns {Array<object>} [{ seq, timestamp, level, module, message, fields? }]
+ */
+function getLogs(filter = {}) {
+  const { since = -1, level = null, module = null, limit = Infinity } = filter;
+  const maxLevel = level ? LEVELS[level] : Infinity;
+  const first = Math.max(0, written - buffer.length, since + 1);
+  const entries = [];
+  for (let seq = first; seq < written; seq++) {
+    const entry = buffer[seq % buffer.length];
+    if (!entry || (module && entry.module !== module) || LEVELS[entry.level] > maxLevel) continue;
+    entries.push(entry);
+  }
+  return entries.length > limit ? entries.slice(entries.length - limit) : entries;
+}
+
+function clearLogs() {
+  buffer = new Array(options.bufferSize);
+  written = 0;
+}
+
+/**
+ * Receive every recorded entry as it's written
+ * @returns {Function} Unsubscribe
+ */
+function onLog(callback) {
+  subscribers.add(callback);
+  return () => subscribers.delete(callback);
+}
+
+// ---------------------------------------------------------------------------
+// Loggers and spans
+// ---------------------------------------------------------------------------
+
+/**
+ * Time a unit of work; end() records it with its duration
+ */
+function startSpan(module, name, fields = null) {
+  if (!isEnabled(module, 'debug')) return { end: () => null };
+  const started = process.hrtime.bigint();
+  return {
+    end: (extra = null) =>
+      logSpan(module, name, Number(process.hrtime.bigint() - started) / 1e6, { ...fields, ...extra }),
+  };
+}
+
+function logSpan(module, name, durationMs, fields) {
+  const level = durationMs >= options.slowSpanMs ? 'debug' : 'trace';
+  if (!isEnabled(module, level)) return null;
+  const message = `${name} took ${durationMs.toFixed(2)}ms`;
+  return write(module, level, message, { span: name, durationMs, ...fields });
+}
+
+/**
+ * Create a logger for one module
+ * @param {string} module - Console tag, e.g. 'DIFF'
+ */
+function createLogger(module) {
+  const log = (level) => (message, fields = null) =>
+    isEnabled(module, level) ? write(module, level, message, fields) : null;
+  return {
+    module,
+    error: log('error'),
+    warn: log('warn'),
+    info: log('info'),
+    debug: log('debug'),
+    trace: log('trace'),
+    enabled: (level) => isEnabled(module, level),
+    span: (name, fields) => startSpan(module, name, fields),
+  };
+}
+
+function isPlainFunction(value) {
+  if (typeof value !== 'function') return false;
+  const kind = value.constructor?.name;
+  if (kind === 'GeneratorFunction' || kind === 'AsyncGeneratorFunction') return false;
+  return !Function.prototype.toString.call(value).startsWith('class');
+}
+
+/**
+ * Wrap every exported function in a timing span and call metrics
+ * Classes and generators are left alone; async functions are timed until they settle.
+ * @param {string} module - Module tag
+ * @param {object} exported - The module's exports object
+ * @returns {object} A copy with instrumented functions
+ */
+function instrument(module, exported) {
+  const wrapped = {};
+  for (const [name, value] of Object.entries(exported)) {
+    if (!isPlainFunction(value)) {
+      wrapped[name] = value;
+      continue;
+    }
+    wrapped[name] = function instrumented(...args) {
+      const started = process.hrtime.bigint();
+      const finish = (error) => {
+        const durationMs = Number(process.hrtime.bigint() - started) / 1e6;
+        recordCall(module, name, durationMs, Boolean(error));
+        if (isEnabled(module, 'debug')) {
+          logSpan(module, name, durationMs, error ? { error: error.message } : null);
+        }
+      };
+      let result;
+      try {
+        result = value.apply(this, args);
+      } catch (error) {
+        finish(error);
+        throw error;
+      }
+      if (result && typeof result.then === 'function') {
+        result.then(
+          () => finish(null),
+          (error) => finish(error)
+        );
+      } else {
+        finish(null);
+      }
+      return result;
+    };
+    Object.defineProperty(wrapped[name], 'name', { value: name });
+  }
+  return wrapped;
+}
+
+if (process.env.COMPANION_LOG) configureLogging(process.env.COMPANION_LOG);
+
+module.exports = {
+  configureLogging,
+  createLogger,
+  instrument,
+  startSpan,
+  getLogs,
+  clearLogs,
+  onLog,
+  isEnabled,
+  LEVELS,
+  DEFAULT_OPTIONS,
+};
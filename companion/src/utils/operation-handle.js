@@ -0,0 +1,192 @@
+/**
+ * Operation Handles
+ * Common shape for long-running work (batch diffs, directory search, dataset
+ * export): the work is started with startOperation and the caller gets an
+ * OperationHandle back straight away, which
+ *   - emits 'progress' ({ completed, total, ...detail }), throttled
+ *   - exposes cancel(reason), wired to an AbortSignal the work polls
+ *   - is awaitable (then/catch/finally), resolving with the work's result
+ *
+ * Work that notices cancellation can return partial results; the handle then
+ * resolves with them and reports status 'cancelled'. Work that throws after
+ * cancellation rejects with a CANCELLED CompanionError instead.
+ *
+ * Running operations are registered by id so routes can list and cancel them.
+ */
+
+const EventEmitter = require('events');
+const { CompanionError, ERROR_CODES } = require('./errors');
+
+const DEFAULT_OPTIONS = {
+  progressIntervalMs: 100, // Minimum gap between 'progress' events (the final update is always sent)
+  keepFinishedMs: 5 * 60 * 1000, // How long finished operations stay listed
+};
+
+const operations = new Map();
+let nextId = 1;
+
+class OperationCancelledError extends CompanionError {
+  constructor(reason = 'Operation cancelled') {
+    super(ERROR_CODES.CANCELLED, typeof reason === 'string' ? reason : 'Operation cancelled');
+    this.name = 'OperationCancelledError';
+  }
+}
+
+/**
+ * Throw if a signal has been aborted; for polling between units of work
+ */
+function throwIfCancelled(signal) {
+  if (signal?.aborted) throw new OperationCancelledError(signal.reason);
+}
+
+class OperationHandle extends EventEmitter {
+  constructor(name, options = {}) {
+    super();
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.id = `op-${Date.now().toString(36)}-${nextId++}`;
+    this.name = name;
+    this.status = 'running';
+    this.progress = { completed: 0, total: null };
+    this.startedAt = Date.now();
+    this.finishedAt = null;
+    this.error = null;
+    this.controller = new AbortController();
+    this.signal = this.controller.signal;
+    this.lastProgressAt = 0;
+    this.progressTimer = null;
+
+    // Chain an outer signal (e.g. a request's) into this operation
+    if (options.signal) {
+      if (options.signal.aborted) this.cancel(options.signal.reason);
+      else options.signal.addEventListener('abort', () => this.cancel(options.signal.reason), { once: true });
+    }
+    if (options.onProgress) this.on('progress', options.onProgress);
+  }
+
+  /**
+   * Record progress; emitted at most every progressIntervalMs
+   * @param {object} update - { completed, total, ...detail }
+   */
+  report(update) {
+    if (this.status !== 'running') return;
+    this.progress = { ...this.progress, ...update };
+    const wait = this.lastProgressAt + this.options.progressIntervalMs - Date.now();
+    if (wait <= 0) {
+      this.emitProgress();
+    } else if (!this.progressTimer) {
+      this.progressTimer = setTimeout(() => this.emitProgress(), wait);
+    }
+  }
+
+  emitProgress() {
+    clearTimeout(this.progressTimer);
+    this.progressTimer = null;
+    this.lastProgressAt = Date.now();
+    this.emit('progress', { id: this.id, ...this.progress });
+  }
+
+  /**
+   * Ask the operation to stop; it finishes as soon as the work next checks the signal
+   */
+  cancel(reason = 'Operation cancelled') {
+    if (this.status !== 'running' || this.signal.aborted) return false;
+    this.controller.abort(reason);
+    this.emit('cancelling', { id: this.id, reason });
+    return true;
+  }
+
+  get cancelled() {
+    return this.signal.aborted;
+  }
+
+  finish(status, error = null) {
+    if (this.progressTimer) this.emitProgress();
+    this.status = status;
+    this.error = error;
+    this.finishedAt = Date.now();
+    this.emit('done', this.toJSON());
+    setTimeout(() => operations.delete(this.id), this.options.keepFinishedMs).unref();
+  }
+
+  then(onFulfilled, onRejected) {
+    return this.promise.then(onFulfilled, onRejected);
+  }
+
+  catch(onRejected) {
+    return this.promise.catch(onRejected);
+  }
+
+  finally(onFinally) {
+    return this.promise.finally(onFinally);
+  }
+
+  toJSON() {
+    return {
+      id: this.id,
+      name: this.name,
+      status: this.status,
+      progress: this.progress,
+      startedAt: this.startedAt,
+      finishedAt: this.finishedAt,
+      durationMs: (this.finishedAt ?? Date.now()) - this.startedAt,
+      error: this.error ? this.error.message : null,
+    };
+  }
+}
+
+/**
+ * Start long-running work and return its handle
+ * @param {string} name - Label shown when listing operations
+ * @param {Function} work - async (handle) => result; poll handle.signal, call handle.report()
+ * @param {object} options - See DEFAULT_OPTIONS, plus { signal, onProgress }
+ * @returns {OperationHandle}
+ */
+function startOperation(name, work, options = {}) {
+  const handle = new OperationHandle(name, options);
+  operations.set(handle.id, handle);
+
+  handle.promise = (async () => {
+    try {
+      // Let the caller attach listeners before the work starts reporting
+      await Promise.resolve();
+      const result = await work(handle);
+      handle.finish(handle.cancelled ? 'cancelled' : 'completed');
+      return result;
+    } catch (error) {
+      if (handle.cancelled) {
+        const cancelled = error instanceof OperationCancelledError ? error : new OperationCancelledError();
+        handle.finish('cancelled', cancelled);
+        throw cancelled;
+      }
+      handle.finish('failed', error);
+      throw error;
+    }
+  })();
+  // Unawaited handles shouldn't crash the process on failure or cancellation
+  handle.promise.catch(() => {});
+  return handle;
+}
+
+function getOperation(id) {
+  return operations.get(id) || null;
+}
+
+function listOperations() {
+  return Array.from(operations.values(), (handle) => handle.toJSON());
+}
+
+function cancelOperation(id, reason) {
+  const handle = operations.get(id);
+  return handle ? handle.cancel(reason) : false;
+}
+
+module.exports = {
+  OperationHandle,
+  OperationCancelledError,
+  startOperation,
+  throwIfCancelled,
+  getOperation,
+  listOperations,
+  cancelOperation,
+  DEFAULT_OPTIONS,
+};
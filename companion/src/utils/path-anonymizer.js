@@ -0,0 +1,207 @@
+/**
+ * Path Anonymizer
+ * Salted pseudonyms for file paths, and a fingerprint that identifies a
+ * project without naming it.
+ *
+ * Paths are anonymized per component: every directory and file name is
+ * replaced with its keyed hash (see pseudonymize), so the same name maps to
+ * the same pseudonym wherever it appears and under every parent. Depth,
+ * separators, the root (/, C:\, ~) and file extensions are kept, so analyses
+ * of layout ("edits per directory", "test vs. source files") still work on
+ * anonymized traces. Conventional names that say nothing about the project
+ * (src, tests, node_modules, package.json, ...) are kept by default.
+ *
+ * A project fingerprint is the hash of something every clone shares: the
+ * repository's root commit, else its normalized remote URL, else the package
+ * name from its manifest. Two machines with the same checkout get the same
+ * fingerprint; the name never leaves the machine. Without a salt, anyone who
+ * can guess the project (e.g. a public repository) can recompute the
+ * fingerprint, so pass the study's shared salt.
+ */
+
+const fs = require('fs');
+const path = require('path');
+const crypto = require('crypto');
+const { execFile } = require('child_process');
+const { promisify } = require('util');
+const { pseudonymize } = require('./pseudonymize');
+
+const execFileAsync = promisify(execFile);
+
+const DEFAULT_OPTIONS = {
+  length: 10, // Characters per hashed component
+  keepCommon: true, // Keep COMMON_NAMES as is
+  keep: [], // Further names to keep
+};
+
+// Directory and file names shared by most projects
+const COMMON_NAMES = new Set(
+  (
+    'src lib libs app apps bin cmd pkg internal include test tests __tests__ spec specs docs doc ' +
+    'examples scripts tools config configs public static assets build dist out target vendor ' +
+    'node_modules packages components utils util helpers services models views controllers routes ' +
+    'migrations fixtures .git .github .vscode .idea Users home ' +
+    'package.json package-lock.json tsconfig.json Cargo.toml Cargo.lock go.mod go.sum pyproject.toml ' +
+    'setup.py setup.cfg requirements.txt Gemfile Makefile Dockerfile README.md LICENSE CHANGELOG.md ' +
+    '.gitignore .editorconfig .env.example index.js index.ts main.go main.rs lib.rs mod.rs __init__.py'
+  ).split(' ')
+);
+
+// Second extensions that describe the file's role, not its name
+const ROLE_EXTENSIONS = new Set(['test', 'spec', 'd', 'min', 'stories', 'module', 'config', 'tar']);
+
+/**
+ * Split a file name into the part to hash and the extension to keep
+ */
+function splitName(name) {
+  const dot = name.lastIndexOf('.');
+  if (dot <= 0) return { stem: name, extension: '' };
+  let stem = name.slice(0, dot);
+  let extension = name.slice(dot);
+  const inner = stem.lastIndexOf('.');
+  if (inner > 0 && ROLE_EXTENSIONS.has(stem.slice(inner + 1).toLowerCase())) {
+    extension = stem.slice(inner) + extension;
+    stem = stem.slice(0, inner);
+  }
+  return { stem, extension };
+}
+
+function anonymizePath(filePath, salt, opts, keep) {
+  if (typeof filePath !== 'string' || !filePath) return filePath;
+  // Root: drive letter, UNC prefix, home, or leading separator
+  const root = /^(?:[A-Za-z]:[\\/]|\\\\[^\\/]+[\\/]|~[\\/]?|[\\/])/.exec(filePath)?.[0] || '';
+  const parts = filePath.slice(root.length).split(/([\\/]+)/);
+  return (
+    root +
+    parts
+      .map((part, index) => {
+        // Odd indexes are separators; '.', '..' and kept names pass through
+        if (index % 2 === 1 || !part || part === '.' || part === '..' || keep.has(part)) return part;
+        const { stem, extension } = splitName(part);
+        const key = salt ?? undefined;
+        return pseudonymize(stem, 'path', { key, prefix: '', length: opts.length }) + extension;
+      })
+      .join('')
+  );
+}
+
+/**
+ * Anonymize paths component by component
+ * @param {string|Array<string>} paths - One path or many (POSIX or Windows separators)
+ * @param {string|Buffer} salt - Key for the hashes (default: the pseudonymization dataset key)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {string|Array<string>} Same shape as the input
+ */
+function anonymizePaths(paths, salt = null, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const keep = new Set([...(opts.keepCommon ? COMMON_NAMES : []), ...opts.keep]);
+  if (!Array.isArray(paths)) return anonymizePath(paths, salt, opts, keep);
+  return paths.map((filePath) => anonymizePath(filePath, salt, opts, keep));
+}
+
+// ---------------------------------------------------------------------------
+// Project fingerprint
+// ---------------------------------------------------------------------------
+
+async function git(root, args) {
+  try {
+    const { stdout } = await execFileAsync('git', args, { cwd: root, timeout: 5000 });
+    return stdout.trim();
+  } catch {
+    return '';
+  }
+}
+
+/**
+ * host/owner/repo, whatever the protocol, credentials or .git suffix
+ */
+function normalizeRemote(url) {
+  return url
+    .trim()
+    .replace(/^[a-z+]+:\/\//i, '')
+    .replace(/^[^@/]+@/, '')
+    .replace(/^([^/:]+):(?!\d)/, '$1/')
+    .replace(/:\d+\//, '/')
+    .replace(/\.git\/?$/, '')
+    .replace(/\/+$/, '')
+    .toLowerCase();
+}
+
+function manifestName(root) {
+  const read = (name) => {
+    try {
+      return fs.readFileSync(path.join(root, name), 'utf8');
+    } catch {
+      return null;
+    }
+  };
+  const packageJson = read('package.json');
+  if (packageJson) {
+    try {
+      const { name } = JSON.parse(packageJson);
+      if (name) return `npm:${name}`;
+    } catch {
+      // Not JSON; try the next manifest
+    }
+  }
+  const patterns = [
+    ['Cargo.toml', /^\s*name\s*=\s*"([^"]+)"/m, 'cargo'],
+    ['pyproject.toml', /^\s*name\s*=\s*["']([^"']+)["']/m, 'python'],
+    ['go.mod', /^module\s+(\S+)/m, 'go'],
+    ['setup.cfg', /^\s*name\s*=\s*(\S+)/m, 'python'],
+  ];
+  for (const [file, pattern, ecosystem] of patterns) {
+    const match = pattern.exec(read(file) || '');
+    if (match) return `${ecosystem}:${match[1]}`;
+  }
+  return null;
+}
+
+/**
+ * Identify a project across machines without revealing it
+ * @param {string} root - Project directory
+ * @param {object} options - { salt, length }
+ * @returns {Promise<object>} { fingerprint, source: 'git-root-commit'|'git-remote'|'manifest'|'directory' }
+ *   'directory' (the folder name) is the last resort, and only stable where the folder name is
+ */
+async function projectFingerprint(root, options = {}) {
+  const length = options.length || 16;
+  const top = (await git(root, ['rev-parse', '--show-toplevel'])) || root;
+
+  let identity = null;
+  let source = null;
+  // Oldest root commit: unchanged by later history, shared by every clone and fork
+  const roots = (await git(top, ['rev-list', '--max-parents=0', 'HEAD'])).split('\n').filter(Boolean);
+  if (roots.length) {
+    identity = roots.sort()[0];
+    source = 'git-root-commit';
+  }
+  if (!identity) {
+    const remote = await git(top, ['config', '--get', 'remote.origin.url']);
+    if (remote) {
+      identity = normalizeRemote(remote);
+      source = 'git-remote';
+    }
+  }
+  if (!identity) {
+    identity = manifestName(top);
+    if (identity) source = 'manifest';
+  }
+  if (!identity) {
+    identity = path.basename(path.resolve(top));
+    source = 'directory';
+  }
+
+  const material = `project\u0000${identity}`;
+  const digest = options.salt
+    ? crypto.createHmac('sha256', options.salt).update(material).digest('hex')
+    : crypto.createHash('sha256').update(material).digest('hex');
+  return { fingerprint: digest.slice(0, length), source };
+}
+
+module.exports = {
+  anonymizePaths,
+  projectFingerprint,
+  normalizeRemote,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,428 @@
+/**
+ * Pattern Search
+ * Counts and locates patterns in file contents.
+ *
+ * searchPatterns() runs each pattern as its own regex, compiled once per
+ * process through a shared cache; patterns that don't compile are reported
+ * with the reason instead of being dropped. For literal patterns
+ * searchLiterals() builds one Aho-Corasick automaton and finds every pattern
+ * in a single pass over the text, which stays fast with hundreds of patterns;
+ * searchLiteralsInFiles() spreads files over worker threads, each holding its
+ * own copy of the automaton.
+ *
+ * Offsets are JS string (UTF-16) offsets, end exclusive.
+ */
+
+const fs = require('fs');
+const { createWorker, resolveConcurrency } = require('./thread-pool');
+const { instrument } = require('./logger');
+
+const DEFAULT_OPTIONS = {
+  caseInsensitive: false,
+  overlapping: true, // false: leftmost-longest, non-overlapping (grep semantics)
+  wholeWord: false, // Only matches not touching a word character on either side
+  maxMatches: Infinity, // Stop recording positions after this many (counts stay exact)
+};
+
+const WORD_CHAR = /[\w$]/;
+const REGEX_CACHE_SIZE = 1024;
+
+// Process-wide compiled regexes keyed by flags + source; failures are cached too so a bad
+// pattern isn't recompiled on every call. Map order doubles as LRU order.
+const regexCache = new Map();
+const regexCacheStats = { hits: 0, misses: 0, evictions: 0 };
+
+function foldChar(char) {
+  const lower = char.toLowerCase();
+  return lower.length === 1 ? lower : char;
+}
+
+/**
+ * Compile a pattern through the shared cache
+ * @param {string} pattern - Regex source
+ * @param {string} flags - Regex flags
+ * @returns {object} { regex } or { error: { code, message, reason } }
+ */
+function compilePattern(pattern, flags = 'g') {
+  if (typeof pattern !== 'string') {
+    return { error: { code: 'INVALID_TYPE', message: `Pattern must be a string, got ${typeof pattern}` } };
+  }
+  const key = `${flags}\u0000${pattern}`;
+  const cached = regexCache.get(key);
+  if (cached) {
+    regexCacheStats.hits++;
+    regexCache.delete(key);
+    regexCache.set(key, cached);
+    return cached;
+  }
+
+  regexCacheStats.misses++;
+  let compiled;
+  try {
+    compiled = { regex: new RegExp(pattern, flags) };
+  } catch (error) {
+    // "Invalid regular expression: /(/: Unterminated group" -> reason "Unterminated group"
+    const reason = error.message.split(': ').pop();
+    const code = /flag/i.test(reason) ? 'INVALID_FLAGS' : 'INVALID_REGEX';
+    compiled = { error: { code, message: error.message, reason } };
+  }
+  regexCache.set(key, compiled);
+  if (regexCache.size > REGEX_CACHE_SIZE) {
+    regexCache.delete(regexCache.keys().next().value);
+    regexCacheStats.evictions++;
+  }
+  return compiled;
+}
+
+function getRegexCacheStats() {
+  return { ...regexCacheStats, size: regexCache.size, capacity: REGEX_CACHE_SIZE };
+}
+
+function clearRegexCache() {
+  regexCache.clear();
+  regexCacheStats.hits = 0;
+  regexCacheStats.misses = 0;
+  regexCacheStats.evictions = 0;
+}
+
+/**
+ * Line starts (string offsets and UTF-8 byte offsets) for locating matches
+ */
+class LineIndex {
+  constructor(text) {
+    this.text = text;
+    this.starts = [0];
+    for (let i = text.indexOf('\n'); i !== -1; i = text.indexOf('\n', i + 1)) this.starts.push(i + 1);
+    this.byteStarts = null;
+  }
+
+  lineOf(offset) {
+    let lo = 0;
+    let hi = this.starts.length - 1;
+    while (lo < hi) {
+      const mid = (lo + hi + 1) >> 1;
+      if (this.starts[mid] <= offset) lo = mid;
+      else hi = mid - 1;
+    }
+    return lo;
+  }
+
+  byteOffset(offset) {
+    if (!this.byteStarts) {
+      this.byteStarts = new Array(this.starts.length);
+      let bytes = 0;
+      this.starts.forEach((start, i) => {
+        if (i > 0) bytes += Buffer.byteLength(this.text.slice(this.starts[i - 1], start), 'utf8');
+        this.byteStarts[i] = bytes;
+      });
+    }
+    const line = this.lineOf(offset);
+    return this.byteStarts[line] + Buffer.byteLength(this.text.slice(this.starts[line], offset), 'utf8');
+  }
+
+  lineText(line) {
+    const end = line + 1 < this.starts.length ? this.starts[line + 1] - 1 : this.text.length;
+    return this.text.slice(this.starts[line], end).replace(/\r$/, '');
+  }
+
+  lines(from, to) {
+    const result = [];
+    for (let line = Math.max(0, from); line <= Math.min(to, this.starts.length - 1); line++) {
+      result.push(this.lineText(line));
+    }
+    return result;
+  }
+}
+
+/**
+ * Structured form of a match: offsets, 1-based line/column, the matched line and context lines
+ */
+function describeMatch(lines, start, end, context) {
+  const line = lines.lineOf(start);
+  const endLine = lines.lineOf(Math.max(start, end - 1));
+  return {
+    start,
+    end,
+    byteStart: lines.byteOffset(start),
+    byteEnd: lines.byteOffset(end),
+    line: line + 1,
+    column: start - lines.starts[line] + 1,
+    endLine: endLine + 1,
+    text: lines.text.slice(start, end),
+    lineText: lines.lineText(line),
+    before: context > 0 ? lines.lines(line - context, line - 1) : [],
+    after: context > 0 ? lines.lines(endLine + 1, endLine + context) : [],
+  };
+}
+
+/**
+ * Count matches of each pattern, one regex at a time
+ * @param {string} content - Text to search
+ * @param {Array<string>} patterns - Regex sources
+ * @param {object} options - { flags, positions, context, maxMatches }
+ *   positions: also return each match with byte offsets, line/column and `context` surrounding lines
+ * @returns {Array<object>} One entry per pattern, in order: { pattern, count, matches?, truncated? },
+ *   or { pattern, count: 0, error: { code, message, reason } } when the pattern doesn't compile;
+ *   matches: [{ start, end, byteStart, byteEnd, line, column, endLine, text, lineText, before, after }]
+ */
+function searchPatterns(content, patterns, options = {}) {
+  const { positions = false, context = 0, maxMatches = Infinity } = options;
+  const text = String(content ?? '');
+  const flags = `g${(options.flags || '').replace(/g/g, '')}`;
+  const lines = positions ? new LineIndex(text) : null;
+  const results = [];
+  for (const pattern of patterns || []) {
+    const { regex, error } = compilePattern(pattern, flags);
+    if (error) {
+      results.push({ pattern, count: 0, error });
+      continue;
+    }
+    let count = 0;
+    const matches = [];
+    for (const match of text.matchAll(regex)) {
+      if (match[0] === '' && match.index >= text.length) break;
+      count++;
+      if (positions && matches.length < maxMatches) {
+        matches.push(describeMatch(lines, match.index, match.index + match[0].length, context));
+      }
+    }
+    if (positions) results.push({ pattern, count, matches, truncated: matches.length < count });
+    else results.push({ pattern, count });
+  }
+  return results;
+}
+
+/**
+ * Aho-Corasick automaton over a fixed set of literal patterns
+ */
+class LiteralMatcher {
+  /**
+   * @param {Array<string>} patterns - Literal strings (empty ones never match)
+   * @param {object} options - { caseInsensitive }
+   */
+  constructor(patterns, options = {}) {
+    this.patterns = (patterns || []).map((pattern) => String(pattern));
+    this.caseInsensitive = Boolean(options.caseInsensitive);
+    this.next = [new Map()]; // state -> Map(char -> state)
+    this.depth = [0];
+    this.output = [null]; // state -> pattern indices ending here
+    this.outputLink = [0]; // nearest suffix state with output (0 = none)
+
+    this.patterns.forEach((pattern, index) => {
+      if (pattern.length === 0) return;
+      let state = 0;
+      for (const char of this.fold(pattern)) {
+        let target = this.next[state].get(char);
+        if (target === undefined) {
+          target = this.next.length;
+          this.next.push(new Map());
+          this.depth.push(this.depth[state] + 1);
+          this.output.push(null);
+          this.outputLink.push(0);
+          this.next[state].set(char, target);
+        }
+        state = target;
+      }
+      (this.output[state] ||= []).push(index);
+    });
+
+    // Breadth-first failure links
+    this.fail = new Int32Array(this.next.length);
+    const queue = [];
+    for (const state of this.next[0].values()) queue.push(state);
+    for (let head = 0; head < queue.length; head++) {
+      const state = queue[head];
+      for (const [char, target] of this.next[state]) {
+        let fallback = this.fail[state];
+        while (fallback !== 0 && !this.next[fallback].has(char)) fallback = this.fail[fallback];
+        const candidate = this.next[fallback].get(char);
+        this.fail[target] = candidate !== undefined && candidate !== target ? candidate : 0;
+        const suffix = this.fail[target];
+        this.outputLink[target] = this.output[suffix] ? suffix : this.outputLink[suffix];
+        queue.push(target);
+      }
+    }
+  }
+
+  fold(text) {
+    if (!this.caseInsensitive) return text;
+    let folded = '';
+    for (let i = 0; i < text.length; i++) folded += foldChar(text[i]);
+    return folded;
+  }
+
+  /**
+   * Scan text, calling onMatch(patternIndex, start, end) for every (overlapping) occurrence
+   */
+  scan(content, onMatch) {
+    const text = this.fold(String(content ?? ''));
+    let state = 0;
+    for (let i = 0; i < text.length; i++) {
+      const char = text[i];
+      let target = this.next[state].get(char);
+      while (target === undefined && state !== 0) {
+        state = this.fail[state];
+        target = this.next[state].get(char);
+      }
+      state = target ?? 0;
+      let out = this.output[state] ? state : this.outputLink[state];
+      while (out !== 0) {
+        for (const index of this.output[out]) onMatch(index, i + 1 - this.depth[out], i + 1);
+        out = this.outputLink[out];
+      }
+    }
+  }
+}
+
+function toMatcher(patterns, opts) {
+  if (patterns instanceof LiteralMatcher) return patterns;
+  return new LiteralMatcher(patterns, { caseInsensitive: opts.caseInsensitive });
+}
+
+function touchesWord(text, start, end) {
+  return (start > 0 && WORD_CHAR.test(text[start - 1])) || (end < text.length && WORD_CHAR.test(text[end]));
+}
+
+/**
+ * Find every literal pattern in one pass
+ * @param {string} content - Text to search
+ * @param {Array<string>|LiteralMatcher} patterns - Literals, or a prebuilt matcher reused across calls
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {object} { counts: [{ pattern, count }], matches: [{ pattern, patternIndex, start, end }],
+ *   truncated }
+ */
+function searchLiterals(content, patterns, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const matcher = toMatcher(patterns, opts);
+  const text = String(content ?? '');
+
+  let found = [];
+  matcher.scan(text, (patternIndex, start, end) => {
+    if (opts.wholeWord && touchesWord(text, start, end)) return;
+    found.push({ patternIndex, start, end });
+  });
+
+  if (!opts.overlapping) {
+    found.sort((a, b) => a.start - b.start || b.end - a.end || a.patternIndex - b.patternIndex);
+    const kept = [];
+    let reached = 0;
+    for (const match of found) {
+      if (match.start < reached) continue;
+      kept.push(match);
+      reached = match.end;
+    }
+    found = kept;
+  } else {
+    found.sort((a, b) => a.start - b.start || a.patternIndex - b.patternIndex);
+  }
+
+  const counts = matcher.patterns.map((pattern) => ({ pattern, count: 0 }));
+  for (const match of found) counts[match.patternIndex].count++;
+  const recorded = found.length > opts.maxMatches ? found.slice(0, opts.maxMatches) : found;
+
+  return {
+    counts,
+    matches: recorded.map(({ patternIndex, start, end }) => ({
+      pattern: matcher.patterns[patternIndex],
+      patternIndex,
+      start,
+      end,
+    })),
+    truncated: recorded.length < found.length,
+  };
+}
+
+function searchFile(filePath, matcher, opts) {
+  try {
+    const stat = fs.statSync(filePath);
+    if (stat.size > opts.maxFileBytes) return { file: filePath, skipped: 'too-large', size: stat.size };
+    return { file: filePath, ...searchLiterals(fs.readFileSync(filePath, 'utf8'), matcher, opts) };
+  } catch (error) {
+    return { file: filePath, error: error.message };
+  }
+}
+
+const FILES_WORKER_SOURCE = `
+const { parentPort, workerData } = require('worker_threads');
+const { LiteralMatcher, searchLiteralsInFiles } = require(workerData.modulePath);
+const matcher = new LiteralMatcher(workerData.patterns, workerData.options);
+parentPort.on('message', async (files) => {
+  const results = await searchLiteralsInFiles(files, matcher, { ...workerData.options, concurrency: 1 });
+  parentPort.postMessage(results);
+});
+`;
+
+/**
+ * Search many files for the same literals, in parallel across worker threads
+ * @param {Array<string>} files - File paths
+ * @param {Array<string>|LiteralMatcher} patterns - Literals
+ * @param {object} options - DEFAULT_OPTIONS plus { concurrency, minParallelFiles, batchSize, maxFileBytes }
+ * @returns {Promise<Array<object>>} In input order: [{ file, counts, matches, truncated }],
+ *   or { file, error } / { file, skipped } for unreadable and oversized files
+ */
+async function searchLiteralsInFiles(files, patterns, options = {}) {
+  const opts = {
+    ...DEFAULT_OPTIONS,
+    concurrency: null,
+    minParallelFiles: 64,
+    batchSize: 32,
+    maxFileBytes: 16 * 1024 * 1024,
+    ...options,
+  };
+  const list = files || [];
+  const matcher = toMatcher(patterns, opts);
+  const concurrency = resolveConcurrency(opts.concurrency);
+
+  if (concurrency <= 1 || list.length < opts.minParallelFiles) {
+    return list.map((file) => searchFile(file, matcher, opts));
+  }
+
+  const results = new Array(list.length);
+  const batches = [];
+  for (let i = 0; i < list.length; i += opts.batchSize) batches.push(i);
+  const workerOptions = { ...opts };
+  delete workerOptions.concurrency;
+  const workers = Array.from(
+    { length: Math.min(concurrency, batches.length) },
+    () =>
+      createWorker(FILES_WORKER_SOURCE, {
+        workerData: { modulePath: __filename, patterns: matcher.patterns, options: workerOptions },
+      })
+  );
+
+  let next = 0;
+  try {
+    await Promise.all(
+      workers.map(async (worker) => {
+        while (next < batches.length) {
+          const start = batches[next++];
+          const batch = await new Promise((resolve, reject) => {
+            worker.once('message', resolve);
+            worker.once('error', reject);
+            worker.postMessage(list.slice(start, start + opts.batchSize));
+          });
+          worker.removeAllListeners('error');
+          batch.forEach((result, i) => {
+            results[start + i] = result;
+          });
+        }
+      })
+    );
+  } finally {
+    await Promise.all(workers.map((worker) => worker.terminate()));
+  }
+  return results;
+}
+
+module.exports = instrument('SEARCH', {
+  searchPatterns,
+  compilePattern,
+  getRegexCacheStats,
+  clearRegexCache,
+  describeMatch,
+  LineIndex,
+  searchLiterals,
+  searchLiteralsInFiles,
+  LiteralMatcher,
+  DEFAULT_OPTIONS,
+});
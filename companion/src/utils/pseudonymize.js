@@ -0,0 +1,143 @@
+/**
+ * Pseudonymization
+ * Keyed, reproducible replacement of identifying values (usernames, paths,
+ * repo names, identifiers). The same value in the same namespace always maps
+ * to the same pseudonym under one dataset key, so joins across exports still
+ * work, while the mapping cannot be rebuilt without the key.
+ *
+ * Pseudonym = PREFIX_ + base32(HMAC-SHA256(key, namespace \0 value))[:length]
+ *
+ * Redaction, the path anonymizer (and the Hugging Face exporter through it)
+ * and the code anonymizer all go through this module so they share one
+ * mapping.
+ */
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const crypto = require('crypto');
+
+const DEFAULT_KEY_PATH = path.join(os.homedir(), '.cursor-telemetry', 'pseudonym.key');
+const KEY_ENV = 'CURSOR_TELEMETRY_PSEUDONYM_KEY';
+const DEFAULT_LENGTH = 12;
+const BASE32 = 'abcdefghijklmnopqrstuvwxyz234567';
+
+let activeKey = null;
+
+function base32(buffer) {
+  let bits = 0;
+  let value = 0;
+  let out = '';
+  for (const byte of buffer) {
+    value = (value << 8) | byte;
+    bits += 8;
+    while (bits >= 5) {
+      out += BASE32[(value >>> (bits - 5)) & 31];
+      bits -= 5;
+    }
+  }
+  if (bits > 0) out += BASE32[(value << (5 - bits)) & 31];
+  return out;
+}
+
+function parseKey(key) {
+  if (Buffer.isBuffer(key)) return key;
+  const text = String(key).trim();
+  if (/^[0-9a-f]{64}$/i.test(text)) return Buffer.from(text, 'hex');
+  return Buffer.from(text, 'utf8');
+}
+
+/**
+ * Load the dataset key: explicit key, then environment, then the key file
+ * (created with a fresh random key on first use)
+ */
+function loadPseudonymKey(keyPath = DEFAULT_KEY_PATH) {
+  if (process.env[KEY_ENV]) return parseKey(process.env[KEY_ENV]);
+
+  try {
+    return parseKey(fs.readFileSync(keyPath, 'utf8'));
+  } catch (error) {
+    if (error.code !== 'ENOENT') throw error;
+  }
+
+  const key = crypto.randomBytes(32);
+  fs.mkdirSync(path.dirname(keyPath), { recursive: true });
+  fs.writeFileSync(keyPath, key.toString('hex'), { mode: 0o600, flag: 'wx' });
+  return key;
+}
+
+/**
+ * Use a specific key (e.g. one shared across a study's participants)
+ */
+function setPseudonymKey(key) {
+  activeKey = key === null ? null : parseKey(key);
+}
+
+function getKey() {
+  if (!activeKey) activeKey = loadPseudonymKey();
+  return activeKey;
+}
+
+function defaultPrefix(namespace) {
+  return String(namespace)
+    .toUpperCase()
+    .replace(/[^A-Z0-9]+/g, '_');
+}
+
+/**
+ * Map a value to its pseudonym
+ * @param {string|number} value - Value to replace
+ * @param {string} namespace - Mapping namespace ('user', 'path', 'repo', 'identifier', ...)
+ * @param {object} options - { key, length, prefix, caseInsensitive }
+ * @returns {string} Stable pseudonym, or the input unchanged for null/empty values
+ */
+function pseudonymize(value, namespace = 'default', options = {}) {
+  if (value === null || value === undefined || value === '') return value;
+
+  let normalized = String(value).normalize('NFC');
+  if (options.caseInsensitive) normalized = normalized.toLowerCase();
+
+  const key = options.key ? parseKey(options.key) : getKey();
+  const digest = crypto.createHmac('sha256', key).update(`${namespace}\0${normalized}`).digest();
+  const body = base32(digest).slice(0, options.length || DEFAULT_LENGTH);
+  const prefix = options.prefix !== undefined ? options.prefix : defaultPrefix(namespace);
+
+  return prefix ? `${prefix}_${body}` : body;
+}
+
+/**
+ * Namespace-bound pseudonymizer; optionally keeps the local reverse mapping
+ * so the key holder can look pseudonyms up during analysis
+ */
+function createPseudonymizer(namespace, options = {}) {
+  const mapping = options.keepMapping ? new Map() : null;
+
+  const fn = (value) => {
+    const pseudonym = pseudonymize(value, namespace, options);
+    if (mapping && pseudonym !== value) mapping.set(pseudonym, value);
+    return pseudonym;
+  };
+  fn.namespace = namespace;
+  fn.lookup = (pseudonym) => (mapping ? mapping.get(pseudonym) : undefined);
+  fn.mapping = () => (mapping ? Object.fromEntries(mapping) : null);
+  return fn;
+}
+
+/**
+ * Short, non-reversible fingerprint of the active key, recorded in exports so
+ * consumers can tell whether two datasets share a mapping
+ */
+function getKeyFingerprint(key = null) {
+  const material = key ? parseKey(key) : getKey();
+  return crypto.createHash('sha256').update(material).digest('hex').slice(0, 16);
+}
+
+module.exports = {
+  pseudonymize,
+  createPseudonymizer,
+  setPseudonymKey,
+  loadPseudonymKey,
+  getKeyFingerprint,
+  DEFAULT_KEY_PATH,
+  KEY_ENV,
+};
@@ -0,0 +1,208 @@
+/**
+ * Redaction
+ * Removes or pseudonymizes personal data in text before it is persisted:
+ * email addresses, IP addresses, usernames in home-directory paths, secrets
+ * (see secret-scanner) and custom patterns.
+ *
+ * Each kind of match is handled by a policy action:
+ *   remove  replaced with a fixed marker, e.g. [EMAIL]
+ *   hash    replaced with a keyed pseudonym, e.g. EMAIL_k3j2h4g5f6d7 (see
+ *           pseudonymize): the same value always gets the same pseudonym
+ *           under one key, so joins across traces still work
+ *   keep    left as is
+ * Pseudonyms are HMACs, so they can't be reversed without the key. With
+ * `reversible: true` the result also carries the pseudonym -> original
+ * mapping, for the key holder to keep (and restoreText to apply); without
+ * it, the key holder can still confirm a guess by re-hashing it.
+ * Secrets are only ever removed: hashing a short password can be undone by
+ * brute force.
+ *
+ * Policies are compiled once and cached per policy object, so calling
+ * redactText for every trace event only pays for the scan. Every pattern
+ * scans in linear time, and text past maxScanChars is dropped rather than
+ * persisted unscanned, so a large blob in a prompt can't stall the writer.
+ */
+
+const { pseudonymize } = require('./pseudonymize');
+const { scanSecrets, keepNonOverlapping } = require('./secret-scanner');
+const { escapeRegExp } = require('./regexp');
+
+const DEFAULT_POLICY = {
+  emails: 'hash',
+  ipAddresses: 'hash',
+  userPaths: 'hash', // The username segment of /Users/<name>, /home/<name>, C:\Users\<name>
+  usernames: [], // Further names to redact wherever they appear (action: userPaths)
+  secrets: 'remove', // 'remove' | 'keep'
+  patterns: [], // [{ name, pattern: RegExp|string, action }]
+  key: null, // HMAC key for 'hash' (default: the pseudonymization dataset key)
+  reversible: false, // Return the pseudonym -> original mapping
+  keepLoopback: true, // Leave 127.0.0.1, ::1 and 0.0.0.0 alone
+  maxScanChars: 512 * 1024, // Longer text is cut here and ends in a [TRUNCATED] marker
+};
+
+const ACTIONS = ['remove', 'hash', 'keep'];
+
+// Anchored at the start of the local part and bounded (RFC 5321 limits), so a long run of word
+// characters without an @ is rejected once instead of once per starting position
+const EMAIL =
+  /(?<![\w.%+-])[A-Za-z0-9._%+-]{1,64}@[A-Za-z0-9-]{1,63}(?:\.[A-Za-z0-9-]{1,63}){0,8}\.[A-Za-z]{2,63}(?![\w-])/g;
+const IPV4 = /(?<![\w.])(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)(?![\w.])/g;
+const IPV6 = /(?<![\w:.])(?:[0-9a-f]{1,4}:){7}[0-9a-f]{1,4}|(?:[0-9a-f]{1,4}:){1,7}:(?:[0-9a-f]{1,4}(?::[0-9a-f]{1,4}){0,6})?(?![\w:.])/gi;
+// The username is group 1; separators may be JSON-escaped backslashes
+const USER_PATH = /(?:\/Users\/|\/home\/|[A-Za-z]:(?:\\+|\/)(?:Users|Documents and Settings)(?:\\+|\/))([^\\/\s"'`:*?<>|]+)/g;
+const LOOPBACK = new Set(['127.0.0.1', '0.0.0.0', '::1', '::']);
+// Path segments under /Users that aren't people
+const SHARED_HOMES = new Set(['shared', 'public', 'default', 'guest', 'all users', 'default user']);
+
+const compiled = new WeakMap();
+
+function checkAction(action, what) {
+  if (!ACTIONS.includes(action)) {
+    throw new Error(`Unknown redaction action for ${what}: ${action} (expected ${ACTIONS.join(', ')})`);
+  }
+  return action;
+}
+
+/**
+ * Validate a policy and build its matchers
+ */
+function compilePolicy(policy = {}) {
+  if (compiled.has(policy)) return compiled.get(policy);
+  const opts = { ...DEFAULT_POLICY, ...policy };
+  const detectors = [];
+  const add = (type, action, pattern, group = 0, accept = null) => {
+    if (checkAction(action, type) !== 'keep') detectors.push({ type, action, pattern, group, accept });
+  };
+
+  // Custom patterns first: where they overlap a built-in match, they win
+  for (const [index, custom] of (opts.patterns || []).entries()) {
+    const source = custom.pattern instanceof RegExp ? custom.pattern : new RegExp(custom.pattern);
+    const flags = source.flags.includes('g') ? source.flags : `${source.flags}g`;
+    add(custom.name || `pattern${index + 1}`, custom.action || 'remove', new RegExp(source.source, flags));
+  }
+  add('email', opts.emails, EMAIL);
+  const routable = (value) => !opts.keepLoopback || !LOOPBACK.has(value.toLowerCase());
+  add('ip', opts.ipAddresses, IPV4, 0, routable);
+  // `Abc::Def` is a path in half the languages we see; addresses have digits
+  add('ip', opts.ipAddresses, IPV6, 0, (value) => /\d/.test(value) && routable(value));
+  add('user', opts.userPaths, USER_PATH, 1, (name) => !SHARED_HOMES.has(name.toLowerCase()));
+  const names = (opts.usernames || []).filter(Boolean).sort((a, b) => b.length - a.length);
+  if (names.length) {
+    const alternatives = names.map(escapeRegExp).join('|');
+    add('user', opts.userPaths, new RegExp(`(?<![\\w.-])(?:${alternatives})(?![\\w-])`, 'g'));
+  }
+  if (opts.secrets !== 'keep' && opts.secrets !== 'remove') {
+    throw new Error(`Unknown redaction action for secrets: ${opts.secrets} (expected remove, keep)`);
+  }
+
+  const result = { opts, detectors };
+  if (policy && typeof policy === 'object') compiled.set(policy, result);
+  return result;
+}
+
+function replacement(type, action, value, opts, mapping) {
+  if (action === 'remove') return `[${type.toUpperCase()}]`;
+  const pseudonym = pseudonymize(value, type, { key: opts.key || undefined, prefix: type.toUpperCase() });
+  if (mapping) mapping[pseudonym] = value;
+  return pseudonym;
+}
+
+/**
+ * Redact personal data and secrets from text
+ * @param {string} content - Text to redact
+ * @param {object} policy - See DEFAULT_POLICY; actions are 'remove' | 'hash' | 'keep'
+ * @returns {object} { text, counts: { [type]: n }, mapping } with mapping (pseudonym -> original)
+ *   only when policy.reversible is set; counts.truncated is the number of characters cut
+ */
+function redactText(content, policy = {}) {
+  const { opts, detectors } = compilePolicy(policy);
+  const full = String(content ?? '');
+  const counts = {};
+  const mapping = opts.reversible ? {} : null;
+  if (!full) return { text: full, counts, mapping };
+  const truncated = opts.maxScanChars > 0 && full.length > opts.maxScanChars;
+  const text = truncated ? full.slice(0, opts.maxScanChars) : full;
+  if (truncated) counts.truncated = full.length - text.length;
+
+  const spans = [];
+  if (opts.secrets !== 'keep') {
+    for (const found of scanSecrets(text)) {
+      spans.push({ start: found.start, end: found.end, type: 'secret', action: 'remove', rule: found.rule });
+    }
+  }
+  for (const detector of detectors) {
+    detector.pattern.lastIndex = 0;
+    let match;
+    while ((match = detector.pattern.exec(text))) {
+      if (match[0].length === 0) {
+        detector.pattern.lastIndex++;
+        continue;
+      }
+      const value = match[detector.group];
+      if (!value || (detector.accept && !detector.accept(value))) continue;
+      const start = match.index + (detector.group ? match[0].lastIndexOf(value) : 0);
+      spans.push({ start, end: start + value.length, type: detector.type, action: detector.action });
+    }
+  }
+
+  // Earlier detectors (secrets, then custom patterns) win overlaps
+  const kept = keepNonOverlapping(spans);
+
+  let out = '';
+  let at = 0;
+  for (const span of kept) {
+    const value = text.slice(span.start, span.end);
+    const marker =
+      span.type === 'secret'
+        ? `[SECRET:${span.rule}]`
+        : replacement(span.type, span.action, value, opts, mapping);
+    out += text.slice(at, span.start) + marker;
+    at = span.end;
+    counts[span.type] = (counts[span.type] || 0) + 1;
+  }
+  const tail = truncated ? `[TRUNCATED:${counts.truncated}]` : '';
+  return { text: out + text.slice(at) + tail, counts, mapping };
+}
+
+/**
+ * redactText over every string in a JSON-like value (keys are left alone)
+ * @returns {object} { value, counts, mapping }
+ */
+function redactValue(value, policy = {}) {
+  const counts = {};
+  const mapping = compilePolicy(policy).opts.reversible ? {} : null;
+  const visit = (node) => {
+    if (typeof node === 'string') {
+      const result = redactText(node, policy);
+      for (const [type, n] of Object.entries(result.counts)) counts[type] = (counts[type] || 0) + n;
+      if (mapping) Object.assign(mapping, result.mapping);
+      return result.text;
+    }
+    if (Array.isArray(node)) return node.map(visit);
+    if (node && typeof node === 'object' && !(node instanceof Date) && !Buffer.isBuffer(node)) {
+      const copy = {};
+      for (const [key, child] of Object.entries(node)) copy[key] = visit(child);
+      return copy;
+    }
+    return node;
+  };
+  return { value: visit(value), counts, mapping };
+}
+
+/**
+ * Put originals back using a mapping from a reversible redaction
+ */
+function restoreText(text, mapping) {
+  const pseudonyms = Object.keys(mapping || {});
+  if (!pseudonyms.length) return text;
+  const pattern = new RegExp(pseudonyms.map(escapeRegExp).join('|'), 'g');
+  return String(text).replace(pattern, (pseudonym) => mapping[pseudonym]);
+}
+
+module.exports = {
+  redactText,
+  redactValue,
+  restoreText,
+  compilePolicy,
+  DEFAULT_POLICY,
+};
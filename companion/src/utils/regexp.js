@@ -0,0 +1,14 @@
+/**
+ * RegExp Helpers
+ */
+
+/**
+ * Escape text for use as a literal inside a regular expression
+ */
+function escapeRegExp(text) {
+  return String(text).replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+}
+
+module.exports = {
+  escapeRegExp,
+};
@@ -0,0 +1,327 @@
+/**
+ * Secret Scanner
+ * Finds API keys, tokens, private keys and credentials in text before it is
+ * written to a trace: known formats by pattern (AWS, GitHub, Stripe, JWTs,
+ * PEM blocks, connection strings, ...), plus values assigned to
+ * secret-looking names and high-entropy string literals.
+ *
+ * Runs on every snapshot, so it's built to be cheap on text without secrets:
+ * every rule declares literal keywords and only runs when one of them occurs
+ * in the text (one case-insensitive substring search per keyword), and
+ * entropy is only computed for candidate string literals. A clean source file
+ * costs a few substring searches and one pass of the string-literal regex.
+ *
+ * Matches never carry the secret itself, only its location and a redacted
+ * preview of the line it's on.
+ */
+
+const DEFAULT_OPTIONS = {
+  entropy: true, // Report high-entropy string literals that match no known format
+  minEntropy: 4.2, // Bits per character for the entropy rule
+  minEntropyLength: 24, // Shortest string literal the entropy rule considers
+  keepPrefix: 4, // Characters of each secret left visible in previews (known prefixes identify the type)
+  previewLength: 120, // Longest preview line
+  rules: null, // Rule ids to run (default: all)
+};
+
+// Values that name a secret rather than contain one
+const PLACEHOLDER =
+  /^(?:\$\{.*\}|\$[A-Z_]+|<[^>]*>|\{\{.*\}\}|%\(?\w+\)?s?|x{4,}|\*{3,}|\.{3,}|(?:your|my|example|sample|dummy|test|fake|changeme|placeholder|redacted)[\w-]*|process\.env\..*|os\.environ.*|env\(.*)$/i;
+
+/**
+ * Rules: { id, description, keywords, pattern, group?, validate? }
+ * group selects the capture group holding the secret (default: the whole match); validate(secret,
+ * match) can reject a match the pattern alone can't
+ */
+const RULES = [
+  {
+    id: 'private-key',
+    description: 'Private key block',
+    keywords: ['private key-----'],
+    pattern: /-----BEGIN[ A-Z0-9]*PRIVATE KEY(?: BLOCK)?-----[\s\S]*?(?:-----END[ A-Z0-9]*PRIVATE KEY(?: BLOCK)?-----|$)/g,
+  },
+  {
+    id: 'aws-access-key',
+    description: 'AWS access key ID',
+    keywords: ['akia', 'asia', 'agpa', 'aida', 'aroa', 'anpa', 'anva', 'aipa'],
+    pattern: /\b(?:AKIA|ASIA|AGPA|AIDA|AROA|ANPA|ANVA|AIPA)[0-9A-Z]{16}\b/g,
+  },
+  {
+    id: 'aws-secret-key',
+    description: 'AWS secret access key',
+    keywords: ['aws'],
+    pattern: /aws_?secret_?(?:access_?)?key\w*["']?\s*[:=]\s*["']?([A-Za-z0-9/+=]{40})(?![A-Za-z0-9/+=])/gi,
+    group: 1,
+  },
+  {
+    id: 'github-token',
+    description: 'GitHub token',
+    keywords: ['ghp_', 'gho_', 'ghu_', 'ghs_', 'ghr_', 'github_pat_'],
+    pattern: /\b(?:gh[pousr]_[A-Za-z0-9]{36,255}|github_pat_[A-Za-z0-9_]{60,255})\b/g,
+  },
+  {
+    id: 'gitlab-token',
+    description: 'GitLab token',
+    keywords: ['glpat-', 'gldt-', 'glrt-'],
+    pattern: /\bgl(?:pat|dt|rt)-[A-Za-z0-9_-]{20,}\b/g,
+  },
+  {
+    id: 'slack-token',
+    description: 'Slack token',
+    keywords: ['xox'],
+    pattern: /\bxox[abeprs]-[A-Za-z0-9-]{10,}\b/g,
+  },
+  {
+    id: 'slack-webhook',
+    description: 'Slack webhook URL',
+    keywords: ['hooks.slack.com'],
+    pattern: /https:\/\/hooks\.slack\.com\/(?:services|workflows)\/[A-Za-z0-9/_-]{20,}/g,
+  },
+  {
+    id: 'stripe-key',
+    description: 'Stripe secret key',
+    keywords: ['sk_live', 'sk_test', 'rk_live', 'rk_test'],
+    pattern: /\b[sr]k_(?:live|test)_[A-Za-z0-9]{20,}\b/g,
+  },
+  {
+    id: 'google-api-key',
+    description: 'Google API key',
+    keywords: ['aiza'],
+    pattern: /\bAIza[0-9A-Za-z_-]{35}(?![0-9A-Za-z_-])/g,
+  },
+  {
+    id: 'anthropic-key',
+    description: 'Anthropic API key',
+    keywords: ['sk-ant-'],
+    pattern: /\bsk-ant-[A-Za-z0-9_-]{32,}/g,
+  },
+  {
+    id: 'openai-key',
+    description: 'OpenAI API key',
+    keywords: ['sk-'],
+    pattern: /\bsk-(?!ant-)(?:proj-|svcacct-|admin-)?[A-Za-z0-9_-]{32,}/g,
+  },
+  {
+    id: 'huggingface-token',
+    description: 'Hugging Face token',
+    keywords: ['hf_'],
+    pattern: /\bhf_[A-Za-z0-9]{34,}\b/g,
+  },
+  {
+    id: 'npm-token',
+    description: 'npm access token',
+    keywords: ['npm_'],
+    pattern: /\bnpm_[A-Za-z0-9]{36}\b/g,
+  },
+  {
+    id: 'sendgrid-key',
+    description: 'SendGrid API key',
+    keywords: ['sg.'],
+    pattern: /\bSG\.[A-Za-z0-9_-]{22}\.[A-Za-z0-9_-]{43}\b/g,
+  },
+  {
+    id: 'jwt',
+    description: 'JSON Web Token',
+    keywords: ['eyj'],
+    pattern: /\beyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}/g,
+  },
+  {
+    id: 'connection-string',
+    description: 'Password in a connection string or URL',
+    keywords: ['://'],
+    pattern: /\b[a-z][a-z0-9+.-]*:\/\/[^\s:@/'"`]+:([^\s@/'"`]+)@[^\s'"`]+/gi,
+    group: 1,
+  },
+  {
+    id: 'assigned-secret',
+    description: 'Value assigned to a secret-looking name',
+    keywords: ['pass', 'pwd', 'secret', 'token', 'key', 'access', 'auth', 'credential'],
+    pattern:
+      /(passw(?:or)?d|pwd|secret|token|api_?key|access_?key|private_?key|auth(?!or)|credentials?)[\w.-]*["']?\s*(?:=|:|:=|=>)\s*["'`]([^"'`\s]{8,})["'`]/gi,
+    group: 2,
+    // Passwords can be anything; other names also label paths, file names and config values
+    validate: (secret, match) =>
+      !/\/|\\|\.(?:[a-z]{1,4})$/i.test(secret) &&
+      (/^(?:passw|pwd)/i.test(match[1]) || (shannonEntropy(secret) >= 3 && /[0-9]/.test(secret))),
+  },
+];
+
+const RULES_BY_ID = new Map(RULES.map((rule) => [rule.id, rule]));
+
+// Quoted literals long enough to be a key, for the entropy rule
+const STRING_LITERAL = /(["'`])([A-Za-z0-9+/=_\-.~]{16,})\1/g;
+// Hashes, digests and integrity strings: high entropy, but not secrets
+const NOT_SECRET = /^(?:[0-9a-f]+|sha\d+-.*|[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12})$/i;
+
+/**
+ * Shannon entropy in bits per character
+ */
+function shannonEntropy(text) {
+  if (!text) return 0;
+  const counts = new Map();
+  for (const char of text) counts.set(char, (counts.get(char) || 0) + 1);
+  let entropy = 0;
+  for (const count of counts.values()) {
+    const p = count / text.length;
+    entropy -= p * Math.log2(p);
+  }
+  return entropy;
+}
+
+function lineStarts(content) {
+  const starts = [0];
+  for (let i = content.indexOf('\n'); i >= 0; i = content.indexOf('\n', i + 1)) starts.push(i + 1);
+  return starts;
+}
+
+function lineIndex(starts, offset) {
+  let low = 0;
+  let high = starts.length - 1;
+  while (low < high) {
+    const mid = (low + high + 1) >> 1;
+    if (starts[mid] <= offset) low = mid;
+    else high = mid - 1;
+  }
+  return low;
+}
+
+/**
+ * Drop spans that overlap an earlier one
+ * @param {Array<object>} spans - { start, end, ... } in priority order (first wins)
+ * @returns {Array<object>} The kept spans, sorted by start
+ */
+function keepNonOverlapping(spans) {
+  // Kept spans never overlap, so ordering by start also orders them by end
+  const kept = [];
+  for (const span of spans) {
+    let low = 0;
+    let high = kept.length;
+    while (low < high) {
+      const mid = (low + high) >> 1;
+      if (kept[mid].end <= span.start) low = mid + 1;
+      else high = mid;
+    }
+    // kept[low] is the first span ending after this one starts
+    if (low < kept.length && kept[low].start < span.end) continue;
+    if (low === kept.length) kept.push(span);
+    else kept.splice(low, 0, span);
+  }
+  return kept;
+}
+
+function mask(secret, keepPrefix) {
+  const visible = secret.length > keepPrefix * 3 ? keepPrefix : 0;
+  return secret.slice(0, visible) + '*'.repeat(Math.min(secret.length - visible, 16));
+}
+
+function candidates(content, opts) {
+  const lower = content.toLowerCase();
+  const rules = opts.rules ? opts.rules.map((id) => RULES_BY_ID.get(id)).filter(Boolean) : RULES;
+  const found = [];
+  for (const rule of rules) {
+    if (!rule.keywords.some((keyword) => lower.includes(keyword))) continue;
+    rule.pattern.lastIndex = 0;
+    let match;
+    while ((match = rule.pattern.exec(content))) {
+      if (match[0].length === 0) {
+        rule.pattern.lastIndex++;
+        continue;
+      }
+      const secret = rule.group ? match[rule.group] : match[0];
+      if (!secret || PLACEHOLDER.test(secret)) continue;
+      if (rule.validate && !rule.validate(secret, match)) continue;
+      const start = match.index + (rule.group ? match[0].indexOf(secret) : 0);
+      found.push({ rule: rule.id, description: rule.description, start, end: start + secret.length, secret });
+    }
+  }
+
+  if (opts.entropy && (!opts.rules || opts.rules.includes('high-entropy'))) {
+    STRING_LITERAL.lastIndex = 0;
+    let match;
+    while ((match = STRING_LITERAL.exec(content))) {
+      const secret = match[2];
+      if (secret.length < opts.minEntropyLength || NOT_SECRET.test(secret)) continue;
+      // Keys mix cases and digits; identifiers, paths and prose don't
+      if (!/[a-z]/.test(secret) || !/[A-Z]/.test(secret) || !/[0-9]/.test(secret)) continue;
+      const entropy = shannonEntropy(secret);
+      if (entropy < opts.minEntropy) continue;
+      const start = match.index + 1;
+      found.push({
+        rule: 'high-entropy',
+        description: 'High-entropy string',
+        start,
+        end: start + secret.length,
+        secret,
+        entropy: Math.round(entropy * 100) / 100,
+      });
+    }
+  }
+  return found;
+}
+
+/**
+ * Find secrets in text
+ * @param {string} content - Text to scan (source, prompt, terminal output...)
+ * @param {object} options - See DEFAULT_OPTIONS
+ * @returns {Array<object>} [{ rule, description, start, end, line, column, length, entropy, preview }]
+ *   sorted by position; start / end are offsets of the secret itself; preview is its line with the
+ *   secret masked. Where rules overlap, the earlier (more specific) rule in RULES wins.
+ */
+function scanSecrets(content, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const text = String(content ?? '');
+  if (!text) return [];
+
+  const order = (rule) => (RULES_BY_ID.has(rule) ? RULES.indexOf(RULES_BY_ID.get(rule)) : RULES.length);
+  const found = candidates(text, opts).sort((a, b) => order(a.rule) - order(b.rule) || a.start - b.start);
+  const kept = keepNonOverlapping(found);
+
+  const starts = lineStarts(text);
+  // Every secret touching the line is masked, not just the one the preview is for. Stops once
+  // the preview is full so a minified line holding thousands of secrets isn't rebuilt per secret
+  const limit = opts.previewLength + 1;
+  const maskLine = (lineStart, lineEnd, from) => {
+    let out = '';
+    let at = lineStart;
+    while (at < lineEnd && /\s/.test(text[at])) at++;
+    for (let i = from; i < kept.length && kept[i].start < lineEnd && out.length < limit; i++) {
+      const start = Math.max(kept[i].start, lineStart);
+      const end = Math.min(kept[i].end, lineEnd);
+      if (end <= at) continue;
+      // Only the part of a secret on this line (PEM blocks span many)
+      const visible = kept[i].start >= lineStart ? opts.keepPrefix : 0;
+      out += text.slice(at, start) + mask(text.slice(start, end), visible);
+      at = end;
+    }
+    return out.length < limit ? out + text.slice(at, Math.min(lineEnd, at + limit - out.length)) : out;
+  };
+
+  let first = 0; // First kept span that may still reach the current line
+  return kept.map(({ rule, description, start, end, secret, entropy }, position) => {
+    const index = lineIndex(starts, start);
+    const lineStart = starts[index];
+    const lineEnd = index + 1 < starts.length ? starts[index + 1] - 1 : text.length;
+    while (first < position && kept[first].end <= lineStart) first++;
+    const trimmed = maskLine(lineStart, lineEnd, first).trim();
+    return {
+      rule,
+      description,
+      start,
+      end,
+      line: index + 1,
+      column: start - lineStart + 1,
+      length: end - start,
+      entropy: entropy ?? Math.round(shannonEntropy(secret) * 100) / 100,
+      preview:
+        trimmed.length > opts.previewLength ? `${trimmed.slice(0, opts.previewLength - 1)}…` : trimmed,
+    };
+  });
+}
+
+module.exports = {
+  scanSecrets,
+  keepNonOverlapping,
+  shannonEntropy,
+  RULES,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,174 @@
+/**
+ * SimHash Near-Duplicate Detection
+ * 64-bit SimHash fingerprints over code token shingles, so a snapshot that
+ * is a reformatted or lightly edited copy of an existing one lands within a
+ * few bits of it. Exact content hashes (content-hash.js) miss those.
+ *
+ * Tokens come from the diff engine's code tokenizer, which already drops
+ * whitespace and comments; shingling keeps token order significant.
+ * Fingerprints are 16-char hex strings.
+ */
+
+const { tokenizeCode } = require('./diff-engine');
+
+const DEFAULT_OPTIONS = {
+  shingleSize: 3,
+  language: null, // Comment syntax for tokenizing (C-style by default)
+};
+
+/**
+ * 32-bit FNV-1a with a seed, then an avalanche finalizer so nearby seeds give independent bits
+ */
+function hash32(text, seed) {
+  let h = (0x811c9dc5 ^ seed) >>> 0;
+  for (let i = 0; i < text.length; i++) {
+    h ^= text.charCodeAt(i);
+    h = Math.imul(h, 0x01000193);
+  }
+  h ^= h >>> 16;
+  h = Math.imul(h, 0x85ebca6b);
+  h ^= h >>> 13;
+  h = Math.imul(h, 0xc2b2ae35);
+  h ^= h >>> 16;
+  return h >>> 0;
+}
+
+function popcount32(x) {
+  x -= (x >>> 1) & 0x55555555;
+  x = (x & 0x33333333) + ((x >>> 2) & 0x33333333);
+  x = (x + (x >>> 4)) & 0x0f0f0f0f;
+  return Math.imul(x, 0x01010101) >>> 24;
+}
+
+function toHex(hi, lo) {
+  return hi.toString(16).padStart(8, '0') + lo.toString(16).padStart(8, '0');
+}
+
+function fromHex(hash) {
+  const value = typeof hash === 'string' ? hash : hash.hash;
+  if (!/^[0-9a-f]{16}$/i.test(value || '')) throw new Error(`Invalid simhash: ${value}`);
+  return [parseInt(value.slice(0, 8), 16) >>> 0, parseInt(value.slice(8), 16) >>> 0];
+}
+
+/**
+ * Compute the SimHash fingerprint of a text
+ * @param {string} content - Snapshot content
+ * @param {object} options - { shingleSize, language }
+ * @returns {string} 64-bit fingerprint as hex
+ */
+function computeSimhash(content, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const tokens = tokenizeCode(content, opts.language);
+  const features = new Map();
+  const size = Math.max(1, Math.min(opts.shingleSize, tokens.length));
+  for (let i = 0; i + size <= tokens.length; i++) {
+    const shingle = tokens.slice(i, i + size).join('\u0000');
+    features.set(shingle, (features.get(shingle) || 0) + 1);
+  }
+
+  const weights = new Float64Array(64);
+  for (const [feature, weight] of features) {
+    const hi = hash32(feature, 0x9e3779b9);
+    const lo = hash32(feature, 0x7f4a7c15);
+    for (let bit = 0; bit < 32; bit++) {
+      weights[bit] += (hi >>> (31 - bit)) & 1 ? weight : -weight;
+      weights[bit + 32] += (lo >>> (31 - bit)) & 1 ? weight : -weight;
+    }
+  }
+
+  let hi = 0;
+  let lo = 0;
+  for (let bit = 0; bit < 32; bit++) {
+    if (weights[bit] > 0) hi |= 1 << (31 - bit);
+    if (weights[bit + 32] > 0) lo |= 1 << (31 - bit);
+  }
+  return toHex(hi >>> 0, lo >>> 0);
+}
+
+/**
+ * Number of differing bits between two fingerprints
+ */
+function hammingDistance(a, b) {
+  const [aHi, aLo] = fromHex(a);
+  const [bHi, bLo] = fromHex(b);
+  return popcount32((aHi ^ bHi) >>> 0) + popcount32((aLo ^ bLo) >>> 0);
+}
+
+/**
+ * Find pairs of fingerprints within a Hamming distance of each other.
+ * Splits the 64 bits into threshold + 1 bands; by pigeonhole, any two fingerprints within
+ * the threshold agree exactly on at least one band, so only band-collisions are compared.
+ * @param {Array<string|object>} hashes - Fingerprints, or { id, hash } objects
+ * @param {number} hammingThreshold - Max differing bits to count as near-duplicate
+ * @returns {object} { pairs: [{ a, b, distance }], groups: [[id, ...]] } (ids are indices for plain strings)
+ */
+function findNearDuplicates(hashes, hammingThreshold = 3) {
+  const items = (hashes || []).map((entry, index) => {
+    const [hi, lo] = fromHex(entry);
+    return { id: typeof entry === 'string' ? index : (entry.id ?? index), hi, lo };
+  });
+
+  const bandCount = Math.min(64, Math.max(1, hammingThreshold + 1));
+  const bandWidth = Math.ceil(64 / bandCount);
+  const bandKey = (item, band) => {
+    let key = '';
+    for (let bit = band * bandWidth; bit < Math.min(64, (band + 1) * bandWidth); bit++) {
+      const word = bit < 32 ? item.hi : item.lo;
+      key += (word >>> (31 - (bit % 32))) & 1;
+    }
+    return `${band}:${key}`;
+  };
+
+  const buckets = new Map();
+  for (let i = 0; i < items.length; i++) {
+    for (let band = 0; band < bandCount; band++) {
+      const key = bandKey(items[i], band);
+      if (!buckets.has(key)) buckets.set(key, []);
+      buckets.get(key).push(i);
+    }
+  }
+
+  const seen = new Set();
+  const pairs = [];
+  const parent = items.map((_, i) => i);
+  const find = (i) => {
+    while (parent[i] !== i) i = parent[i] = parent[parent[i]];
+    return i;
+  };
+
+  for (const members of buckets.values()) {
+    for (let x = 0; x < members.length; x++) {
+      for (let y = x + 1; y < members.length; y++) {
+        const i = members[x];
+        const j = members[y];
+        const pairKey = i < j ? `${i},${j}` : `${j},${i}`;
+        if (seen.has(pairKey)) continue;
+        seen.add(pairKey);
+        const distance =
+          popcount32((items[i].hi ^ items[j].hi) >>> 0) + popcount32((items[i].lo ^ items[j].lo) >>> 0);
+        if (distance > hammingThreshold) continue;
+        pairs.push({ a: items[i].id, b: items[j].id, distance });
+        parent[find(i)] = find(j);
+      }
+    }
+  }
+
+  const groups = new Map();
+  items.forEach((item, i) => {
+    const root = find(i);
+    if (!groups.has(root)) groups.set(root, []);
+    groups.get(root).push(item.id);
+  });
+
+  return {
+    pairs: pairs.sort((p, q) => p.distance - q.distance),
+    groups: Array.from(groups.values()).filter((group) => group.length > 1),
+  };
+}
+
+module.exports = {
+  computeSimhash,
+  hammingDistance,
+  findNearDuplicates,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,149 @@
+/**
+ * String Deduplication
+ * Exact and similarity-based deduplication for prompt histories and other
+ * text lists, preserving first-appearance order. deduplicateWithCounts keeps
+ * each string's occurrence count and indices so callers can rebuild
+ * frequency statistics without a second pass.
+ *
+ * deduplicateSimilar collapses near-identical strings (whitespace changes,
+ * small edits such as a retried prompt with a typo fixed) into one canonical
+ * representative and reports which original indices it absorbed. Strings are
+ * compared after whitespace normalization; candidates are limited to
+ * representatives of compatible length and compared with a bounded edit
+ * distance, so dissimilar pairs exit early.
+ */
+
+const { editDistance } = require('./diff-engine');
+
+const DEFAULT_OPTIONS = {
+  ignoreWhitespace: true,
+  caseInsensitive: false,
+  representative: 'first', // 'first' | 'longest' | 'mostFrequent'
+};
+
+/**
+ * Remove exact duplicates, keeping the first occurrence of each string
+ */
+function deduplicateStrings(strings) {
+  return Array.from(new Set(strings || []));
+}
+
+/**
+ * Exact, order-preserving dedup that also reports how often each string occurred and where
+ * @param {Array<string>} strings - Input strings
+ * @param {object} options - { ignoreWhitespace, caseInsensitive } (both off by default here)
+ * @returns {Array<object>} [{ value, count, firstIndex, indices }] in first-appearance order
+ */
+function deduplicateWithCounts(strings, options = {}) {
+  const opts = { ignoreWhitespace: false, caseInsensitive: false, ...options };
+  const entries = [];
+  const byKey = new Map();
+  (strings || []).forEach((text, index) => {
+    const key = opts.ignoreWhitespace || opts.caseInsensitive ? normalizeText(text, opts) : text;
+    let entry = byKey.get(key);
+    if (!entry) {
+      entry = { value: text, count: 0, firstIndex: index, indices: [] };
+      byKey.set(key, entry);
+      entries.push(entry);
+    }
+    entry.count++;
+    entry.indices.push(index);
+  });
+  return entries;
+}
+
+function normalizeText(text, opts) {
+  let value = String(text ?? '');
+  if (opts.ignoreWhitespace) value = value.replace(/\s+/g, ' ').trim();
+  if (opts.caseInsensitive) value = value.toLowerCase();
+  return value;
+}
+
+/**
+ * Similarity in [0, 1] from edit distance, or 0 when the distance would exceed what the threshold allows
+ */
+function similarity(a, b, threshold) {
+  const longer = Math.max(a.length, b.length);
+  if (longer === 0) return 1;
+  const maxDistance = Math.floor((1 - threshold) * longer);
+  const distance = editDistance(a, b, { maxDistance });
+  return distance > maxDistance ? 0 : 1 - distance / longer;
+}
+
+/**
+ * Collapse near-duplicate strings into canonical representatives
+ * @param {Array<string>} strings - Input strings
+ * @param {number} threshold - Minimum similarity (1 - editDistance / maxLength) to merge
+ * @param {object} options - { ignoreWhitespace, caseInsensitive, representative }
+ * @returns {object} { unique, groups: [{ representative, value, count, indices }], mapping }
+ *   mapping[i] is the index into unique/groups that strings[i] collapsed into
+ */
+function deduplicateSimilar(strings, threshold = 0.9, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  const input = strings || [];
+  const groups = [];
+  const mapping = new Array(input.length);
+  const exact = new Map();
+  const byLength = new Map(); // normalized length -> group indices
+
+  // Two strings can only reach the threshold if their lengths are within its ratio
+  const lengthRange = (length) => [Math.ceil(length * threshold), Math.floor(length / Math.max(threshold, 0.01))];
+
+  input.forEach((text, index) => {
+    const normalized = normalizeText(text, opts);
+
+    let target = exact.get(normalized);
+    if (target === undefined && threshold < 1) {
+      let bestScore = 0;
+      const [minLength, maxLength] = lengthRange(normalized.length);
+      for (let length = minLength; length <= maxLength; length++) {
+        const candidates = byLength.get(length);
+        if (!candidates) continue;
+        for (const g of candidates) {
+          const score = similarity(normalized, groups[g].normalized, threshold);
+          if (score >= threshold && score > bestScore) {
+            bestScore = score;
+            target = g;
+          }
+        }
+      }
+    }
+
+    if (target === undefined) {
+      target = groups.length;
+      groups.push({ normalized, indices: [] });
+      if (!byLength.has(normalized.length)) byLength.set(normalized.length, []);
+      byLength.get(normalized.length).push(target);
+    }
+    exact.set(normalized, target);
+    groups[target].indices.push(index);
+    mapping[index] = target;
+  });
+
+  const result = groups.map((group) => {
+    let representative = group.indices[0];
+    if (opts.representative === 'longest') {
+      const length = (i) => String(input[i] ?? '').length;
+      representative = group.indices.reduce((best, i) => (length(i) > length(best) ? i : best));
+    } else if (opts.representative === 'mostFrequent') {
+      const counts = new Map();
+      for (const i of group.indices) counts.set(input[i], (counts.get(input[i]) || 0) + 1);
+      const count = (i) => counts.get(input[i]);
+      representative = group.indices.reduce((best, i) => (count(i) > count(best) ? i : best));
+    }
+    return { representative, value: input[representative], count: group.indices.length, indices: group.indices };
+  });
+
+  return {
+    unique: result.map((group) => group.value),
+    groups: result,
+    mapping,
+  };
+}
+
+module.exports = {
+  deduplicateStrings,
+  deduplicateWithCounts,
+  deduplicateSimilar,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,98 @@
+/**
+ * Text Segmentation
+ * Unicode-aware word, sentence and grapheme counting (UAX-29 via Intl.Segmenter)
+ * for prose and documentation files, where whitespace splitting produces
+ * nonsense for CJK, Thai, and other non-space-delimited scripts.
+ */
+
+const PROSE_EXTENSIONS = new Set(['.md', '.markdown', '.mdx', '.txt', '.rst', '.adoc', '.org', '.tex']);
+const PROSE_LANGUAGES = new Set(['markdown', 'text', 'plaintext', 'restructuredtext', 'asciidoc', 'org', 'latex']);
+
+const segmenterCache = new Map();
+
+function getSegmenter(granularity, locale) {
+  const key = `${granularity}:${locale || ''}`;
+  let segmenter = segmenterCache.get(key);
+  if (!segmenter) {
+    segmenter = new Intl.Segmenter(locale || undefined, { granularity });
+    segmenterCache.set(key, segmenter);
+  }
+  return segmenter;
+}
+
+/**
+ * Whether a file should be treated as prose rather than code
+ */
+function isProse(filename = null, language = null) {
+  if (language && PROSE_LANGUAGES.has(String(language).toLowerCase())) return true;
+  if (!filename) return false;
+  const dot = filename.lastIndexOf('.');
+  return dot !== -1 && PROSE_EXTENSIONS.has(filename.slice(dot).toLowerCase());
+}
+
+/**
+ * Remove Markdown syntax that would otherwise be counted as words
+ */
+function stripMarkdown(content) {
+  return content
+    .replace(/^(```|~~~)[^\n]*\n[\s\S]*?^\1[^\n]*$/gm, ' ') // fenced code blocks
+    .replace(/`[^`\n]*`/g, ' ') // inline code
+    .replace(/!\[([^\]]*)\]\([^)]*\)/g, '$1') // images -> alt text
+    .replace(/\[([^\]]*)\]\([^)]*\)/g, '$1') // links -> link text
+    .replace(/<[^>\n]+>/g, ' ') // inline HTML
+    .replace(/^\s{0,3}(#{1,6}|>|[-*+]|\d+[.)])\s+/gm, '') // headings, quotes, list markers
+    .replace(/^\s*([-*_]\s*){3,}$/gm, ' ') // horizontal rules
+    .replace(/[*_~]{1,3}/g, ''); // emphasis markers
+}
+
+function countWords(text, locale = null) {
+  let words = 0;
+  for (const segment of getSegmenter('word', locale).segment(text)) {
+    if (segment.isWordLike) words++;
+  }
+  return words;
+}
+
+function countSentences(text, locale = null) {
+  let sentences = 0;
+  for (const segment of getSegmenter('sentence', locale).segment(text)) {
+    if (/[\p{L}\p{N}]/u.test(segment.segment)) sentences++;
+  }
+  return sentences;
+}
+
+function countGraphemes(text, locale = null) {
+  let graphemes = 0;
+  for (const _ of getSegmenter('grapheme', locale).segment(text)) graphemes++;
+  return graphemes;
+}
+
+/**
+ * Prose statistics for Markdown and plain-text content
+ * @param {string} content - File content
+ * @param {object} options - { locale, markdown }
+ */
+function proseStats(content, options = {}) {
+  const text = options.markdown === false ? content : stripMarkdown(content);
+  const locale = options.locale || null;
+  const words = countWords(text, locale);
+  const sentences = countSentences(text, locale);
+  const paragraphs = text.split(/\n\s*\n/).filter((p) => /[\p{L}\p{N}]/u.test(p)).length;
+
+  return {
+    words,
+    sentences,
+    paragraphs,
+    graphemes: countGraphemes(content, locale),
+    avgWordsPerSentence: sentences > 0 ? words / sentences : 0,
+  };
+}
+
+module.exports = {
+  isProse,
+  stripMarkdown,
+  countWords,
+  countSentences,
+  countGraphemes,
+  proseStats,
+};
@@ -0,0 +1,109 @@
+/**
+ * Worker Thread Pool Settings
+ * Process-wide limits for every worker-thread fan-out in the companion:
+ * tokenizer batches, literal and directory search, fuzzy ranking and backfill
+ * analyzers. Left alone, each sizes itself to all cores but one, which is
+ * enough to make the editor stutter during a large batch; configureThreadPool
+ * caps them (e.g. 2 low-priority threads while the user is actively coding).
+ *
+ * The nice level is applied from inside each worker. Linux schedules threads
+ * individually, so only the workers are deprioritised; other platforms only
+ * expose per-process priority, and there the nice level is ignored rather
+ * than slowing down the companion's own event loop.
+ *
+ * Settings apply to workers started after the call; running batches keep the
+ * threads they already have.
+ */
+
+const os = require('os');
+const { Worker } = require('worker_threads');
+const { CompanionError, ERROR_CODES } = require('./errors');
+
+const MIN_NICE = -20;
+const MAX_NICE = 19;
+
+let settings = {
+  numThreads: null, // null = all cores but one
+  stackSize: null, // Bytes; null = V8 default
+  niceLevel: null, // -20 (highest) .. 19 (lowest); null = inherit
+};
+
+function invalid(message) {
+  return new CompanionError(ERROR_CODES.INVALID_INPUT, message);
+}
+
+function defaultThreads() {
+  return Math.max(1, os.cpus().length - 1);
+}
+
+/**
+ * Configure the worker threads used for parallel work
+ * @param {number|null} numThreads - Maximum worker threads per batch (null restores the default)
+ * @param {number|null} stackSize - Worker stack size in bytes
+ * @param {number|null} niceLevel - Scheduling priority for worker threads (Linux only)
+ * @returns {object} The resulting configuration, as getThreadPoolConfig
+ */
+function configureThreadPool(numThreads = null, stackSize = null, niceLevel = null) {
+  if (numThreads !== null && !(Number.isInteger(numThreads) && numThreads >= 1)) {
+    throw invalid(`numThreads must be a positive integer, got ${numThreads}`);
+  }
+  if (stackSize !== null && !(Number.isFinite(stackSize) && stackSize > 0)) {
+    throw invalid(`stackSize must be a positive number of bytes, got ${stackSize}`);
+  }
+  const niceInRange = Number.isInteger(niceLevel) && niceLevel >= MIN_NICE && niceLevel <= MAX_NICE;
+  if (niceLevel !== null && !niceInRange) {
+    throw invalid(`niceLevel must be an integer in ${MIN_NICE}..${MAX_NICE}, got ${niceLevel}`);
+  }
+  settings = { numThreads, stackSize, niceLevel };
+  return getThreadPoolConfig();
+}
+
+function resetThreadPool() {
+  return configureThreadPool();
+}
+
+function getThreadPoolConfig() {
+  return {
+    ...settings,
+    threads: settings.numThreads ?? defaultThreads(),
+    niceApplied: settings.niceLevel !== null && process.platform === 'linux',
+  };
+}
+
+/**
+ * Worker count for a batch: the caller's request (or the pool default), capped by numThreads
+ * @param {number|null} requested - A caller-supplied concurrency option
+ */
+function resolveConcurrency(requested = null) {
+  const threads = settings.numThreads ?? defaultThreads();
+  if (requested === null || requested === undefined) return threads;
+  return Math.max(1, Math.min(requested, threads));
+}
+
+/**
+ * Start an eval'd worker with the configured stack size and priority
+ * @param {string} source - Worker source code
+ * @param {object} options - Worker options (workerData, ...)
+ * @returns {Worker}
+ */
+function createWorker(source, options = {}) {
+  const { stackSize, niceLevel } = settings;
+  const resourceLimits = { ...options.resourceLimits };
+  if (stackSize !== null) resourceLimits.stackSizeMb = stackSize / (1024 * 1024);
+
+  // Raising priority needs privileges; a failed setPriority just leaves the worker at the default
+  const prelude =
+    niceLevel !== null && process.platform === 'linux'
+      ? `try { require('os').setPriority(${niceLevel}); } catch {}\n`
+      : '';
+
+  return new Worker(prelude + source, { ...options, eval: true, resourceLimits });
+}
+
+module.exports = {
+  configureThreadPool,
+  resetThreadPool,
+  getThreadPoolConfig,
+  resolveConcurrency,
+  createWorker,
+};
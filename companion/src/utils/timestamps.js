@@ -0,0 +1,293 @@
+/**
+ * Timestamp Parsing
+ * Normalizes the timestamp formats found across editor logs, shell history and
+ * test output to UTC epoch milliseconds.
+ *
+ * Ambiguous numeric dates (03/05/2024) are resolved per batch: if any line in
+ * the batch can only be day-first (or month-first), that order is used for all.
+ *
+ * A line is tried against each format in turn; a match that doesn't make a
+ * real date (an unknown month name, 31/02) falls through to the next format.
+ *
+ * toMillis() is the one conversion for timestamps already stored on events
+ * (epoch ms or ISO strings): anything that isn't a finite time is null.
+ */
+
+const MONTHS = {
+  // English
+  jan: 1, feb: 2, mar: 3, apr: 4, may: 5, jun: 6, jul: 7, aug: 8, sep: 9, sept: 9, oct: 10, nov: 11, dec: 12,
+  january: 1, february: 2, march: 3, april: 4, june: 6, july: 7, august: 8, september: 9, october: 10,
+  november: 11, december: 12,
+  // German
+  jän: 1, januar: 1, februar: 2, mär: 3, märz: 3, mai: 5, juni: 6, juli: 7, okt: 10, oktober: 10, dez: 12,
+  dezember: 12,
+  // French
+  janv: 1, janvier: 1, févr: 2, février: 2, mars: 3, avr: 4, avril: 4, juin: 6, juil: 7, juillet: 7, août: 8,
+  aout: 8, septembre: 9, octobre: 10, novembre: 11, déc: 12, décembre: 12,
+  // Spanish / Italian / Portuguese
+  ene: 1, enero: 1, febrero: 2, marzo: 3, abr: 4, abril: 4, mayo: 5, junio: 6, julio: 7, ago: 8, agosto: 8,
+  septiembre: 9, set: 9, octubre: 10, noviembre: 11, dic: 12, diciembre: 12, gen: 1, gennaio: 1, febbraio: 2,
+  aprile: 4, maggio: 5, giugno: 6, luglio: 7, settembre: 9, ottobre: 10, dicembre: 12, fev: 2, fevereiro: 2,
+  março: 3, maio: 5, junho: 6, julho: 7, out: 10, outubro: 10, dezembro: 12,
+};
+
+const DAY_FIRST_LOCALES = /^(en-(gb|au|nz|ie|in|za)|de|fr|es|it|pt|nl|ru|pl|tr|sv|da|nb|fi|cs|el|id|vi)/i;
+
+const MONTH_NAME = '([A-Za-zÀ-ÿ]{3,10})\\.?';
+const TIME = '(\\d{1,2}):(\\d{2})(?::(\\d{2}))?(?:[.,](\\d{1,9}))?(?:\\s*([AaPp][Mm]))?';
+const ZONE = '\\s*(Z|UTC|GMT|[+-]\\d{2}:?\\d{2})?';
+
+/**
+ * Ordered format table - more specific patterns first
+ */
+const FORMATS = [
+  {
+    name: 'iso8601',
+    regex: new RegExp(`(\\d{4})-(\\d{2})-(\\d{2})[T ]${TIME}${ZONE}`),
+    build: (m) => ({ year: m[1], month: m[2], day: m[3], time: m.slice(4, 9), zone: m[9] }),
+  },
+  {
+    name: 'clf',
+    regex: new RegExp(`(\\d{2})/${MONTH_NAME}/(\\d{4}):(\\d{2}):(\\d{2}):(\\d{2})${ZONE}`),
+    build: (m) => ({ year: m[3], monthName: m[2], day: m[1], time: [m[4], m[5], m[6]], zone: m[7] }),
+  },
+  {
+    name: 'rfc2822',
+    regex: new RegExp(`(?:[A-Za-z]{3},\\s*)?(\\d{1,2})\\s+${MONTH_NAME}\\s+(\\d{4})\\s+${TIME}${ZONE}`),
+    build: (m) => ({ year: m[3], monthName: m[2], day: m[1], time: m.slice(4, 9), zone: m[9] }),
+  },
+  {
+    name: 'day_month_name',
+    regex: new RegExp(`(?<!\\d)(\\d{1,2})\\.?\\s+${MONTH_NAME}\\s+(\\d{4})(?:[, ]+${TIME})?${ZONE}`),
+    build: (m) => ({ year: m[3], monthName: m[2], day: m[1], time: m.slice(4, 9), zone: m[9] }),
+  },
+  {
+    name: 'month_name_first',
+    regex: new RegExp(`${MONTH_NAME}\\s+(\\d{1,2}),?\\s+(\\d{4})[, ]+${TIME}${ZONE}`),
+    build: (m) => ({ year: m[3], monthName: m[1], day: m[2], time: m.slice(4, 9), zone: m[9] }),
+  },
+  {
+    name: 'ymd_slash',
+    regex: new RegExp(`(\\d{4})/(\\d{2})/(\\d{2})[ T]${TIME}${ZONE}`),
+    build: (m) => ({ year: m[1], month: m[2], day: m[3], time: m.slice(4, 9), zone: m[9] }),
+  },
+  {
+    name: 'iso_date',
+    regex: /(\d{4})-(\d{2})-(\d{2})(?![\d:T])/,
+    build: (m) => ({ year: m[1], month: m[2], day: m[3], time: [] }),
+  },
+  {
+    name: 'numeric_date',
+    regex: new RegExp(`(?<![\\d/.-])(\\d{1,2})([/.-])(\\d{1,2})\\2(\\d{4}|\\d{2})(?!\\d)(?:[ ,T]+${TIME})?${ZONE}`),
+    build: (m) => ({ ambiguous: [m[1], m[3]], separator: m[2], year: m[4], time: m.slice(5, 10), zone: m[10] }),
+  },
+  {
+    name: 'syslog',
+    regex: new RegExp(`${MONTH_NAME}\\s+(\\d{1,2})\\s+${TIME}`),
+    build: (m) => ({ monthName: m[1], day: m[2], time: m.slice(3, 8) }),
+  },
+  {
+    name: 'zsh_history',
+    regex: /^:\s*(\d{9,10}):\d+;/,
+    build: (m) => ({ epochSeconds: m[1] }),
+  },
+  {
+    name: 'bash_history',
+    regex: /^#(\d{9,10})\s*$/,
+    build: (m) => ({ epochSeconds: m[1] }),
+  },
+  {
+    name: 'epoch',
+    regex: /(?<![\d.])(\d{10}(?:\.\d{1,6})?|\d{13})(?![\d])/,
+    build: (m) => (m[1].length === 13 ? { epochMillis: m[1] } : { epochSeconds: m[1] }),
+  },
+  {
+    name: 'time_only',
+    regex: new RegExp(`(?:^|[\\s\\[])${TIME}(?=[\\]\\s]|$)`),
+    build: (m) => ({ timeOnly: true, time: m.slice(1, 6) }),
+  },
+];
+
+function monthFromName(name) {
+  if (!name) return null;
+  const key = name.toLowerCase().replace(/\.$/, '');
+  return MONTHS[key] || MONTHS[key.slice(0, 3)] || null;
+}
+
+function zoneOffsetMinutes(zone, hints) {
+  if (!zone) {
+    if (hints.utcOffsetMinutes !== undefined) return hints.utcOffsetMinutes;
+    return hints.timezone === 'local' ? null : 0;
+  }
+  const z = zone.trim().toUpperCase();
+  if (z === 'Z' || z === 'UTC' || z === 'GMT') return 0;
+  const m = z.match(/^([+-])(\d{2}):?(\d{2})$/);
+  if (!m) return 0;
+  const minutes = parseInt(m[2], 10) * 60 + parseInt(m[3], 10);
+  return m[1] === '-' ? -minutes : minutes;
+}
+
+function parseTimeParts(time = []) {
+  let hour = parseInt(time[0] || '0', 10);
+  const minute = parseInt(time[1] || '0', 10);
+  const second = parseInt(time[2] || '0', 10);
+  const fraction = time[3] ? parseInt(time[3].padEnd(3, '0').slice(0, 3), 10) : 0;
+  const meridiem = time[4] ? time[4].toLowerCase() : null;
+
+  if (meridiem === 'pm' && hour < 12) hour += 12;
+  if (meridiem === 'am' && hour === 12) hour = 0;
+
+  return { hour, minute, second, millis: fraction };
+}
+
+function normalizeYear(year) {
+  const y = parseInt(year, 10);
+  if (year.length === 2) return y >= 70 ? 1900 + y : 2000 + y;
+  return y;
+}
+
+function toEpoch(fields, hints) {
+  const { hour, minute, second, millis } = parseTimeParts(fields.time);
+  if (hour > 23 || minute > 59 || second > 60) return null;
+  if (fields.month < 1 || fields.month > 12 || fields.day < 1 || fields.day > 31) return null;
+
+  // Date.UTC rolls 31/02 over into March instead of rejecting it
+  const calendar = new Date(Date.UTC(fields.year, fields.month - 1, fields.day));
+  if (calendar.getUTCMonth() !== fields.month - 1 || calendar.getUTCDate() !== fields.day) return null;
+
+  const offset = zoneOffsetMinutes(fields.zone, hints);
+  if (offset === null) {
+    const local = new Date(fields.year, fields.month - 1, fields.day, hour, minute, second, millis);
+    return local.getTime();
+  }
+  const utc = Date.UTC(fields.year, fields.month - 1, fields.day, hour, minute, second, millis);
+  return utc - offset * 60 * 1000;
+}
+
+/**
+ * Decide whether ambiguous numeric dates are day-first
+ * @returns {boolean|null} null when neither the hints nor the value disambiguate
+ */
+function resolveDayFirst(a, b, separator, hints) {
+  if (a > 12 && b <= 12) return true;
+  if (b > 12 && a <= 12) return false;
+  if (typeof hints.dayFirst === 'boolean') return hints.dayFirst;
+  if (separator === '.') return true; // dd.mm.yyyy is never month-first in practice
+  if (hints.locale) return DAY_FIRST_LOCALES.test(hints.locale);
+  return null;
+}
+
+function resolveFields(format, raw, hints) {
+  if (raw.epochMillis) return { epoch: parseInt(raw.epochMillis, 10) };
+  if (raw.epochSeconds) return { epoch: Math.round(parseFloat(raw.epochSeconds) * 1000) };
+
+  if (raw.timeOnly) {
+    const base = hints.baseDate !== undefined ? new Date(hints.baseDate) : null;
+    if (!base || Number.isNaN(base.getTime())) return null;
+    return {
+      year: base.getUTCFullYear(),
+      month: base.getUTCMonth() + 1,
+      day: base.getUTCDate(),
+      time: raw.time,
+    };
+  }
+
+  let month;
+  let day;
+  if (raw.ambiguous) {
+    const a = parseInt(raw.ambiguous[0], 10);
+    const b = parseInt(raw.ambiguous[1], 10);
+    const dayFirst = resolveDayFirst(a, b, raw.separator, hints);
+    const useDayFirst = dayFirst === null ? hints.batchDayFirst === true : dayFirst;
+    day = useDayFirst ? a : b;
+    month = useDayFirst ? b : a;
+  } else {
+    month = raw.monthName ? monthFromName(raw.monthName) : parseInt(raw.month, 10);
+    day = parseInt(raw.day, 10);
+  }
+  if (!month) return null;
+
+  const year = raw.year ? normalizeYear(raw.year) : hints.year || new Date().getUTCFullYear();
+  return { year, month, day, time: raw.time, zone: raw.zone };
+}
+
+/**
+ * Every format that matches the line, most specific first
+ */
+function* matchLine(line) {
+  for (const format of FORMATS) {
+    const m = line.match(format.regex);
+    if (m) {
+      yield { format, raw: format.build(m), start: m.index, end: m.index + m[0].length };
+    }
+  }
+}
+
+/**
+ * Parse a single timestamp string
+ * @param {string} text - Line or fragment containing a timestamp
+ * @param {object} hints - { dayFirst, locale, year, baseDate, timezone: 'utc'|'local', utcOffsetMinutes }
+ * @returns {object|null} { timestamp, format, start, end }
+ */
+function parseTimestamp(text, hints = {}) {
+  if (text === null || text === undefined) return null;
+  for (const match of matchLine(String(text))) {
+    const fields = resolveFields(match.format, match.raw, hints);
+    if (!fields) continue;
+
+    const timestamp = fields.epoch !== undefined ? fields.epoch : toEpoch(fields, hints);
+    if (timestamp === null || Number.isNaN(timestamp)) continue;
+
+    return { timestamp, format: match.format.name, start: match.start, end: match.end };
+  }
+  return null;
+}
+
+/**
+ * Parse timestamps for a batch of lines
+ * Ambiguous day/month order is inferred from the whole batch before parsing.
+ * @param {string[]} lines - Log lines
+ * @param {object} hints - See parseTimestamp
+ * @returns {Array<object|null>} One result per input line
+ */
+function parseTimestamps(lines, hints = {}) {
+  const batchHints = { ...hints };
+
+  if (typeof hints.dayFirst !== 'boolean') {
+    let dayFirstVotes = 0;
+    let monthFirstVotes = 0;
+    for (const line of lines) {
+      const match = [...matchLine(String(line ?? ''))].find((candidate) => candidate.raw.ambiguous);
+      if (match) {
+        const a = parseInt(match.raw.ambiguous[0], 10);
+        const b = parseInt(match.raw.ambiguous[1], 10);
+        if (a > 12 && b <= 12) dayFirstVotes++;
+        else if (b > 12 && a <= 12) monthFirstVotes++;
+      }
+    }
+    if (dayFirstVotes > 0 || monthFirstVotes > 0) {
+      batchHints.dayFirst = dayFirstVotes >= monthFirstVotes;
+    } else if (hints.locale) {
+      batchHints.batchDayFirst = DAY_FIRST_LOCALES.test(hints.locale);
+    }
+  }
+
+  return lines.map((line) => parseTimestamp(line, batchHints));
+}
+
+/**
+ * Epoch ms from a stored timestamp (number or Date.parse-able string)
+ * @returns {number|null} null for null/undefined, NaN, Infinity and unparseable strings
+ */
+function toMillis(value) {
+  if (value === null || value === undefined) return null;
+  const ms = typeof value === 'number' ? value : value instanceof Date ? value.getTime() : Date.parse(value);
+  return Number.isFinite(ms) ? ms : null;
+}
+
+module.exports = {
+  toMillis,
+  parseTimestamp,
+  parseTimestamps,
+  FORMATS: FORMATS.map((f) => f.name),
+};
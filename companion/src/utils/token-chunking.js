@@ -0,0 +1,176 @@
+/**
+ * Token-Aware Chunking
+ * Splits large files into chunks bounded by model tokens rather than
+ * characters, so every chunk is guaranteed to fit a context budget.
+ *
+ * Cuts are placed on line boundaries, preferring the ones that end a
+ * logical unit: a blank line, the line before a top-level function/class
+ * declaration, or a closing brace at column 0. A cut is looked for in the
+ * last part of each chunk so chunks stay reasonably full. Lines longer than
+ * the budget on their own are split mid-line.
+ *
+ * Counting uses the BPE tokenizer when the encoding's vocabulary is cached
+ * and falls back to the diff engine's estimate otherwise (chunks are then
+ * flagged as estimated).
+ */
+
+const { countTokens, isEncodingAvailable, DEFAULT_ENCODING } = require('./bpe-tokenizer');
+const { estimateTokens } = require('./diff-engine');
+
+const DEFAULT_OPTIONS = {
+  minFill: 0.5, // Never cut a chunk before it holds half the budget
+  includeText: true,
+};
+
+const DECLARATION =
+  /^(?:export\s+)?(?:default\s+)?(?:async\s+)?(?:function|class|def|fn|func|pub\s+fn|impl|struct|enum|interface|type|module|describe|it|test)\b/;
+
+/**
+ * How good a cut is right before line i (higher is better)
+ */
+function boundaryScore(lines, i) {
+  const line = lines[i].text;
+  const previous = i > 0 ? lines[i - 1].text : '';
+  if (DECLARATION.test(line)) return previous.trim() === '' ? 4 : 3;
+  if (/^[}\])]/.test(previous) && !/^\s/.test(previous)) return 2.5;
+  if (previous.trim() === '') return /^\S/.test(line) ? 2 : 1.5;
+  if (/^\S/.test(line) && !/^[}\])]/.test(line)) return 1;
+  return 0;
+}
+
+function splitLines(text) {
+  const lines = [];
+  let start = 0;
+  while (start < text.length) {
+    const newline = text.indexOf('\n', start);
+    const end = newline === -1 ? text.length : newline + 1;
+    lines.push({ start, end, text: text.slice(start, end).replace(/\r?\n$/, '') });
+    start = end;
+  }
+  return lines;
+}
+
+/**
+ * Split a single over-long span into pieces of at most maxTokens by binary-searching cut offsets
+ */
+function splitSpan(text, start, end, maxTokens, count) {
+  const pieces = [];
+  let position = start;
+  while (position < end) {
+    if (count(text.slice(position, end)) <= maxTokens) {
+      pieces.push([position, end]);
+      break;
+    }
+    let lo = position + 1;
+    let hi = end;
+    while (lo < hi) {
+      const mid = Math.ceil((lo + hi) / 2);
+      if (count(text.slice(position, mid)) <= maxTokens) lo = mid;
+      else hi = mid - 1;
+    }
+    // Don't cut a surrogate pair in half
+    let cut = lo;
+    if (cut < end && cut > position + 1 && /[\uD800-\uDBFF]/.test(text[cut - 1])) cut--;
+    pieces.push([position, cut]);
+    position = cut;
+  }
+  return pieces;
+}
+
+/**
+ * Split text into token-bounded chunks
+ * @param {string} text - Text to split
+ * @param {number} maxTokens - Token budget per chunk
+ * @param {number} overlap - Tokens of trailing context repeated at the start of the next chunk
+ * @param {string} encoding - 'cl100k_base' | 'o200k_base'
+ * @param {object} options - { minFill, includeText, countTokens } (custom counter overrides the encoding)
+ * @returns {Array<object>} [{ index, start, end, startLine, endLine, tokenCount, estimated, text }]
+ *   start/end are string offsets (end exclusive); lines are 1-based
+ */
+function chunkByTokens(text, maxTokens, overlap = 0, encoding = DEFAULT_ENCODING, options = {}) {
+  const opts = { ...DEFAULT_OPTIONS, ...options };
+  if (!Number.isInteger(maxTokens) || maxTokens <= 0) throw new Error('maxTokens must be a positive integer');
+  if (overlap < 0 || overlap >= maxTokens) throw new Error('overlap must be between 0 and maxTokens');
+
+  const source = String(text ?? '');
+  const exact = !opts.countTokens && isEncodingAvailable(encoding);
+  const count =
+    opts.countTokens || (exact ? (value) => countTokens(value, encoding) : (value) => estimateTokens(value));
+
+  // Units are whole lines, or pieces of lines that alone exceed the budget
+  const units = [];
+  splitLines(source).forEach((line, lineIndex) => {
+    const tokens = count(source.slice(line.start, line.end));
+    if (tokens <= maxTokens) {
+      units.push({ ...line, line: lineIndex, tokens, midLine: false });
+      return;
+    }
+    splitSpan(source, line.start, line.end, maxTokens, count).forEach(([start, end], k) => {
+      const piece = source.slice(start, end);
+      units.push({ start, end, text: piece, line: lineIndex, tokens: count(piece), midLine: k > 0 });
+    });
+  });
+
+  const chunks = [];
+  const chunkTokens = (from, to) => count(source.slice(units[from].start, units[to - 1].end));
+  let first = 0;
+
+  while (first < units.length) {
+    // Grow by summed unit counts, then verify on the real text (BPE merges across lines differ slightly)
+    let end = first;
+    let total = 0;
+    while (end < units.length && total + units[end].tokens <= maxTokens) total += units[end++].tokens;
+    if (end === first) end = first + 1;
+    while (end - first > 1 && chunkTokens(first, end) > maxTokens) end--;
+
+    // Prefer a cut at a logical boundary within the tail of the chunk
+    if (end < units.length) {
+      let best = end;
+      let bestScore = units[end].midLine ? -1 : boundaryScore(units, end);
+      let filled = 0;
+      const minTokens = maxTokens * opts.minFill;
+      for (let k = first; k < end; k++) {
+        filled += units[k].tokens;
+        const candidate = k + 1;
+        if (candidate >= end || filled < minTokens || units[candidate].midLine) continue;
+        const score = boundaryScore(units, candidate);
+        if (score > bestScore) {
+          bestScore = score;
+          best = candidate;
+        }
+      }
+      end = best;
+    }
+
+    const start = units[first].start;
+    const stop = units[end - 1].end;
+    const chunkText = source.slice(start, stop);
+    chunks.push({
+      index: chunks.length,
+      start,
+      end: stop,
+      startLine: units[first].line + 1,
+      endLine: units[end - 1].line + 1,
+      tokenCount: count(chunkText),
+      estimated: !exact && !opts.countTokens,
+      ...(opts.includeText ? { text: chunkText } : {}),
+    });
+
+    if (end >= units.length) break;
+
+    // Back up whole units to repeat about `overlap` tokens, always making progress
+    let next = end;
+    let repeated = 0;
+    while (overlap > 0 && next - 1 > first && repeated + units[next - 1].tokens <= overlap) {
+      repeated += units[--next].tokens;
+    }
+    first = next;
+  }
+
+  return chunks;
+}
+
+module.exports = {
+  chunkByTokens,
+  DEFAULT_OPTIONS,
+};
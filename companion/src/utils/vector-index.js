@@ -0,0 +1,418 @@
+/**
+ * Vector Index
+ * Approximate nearest-neighbour search over embeddings with an HNSW graph
+ * (Malkov & Yashunin), used to match prompt text to edited code.
+ *
+ * Vectors live in one contiguous Float32Array that grows by doubling, and
+ * neighbour lists are Int32Arrays, so memory stays close to the raw data
+ * instead of one JS object per vector. Cosine similarity is implemented as
+ * inner product over vectors normalized at insert time.
+ *
+ * The index persists to a single binary file: a JSON header followed by the
+ * vector block and the graph.
+ */
+
+const fs = require('fs');
+
+const DEFAULT_OPTIONS = {
+  metric: 'cosine', // 'cosine' | 'ip'
+  M: 16, // Max neighbours per node on upper layers (2M on layer 0)
+  efConstruction: 200,
+  efSearch: 64,
+  initialCapacity: 1024,
+  seed: 42,
+};
+
+const MAGIC = 'HNSWIDX1';
+
+function mulberry32(seed) {
+  let a = seed >>> 0;
+  return () => {
+    a = (a + 0x6d2b79f5) >>> 0;
+    let t = a;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
+
+/**
+ * Binary heap of (distance, node) pairs; min-heap, or max-heap when max = true
+ */
+class Heap {
+  constructor(max = false) {
+    this.sign = max ? -1 : 1;
+    this.keys = [];
+    this.values = [];
+  }
+
+  get size() {
+    return this.keys.length;
+  }
+
+  push(distance, node) {
+    const keys = this.keys;
+    const values = this.values;
+    let i = keys.length;
+    keys.push(distance * this.sign);
+    values.push(node);
+    while (i > 0) {
+      const parent = (i - 1) >> 1;
+      if (keys[parent] <= keys[i]) break;
+      [keys[parent], keys[i]] = [keys[i], keys[parent]];
+      [values[parent], values[i]] = [values[i], values[parent]];
+      i = parent;
+    }
+  }
+
+  peekDistance() {
+    return this.keys[0] * this.sign;
+  }
+
+  peekNode() {
+    return this.values[0];
+  }
+
+  pop() {
+    const keys = this.keys;
+    const values = this.values;
+    const top = { distance: keys[0] * this.sign, node: values[0] };
+    const lastKey = keys.pop();
+    const lastValue = values.pop();
+    if (keys.length > 0) {
+      keys[0] = lastKey;
+      values[0] = lastValue;
+      let i = 0;
+      for (;;) {
+        const left = 2 * i + 1;
+        const right = left + 1;
+        let smallest = i;
+        if (left < keys.length && keys[left] < keys[smallest]) smallest = left;
+        if (right < keys.length && keys[right] < keys[smallest]) smallest = right;
+        if (smallest === i) break;
+        [keys[smallest], keys[i]] = [keys[i], keys[smallest]];
+        [values[smallest], values[i]] = [values[i], values[smallest]];
+        i = smallest;
+      }
+    }
+    return top;
+  }
+}
+
+class VectorIndex {
+  /**
+   * @param {number} dimensions - Embedding size
+   * @param {object} options - See DEFAULT_OPTIONS
+   */
+  constructor(dimensions, options = {}) {
+    if (!Number.isInteger(dimensions) || dimensions <= 0) throw new Error('dimensions must be a positive integer');
+    this.dimensions = dimensions;
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    if (!['cosine', 'ip'].includes(this.options.metric)) throw new Error(`Unknown metric: ${this.options.metric}`);
+
+    this.capacity = this.options.initialCapacity;
+    this.vectors = new Float32Array(this.capacity * dimensions);
+    this.count = 0;
+    this.ids = [];
+    this.idToNode = new Map();
+    this.levels = [];
+    this.neighbors = []; // neighbors[node][level] = Int32Array (first element is the length)
+    this.deleted = new Set();
+    this.entryPoint = -1;
+    this.maxLevel = -1;
+    this.levelMultiplier = 1 / Math.log(this.options.M);
+    this.random = mulberry32(this.options.seed);
+  }
+
+  get size() {
+    return this.count - this.deleted.size;
+  }
+
+  ensureCapacity() {
+    if (this.count < this.capacity) return;
+    this.capacity *= 2;
+    const grown = new Float32Array(this.capacity * this.dimensions);
+    grown.set(this.vectors);
+    this.vectors = grown;
+  }
+
+  prepare(embedding) {
+    if (!embedding || embedding.length !== this.dimensions) {
+      throw new Error(`Expected an embedding of ${this.dimensions} dimensions`);
+    }
+    const vector = Float32Array.from(embedding);
+    if (this.options.metric === 'cosine') {
+      let norm = 0;
+      for (let i = 0; i < vector.length; i++) norm += vector[i] * vector[i];
+      norm = Math.sqrt(norm);
+      if (norm > 0) for (let i = 0; i < vector.length; i++) vector[i] /= norm;
+    }
+    return vector;
+  }
+
+  /**
+   * Distance used for graph ordering: 1 - dot for cosine, -dot for inner product
+   */
+  distance(query, node) {
+    const offset = node * this.dimensions;
+    const vectors = this.vectors;
+    let dot = 0;
+    for (let i = 0; i < this.dimensions; i++) dot += query[i] * vectors[offset + i];
+    return this.options.metric === 'cosine' ? 1 - dot : -dot;
+  }
+
+  neighborList(node, level) {
+    const list = this.neighbors[node][level];
+    return list.subarray(1, 1 + list[0]);
+  }
+
+  setNeighbors(node, level, nodes) {
+    const list = this.neighbors[node][level];
+    list[0] = nodes.length;
+    list.set(nodes, 1);
+  }
+
+  /**
+   * Beam search within one layer, returning a max-heap of the ef closest nodes found
+   */
+  searchLayer(query, entryPoints, ef, level) {
+    const visited = new Set();
+    const candidates = new Heap(false);
+    const results = new Heap(true);
+    for (const { node, distance } of entryPoints) {
+      visited.add(node);
+      candidates.push(distance, node);
+      results.push(distance, node);
+    }
+
+    while (candidates.size > 0) {
+      const current = candidates.pop();
+      if (results.size >= ef && current.distance > results.peekDistance()) break;
+      for (const neighbor of this.neighborList(current.node, level)) {
+        if (visited.has(neighbor)) continue;
+        visited.add(neighbor);
+        const distance = this.distance(query, neighbor);
+        if (results.size < ef || distance < results.peekDistance()) {
+          candidates.push(distance, neighbor);
+          results.push(distance, neighbor);
+          if (results.size > ef) results.pop();
+        }
+      }
+    }
+    return results;
+  }
+
+  /**
+   * HNSW neighbour-selection heuristic: keep a candidate only if it is closer to the base
+   * than to every neighbour already kept, which preserves links between clusters
+   */
+  selectNeighbors(base, candidates, limit) {
+    const sorted = candidates.slice().sort((a, b) => a.distance - b.distance);
+    const selected = [];
+    for (const candidate of sorted) {
+      if (selected.length >= limit) break;
+      if (candidate.node === base) continue;
+      const candidateVector = this.vectors.subarray(
+        candidate.node * this.dimensions,
+        (candidate.node + 1) * this.dimensions
+      );
+      let keep = true;
+      for (const chosen of selected) {
+        if (this.distance(candidateVector, chosen.node) < candidate.distance) {
+          keep = false;
+          break;
+        }
+      }
+      if (keep) selected.push(candidate);
+    }
+    return selected;
+  }
+
+  heapToArray(heap) {
+    const out = [];
+    while (heap.size > 0) out.push(heap.pop());
+    return out.reverse();
+  }
+
+  /**
+   * Insert (or replace) an embedding
+   * @param {string|number} id - External id
+   * @param {Float32Array|Array<number>} embedding - Vector of `dimensions` floats
+   */
+  insert(id, embedding) {
+    const vector = this.prepare(embedding);
+    if (this.idToNode.has(id)) this.remove(id);
+
+    this.ensureCapacity();
+    const node = this.count++;
+    this.vectors.set(vector, node * this.dimensions);
+    this.ids[node] = id;
+    this.idToNode.set(id, node);
+
+    const { M } = this.options;
+    const level = Math.floor(-Math.log(1 - this.random()) * this.levelMultiplier);
+    this.levels[node] = level;
+    this.neighbors[node] = Array.from({ length: level + 1 }, (_, l) => new Int32Array((l === 0 ? 2 * M : M) + 1));
+
+    if (this.entryPoint === -1) {
+      this.entryPoint = node;
+      this.maxLevel = level;
+      return this;
+    }
+
+    let entry = [{ node: this.entryPoint, distance: this.distance(vector, this.entryPoint) }];
+    for (let l = this.maxLevel; l > level; l--) {
+      entry = this.heapToArray(this.searchLayer(vector, entry, 1, l)).slice(0, 1);
+    }
+
+    for (let l = Math.min(level, this.maxLevel); l >= 0; l--) {
+      const found = this.heapToArray(this.searchLayer(vector, entry, this.options.efConstruction, l));
+      const limit = l === 0 ? 2 * M : M;
+      const selected = this.selectNeighbors(node, found, M);
+      this.setNeighbors(node, l, selected.map((s) => s.node));
+
+      for (const { node: neighbor } of selected) {
+        const existing = Array.from(this.neighborList(neighbor, l));
+        if (existing.length < limit) {
+          this.setNeighbors(neighbor, l, [...existing, node]);
+          continue;
+        }
+        const neighborVector = this.vectors.subarray(neighbor * this.dimensions, (neighbor + 1) * this.dimensions);
+        const pool = [...existing, node].map((n) => ({ node: n, distance: this.distance(neighborVector, n) }));
+        const kept = this.selectNeighbors(neighbor, pool, limit);
+        this.setNeighbors(neighbor, l, kept.map((s) => s.node));
+      }
+      entry = found;
+    }
+
+    if (level > this.maxLevel) {
+      this.maxLevel = level;
+      this.entryPoint = node;
+    }
+    return this;
+  }
+
+  /**
+   * Mark an id as deleted; it stays in the graph for navigation but is never returned
+   */
+  remove(id) {
+    const node = this.idToNode.get(id);
+    if (node === undefined) return false;
+    this.deleted.add(node);
+    this.idToNode.delete(id);
+    return true;
+  }
+
+  has(id) {
+    return this.idToNode.has(id);
+  }
+
+  /**
+   * k nearest neighbours of a query embedding
+   * @param {object} options - { ef } beam width (defaults to efSearch)
+   * @returns {Array<object>} [{ id, score, distance }] best first; score is cosine similarity or inner product
+   */
+  search(query, k = 10, options = {}) {
+    if (this.entryPoint === -1 || k <= 0) return [];
+    const vector = this.prepare(query);
+    // Widen the beam a little when deleted nodes may take up result slots
+    const ef = Math.max(options.ef || this.options.efSearch, k) + Math.min(this.deleted.size, k);
+
+    let entry = [{ node: this.entryPoint, distance: this.distance(vector, this.entryPoint) }];
+    for (let l = this.maxLevel; l > 0; l--) {
+      entry = this.heapToArray(this.searchLayer(vector, entry, 1, l)).slice(0, 1);
+    }
+
+    return this.heapToArray(this.searchLayer(vector, entry, ef, 0))
+      .filter((result) => !this.deleted.has(result.node))
+      .slice(0, k)
+      .map(({ node, distance }) => ({
+        id: this.ids[node],
+        score: this.options.metric === 'cosine' ? 1 - distance : -distance,
+        distance,
+      }));
+  }
+
+  /**
+   * Run several queries at once
+   */
+  searchBatch(queries, k = 10, options = {}) {
+    return queries.map((query) => this.search(query, k, options));
+  }
+
+  /**
+   * Persist the index to a single binary file
+   */
+  save(filePath) {
+    const header = {
+      dimensions: this.dimensions,
+      options: { ...this.options, initialCapacity: Math.max(1, this.count) },
+      count: this.count,
+      ids: this.ids,
+      levels: this.levels,
+      deleted: Array.from(this.deleted),
+      entryPoint: this.entryPoint,
+      maxLevel: this.maxLevel,
+    };
+    const headerBuffer = Buffer.from(JSON.stringify(header), 'utf8');
+
+    const graph = [];
+    for (let node = 0; node < this.count; node++) {
+      for (const list of this.neighbors[node]) graph.push(Buffer.from(list.buffer, list.byteOffset, list.byteLength));
+    }
+    const vectorBytes = Buffer.from(this.vectors.buffer, 0, this.count * this.dimensions * 4);
+
+    const prefix = Buffer.alloc(MAGIC.length + 4);
+    prefix.write(MAGIC, 0, 'ascii');
+    prefix.writeUInt32LE(headerBuffer.length, MAGIC.length);
+
+    const tmp = `${filePath}.${process.pid}.tmp`;
+    fs.writeFileSync(tmp, Buffer.concat([prefix, headerBuffer, vectorBytes, ...graph]));
+    fs.renameSync(tmp, filePath);
+    return filePath;
+  }
+
+  /**
+   * Load an index written by save()
+   */
+  static load(filePath) {
+    const data = fs.readFileSync(filePath);
+    if (data.toString('ascii', 0, MAGIC.length) !== MAGIC) throw new Error(`${filePath} is not a vector index`);
+    const headerLength = data.readUInt32LE(MAGIC.length);
+    let offset = MAGIC.length + 4;
+    const header = JSON.parse(data.toString('utf8', offset, offset + headerLength));
+    offset += headerLength;
+
+    const index = new VectorIndex(header.dimensions, header.options);
+    index.count = header.count;
+    index.capacity = Math.max(1, header.count);
+    index.vectors = new Float32Array(index.capacity * header.dimensions);
+    const vectorBytes = header.count * header.dimensions * 4;
+    new Uint8Array(index.vectors.buffer).set(data.subarray(offset, offset + vectorBytes));
+    offset += vectorBytes;
+
+    const { M } = index.options;
+    index.ids = header.ids;
+    index.levels = header.levels;
+    index.deleted = new Set(header.deleted);
+    index.entryPoint = header.entryPoint;
+    index.maxLevel = header.maxLevel;
+    for (let node = 0; node < header.count; node++) {
+      index.neighbors[node] = [];
+      for (let l = 0; l <= header.levels[node]; l++) {
+        const length = (l === 0 ? 2 * M : M) + 1;
+        const list = new Int32Array(length);
+        new Uint8Array(list.buffer).set(data.subarray(offset, offset + length * 4));
+        offset += length * 4;
+        index.neighbors[node].push(list);
+      }
+      if (!index.deleted.has(node)) index.idToNode.set(header.ids[node], node);
+    }
+    return index;
+  }
+}
+
+module.exports = {
+  VectorIndex,
+  DEFAULT_OPTIONS,
+};
@@ -0,0 +1,293 @@
+/**
+ * Work Queue
+ * Batches small CPU jobs (diff, hash, stats, halstead) onto a fixed set of worker
+ * threads. Capture produces thousands of these per second; sending each one
+ * to a worker (or running it inline on the event loop) costs more in message
+ * overhead than the work itself, so jobs are collected into batches of up to
+ * batchSize (or whatever arrived within maxBatchWaitMs) and each batch is one
+ * round trip.
+ *
+ * Backpressure follows TraceWriter: enqueue() past highWaterMark still
+ * accepts the job but sets writable = false and 'drain' is emitted once the
+ * backlog falls to half; past maxPending, jobs are rejected with LIMIT_EXCEEDED.
+ *
+ * Events:
+ *   batch   { kind counts, size, durationMs } per completed batch
+ *   drain   backlog fell back below highWaterMark / 2
+ *   idle    nothing queued or in flight
+ */
+
+const EventEmitter = require('events');
+const { createWorker, resolveConcurrency } = require('./thread-pool');
+const { CompanionError, ERROR_CODES } = require('./errors');
+
+const DEFAULT_OPTIONS = {
+  concurrency: null, // Worker threads; null = thread pool default, 0 = run inline on the event loop
+  batchSize: 64,
+  maxBatchWaitMs: 5,
+  highWaterMark: 10000, // Pending jobs before enqueue() signals backpressure
+  maxPending: 100000, // Pending jobs before enqueue() rejects with LIMIT_EXCEEDED
+};
+
+const JOB_KINDS = ['diff', 'hash', 'stats', 'halstead'];
+
+/**
+ * Run one job; shared by the inline path and the workers
+ * @param {string} kind - 'diff' | 'hash' | 'stats' | 'halstead'
+ * @param {object} payload - diff: { before, after, options }, hash: { content, algorithm },
+ *   stats: { content, options }, halstead: { content, language, options }
+ */
+function runJob(kind, payload) {
+  switch (kind) {
+    case 'diff':
+      return require('./diff-engine').calculateDiff(payload.before, payload.after, {
+        includeAfterContent: false,
+        ...payload.options,
+      });
+    case 'hash':
+      return require('./content-hash').hashContent(payload.content, payload.algorithm);
+    case 'stats':
+      return require('./diff-engine').calculateFileStats(payload.content, payload.options);
+    case 'halstead':
+      return require('./code-metrics').computeHalstead(payload.content, payload.language, payload.options);
+    default:
+      throw unknownKind(kind);
+  }
+}
+
+function unknownKind(kind) {
+  const message = `Unknown job kind: ${kind} (expected ${JOB_KINDS.join(', ')})`;
+  return new CompanionError(ERROR_CODES.INVALID_INPUT, message);
+}
+
+function runBatch(jobs) {
+  return jobs.map(([kind, payload]) => {
+    try {
+      return { result: runJob(kind, payload) };
+    } catch (error) {
+      return { error: error.message, code: error instanceof CompanionError ? error.code : ERROR_CODES.IO };
+    }
+  });
+}
+
+const QUEUE_WORKER_SOURCE = `
+const { parentPort, workerData } = require('worker_threads');
+const { runBatch } = require(workerData.modulePath);
+parentPort.on('message', (jobs) => parentPort.postMessage(runBatch(jobs)));
+`;
+
+function queueFull(pending) {
+  return new CompanionError(ERROR_CODES.LIMIT_EXCEEDED, `Work queue full (${pending} pending jobs)`, {
+    reason: 'queue-full',
+  });
+}
+
+class WorkQueue extends EventEmitter {
+  constructor(options = {}) {
+    super();
+    this.options = { ...DEFAULT_OPTIONS, ...options };
+    this.threads = this.options.concurrency === 0 ? 0 : resolveConcurrency(this.options.concurrency);
+    this.jobs = []; // [{ kind, payload, resolve, reject }] waiting for a batch
+    this.workers = [];
+    this.idleWorkers = [];
+    this.inFlight = 0; // Jobs dispatched but not yet answered
+    this.batchTimer = null;
+    this.needsDrain = false;
+    this.closed = false;
+    this.stats = { enqueued: 0, completed: 0, failed: 0, rejected: 0, batches: 0, maxPending: 0 };
+  }
+
+  get pending() {
+    return this.jobs.length + this.inFlight;
+  }
+
+  get writable() {
+    return this.pending < this.options.highWaterMark;
+  }
+
+  /**
+   * Queue one job
+   * @returns {Promise<any>} The job's result; rejects with LIMIT_EXCEEDED past maxPending
+   */
+  enqueue(kind, payload) {
+    if (this.closed) {
+      return Promise.reject(new CompanionError(ERROR_CODES.UNAVAILABLE, 'WorkQueue is closed'));
+    }
+    if (!JOB_KINDS.includes(kind)) return Promise.reject(unknownKind(kind));
+    if (this.pending >= this.options.maxPending) {
+      this.stats.rejected++;
+      return Promise.reject(queueFull(this.pending));
+    }
+
+    const promise = new Promise((resolve, reject) => {
+      this.jobs.push({ kind, payload, resolve, reject });
+    });
+    this.stats.enqueued++;
+    this.stats.maxPending = Math.max(this.stats.maxPending, this.pending);
+    if (this.pending >= this.options.highWaterMark) this.needsDrain = true;
+    this.schedule();
+    return promise;
+  }
+
+  /**
+   * Queue several jobs of one kind
+   * @returns {Promise<Array>} Results in payload order (rejects on the first failed job)
+   */
+  enqueueAll(kind, payloads) {
+    return Promise.all(payloads.map((payload) => this.enqueue(kind, payload)));
+  }
+
+  diff(before, after, options = {}) {
+    return this.enqueue('diff', { before, after, options });
+  }
+
+  hash(content, algorithm) {
+    return this.enqueue('hash', { content, algorithm });
+  }
+
+  fileStats(content, options = {}) {
+    return this.enqueue('stats', { content, options });
+  }
+
+  halstead(content, language, options = {}) {
+    return this.enqueue('halstead', { content, language, options });
+  }
+
+  /**
+   * Resolve once the queue accepts more work without exceeding highWaterMark
+   */
+  ready() {
+    if (this.writable) return Promise.resolve();
+    return new Promise((resolve) => this.once('drain', resolve));
+  }
+
+  // ---------------------------------------------------------------------------
+  // Dispatch
+  // ---------------------------------------------------------------------------
+
+  schedule() {
+    if (this.jobs.length >= this.options.batchSize) {
+      this.dispatch();
+    } else if (!this.batchTimer) {
+      this.batchTimer = setTimeout(() => {
+        this.batchTimer = null;
+        this.dispatch();
+      }, this.options.maxBatchWaitMs);
+    }
+  }
+
+  dispatch() {
+    while (this.jobs.length > 0) {
+      const worker = this.threads === 0 ? null : this.acquireWorker();
+      if (this.threads > 0 && !worker) return; // All workers busy; resumes when one frees up
+      const batch = this.jobs.splice(0, this.options.batchSize);
+      this.inFlight += batch.length;
+      if (worker) this.runOnWorker(worker, batch);
+      else this.runInline(batch);
+      if (this.threads === 0) return; // Inline batches run one per tick
+    }
+  }
+
+  acquireWorker() {
+    if (this.idleWorkers.length > 0) return this.idleWorkers.pop();
+    if (this.workers.length >= this.threads) return null;
+    const worker = createWorker(QUEUE_WORKER_SOURCE, { workerData: { modulePath: __filename } });
+    worker.unref();
+    this.workers.push(worker);
+    return worker;
+  }
+
+  runOnWorker(worker, batch) {
+    const started = Date.now();
+    const onMessage = (results) => {
+      worker.off('error', onError);
+      this.idleWorkers.push(worker);
+      this.settle(batch, results, started);
+    };
+    const onError = (error) => {
+      worker.off('message', onMessage);
+      // A crashed worker is replaced on the next dispatch
+      this.workers = this.workers.filter((w) => w !== worker);
+      this.settle(
+        batch,
+        batch.map(() => ({ error: error.message, code: ERROR_CODES.IO })),
+        started
+      );
+    };
+    worker.once('message', onMessage);
+    worker.once('error', onError);
+    worker.postMessage(batch.map((job) => [job.kind, job.payload]));
+  }
+
+  runInline(batch) {
+    setImmediate(() => {
+      const started = Date.now();
+      this.settle(batch, runBatch(batch.map((job) => [job.kind, job.payload])), started);
+    });
+  }
+
+  settle(batch, results, started) {
+    const kinds = {};
+    batch.forEach((job, i) => {
+      kinds[job.kind] = (kinds[job.kind] || 0) + 1;
+      const outcome = results[i];
+      if (outcome.error !== undefined) {
+        this.stats.failed++;
+        job.reject(new CompanionError(outcome.code, outcome.error));
+      } else {
+        this.stats.completed++;
+        job.resolve(outcome.result);
+      }
+    });
+    this.inFlight -= batch.length;
+    this.stats.batches++;
+    this.emit('batch', { kinds, size: batch.length, durationMs: Date.now() - started });
+
+    if (this.needsDrain && this.pending < this.options.highWaterMark / 2) {
+      this.needsDrain = false;
+      this.emit('drain');
+    }
+    if (this.jobs.length > 0) this.dispatch();
+    else if (this.inFlight === 0) this.emit('idle');
+  }
+
+  /**
+   * Wait for every queued job to finish
+   */
+  flush() {
+    if (this.pending === 0) return Promise.resolve();
+    clearTimeout(this.batchTimer);
+    this.batchTimer = null;
+    this.dispatch();
+    return new Promise((resolve) => this.once('idle', resolve));
+  }
+
+  /**
+   * Finish queued work, then stop the workers
+   */
+  async close() {
+    this.closed = true;
+    await this.flush();
+    await Promise.all(this.workers.map((worker) => worker.terminate()));
+    this.workers = [];
+    this.idleWorkers = [];
+  }
+
+  getStats() {
+    return {
+      ...this.stats,
+      pending: this.pending,
+      queued: this.jobs.length,
+      inFlight: this.inFlight,
+      workers: this.workers.length,
+      writable: this.writable,
+    };
+  }
+}
+
+module.exports = {
+  WorkQueue,
+  runBatch,
+  JOB_KINDS,
+  DEFAULT_OPTIONS,
+};